@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use asterix_core::TabId;
+
+use crate::priority::{BackgroundTurns, Priority, PriorityQueue, Slot};
+
+/// Soonest a page timer, such as a refresh, fires in a background tab.
+const BACKGROUND_TIMER_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct ActivityState {
+    active: Option<TabId>,
+    quiet: HashSet<TabId>,
+    hibernated: HashSet<TabId>,
+    /// Navigations in flight in any tab.
+    loading: usize,
+}
+
+/// Counts a navigation as in flight until dropped.
+pub(crate) struct Loading(Arc<TabActivity>);
+
+impl Drop for Loading {
+    fn drop(&mut self) {
+        self.0.state.lock().loading -= 1;
+        self.0.changed.notify_waiters();
+    }
+}
+
+/// Decides when work for a tab is allowed to run.
+///
+/// The focused tab always runs immediately. Background tabs queue as
+/// speculative work, and quiet or hibernated tabs are parked until they are
+/// focused or woken up again.
+pub(crate) struct TabActivity {
+    state: Mutex<ActivityState>,
+    changed: Notify,
+    priorities: Arc<PriorityQueue>,
+}
+
+impl TabActivity {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::default(),
+            changed: Notify::new(),
+            priorities: Arc::default(),
+        }
+    }
+
+    pub(crate) fn set_active(&self, tab: TabId) {
+        self.state.lock().active = Some(tab);
+        self.changed.notify_waiters();
+    }
+
+    pub(crate) fn set_quiet(&self, tab: TabId, quiet: bool) {
+        {
+            let mut guard = self.state.lock();
+            if quiet {
+                guard.quiet.insert(tab);
+            } else {
+                guard.quiet.remove(&tab);
+            }
+        }
+        self.changed.notify_waiters();
+    }
+
+    /// Replaces the set of tabs hibernated because their workspace is hidden.
+    pub(crate) fn set_hibernated(&self, tabs: HashSet<TabId>) {
+        self.state.lock().hibernated = tabs;
+        self.changed.notify_waiters();
+    }
+
+    pub(crate) fn is_quiet(&self, tab: TabId) -> bool {
+        self.state.lock().quiet.contains(&tab)
+    }
+
+    fn is_active(&self, tab: TabId) -> bool {
+        self.state.lock().active == Some(tab)
+    }
+
+    fn is_paused(&self, tab: TabId) -> bool {
+        let guard = self.state.lock();
+        let parked = guard.quiet.contains(&tab) || guard.hibernated.contains(&tab);
+        parked && guard.active != Some(tab)
+    }
+
+    /// Waits until `tab` may start network work, returning the slot to hold
+    /// for the duration of the fetch.
+    pub(crate) async fn admit(&self, tab: TabId) -> Slot {
+        self.resumed(tab).await;
+        if self.is_active(tab) {
+            self.admit_as(Priority::UserInitiated).await
+        } else {
+            self.admit_as(Priority::Speculative).await
+        }
+    }
+
+    /// Waits until `tab` is neither quiet nor hibernated, or is focused.
+    async fn resumed(&self, tab: TabId) {
+        loop {
+            let notified = self.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if !self.is_paused(tab) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Waits out a page timer of `delay` set by `tab`. Background tabs wait
+    /// at least [`BACKGROUND_TIMER_DELAY`] from the start unless focused in
+    /// the meantime, and quiet or hibernated tabs until they are woken up.
+    pub(crate) async fn timer(&self, tab: TabId, delay: Duration) {
+        let throttled = Instant::now() + BACKGROUND_TIMER_DELAY;
+        tokio::time::sleep(delay).await;
+        loop {
+            let notified = self.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_paused(tab) {
+                notified.await;
+            } else if self.is_active(tab) || Instant::now() >= throttled {
+                return;
+            } else {
+                tokio::select! {
+                    _ = notified => {}
+                    _ = tokio::time::sleep_until(throttled) => {}
+                }
+            }
+        }
+    }
+
+    /// Notes a navigation starting; it counts as loading until the returned
+    /// guard is dropped.
+    pub(crate) fn start_loading(self: &Arc<Self>) -> Loading {
+        self.state.lock().loading += 1;
+        Loading(Arc::clone(self))
+    }
+
+    /// Waits until no navigation is loading, for speculative work that
+    /// should not compete with pages the user is waiting for.
+    pub(crate) async fn idle(&self) {
+        loop {
+            let notified = self.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.state.lock().loading == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Waits until work of `priority` may run, returning the slot to hold
+    /// while it does.
+    pub(crate) async fn admit_as(&self, priority: Priority) -> Slot {
+        self.priorities.admit(priority).await
+    }
+
+    /// Gate admitting the core's background requests one at a time through
+    /// the same queues.
+    pub(crate) fn background_turns(&self) -> BackgroundTurns {
+        BackgroundTurns(Arc::clone(&self.priorities))
+    }
+
+    /// Spawns `work` to run once admitted as `priority`, holding its slot
+    /// until it finishes.
+    pub(crate) fn spawn_as(
+        self: &Arc<Self>,
+        priority: Priority,
+        work: impl Future<Output = ()> + Send + 'static,
+    ) {
+        let activity = Arc::clone(self);
+        tokio::spawn(async move {
+            let _slot = activity.admit_as(priority).await;
+            work.await;
+        });
+    }
+}
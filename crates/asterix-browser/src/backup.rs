@@ -0,0 +1,590 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::{KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use sha2::Sha256;
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::error::TryRecvError;
+
+/// Marks backup files: the archive sealed in chunks, so neither writing
+/// nor reading it holds the profile in memory.
+const MAGIC: &[u8; 4] = b"AXB2";
+
+/// PBKDF2 rounds for turning the passphrase into a key.
+const KEY_DERIVATION_ROUNDS: u32 = 210_000;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Nonce prefix of the chunked format; the rest of the nonce counts chunks
+/// and flags the last one.
+const STREAM_NONCE_LEN: usize = NONCE_LEN - 5;
+
+/// Compressed archive bytes sealed per chunk.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Authentication tag added to each sealed chunk.
+const TAG_LEN: usize = 16;
+
+/// Longest path, in bytes, an archived file may have.
+const MAX_NAME_LEN: u32 = 4096;
+
+/// Profile directories left out of backups: state the browser rebuilds on
+/// its own and keeps writing to while it runs.
+const SKIPPED_DIRS: [&str; 1] = ["cache"];
+
+/// Extension given to profile backups.
+pub const BACKUP_EXTENSION: &str = "axbackup";
+
+/// What a backup or restore covered.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BackupSummary {
+    pub files: usize,
+    /// Size of the profile files, before compression.
+    pub bytes: u64,
+}
+
+/// Work a [`BackupJob`] carries out.
+pub(crate) enum BackupTask {
+    Create {
+        profile: PathBuf,
+        destination: PathBuf,
+        passphrase: String,
+    },
+    Restore {
+        archive: PathBuf,
+        profile: PathBuf,
+        passphrase: String,
+    },
+}
+
+impl BackupTask {
+    pub(crate) fn run(self) -> anyhow::Result<BackupSummary> {
+        match self {
+            BackupTask::Create {
+                profile,
+                destination,
+                passphrase,
+            } => create_backup(&profile, &destination, &passphrase),
+            BackupTask::Restore {
+                archive,
+                profile,
+                passphrase,
+            } => restore_backup(&archive, &profile, &passphrase),
+        }
+    }
+}
+
+/// Backup or restore running on the browser runtime.
+pub struct BackupJob {
+    receiver: oneshot::Receiver<anyhow::Result<BackupSummary>>,
+}
+
+impl BackupJob {
+    pub(crate) fn new(receiver: oneshot::Receiver<anyhow::Result<BackupSummary>>) -> Self {
+        Self { receiver }
+    }
+
+    pub fn try_complete(&mut self) -> Option<anyhow::Result<BackupSummary>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(anyhow::anyhow!("the backup was cancelled"))),
+        }
+    }
+}
+
+/// Packs the files under `profile` into one archive at `destination`,
+/// compressed and encrypted with a key derived from `passphrase`. The HTTP
+/// cache is left out.
+pub fn create_backup(
+    profile: &Path,
+    destination: &Path,
+    passphrase: &str,
+) -> anyhow::Result<BackupSummary> {
+    if passphrase.is_empty() {
+        bail!("a passphrase is needed to encrypt the backup");
+    }
+    if !profile.is_dir() {
+        bail!("there is no profile at {}", profile.display());
+    }
+    let mut files = Vec::new();
+    collect_files(profile, profile, destination, &mut files)?;
+    files.sort();
+
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    // Written aside first so a failed backup leaves no half-written file,
+    // nor replaces an earlier one.
+    let staging = sibling(destination, "partial");
+    let written = write_backup(profile, &files, &staging, passphrase).and_then(|summary| {
+        std::fs::rename(&staging, destination)
+            .with_context(|| format!("failed to write {}", destination.display()))?;
+        Ok(summary)
+    });
+    if written.is_err() {
+        let _ = std::fs::remove_file(&staging);
+    }
+    written
+}
+
+/// Streams `files` of `profile` through the archive, compression and
+/// encryption into a new file at `path`.
+fn write_backup(
+    profile: &Path,
+    files: &[PathBuf],
+    path: &Path,
+    passphrase: &str,
+) -> anyhow::Result<BackupSummary> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut output = BufWriter::new(file);
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; STREAM_NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    output.write_all(MAGIC)?;
+    output.write_all(&salt)?;
+    output.write_all(&nonce)?;
+
+    let sealed = SealingWriter::new(output, cipher(passphrase, &salt), &nonce);
+    let mut archive = DeflateEncoder::new(sealed, Compression::default());
+    let mut summary = BackupSummary::default();
+    for relative in files {
+        let path = profile.join(relative);
+        let file =
+            File::open(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let len = file.metadata()?.len();
+        let name = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if name.len() > MAX_NAME_LEN as usize {
+            bail!("{} has too long a path to back up", path.display());
+        }
+        archive.write_all(&(name.len() as u32).to_le_bytes())?;
+        archive.write_all(name.as_bytes())?;
+        archive.write_all(&len.to_le_bytes())?;
+        let copied = io::copy(&mut file.take(len), &mut archive)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        if copied != len {
+            bail!("{} shrank while it was being backed up", path.display());
+        }
+        summary.files += 1;
+        summary.bytes += len;
+    }
+    let mut output = archive.finish()?.finish()?;
+    output.flush()?;
+    output
+        .get_ref()
+        .sync_all()
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(summary)
+}
+
+/// Seals what is written to it in chunks of [`CHUNK_LEN`] bytes, each
+/// authenticated on its own and in sequence, so the last can be told from
+/// a truncated file.
+struct SealingWriter<W: Write> {
+    output: W,
+    encryptor: Option<EncryptorBE32<XChaCha20Poly1305>>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> SealingWriter<W> {
+    fn new(output: W, cipher: XChaCha20Poly1305, nonce: &[u8; STREAM_NONCE_LEN]) -> Self {
+        Self {
+            output,
+            encryptor: Some(EncryptorBE32::from_aead(cipher, nonce.as_slice().into())),
+            buffer: Vec::with_capacity(CHUNK_LEN),
+        }
+    }
+
+    /// Seals what is left as the last chunk and hands back the output.
+    fn finish(mut self) -> io::Result<W> {
+        let encryptor = self.encryptor.take().expect("finished once");
+        let chunk = encryptor
+            .encrypt_last(self.buffer.as_slice())
+            .map_err(|_| io::Error::other("failed to encrypt the backup"))?;
+        self.output.write_all(&chunk)?;
+        Ok(self.output)
+    }
+}
+
+impl<W: Write> Write for SealingWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        // A full chunk is only sealed once more follows: the last chunk,
+        // sealed by `finish`, must not be empty unless the archive is.
+        if self.buffer.len() == CHUNK_LEN {
+            let encryptor = self.encryptor.as_mut().expect("written before finish");
+            let chunk = encryptor
+                .encrypt_next(self.buffer.as_slice())
+                .map_err(|_| io::Error::other("failed to encrypt the backup"))?;
+            self.output.write_all(&chunk)?;
+            self.buffer.clear();
+        }
+        let len = data.len().min(CHUNK_LEN - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Opens what a [`SealingWriter`] sealed, one chunk at a time.
+struct OpeningReader<R: Read> {
+    input: R,
+    decryptor: Option<DecryptorBE32<XChaCha20Poly1305>>,
+    /// Sealed bytes read ahead, one past a chunk to tell whether it is the
+    /// last.
+    sealed: Vec<u8>,
+    plain: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> OpeningReader<R> {
+    fn new(input: R, cipher: XChaCha20Poly1305, nonce: &[u8]) -> Self {
+        Self {
+            input,
+            decryptor: Some(DecryptorBE32::from_aead(cipher, nonce.into())),
+            sealed: Vec::with_capacity(CHUNK_LEN + TAG_LEN + 1),
+            plain: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Opens the next chunk into `plain`; false once the last was read.
+    fn open_next(&mut self) -> io::Result<bool> {
+        if self.decryptor.is_none() {
+            return Ok(false);
+        }
+        let want = CHUNK_LEN + TAG_LEN + 1;
+        while self.sealed.len() < want {
+            let start = self.sealed.len();
+            self.sealed.resize(want, 0);
+            let read = self.input.read(&mut self.sealed[start..])?;
+            self.sealed.truncate(start + read);
+            if read == 0 {
+                break;
+            }
+        }
+        let damaged = || io::Error::new(io::ErrorKind::InvalidData, "the backup is damaged");
+        self.plain = if self.sealed.len() == want {
+            let decryptor = self.decryptor.as_mut().expect("checked above");
+            let plain = decryptor
+                .decrypt_next(&self.sealed[..want - 1])
+                .map_err(|_| damaged())?;
+            self.sealed.drain(..want - 1);
+            plain
+        } else {
+            let decryptor = self.decryptor.take().expect("checked above");
+            let plain = decryptor
+                .decrypt_last(self.sealed.as_slice())
+                .map_err(|_| damaged())?;
+            self.sealed.clear();
+            plain
+        };
+        self.position = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for OpeningReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plain.len() {
+            if !self.open_next()? {
+                return Ok(0);
+            }
+        }
+        let len = out.len().min(self.plain.len() - self.position);
+        out[..len].copy_from_slice(&self.plain[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+/// Replaces `profile` with the files in the backup at `archive`. The profile
+/// it replaces is kept next to it with a `.before-restore` suffix.
+pub fn restore_backup(
+    archive: &Path,
+    profile: &Path,
+    passphrase: &str,
+) -> anyhow::Result<BackupSummary> {
+    let file =
+        File::open(archive).with_context(|| format!("failed to read {}", archive.display()))?;
+    let mut input = BufReader::new(file);
+    let mut magic = [0u8; 4];
+    let mut salt = [0u8; SALT_LEN];
+    input
+        .read_exact(&mut magic)
+        .and_then(|()| input.read_exact(&mut salt))
+        .map_err(|_| anyhow::anyhow!("{} is not an ASTERIX profile backup", archive.display()))?;
+
+    // Unpack next to the profile first so a bad archive leaves it untouched.
+    let staging = sibling(profile, "restoring");
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)
+            .with_context(|| format!("failed to remove {}", staging.display()))?;
+    }
+    if &magic != MAGIC {
+        bail!("{} is not an ASTERIX profile backup", archive.display());
+    }
+    let mut nonce = [0u8; STREAM_NONCE_LEN];
+    input
+        .read_exact(&mut nonce)
+        .with_context(|| format!("{} is truncated", archive.display()))?;
+    let plain = OpeningReader::new(input, cipher(passphrase, &salt), &nonce);
+    let unpacked = unpack(DeflateDecoder::new(plain), &staging);
+    let summary = match unpacked {
+        Ok(summary) => summary,
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            // Every chunk is authenticated, so the first to fail is all
+            // there is to tell of a wrong passphrase.
+            if err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::InvalidData)
+            {
+                bail!("wrong passphrase, or the backup is damaged");
+            }
+            return Err(err);
+        }
+    };
+
+    let previous = sibling(profile, "before-restore");
+    if profile.exists() {
+        if previous.exists() {
+            std::fs::remove_dir_all(&previous)
+                .with_context(|| format!("failed to remove {}", previous.display()))?;
+        }
+        std::fs::rename(profile, &previous)
+            .with_context(|| format!("failed to move {} aside", profile.display()))?;
+    }
+    std::fs::rename(&staging, profile).with_context(|| {
+        format!(
+            "failed to move the restored profile to {}",
+            profile.display()
+        )
+    })?;
+    Ok(summary)
+}
+
+/// Writes the files of the archive read from `plain` under `into`.
+fn unpack(mut plain: impl Read, into: &Path) -> anyhow::Result<BackupSummary> {
+    let mut summary = BackupSummary::default();
+    std::fs::create_dir_all(into)
+        .with_context(|| format!("failed to create {}", into.display()))?;
+    while let Some(name_len) = read_name_len(&mut plain)? {
+        if name_len > MAX_NAME_LEN {
+            bail!("the backup is damaged");
+        }
+        let mut name = vec![0u8; name_len as usize];
+        let mut len = [0u8; 8];
+        plain
+            .read_exact(&mut name)
+            .and_then(|()| plain.read_exact(&mut len))
+            .map_err(damaged)?;
+        let len = u64::from_le_bytes(len);
+        let name = String::from_utf8(name).context("the backup is damaged")?;
+        let relative = Path::new(&name);
+        if !relative
+            .components()
+            .all(|part| matches!(part, Component::Normal(_)))
+        {
+            bail!("the backup contains an unsafe path `{name}`");
+        }
+        let path = into.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let mut file =
+            File::create(&path).with_context(|| format!("failed to write {}", path.display()))?;
+        let copied = io::copy(&mut (&mut plain).take(len), &mut file)?;
+        if copied != len {
+            bail!("the backup is damaged");
+        }
+        summary.files += 1;
+        summary.bytes += len;
+    }
+    Ok(summary)
+}
+
+/// Length of the name of the next archived file, `None` at the end of the
+/// archive.
+fn read_name_len(plain: &mut impl Read) -> anyhow::Result<Option<u32>> {
+    let mut len = [0u8; 4];
+    let mut read = 0;
+    while read < len.len() {
+        match plain.read(&mut len[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => bail!("the backup is damaged"),
+            n => read += n,
+        }
+    }
+    Ok(Some(u32::from_le_bytes(len)))
+}
+
+/// Error for an archive that ended or broke off inside an entry.
+fn damaged(err: io::Error) -> anyhow::Error {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        anyhow::anyhow!("the backup is damaged")
+    } else {
+        err.into()
+    }
+}
+
+/// Lists the regular files under `dir` relative to `root`, leaving out
+/// `skip` so a backup written into the profile does not contain itself,
+/// and the directories of [`SKIPPED_DIRS`] at the top of the profile.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    skip: &Path,
+    files: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            let skipped = dir == root && SKIPPED_DIRS.iter().any(|name| entry.file_name() == *name);
+            if !skipped {
+                collect_files(root, &path, skip, files)?;
+            }
+        } else if kind.is_file() && path != skip {
+            if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `path` with `.suffix` appended to its name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> XChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KEY_DERIVATION_ROUNDS, &mut key);
+    XChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes deflate cannot shrink, so the archive spans several chunks.
+    fn noise(len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        OsRng.fill_bytes(&mut data);
+        data
+    }
+
+    fn profile_with(files: &[(&str, &[u8])]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in files {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn round_trips_without_the_cache() {
+        let large = noise(3 * CHUNK_LEN + 17);
+        let profile = profile_with(&[
+            ("settings.json", b"{}"),
+            ("scripts/a.rhai", b"print(1)"),
+            ("empty", b""),
+            ("large", &large),
+            ("cache/index.json", b"[]"),
+        ]);
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("profile.axbackup");
+        let created = create_backup(profile.path(), &archive, "secret").unwrap();
+        assert_eq!(created.files, 4);
+        assert!(!sibling(&archive, "partial").exists());
+
+        let restored_dir = out.path().join("restored");
+        let restored = restore_backup(&archive, &restored_dir, "secret").unwrap();
+        assert_eq!(restored.files, 4);
+        assert_eq!(restored.bytes, created.bytes);
+        assert_eq!(std::fs::read(restored_dir.join("large")).unwrap(), large);
+        assert_eq!(
+            std::fs::read(restored_dir.join("scripts/a.rhai")).unwrap(),
+            b"print(1)"
+        );
+        assert!(!restored_dir.join("cache").exists());
+    }
+
+    #[test]
+    fn refuses_a_wrong_passphrase_and_truncated_backups() {
+        let profile = profile_with(&[("large", &noise(2 * CHUNK_LEN))]);
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("profile.axbackup");
+        create_backup(profile.path(), &archive, "secret").unwrap();
+        let target = out.path().join("restored");
+
+        let err = restore_backup(&archive, &target, "wrong").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"), "{err:#}");
+        assert!(!target.exists());
+
+        // Cut at a chunk boundary, the rest still looks well formed.
+        let sealed = std::fs::read(&archive).unwrap();
+        let header = MAGIC.len() + SALT_LEN + STREAM_NONCE_LEN;
+        std::fs::write(&archive, &sealed[..header + CHUNK_LEN + TAG_LEN]).unwrap();
+        assert!(restore_backup(&archive, &target, "secret").is_err());
+        assert!(!target.exists());
+
+        std::fs::write(&archive, b"AXB2").unwrap();
+        assert!(restore_backup(&archive, &target, "secret").is_err());
+        std::fs::write(&archive, b"not a backup at all").unwrap();
+        assert!(restore_backup(&archive, &target, "secret").is_err());
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&5u32.to_le_bytes());
+        archive.extend_from_slice(b"../x1");
+        archive.extend_from_slice(&0u64.to_le_bytes());
+        let into = tempfile::tempdir().unwrap();
+        let err = unpack(archive.as_slice(), &into.path().join("p")).unwrap_err();
+        assert!(err.to_string().contains("unsafe path"), "{err:#}");
+    }
+
+    #[test]
+    fn rejects_oversized_names() {
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&u32::MAX.to_le_bytes());
+        archive.extend_from_slice(b"x");
+        let into = tempfile::tempdir().unwrap();
+        let err = unpack(archive.as_slice(), &into.path().join("p")).unwrap_err();
+        assert!(err.to_string().contains("damaged"), "{err:#}");
+    }
+}
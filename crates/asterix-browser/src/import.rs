@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use asterix_core::BrowserCore;
+use asterix_import::{ImportError, ImportSelection, ImportSource};
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::error::TryRecvError;
+
+/// How much of an imported profile was new to this browser.
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub bookmarks: usize,
+    pub history: usize,
+    pub passwords: usize,
+    /// Parts of the profile that could not be read.
+    pub warnings: Vec<String>,
+}
+
+/// Profile import running on the browser runtime.
+pub struct ImportJob {
+    receiver: oneshot::Receiver<Result<ImportSummary, ImportError>>,
+}
+
+impl ImportJob {
+    pub(crate) fn new(receiver: oneshot::Receiver<Result<ImportSummary, ImportError>>) -> Self {
+        Self { receiver }
+    }
+
+    pub fn try_complete(&mut self) -> Option<Result<ImportSummary, ImportError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(ImportError::Format(
+                "the import was cancelled".to_owned(),
+            ))),
+        }
+    }
+}
+
+/// Reads `source` and merges what it holds into the browser's own stores.
+pub(crate) async fn import_into(
+    core: Arc<BrowserCore>,
+    source: ImportSource,
+    selection: ImportSelection,
+) -> Result<ImportSummary, ImportError> {
+    // SQLite and the key derivation for saved passwords both block.
+    let data =
+        tokio::task::spawn_blocking(move || asterix_import::import_profile(&source, selection))
+            .await
+            .expect("profile import panicked")?;
+    Ok(ImportSummary {
+        bookmarks: core.merge_bookmarks(data.bookmarks),
+        history: core.merge_history(data.history),
+        passwords: core.merge_logins(data.logins),
+        warnings: data.warnings,
+    })
+}
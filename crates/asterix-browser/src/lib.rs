@@ -1,26 +1,193 @@
+mod activity;
+mod backup;
+mod import;
+mod macros;
+mod parental;
+mod persist;
+mod priority;
+mod scheduler;
+mod scope;
+mod scripting;
+mod sync;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Context;
-use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
+use chrono::{DateTime, Utc};
+use tokio::runtime::{Builder as RuntimeBuilder, Handle, Runtime};
 use tokio::sync::{mpsc, oneshot};
 use tokio::sync::oneshot::error::TryRecvError;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 use url::Url;
 
-pub use asterix_core::{BrowserError, PageRequest, PageResponse, TabId, TabSnapshot};
-use asterix_core::BrowserCore;
+pub use asterix_core::{
+    alternate_languages, audit_page, decode_qr, element_at, extract_article, extract_forms,
+    extract_links, extract_metadata, extract_seo, favicon_url, flag_enabled, flag_overrides, flags,
+    format_citation, html_to_markdown, is_dead_status, link_selector, map_elements,
+    network_address, page_refresh, parse_magnet, parse_site_file, prefetch_hints, prettify,
+    register_flag, render_document, render_with_stylesheets, select_attribute, select_text,
+    set_flag, set_flag_overrides, subresource_refs, Allowlist, AlternateLanguage, AnnotationStore,
+    ArenaText, Article, ArticleBlock, AuditFinding, AuditRule, AuthChallenge, AuthScheme,
+    BlockRule, Bookmark, BoxModel, BrowserError, CachePolicy, CacheStatus, CachedDocument,
+    CertErrorDetails, CertificateInfo, CitationStyle, ClientIdentity, ColorScheme, ComposedRequest,
+    ComputedStyle, ConnectionInfo, ConnectionLimits, ConnectionUse, Connectivity, ConsentPlatform,
+    ConsentSettings, ConsoleCommand, ContentCoding, ContentDecoding, CrawlOptions, CrawlSummary,
+    CrawledPage, DeclarationId, DecodedImage, DiffLine, DownloadEntry, DownloadStatus,
+    EventSourceEvent, FetchProgress, FileAction, FileTypeRule, FilterList, FilterListInfo, Flag,
+    FlagState, FormEncoding, FormField, FormMethod, Highlight, HistoryEntry, HostConnections,
+    HostIdentity, HstsEntry, HttpCache, HttpVersionPolicy, IdentityError, IdentityFile,
+    ImageCacheStats, IpPreference, IpfsSettings, LinkReport, LinkStatus, MagnetLink, MediaFeatures,
+    MixedContent, MixedContentAction, MultipartPart, Name, NavigationEntry, NavigationSecurity,
+    NetworkConditions, NetworkEntry, PageAnnotations, PageForm, PageLink, PageMetadata,
+    PageRefresh, PageRequest, PageResource, PageResources, PageResponse, PageText, PageWatch,
+    PasswordReport, PrivacyReport, PrivacySettings, PropertyOrigin, ProxyMode, ProxySettings,
+    QrError, RawResponse, RedirectHop, RenderOptions, RenderedPage, RequestAuth, RequestBody,
+    RequestKind, ResourceKind, ResponseOverride, RobotsGroup, RobotsRule, RobotsTxt, SavedLogin,
+    SchemeFuture, SchemeHandler, SeoSummary, ServerSentEvent, ShareCard, SiteFile, SiteUserAgent,
+    Sitemap, SitemapEntry, SitemapKind, SourceElement, SourceLanguage, StructuredData,
+    StructuredFormat, StyleSheet, SubresourceRef, TabId, TabOptions, TabSnapshot,
+    ThrowawayIdentity, Timings, Totp, TotpError, Viewport, WebSocketClose, WebSocketCommand,
+    WebSocketEvent, WebSocketMessage, Workspace, WorkspaceId, WorkspaceSession, CONNECTIVITY_PROBE,
+    CONSENT_PLATFORMS, DEFAULT_IMAGE_CACHE_BYTES, LOCAL_IPFS_GATEWAY, TEXT_ENCODINGS,
+};
+use asterix_core::{BrowserCore, CpuPool};
+pub use asterix_import::{
+    discover_profiles, ImportError, ImportSelection, ImportSource, SourceBrowser,
+};
+pub use asterix_storage::{CookieJar, StorageError, StoredCookie};
+pub use asterix_sync::{
+    Backend as SyncBackend, Collection, SyncBatch, SyncConfig, SyncError, SyncOutcome, SyncRecord,
+    SyncSecrets, SyncState,
+};
+
+use crate::activity::TabActivity;
+use crate::backup::BackupTask;
+use crate::priority::Priority;
+use crate::scheduler::Scheduler;
+use crate::scope::PageScope;
+
+pub use crate::backup::{
+    create_backup, restore_backup, BackupJob, BackupSummary, BACKUP_EXTENSION,
+};
+pub use crate::import::{ImportJob, ImportSummary};
+pub use crate::macros::{load_macros, save_macros, Macro, MacroStep};
+pub use crate::parental::{load_parental_controls, save_parental_controls, ParentalControls};
+pub use crate::scheduler::{JobId, JobKind, ScheduledJob};
+pub use crate::scripting::{ScriptJob, SCRIPT_API};
+pub use crate::sync::{
+    load_sync_config, load_sync_state, save_sync_config, save_sync_state, SyncJob, SyncResult,
+};
 
 enum RuntimeCommand {
     Navigate {
         request: PageRequest,
+        progress: Arc<FetchProgress>,
         respond_to: oneshot::Sender<Result<PageResponse, BrowserError>>,
     },
+    /// Stops every navigation still loading in the tab.
+    CancelNavigation {
+        tab: TabId,
+    },
+    Preload {
+        url: Url,
+    },
+    /// Documents the page `tab` shows hinted at, fetched once no
+    /// navigation is loading.
+    Prefetch {
+        tab: TabId,
+        urls: Vec<Url>,
+    },
+    RevalidateCache,
+    SendRequest {
+        request: ComposedRequest,
+        respond_to: oneshot::Sender<Result<RawResponse, BrowserError>>,
+    },
+    FindArchived {
+        url: Url,
+        respond_to: oneshot::Sender<Result<Option<Url>, BrowserError>>,
+    },
+    CheckPasswords {
+        respond_to: oneshot::Sender<Result<Vec<PasswordReport>, BrowserError>>,
+    },
+    CheckConnectivity {
+        respond_to: oneshot::Sender<Result<Connectivity, BrowserError>>,
+    },
+    FetchImage {
+        tab: TabId,
+        url: Url,
+        respond_to: oneshot::Sender<Result<Arc<DecodedImage>, BrowserError>>,
+    },
+    FetchSubresources {
+        tab: TabId,
+        page: Box<PageResponse>,
+        respond_to: oneshot::Sender<Result<PageResources, BrowserError>>,
+    },
+    /// Waits out the delay of a refresh the page `tab` shows asked for.
+    ScheduleRefresh {
+        tab: TabId,
+        page: Url,
+        refresh: PageRefresh,
+        respond_to: oneshot::Sender<Url>,
+    },
+    Download {
+        url: Url,
+        dir: PathBuf,
+        respond_to: oneshot::Sender<Result<DownloadEntry, BrowserError>>,
+    },
+    SubscribeFilterList {
+        url: Url,
+        respond_to: oneshot::Sender<Result<FilterListInfo, BrowserError>>,
+    },
+    CheckLinks {
+        links: Vec<PageLink>,
+        respond_to: oneshot::Sender<Vec<LinkReport>>,
+    },
+    Crawl {
+        start: Url,
+        options: CrawlOptions,
+        progress: mpsc::UnboundedSender<CrawledPage>,
+        respond_to: oneshot::Sender<CrawlSummary>,
+    },
+    OpenWebSocket {
+        tab: TabId,
+        url: Url,
+        commands: mpsc::UnboundedReceiver<WebSocketCommand>,
+        events: mpsc::UnboundedSender<WebSocketEvent>,
+        respond_to: oneshot::Sender<Result<WebSocketClose, BrowserError>>,
+    },
+    OpenEventSource {
+        tab: TabId,
+        url: Url,
+        events: mpsc::UnboundedSender<EventSourceEvent>,
+        respond_to: oneshot::Sender<Result<(), BrowserError>>,
+    },
+    Sync {
+        config: SyncConfig,
+        secrets: SyncSecrets,
+        batches: Vec<SyncBatch>,
+        respond_to: oneshot::Sender<SyncResult>,
+    },
+    Import {
+        source: ImportSource,
+        selection: ImportSelection,
+        respond_to: oneshot::Sender<Result<ImportSummary, ImportError>>,
+    },
+    Backup {
+        task: BackupTask,
+        respond_to: oneshot::Sender<anyhow::Result<BackupSummary>>,
+    },
     Shutdown,
 }
 
 struct RuntimeInner {
     core: Arc<BrowserCore>,
+    activity: Arc<TabActivity>,
+    scheduler: Arc<Scheduler>,
     tx: mpsc::UnboundedSender<RuntimeCommand>,
+    /// Lets threads outside the runtime, such as scripts, wait on its work.
+    runtime: Handle,
 }
 
 /// Long-lived runtime responsible for executing asynchronous browser work.
@@ -30,28 +197,428 @@ pub struct BrowserRuntime {
     supervisor: Option<tokio::task::JoinHandle<()>>,
 }
 
-impl BrowserRuntime {
-    pub fn new(user_agent: Option<&str>) -> anyhow::Result<Self> {
-        let core = Arc::new(BrowserCore::new(user_agent)?);
-        let runtime = RuntimeBuilder::new_multi_thread()
+/// Worker threads of the async runtime unless the builder says otherwise.
+const DEFAULT_WORKER_THREADS: usize = 4;
+
+/// Settings for a [`BrowserRuntime`] and the threads it runs on, from
+/// [`BrowserRuntime::builder`].
+#[derive(Default)]
+pub struct BrowserRuntimeBuilder {
+    user_agent: Option<String>,
+    cookie_jar: Option<Arc<CookieJar>>,
+    http_cache: Option<HttpCache>,
+    proxy: ProxySettings,
+    connection_limits: ConnectionLimits,
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+    thread_stack_size: Option<usize>,
+    cpu_threads: usize,
+    cpu_stack_size: Option<usize>,
+}
+
+impl BrowserRuntimeBuilder {
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Jar the regular client reads and fills; an empty one in memory by
+    /// default.
+    pub fn cookie_jar(mut self, cookie_jar: Arc<CookieJar>) -> Self {
+        self.cookie_jar = Some(cookie_jar);
+        self
+    }
+
+    pub fn http_cache(mut self, http_cache: Option<HttpCache>) -> Self {
+        self.http_cache = http_cache;
+        self
+    }
+
+    pub fn proxy(mut self, proxy: ProxySettings) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Requests allowed in flight to one host and overall; excess ones wait.
+    pub fn connection_limits(mut self, limits: ConnectionLimits) -> Self {
+        self.connection_limits = limits;
+        self
+    }
+
+    /// Threads driving network I/O and other async work, 4 by default.
+    pub fn worker_threads(mut self, threads: usize) -> Self {
+        self.worker_threads = Some(threads);
+        self
+    }
+
+    /// Most threads kept for blocking work such as file I/O and backups,
+    /// tokio's limit of 512 by default.
+    pub fn max_blocking_threads(mut self, threads: usize) -> Self {
+        self.max_blocking_threads = Some(threads);
+        self
+    }
+
+    /// Stack size in bytes of the async worker and blocking threads.
+    pub fn thread_stack_size(mut self, bytes: usize) -> Self {
+        self.thread_stack_size = Some(bytes);
+        self
+    }
+
+    /// Threads parsing pages and decoding images, one per CPU by default.
+    pub fn cpu_threads(mut self, threads: usize) -> Self {
+        self.cpu_threads = threads;
+        self
+    }
+
+    /// Stack size in bytes of the threads parsing pages and decoding images.
+    pub fn cpu_stack_size(mut self, bytes: usize) -> Self {
+        self.cpu_stack_size = Some(bytes);
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<BrowserRuntime> {
+        let cookie_jar = self
+            .cookie_jar
+            .unwrap_or_else(|| Arc::new(CookieJar::in_memory()));
+        let cpu = CpuPool::new(self.cpu_threads, self.cpu_stack_size)?;
+        let core = BrowserCore::new(
+            self.user_agent.as_deref(),
+            cookie_jar,
+            self.http_cache,
+            self.proxy,
+        )?
+        .with_cpu_pool(cpu);
+        core.set_connection_limits(self.connection_limits);
+        let mut builder = RuntimeBuilder::new_multi_thread();
+        builder
             .enable_io()
             .enable_time()
-            .worker_threads(4)
-            .thread_name("asterix-worker")
+            .worker_threads(self.worker_threads.unwrap_or(DEFAULT_WORKER_THREADS))
+            .thread_name("asterix-worker");
+        if let Some(threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(threads);
+        }
+        if let Some(bytes) = self.thread_stack_size {
+            builder.thread_stack_size(bytes);
+        }
+        let runtime = builder
             .build()
             .context("failed to construct tokio runtime")?;
+        Ok(BrowserRuntime::start(Arc::new(core), runtime))
+    }
+}
+
+impl BrowserRuntime {
+    /// Runtime with the thread counts and stack sizes of the async runtime
+    /// and the CPU pool set up front.
+    pub fn builder() -> BrowserRuntimeBuilder {
+        BrowserRuntimeBuilder::default()
+    }
+
+    pub fn new(
+        user_agent: Option<&str>,
+        cookie_jar: Arc<CookieJar>,
+        http_cache: Option<HttpCache>,
+        proxy: ProxySettings,
+    ) -> anyhow::Result<Self> {
+        let mut builder = Self::builder()
+            .cookie_jar(cookie_jar)
+            .http_cache(http_cache)
+            .proxy(proxy);
+        if let Some(user_agent) = user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        builder.build()
+    }
 
+    /// Runs the command loop for `core` on `runtime`.
+    fn start(core: Arc<BrowserCore>, runtime: Runtime) -> Self {
         let (tx, mut rx) = mpsc::unbounded_channel();
+        let activity = Arc::new(TabActivity::new());
+        core.set_background_gate(activity.background_turns());
         let core_for_task = Arc::clone(&core);
+        let activity_for_task = Arc::clone(&activity);
         let supervisor = runtime.spawn(async move {
+            // Tasks of the page each tab is loading or showing, so they can
+            // be stopped together. Scopes go once their last task is done.
+            let mut scopes: HashMap<TabId, PageScope> = HashMap::new();
             while let Some(command) = rx.recv().await {
                 match command {
-                    RuntimeCommand::Navigate { request, respond_to } => {
-                        let result = core_for_task.fetch_page(request).await;
-                        if respond_to.send(result).is_err() {
-                            warn!("navigation consumer dropped before response arrived");
+                    RuntimeCommand::Navigate {
+                        request,
+                        progress,
+                        respond_to,
+                    } => {
+                        scopes.retain(|_, scope| scope.reap());
+                        let tab = request.tab;
+                        let core = Arc::clone(&core_for_task);
+                        let activity = Arc::clone(&activity_for_task);
+                        let loading = activity.start_loading();
+                        let mut scope = PageScope::for_navigation(&progress);
+                        scope.spawn(async move {
+                            let loading = loading;
+                            let slot = activity.admit(request.tab).await;
+                            let result = if progress.is_cancelled() {
+                                Err(BrowserError::Cancelled)
+                            } else {
+                                core.fetch_page_with_progress(request, &progress).await
+                            };
+                            let favicon = result.as_ref().ok().and_then(|page| {
+                                let icon = favicon_url(page)?;
+                                Some((page.url.clone(), icon))
+                            });
+                            if respond_to.send(result).is_err() && !progress.is_cancelled() {
+                                warn!("navigation consumer dropped before response arrived");
+                            }
+                            // The icon belongs to the page, but no longer
+                            // holds up other navigations.
+                            drop((slot, loading));
+                            if let Some((page, icon)) = favicon {
+                                if let Err(err) = core.load_favicon(tab, &page, icon).await {
+                                    debug!("favicon of {page} failed: {err}");
+                                }
+                            }
+                        });
+                        // The tab is leaving whatever it was loading.
+                        if let Some(previous) = scopes.insert(tab, scope) {
+                            previous.cancel();
                         }
                     }
+                    RuntimeCommand::CancelNavigation { tab } => {
+                        if let Some(scope) = scopes.remove(&tab) {
+                            scope.cancel();
+                        }
+                    }
+                    RuntimeCommand::Preload { url } => {
+                        let core = Arc::clone(&core_for_task);
+                        activity_for_task.spawn_as(Priority::Speculative, async move {
+                            if let Err(err) = core.preload(url.clone()).await {
+                                debug!("preload of {url} failed: {err}");
+                            }
+                        });
+                    }
+                    RuntimeCommand::Prefetch { tab, urls } => {
+                        let core = Arc::clone(&core_for_task);
+                        let activity = Arc::clone(&activity_for_task);
+                        // Hints of a page the tab has left are not followed.
+                        scopes.entry(tab).or_default().spawn(async move {
+                            for url in urls {
+                                activity.idle().await;
+                                let _slot = activity.admit_as(Priority::Speculative).await;
+                                if let Err(err) = core.prefetch(url.clone()).await {
+                                    debug!("prefetch of {url} failed: {err}");
+                                }
+                            }
+                        });
+                    }
+                    RuntimeCommand::RevalidateCache => {
+                        let core = Arc::clone(&core_for_task);
+                        // Long-running jobs take a background turn per
+                        // request, through the core's background gate.
+                        tokio::spawn(async move {
+                            let refreshed = core.revalidate_stale_documents().await;
+                            info!("revalidated {refreshed} stale cached documents");
+                        });
+                    }
+                    RuntimeCommand::SendRequest {
+                        request,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        activity_for_task.spawn_as(Priority::UserInitiated, async move {
+                            let result = core.send_request(request).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("request composer dropped before response arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::FindArchived { url, respond_to } => {
+                        let core = Arc::clone(&core_for_task);
+                        activity_for_task.spawn_as(Priority::UserInitiated, async move {
+                            let result = core.find_archived_copy(&url).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("archive lookup consumer dropped before response arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::CheckPasswords { respond_to } => {
+                        let core = Arc::clone(&core_for_task);
+                        tokio::spawn(async move {
+                            let result = core.check_saved_passwords().await;
+                            if respond_to.send(result).is_err() {
+                                debug!("password check consumer dropped before results arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::CheckConnectivity { respond_to } => {
+                        let core = Arc::clone(&core_for_task);
+                        activity_for_task.spawn_as(Priority::Background, async move {
+                            let result = core.check_connectivity().await;
+                            if respond_to.send(result).is_err() {
+                                debug!("connectivity check consumer dropped before result arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::FetchImage {
+                        tab,
+                        url,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        let activity = Arc::clone(&activity_for_task);
+                        scopes.entry(tab).or_default().spawn(async move {
+                            let _slot = activity.admit_as(Priority::UserInitiated).await;
+                            let result = core.fetch_image(tab, url).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("image consumer dropped before the image arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::FetchSubresources {
+                        tab,
+                        page,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        let activity = Arc::clone(&activity_for_task);
+                        scopes.entry(tab).or_default().spawn(async move {
+                            let _slot = activity.admit_as(Priority::UserInitiated).await;
+                            let resources = core.fetch_subresources(tab, &page).await;
+                            if respond_to.send(Ok(resources)).is_err() {
+                                debug!("subresource consumer dropped before the resources arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::ScheduleRefresh {
+                        tab,
+                        page,
+                        refresh,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        let activity = Arc::clone(&activity_for_task);
+                        // Part of the page, so leaving it calls the refresh off.
+                        scopes.entry(tab).or_default().spawn(async move {
+                            activity.timer(tab, refresh.delay(&page)).await;
+                            if !core.tab_auto_refresh(tab) {
+                                debug!("refresh of {page} turned off in its tab");
+                                return;
+                            }
+                            if respond_to.send(refresh.url).is_err() {
+                                debug!("refresh consumer dropped before the delay ran out");
+                            }
+                        });
+                    }
+                    RuntimeCommand::SubscribeFilterList { url, respond_to } => {
+                        let core = Arc::clone(&core_for_task);
+                        activity_for_task.spawn_as(Priority::UserInitiated, async move {
+                            let result = core.subscribe_filter_list(&url).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("filter list consumer dropped before the list arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::Download {
+                        url,
+                        dir,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        activity_for_task.spawn_as(Priority::UserInitiated, async move {
+                            let result = core.download(url, &dir).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("download consumer dropped before it finished");
+                            }
+                        });
+                    }
+                    RuntimeCommand::CheckLinks { links, respond_to } => {
+                        let core = Arc::clone(&core_for_task);
+                        tokio::spawn(async move {
+                            let reports = core.check_links(links).await;
+                            if respond_to.send(reports).is_err() {
+                                debug!("link check consumer dropped before results arrived");
+                            }
+                        });
+                    }
+                    RuntimeCommand::Crawl {
+                        start,
+                        options,
+                        progress,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        tokio::spawn(async move {
+                            let summary = core.crawl(start, options, progress).await;
+                            if respond_to.send(summary).is_err() {
+                                debug!("crawl consumer dropped before it finished");
+                            }
+                        });
+                    }
+                    RuntimeCommand::OpenWebSocket {
+                        tab,
+                        url,
+                        commands,
+                        events,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        // The page's socket closes when the tab leaves it.
+                        scopes.entry(tab).or_default().spawn(async move {
+                            let result = core.run_websocket(tab, url, commands, events).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("WebSocket consumer dropped before the socket closed");
+                            }
+                        });
+                    }
+                    RuntimeCommand::OpenEventSource {
+                        tab,
+                        url,
+                        events,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        scopes.entry(tab).or_default().spawn(async move {
+                            let result = core.run_event_source(tab, url, events).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("event stream consumer dropped before the stream ended");
+                            }
+                        });
+                    }
+                    RuntimeCommand::Sync {
+                        config,
+                        secrets,
+                        batches,
+                        respond_to,
+                    } => {
+                        let activity = Arc::clone(&activity_for_task);
+                        tokio::spawn(async move {
+                            let result =
+                                sync::sync_collections(&activity, config, secrets, batches).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("sync consumer dropped before it finished");
+                            }
+                        });
+                    }
+                    RuntimeCommand::Import {
+                        source,
+                        selection,
+                        respond_to,
+                    } => {
+                        let core = Arc::clone(&core_for_task);
+                        activity_for_task.spawn_as(Priority::UserInitiated, async move {
+                            let result = import::import_into(core, source, selection).await;
+                            if respond_to.send(result).is_err() {
+                                debug!("import consumer dropped before it finished");
+                            }
+                        });
+                    }
+                    RuntimeCommand::Backup { task, respond_to } => {
+                        tokio::task::spawn_blocking(move || {
+                            if respond_to.send(task.run()).is_err() {
+                                debug!("backup consumer dropped before it finished");
+                            }
+                        });
+                    }
                     RuntimeCommand::Shutdown => {
                         info!("browser runtime shutting down");
                         break;
@@ -60,13 +627,26 @@ impl BrowserRuntime {
             }
         });
 
-        let inner = Arc::new(RuntimeInner { core, tx });
+        let scheduler = Arc::new(Scheduler::new());
+        runtime.spawn(scheduler::run_scheduler_loop(
+            Arc::clone(&scheduler),
+            Arc::clone(&core),
+            Arc::clone(&activity),
+        ));
+
+        let inner = Arc::new(RuntimeInner {
+            core,
+            activity,
+            scheduler,
+            tx,
+            runtime: runtime.handle().clone(),
+        });
 
-        Ok(Self {
+        Self {
             runtime,
             inner,
             supervisor: Some(supervisor),
-        })
+        }
     }
 
     /// Returns a lightweight handle for interacting with the runtime from the UI thread.
@@ -97,34 +677,1530 @@ impl BrowserHandle {
         self.inner.core.create_tab(title)
     }
 
+    pub fn create_tab_with(&self, title: impl Into<String>, options: TabOptions) -> TabSnapshot {
+        self.inner.core.create_tab_with(title, options)
+    }
+
+    pub fn duplicate_tab(&self, tab: TabId) -> Option<TabSnapshot> {
+        self.inner.core.duplicate_tab(tab)
+    }
+
     pub fn tabs(&self) -> Vec<TabSnapshot> {
         self.inner.core.snapshot_tabs()
     }
 
+    /// Marks `tab` as focused so its work is no longer throttled.
+    pub fn set_active_tab(&self, tab: TabId) {
+        self.inner.activity.set_active(tab);
+    }
+
+    /// Changes the user agent `tab` sends from its next request on; `None`
+    /// restores the default.
+    pub fn set_user_agent(&self, tab: TabId, user_agent: Option<String>) {
+        self.inner.core.set_user_agent(tab, user_agent);
+    }
+
+    /// Sets the user agents sent to particular sites from tabs without one
+    /// of their own, taking effect with the next request.
+    pub fn set_site_user_agents(&self, overrides: Vec<SiteUserAgent>) {
+        self.inner.core.set_site_user_agents(overrides);
+    }
+
+    pub fn site_user_agents(&self) -> Vec<SiteUserAgent> {
+        self.inner.core.site_user_agents()
+    }
+
+    pub fn set_tab_viewport(&self, tab: TabId, viewport: Option<Viewport>) {
+        self.inner.core.set_tab_viewport(tab, viewport);
+    }
+
+    /// Quiet tabs defer all network activity until they are focused again.
+    pub fn set_tab_quiet(&self, tab: TabId, quiet: bool) {
+        self.inner.activity.set_quiet(tab, quiet);
+    }
+
+    pub fn is_tab_quiet(&self, tab: TabId) -> bool {
+        self.inner.activity.is_quiet(tab)
+    }
+
+    /// Lets `tab` follow the refreshes pages ask for, or stops it.
+    pub fn set_tab_auto_refresh(&self, tab: TabId, enabled: bool) {
+        self.inner.core.set_tab_auto_refresh(tab, enabled);
+    }
+
+    pub fn tab_auto_refresh(&self, tab: TabId) -> bool {
+        self.inner.core.tab_auto_refresh(tab)
+    }
+
+    pub fn workspaces(&self) -> Vec<Workspace> {
+        self.inner.core.workspaces()
+    }
+
+    pub fn active_workspace(&self) -> WorkspaceId {
+        self.inner.core.active_workspace()
+    }
+
+    pub fn create_workspace(&self, name: impl Into<String>) -> Workspace {
+        self.inner.core.create_workspace(name)
+    }
+
+    pub fn rename_workspace(&self, id: WorkspaceId, name: impl Into<String>) -> bool {
+        self.inner.core.rename_workspace(id, name)
+    }
+
+    /// Activates `id` and hibernates the tabs of every other workspace.
+    pub fn switch_workspace(&self, id: WorkspaceId) -> bool {
+        if !self.inner.core.switch_workspace(id) {
+            return false;
+        }
+        self.hibernate_background_workspaces();
+        true
+    }
+
+    pub fn workspace_tabs(&self, workspace: WorkspaceId) -> Vec<TabSnapshot> {
+        self.inner.core.workspace_tabs(workspace)
+    }
+
+    /// Writes the workspaces and their tabs to `path` as JSON.
+    pub fn save_session(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.export_session())
+    }
+
+    /// Restores workspaces previously written by [`BrowserHandle::save_session`].
+    ///
+    /// Returns `Ok(false)` when no session file exists yet.
+    pub fn load_session(&self, path: &Path) -> anyhow::Result<bool> {
+        let Some(session) = persist::read_json::<WorkspaceSession>(path)? else {
+            return Ok(false);
+        };
+        let restored = !self.inner.core.restore_session(session).is_empty();
+        self.hibernate_background_workspaces();
+        Ok(restored)
+    }
+
+    pub fn annotations_for(&self, url: &Url) -> Option<PageAnnotations> {
+        self.inner.core.annotations_for(url)
+    }
+
+    pub fn set_page_note(&self, url: &Url, title: &str, note: impl Into<String>) {
+        self.inner.core.set_page_note(url, title, note);
+    }
+
+    pub fn add_highlight(&self, url: &Url, title: &str, text: impl Into<String>) {
+        self.inner.core.add_highlight(url, title, text);
+    }
+
+    pub fn remove_highlight(&self, url: &Url, index: usize) {
+        self.inner.core.remove_highlight(url, index);
+    }
+
+    /// Renders all notes and highlights as a Markdown document.
+    pub fn annotations_markdown(&self) -> String {
+        self.inner.core.export_annotations().to_markdown()
+    }
+
+    pub fn save_annotations(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.export_annotations())
+    }
+
+    pub fn load_annotations(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(store) = persist::read_json::<AnnotationStore>(path)? {
+            self.inner.core.import_annotations(store);
+        }
+        Ok(())
+    }
+
+    fn hibernate_background_workspaces(&self) {
+        let active = self.inner.core.active_workspace();
+        let hibernated = self
+            .inner
+            .core
+            .snapshot_tabs()
+            .into_iter()
+            .filter(|tab| tab.workspace != active)
+            .map(|tab| tab.id)
+            .collect();
+        self.inner.activity.set_hibernated(hibernated);
+    }
+
     pub fn request_navigation(&self, tab: TabId, url: Url) -> anyhow::Result<NavigationJob> {
+        self.request_page(PageRequest::new(tab, url))
+    }
+
+    /// Submits a fully specified page request, e.g. a reload that bypasses caches.
+    pub fn request_page(&self, request: PageRequest) -> anyhow::Result<NavigationJob> {
         let (respond_to, receiver) = oneshot::channel();
-        let request = PageRequest { tab, url };
+        let tab = request.tab;
+        let url = request.url.clone();
+        let progress = Arc::new(FetchProgress::new());
 
         self.inner
             .tx
-            .send(RuntimeCommand::Navigate { request, respond_to })
+            .send(RuntimeCommand::Navigate {
+                request,
+                progress: Arc::clone(&progress),
+                respond_to,
+            })
             .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
 
-        Ok(NavigationJob { receiver })
+        Ok(NavigationJob {
+            tab,
+            url,
+            progress,
+            receiver,
+        })
     }
-}
 
-/// Represents an in-flight navigation that the UI can poll for completion.
-pub struct NavigationJob {
-    receiver: oneshot::Receiver<Result<PageResponse, BrowserError>>,
-}
+    /// Stops whatever `tab` is still loading, the document along with the
+    /// images asked for since. Its jobs complete with
+    /// [`BrowserError::Cancelled`]; navigations requested afterwards are not
+    /// affected.
+    pub fn cancel_navigation(&self, tab: TabId) -> anyhow::Result<()> {
+        self.inner
+            .tx
+            .send(RuntimeCommand::CancelNavigation { tab })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))
+    }
 
-impl NavigationJob {
-    pub fn try_complete(&mut self) -> Option<Result<PageResponse, BrowserError>> {
-        match self.receiver.try_recv() {
-            Ok(value) => Some(value),
-            Err(TryRecvError::Empty) => None,
-            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
-        }
+    /// Starts fetching `url` in the background so a follow-up navigation is instant.
+    pub fn preload(&self, url: Url) -> anyhow::Result<()> {
+        self.inner
+            .tx
+            .send(RuntimeCommand::Preload { url })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))
+    }
+
+    /// Fetches `urls`, hinted at by the page `tab` shows, in the background
+    /// once no navigation is loading; leaving the page stops it. See
+    /// [`prefetch_hints`].
+    pub fn prefetch(&self, tab: TabId, urls: Vec<Url>) -> anyhow::Result<()> {
+        self.inner
+            .tx
+            .send(RuntimeCommand::Prefetch { tab, urls })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))
+    }
+
+    /// Checks stale documents in the HTTP cache for changes in the
+    /// background, e.g. once the network is back.
+    pub fn revalidate_stale_documents(&self) -> anyhow::Result<()> {
+        self.inner
+            .tx
+            .send(RuntimeCommand::RevalidateCache)
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))
+    }
+
+    /// User agent requests send unless a tab or site overrides it.
+    pub fn user_agent(&self) -> Option<&str> {
+        self.inner.core.user_agent()
+    }
+
+    /// The HTTP cache documents are stored in, if the browser keeps one.
+    pub fn http_cache(&self) -> Option<&HttpCache> {
+        self.inner.core.http_cache()
+    }
+
+    /// Documents preloaded for upcoming navigations, with when each expires.
+    pub fn preloaded_documents(&self) -> Vec<(Url, DateTime<Utc>)> {
+        self.inner.core.preloaded_documents()
+    }
+
+    /// Empties the HTTP cache and drops preloaded documents.
+    pub fn clear_http_cache(&self) {
+        self.inner.core.clear_http_cache();
+    }
+
+    pub fn decode_image(&self, encoded: &[u8]) -> Result<Arc<DecodedImage>, BrowserError> {
+        self.inner.core.decode_image(encoded)
+    }
+
+    pub fn image_cache_stats(&self) -> ImageCacheStats {
+        self.inner.core.image_cache_stats()
+    }
+
+    pub fn set_image_cache_budget(&self, bytes: usize) {
+        self.inner.core.set_image_cache_budget(bytes);
+    }
+
+    /// Requests captured since startup, oldest first.
+    pub fn network_log(&self) -> Vec<NetworkEntry> {
+        self.inner.core.network_log()
+    }
+
+    /// Requests made for `tab` that have not finished yet.
+    pub fn pending_requests(&self, tab: TabId) -> usize {
+        self.inner.core.pending_requests(tab)
+    }
+
+    pub fn clear_network_log(&self) {
+        self.inner.core.clear_network_log();
+    }
+
+    /// Connections opened and reused per host, busiest first.
+    pub fn connection_stats(&self) -> Vec<HostConnections> {
+        self.inner.core.connection_stats()
+    }
+
+    pub fn block_rules(&self) -> Vec<BlockRule> {
+        self.inner.core.block_rules()
+    }
+
+    pub fn add_block_rule(&self, rule: BlockRule) {
+        self.inner.core.add_block_rule(rule);
+    }
+
+    pub fn remove_block_rule(&self, rule: &BlockRule) {
+        self.inner.core.remove_block_rule(rule);
+    }
+
+    /// Evaluates a developer tools console command against the page shown
+    /// in `tab`.
+    pub fn run_console(
+        &self,
+        tab: TabId,
+        url: &Url,
+        document: &str,
+        command: &ConsoleCommand,
+    ) -> Result<Vec<String>, String> {
+        self.inner.core.run_console(tab, url, document, command)
+    }
+
+    pub fn response_overrides(&self) -> Vec<ResponseOverride> {
+        self.inner.core.response_overrides()
+    }
+
+    pub fn add_response_override(&self, rule: ResponseOverride) {
+        self.inner.core.add_response_override(rule);
+    }
+
+    pub fn remove_response_override(&self, pattern: &str) {
+        self.inner.core.remove_response_override(pattern);
+    }
+
+    /// Sends a hand-written request from the developer tools.
+    pub fn send_request(&self, request: ComposedRequest) -> anyhow::Result<RequestJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::SendRequest {
+                request,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(RequestJob { receiver })
+    }
+
+    /// Turns restricted mode on or off to match `controls`.
+    pub fn apply_parental_controls(&self, controls: &ParentalControls) {
+        self.inner.core.set_allowlist(controls.active_allowlist());
+    }
+
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        self.inner.core.is_allowed(url)
+    }
+
+    /// Rejects cookie banners on every site except those `settings` leaves
+    /// out, or stops doing so with `None`.
+    pub fn set_consent_handling(&self, settings: Option<ConsentSettings>) {
+        self.inner.core.set_consent_handling(settings);
+    }
+
+    pub fn consent_handling(&self) -> Option<ConsentSettings> {
+        self.inner.core.consent_handling()
+    }
+
+    /// Bounds the requests in flight to one host and overall.
+    pub fn set_connection_limits(&self, limits: ConnectionLimits) {
+        self.inner.core.set_connection_limits(limits);
+    }
+
+    pub fn connection_limits(&self) -> ConnectionLimits {
+        self.inner.core.connection_limits()
+    }
+
+    /// Emulates a slow or missing network for requests from now on, or the
+    /// real one with [`NetworkConditions::NONE`].
+    pub fn set_network_conditions(&self, conditions: NetworkConditions) {
+        self.inner.core.set_network_conditions(conditions);
+    }
+
+    pub fn network_conditions(&self) -> NetworkConditions {
+        self.inner.core.network_conditions()
+    }
+
+    /// Turns offline mode on or off. While it is on, navigations are served
+    /// from the HTTP cache and fail with [`BrowserError::Offline`] when the
+    /// page is not there.
+    pub fn set_offline(&self, offline: bool) {
+        self.inner.core.set_offline(offline);
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.inner.core.is_offline()
+    }
+
+    /// Turns tracker protection on with `settings`, or off with `None`.
+    pub fn set_tracker_protection(&self, settings: Option<PrivacySettings>) {
+        self.inner.core.set_tracker_protection(settings);
+    }
+
+    pub fn tracker_protection(&self) -> Option<PrivacySettings> {
+        self.inner.core.tracker_protection()
+    }
+
+    /// Requests the page in `tab` made to other sites that went without
+    /// their cookies.
+    pub fn withheld_cookies(&self, tab: TabId) -> usize {
+        self.inner.core.withheld_cookies(tab)
+    }
+
+    pub fn cookies(&self) -> Vec<StoredCookie> {
+        self.inner.core.cookies()
+    }
+
+    pub fn cookies_for(&self, url: &Url) -> Vec<StoredCookie> {
+        self.inner.core.cookies_for(url)
+    }
+
+    pub fn remove_cookie(&self, cookie: &StoredCookie) -> bool {
+        self.inner.core.remove_cookie(cookie)
+    }
+
+    pub fn remove_cookies_for(&self, domain: &str) -> usize {
+        self.inner.core.remove_cookies_for(domain)
+    }
+
+    pub fn clear_cookies(&self) {
+        self.inner.core.clear_cookies();
+    }
+
+    /// Signs in to the realm `challenge` came from with `username` and
+    /// `password` for the rest of the session. Load the refused address
+    /// again to use them.
+    pub fn answer_auth_challenge(&self, challenge: &AuthChallenge, username: &str, password: &str) {
+        self.inner
+            .core
+            .answer_auth_challenge(challenge, username, password);
+    }
+
+    pub fn clear_http_credentials(&self) {
+        self.inner.core.clear_http_credentials();
+    }
+
+    pub fn save_cookies(&self) -> Result<(), StorageError> {
+        self.inner.core.save_cookies()
+    }
+
+    /// Routes requests from now on through the proxies in `settings`.
+    pub fn set_proxy(&self, settings: ProxySettings) -> anyhow::Result<()> {
+        self.inner.core.set_proxy(settings)
+    }
+
+    pub fn proxy_settings(&self) -> ProxySettings {
+        self.inner.core.proxy_settings()
+    }
+
+    /// Presents each identity to servers of its host asking for a client
+    /// certificate, replacing those set before.
+    pub fn set_client_identities(&self, identities: Vec<HostIdentity>) -> anyhow::Result<()> {
+        self.inner.core.set_client_identities(identities)
+    }
+
+    pub fn client_identities(&self) -> Vec<HostIdentity> {
+        self.inner.core.client_identities()
+    }
+
+    /// Sets which IP versions new connections try first, or turns IPv6 off.
+    pub fn set_ip_preference(&self, preference: IpPreference) {
+        self.inner.core.set_ip_preference(preference);
+    }
+
+    pub fn ip_preference(&self) -> IpPreference {
+        self.inner.core.ip_preference()
+    }
+
+    /// Sets the compression codings requests offer to servers.
+    pub fn set_content_codings(&self, codings: Vec<ContentCoding>) {
+        self.inner.core.set_content_codings(codings);
+    }
+
+    pub fn content_codings(&self) -> Vec<ContentCoding> {
+        self.inner.core.content_codings()
+    }
+
+    /// Sets which HTTP versions requests may use from now on, e.g. HTTP/1.1
+    /// only to rule out a server's HTTP/2 support as the cause of a problem.
+    pub fn set_http_version(&self, policy: HttpVersionPolicy) -> anyhow::Result<()> {
+        self.inner.core.set_http_version(policy)
+    }
+
+    pub fn http_version(&self) -> HttpVersionPolicy {
+        self.inner.core.http_version()
+    }
+
+    /// Has `handler` load every URL with `scheme`, e.g. `gemini`. HTTP and
+    /// HTTPS cannot be taken over.
+    pub fn register_scheme_handler(
+        &self,
+        scheme: &str,
+        handler: impl SchemeHandler + 'static,
+    ) -> Result<(), BrowserError> {
+        self.inner.core.register_scheme_handler(scheme, handler)
+    }
+
+    pub fn unregister_scheme_handler(&self, scheme: &str) -> bool {
+        self.inner.core.unregister_scheme_handler(scheme)
+    }
+
+    /// Schemes with a registered handler, sorted.
+    pub fn scheme_handlers(&self) -> Vec<String> {
+        self.inner.core.scheme_handlers()
+    }
+
+    /// Sets the gateway `ipfs://` and `ipns://` URLs are loaded through.
+    pub fn set_ipfs_settings(&self, settings: IpfsSettings) {
+        self.inner.core.set_ipfs_settings(settings);
+    }
+
+    pub fn ipfs_settings(&self) -> IpfsSettings {
+        self.inner.core.ipfs_settings()
+    }
+
+    /// Whether a local IPFS node answered the last time one was looked for.
+    pub fn local_ipfs_node(&self) -> Option<bool> {
+        self.inner.core.local_ipfs_node()
+    }
+
+    /// `page` re-decoded with the encoding labelled `charset`, or with the
+    /// detected one when `charset` is `None`.
+    pub fn redecode_page(
+        &self,
+        tab: TabId,
+        page: &PageResponse,
+        charset: Option<&str>,
+    ) -> Option<PageResponse> {
+        self.inner.core.redecode_page(tab, page, charset)
+    }
+
+    /// TLS version, cipher and certificate chain of the connection that
+    /// served the page in `tab`.
+    pub fn connection_info(&self, tab: TabId) -> Option<ConnectionInfo> {
+        self.inner.core.connection_info(tab)
+    }
+
+    /// Lets the refused certificate in `error` through for the rest of the
+    /// session, for "Proceed anyway".
+    pub fn add_certificate_exception(&self, error: &CertErrorDetails) {
+        self.inner.core.add_certificate_exception(error);
+    }
+
+    pub fn remove_certificate_exception(&self, host: &str) {
+        self.inner.core.remove_certificate_exception(host);
+    }
+
+    pub fn certificate_exceptions(&self) -> Vec<String> {
+        self.inner.core.certificate_exceptions()
+    }
+
+    /// Steps `tab` back in its history, returning the page to load there.
+    pub fn go_back(&self, tab: TabId) -> Option<NavigationEntry> {
+        self.inner.core.go_back(tab)
+    }
+
+    /// Steps `tab` forward in its history, returning the page to load there.
+    pub fn go_forward(&self, tab: TabId) -> Option<NavigationEntry> {
+        self.inner.core.go_forward(tab)
+    }
+
+    /// Commits a page kept by the shell after stepping `tab` to `url`, as
+    /// if it had just been loaded there.
+    pub fn restore_page(&self, tab: TabId, url: &Url, page: &PageResponse) {
+        self.inner.core.restore_page(tab, url, page);
+    }
+
+    pub fn can_go_back(&self, tab: TabId) -> bool {
+        self.inner.core.can_go_back(tab)
+    }
+
+    pub fn can_go_forward(&self, tab: TabId) -> bool {
+        self.inner.core.can_go_forward(tab)
+    }
+
+    /// Pages visited in `tab`, oldest first, with the index of the one shown.
+    pub fn history_entries(&self, tab: TabId) -> (Vec<NavigationEntry>, usize) {
+        self.inner.core.history_entries(tab)
+    }
+
+    pub fn set_scroll_offset(&self, tab: TabId, offset: f32) {
+        self.inner.core.set_scroll_offset(tab, offset);
+    }
+
+    /// Starts monitoring `url` for content changes every `interval_minutes`.
+    pub fn watch_page(&self, url: Url, title: impl Into<String>, interval_minutes: u32) {
+        self.inner.core.watch_page(url, title, interval_minutes);
+    }
+
+    pub fn unwatch_page(&self, url: &Url) {
+        self.inner.core.unwatch_page(url);
+    }
+
+    pub fn set_watch_interval(&self, url: &Url, interval_minutes: u32) {
+        self.inner.core.set_watch_interval(url, interval_minutes);
+    }
+
+    pub fn mark_watch_seen(&self, url: &Url) {
+        self.inner.core.mark_watch_seen(url);
+    }
+
+    pub fn watches(&self) -> Vec<PageWatch> {
+        self.inner.core.watches()
+    }
+
+    pub fn save_watches(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.watches())
+    }
+
+    pub fn load_watches(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(watches) = persist::read_json::<Vec<PageWatch>>(path)? {
+            self.inner.core.import_watches(watches);
+        }
+        Ok(())
+    }
+
+    pub fn add_bookmark(&self, url: Url, title: impl Into<String>) {
+        self.inner.core.add_bookmark(url, title);
+    }
+
+    pub fn remove_bookmark(&self, url: &Url) {
+        self.inner.core.remove_bookmark(url);
+    }
+
+    pub fn is_bookmarked(&self, url: &Url) -> bool {
+        self.inner.core.is_bookmarked(url)
+    }
+
+    pub fn bookmarks(&self) -> Vec<Bookmark> {
+        self.inner.core.bookmarks()
+    }
+
+    /// Replaces every bookmark, e.g. with the result of a sync.
+    pub fn replace_bookmarks(&self, bookmarks: Vec<Bookmark>) {
+        self.inner.core.import_bookmarks(bookmarks);
+    }
+
+    pub fn save_bookmarks(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.bookmarks())
+    }
+
+    pub fn load_bookmarks(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(bookmarks) = persist::read_json::<Vec<Bookmark>>(path)? {
+            self.inner.core.import_bookmarks(bookmarks);
+        }
+        Ok(())
+    }
+
+    /// Visited pages, most recent first. Private tabs are never recorded.
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        self.inner.core.history()
+    }
+
+    pub fn remove_history_entry(&self, url: &Url) {
+        self.inner.core.remove_history_entry(url);
+    }
+
+    pub fn clear_history(&self) {
+        self.inner.core.clear_history();
+    }
+
+    /// Replaces the whole history, e.g. with the result of a sync.
+    pub fn replace_history(&self, entries: Vec<HistoryEntry>) {
+        self.inner.core.import_history(entries);
+    }
+
+    pub fn save_history(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.history())
+    }
+
+    pub fn load_history(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(entries) = persist::read_json::<Vec<HistoryEntry>>(path)? {
+            self.inner.core.import_history(entries);
+        }
+        Ok(())
+    }
+
+    /// Hosts that may only be reached over HTTPS, sorted by host.
+    pub fn hsts_entries(&self) -> Vec<HstsEntry> {
+        self.inner.core.hsts_entries()
+    }
+
+    pub fn is_hsts_host(&self, host: &str) -> bool {
+        self.inner.core.is_hsts_host(host)
+    }
+
+    pub fn remove_hsts_entry(&self, host: &str) {
+        self.inner.core.remove_hsts_entry(host);
+    }
+
+    pub fn clear_hsts(&self) {
+        self.inner.core.clear_hsts();
+    }
+
+    pub fn save_hsts(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.hsts_entries())
+    }
+
+    pub fn load_hsts(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(entries) = persist::read_json::<Vec<HstsEntry>>(path)? {
+            self.inner.core.import_hsts(entries);
+        }
+        Ok(())
+    }
+
+    /// Saves the profile's choices on `about:flags`.
+    pub fn save_flags(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &flag_overrides())
+    }
+
+    /// Restores the profile's choices on `about:flags`, which apply to the
+    /// whole process.
+    pub fn load_flags(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(overrides) = persist::read_json(path)? {
+            set_flag_overrides(overrides);
+        }
+        Ok(())
+    }
+
+    /// Downloads, most recently started first.
+    pub fn downloads(&self) -> Vec<DownloadEntry> {
+        self.inner.core.downloads()
+    }
+
+    pub fn remove_download(&self, id: u64) {
+        self.inner.core.remove_download(id);
+    }
+
+    pub fn clear_downloads(&self) {
+        self.inner.core.clear_downloads();
+    }
+
+    pub fn save_downloads(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.downloads())
+    }
+
+    pub fn load_downloads(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(entries) = persist::read_json::<Vec<DownloadEntry>>(path)? {
+            self.inner.core.import_downloads(entries);
+        }
+        Ok(())
+    }
+
+    /// Saves a page that was already loaded into `dir` as a download.
+    pub fn save_download(
+        &self,
+        page: &PageResponse,
+        dir: &Path,
+    ) -> Result<DownloadEntry, BrowserError> {
+        self.inner.core.save_download(page, dir)
+    }
+
+    /// Sets what happens to responses by MIME type or extension.
+    pub fn set_file_type_rules(&self, rules: Vec<FileTypeRule>) {
+        self.inner.core.set_file_type_rules(rules);
+    }
+
+    pub fn file_type_rules(&self) -> Vec<FileTypeRule> {
+        self.inner.core.file_type_rules()
+    }
+
+    /// Makes a page the rules held back the current page of `tab`.
+    pub fn open_routed_page(&self, tab: TabId, requested: &Url, page: &PageResponse) {
+        self.inner.core.open_routed_page(tab, requested, page);
+    }
+
+    pub fn saved_logins(&self) -> Vec<SavedLogin> {
+        self.inner.core.saved_logins()
+    }
+
+    pub fn logins_for(&self, url: &Url) -> Vec<SavedLogin> {
+        self.inner.core.logins_for(url)
+    }
+
+    pub fn set_login_totp(
+        &self,
+        origin: &str,
+        username: &str,
+        secret: Option<&str>,
+    ) -> Result<(), TotpError> {
+        self.inner.core.set_login_totp(origin, username, secret)
+    }
+
+    pub fn remove_login(&self, origin: &str, username: &str) {
+        self.inner.core.remove_login(origin, username);
+    }
+
+    /// Writes the saved passwords where only the current user can read them.
+    pub fn save_logins(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.saved_logins())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("failed to restrict {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    pub fn load_logins(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(logins) = persist::read_json::<Vec<SavedLogin>>(path)? {
+            self.inner.core.import_logins(logins);
+        }
+        Ok(())
+    }
+
+    /// Background jobs registered with the scheduler, the built-in watch job first.
+    pub fn jobs(&self) -> Vec<ScheduledJob> {
+        self.inner.scheduler.jobs()
+    }
+
+    pub fn add_job(&self, kind: JobKind, interval_minutes: u32) -> JobId {
+        self.inner.scheduler.add(kind, interval_minutes)
+    }
+
+    /// Removes job `id`; the built-in watch job can only be disabled.
+    pub fn remove_job(&self, id: JobId) -> bool {
+        self.inner.scheduler.remove(id)
+    }
+
+    pub fn set_job_enabled(&self, id: JobId, enabled: bool) {
+        self.inner.scheduler.update(id, |job| job.enabled = enabled);
+    }
+
+    pub fn set_job_interval(&self, id: JobId, interval_minutes: u32) {
+        self.inner
+            .scheduler
+            .update(id, |job| job.interval_minutes = interval_minutes.max(1));
+    }
+
+    /// Runs job `id` on the next scheduler pass, even when it is disabled.
+    pub fn run_job_now(&self, id: JobId) {
+        self.inner.scheduler.run_now(id);
+    }
+
+    pub fn save_jobs(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.scheduler.jobs())
+    }
+
+    pub fn load_jobs(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(jobs) = persist::read_json::<Vec<ScheduledJob>>(path)? {
+            self.inner.scheduler.import(jobs);
+        }
+        Ok(())
+    }
+
+    /// Shows a page the shell draws itself, such as `about:jobs`, in `tab`.
+    pub fn show_internal_page(&self, tab: TabId, url: Url, title: &str) {
+        self.inner.core.show_internal_page(tab, url, title);
+    }
+
+    /// Looks up the latest Wayback Machine snapshot of `url`.
+    pub fn find_archived_copy(&self, url: Url) -> anyhow::Result<ArchiveLookupJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::FindArchived { url, respond_to })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(ArchiveLookupJob { receiver })
+    }
+
+    /// Probes for a captive portal in the background.
+    pub fn check_connectivity(&self) -> anyhow::Result<ConnectivityJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::CheckConnectivity { respond_to })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(ConnectivityJob { receiver })
+    }
+
+    /// Fetches and decodes the image at `url` for `tab` in the background.
+    /// Decoded images are shared by all tabs. The fetch belongs to the page
+    /// in the tab and is abandoned when it navigates away.
+    pub fn fetch_image(&self, tab: TabId, url: Url) -> anyhow::Result<ImageJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::FetchImage {
+                tab,
+                url,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(ImageJob { receiver })
+    }
+
+    /// Fetches the images, stylesheets and scripts an HTML `page` shown in
+    /// `tab` refers to, in the background. Like images, the fetches belong
+    /// to the page and are abandoned when the tab navigates away.
+    pub fn fetch_subresources(
+        &self,
+        tab: TabId,
+        page: &PageResponse,
+    ) -> anyhow::Result<SubresourcesJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::FetchSubresources {
+                tab,
+                page: Box::new(page.clone()),
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(SubresourcesJob { receiver })
+    }
+
+    /// Follows the refresh `page`, shown in `tab`, asks for once its delay
+    /// runs out, unless the tab has refreshes turned off. The refresh is
+    /// called off when the tab navigates or stops loading. Background tabs
+    /// refresh at most once a minute, and quiet or hibernated ones only once
+    /// woken up.
+    pub fn schedule_refresh(
+        &self,
+        tab: TabId,
+        page: &PageResponse,
+    ) -> anyhow::Result<Option<RefreshJob>> {
+        let Some(refresh) = page_refresh(page).filter(|_| self.tab_auto_refresh(tab)) else {
+            return Ok(None);
+        };
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::ScheduleRefresh {
+                tab,
+                page: page.url.clone(),
+                refresh,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(Some(RefreshJob { tab, receiver }))
+    }
+
+    /// Fetches the filter list at `url` in the background and starts
+    /// blocking what it filters.
+    pub fn subscribe_filter_list(&self, url: Url) -> anyhow::Result<FilterListJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::SubscribeFilterList { url, respond_to })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(FilterListJob { receiver })
+    }
+
+    pub fn add_filter_list(&self, list: FilterList) -> FilterListInfo {
+        self.inner.core.add_filter_list(list)
+    }
+
+    pub fn remove_filter_list(&self, name: &str) {
+        self.inner.core.remove_filter_list(name);
+    }
+
+    pub fn filter_lists(&self) -> Vec<FilterListInfo> {
+        self.inner.core.filter_lists()
+    }
+
+    pub fn set_content_blocking(&self, enabled: bool) {
+        self.inner.core.set_content_blocking(enabled);
+    }
+
+    pub fn content_blocking(&self) -> bool {
+        self.inner.core.content_blocking()
+    }
+
+    /// Requests the content blocker stopped for `tab` since it last navigated.
+    pub fn blocked_requests(&self, tab: TabId) -> usize {
+        self.inner.core.blocked_requests(tab)
+    }
+
+    pub fn save_filter_lists(&self, path: &Path) -> anyhow::Result<()> {
+        persist::write_json(path, &self.inner.core.export_filter_lists())
+    }
+
+    pub fn load_filter_lists(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(lists) = persist::read_json::<Vec<FilterList>>(path)? {
+            self.inner.core.import_filter_lists(lists);
+        }
+        Ok(())
+    }
+
+    /// Checks the saved passwords for breaches and reuse in the background.
+    pub fn check_saved_passwords(&self) -> anyhow::Result<PasswordCheckJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::CheckPasswords { respond_to })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(PasswordCheckJob { receiver })
+    }
+
+    /// Saves `url` into `dir` in the background. The download shows up in
+    /// [`BrowserHandle::downloads`] right away.
+    pub fn download(&self, url: Url, dir: PathBuf) -> anyhow::Result<DownloadJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::Download {
+                url,
+                dir,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(DownloadJob { receiver })
+    }
+
+    /// Checks `links` in the background; poll the returned job for the report.
+    pub fn check_links(&self, links: Vec<PageLink>) -> anyhow::Result<LinkCheckJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::CheckLinks { links, respond_to })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(LinkCheckJob { receiver })
+    }
+
+    /// Crawls from `start` in the background; poll the returned job for pages
+    /// as they are fetched. Dropping the job stops the crawl.
+    pub fn crawl(&self, start: Url, options: CrawlOptions) -> anyhow::Result<CrawlJob> {
+        let (progress, pages) = mpsc::unbounded_channel();
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::Crawl {
+                start,
+                options,
+                progress,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(CrawlJob {
+            pages,
+            receiver,
+            finished: None,
+        })
+    }
+
+    /// Opens a WebSocket to the `ws:` or `wss:` endpoint at `url` for `tab`,
+    /// sending its cookies unless the tab is private. Dropping the job, or
+    /// the tab leaving the page it was opened from, closes the socket.
+    pub fn open_websocket(&self, tab: TabId, url: Url) -> anyhow::Result<WebSocketJob> {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (event_tx, events) = mpsc::unbounded_channel();
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::OpenWebSocket {
+                tab,
+                url,
+                commands: command_rx,
+                events: event_tx,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(WebSocketJob {
+            commands,
+            events,
+            receiver,
+            finished: None,
+        })
+    }
+
+    /// Follows the server-sent event stream at `url` for `tab`, reconnecting
+    /// with the last event ID whenever the connection drops. Dropping the
+    /// job, or the tab leaving the page it was opened from, stops it.
+    pub fn open_event_source(&self, tab: TabId, url: Url) -> anyhow::Result<EventSourceJob> {
+        let (event_tx, events) = mpsc::unbounded_channel();
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::OpenEventSource {
+                tab,
+                url,
+                events: event_tx,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+
+        Ok(EventSourceJob {
+            events,
+            receiver,
+            finished: None,
+        })
+    }
+
+    /// Syncs each batch with the server in `config`, one collection at a time.
+    pub fn sync(
+        &self,
+        config: SyncConfig,
+        secrets: SyncSecrets,
+        batches: Vec<SyncBatch>,
+    ) -> anyhow::Result<SyncJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::Sync {
+                config,
+                secrets,
+                batches,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+        Ok(SyncJob::new(receiver))
+    }
+
+    /// Reads the selected data from another browser's profile and adds
+    /// whatever is new to the bookmarks, history and saved passwords.
+    pub fn import_profile(
+        &self,
+        source: ImportSource,
+        selection: ImportSelection,
+    ) -> anyhow::Result<ImportJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::Import {
+                source,
+                selection,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+        Ok(ImportJob::new(receiver))
+    }
+
+    /// Writes an encrypted backup of the profile directory to `destination`.
+    pub fn backup_profile(
+        &self,
+        profile: PathBuf,
+        destination: PathBuf,
+        passphrase: String,
+    ) -> anyhow::Result<BackupJob> {
+        self.run_backup(BackupTask::Create {
+            profile,
+            destination,
+            passphrase,
+        })
+    }
+
+    /// Replaces the profile directory with the contents of a backup. The
+    /// running shell keeps its current state until it is restarted.
+    pub fn restore_profile(
+        &self,
+        archive: PathBuf,
+        profile: PathBuf,
+        passphrase: String,
+    ) -> anyhow::Result<BackupJob> {
+        self.run_backup(BackupTask::Restore {
+            archive,
+            profile,
+            passphrase,
+        })
+    }
+
+    fn run_backup(&self, task: BackupTask) -> anyhow::Result<BackupJob> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.inner
+            .tx
+            .send(RuntimeCommand::Backup { task, respond_to })
+            .map_err(|_| anyhow::anyhow!("browser runtime is no longer running"))?;
+        Ok(BackupJob::new(receiver))
+    }
+}
+
+/// Represents an in-flight navigation that the UI can poll for completion.
+pub struct NavigationJob {
+    tab: TabId,
+    url: Url,
+    progress: Arc<FetchProgress>,
+    receiver: oneshot::Receiver<Result<PageResponse, BrowserError>>,
+}
+
+impl NavigationJob {
+    /// Tab the navigation was issued for.
+    pub fn tab(&self) -> TabId {
+        self.tab
+    }
+
+    /// Location that was requested.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// How much of the document has arrived so far.
+    pub fn progress(&self) -> &FetchProgress {
+        &self.progress
+    }
+
+    /// Stops the download right away, closing the connection; the job then
+    /// completes with [`BrowserError::Cancelled`].
+    pub fn cancel(&self) {
+        self.progress.cancel();
+    }
+
+    pub fn try_complete(&mut self) -> Option<Result<PageResponse, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+
+    /// Resolves once the navigation has finished.
+    pub async fn complete(&mut self) -> Result<PageResponse, BrowserError> {
+        (&mut self.receiver)
+            .await
+            .unwrap_or(Err(BrowserError::Cancelled))
+    }
+}
+
+/// Composed request in flight on the browser runtime.
+pub struct RequestJob {
+    receiver: oneshot::Receiver<Result<RawResponse, BrowserError>>,
+}
+
+impl RequestJob {
+    pub fn try_complete(&mut self) -> Option<Result<RawResponse, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Wayback Machine lookup running on the browser runtime.
+pub struct ArchiveLookupJob {
+    receiver: oneshot::Receiver<Result<Option<Url>, BrowserError>>,
+}
+
+impl ArchiveLookupJob {
+    pub fn try_complete(&mut self) -> Option<Result<Option<Url>, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Captive portal probe running on the browser runtime.
+pub struct ConnectivityJob {
+    receiver: oneshot::Receiver<Result<Connectivity, BrowserError>>,
+}
+
+impl ConnectivityJob {
+    pub fn try_complete(&mut self) -> Option<Result<Connectivity, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Image fetch and decode running on the browser runtime.
+pub struct ImageJob {
+    receiver: oneshot::Receiver<Result<Arc<DecodedImage>, BrowserError>>,
+}
+
+impl ImageJob {
+    pub fn try_complete(&mut self) -> Option<Result<Arc<DecodedImage>, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Subresource fetches of a page running on the browser runtime.
+pub struct SubresourcesJob {
+    receiver: oneshot::Receiver<Result<PageResources, BrowserError>>,
+}
+
+impl SubresourcesJob {
+    pub fn try_complete(&mut self) -> Option<Result<PageResources, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Refresh a page asked for, waiting out its delay on the browser runtime.
+pub struct RefreshJob {
+    tab: TabId,
+    receiver: oneshot::Receiver<Url>,
+}
+
+impl RefreshJob {
+    pub fn tab(&self) -> TabId {
+        self.tab
+    }
+
+    /// Page to load once the delay has run out.
+    pub fn try_complete(&mut self) -> Option<Result<Url, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(url) => Some(Ok(url)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Filter list subscription running on the browser runtime.
+pub struct FilterListJob {
+    receiver: oneshot::Receiver<Result<FilterListInfo, BrowserError>>,
+}
+
+impl FilterListJob {
+    pub fn try_complete(&mut self) -> Option<Result<FilterListInfo, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Breach check of the saved passwords running on the browser runtime.
+pub struct PasswordCheckJob {
+    receiver: oneshot::Receiver<Result<Vec<PasswordReport>, BrowserError>>,
+}
+
+impl PasswordCheckJob {
+    pub fn try_complete(&mut self) -> Option<Result<Vec<PasswordReport>, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Download running on the browser runtime.
+pub struct DownloadJob {
+    receiver: oneshot::Receiver<Result<DownloadEntry, BrowserError>>,
+}
+
+impl DownloadJob {
+    pub fn try_complete(&mut self) -> Option<Result<DownloadEntry, BrowserError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+        }
+    }
+}
+
+/// Link check running on the browser runtime.
+pub struct LinkCheckJob {
+    receiver: oneshot::Receiver<Vec<LinkReport>>,
+}
+
+impl LinkCheckJob {
+    /// Returns the reports once every link has been checked. A check that was
+    /// torn down early yields an empty report.
+    pub fn try_complete(&mut self) -> Option<Vec<LinkReport>> {
+        match self.receiver.try_recv() {
+            Ok(reports) => Some(reports),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Vec::new()),
+        }
+    }
+}
+
+/// Crawl running on the browser runtime.
+pub struct CrawlJob {
+    pages: mpsc::UnboundedReceiver<CrawledPage>,
+    receiver: oneshot::Receiver<CrawlSummary>,
+    /// Totals received while pages were still waiting to be taken.
+    finished: Option<CrawlSummary>,
+}
+
+impl CrawlJob {
+    /// Next page fetched since the last call, if any.
+    pub fn try_next(&mut self) -> Option<CrawledPage> {
+        self.pages.try_recv().ok()
+    }
+
+    /// Returns the totals once the crawl has ended and every page has been
+    /// taken with [`CrawlJob::try_next`].
+    pub fn try_complete(&mut self) -> Option<CrawlSummary> {
+        if self.finished.is_none() {
+            self.finished = match self.receiver.try_recv() {
+                Ok(summary) => Some(summary),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Closed) => Some(CrawlSummary {
+                    cancelled: true,
+                    ..CrawlSummary::default()
+                }),
+            };
+        }
+        // The crawl stops sending pages before it reports its totals.
+        if self.pages.is_empty() {
+            self.finished.take()
+        } else {
+            None
+        }
+    }
+
+    /// Waits for the next page, returning `None` once the crawl has ended.
+    /// Must not be called from within the browser runtime.
+    pub fn blocking_next(&mut self) -> Option<CrawledPage> {
+        self.pages.blocking_recv()
+    }
+
+    /// Waits for the crawl to end and returns its totals.
+    pub fn blocking_finish(self) -> CrawlSummary {
+        self.receiver.blocking_recv().unwrap_or(CrawlSummary {
+            cancelled: true,
+            ..CrawlSummary::default()
+        })
+    }
+}
+
+/// WebSocket open on the browser runtime.
+pub struct WebSocketJob {
+    commands: mpsc::UnboundedSender<WebSocketCommand>,
+    events: mpsc::UnboundedReceiver<WebSocketEvent>,
+    receiver: oneshot::Receiver<Result<WebSocketClose, BrowserError>>,
+    /// Outcome received while events were still waiting to be taken.
+    finished: Option<Result<WebSocketClose, BrowserError>>,
+}
+
+impl WebSocketJob {
+    /// Queues `message`; it is sent once the handshake has completed.
+    pub fn send(&self, message: WebSocketMessage) -> anyhow::Result<()> {
+        self.commands
+            .send(WebSocketCommand::Send(message))
+            .map_err(|_| anyhow::anyhow!("WebSocket is closed"))
+    }
+
+    /// Starts the closing handshake with `code` and `reason`.
+    pub fn close(&self, code: u16, reason: impl Into<String>) -> anyhow::Result<()> {
+        self.commands
+            .send(WebSocketCommand::Close {
+                code,
+                reason: reason.into(),
+            })
+            .map_err(|_| anyhow::anyhow!("WebSocket is closed"))
+    }
+
+    /// Next event since the last call, if any.
+    pub fn try_next(&mut self) -> Option<WebSocketEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Waits for the next event, returning `None` once the socket is closed.
+    pub async fn next(&mut self) -> Option<WebSocketEvent> {
+        self.events.recv().await
+    }
+
+    /// Returns how the socket ended once it is closed and every event has
+    /// been taken with [`WebSocketJob::try_next`].
+    pub fn try_complete(&mut self) -> Option<Result<WebSocketClose, BrowserError>> {
+        if self.finished.is_none() {
+            self.finished = match self.receiver.try_recv() {
+                Ok(result) => Some(result),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+            };
+        }
+        // The socket stops sending events before it reports how it ended.
+        if self.events.is_empty() {
+            self.finished.take()
+        } else {
+            None
+        }
+    }
+
+    /// Waits for the next event, returning `None` once the socket is closed.
+    /// Must not be called from within the browser runtime.
+    pub fn blocking_next(&mut self) -> Option<WebSocketEvent> {
+        self.events.blocking_recv()
+    }
+
+    /// Waits for the socket to close and returns how it ended.
+    pub fn blocking_finish(self) -> Result<WebSocketClose, BrowserError> {
+        self.receiver
+            .blocking_recv()
+            .unwrap_or(Err(BrowserError::Cancelled))
+    }
+}
+
+/// Server-sent event stream followed on the browser runtime.
+pub struct EventSourceJob {
+    events: mpsc::UnboundedReceiver<EventSourceEvent>,
+    receiver: oneshot::Receiver<Result<(), BrowserError>>,
+    /// Outcome received while events were still waiting to be taken.
+    finished: Option<Result<(), BrowserError>>,
+}
+
+impl EventSourceJob {
+    /// Stops following the stream; events already received can still be
+    /// taken.
+    pub fn close(&mut self) {
+        self.events.close();
+    }
+
+    /// Next event since the last call, if any.
+    pub fn try_next(&mut self) -> Option<EventSourceEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Waits for the next event, returning `None` once the stream has ended.
+    pub async fn next(&mut self) -> Option<EventSourceEvent> {
+        self.events.recv().await
+    }
+
+    /// Returns how the stream ended once it has and every event has been
+    /// taken with [`EventSourceJob::try_next`]. Streams only end when closed
+    /// or when the server refuses them.
+    pub fn try_complete(&mut self) -> Option<Result<(), BrowserError>> {
+        if self.finished.is_none() {
+            self.finished = match self.receiver.try_recv() {
+                Ok(result) => Some(result),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Closed) => Some(Err(BrowserError::Cancelled)),
+            };
+        }
+        // The stream stops sending events before it reports how it ended.
+        if self.events.is_empty() {
+            self.finished.take()
+        } else {
+            None
+        }
+    }
+
+    /// Waits for the next event, returning `None` once the stream has ended.
+    /// Must not be called from within the browser runtime.
+    pub fn blocking_next(&mut self) -> Option<EventSourceEvent> {
+        self.events.blocking_recv()
+    }
+
+    /// Waits for the stream to end and returns why.
+    pub fn blocking_finish(self) -> Result<(), BrowserError> {
+        self.receiver
+            .blocking_recv()
+            .unwrap_or(Err(BrowserError::Cancelled))
     }
 }
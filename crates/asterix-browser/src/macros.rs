@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::persist::{read_json, write_json};
+
+/// One recorded user action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MacroStep {
+    Open { url: String },
+    Click { selector: String },
+    Fill { selector: String, value: String },
+    Submit { selector: String },
+}
+
+impl MacroStep {
+    /// Text of the step that may contain `{{name}}` parameters.
+    pub fn value(&self) -> &str {
+        match self {
+            MacroStep::Open { url } => url,
+            MacroStep::Fill { value, .. } => value,
+            MacroStep::Click { selector } | MacroStep::Submit { selector } => selector,
+        }
+    }
+
+    pub fn value_mut(&mut self) -> &mut String {
+        match self {
+            MacroStep::Open { url } => url,
+            MacroStep::Fill { value, .. } => value,
+            MacroStep::Click { selector } | MacroStep::Submit { selector } => selector,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MacroStep::Open { .. } => "Open",
+            MacroStep::Click { .. } => "Click",
+            MacroStep::Fill { .. } => "Fill",
+            MacroStep::Submit { .. } => "Submit",
+        }
+    }
+}
+
+/// A recorded sequence of browsing steps, replayed as a script.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    /// Names of the `{{name}}` placeholders in the steps, in order of first use.
+    pub fn parameters(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for step in &self.steps {
+            let mut rest = step.value();
+            while let Some((_, after)) = rest.split_once("{{") {
+                let Some((name, after)) = after.split_once("}}") else {
+                    break;
+                };
+                let name = name.trim();
+                if !name.is_empty() && !names.iter().any(|known| known == name) {
+                    names.push(name.to_owned());
+                }
+                rest = after;
+            }
+        }
+        names
+    }
+
+    /// Rhai script performing the steps, with `values` substituted for the
+    /// parameters; unknown parameters become empty.
+    pub fn to_script(&self, values: &HashMap<String, String>) -> String {
+        let substitute = |text: &str| {
+            let mut out = String::new();
+            let mut rest = text;
+            while let Some((before, after)) = rest.split_once("{{") {
+                let Some((name, after)) = after.split_once("}}") else {
+                    break;
+                };
+                out.push_str(before);
+                out.push_str(values.get(name.trim()).map_or("", String::as_str));
+                rest = after;
+            }
+            out.push_str(rest);
+            rhai_string(&out)
+        };
+
+        let mut script = format!("// Macro \"{}\"\n", self.name);
+        for step in &self.steps {
+            let line = match step {
+                MacroStep::Open { url } => format!("open({});\nwait_for_load();", substitute(url)),
+                MacroStep::Click { selector } => {
+                    format!("click({});\nwait_for_load();", substitute(selector))
+                }
+                MacroStep::Fill { selector, value } => {
+                    format!("fill({}, {});", substitute(selector), substitute(value))
+                }
+                MacroStep::Submit { selector } => {
+                    format!("submit({});\nwait_for_load();", substitute(selector))
+                }
+            };
+            script.push_str(&line);
+            script.push('\n');
+            if !matches!(step, MacroStep::Fill { .. }) {
+                script.push_str("print(`${status()} ${url()}`);\n");
+            }
+        }
+        script
+    }
+}
+
+/// Quotes `text` as a Rhai string literal.
+fn rhai_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Loads the macros saved at `path`, or none when it does not exist yet.
+pub fn load_macros(path: &Path) -> anyhow::Result<Vec<Macro>> {
+    Ok(read_json(path)?.unwrap_or_default())
+}
+
+pub fn save_macros(path: &Path, macros: &[Macro]) -> anyhow::Result<()> {
+    write_json(path, &macros)
+}
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use asterix_core::Allowlist;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::persist;
+
+/// PBKDF2 rounds for hashing the administrator passphrase.
+const PASSPHRASE_ROUNDS: u32 = 210_000;
+
+/// Restricted browsing mode: when enabled only the allowed domains can be
+/// visited, and changing settings needs the administrator passphrase.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParentalControls {
+    pub enabled: bool,
+    pub allowlist: Allowlist,
+    passphrase: Option<PassphraseHash>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PassphraseHash {
+    salt: String,
+    hash: String,
+}
+
+impl ParentalControls {
+    pub fn has_passphrase(&self) -> bool {
+        self.passphrase.is_some()
+    }
+
+    pub fn set_passphrase(&mut self, passphrase: &str) {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        self.passphrase = Some(PassphraseHash {
+            salt: to_hex(&salt),
+            hash: to_hex(&hash_passphrase(passphrase, &salt)),
+        });
+    }
+
+    /// Whether `passphrase` is the administrator passphrase. Always true
+    /// while none has been set.
+    pub fn verify(&self, passphrase: &str) -> bool {
+        let Some(stored) = &self.passphrase else {
+            return true;
+        };
+        let Some(salt) = from_hex(&stored.salt) else {
+            return false;
+        };
+        let hash = to_hex(&hash_passphrase(passphrase, &salt));
+        // Compare without stopping at the first difference.
+        hash.len() == stored.hash.len()
+            && hash
+                .bytes()
+                .zip(stored.hash.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    /// The allowlist to enforce, if restricted mode is on.
+    pub(crate) fn active_allowlist(&self) -> Option<Allowlist> {
+        self.enabled.then(|| self.allowlist.clone())
+    }
+}
+
+fn hash_passphrase(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PASSPHRASE_ROUNDS, &mut hash);
+    hash
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Reads the restricted mode settings, or the defaults (off).
+pub fn load_parental_controls(path: &Path) -> anyhow::Result<ParentalControls> {
+    Ok(persist::read_json(path)?.unwrap_or_default())
+}
+
+pub fn save_parental_controls(path: &Path, controls: &ParentalControls) -> anyhow::Result<()> {
+    persist::write_json(path, controls)
+}
@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Reads a JSON document written by [`write_json`], returning `None` if the
+/// file does not exist yet.
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let value = serde_json::from_slice(&json)
+        .with_context(|| format!("failed to decode {}", path.display()))?;
+    Ok(Some(value))
+}
+
+/// Writes `value` as pretty-printed JSON, creating parent directories as needed.
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let json = serde_json::to_vec_pretty(value)
+        .with_context(|| format!("failed to encode {}", path.display()))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+}
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use asterix_core::{BackgroundGate, BackgroundTurn};
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+/// Slots speculative and background work share while the user waits on
+/// nothing.
+const SHARED_SLOTS: usize = 4;
+
+/// Slots left to them while user-initiated work is running, so it has the
+/// network to itself save for one straggler.
+const SHARED_SLOTS_WHILE_BUSY: usize = 1;
+
+/// How urgently work started by a runtime command should run, most urgent
+/// first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Priority {
+    /// Something the user just asked for, like a navigation in the focused
+    /// tab. Never waits.
+    UserInitiated,
+    /// Work done ahead of the user: preloads, prefetches and loads in tabs
+    /// they are not looking at.
+    Speculative,
+    /// Crawls, feed refreshes, checks and other jobs nobody is waiting on.
+    /// Jobs that run for long take a turn per request instead, through
+    /// [`BackgroundTurns`], so they never hold a shared slot throughout.
+    Background,
+}
+
+/// Classes that may have to wait, with the slots each is handed in turn
+/// before the other gets one when both are waiting.
+const QUEUED: [(Priority, usize); 2] = [(Priority::Speculative, 3), (Priority::Background, 1)];
+
+struct Queues {
+    /// User-initiated work running.
+    urgent: usize,
+    /// Speculative and background work running.
+    shared: usize,
+    waiting: [VecDeque<oneshot::Sender<Slot>>; 2],
+    /// Queue being served in the current round and the turns it has left.
+    serving: usize,
+    turns: usize,
+}
+
+impl Default for Queues {
+    fn default() -> Self {
+        Self {
+            urgent: 0,
+            shared: 0,
+            waiting: Default::default(),
+            // The first round goes to speculative work.
+            serving: QUEUED.len() - 1,
+            turns: 0,
+        }
+    }
+}
+
+impl Queues {
+    fn limit(&self) -> usize {
+        if self.urgent > 0 {
+            SHARED_SLOTS_WHILE_BUSY
+        } else {
+            SHARED_SLOTS
+        }
+    }
+
+    /// Next waiter by weighted round robin, moving on to the other queue
+    /// once the current one is empty or out of turns.
+    fn next_waiter(&mut self) -> Option<(Priority, oneshot::Sender<Slot>)> {
+        for _ in 0..=QUEUED.len() {
+            if self.turns > 0 {
+                if let Some(waiter) = self.waiting[self.serving].pop_front() {
+                    self.turns -= 1;
+                    return Some((QUEUED[self.serving].0, waiter));
+                }
+            }
+            self.serving = (self.serving + 1) % QUEUED.len();
+            self.turns = QUEUED[self.serving].1;
+        }
+        None
+    }
+}
+
+/// Admits runtime work by [`Priority`]: user-initiated work starts at once
+/// and squeezes the rest down to a single slot, which speculative and
+/// background work share by weighted round robin so neither starves.
+#[derive(Default)]
+pub(crate) struct PriorityQueue {
+    queues: Mutex<Queues>,
+}
+
+/// Admission of one piece of work, given back when dropped.
+pub(crate) struct Slot {
+    queue: Arc<PriorityQueue>,
+    priority: Priority,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        {
+            let mut queues = self.queue.queues.lock();
+            match self.priority {
+                Priority::UserInitiated => queues.urgent -= 1,
+                _ => queues.shared -= 1,
+            }
+        }
+        self.queue.dispatch();
+    }
+}
+
+impl PriorityQueue {
+    /// Waits until work of `priority` may run.
+    pub(crate) async fn admit(self: &Arc<Self>, priority: Priority) -> Slot {
+        let waiting = {
+            let mut queues = self.queues.lock();
+            if priority == Priority::UserInitiated {
+                queues.urgent += 1;
+                return self.slot(priority);
+            }
+            let queue = QUEUED
+                .iter()
+                .position(|(class, _)| *class == priority)
+                .unwrap_or_default();
+            let (tx, rx) = oneshot::channel();
+            queues.waiting[queue].push_back(tx);
+            rx
+        };
+        self.dispatch();
+        // Waiters are only ever taken off the queue to be sent a slot.
+        waiting.await.expect("priority queue dropped a waiter")
+    }
+
+    fn slot(self: &Arc<Self>, priority: Priority) -> Slot {
+        Slot {
+            queue: Arc::clone(self),
+            priority,
+        }
+    }
+
+    /// Hands free slots to waiters. Waiters that gave up are skipped; a slot
+    /// sent to one that gives up later comes back when it is dropped.
+    fn dispatch(self: &Arc<Self>) {
+        loop {
+            let (priority, waiter) = {
+                let mut queues = self.queues.lock();
+                if queues.shared >= queues.limit() {
+                    return;
+                }
+                let Some(next) = queues.next_waiter() else {
+                    return;
+                };
+                queues.shared += 1;
+                next
+            };
+            // A refused slot is dropped here, which gives it back and
+            // dispatches again.
+            let _ = waiter.send(self.slot(priority));
+        }
+    }
+}
+
+/// Admits each request of the core's background jobs as
+/// [`Priority::Background`] work of its own.
+pub(crate) struct BackgroundTurns(pub(crate) Arc<PriorityQueue>);
+
+impl BackgroundGate for BackgroundTurns {
+    fn admit(&self) -> BackgroundTurn {
+        let queue = Arc::clone(&self.0);
+        Box::pin(async move { Box::new(queue.admit(Priority::Background).await) as _ })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn background_turns_free_the_shared_slot_between_requests() {
+        let queue = Arc::new(PriorityQueue::default());
+        let _urgent = queue.admit(Priority::UserInitiated).await;
+        let turns = BackgroundTurns(Arc::clone(&queue));
+
+        let turn = turns.admit().await;
+        let speculative = queue.admit(Priority::Speculative);
+        tokio::pin!(speculative);
+        assert!(timeout(Duration::from_millis(20), &mut speculative)
+            .await
+            .is_err());
+
+        // The job's next request queues behind the speculative work that
+        // was waiting when its turn ended.
+        drop(turn);
+        let _speculative = timeout(Duration::from_secs(1), speculative)
+            .await
+            .expect("speculative work was not admitted");
+        let next_turn = turns.admit();
+        tokio::pin!(next_turn);
+        assert!(timeout(Duration::from_millis(20), &mut next_turn)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use asterix_core::BrowserCore;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tracing::{debug, info};
+use url::Url;
+
+use crate::activity::TabActivity;
+use crate::priority::Priority;
+
+/// How often the scheduler looks for jobs that are due.
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+
+/// Entries listed in a feed job's result before the rest are summarised.
+const LISTED_FEED_ENTRIES: usize = 3;
+
+/// Identifier of a job registered with the scheduler.
+pub type JobId = u64;
+
+/// Work a scheduled job performs each time it runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobKind {
+    /// Refetches watched pages whose own interval has elapsed. Always present.
+    WatchPages,
+    /// Fetches an RSS or Atom feed and reports entries not seen before.
+    RefreshFeed { url: Url },
+    /// Keeps a pinned site in the preload cache so opening it is instant.
+    WarmCache { url: Url },
+}
+
+impl JobKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobKind::WatchPages => "Watch pages",
+            JobKind::RefreshFeed { .. } => "Refresh feed",
+            JobKind::WarmCache { .. } => "Warm cache",
+        }
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        match self {
+            JobKind::WatchPages => None,
+            JobKind::RefreshFeed { url } | JobKind::WarmCache { url } => Some(url),
+        }
+    }
+}
+
+/// A job registered with the scheduler, with the outcome of its last run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub interval_minutes: u32,
+    pub enabled: bool,
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+    /// Summary of the last run, or why it failed.
+    #[serde(default)]
+    pub last_result: Option<Result<String, String>>,
+    /// Feed entries already reported by a `RefreshFeed` job.
+    #[serde(default)]
+    pub seen_entries: Vec<String>,
+    #[serde(skip)]
+    pub running: bool,
+    #[serde(skip)]
+    run_now: bool,
+}
+
+impl ScheduledJob {
+    fn new(id: JobId, kind: JobKind, interval_minutes: u32) -> Self {
+        Self {
+            id,
+            kind,
+            interval_minutes: interval_minutes.max(1),
+            enabled: true,
+            last_run: None,
+            last_result: None,
+            seen_entries: Vec::new(),
+            running: false,
+            run_now: false,
+        }
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        if self.running {
+            return false;
+        }
+        self.run_now
+            || (self.enabled
+                && self.last_run.is_none_or(|last| {
+                    now - last >= chrono::Duration::minutes(i64::from(self.interval_minutes))
+                }))
+    }
+}
+
+/// Registered jobs shared between the handle and the scheduler loop.
+pub(crate) struct Scheduler {
+    jobs: Mutex<Vec<ScheduledJob>>,
+    wake: Notify,
+}
+
+impl Scheduler {
+    /// Starts with the built-in job that checks watched pages every minute.
+    pub(crate) fn new() -> Self {
+        Self {
+            jobs: Mutex::new(vec![ScheduledJob::new(1, JobKind::WatchPages, 1)]),
+            wake: Notify::new(),
+        }
+    }
+
+    pub(crate) fn jobs(&self) -> Vec<ScheduledJob> {
+        self.jobs.lock().clone()
+    }
+
+    pub(crate) fn add(&self, kind: JobKind, interval_minutes: u32) -> JobId {
+        let mut jobs = self.jobs.lock();
+        let id = jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        jobs.push(ScheduledJob::new(id, kind, interval_minutes));
+        drop(jobs);
+        self.wake.notify_one();
+        id
+    }
+
+    /// Removes job `id`; the built-in watch job can only be disabled.
+    pub(crate) fn remove(&self, id: JobId) -> bool {
+        let mut jobs = self.jobs.lock();
+        let before = jobs.len();
+        jobs.retain(|job| job.id != id || job.kind == JobKind::WatchPages);
+        jobs.len() != before
+    }
+
+    pub(crate) fn update(&self, id: JobId, change: impl FnOnce(&mut ScheduledJob)) {
+        if let Some(job) = self.jobs.lock().iter_mut().find(|job| job.id == id) {
+            change(job);
+        }
+    }
+
+    pub(crate) fn run_now(&self, id: JobId) {
+        self.update(id, |job| job.run_now = true);
+        self.wake.notify_one();
+    }
+
+    /// Replaces the registered jobs with `jobs` loaded from the profile,
+    /// keeping the built-in watch job if the file lacks it.
+    pub(crate) fn import(&self, mut jobs: Vec<ScheduledJob>) {
+        if !jobs.iter().any(|job| job.kind == JobKind::WatchPages) {
+            let id = jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+            jobs.insert(0, ScheduledJob::new(id, JobKind::WatchPages, 1));
+        }
+        *self.jobs.lock() = jobs;
+        self.wake.notify_one();
+    }
+
+    fn claim_due(&self) -> Vec<ScheduledJob> {
+        let now = Utc::now();
+        let mut jobs = self.jobs.lock();
+        jobs.iter_mut()
+            .filter(|job| job.is_due(now))
+            .map(|job| {
+                job.running = true;
+                job.run_now = false;
+                job.last_run = Some(now);
+                job.clone()
+            })
+            .collect()
+    }
+
+    fn finish(&self, id: JobId, result: Result<String, String>, seen_entries: Option<Vec<String>>) {
+        self.update(id, |job| {
+            job.running = false;
+            job.last_result = Some(result);
+            if let Some(seen) = seen_entries {
+                job.seen_entries = seen;
+            }
+        });
+    }
+}
+
+/// Runs due jobs in the background for as long as the runtime lives, whether
+/// or not the UI is doing anything.
+pub(crate) async fn run_scheduler_loop(
+    scheduler: Arc<Scheduler>,
+    core: Arc<BrowserCore>,
+    activity: Arc<TabActivity>,
+) {
+    let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = scheduler.wake.notified() => {}
+        }
+        for job in scheduler.claim_due() {
+            let scheduler = Arc::clone(&scheduler);
+            let core = Arc::clone(&core);
+            activity.spawn_as(Priority::Background, async move {
+                let (result, seen) = run_job(&core, &job).await;
+                match &result {
+                    Ok(summary) => debug!("job {} ({}): {summary}", job.id, job.kind.label()),
+                    Err(err) => debug!("job {} ({}) failed: {err}", job.id, job.kind.label()),
+                }
+                scheduler.finish(job.id, result, seen);
+            });
+        }
+    }
+}
+
+async fn run_job(
+    core: &BrowserCore,
+    job: &ScheduledJob,
+) -> (Result<String, String>, Option<Vec<String>>) {
+    match &job.kind {
+        JobKind::WatchPages => (Ok(check_watches(core).await), None),
+        JobKind::RefreshFeed { url } => match core.fetch_feed(url).await {
+            Ok(entries) => {
+                let fresh: Vec<&str> = entries
+                    .iter()
+                    .filter(|entry| !job.seen_entries.contains(&entry.id))
+                    .map(|entry| entry.title.as_str())
+                    .collect();
+                let summary = if job.seen_entries.is_empty() {
+                    format!("{} entries", entries.len())
+                } else if fresh.is_empty() {
+                    "No new entries".to_owned()
+                } else {
+                    info!("feed {url} has {} new entries", fresh.len());
+                    let mut summary = format!(
+                        "{} new: {}",
+                        fresh.len(),
+                        fresh[..fresh.len().min(LISTED_FEED_ENTRIES)].join(", ")
+                    );
+                    if fresh.len() > LISTED_FEED_ENTRIES {
+                        summary.push_str(", \u{2026}");
+                    }
+                    summary
+                };
+                let seen = entries.into_iter().map(|entry| entry.id).collect();
+                (Ok(summary), Some(seen))
+            }
+            Err(err) => (Err(err.to_string()), None),
+        },
+        JobKind::WarmCache { url } => {
+            // Keep the copy a little past the next run so there is no gap.
+            let ttl = chrono::Duration::minutes(i64::from(job.interval_minutes) + 1);
+            match core.warm_cache(url.clone(), ttl).await {
+                Ok(()) => (Ok("Cached".to_owned()), None),
+                Err(err) => (Err(err.to_string()), None),
+            }
+        }
+    }
+}
+
+/// Refetches the watched pages that are due and describes what happened.
+async fn check_watches(core: &BrowserCore) -> String {
+    let due = core.claim_due_watches();
+    if due.is_empty() {
+        return "No pages due".to_owned();
+    }
+    let mut changed = 0;
+    let mut failed = 0;
+    for url in &due {
+        match core.check_watch(url).await {
+            Ok(true) => {
+                info!("watched page {url} changed");
+                changed += 1;
+            }
+            Ok(false) => {}
+            Err(err) => {
+                debug!("watch check of {url} failed: {err}");
+                failed += 1;
+            }
+        }
+    }
+    let mut summary = format!("{} checked, {changed} changed", due.len());
+    if failed > 0 {
+        summary.push_str(&format!(", {failed} failed"));
+    }
+    summary
+}
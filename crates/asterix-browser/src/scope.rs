@@ -0,0 +1,46 @@
+use std::future::Future;
+use std::sync::{Arc, Weak};
+
+use tokio::task::JoinSet;
+
+use asterix_core::FetchProgress;
+
+/// Every task working for one page load in a tab: its document and the
+/// images and other subresources asked for while it is shown. Tasks are
+/// only ever spawned into a scope, and dropping the scope aborts all that
+/// are still running, so none can outlive the page.
+#[derive(Default)]
+pub(crate) struct PageScope {
+    tasks: JoinSet<()>,
+    /// Progress of the document loads among the tasks.
+    navigations: Vec<Weak<FetchProgress>>,
+}
+
+impl PageScope {
+    /// Scope for a navigation reporting to `progress`.
+    pub(crate) fn for_navigation(progress: &Arc<FetchProgress>) -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            navigations: vec![Arc::downgrade(progress)],
+        }
+    }
+
+    pub(crate) fn spawn(&mut self, task: impl Future<Output = ()> + Send + 'static) {
+        self.reap();
+        self.tasks.spawn(task);
+    }
+
+    /// Forgets tasks that have finished, returning whether any are left.
+    pub(crate) fn reap(&mut self) -> bool {
+        while self.tasks.try_join_next().is_some() {}
+        !self.tasks.is_empty()
+    }
+
+    /// Marks the document loads cancelled, so their jobs report it, and
+    /// aborts every task.
+    pub(crate) fn cancel(self) {
+        for progress in self.navigations.iter().filter_map(Weak::upgrade) {
+            progress.cancel();
+        }
+    }
+}
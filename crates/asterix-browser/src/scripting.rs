@@ -0,0 +1,410 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use tokio::sync::watch;
+use url::Url;
+
+use asterix_core::{
+    click_target, control_name, extract_links, find_form, select_attribute, select_text,
+    ClickTarget, PageForm, PageRequest, PageResponse, RequestBody, TabId,
+};
+
+use crate::{BrowserHandle, NavigationJob};
+
+/// Functions a script can call, shown by the CLI and the Scripts window.
+pub const SCRIPT_API: &str = "\
+open(url)                  start loading url, relative to the current page
+post(url, fields)          start submitting the map fields to url as a form
+wait_for_load()            wait for the page to load; returns the HTTP status
+url(), title(), status()   details of the loaded page
+html()                     source of the loaded page
+select(css)                text of every element matching css
+select_attr(css, name)     attribute name of every element matching css
+links()                    absolute URLs of the page's links
+fill(css, value)           set the form field matching css for the next submit
+submit(css)                submit the form matching or containing css
+click(css)                 follow the link or press the submit button at css
+save(path, text)           write text to path, replacing it
+append(path, text)         add text to the end of path
+sleep(ms)                  pause the script
+print(value)               show value in the script output";
+
+/// Longest a script waits for a single page.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest a single `sleep` lasts when the requested one would not fit in
+/// an [`Instant`].
+const LONGEST_SLEEP: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Browser state shared by the functions registered with one script run.
+struct ScriptHost {
+    handle: BrowserHandle,
+    tab: TabId,
+    /// Directory relative `save` and `append` paths are resolved against.
+    base_dir: PathBuf,
+    /// Turns `true` once the script is asked to stop.
+    cancel: watch::Receiver<bool>,
+    loading: Option<NavigationJob>,
+    page: Option<PageResponse>,
+    /// Field values set with `fill`, as selector, field name and value.
+    fills: Vec<(String, String, String)>,
+}
+
+impl ScriptHost {
+    fn open(&mut self, url: &str) -> ScriptResult<()> {
+        let url = self.resolve(url)?;
+        self.navigate(PageRequest::new(self.tab, url))
+    }
+
+    /// `url` resolved against the loaded page.
+    fn resolve(&self, url: &str) -> ScriptResult<Url> {
+        match &self.page {
+            Some(page) => page.url.join(url),
+            None => Url::parse(url),
+        }
+        .map_err(|err| format!("invalid URL `{url}`: {err}").into())
+    }
+
+    fn post(&mut self, url: &str, fields: Map) -> ScriptResult<()> {
+        let url = self.resolve(url)?;
+        let fields = fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self.navigate(PageRequest::post(
+            self.tab,
+            url,
+            RequestBody::UrlEncoded(fields),
+        ))
+    }
+
+    fn navigate(&mut self, request: PageRequest) -> ScriptResult<()> {
+        let job = self
+            .handle
+            .request_page(request)
+            .map_err(|err| err.to_string())?;
+        self.loading = Some(job);
+        Ok(())
+    }
+
+    fn fill(&mut self, selector: &str, value: &str) -> ScriptResult<()> {
+        let name = control_name(&self.page()?.body, selector)?;
+        self.fills
+            .push((selector.to_owned(), name, value.to_owned()));
+        Ok(())
+    }
+
+    fn submit(&mut self, selector: &str) -> ScriptResult<()> {
+        let page = self.page()?;
+        let form = find_form(&page.body, &page.url, selector)?;
+        self.submit_form(form)
+    }
+
+    fn click(&mut self, selector: &str) -> ScriptResult<()> {
+        let page = self.page()?;
+        match click_target(&page.body, &page.url, selector)? {
+            ClickTarget::Link(url) => self.navigate(PageRequest::new(self.tab, url)),
+            ClickTarget::Submit(form) => self.submit_form(form),
+        }
+    }
+
+    /// Submits `form` with the values filled into its fields since the page
+    /// loaded.
+    fn submit_form(&mut self, mut form: PageForm) -> ScriptResult<()> {
+        let page = self.page()?;
+        for (selector, name, value) in &self.fills {
+            let same_form = find_form(&page.body, &page.url, selector)
+                .is_ok_and(|owner| owner.selector == form.selector);
+            if same_form {
+                form.set_value(name, value);
+            }
+        }
+        self.fills.clear();
+        self.navigate(form.request(self.tab))
+    }
+
+    fn wait_for_load(&mut self) -> ScriptResult<i64> {
+        let Some(mut job) = self.loading.take() else {
+            return match &self.page {
+                Some(page) => Ok(page.status.into()),
+                None => Err("nothing is loading; call open(url) first".into()),
+            };
+        };
+        let url = job.url().clone();
+        let mut cancel = self.cancel.clone();
+        let outcome = self.handle.inner.runtime.block_on(async {
+            tokio::select! {
+                result = tokio::time::timeout(LOAD_TIMEOUT, job.complete()) => Some(result),
+                _ = cancel.wait_for(|stopped| *stopped) => None,
+            }
+        });
+        match outcome {
+            Some(Ok(Ok(page))) => {
+                let status = page.status.into();
+                self.page = Some(page);
+                self.fills.clear();
+                Ok(status)
+            }
+            Some(Ok(Err(err))) => Err(format!("could not load {url}: {err}").into()),
+            Some(Err(_)) => {
+                job.cancel();
+                Err(format!("timed out loading {url}").into())
+            }
+            None => {
+                job.cancel();
+                Err("script stopped".into())
+            }
+        }
+    }
+
+    fn page(&self) -> ScriptResult<&PageResponse> {
+        self.page
+            .as_ref()
+            .ok_or_else(|| "no page loaded; call open(url) and wait_for_load() first".into())
+    }
+
+    fn write(&self, path: &str, text: &str, append: bool) -> ScriptResult<()> {
+        let path = self.base_dir.join(path);
+        write_file(&path, text, append)
+            .map_err(|err| format!("could not write {}: {err}", path.display()).into())
+    }
+
+    fn sleep(&self, millis: i64) -> ScriptResult<()> {
+        let now = Instant::now();
+        let until = now
+            .checked_add(Duration::from_millis(millis.max(0) as u64))
+            .unwrap_or(now + LONGEST_SLEEP);
+        let mut cancel = self.cancel.clone();
+        let stopped = self.handle.inner.runtime.block_on(async {
+            tokio::select! {
+                _ = tokio::time::sleep_until(until.into()) => false,
+                _ = cancel.wait_for(|stopped| *stopped) => true,
+            }
+        });
+        if stopped {
+            return Err("script stopped".into());
+        }
+        Ok(())
+    }
+}
+
+fn write_file(path: &Path, text: &str, append: bool) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    file.write_all(text.as_bytes())
+}
+
+fn strings(values: Vec<String>) -> Array {
+    values.into_iter().map(Dynamic::from).collect()
+}
+
+/// Builds an engine whose browser functions act on `host` and whose output
+/// goes to `output`.
+fn build_engine(host: Rc<RefCell<ScriptHost>>, output: mpsc::Sender<String>) -> Engine {
+    let mut engine = Engine::new();
+
+    let print = output.clone();
+    engine.on_print(move |text| {
+        let _ = print.send(text.to_owned());
+    });
+    engine.on_debug(move |text, _, position| {
+        let _ = output.send(format!("{position:?} {text}"));
+    });
+    let cancel = host.borrow().cancel.clone();
+    engine.on_progress(move |_| {
+        let stopped = *cancel.borrow();
+        stopped.then(|| Dynamic::from("script stopped"))
+    });
+
+    let state = Rc::clone(&host);
+    engine.register_fn("open", move |url: &str| state.borrow_mut().open(url));
+    let state = Rc::clone(&host);
+    engine.register_fn("post", move |url: &str, fields: Map| {
+        state.borrow_mut().post(url, fields)
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("wait_for_load", move || state.borrow_mut().wait_for_load());
+    let state = Rc::clone(&host);
+    engine.register_fn("url", move || -> ScriptResult<String> {
+        Ok(state.borrow().page()?.url.to_string())
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("title", move || -> ScriptResult<String> {
+        Ok(state.borrow().page()?.title.clone().unwrap_or_default())
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("status", move || -> ScriptResult<i64> {
+        Ok(state.borrow().page()?.status.into())
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("html", move || -> ScriptResult<String> {
+        Ok(state.borrow().page()?.body.to_string())
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("select", move |selector: &str| -> ScriptResult<Array> {
+        let host = state.borrow();
+        Ok(strings(select_text(&host.page()?.body, selector)?))
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn(
+        "select_attr",
+        move |selector: &str, attribute: &str| -> ScriptResult<Array> {
+            let host = state.borrow();
+            Ok(strings(select_attribute(
+                &host.page()?.body,
+                selector,
+                attribute,
+            )?))
+        },
+    );
+    let state = Rc::clone(&host);
+    engine.register_fn("links", move || -> ScriptResult<Array> {
+        let host = state.borrow();
+        let links = extract_links(host.page()?);
+        Ok(strings(
+            links.into_iter().map(|link| link.url.to_string()).collect(),
+        ))
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("fill", move |selector: &str, value: &str| {
+        state.borrow_mut().fill(selector, value)
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("submit", move |selector: &str| {
+        state.borrow_mut().submit(selector)
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("click", move |selector: &str| {
+        state.borrow_mut().click(selector)
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("save", move |path: &str, text: &str| {
+        state.borrow().write(path, text, false)
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("append", move |path: &str, text: &str| {
+        state.borrow().write(path, text, true)
+    });
+    let state = Rc::clone(&host);
+    engine.register_fn("sleep", move |millis: i64| state.borrow().sleep(millis));
+    engine
+}
+
+impl BrowserHandle {
+    /// Runs the Rhai `source` on a thread of its own, driving a new tab named
+    /// after the script. Dropping the returned job stops the script.
+    pub fn run_script(
+        &self,
+        name: &str,
+        source: String,
+        base_dir: PathBuf,
+    ) -> anyhow::Result<ScriptJob> {
+        let (output, receiver) = mpsc::channel();
+        let (cancel, cancelled) = watch::channel(false);
+        let tab = self.create_tab(name).id;
+        let host = ScriptHost {
+            handle: self.clone(),
+            tab,
+            base_dir,
+            cancel: cancelled,
+            loading: None,
+            page: None,
+            fills: Vec::new(),
+        };
+        let thread = std::thread::Builder::new()
+            .name(format!("script {name}"))
+            .spawn(move || {
+                let engine = build_engine(Rc::new(RefCell::new(host)), output);
+                engine.run(&source).map_err(|err| err.to_string())
+            })?;
+
+        Ok(ScriptJob {
+            tab,
+            output: receiver,
+            held: None,
+            thread: Some(thread),
+            cancel,
+        })
+    }
+}
+
+/// User script running on its own thread.
+pub struct ScriptJob {
+    tab: TabId,
+    output: mpsc::Receiver<String>,
+    /// Line taken while checking whether all output had been read.
+    held: Option<String>,
+    thread: Option<JoinHandle<Result<(), String>>>,
+    cancel: watch::Sender<bool>,
+}
+
+impl ScriptJob {
+    /// Tab the script loads its pages in.
+    pub fn tab(&self) -> TabId {
+        self.tab
+    }
+
+    /// Asks the script to stop at its next statement or wait.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Next line the script printed since the last call, if any.
+    pub fn try_next(&mut self) -> Option<String> {
+        self.held.take().or_else(|| self.output.try_recv().ok())
+    }
+
+    /// Returns how the script ended once it has finished and all of its
+    /// output has been taken with [`ScriptJob::try_next`].
+    pub fn try_complete(&mut self) -> Option<Result<(), String>> {
+        let finished = self.thread.as_ref().is_some_and(JoinHandle::is_finished);
+        if !finished || self.held.is_some() {
+            return None;
+        }
+        if let Ok(line) = self.output.try_recv() {
+            self.held = Some(line);
+            return None;
+        }
+        self.thread.take().map(join)
+    }
+
+    /// Waits for the next printed line, returning `None` once the script has
+    /// ended.
+    pub fn blocking_next(&mut self) -> Option<String> {
+        self.held.take().or_else(|| self.output.recv().ok())
+    }
+
+    /// Waits for the script to end and returns how it ended.
+    pub fn blocking_finish(mut self) -> Result<(), String> {
+        self.thread
+            .take()
+            .map_or_else(|| Err("script already finished".to_owned()), join)
+    }
+}
+
+impl Drop for ScriptJob {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn join(thread: JoinHandle<Result<(), String>>) -> Result<(), String> {
+    thread
+        .join()
+        .unwrap_or_else(|_| Err("script panicked".to_owned()))
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use asterix_sync::{
+    Collection, SyncBatch, SyncClient, SyncConfig, SyncError, SyncOutcome, SyncSecrets, SyncState,
+};
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::error::TryRecvError;
+
+use crate::activity::TabActivity;
+use crate::persist;
+use crate::priority::Priority;
+
+/// Outcome of every synced collection, or why syncing could not start.
+pub type SyncResult = Result<Vec<(Collection, Result<SyncOutcome, SyncError>)>, SyncError>;
+
+/// Sync running on the browser runtime.
+pub struct SyncJob {
+    receiver: oneshot::Receiver<SyncResult>,
+}
+
+impl SyncJob {
+    pub(crate) fn new(receiver: oneshot::Receiver<SyncResult>) -> Self {
+        Self { receiver }
+    }
+
+    pub fn try_complete(&mut self) -> Option<SyncResult> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Ok(Vec::new())),
+        }
+    }
+}
+
+pub(crate) async fn sync_collections(
+    activity: &TabActivity,
+    config: SyncConfig,
+    secrets: SyncSecrets,
+    batches: Vec<SyncBatch>,
+) -> SyncResult {
+    // Deriving the key is deliberately slow; keep it off the async workers.
+    let client = tokio::task::spawn_blocking(move || SyncClient::new(&config, &secrets))
+        .await
+        .expect("sync key derivation panicked")?;
+    let mut outcomes = Vec::with_capacity(batches.len());
+    for batch in batches {
+        // Each collection takes its own turn, so a long sync never holds a
+        // background slot throughout.
+        let _slot = activity.admit_as(Priority::Background).await;
+        let outcome = client
+            .sync(batch.collection, &batch.base, batch.local)
+            .await;
+        outcomes.push((batch.collection, outcome));
+    }
+    Ok(outcomes)
+}
+
+/// Reads the sync settings saved by [`save_sync_config`], or the defaults.
+pub fn load_sync_config(path: &Path) -> anyhow::Result<SyncConfig> {
+    Ok(persist::read_json(path)?.unwrap_or_default())
+}
+
+pub fn save_sync_config(path: &Path, config: &SyncConfig) -> anyhow::Result<()> {
+    persist::write_json(path, config)
+}
+
+/// Reads what was agreed with the server at the last sync.
+pub fn load_sync_state(path: &Path) -> anyhow::Result<SyncState> {
+    Ok(persist::read_json(path)?.unwrap_or_default())
+}
+
+pub fn save_sync_state(path: &Path, state: &SyncState) -> anyhow::Result<()> {
+    persist::write_json(path, state)
+}
@@ -0,0 +1,83 @@
+//! Memory taken by the element map of large documents.
+//!
+//! Run with `cargo bench -p asterix-core --bench dom_memory`. Counts the
+//! bytes and allocations the map of each document keeps alive, through an
+//! allocator that tallies what passes through it.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use asterix_core::map_elements;
+
+struct Counting;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Listing page of `rows` product cards, the shape of most large pages.
+fn listing(rows: usize) -> String {
+    let mut html = String::from("<!DOCTYPE html><html lang=\"en\"><head><title>Shop</title>");
+    html.push_str("<link rel=\"stylesheet\" href=\"/site.css\"></head><body><main id=\"list\">");
+    for row in 0..rows {
+        html.push_str(&format!(
+            "<div class=\"card product\" data-id=\"{row}\"><a href=\"/item/{row}\" \
+             class=\"card-link\"><img src=\"/img/{row}.jpg\" alt=\"Item {row}\" width=\"120\" \
+             height=\"90\"></a><h3 class=\"title\">Item {row}</h3><p class=\"price\">\
+             <span>{row}.99</span></p><button type=\"button\" aria-label=\"Add item {row}\">\
+             Add</button></div>"
+        ));
+    }
+    html.push_str("</main></body></html>");
+    html
+}
+
+fn main() {
+    // Warm up anything kept for the life of the process.
+    drop(map_elements(&listing(10)));
+
+    println!(
+        "{:>8} {:>10} {:>14} {:>12} {:>10}",
+        "rows", "elements", "retained", "allocations", "time"
+    );
+    for rows in [1_000, 10_000, 50_000] {
+        let html = listing(rows);
+        let live = LIVE_BYTES.load(Ordering::Relaxed);
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let started = Instant::now();
+        let elements = map_elements(&html);
+        let elapsed = started.elapsed();
+        let retained = LIVE_BYTES.load(Ordering::Relaxed) - live;
+        println!(
+            "{rows:>8} {:>10} {:>12} B {:>12} {:>8.1?}",
+            elements.len(),
+            retained,
+            ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+            elapsed
+        );
+        drop(elements);
+    }
+}
@@ -0,0 +1,164 @@
+//! Bytes copied while large pages go from the network to the tab.
+//!
+//! Run with `cargo bench -p asterix-core --bench page_pipeline`. Serves
+//! multi-megabyte pages from a local server and counts the bytes allocated
+//! to fetch each one, store it in the HTTP cache, serve it again from the
+//! cache and clone the result, as the shell does when keeping a page. Sizes
+//! are also given in copies of the page. Pages are served as plain text:
+//! HTML pages are parsed for their title on top, which costs the same
+//! whichever way the body travels.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use asterix_core::{BrowserCore, HttpCache, PageRequest, PageResponse};
+use asterix_storage::CookieJar;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use url::Url;
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// UTF-8 document of about `megabytes` MB.
+fn page(megabytes: usize) -> Arc<Vec<u8>> {
+    let mut html = String::from("<!DOCTYPE html><html><head><title>Catalogue</title></head><body>");
+    let mut row = 0;
+    while html.len() < megabytes << 20 {
+        html.push_str(&format!(
+            "<div class=\"card\"><a href=\"/item/{row}\">Item {row} – café crème</a>\
+             <p class=\"price\">{row}.99 €</p></div>"
+        ));
+        row += 1;
+    }
+    html.push_str("</body></html>");
+    Arc::new(html.into_bytes())
+}
+
+/// Answers every request with `body`, written in 64 KiB pieces so the
+/// client receives it in many chunks.
+async fn serve(listener: TcpListener, body: Arc<Vec<u8>>) {
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            return;
+        };
+        let body = Arc::clone(&body);
+        tokio::spawn(async move {
+            let mut request = [0; 4096];
+            if stream.read(&mut request).await.is_err() {
+                return;
+            }
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                 Content-Length: {}\r\nCache-Control: max-age=3600\r\n\
+                 Connection: close\r\n\r\n",
+                body.len()
+            );
+            if stream.write_all(head.as_bytes()).await.is_err() {
+                return;
+            }
+            for piece in body.chunks(64 << 10) {
+                if stream.write_all(piece).await.is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+/// Runs `step`, returning its result with the time taken and the bytes it
+/// allocated.
+async fn measure<T>(step: impl std::future::Future<Output = T>) -> (T, f64, usize) {
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let started = Instant::now();
+    let value = step.await;
+    let elapsed = started.elapsed().as_secs_f64() * 1000.0;
+    (
+        value,
+        elapsed,
+        ALLOCATED.load(Ordering::Relaxed) - allocated,
+    )
+}
+
+fn main() {
+    let runtime = tokio::runtime::Runtime::new().expect("failed to start tokio");
+    runtime.block_on(async {
+        println!(
+            "{:>6} {:>10} {:>22} {:>22} {:>16}",
+            "MB", "step", "network and store", "cache hit", "clone"
+        );
+        for megabytes in [4, 16, 64] {
+            let body = page(megabytes);
+            let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+            let address = listener.local_addr().expect("address");
+            let server = tokio::spawn(serve(listener, Arc::clone(&body)));
+
+            let dir = std::env::temp_dir().join(format!(
+                "asterix-page-pipeline-{}-{megabytes}",
+                std::process::id()
+            ));
+            let cache = HttpCache::open(&dir).expect("failed to open the cache");
+            let core = BrowserCore::new(
+                None,
+                Arc::new(CookieJar::default()),
+                Some(cache),
+                Default::default(),
+            )
+            .expect("failed to create the core");
+            let tab = core.create_tab("bench").id;
+            let url = Url::parse(&format!("http://{address}/catalogue")).expect("url");
+
+            let fetch = || core.fetch_page(PageRequest::new(tab, url.clone()));
+            let (missed, miss_ms, miss_bytes) = measure(fetch()).await;
+            let missed = missed.expect("fetch failed");
+            let (hit, hit_ms, hit_bytes) = measure(fetch()).await;
+            let hit = hit.expect("cached fetch failed");
+            assert_eq!(hit.body.len(), missed.body.len());
+            let (kept, clone_ms, clone_bytes) = measure(async { PageResponse::clone(&hit) }).await;
+            drop((missed, hit, kept));
+
+            let copies = |bytes: usize| bytes as f64 / body.len() as f64;
+            println!(
+                "{megabytes:>6} {:>10} {:>15.1} ms {:>15.1} ms {:>13.2} ms",
+                "time", miss_ms, hit_ms, clone_ms
+            );
+            println!(
+                "{:>6} {:>10} {:>13} B {:>4.1}x {:>13} B {:>4.1}x {:>9} B {:>4.1}x",
+                "",
+                "allocated",
+                miss_bytes,
+                copies(miss_bytes),
+                hit_bytes,
+                copies(hit_bytes),
+                clone_bytes,
+                copies(clone_bytes)
+            );
+
+            server.abort();
+            drop(core);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    });
+}
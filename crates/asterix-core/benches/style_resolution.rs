@@ -0,0 +1,72 @@
+//! Time taken to resolve the style of every element of large pages.
+//!
+//! Run with `cargo bench -p asterix-core --bench style_resolution`.
+//! Compares cascading each element on its own, as the inspector does, with
+//! `StyleSheet::compute_all`, which the renderer uses, and checks both
+//! agree.
+
+use std::collections::HashSet;
+use std::time::Instant;
+
+use asterix_core::{map_elements, StyleSheet};
+
+const STYLES: &str = "<style>
+body { color: #222; font-family: sans-serif; }
+main > div.card { margin: 8px; padding: 4px 8px; border: 1px solid #ccc; }
+.card a.card-link img { width: 120px; }
+div.product h3.title { font-size: 18px; font-weight: bold; }
+.price span { color: #080; }
+nav ul li a { text-decoration: none; }
+footer .legal p { font-size: 12px; }
+aside.promo .banner strong { color: red; }
+table.specs td { padding: 2px; }
+button[type=button] { cursor: pointer; }
+#list .card button { background: #eee; }
+section.reviews article p { line-height: 1.4; }
+</style>";
+
+/// Listing page of `rows` product cards.
+fn listing(rows: usize) -> String {
+    let mut html = format!("<!DOCTYPE html><html><head>{STYLES}</head><body><main id=\"list\">");
+    for row in 0..rows {
+        html.push_str(&format!(
+            "<div class=\"card product\"><a href=\"/item/{row}\" class=\"card-link\">\
+             <img src=\"/img/{row}.jpg\" alt=\"\"></a><h3 class=\"title\">Item {row}</h3>\
+             <p class=\"price\"><span>{row}.99</span></p>\
+             <button type=\"button\">Add</button></div>"
+        ));
+    }
+    html.push_str("</main></body></html>");
+    html
+}
+
+fn main() {
+    let disabled = HashSet::new();
+    println!(
+        "{:>8} {:>10} {:>14} {:>14}",
+        "rows", "elements", "one by one", "compute_all"
+    );
+    for rows in [500, 2_000, 5_000] {
+        let html = listing(rows);
+        let elements = map_elements(&html);
+        let sheet = StyleSheet::from_page(&html, &elements);
+
+        let started = Instant::now();
+        let each: Vec<_> = (0..elements.len())
+            .map(|index| sheet.compute(&elements, index, &disabled))
+            .collect();
+        let one_by_one = started.elapsed();
+
+        let started = Instant::now();
+        let all = sheet.compute_all(&elements, &disabled);
+        let at_once = started.elapsed();
+
+        assert!(each == all, "compute_all disagrees with compute");
+        println!(
+            "{rows:>8} {:>10} {:>12.1?} {:>12.1?}",
+            elements.len(),
+            one_by_one,
+            at_once
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BlockRule, BrowserCore};
+
+/// Sites restricted mode lets the browser reach. Every other request fails
+/// with [`BrowserError::Restricted`](crate::BrowserError::Restricted).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allowlist {
+    /// Hosts allowed along with all of their subdomains.
+    pub domains: Vec<String>,
+}
+
+impl Allowlist {
+    pub fn allows(&self, url: &Url) -> bool {
+        self.domains
+            .iter()
+            .any(|domain| BlockRule::Domain(domain.clone()).matches(url))
+    }
+}
+
+impl BrowserCore {
+    /// Restricts all requests to the domains on `allowlist`, or lifts the
+    /// restriction with `None`.
+    pub fn set_allowlist(&self, allowlist: Option<Allowlist>) {
+        let mut guard = self.state.write();
+        if allowlist.is_some() {
+            // Pages preloaded before the restriction must not slip through.
+            guard.preloaded.clear();
+        }
+        guard.allowlist = allowlist;
+    }
+
+    pub fn allowlist(&self) -> Option<Allowlist> {
+        self.state.read().allowlist.clone()
+    }
+
+    /// Whether restricted mode, if on, lets the browser load `url`.
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        self.state
+            .read()
+            .allowlist
+            .as_ref()
+            .is_none_or(|allowlist| allowlist.allows(url))
+    }
+}
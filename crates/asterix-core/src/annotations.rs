@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::BrowserCore;
+
+/// Passage of page text the user marked for later reference.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Free-form note and highlights attached to a single URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageAnnotations {
+    pub url: Url,
+    pub title: String,
+    pub note: String,
+    pub highlights: Vec<Highlight>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PageAnnotations {
+    fn new(url: Url, title: String) -> Self {
+        Self {
+            url,
+            title,
+            note: String::new(),
+            highlights: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.note.trim().is_empty() && self.highlights.is_empty()
+    }
+}
+
+/// All annotations known to the profile, keyed by URL without fragment.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnnotationStore {
+    pages: BTreeMap<String, PageAnnotations>,
+}
+
+impl AnnotationStore {
+    pub fn get(&self, url: &Url) -> Option<&PageAnnotations> {
+        self.pages.get(&annotation_key(url))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PageAnnotations> {
+        self.pages.values()
+    }
+
+    fn entry(&mut self, url: &Url, title: &str) -> &mut PageAnnotations {
+        let entry = self
+            .pages
+            .entry(annotation_key(url))
+            .or_insert_with(|| PageAnnotations::new(url.clone(), title.to_owned()));
+        if !title.is_empty() {
+            entry.title = title.to_owned();
+        }
+        entry.updated_at = Utc::now();
+        entry
+    }
+
+    fn prune(&mut self, url: &Url) {
+        let key = annotation_key(url);
+        if self.pages.get(&key).is_some_and(PageAnnotations::is_empty) {
+            self.pages.remove(&key);
+        }
+    }
+
+    /// Renders every annotated page as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Annotations\n");
+        let mut pages: Vec<_> = self.pages.values().collect();
+        pages.sort_by_key(|page| std::cmp::Reverse(page.updated_at));
+
+        for page in pages {
+            let title = if page.title.is_empty() {
+                page.url.as_str()
+            } else {
+                page.title.as_str()
+            };
+            let _ = writeln!(out, "\n## [{}]({})\n", title, page.url);
+            let _ = writeln!(
+                out,
+                "_Updated {}_",
+                page.updated_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            if !page.note.trim().is_empty() {
+                let _ = writeln!(out, "\n{}", page.note.trim());
+            }
+            if !page.highlights.is_empty() {
+                out.push('\n');
+                for highlight in &page.highlights {
+                    let quoted = highlight.text.trim().replace('\n', "\n> ");
+                    let _ = writeln!(out, "> {quoted}\n");
+                }
+            }
+        }
+        out
+    }
+}
+
+impl BrowserCore {
+    pub fn annotations_for(&self, url: &Url) -> Option<PageAnnotations> {
+        self.state.read().annotations.get(url).cloned()
+    }
+
+    /// Replaces the note attached to `url`, removing the entry once it is empty.
+    pub fn set_page_note(&self, url: &Url, title: &str, note: impl Into<String>) {
+        let mut guard = self.state.write();
+        guard.annotations.entry(url, title).note = note.into();
+        guard.annotations.prune(url);
+    }
+
+    pub fn add_highlight(&self, url: &Url, title: &str, text: impl Into<String>) {
+        let text = text.into();
+        if text.trim().is_empty() {
+            return;
+        }
+        let mut guard = self.state.write();
+        let entry = guard.annotations.entry(url, title);
+        if !entry
+            .highlights
+            .iter()
+            .any(|existing| existing.text == text)
+        {
+            entry.highlights.push(Highlight {
+                text,
+                created_at: Utc::now(),
+            });
+        }
+    }
+
+    pub fn remove_highlight(&self, url: &Url, index: usize) {
+        let mut guard = self.state.write();
+        let key = annotation_key(url);
+        if let Some(entry) = guard.annotations.pages.get_mut(&key) {
+            if index < entry.highlights.len() {
+                entry.highlights.remove(index);
+                entry.updated_at = Utc::now();
+            }
+        }
+        guard.annotations.prune(url);
+    }
+
+    pub fn export_annotations(&self) -> AnnotationStore {
+        self.state.read().annotations.clone()
+    }
+
+    pub fn import_annotations(&self, store: AnnotationStore) {
+        self.state.write().annotations = store;
+    }
+}
+
+fn annotation_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.into()
+}
@@ -0,0 +1,69 @@
+use serde::Deserialize;
+use url::Url;
+
+use crate::{BrowserCore, BrowserError, RequestKind};
+
+/// Internet Archive endpoint returning the closest snapshot of a URL.
+const WAYBACK_AVAILABILITY_API: &str = "https://archive.org/wayback/available";
+
+#[derive(Deserialize)]
+struct Availability {
+    #[serde(default)]
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Default, Deserialize)]
+struct ArchivedSnapshots {
+    closest: Option<Snapshot>,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    available: bool,
+    url: String,
+}
+
+/// Whether a response status means the page is gone and worth looking up in
+/// the Wayback Machine.
+pub fn is_dead_status(status: u16) -> bool {
+    matches!(status, 404 | 410)
+}
+
+impl BrowserError {
+    /// True when the host could not be reached at all, e.g. a DNS failure.
+    pub fn is_unreachable(&self) -> bool {
+        matches!(self, BrowserError::Network(err) if err.is_connect())
+    }
+}
+
+impl BrowserCore {
+    /// Asks the Wayback Machine for its most recent snapshot of `url`.
+    pub async fn find_archived_copy(&self, url: &Url) -> Result<Option<Url>, BrowserError> {
+        let builder = self
+            .private_client()
+            .get(WAYBACK_AVAILABILITY_API)
+            .query(&[("url", url.as_str())]);
+        let response = self
+            .send_logged(builder, RequestKind::Archive, None)
+            .await?;
+        if !response.status.is_success() {
+            return Err(BrowserError::Status(response.status.as_u16()));
+        }
+        let availability: Availability =
+            serde_json::from_slice(&response.body).map_err(|_| BrowserError::InvalidBody)?;
+
+        let snapshot = availability
+            .archived_snapshots
+            .closest
+            .filter(|snapshot| snapshot.available)
+            .and_then(|snapshot| Url::parse(&snapshot.url).ok())
+            .map(|mut snapshot| {
+                // The API hands out http:// links even though the archive serves https.
+                if snapshot.scheme() == "http" {
+                    let _ = snapshot.set_scheme("https");
+                }
+                snapshot
+            });
+        Ok(snapshot)
+    }
+}
@@ -0,0 +1,129 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::PageResponse;
+
+/// Average adult silent reading speed used for reading time estimates.
+const WORDS_PER_MINUTE: usize = 230;
+
+/// Minimum amount of paragraph text for a page to count as an article.
+const ARTICLE_MIN_WORDS: usize = 250;
+
+/// Block of readable content extracted from a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArticleBlock {
+    Heading { level: u8, text: String },
+    Paragraph(String),
+}
+
+/// Main readable content of a page, as shown by reader mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Article {
+    pub title: Option<String>,
+    pub blocks: Vec<ArticleBlock>,
+    pub word_count: usize,
+}
+
+impl Article {
+    /// Estimated reading time in whole minutes, never less than one.
+    pub fn reading_minutes(&self) -> usize {
+        self.word_count.div_ceil(WORDS_PER_MINUTE).max(1)
+    }
+
+    /// Whether the page has enough prose to be treated as an article.
+    pub fn is_article_like(&self) -> bool {
+        self.word_count >= ARTICLE_MIN_WORDS
+    }
+}
+
+/// Extracts the main readable content of an HTML document.
+///
+/// Prefers `<article>` and `<main>` elements and otherwise picks the
+/// container holding the most paragraph text.
+pub fn extract_article(page: &PageResponse) -> Option<Article> {
+    if let Some(mime) = &page.mime_type {
+        if !mime.starts_with("text/html") {
+            return None;
+        }
+    }
+
+    let document = Html::parse_document(&page.body);
+    let root = pick_content_root(&document)?;
+    let blocks_selector = Selector::parse("h1, h2, h3, h4, h5, h6, p, li, blockquote, pre").ok()?;
+
+    let mut blocks = Vec::new();
+    let mut word_count = 0;
+    for element in root.select(&blocks_selector) {
+        // Nested matches (e.g. a paragraph inside a list item) are emitted by
+        // their outermost block only.
+        if element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .take_while(|ancestor| ancestor.id() != root.id())
+            .any(|ancestor| blocks_selector.matches(&ancestor))
+        {
+            continue;
+        }
+
+        let text = collapse_text(element);
+        if text.is_empty() {
+            continue;
+        }
+        let name = element.value().name();
+        match name
+            .strip_prefix('h')
+            .and_then(|level| level.parse::<u8>().ok())
+        {
+            Some(level) => blocks.push(ArticleBlock::Heading { level, text }),
+            None => {
+                word_count += text.split_whitespace().count();
+                blocks.push(ArticleBlock::Paragraph(text));
+            }
+        }
+    }
+
+    if blocks.is_empty() {
+        return None;
+    }
+
+    let title_selector = Selector::parse("title").ok()?;
+    let title = document
+        .select(&title_selector)
+        .next()
+        .map(collapse_text)
+        .filter(|title| !title.is_empty());
+
+    Some(Article {
+        title,
+        blocks,
+        word_count,
+    })
+}
+
+fn pick_content_root(document: &Html) -> Option<ElementRef<'_>> {
+    for candidate in ["article", "main", "[role=main]"] {
+        let selector = Selector::parse(candidate).ok()?;
+        if let Some(element) = document.select(&selector).next() {
+            return Some(element);
+        }
+    }
+
+    let paragraph = Selector::parse("p").ok()?;
+    let containers = Selector::parse("div, section, body").ok()?;
+    document.select(&containers).max_by_key(|container| {
+        container
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|child| paragraph.matches(child))
+            .map(|child| child.text().map(str::len).sum::<usize>())
+            .sum::<usize>()
+    })
+}
+
+fn collapse_text(element: ElementRef<'_>) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,471 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ComputedStyle, SourceElement, StyleSheet};
+
+/// Minimum contrast ratio for body text, and for large or bold text.
+const NORMAL_TEXT_CONTRAST: f64 = 4.5;
+const LARGE_TEXT_CONTRAST: f64 = 3.0;
+
+/// Input types that need no label because they label themselves or are
+/// never shown.
+const UNLABELLED_INPUT_TYPES: [&str; 5] = ["hidden", "submit", "button", "reset", "image"];
+
+const NAMED_COLORS: [(&str, [u8; 3]); 20] = [
+    ("black", [0, 0, 0]),
+    ("white", [255, 255, 255]),
+    ("red", [255, 0, 0]),
+    ("green", [0, 128, 0]),
+    ("blue", [0, 0, 255]),
+    ("yellow", [255, 255, 0]),
+    ("orange", [255, 165, 0]),
+    ("purple", [128, 0, 128]),
+    ("gray", [128, 128, 128]),
+    ("grey", [128, 128, 128]),
+    ("silver", [192, 192, 192]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgrey", [211, 211, 211]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgrey", [169, 169, 169]),
+    ("maroon", [128, 0, 0]),
+    ("navy", [0, 0, 128]),
+    ("teal", [0, 128, 128]),
+    ("olive", [128, 128, 0]),
+    ("lime", [0, 255, 0]),
+];
+
+/// Kind of accessibility problem reported by [`audit_page`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AuditRule {
+    MissingAlt,
+    LowContrast,
+    MissingLabel,
+    HeadingOrder,
+}
+
+impl AuditRule {
+    pub const ALL: [AuditRule; 4] = [
+        AuditRule::MissingAlt,
+        AuditRule::LowContrast,
+        AuditRule::MissingLabel,
+        AuditRule::HeadingOrder,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AuditRule::MissingAlt => "Missing alt text",
+            AuditRule::LowContrast => "Low contrast",
+            AuditRule::MissingLabel => "Missing form label",
+            AuditRule::HeadingOrder => "Heading order",
+        }
+    }
+}
+
+/// One problem found on a page, pointing at the element it concerns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFinding {
+    pub rule: AuditRule,
+    /// Index of the element in the page's element list.
+    pub element: usize,
+    pub message: String,
+}
+
+/// Checks a page for common accessibility problems: images without alt
+/// text, text whose computed colors contrast too little, form controls
+/// without a label and skipped heading levels.
+///
+/// Colors come from the page's embedded styles only, so text styled by
+/// linked stylesheets is checked against the browser defaults.
+pub fn audit_page(
+    source: &str,
+    elements: &[SourceElement],
+    sheet: &StyleSheet,
+) -> Vec<AuditFinding> {
+    let mut findings = Vec::new();
+    missing_alt(elements, &mut findings);
+    Contrast::new(source, elements, sheet).check(&mut findings);
+    missing_labels(elements, &mut findings);
+    heading_order(elements, &mut findings);
+    findings
+}
+
+fn missing_alt(elements: &[SourceElement], findings: &mut Vec<AuditFinding>) {
+    for (index, element) in elements.iter().enumerate() {
+        let needs_alt = match element.tag.as_str() {
+            "img" => true,
+            "input" => element
+                .attribute("type")
+                .is_some_and(|kind| kind.eq_ignore_ascii_case("image")),
+            "area" => element.attribute("href").is_some(),
+            _ => false,
+        };
+        if !needs_alt || element.attribute("alt").is_some() {
+            continue;
+        }
+        let message = match element.attribute("src") {
+            Some(src) => format!("No alt attribute on {} ({src})", element.tag),
+            None => format!("No alt attribute on {}", element.tag),
+        };
+        findings.push(AuditFinding {
+            rule: AuditRule::MissingAlt,
+            element: index,
+            message,
+        });
+    }
+}
+
+fn missing_labels(elements: &[SourceElement], findings: &mut Vec<AuditFinding>) {
+    let labelled_ids: HashSet<&str> = elements
+        .iter()
+        .filter(|element| element.tag == "label")
+        .filter_map(|element| element.attribute("for"))
+        .collect();
+    for (index, element) in elements.iter().enumerate() {
+        let kind = element
+            .attribute("type")
+            .unwrap_or("text")
+            .to_ascii_lowercase();
+        let is_control = match element.tag.as_str() {
+            "input" => !UNLABELLED_INPUT_TYPES.contains(&kind.as_str()),
+            "select" | "textarea" => true,
+            _ => false,
+        };
+        if !is_control {
+            continue;
+        }
+        let named = ["aria-label", "aria-labelledby", "title"]
+            .iter()
+            .any(|name| {
+                element
+                    .attribute(name)
+                    .is_some_and(|value| !value.trim().is_empty())
+            });
+        let labelled_by_id = element.id().is_some_and(|id| labelled_ids.contains(id));
+        let mut ancestor = element.parent;
+        let mut wrapped = false;
+        while let Some(parent) = ancestor {
+            wrapped |= elements[parent].tag == "label";
+            ancestor = elements[parent].parent;
+        }
+        if named || labelled_by_id || wrapped {
+            continue;
+        }
+        let control = if element.tag == "input" {
+            format!("input type={kind}")
+        } else {
+            element.tag.to_string()
+        };
+        findings.push(AuditFinding {
+            rule: AuditRule::MissingLabel,
+            element: index,
+            message: format!("{control} has no associated label"),
+        });
+    }
+}
+
+fn heading_order(elements: &[SourceElement], findings: &mut Vec<AuditFinding>) {
+    let mut previous = 0;
+    for (index, element) in elements.iter().enumerate() {
+        let Some(level) = heading_level(&element.tag) else {
+            continue;
+        };
+        if level > previous + 1 {
+            let message = if previous == 0 {
+                format!("First heading is h{level} rather than h1")
+            } else {
+                format!("h{level} follows h{previous}, skipping h{}", previous + 1)
+            };
+            findings.push(AuditFinding {
+                rule: AuditRule::HeadingOrder,
+                element: index,
+                message,
+            });
+        }
+        previous = level;
+    }
+}
+
+fn heading_level(tag: &str) -> Option<u32> {
+    let level = tag.strip_prefix('h')?.parse().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+/// Contrast check over the elements that directly contain text, with the
+/// styles of the whole page computed up front.
+struct Contrast<'a> {
+    source: &'a str,
+    elements: &'a [SourceElement],
+    children: Vec<Vec<usize>>,
+    styles: Vec<ComputedStyle>,
+}
+
+/// Value of a color property.
+pub(crate) enum Paint {
+    Color([u8; 3]),
+    Transparent,
+}
+
+impl<'a> Contrast<'a> {
+    fn new(source: &'a str, elements: &'a [SourceElement], sheet: &'a StyleSheet) -> Self {
+        let mut children = vec![Vec::new(); elements.len()];
+        for (index, element) in elements.iter().enumerate() {
+            if let Some(parent) = element.parent {
+                children[parent].push(index);
+            }
+        }
+        Self {
+            source,
+            elements,
+            children,
+            styles: sheet.compute_all(elements, &HashSet::new()),
+        }
+    }
+
+    fn check(self, findings: &mut Vec<AuditFinding>) {
+        for index in 0..self.elements.len() {
+            if !self.has_own_text(index) {
+                continue;
+            }
+            let Some((foreground, background)) = self.colors(index) else {
+                continue;
+            };
+            let ratio = contrast_ratio(foreground, background);
+            let required = if self.is_large_text(index) {
+                LARGE_TEXT_CONTRAST
+            } else {
+                NORMAL_TEXT_CONTRAST
+            };
+            if ratio < required {
+                findings.push(AuditFinding {
+                    rule: AuditRule::LowContrast,
+                    element: index,
+                    message: format!(
+                        "Contrast {ratio:.2}:1 between {} and {} is below {required}:1",
+                        hex(foreground),
+                        hex(background)
+                    ),
+                });
+            }
+        }
+    }
+
+    fn style(&self, index: usize) -> &ComputedStyle {
+        &self.styles[index]
+    }
+
+    /// Whether text sits directly in the element rather than only in its
+    /// children.
+    fn has_own_text(&self, index: usize) -> bool {
+        let element = &self.elements[index];
+        let region = &self.source[element.start..element.end];
+        let Some(open_end) = region.find('>') else {
+            return false;
+        };
+        let mut content = &region[open_end + 1..];
+        if let Some(close) = content.rfind("</") {
+            content = &content[..close];
+        }
+        let content_start = element.start + open_end + 1;
+        let content_end = content_start + content.len();
+        let mut pos = content_start;
+        let mut text = String::new();
+        for &child in &self.children[index] {
+            let child = &self.elements[child];
+            let stop = child.start.min(content_end);
+            if stop > pos {
+                text.push_str(&self.source[pos..stop]);
+            }
+            pos = pos.max(child.end);
+        }
+        if pos < content_end {
+            text.push_str(&self.source[pos..content_end]);
+        }
+        !strip_comments(&text).trim().is_empty()
+    }
+
+    /// Text and background color of the element, or `None` when it is not
+    /// displayed or a color is not understood.
+    fn colors(&self, index: usize) -> Option<([u8; 3], [u8; 3])> {
+        let foreground = match self.style(index).property("color") {
+            Some(value) => match parse_color(value)? {
+                Paint::Color(color) => color,
+                Paint::Transparent => return None,
+            },
+            None => [0, 0, 0],
+        };
+
+        let mut background = None;
+        let mut current = Some(index);
+        while let Some(element) = current {
+            let style = self.style(element);
+            if style.property("display") == Some("none") {
+                return None;
+            }
+            if background.is_none() {
+                let value = style
+                    .property("background-color")
+                    .or_else(|| style.property("background"));
+                if let Some(value) = value {
+                    if let Paint::Color(color) = background_paint(value)? {
+                        background = Some(color);
+                    }
+                }
+            }
+            current = self.elements[element].parent;
+        }
+        Some((foreground, background.unwrap_or([255, 255, 255])))
+    }
+
+    fn is_large_text(&self, index: usize) -> bool {
+        let tag = &self.elements[index].tag;
+        let style = self.style(index);
+        let size = style
+            .property("font-size")
+            .and_then(font_size_px)
+            .unwrap_or(match tag.as_str() {
+                "h1" => 32.0,
+                "h2" => 24.0,
+                "h3" => 18.72,
+                _ => 16.0,
+            });
+        let bold = match style.property("font-weight") {
+            Some(weight) => {
+                matches!(weight, "bold" | "bolder")
+                    || weight.parse::<u32>().is_ok_and(|weight| weight >= 700)
+            }
+            None => heading_level(tag).is_some() || matches!(tag.as_str(), "b" | "strong" | "th"),
+        };
+        size >= 24.0 || (bold && size >= 18.66)
+    }
+}
+
+pub(crate) fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<!--") {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find("-->")
+            .map_or("", |end| &rest[start + end + 3..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// The color in a `background` or `background-color` value; images and
+/// gradients are treated as unknown.
+pub(crate) fn background_paint(value: &str) -> Option<Paint> {
+    if value.contains("url(") || value.contains("gradient(") {
+        return None;
+    }
+    if let Some(paint) = parse_color(value) {
+        return Some(paint);
+    }
+    // The shorthand may list the color among other components.
+    value
+        .split_whitespace()
+        .find_map(parse_color)
+        .or(Some(Paint::Transparent))
+}
+
+pub(crate) fn parse_color(value: &str) -> Option<Paint> {
+    let value = value.trim().to_ascii_lowercase();
+    if value == "transparent" {
+        return Some(Paint::Transparent);
+    }
+    if let Some(hex) = value.strip_prefix('#') {
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<_>>()?;
+        let (rgb, alpha) = match digits.as_slice() {
+            [r, g, b] => ([r * 17, g * 17, b * 17], 255),
+            [r, g, b, a] => ([r * 17, g * 17, b * 17], a * 17),
+            [r1, r2, g1, g2, b1, b2] => ([r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2], 255),
+            [r1, r2, g1, g2, b1, b2, a1, a2] => {
+                ([r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2], a1 * 16 + a2)
+            }
+            _ => return None,
+        };
+        return Some(if alpha == 0 {
+            Paint::Transparent
+        } else {
+            Paint::Color(rgb)
+        });
+    }
+    if let Some(arguments) = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let parts: Vec<&str> = arguments
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        let channel = |part: &str| -> Option<u8> {
+            match part.strip_suffix('%') {
+                Some(percent) => Some((percent.parse::<f64>().ok()? * 2.55).round() as u8),
+                None => Some(part.parse::<f64>().ok()?.round().clamp(0.0, 255.0) as u8),
+            }
+        };
+        let rgb = [
+            channel(parts.first()?)?,
+            channel(parts.get(1)?)?,
+            channel(parts.get(2)?)?,
+        ];
+        let transparent = parts.get(3).is_some_and(|alpha| {
+            alpha
+                .trim_end_matches('%')
+                .parse::<f64>()
+                .is_ok_and(|alpha| alpha == 0.0)
+        });
+        return Some(if transparent {
+            Paint::Transparent
+        } else {
+            Paint::Color(rgb)
+        });
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, rgb)| Paint::Color(*rgb))
+}
+
+fn font_size_px(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, scale) = if let Some(px) = value.strip_suffix("px") {
+        (px, 1.0)
+    } else if let Some(pt) = value.strip_suffix("pt") {
+        (pt, 4.0 / 3.0)
+    } else if let Some(em) = value
+        .strip_suffix("rem")
+        .or_else(|| value.strip_suffix("em"))
+    {
+        (em, 16.0)
+    } else {
+        return None;
+    };
+    number.trim().parse::<f64>().ok().map(|size| size * scale)
+}
+
+/// WCAG contrast ratio between two sRGB colors.
+fn contrast_ratio(a: [u8; 3], b: [u8; 3]) -> f64 {
+    let luminance = |rgb: [u8; 3]| {
+        let [r, g, b] = rgb.map(|channel| {
+            let channel = channel as f64 / 255.0;
+            if channel <= 0.03928 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    };
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::BrowserCore;
+
+/// A page the user bookmarked or imported from another browser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub url: Url,
+    pub title: String,
+    /// Folder path such as `Toolbar/News`, `None` for the top level.
+    #[serde(default)]
+    pub folder: Option<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+impl BrowserCore {
+    /// Bookmarks `url` unless it already is.
+    pub fn add_bookmark(&self, url: Url, title: impl Into<String>) {
+        let mut guard = self.state.write();
+        if guard.bookmarks.iter().any(|bookmark| bookmark.url == url) {
+            return;
+        }
+        guard.bookmarks.push(Bookmark {
+            url,
+            title: title.into(),
+            folder: None,
+            added_at: Utc::now(),
+        });
+    }
+
+    pub fn remove_bookmark(&self, url: &Url) {
+        self.state
+            .write()
+            .bookmarks
+            .retain(|bookmark| &bookmark.url != url);
+    }
+
+    pub fn is_bookmarked(&self, url: &Url) -> bool {
+        self.state
+            .read()
+            .bookmarks
+            .iter()
+            .any(|bookmark| &bookmark.url == url)
+    }
+
+    pub fn bookmarks(&self) -> Vec<Bookmark> {
+        self.state.read().bookmarks.clone()
+    }
+
+    /// Adds `bookmarks` whose URL is not bookmarked yet and returns how many
+    /// were new.
+    pub fn merge_bookmarks(&self, bookmarks: Vec<Bookmark>) -> usize {
+        let mut guard = self.state.write();
+        let before = guard.bookmarks.len();
+        for bookmark in bookmarks {
+            if !guard
+                .bookmarks
+                .iter()
+                .any(|known| known.url == bookmark.url)
+            {
+                guard.bookmarks.push(bookmark);
+            }
+        }
+        guard.bookmarks.len() - before
+    }
+
+    pub fn import_bookmarks(&self, bookmarks: Vec<Bookmark>) {
+        self.state.write().bookmarks = bookmarks;
+    }
+}
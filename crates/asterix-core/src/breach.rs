@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::{BrowserCore, BrowserError, RequestKind};
+
+/// Pwned Passwords range endpoint, taking the first five hex digits of a
+/// SHA-1 hash and listing the suffixes of breached passwords sharing them.
+const PWNED_PASSWORDS_RANGE_API: &str = "https://api.pwnedpasswords.com/range/";
+
+/// Hex digits of a hash sent to the breach service.
+const HASH_PREFIX_LEN: usize = 5;
+
+/// What checking a saved login's password found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordReport {
+    pub origin: String,
+    pub username: String,
+    /// Times the password appears in known data breaches.
+    pub breach_count: u64,
+    /// Origins of the other saved logins using the same password.
+    pub reused_on: Vec<String>,
+}
+
+impl PasswordReport {
+    pub fn is_compromised(&self) -> bool {
+        self.breach_count > 0
+    }
+
+    pub fn is_reused(&self) -> bool {
+        !self.reused_on.is_empty()
+    }
+}
+
+impl BrowserCore {
+    /// Checks every saved password against the Pwned Passwords list and for
+    /// reuse between sites. Only the first five hex digits of each
+    /// password's SHA-1 hash are sent, so the service never learns which
+    /// password was looked up.
+    pub async fn check_saved_passwords(&self) -> Result<Vec<PasswordReport>, BrowserError> {
+        let logins = self.saved_logins();
+        let hashes: Vec<String> = logins
+            .iter()
+            .map(|login| {
+                Sha1::digest(login.password.as_bytes())
+                    .iter()
+                    .map(|byte| format!("{byte:02X}"))
+                    .collect()
+            })
+            .collect();
+
+        let mut prefixes: Vec<&str> = hashes.iter().map(|hash| &hash[..HASH_PREFIX_LEN]).collect();
+        prefixes.sort_unstable();
+        prefixes.dedup();
+        let mut breach_counts = HashMap::new();
+        for prefix in prefixes {
+            let builder = self
+                .private_client()
+                .get(format!("{PWNED_PASSWORDS_RANGE_API}{prefix}"))
+                // Pads the answer with fake entries so its size gives nothing away.
+                .header("Add-Padding", "true");
+            let response = self
+                .send_logged(builder, RequestKind::BreachCheck, None)
+                .await?;
+            if !response.status.is_success() {
+                return Err(BrowserError::Status(response.status.as_u16()));
+            }
+            let body =
+                std::str::from_utf8(&response.body).map_err(|_| BrowserError::InvalidBody)?;
+            for line in body.lines() {
+                let Some((suffix, count)) = line.trim().split_once(':') else {
+                    continue;
+                };
+                let count: u64 = count.parse().unwrap_or(0);
+                if count > 0 {
+                    breach_counts.insert(format!("{prefix}{suffix}"), count);
+                }
+            }
+        }
+
+        Ok(logins
+            .iter()
+            .zip(&hashes)
+            .map(|(login, hash)| {
+                let mut reused_on: Vec<String> = logins
+                    .iter()
+                    .zip(&hashes)
+                    .filter(|(other, other_hash)| {
+                        *other_hash == hash && other.origin != login.origin
+                    })
+                    .map(|(other, _)| other.origin.clone())
+                    .collect();
+                reused_on.sort();
+                reused_on.dedup();
+                PasswordReport {
+                    origin: login.origin.clone(),
+                    username: login.username.clone(),
+                    breach_count: breach_counts.get(hash).copied().unwrap_or(0),
+                    reused_on,
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,815 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::http_cache::CacheEntry;
+
+/// Index of the records in the block files, rewritten as a whole.
+const INDEX_FILE: &str = "index.json";
+
+/// Bumped when the index or record layout changes; an index of another
+/// version is rebuilt from the block files.
+const INDEX_VERSION: u32 = 1;
+
+/// Size at which appends move on to a new block file.
+const BLOCK_FILE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Start of every record, to find damage when scanning a block file.
+const RECORD_MAGIC: [u8; 4] = *b"AXC1";
+
+/// Magic, kind and padding, metadata length, body length and CRC-32.
+const RECORD_HEADER_BYTES: usize = 20;
+
+/// Changes kept only in memory before the index is written out. Records
+/// appended since are found again by scanning the ends of the block files.
+const MAX_UNFLUSHED_CHANGES: usize = 64;
+
+/// What a record in a block file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordKind {
+    /// A response: its [`CacheEntry`] as JSON, then its body.
+    Document = 0,
+    /// The URL, as JSON, of a response dropped from the cache, so that it
+    /// does not come back when the index is rebuilt from the block files.
+    Removal = 1,
+}
+
+/// Where a record sits in the block files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Location {
+    file: u32,
+    offset: u64,
+    /// Length of the whole record, header included.
+    len: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Slot {
+    entry: CacheEntry,
+    location: Location,
+    /// When the response was last served, for evicting the least recently
+    /// used first.
+    last_used: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    /// Length of each block file the index accounts for, by id.
+    files: BTreeMap<u32, u64>,
+    slots: Vec<Slot>,
+}
+
+/// A record parsed from a block file.
+struct RawRecord<'a> {
+    kind: RecordKind,
+    meta: &'a [u8],
+    body: &'a [u8],
+    len: usize,
+    crc: u32,
+}
+
+impl RawRecord<'_> {
+    fn is_intact(&self) -> bool {
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(self.meta);
+        crc.update(self.body);
+        crc.finalize() == self.crc
+    }
+}
+
+/// Responses packed into a few append-only block files, read through
+/// memory maps, with an index of where each one sits.
+///
+/// Records carry their metadata and a checksum, so a lost or damaged index
+/// is rebuilt by scanning the block files, and damaged records are dropped
+/// when read. Replaced and evicted records leave dead space behind, which
+/// compaction reclaims by moving the live records of mostly dead files to
+/// the end of the newest one.
+#[derive(Debug)]
+pub(crate) struct BlockStore {
+    dir: PathBuf,
+    slots: HashMap<Url, Slot>,
+    /// Length of each block file, by id.
+    files: BTreeMap<u32, u64>,
+    /// Bytes of each block file no slot points to.
+    dead: HashMap<u32, u64>,
+    /// Map of each block file read from. Bodies handed out are slices of
+    /// it, which keep the map alive while in use.
+    maps: HashMap<u32, Bytes>,
+    /// File appends go to.
+    active: Option<(u32, File)>,
+    unflushed: usize,
+}
+
+impl BlockStore {
+    /// Opens the store in `dir`, recovering records written after the index
+    /// was last saved and moving over documents stored one file per entry.
+    pub(crate) fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut files = BTreeMap::new();
+        for file in fs::read_dir(dir)? {
+            let file = file?;
+            if let Some(id) = block_id(&file.path()) {
+                files.insert(id, file.metadata()?.len());
+            }
+        }
+        let (mut slots, indexed) = match read_index(dir) {
+            Some(index) => {
+                let slots = index
+                    .slots
+                    .into_iter()
+                    .map(|slot| (slot.entry.url.clone(), slot))
+                    .collect();
+                (slots, index.files)
+            }
+            None => {
+                if !files.is_empty() {
+                    info!("rebuilding the HTTP cache index from its block files");
+                }
+                (HashMap::new(), BTreeMap::new())
+            }
+        };
+        // Files shorter than the index says lost their ends.
+        slots.retain(|_, slot| {
+            let end = slot.location.offset.checked_add(slot.location.len);
+            files
+                .get(&slot.location.file)
+                .is_some_and(|len| end.is_some_and(|end| end <= *len))
+        });
+        let mut store = Self {
+            dir: dir.to_owned(),
+            slots,
+            files: files.clone(),
+            dead: HashMap::new(),
+            maps: HashMap::new(),
+            active: None,
+            unflushed: 0,
+        };
+        // Files in id order are records in the order they were written.
+        for (id, len) in files {
+            let from = indexed.get(&id).copied().unwrap_or(0).min(len);
+            if from < len {
+                store.recover(id, from)?;
+            }
+        }
+        store.count_dead();
+        if store.unflushed > 0 {
+            store.flush();
+        }
+        Ok(store)
+    }
+
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &CacheEntry> {
+        self.slots.values().map(|slot| &slot.entry)
+    }
+
+    pub(crate) fn entry(&self, url: &Url) -> Option<&CacheEntry> {
+        self.slots.get(url).map(|slot| &slot.entry)
+    }
+
+    /// The stored response for `url` with its body, which points into the
+    /// block file rather than being copied out. A record that fails its
+    /// checksum is dropped.
+    pub(crate) fn get(&mut self, url: &Url) -> Option<(CacheEntry, Bytes)> {
+        let location = self.slots.get(url)?.location;
+        let body = self.map(location.file).cloned().map(|map| {
+            record_at(&map, location)
+                .filter(|record| record.kind == RecordKind::Document)
+                .map(|record| map.slice_ref(record.body))
+        });
+        let body = match body {
+            Ok(Some(body)) => body,
+            Ok(None) => {
+                warn!("cached copy of {url} is damaged, dropping it");
+                self.remove(url);
+                return None;
+            }
+            Err(err) => {
+                debug!("cached body of {url} is gone: {err}");
+                self.remove(url);
+                return None;
+            }
+        };
+        let slot = self.slots.get_mut(url)?;
+        slot.last_used = Utc::now();
+        Some((slot.entry.clone(), body))
+    }
+
+    /// Stores `body` as the response for the URL of `entry`, replacing any
+    /// earlier one.
+    pub(crate) fn insert(&mut self, entry: CacheEntry, body: &[u8]) -> io::Result<()> {
+        let meta = serde_json::to_vec(&entry).map_err(io::Error::other)?;
+        let head = record_head(RecordKind::Document, &meta, body)?;
+        let location = self.append(&[&head, body])?;
+        let slot = Slot {
+            entry,
+            location,
+            last_used: Utc::now(),
+        };
+        if let Some(old) = self.slots.insert(slot.entry.url.clone(), slot) {
+            self.mark_dead(old.location);
+        }
+        self.changed();
+        Ok(())
+    }
+
+    /// Replaces the metadata of a stored response, e.g. after revalidation.
+    /// The body stays where it is.
+    pub(crate) fn update(&mut self, entry: CacheEntry) {
+        if let Some(slot) = self.slots.get_mut(&entry.url) {
+            slot.entry = entry;
+            self.changed();
+        }
+    }
+
+    /// Drops the response for `url`. Returns whether there was one.
+    pub(crate) fn remove(&mut self, url: &Url) -> bool {
+        let Some(slot) = self.slots.remove(url) else {
+            return false;
+        };
+        self.mark_dead(slot.location);
+        let removal = serde_json::to_vec(url)
+            .map_err(io::Error::other)
+            .and_then(|meta| record_head(RecordKind::Removal, &meta, &[]))
+            .and_then(|record| self.append(&[&record]));
+        match removal {
+            Ok(location) => self.mark_dead(location),
+            Err(err) => debug!("failed to record the removal of {url}: {err}"),
+        }
+        self.changed();
+        true
+    }
+
+    /// Evicts the least recently used responses until their bodies take no
+    /// more than `max_bytes`.
+    pub(crate) fn evict_to(&mut self, max_bytes: u64) {
+        let mut total: u64 = self.slots.values().map(|slot| slot.entry.size).sum();
+        while total > max_bytes {
+            let Some(oldest) = self
+                .slots
+                .values()
+                .min_by_key(|slot| slot.last_used)
+                .map(|slot| slot.entry.url.clone())
+            else {
+                break;
+            };
+            total -= self.slots[&oldest].entry.size;
+            self.remove(&oldest);
+        }
+    }
+
+    /// Deletes every block file and the index.
+    pub(crate) fn clear(&mut self) {
+        self.maps.clear();
+        self.active = None;
+        for id in std::mem::take(&mut self.files).into_keys() {
+            let _ = fs::remove_file(self.block_path(id));
+        }
+        let _ = fs::remove_file(self.dir.join(INDEX_FILE));
+        self.slots.clear();
+        self.dead.clear();
+        self.unflushed = 0;
+    }
+
+    /// A block file, other than the one appended to, that is at least half
+    /// dead and worth compacting.
+    pub(crate) fn compaction_candidate(&self) -> Option<u32> {
+        let active = self.active.as_ref().map(|(id, _)| *id);
+        let newest = self.files.keys().next_back().copied();
+        self.files
+            .iter()
+            .filter(|(id, _)| Some(**id) != active && Some(**id) != newest)
+            .find(|(id, len)| self.dead.get(id).copied().unwrap_or(0) * 2 >= **len)
+            .map(|(id, _)| *id)
+    }
+
+    /// URLs whose records sit in block file `id`.
+    pub(crate) fn urls_in(&self, id: u32) -> Vec<Url> {
+        self.slots
+            .values()
+            .filter(|slot| slot.location.file == id)
+            .map(|slot| slot.entry.url.clone())
+            .collect()
+    }
+
+    /// Copies the record of `url` out of block file `id` to the end of the
+    /// active one, if it still sits there.
+    pub(crate) fn relocate(&mut self, url: &Url, id: u32) {
+        let Some(location) = self
+            .slots
+            .get(url)
+            .map(|slot| slot.location)
+            .filter(|location| location.file == id)
+        else {
+            return;
+        };
+        let start = location.offset as usize;
+        let record = self.read(location).ok().flatten().map(|record| record.len);
+        let record = match record {
+            Some(len) => self.maps[&id].slice(start..start + len),
+            None => {
+                warn!("cached copy of {url} is damaged, dropping it");
+                self.remove(url);
+                return;
+            }
+        };
+        match self.append(&[&record]) {
+            Ok(new_location) => {
+                if let Some(slot) = self.slots.get_mut(url) {
+                    slot.location = new_location;
+                }
+            }
+            Err(err) => {
+                warn!("failed to move the cached copy of {url}: {err}");
+                self.remove(url);
+            }
+        }
+    }
+
+    /// Deletes block file `id`, dropping whatever records are left in it.
+    pub(crate) fn delete_file(&mut self, id: u32) {
+        let left = self.urls_in(id);
+        for url in &left {
+            self.slots.remove(url);
+        }
+        self.maps.remove(&id);
+        self.files.remove(&id);
+        self.dead.remove(&id);
+        if let Err(err) = fs::remove_file(self.block_path(id)) {
+            warn!("failed to delete cache block file {id}: {err}");
+        }
+        // The index must not point into the deleted file.
+        self.flush();
+    }
+
+    /// Writes the index out.
+    pub(crate) fn flush(&mut self) {
+        let index = IndexFile {
+            version: INDEX_VERSION,
+            files: self.files.clone(),
+            slots: self.slots.values().cloned().collect(),
+        };
+        let path = self.dir.join(INDEX_FILE);
+        let temp = path.with_extension("json.tmp");
+        let written = serde_json::to_vec(&index)
+            .map_err(io::Error::other)
+            .and_then(|json| fs::write(&temp, json))
+            .and_then(|()| fs::rename(&temp, &path));
+        match written {
+            Ok(()) => self.unflushed = 0,
+            Err(err) => warn!("failed to save the HTTP cache index: {err}"),
+        }
+    }
+
+    pub(crate) fn has_unflushed_changes(&self) -> bool {
+        self.unflushed > 0
+    }
+
+    fn changed(&mut self) {
+        self.unflushed += 1;
+        if self.unflushed >= MAX_UNFLUSHED_CHANGES {
+            self.flush();
+        }
+    }
+
+    fn block_path(&self, id: u32) -> PathBuf {
+        self.dir.join(format!("blocks-{id:05}.dat"))
+    }
+
+    fn map(&mut self, id: u32) -> io::Result<&Bytes> {
+        if !self.maps.contains_key(&id) {
+            let file = File::open(self.block_path(id))?;
+            // SAFETY: block files are only changed by this store, which
+            // appends past the mapped range, only cuts a file short while
+            // recovering it before any body is handed out, and deletes files
+            // whose maps stay readable until their last body is dropped.
+            let map = unsafe { Mmap::map(&file)? };
+            self.maps.insert(id, Bytes::from_owner(map));
+        }
+        Ok(&self.maps[&id])
+    }
+
+    /// The record at `location`, `None` when it fails its checks.
+    fn read(&mut self, location: Location) -> io::Result<Option<RawRecord<'_>>> {
+        let map = self.map(location.file)?;
+        Ok(record_at(map, location))
+    }
+
+    /// Appends the record made of `parts` to the active block file, starting
+    /// a new file once it is full. Parts are written one after the other, so
+    /// bodies go to the file without being copied into a record first.
+    fn append(&mut self, parts: &[&[u8]]) -> io::Result<Location> {
+        let id = self.active_file()?;
+        let offset = self.files.get(&id).copied().unwrap_or(0);
+        let Some((_, file)) = self.active.as_mut() else {
+            return Err(io::Error::other("no active block file"));
+        };
+        if let Err(err) = parts.iter().try_for_each(|part| file.write_all(part)) {
+            // Leave no partial record behind.
+            let _ = file.set_len(offset);
+            return Err(err);
+        }
+        let len = parts.iter().map(|part| part.len() as u64).sum();
+        self.files.insert(id, offset + len);
+        // The map no longer covers the whole file.
+        self.maps.remove(&id);
+        Ok(Location {
+            file: id,
+            offset,
+            len,
+        })
+    }
+
+    fn active_file(&mut self) -> io::Result<u32> {
+        let newest = self.files.iter().next_back().map(|(id, len)| (*id, *len));
+        match (&self.active, newest) {
+            (Some((id, _)), Some((newest, len))) if *id == newest && len < BLOCK_FILE_BYTES => {
+                return Ok(newest);
+            }
+            (None, Some((newest, len))) if len < BLOCK_FILE_BYTES => {
+                let file = OpenOptions::new()
+                    .append(true)
+                    .open(self.block_path(newest))?;
+                self.active = Some((newest, file));
+                return Ok(newest);
+            }
+            _ => {}
+        }
+        let id = newest.map_or(1, |(id, _)| id + 1);
+        let file = OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(self.block_path(id))?;
+        self.files.insert(id, 0);
+        self.active = Some((id, file));
+        Ok(id)
+    }
+
+    /// Replays the records of block file `id` from `from` on, which the
+    /// index does not know about. Records failing their checksum are skipped;
+    /// the file is cut off where no record can be made out any more.
+    fn recover(&mut self, id: u32, from: u64) -> io::Result<()> {
+        let mut applied = Vec::new();
+        let mut end = from as usize;
+        let len = {
+            let map = self.map(id)?;
+            while let Some(record) = map.get(end..).and_then(frame_record) {
+                if !record.is_intact() {
+                    // The header still tells where the next record starts.
+                    warn!("skipping a damaged record in cache block file {id} at byte {end}");
+                    end += record.len;
+                    continue;
+                }
+                let location = Location {
+                    file: id,
+                    offset: end as u64,
+                    len: record.len as u64,
+                };
+                match record.kind {
+                    RecordKind::Document => {
+                        if let Ok(entry) = serde_json::from_slice::<CacheEntry>(record.meta) {
+                            applied.push((entry.url.clone(), Some((entry, location))));
+                        }
+                    }
+                    RecordKind::Removal => {
+                        if let Ok(url) = serde_json::from_slice::<Url>(record.meta) {
+                            applied.push((url, None));
+                        }
+                    }
+                }
+                end += record.len;
+            }
+            map.len()
+        };
+        self.unflushed += applied.len();
+        for (url, document) in applied {
+            match document {
+                Some((entry, location)) => {
+                    let last_used = entry.stored_at;
+                    self.slots.insert(
+                        url,
+                        Slot {
+                            entry,
+                            location,
+                            last_used,
+                        },
+                    );
+                }
+                None => {
+                    self.slots.remove(&url);
+                }
+            }
+        }
+        if end < len {
+            warn!("cache block file {id} is damaged after byte {end}, dropping the rest");
+            self.maps.remove(&id);
+            OpenOptions::new()
+                .write(true)
+                .open(self.block_path(id))?
+                .set_len(end as u64)?;
+            self.files.insert(id, end as u64);
+            self.slots.retain(|_, slot| {
+                slot.location.file != id || slot.location.offset + slot.location.len <= end as u64
+            });
+            self.unflushed += 1;
+        }
+        Ok(())
+    }
+
+    fn mark_dead(&mut self, location: Location) {
+        *self.dead.entry(location.file).or_default() += location.len;
+    }
+
+    fn count_dead(&mut self) {
+        let mut live: HashMap<u32, u64> = HashMap::new();
+        for slot in self.slots.values() {
+            *live.entry(slot.location.file).or_default() += slot.location.len;
+        }
+        self.dead = self
+            .files
+            .iter()
+            .map(|(id, len)| (*id, len.saturating_sub(live.get(id).copied().unwrap_or(0))))
+            .collect();
+    }
+}
+
+/// Id of a block file named like `blocks-00001.dat`.
+fn block_id(path: &Path) -> Option<u32> {
+    if path.extension()? != "dat" {
+        return None;
+    }
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix("blocks-")?
+        .parse()
+        .ok()
+}
+
+fn read_index(dir: &Path) -> Option<IndexFile> {
+    let json = fs::read(dir.join(INDEX_FILE)).ok()?;
+    match serde_json::from_slice::<IndexFile>(&json) {
+        Ok(index) if index.version == INDEX_VERSION => Some(index),
+        Ok(_) => None,
+        Err(err) => {
+            warn!("HTTP cache index is damaged: {err}");
+            None
+        }
+    }
+}
+
+/// The record at `location` of a mapped block file, `None` when it fails
+/// its checks.
+fn record_at(map: &[u8], location: Location) -> Option<RawRecord<'_>> {
+    let start = usize::try_from(location.offset).ok()?;
+    let end = start.checked_add(usize::try_from(location.len).ok()?)?;
+    map.get(start..end)
+        .and_then(parse_record)
+        .filter(|record| record.len as u64 == location.len)
+}
+
+/// Header and metadata of a record, which `body` follows in the file.
+fn record_head(kind: RecordKind, meta: &[u8], body: &[u8]) -> io::Result<Vec<u8>> {
+    let too_large = || io::Error::other("response too large to cache");
+    let meta_len = u32::try_from(meta.len()).map_err(|_| too_large())?;
+    let body_len = u32::try_from(body.len()).map_err(|_| too_large())?;
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(meta);
+    crc.update(body);
+    let mut head = Vec::with_capacity(RECORD_HEADER_BYTES + meta.len());
+    head.extend_from_slice(&RECORD_MAGIC);
+    head.extend_from_slice(&[kind as u8, 0, 0, 0]);
+    head.extend_from_slice(&meta_len.to_le_bytes());
+    head.extend_from_slice(&body_len.to_le_bytes());
+    head.extend_from_slice(&crc.finalize().to_le_bytes());
+    head.extend_from_slice(meta);
+    Ok(head)
+}
+
+/// The record at the start of `data`, `None` when it is cut short or fails
+/// its checksum.
+fn parse_record(data: &[u8]) -> Option<RawRecord<'_>> {
+    frame_record(data).filter(RawRecord::is_intact)
+}
+
+/// The record at the start of `data` as its header describes it, `None`
+/// when the header is damaged or the record cut short.
+fn frame_record(data: &[u8]) -> Option<RawRecord<'_>> {
+    let header = data.get(..RECORD_HEADER_BYTES)?;
+    if header[..4] != RECORD_MAGIC {
+        return None;
+    }
+    let kind = match header[4] {
+        0 => RecordKind::Document,
+        1 => RecordKind::Removal,
+        _ => return None,
+    };
+    let word = |at: usize| {
+        u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    let meta_len = word(8) as usize;
+    let body_len = word(12) as usize;
+    let rest = &data[RECORD_HEADER_BYTES..];
+    let meta = rest.get(..meta_len)?;
+    let body = rest.get(meta_len..meta_len + body_len)?;
+    Some(RawRecord {
+        kind,
+        meta,
+        body,
+        len: RECORD_HEADER_BYTES + meta_len + body_len,
+        crc: word(16),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, size: usize) -> CacheEntry {
+        serde_json::from_value(serde_json::json!({
+            "url": url,
+            "status": 200,
+            "headers": [["content-type", "text/html"]],
+            "stored_at": "2024-01-01T00:00:00Z",
+            "size": size,
+        }))
+        .unwrap()
+    }
+
+    fn url(address: &str) -> Url {
+        Url::parse(address).unwrap()
+    }
+
+    /// Store in `dir` holding `a`, `b` and `c`, none of them flushed.
+    fn store_with_three(dir: &Path) -> BlockStore {
+        let mut store = BlockStore::open(dir).unwrap();
+        for (address, body) in [
+            ("https://a.test/", b"first body".as_slice()),
+            ("https://b.test/", b"second body"),
+            ("https://c.test/", b"third body"),
+        ] {
+            store.insert(entry(address, body.len()), body).unwrap();
+        }
+        store
+    }
+
+    fn body(store: &mut BlockStore, address: &str) -> Option<Vec<u8>> {
+        store.get(&url(address)).map(|(_, body)| body.to_vec())
+    }
+
+    fn block_file(dir: &Path) -> PathBuf {
+        dir.join("blocks-00001.dat")
+    }
+
+    #[test]
+    fn bodies_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store_with_three(dir.path());
+        store.flush();
+        drop(store);
+        let mut store = BlockStore::open(dir.path()).unwrap();
+        assert_eq!(body(&mut store, "https://b.test/").unwrap(), b"second body");
+        assert_eq!(store.entries().count(), 3);
+    }
+
+    #[test]
+    fn recovers_records_written_after_the_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = BlockStore::open(dir.path()).unwrap();
+        store
+            .insert(entry("https://a.test/", 10), b"first body")
+            .unwrap();
+        store.flush();
+        // Changes after the last flush, then a crash.
+        store
+            .insert(entry("https://b.test/", 11), b"second body")
+            .unwrap();
+        assert!(store.remove(&url("https://a.test/")));
+        assert!(store.has_unflushed_changes());
+        drop(store);
+
+        let mut store = BlockStore::open(dir.path()).unwrap();
+        assert!(store.entry(&url("https://a.test/")).is_none());
+        assert_eq!(body(&mut store, "https://b.test/").unwrap(), b"second body");
+    }
+
+    #[test]
+    fn rebuilds_a_corrupt_or_outdated_index() {
+        for index in [
+            b"{ not json".as_slice(),
+            b"",
+            br#"{"version":0,"files":{},"slots":[]}"#,
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let mut store = store_with_three(dir.path());
+            store.flush();
+            drop(store);
+            fs::write(dir.path().join(INDEX_FILE), index).unwrap();
+            let mut store = BlockStore::open(dir.path()).unwrap();
+            assert_eq!(store.entries().count(), 3);
+            assert_eq!(body(&mut store, "https://c.test/").unwrap(), b"third body");
+        }
+    }
+
+    #[test]
+    fn drops_index_slots_pointing_past_the_block_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store_with_three(dir.path());
+        store.flush();
+        drop(store);
+        let path = dir.path().join(INDEX_FILE);
+        let mut index: serde_json::Value =
+            serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        for slot in index["slots"].as_array_mut().unwrap() {
+            slot["location"]["offset"] = serde_json::json!(u64::MAX);
+        }
+        fs::write(&path, serde_json::to_vec(&index).unwrap()).unwrap();
+        let store = BlockStore::open(dir.path()).unwrap();
+        assert_eq!(store.entries().count(), 0);
+    }
+
+    #[test]
+    fn cuts_off_a_truncated_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_three(dir.path());
+        let last = store.slots[&url("https://c.test/")].location;
+        drop(store);
+        let file = OpenOptions::new()
+            .write(true)
+            .open(block_file(dir.path()))
+            .unwrap();
+        file.set_len(last.offset + last.len - 3).unwrap();
+        drop(file);
+
+        let mut store = BlockStore::open(dir.path()).unwrap();
+        assert!(store.entry(&url("https://c.test/")).is_none());
+        assert_eq!(body(&mut store, "https://b.test/").unwrap(), b"second body");
+        // The damaged tail is gone, so appends follow the last good record.
+        assert_eq!(
+            fs::metadata(block_file(dir.path())).unwrap().len(),
+            last.offset
+        );
+        store.insert(entry("https://d.test/", 4), b"more").unwrap();
+        assert_eq!(body(&mut store, "https://d.test/").unwrap(), b"more");
+    }
+
+    #[test]
+    fn skips_records_failing_their_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_three(dir.path());
+        let first = store.slots[&url("https://a.test/")].location;
+        drop(store);
+        let path = block_file(dir.path());
+        let mut data = fs::read(&path).unwrap();
+        data[(first.offset + first.len - 1) as usize] ^= 0xff;
+        fs::write(&path, data).unwrap();
+
+        let mut store = BlockStore::open(dir.path()).unwrap();
+        assert!(store.entry(&url("https://a.test/")).is_none());
+        assert_eq!(body(&mut store, "https://b.test/").unwrap(), b"second body");
+        assert_eq!(body(&mut store, "https://c.test/").unwrap(), b"third body");
+    }
+
+    #[test]
+    fn damage_found_on_read_drops_the_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store_with_three(dir.path());
+        store.flush();
+        let first = store.slots[&url("https://a.test/")].location;
+        drop(store);
+        let path = block_file(dir.path());
+        let mut data = fs::read(&path).unwrap();
+        data[(first.offset + first.len - 1) as usize] ^= 0xff;
+        fs::write(&path, data).unwrap();
+
+        // The index still points at the record; reading it finds the damage.
+        let mut store = BlockStore::open(dir.path()).unwrap();
+        assert!(store.entry(&url("https://a.test/")).is_some());
+        assert!(body(&mut store, "https://a.test/").is_none());
+        assert!(store.entry(&url("https://a.test/")).is_none());
+    }
+
+    #[test]
+    fn frames_reject_cut_and_garbled_headers() {
+        let head = record_head(RecordKind::Document, b"{}", b"body").unwrap();
+        let record = [head.as_slice(), b"body"].concat();
+        assert!(parse_record(&record).is_some());
+        for len in 0..record.len() {
+            assert!(parse_record(&record[..len]).is_none());
+        }
+        let mut garbled = record.clone();
+        garbled[4] = 7;
+        assert!(frame_record(&garbled).is_none());
+        let mut huge = record;
+        huge[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(frame_record(&huge).is_none());
+    }
+}
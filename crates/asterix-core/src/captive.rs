@@ -0,0 +1,85 @@
+use std::net::{IpAddr, UdpSocket};
+use std::time::Duration;
+
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, BrowserError, RequestKind};
+
+/// Plain-HTTP page that answers with [`PROBE_BODY`] on an open network.
+/// Captive portals intercept it to redirect to, or serve, their login page.
+pub const CONNECTIVITY_PROBE: &str = "http://detectportal.firefox.com/success.txt";
+
+/// Body of [`CONNECTIVITY_PROBE`] when nothing sits in between.
+const PROBE_BODY: &str = "success";
+
+/// How long the probe may take before the network counts as offline.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Address outgoing traffic to the internet would leave from. No packet is
+/// sent: connecting a UDP socket only picks the route.
+const ROUTE_TARGET: (&str, u16) = ("192.0.2.1", 80);
+
+/// Outcome of a connectivity probe.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Connectivity {
+    /// The probe came back untouched.
+    Online,
+    /// Something on the network answered in the probe's stead; `login` is
+    /// the page it pointed to, or the probe itself when it served its page
+    /// directly.
+    CaptivePortal { login: Url },
+    /// The probe could not reach its host.
+    Offline,
+}
+
+/// Local address of the route to the internet, `None` without one. It
+/// changes when the machine joins another network, which is a cue to probe
+/// connectivity again.
+pub fn network_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).ok()?;
+    socket.connect(ROUTE_TARGET).ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+impl BrowserCore {
+    /// Fetches [`CONNECTIVITY_PROBE`] without following redirects or sending
+    /// cookies, and tells from the answer whether a captive portal is in the
+    /// way.
+    pub async fn check_connectivity(&self) -> Result<Connectivity, BrowserError> {
+        let proxy = self.clients.read().proxy.clone();
+        let builder = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .timeout(PROBE_TIMEOUT);
+        let client = proxy.apply(builder)?.build()?;
+        let probe = Url::parse(CONNECTIVITY_PROBE).expect("probe is a valid URL");
+        let response = match self
+            .send_logged(client.get(probe.clone()), RequestKind::Connectivity, None)
+            .await
+        {
+            Ok(response) => response,
+            Err(BrowserError::Network(err)) if err.is_connect() || err.is_timeout() => {
+                return Ok(Connectivity::Offline);
+            }
+            Err(err) => return Err(err),
+        };
+        if response.status.is_redirection() {
+            let login = response
+                .headers
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|location| probe.join(location).ok())
+                .unwrap_or(probe);
+            return Ok(Connectivity::CaptivePortal { login });
+        }
+        let untouched = response.status.is_success()
+            && String::from_utf8_lossy(&response.body).trim() == PROBE_BODY;
+        Ok(if untouched {
+            Connectivity::Online
+        } else {
+            Connectivity::CaptivePortal { login: probe }
+        })
+    }
+}
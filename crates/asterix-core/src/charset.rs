@@ -0,0 +1,200 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Deref;
+
+use bytes::Bytes;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{BrowserCore, PageResponse, TabId};
+
+/// How far into a document to look for a `<meta charset>` declaration, as
+/// in the HTML prescan.
+const META_PRESCAN_BYTES: usize = 1024;
+
+/// Encodings offered for overriding the detected one, by their WHATWG names.
+pub const TEXT_ENCODINGS: [&str; 16] = [
+    "UTF-8",
+    "windows-1252",
+    "ISO-8859-2",
+    "ISO-8859-15",
+    "windows-1250",
+    "windows-1251",
+    "KOI8-R",
+    "windows-1253",
+    "windows-1254",
+    "windows-1255",
+    "windows-1256",
+    "Shift_JIS",
+    "EUC-JP",
+    "EUC-KR",
+    "GBK",
+    "Big5",
+];
+
+/// Decoded text of a page. Text that needed no transcoding points into the
+/// bytes it was received as instead of being copied out of them, and clones
+/// share one buffer.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct PageText(Bytes);
+
+impl PageText {
+    /// Text of `decoded`, which `bytes` decoded to.
+    fn decoded(bytes: &Bytes, decoded: Cow<'_, str>) -> Self {
+        match decoded {
+            Cow::Borrowed(text) => PageText(bytes.slice_ref(text.as_bytes())),
+            Cow::Owned(text) => PageText::from(text),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // SAFETY: a `PageText` is only ever made from a `str`.
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+}
+
+impl From<String> for PageText {
+    fn from(text: String) -> Self {
+        PageText(Bytes::from(text))
+    }
+}
+
+impl From<&str> for PageText {
+    fn from(text: &str) -> Self {
+        PageText(Bytes::copy_from_slice(text.as_bytes()))
+    }
+}
+
+impl Deref for PageText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for PageText {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq<str> for PageText {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for PageText {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for PageText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for PageText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for PageText {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for PageText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(PageText::from)
+    }
+}
+
+/// Decodes a response body to text. The encoding comes from a byte order
+/// mark, the `charset` parameter of `content_type`, a `<meta charset>` in the
+/// document, in that order, falling back to UTF-8 when the body is valid
+/// UTF-8 and windows-1252 otherwise.
+pub(crate) fn decode_body(
+    bytes: &Bytes,
+    content_type: Option<&str>,
+) -> (PageText, &'static Encoding) {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| content_type.and_then(header_charset))
+        .or_else(|| meta_charset(bytes))
+        .unwrap_or_else(|| {
+            if std::str::from_utf8(bytes).is_ok() {
+                UTF_8
+            } else {
+                WINDOWS_1252
+            }
+        });
+    let (text, used, _) = encoding.decode(bytes);
+    (PageText::decoded(bytes, text), used)
+}
+
+impl BrowserCore {
+    /// `page` decoded again from the bytes it was received as, with the
+    /// encoding labelled `charset` instead of the detected one, or detected
+    /// afresh when `charset` is `None`. Returns `None` for an unknown label.
+    pub fn redecode_page(
+        &self,
+        tab: TabId,
+        page: &PageResponse,
+        charset: Option<&str>,
+    ) -> Option<PageResponse> {
+        let (body, used) = match charset {
+            Some(label) => {
+                let encoding = Encoding::for_label(label.as_bytes())?;
+                let (text, _) = encoding.decode_with_bom_removal(&page.bytes);
+                (PageText::decoded(&page.bytes, text), encoding)
+            }
+            None => decode_body(&page.bytes, page.mime_type.as_deref()),
+        };
+        let mut page = PageResponse {
+            body,
+            charset: used.name().to_owned(),
+            consent_rejected: Vec::new(),
+            ..page.clone()
+        };
+        self.reject_consent(&mut page, self.tab_options(tab).private);
+        self.retitle_tab(tab, &page);
+        Some(page)
+    }
+}
+
+/// Encoding named by the `charset` parameter of a `Content-Type` value.
+fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+/// Encoding declared by a `<meta charset>` or `<meta http-equiv>` tag near
+/// the start of the document.
+fn meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(META_PRESCAN_BYTES)];
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+    head.match_indices("<meta").find_map(|(start, _)| {
+        let tag = &head[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let value = &tag[tag.find("charset")? + "charset".len()..];
+        let value = value.trim_start().strip_prefix('=')?.trim_start();
+        let label = value
+            .trim_start_matches(['"', '\''])
+            .split(['"', '\'', ';', ' ', '/', '>'])
+            .next()?;
+        // A page that could be read to find this tag is not UTF-16, which
+        // `output_encoding` maps to UTF-8.
+        Encoding::for_label(label.as_bytes()).map(Encoding::output_encoding)
+    })
+}
@@ -0,0 +1,301 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::PageMetadata;
+
+/// Reference formats offered by "Copy citation".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CitationStyle {
+    BibTeX,
+    Apa,
+    Mla,
+}
+
+impl CitationStyle {
+    pub const ALL: [CitationStyle; 3] = [
+        CitationStyle::BibTeX,
+        CitationStyle::Apa,
+        CitationStyle::Mla,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CitationStyle::BibTeX => "BibTeX",
+            CitationStyle::Apa => "APA",
+            CitationStyle::Mla => "MLA",
+        }
+    }
+}
+
+/// Formats a web page reference in the requested style.
+pub fn format_citation(
+    metadata: &PageMetadata,
+    url: &Url,
+    accessed: DateTime<Utc>,
+    style: CitationStyle,
+) -> String {
+    let title = metadata.title.clone().unwrap_or_else(|| url.to_string());
+    let site = metadata.site_name.clone().or_else(|| {
+        url.host_str()
+            .map(|host| host.trim_start_matches("www.").to_owned())
+    });
+    let published = metadata.published.as_deref().and_then(parse_date);
+    let authors: Vec<Name> = metadata
+        .authors
+        .iter()
+        .map(|author| Name::parse(author))
+        .collect();
+
+    match style {
+        CitationStyle::BibTeX => {
+            bibtex(&title, &authors, site.as_deref(), published, url, accessed)
+        }
+        CitationStyle::Apa => apa(&title, &authors, site.as_deref(), published, url, accessed),
+        CitationStyle::Mla => mla(&title, &authors, site.as_deref(), published, url, accessed),
+    }
+}
+
+/// Publication date with whatever precision the page provided.
+#[derive(Clone, Copy)]
+enum PublishedDate {
+    Day(NaiveDate),
+    Year(i32),
+}
+
+impl PublishedDate {
+    fn year(self) -> i32 {
+        match self {
+            PublishedDate::Day(date) => date.year(),
+            PublishedDate::Year(year) => year,
+        }
+    }
+}
+
+fn parse_date(raw: &str) -> Option<PublishedDate> {
+    let raw = raw.trim();
+    let head = raw.get(..10).unwrap_or(raw);
+    for format in ["%Y-%m-%d", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(head, format) {
+            return Some(PublishedDate::Day(date));
+        }
+    }
+    raw.get(..4)
+        .and_then(|year| year.parse().ok())
+        .map(PublishedDate::Year)
+}
+
+/// Author name split into given and family parts.
+struct Name {
+    given: Vec<String>,
+    family: String,
+}
+
+impl Name {
+    /// Accepts both "Given Family" and "Family, Given" spellings.
+    fn parse(raw: &str) -> Self {
+        if let Some((family, given)) = raw.split_once(',') {
+            return Self {
+                given: given.split_whitespace().map(str::to_owned).collect(),
+                family: family.trim().to_owned(),
+            };
+        }
+        let mut parts: Vec<String> = raw.split_whitespace().map(str::to_owned).collect();
+        let family = parts.pop().unwrap_or_default();
+        Self {
+            given: parts,
+            family,
+        }
+    }
+
+    /// "Family, G. H."
+    fn with_initials(&self) -> String {
+        if self.given.is_empty() {
+            return self.family.clone();
+        }
+        let initials = self
+            .given
+            .iter()
+            .filter_map(|part| part.chars().next())
+            .map(|initial| format!("{initial}."))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{}, {initials}", self.family)
+    }
+
+    /// "Family, Given Middle"
+    fn inverted(&self) -> String {
+        if self.given.is_empty() {
+            return self.family.clone();
+        }
+        format!("{}, {}", self.family, self.given.join(" "))
+    }
+
+    /// "Given Middle Family"
+    fn natural(&self) -> String {
+        let mut parts = self.given.clone();
+        parts.push(self.family.clone());
+        parts.join(" ")
+    }
+}
+
+fn bibtex(
+    title: &str,
+    authors: &[Name],
+    site: Option<&str>,
+    published: Option<PublishedDate>,
+    url: &Url,
+    accessed: DateTime<Utc>,
+) -> String {
+    let key_author = authors
+        .first()
+        .map(|name| name.family.as_str())
+        .or(site)
+        .unwrap_or("web");
+    let key_word = title
+        .split_whitespace()
+        .find(|word| word.len() > 3)
+        .unwrap_or("page");
+    let year = published.map(PublishedDate::year);
+    let key = format!(
+        "{}{}{}",
+        bibtex_key_part(key_author),
+        year.map(|year| year.to_string()).unwrap_or_default(),
+        bibtex_key_part(key_word),
+    );
+
+    let mut fields = vec![("title", format!("{{{}}}", escape_bibtex(title)))];
+    if !authors.is_empty() {
+        let names = authors
+            .iter()
+            .map(Name::inverted)
+            .collect::<Vec<_>>()
+            .join(" and ");
+        fields.push(("author", escape_bibtex(&names)));
+    }
+    if let Some(site) = site {
+        fields.push(("organization", escape_bibtex(site)));
+    }
+    if let Some(year) = year {
+        fields.push(("year", year.to_string()));
+    }
+    fields.push(("url", url.to_string()));
+    fields.push(("urldate", accessed.format("%Y-%m-%d").to_string()));
+    fields.push(("note", format!("Accessed: {}", accessed.format("%Y-%m-%d"))));
+
+    let body = fields
+        .iter()
+        .map(|(name, value)| format!("  {name} = {{{value}}}"))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("@misc{{{key},\n{body}\n}}")
+}
+
+fn apa(
+    title: &str,
+    authors: &[Name],
+    site: Option<&str>,
+    published: Option<PublishedDate>,
+    url: &Url,
+    accessed: DateTime<Utc>,
+) -> String {
+    let names: Vec<String> = authors.iter().map(Name::with_initials).collect();
+    let author_part = match names.as_slice() {
+        [] => None,
+        [only] => Some(only.clone()),
+        [rest @ .., last] => Some(format!("{}, & {last}", rest.join(", "))),
+    };
+    let date_part = match published {
+        Some(PublishedDate::Day(date)) => format!("({})", date.format("%Y, %B %-d")),
+        Some(PublishedDate::Year(year)) => format!("({year})"),
+        None => "(n.d.)".to_owned(),
+    };
+
+    let mut citation = match author_part {
+        Some(authors) => format!("{authors} {date_part}. {title}."),
+        None => format!("{title}. {date_part}."),
+    };
+    if let Some(site) = site {
+        citation.push_str(&format!(" {site}."));
+    }
+    if published.is_none() {
+        citation.push_str(&format!(
+            " Retrieved {}, from",
+            accessed.format("%B %-d, %Y")
+        ));
+    }
+    citation.push_str(&format!(" {url}"));
+    citation
+}
+
+fn mla(
+    title: &str,
+    authors: &[Name],
+    site: Option<&str>,
+    published: Option<PublishedDate>,
+    url: &Url,
+    accessed: DateTime<Utc>,
+) -> String {
+    let author_part = match authors {
+        [] => None,
+        [only] => Some(only.inverted()),
+        [first, second] => Some(format!("{}, and {}", first.inverted(), second.natural())),
+        [first, ..] => Some(format!("{}, et al", first.inverted())),
+    };
+
+    let mut citation = String::new();
+    if let Some(authors) = author_part {
+        citation.push_str(&format!("{}. ", authors.trim_end_matches('.')));
+    }
+    citation.push_str(&format!("\u{201c}{title}.\u{201d}"));
+    if let Some(site) = site {
+        citation.push_str(&format!(" {site},"));
+    }
+    match published {
+        Some(PublishedDate::Day(date)) => citation.push_str(&format!(" {},", mla_date(date))),
+        Some(PublishedDate::Year(year)) => citation.push_str(&format!(" {year},")),
+        None => {}
+    }
+    let url = url.as_str();
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    citation.push_str(&format!(
+        " {url}. Accessed {}.",
+        mla_date(accessed.date_naive())
+    ));
+    citation
+}
+
+/// MLA abbreviates month names longer than four letters: "14 Sept. 2024".
+fn mla_date(date: NaiveDate) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan.", "Feb.", "Mar.", "Apr.", "May", "June", "July", "Aug.", "Sept.", "Oct.", "Nov.",
+        "Dec.",
+    ];
+    format!(
+        "{} {} {}",
+        date.day(),
+        MONTHS[date.month0() as usize],
+        date.year()
+    )
+}
+
+fn bibtex_key_part(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn escape_bibtex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '&' | '%' | '$' | '#' | '_' | '{' | '}') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,112 @@
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::network::header_pairs;
+use crate::request_body::{apply_headers, parse_method};
+use crate::{BrowserCore, BrowserError, RequestKind};
+
+/// Credentials attached to a composed request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequestAuth {
+    #[default]
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer(String),
+}
+
+/// Arbitrary HTTP request built in the developer tools.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposedRequest {
+    pub method: String,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub auth: RequestAuth,
+}
+
+impl ComposedRequest {
+    pub fn get(url: Url) -> Self {
+        Self {
+            method: "GET".to_owned(),
+            url,
+            headers: Vec::new(),
+            body: String::new(),
+            auth: RequestAuth::None,
+        }
+    }
+}
+
+/// Response to a composed request, kept as close to the wire as possible.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawResponse {
+    /// Final URL after redirects.
+    pub url: Url,
+    pub status: u16,
+    pub reason: Option<String>,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    /// Body decoded as UTF-8, with invalid sequences replaced.
+    pub body: String,
+    pub size: usize,
+    pub elapsed_ms: u64,
+    pub received_at: DateTime<Utc>,
+}
+
+impl RawResponse {
+    /// Value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl BrowserCore {
+    /// Sends `request` through the shared client, cookies included, and
+    /// returns the raw response regardless of its status code.
+    pub async fn send_request(
+        &self,
+        request: ComposedRequest,
+    ) -> Result<RawResponse, BrowserError> {
+        let method = parse_method(&request.method)?;
+        let builder = self
+            .client_for(&request.url, false)
+            .request(method, request.url.clone());
+        let mut builder = apply_headers(builder, &request.headers)?;
+        builder = match &request.auth {
+            RequestAuth::None => builder,
+            RequestAuth::Basic { username, password } => builder.basic_auth(
+                username,
+                Some(password).filter(|password| !password.is_empty()),
+            ),
+            RequestAuth::Bearer(token) => builder.bearer_auth(token),
+        };
+        if !request.body.is_empty() {
+            builder = builder.body(request.body);
+        }
+
+        let started = Instant::now();
+        let response = self
+            .send_logged(builder, RequestKind::Composer, None)
+            .await?;
+
+        Ok(RawResponse {
+            url: response.url,
+            status: response.status.as_u16(),
+            reason: response.status.canonical_reason().map(ToOwned::to_owned),
+            version: format!("{:?}", response.version),
+            headers: header_pairs(&response.headers),
+            body: String::from_utf8_lossy(&response.body).into_owned(),
+            size: response.body.len(),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            received_at: Utc::now(),
+        })
+    }
+}
@@ -0,0 +1,127 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+use crate::BrowserCore;
+
+/// How many requests may be in flight at once. Requests over either limit
+/// wait their turn, so restoring many tabs or a page with hundreds of
+/// subresources cannot flood one server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionLimits {
+    /// Requests to one host and port.
+    pub per_host: usize,
+    /// Requests to all hosts together.
+    pub total: usize,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            per_host: 6,
+            total: 32,
+        }
+    }
+}
+
+/// Turn a [`BackgroundGate`] hands a request, held until its body has
+/// been read.
+pub type BackgroundTurn = Pin<Box<dyn Future<Output = Box<dyn Any + Send>> + Send>>;
+
+/// Decides when each request of a background job, such as a crawl, a link
+/// check or a breach check, may go out, so the embedder can hold them back
+/// for more urgent work without holding up the job for its whole run.
+/// Install one with [`BrowserCore::set_background_gate`].
+pub trait BackgroundGate: Send + Sync {
+    /// Waits for the turn of one background request.
+    fn admit(&self) -> BackgroundTurn;
+}
+
+/// Hands out request slots within [`ConnectionLimits`].
+pub(crate) struct RequestLimiter {
+    limits: ConnectionLimits,
+    total: Arc<Semaphore>,
+    /// Slots by `host:port`, for hosts with requests running or waiting.
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+/// Slots a request holds until it is done.
+pub(crate) struct Admission {
+    _host: Option<OwnedSemaphorePermit>,
+    _total: Option<OwnedSemaphorePermit>,
+}
+
+impl Default for RequestLimiter {
+    fn default() -> Self {
+        Self::new(ConnectionLimits::default())
+    }
+}
+
+impl RequestLimiter {
+    fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            total: Arc::new(Semaphore::new(limits.total.max(1))),
+            hosts: Mutex::default(),
+            limits,
+        }
+    }
+
+    /// Waits for a slot to the host of `url` and one overall. The future
+    /// holds no lock, so it may be awaited after the state is released.
+    pub(crate) fn admit(&self, url: &Url) -> impl Future<Output = Admission> + Send + 'static {
+        let host = self.host(url);
+        let total = Arc::clone(&self.total);
+        async move {
+            // The host's slot comes first, so requests stuck behind a busy
+            // host leave the overall ones to others.
+            let host = host.acquire_owned().await.ok();
+            let total = total.acquire_owned().await.ok();
+            Admission {
+                _host: host,
+                _total: total,
+            }
+        }
+    }
+
+    fn host(&self, url: &Url) -> Arc<Semaphore> {
+        let key = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+        let mut hosts = self.hosts.lock();
+        // Running and waiting requests hold a reference to their host's
+        // slots; nobody else does.
+        hosts.retain(|_, slots| Arc::strong_count(slots) > 1);
+        let slots = hosts
+            .entry(key)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.limits.per_host.max(1))));
+        Arc::clone(slots)
+    }
+}
+
+impl BrowserCore {
+    /// Bounds the requests in flight by `limits`. Requests already running
+    /// or waiting finish under the old limits.
+    pub fn set_connection_limits(&self, limits: ConnectionLimits) {
+        self.state.write().limiter = RequestLimiter::new(limits);
+    }
+
+    pub fn connection_limits(&self) -> ConnectionLimits {
+        self.state.read().limiter.limits
+    }
+
+    /// Has every background request wait for its turn from `gate` before
+    /// taking a connection slot.
+    pub fn set_background_gate(&self, gate: impl BackgroundGate + 'static) {
+        self.state.write().background_gate = Some(Arc::new(gate));
+    }
+}
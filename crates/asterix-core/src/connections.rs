@@ -0,0 +1,158 @@
+use std::collections::{BTreeSet, HashMap};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::BrowserCore;
+
+/// How long the clients keep an idle connection in their pool. A socket
+/// seen again after a longer pause was opened anew on the same ports.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Connection a logged request went out on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionUse {
+    /// `HTTP/1.1`, `HTTP/2` and so on.
+    pub protocol: String,
+    /// Server, or proxy, the socket is connected to.
+    pub remote_addr: SocketAddr,
+    /// Whether an earlier request had already opened the socket.
+    pub reused: bool,
+}
+
+/// How the connection pool served one host since the log was cleared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostConnections {
+    pub host: String,
+    /// Connections opened to the host.
+    pub opened: u64,
+    /// Connections that may still be open, used within the pool's idle
+    /// timeout.
+    pub open: usize,
+    pub requests: u64,
+    /// Requests sent on a connection that was already open.
+    pub reused: u64,
+    /// Protocols spoken with the host, such as `HTTP/2`.
+    pub protocols: Vec<String>,
+}
+
+impl HostConnections {
+    /// Share of requests that found a connection already open, from 0 to 1.
+    pub fn reuse_ratio(&self) -> f32 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.reused as f32 / self.requests as f32
+        }
+    }
+}
+
+/// Socket a response came back on, as `(local, remote)` addresses.
+pub(crate) type SocketPair = (SocketAddr, SocketAddr);
+
+struct SocketRecord {
+    host: String,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+struct HostTally {
+    opened: u64,
+    requests: u64,
+    reused: u64,
+    protocols: BTreeSet<String>,
+}
+
+/// Sockets the clients' pools have handed out, told apart by their
+/// addresses since the pool itself is not observable.
+#[derive(Default)]
+pub(crate) struct ConnectionTracker {
+    sockets: HashMap<SocketPair, SocketRecord>,
+    hosts: HashMap<String, HostTally>,
+}
+
+impl ConnectionTracker {
+    /// Notes a request for `host` answered over `socket` with `protocol`.
+    pub(crate) fn record(
+        &mut self,
+        socket: SocketPair,
+        host: &str,
+        protocol: &str,
+    ) -> ConnectionUse {
+        let now = Instant::now();
+        self.sockets
+            .retain(|_, record| now.duration_since(record.last_used) <= POOL_IDLE_TIMEOUT);
+        let reused = self.sockets.contains_key(&socket);
+        self.sockets.insert(
+            socket,
+            SocketRecord {
+                host: host.to_owned(),
+                last_used: now,
+            },
+        );
+        let tally = self.hosts.entry(host.to_owned()).or_default();
+        tally.requests += 1;
+        if reused {
+            tally.reused += 1;
+        } else {
+            tally.opened += 1;
+        }
+        tally.protocols.insert(protocol.to_owned());
+        ConnectionUse {
+            protocol: protocol.to_owned(),
+            remote_addr: socket.1,
+            reused,
+        }
+    }
+
+    /// Forgets the tallies; sockets still open are recognised when reused.
+    pub(crate) fn clear(&mut self) {
+        self.hosts.clear();
+    }
+
+    fn stats(&self) -> Vec<HostConnections> {
+        let now = Instant::now();
+        let mut stats: Vec<HostConnections> = self
+            .hosts
+            .iter()
+            .map(|(host, tally)| HostConnections {
+                host: host.clone(),
+                opened: tally.opened,
+                open: self
+                    .sockets
+                    .values()
+                    .filter(|record| {
+                        record.host == *host
+                            && now.duration_since(record.last_used) <= POOL_IDLE_TIMEOUT
+                    })
+                    .count(),
+                requests: tally.requests,
+                reused: tally.reused,
+                protocols: tally.protocols.iter().cloned().collect(),
+            })
+            .collect();
+        stats.sort_by(|a, b| b.requests.cmp(&a.requests).then(a.host.cmp(&b.host)));
+        stats
+    }
+}
+
+/// Label of an HTTP version as shown in the network log.
+pub(crate) fn protocol_label(version: reqwest::Version) -> &'static str {
+    match version {
+        reqwest::Version::HTTP_09 => "HTTP/0.9",
+        reqwest::Version::HTTP_10 => "HTTP/1.0",
+        reqwest::Version::HTTP_11 => "HTTP/1.1",
+        reqwest::Version::HTTP_2 => "HTTP/2",
+        reqwest::Version::HTTP_3 => "HTTP/3",
+        _ => "HTTP",
+    }
+}
+
+impl BrowserCore {
+    /// Connection reuse per host since the network log was last cleared,
+    /// busiest host first.
+    pub fn connection_stats(&self) -> Vec<HostConnections> {
+        self.state.read().network.connections.stats()
+    }
+}
@@ -0,0 +1,237 @@
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BlockRule, BrowserCore, PageResponse};
+
+/// A consent-management platform whose cookie banner can be answered
+/// without running its script.
+#[derive(Debug)]
+pub struct ConsentPlatform {
+    pub name: &'static str,
+    /// Substrings of the page source showing the platform is in use.
+    markers: &'static [&'static str],
+    /// Cookies recording that only essential cookies were accepted.
+    reject_cookies: &'static [&'static str],
+    /// Elements making up the banner and its overlay.
+    banner_selectors: &'static [&'static str],
+}
+
+/// Platforms recognised when rejecting cookie banners.
+pub const CONSENT_PLATFORMS: &[ConsentPlatform] = &[
+    ConsentPlatform {
+        name: "OneTrust",
+        markers: &["cdn.cookielaw.org", "onetrust-consent-sdk", "optanon-alert-box"],
+        reject_cookies: &[
+            "OptanonAlertBoxClosed=2024-01-01T00:00:00.000Z",
+            "OptanonConsent=isGpcEnabled=1&groups=C0001%3A1%2CC0002%3A0%2CC0003%3A0%2CC0004%3A0%2CC0005%3A0",
+        ],
+        banner_selectors: &[
+            "#onetrust-consent-sdk",
+            "#onetrust-banner-sdk",
+            ".optanon-alert-box-wrapper",
+        ],
+    },
+    ConsentPlatform {
+        name: "Cookiebot",
+        markers: &["consent.cookiebot.com", "CybotCookiebotDialog"],
+        reject_cookies: &[
+            "CookieConsent={stamp:%27-1%27%2Cnecessary:true%2Cpreferences:false%2Cstatistics:false%2Cmarketing:false%2Cmethod:%27explicit%27%2Cver:1}",
+        ],
+        banner_selectors: &["#CybotCookiebotDialog", "#CybotCookiebotDialogBodyUnderlay"],
+    },
+    ConsentPlatform {
+        name: "Complianz",
+        markers: &["cmplz-cookiebanner"],
+        reject_cookies: &[
+            "cmplz_banner-status=dismissed",
+            "cmplz_preferences=deny",
+            "cmplz_statistics=deny",
+            "cmplz_marketing=deny",
+        ],
+        banner_selectors: &["#cmplz-cookiebanner-container", ".cmplz-cookiebanner"],
+    },
+    ConsentPlatform {
+        name: "CookieYes",
+        markers: &["cdn-cookieyes.com", "cky-consent-container"],
+        reject_cookies: &[
+            "cookieyes-consent=consent:no,action:yes,necessary:yes,functional:no,analytics:no,performance:no,advertisement:no",
+        ],
+        banner_selectors: &[".cky-consent-container", ".cky-overlay"],
+    },
+    ConsentPlatform {
+        name: "GDPR Cookie Consent",
+        markers: &["cookie-law-info-bar"],
+        reject_cookies: &[
+            "viewed_cookie_policy=no",
+            "cookielawinfo-checkbox-necessary=yes",
+            "cookielawinfo-checkbox-analytics=no",
+            "cookielawinfo-checkbox-advertisement=no",
+        ],
+        banner_selectors: &["#cookie-law-info-bar", ".cli-modal-backdrop"],
+    },
+    ConsentPlatform {
+        name: "Osano Cookie Consent",
+        markers: &["cookieconsent.min.js", "cc-window"],
+        reject_cookies: &["cookieconsent_status=deny"],
+        banner_selectors: &[".cc-window", ".cc-revoke"],
+    },
+    ConsentPlatform {
+        name: "TrustArc",
+        markers: &["consent.trustarc.com", "truste-consent-track"],
+        reject_cookies: &["notice_gdpr_prefs=0:", "notice_preferences=0:"],
+        banner_selectors: &["#truste-consent-track", "#consent_blackbar", ".truste_overlay"],
+    },
+    ConsentPlatform {
+        name: "Didomi",
+        markers: &["sdk.privacy-center.org", "didomi-host"],
+        reject_cookies: &[],
+        banner_selectors: &["#didomi-host", "#didomi-popup"],
+    },
+    ConsentPlatform {
+        name: "Quantcast Choice",
+        markers: &["cmp.quantcast.com", "qc-cmp2-container"],
+        reject_cookies: &[],
+        banner_selectors: &["#qc-cmp2-container", ".qc-cmp2-container"],
+    },
+    ConsentPlatform {
+        name: "Usercentrics",
+        markers: &["app.usercentrics.eu", "usercentrics-root"],
+        reject_cookies: &[],
+        banner_selectors: &["#usercentrics-root", "#usercentrics-cmp-ui"],
+    },
+    ConsentPlatform {
+        name: "Sourcepoint",
+        markers: &["sp_message_container"],
+        reject_cookies: &[],
+        banner_selectors: &["[id^=\"sp_message_container\"]"],
+    },
+];
+
+/// Where cookie banners are rejected automatically.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentSettings {
+    /// Sites, with their subdomains, whose banners are left alone.
+    pub disabled_sites: Vec<String>,
+}
+
+impl ConsentSettings {
+    pub fn applies_to(&self, url: &Url) -> bool {
+        !self
+            .disabled_sites
+            .iter()
+            .any(|site| BlockRule::Domain(site.clone()).matches(url))
+    }
+}
+
+impl BrowserCore {
+    /// Turns automatic rejection of cookie banners on with `settings`, or
+    /// off with `None`.
+    pub fn set_consent_handling(&self, settings: Option<ConsentSettings>) {
+        self.state.write().consent = settings;
+    }
+
+    pub fn consent_handling(&self) -> Option<ConsentSettings> {
+        self.state.read().consent.clone()
+    }
+
+    /// Answers the cookie banners on `page`: stores each platform's
+    /// reject-all cookies for the site, unless the user already chose, and
+    /// strips the banner from the body. `private` pages only get the latter
+    /// as they never share the cookie jar.
+    pub(crate) fn reject_consent(&self, page: &mut PageResponse, private: bool) {
+        let platforms = self.answer_consent(page, private);
+        strip_banners(page, &platforms);
+    }
+
+    /// The cookie half of [`reject_consent`](Self::reject_consent),
+    /// returning the platforms whose banners are left to strip.
+    pub(crate) fn answer_consent(
+        &self,
+        page: &mut PageResponse,
+        private: bool,
+    ) -> Vec<&'static ConsentPlatform> {
+        let applies = self
+            .state
+            .read()
+            .consent
+            .as_ref()
+            .is_some_and(|settings| settings.applies_to(&page.url));
+        let is_html = page
+            .mime_type
+            .as_deref()
+            .is_none_or(|mime| mime.starts_with("text/html"));
+        if !applies || !is_html {
+            return Vec::new();
+        }
+        let platforms: Vec<&'static ConsentPlatform> = CONSENT_PLATFORMS
+            .iter()
+            .filter(|platform| {
+                platform
+                    .markers
+                    .iter()
+                    .any(|marker| page.body.contains(marker))
+            })
+            .collect();
+        if platforms.is_empty() {
+            return platforms;
+        }
+
+        if !private {
+            let existing: Vec<String> = self
+                .cookie_jar
+                .cookies_for(&page.url)
+                .into_iter()
+                .map(|cookie| cookie.name)
+                .collect();
+            for platform in &platforms {
+                for cookie in platform.reject_cookies {
+                    let name = cookie.split_once('=').map_or(*cookie, |(name, _)| name);
+                    if !existing.iter().any(|existing| existing == name) {
+                        self.cookie_jar
+                            .add_cookie_str(&format!("{cookie}; Path=/"), &page.url);
+                    }
+                }
+            }
+        }
+
+        page.consent_rejected = platforms
+            .iter()
+            .map(|platform| platform.name.to_owned())
+            .collect();
+        platforms
+    }
+}
+
+/// Removes the banners of `platforms` from the body of `page`.
+pub(crate) fn strip_banners(page: &mut PageResponse, platforms: &[&ConsentPlatform]) {
+    if platforms.is_empty() {
+        return;
+    }
+    if let Some(body) = without_banners(&page.body, platforms) {
+        page.body = body.into();
+    }
+}
+
+/// `body` with the banners of `platforms` removed, or `None` if none of
+/// them are in the markup.
+fn without_banners(body: &str, platforms: &[&ConsentPlatform]) -> Option<String> {
+    let mut document = Html::parse_document(body);
+    let mut banners = Vec::new();
+    for selector in platforms
+        .iter()
+        .flat_map(|platform| platform.banner_selectors)
+        .filter_map(|selector| Selector::parse(selector).ok())
+    {
+        banners.extend(document.select(&selector).map(|element| element.id()));
+    }
+    if banners.is_empty() {
+        return None;
+    }
+    for id in banners {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    Some(document.html())
+}
@@ -0,0 +1,209 @@
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use crate::{document_links, BrowserCore, NetworkEntry, RequestKind, TabId};
+
+/// Most elements listed for a single `$('selector')` query.
+const MAX_QUERY_RESULTS: usize = 200;
+
+/// Longest text excerpt shown next to a matched element.
+const TEXT_EXCERPT_CHARS: usize = 60;
+
+/// Summary printed by the `help` command.
+pub const CONSOLE_HELP: &str = "\
+$('selector')  list elements matching a CSS selector
+headers        response headers of the page
+cookies        cookies sent with requests to the page
+timing         how long the page took to load
+links          links found in the page
+clear          clear the console";
+
+/// Built-in command understood by the developer tools console.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    Query(String),
+    Headers,
+    Cookies,
+    Timing,
+    Links,
+    Help,
+    /// Handled by the console itself; evaluates to nothing.
+    Clear,
+}
+
+impl ConsoleCommand {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim().trim_end_matches(';').trim();
+        if let Some(argument) = input
+            .strip_prefix("$(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return unquote(argument.trim())
+                .map(|selector| ConsoleCommand::Query(selector.to_owned()))
+                .ok_or_else(|| "usage: $('selector')".to_owned());
+        }
+        match input {
+            "headers" => Ok(ConsoleCommand::Headers),
+            "cookies" => Ok(ConsoleCommand::Cookies),
+            "timing" => Ok(ConsoleCommand::Timing),
+            "links" => Ok(ConsoleCommand::Links),
+            "help" => Ok(ConsoleCommand::Help),
+            "clear" => Ok(ConsoleCommand::Clear),
+            _ => Err(format!(
+                "unknown command `{input}`; JavaScript is not supported yet, type help for the built-in commands"
+            )),
+        }
+    }
+}
+
+impl BrowserCore {
+    /// Runs a console command against the page `url` shown in `tab`, whose
+    /// source is `document`. Returns the lines to print.
+    pub fn run_console(
+        &self,
+        tab: TabId,
+        url: &Url,
+        document: &str,
+        command: &ConsoleCommand,
+    ) -> Result<Vec<String>, String> {
+        match command {
+            ConsoleCommand::Query(selector) => query(document, selector),
+            ConsoleCommand::Headers => {
+                let entry = self.page_entry(tab, url)?;
+                let mut lines = vec![match entry.status {
+                    Some(status) => format!("{} {} -> {status}", entry.method, entry.url),
+                    None => format!("{} {} (no response)", entry.method, entry.url),
+                }];
+                lines.extend(
+                    entry
+                        .response_headers
+                        .iter()
+                        .map(|(name, value)| format!("{name}: {value}")),
+                );
+                Ok(lines)
+            }
+            ConsoleCommand::Cookies => {
+                let cookies = self.cookie_jar.cookies_for(url);
+                if cookies.is_empty() {
+                    return Ok(vec!["no cookies for this page".to_owned()]);
+                }
+                Ok(cookies
+                    .into_iter()
+                    .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                    .collect())
+            }
+            ConsoleCommand::Timing => {
+                let entry = self.page_entry(tab, url)?;
+                let mut lines = vec![format!(
+                    "started   {}",
+                    entry.started_at.format("%H:%M:%S%.3f")
+                )];
+                for hop in &entry.redirects {
+                    lines.push(format!(
+                        "redirect  {} {} -> {} ({} ms)",
+                        hop.status, hop.url, hop.location, hop.duration_ms
+                    ));
+                }
+                if let Some(timings) = entry.timings {
+                    let phases = [
+                        ("dns", timings.dns_ms),
+                        ("connect", timings.connect_ms),
+                        ("tls", timings.tls_ms),
+                    ];
+                    for (name, ms) in phases {
+                        if let Some(ms) = ms {
+                            lines.push(format!("{name:<9} {ms} ms"));
+                        }
+                    }
+                    lines.push(format!("ttfb      {} ms", timings.ttfb_ms));
+                    lines.push(format!("waiting   {} ms", timings.waiting_ms));
+                    lines.push(format!("download  {} ms", timings.download_ms));
+                }
+                if let Some(duration) = entry.duration_ms {
+                    lines.push(format!("total     {duration} ms"));
+                }
+                lines.push(format!("size      {} bytes", entry.size));
+                Ok(lines)
+            }
+            ConsoleCommand::Links => {
+                let links = document_links(&Html::parse_document(document), url);
+                let mut lines = vec![format!("{} links", links.len())];
+                lines.extend(
+                    links
+                        .into_iter()
+                        .map(|link| format!("{} -> {}", link.text, link.url)),
+                );
+                Ok(lines)
+            }
+            ConsoleCommand::Help => Ok(CONSOLE_HELP.lines().map(ToOwned::to_owned).collect()),
+            ConsoleCommand::Clear => Ok(Vec::new()),
+        }
+    }
+
+    /// The network log entry that fetched the page, either as a navigation
+    /// in `tab` or as a preload or prefetch the navigation reused.
+    fn page_entry(&self, tab: TabId, url: &Url) -> Result<NetworkEntry, String> {
+        self.network_log()
+            .into_iter()
+            .rev()
+            .find(|entry| {
+                entry.url == *url
+                    && match entry.kind {
+                        RequestKind::Document => entry.tab == Some(tab),
+                        RequestKind::Preload | RequestKind::Prefetch => true,
+                        _ => false,
+                    }
+            })
+            .ok_or_else(|| "the request for this page is no longer in the network log".to_owned())
+    }
+}
+
+fn query(document: &str, selector: &str) -> Result<Vec<String>, String> {
+    let selector = Selector::parse(selector).map_err(|err| format!("invalid selector: {err}"))?;
+    let document = Html::parse_document(document);
+    let matches: Vec<ElementRef> = document.select(&selector).collect();
+    let mut lines = vec![match matches.len() {
+        1 => "1 element".to_owned(),
+        count => format!("{count} elements"),
+    }];
+    lines.extend(matches.iter().take(MAX_QUERY_RESULTS).map(describe_element));
+    if matches.len() > MAX_QUERY_RESULTS {
+        lines.push(format!(
+            "... {} more not shown",
+            matches.len() - MAX_QUERY_RESULTS
+        ));
+    }
+    Ok(lines)
+}
+
+/// Renders an element as `tag#id.class "text"`.
+fn describe_element(element: &ElementRef) -> String {
+    let value = element.value();
+    let mut label = value.name().to_owned();
+    if let Some(id) = value.id() {
+        label.push('#');
+        label.push_str(id);
+    }
+    for class in value.classes() {
+        label.push('.');
+        label.push_str(class);
+    }
+    let text = element.text().collect::<Vec<_>>().join(" ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return label;
+    }
+    let mut excerpt: String = text.chars().take(TEXT_EXCERPT_CHARS).collect();
+    if text.chars().count() > TEXT_EXCERPT_CHARS {
+        excerpt.push('\u{2026}');
+    }
+    format!("{label} \"{excerpt}\"")
+}
+
+fn unquote(argument: &str) -> Option<&str> {
+    ['\'', '"', '`'].into_iter().find_map(|quote| {
+        argument
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+    })
+}
@@ -0,0 +1,269 @@
+use std::io::{self, Read};
+
+use bytes::Bytes;
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH};
+use ruzstd::decoding::StreamingDecoder;
+use serde::{Deserialize, Serialize};
+
+use crate::network::LoggedResponse;
+use crate::{BrowserCore, BrowserError};
+
+/// Largest body a response may decode to, so a small compressed body
+/// cannot expand into all of memory.
+const MAX_DECODED_BYTES: usize = 256 * 1024 * 1024;
+
+/// Compression a response body may be sent with, named as in
+/// `Accept-Encoding` and `Content-Encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentCoding {
+    Gzip,
+    Deflate,
+    #[serde(rename = "br")]
+    Brotli,
+    Zstd,
+}
+
+impl ContentCoding {
+    pub const ALL: [Self; 4] = [Self::Gzip, Self::Deflate, Self::Brotli, Self::Zstd];
+
+    /// Codings advertised unless the profile says otherwise: every one this
+    /// build decodes.
+    pub const DEFAULT: [Self; 4] = Self::ALL;
+
+    /// Token of the coding in HTTP headers.
+    pub fn token(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "Deflate",
+            Self::Brotli => "Brotli",
+            Self::Zstd => "Zstandard",
+        }
+    }
+
+    /// Coding named by a `Content-Encoding` token, including the legacy
+    /// `x-gzip`.
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "deflate" => Some(Self::Deflate),
+            "br" => Some(Self::Brotli),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Decodes `body`, failing once it grows past `limit` bytes.
+    fn decode(self, body: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+        // One byte past the limit tells a body that fits from one cut off.
+        let cap = limit as u64 + 1;
+        let mut decoded = Vec::with_capacity(body.len().saturating_mul(4).min(limit));
+        let read = match self {
+            Self::Gzip => MultiGzDecoder::new(body)
+                .take(cap)
+                .read_to_end(&mut decoded),
+            // `deflate` is meant to be zlib-wrapped, but some servers send
+            // the bare stream.
+            Self::Deflate => ZlibDecoder::new(body)
+                .take(cap)
+                .read_to_end(&mut decoded)
+                .or_else(|_| {
+                    decoded.clear();
+                    DeflateDecoder::new(body)
+                        .take(cap)
+                        .read_to_end(&mut decoded)
+                }),
+            Self::Brotli => brotli_decompressor::Decompressor::new(body, 4096)
+                .take(cap)
+                .read_to_end(&mut decoded),
+            Self::Zstd => decode_zstd(body, &mut decoded, cap),
+        };
+        read.map_err(|err| err.to_string())?;
+        if decoded.len() > limit {
+            return Err(format!("decodes to more than {limit} bytes"));
+        }
+        Ok(decoded)
+    }
+}
+
+/// Decodes every Zstandard frame of `body` into `decoded`, up to `cap`
+/// bytes in all; encoders may split a body into several frames sent back
+/// to back.
+fn decode_zstd(mut body: &[u8], decoded: &mut Vec<u8>, cap: u64) -> io::Result<usize> {
+    let start = decoded.len();
+    while !body.is_empty() {
+        let left = cap.saturating_sub((decoded.len() - start) as u64);
+        if left == 0 {
+            break;
+        }
+        let frame = StreamingDecoder::new(&mut body)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        frame.take(left).read_to_end(decoded)?;
+    }
+    Ok(decoded.len() - start)
+}
+
+/// How a response body that arrived compressed was decoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentDecoding {
+    /// `Content-Encoding` the server sent, such as `br` or `gzip, br`.
+    pub encoding: String,
+    /// Size of the body as received.
+    pub encoded_size: usize,
+    /// Size of the body once decoded.
+    pub decoded_size: usize,
+}
+
+impl ContentDecoding {
+    /// Decoded size per byte received, 1.0 for a body that did not shrink.
+    pub fn ratio(&self) -> f64 {
+        if self.encoded_size == 0 {
+            return 1.0;
+        }
+        self.decoded_size as f64 / self.encoded_size as f64
+    }
+}
+
+/// `Accept-Encoding` offering `codings`; `identity` when there are none.
+pub(crate) fn accept_encoding(codings: &[ContentCoding]) -> HeaderValue {
+    let tokens: Vec<&str> = codings.iter().map(|coding| coding.token()).collect();
+    if tokens.is_empty() {
+        return HeaderValue::from_static("identity");
+    }
+    HeaderValue::from_str(&tokens.join(", ")).unwrap_or(HeaderValue::from_static("identity"))
+}
+
+/// Decodes the body of `response` as its `Content-Encoding` says,
+/// dropping the header along with `Content-Length` so the response reads
+/// as if it had been sent plain.
+pub(crate) fn decode_response(
+    mut response: LoggedResponse,
+) -> Result<LoggedResponse, BrowserError> {
+    let Some(encoding) = content_encoding(&response.headers) else {
+        return Ok(response);
+    };
+    // Bodiless responses, such as to HEAD requests, keep the header.
+    if response.body.is_empty() {
+        return Ok(response);
+    }
+    let encoded_size = response.body.len();
+    let mut body = response.body.clone();
+    // Codings are listed in the order they were applied.
+    for token in encoding.rsplit(", ") {
+        let coding = ContentCoding::from_token(token).ok_or_else(|| {
+            BrowserError::ContentDecoding(format!("unknown content coding {token:?}"))
+        })?;
+        body = coding
+            .decode(&body, MAX_DECODED_BYTES)
+            .map(Bytes::from)
+            .map_err(|err| BrowserError::ContentDecoding(format!("{token}: {err}")))?;
+    }
+    response.headers.remove(CONTENT_ENCODING);
+    response.headers.remove(CONTENT_LENGTH);
+    response.decoding = Some(ContentDecoding {
+        encoding,
+        encoded_size,
+        decoded_size: body.len(),
+    });
+    response.body = body;
+    Ok(response)
+}
+
+/// Every `Content-Encoding` of `headers` joined in order, unless the body
+/// was sent as it is.
+fn content_encoding(headers: &HeaderMap) -> Option<String> {
+    let tokens: Vec<String> = headers
+        .get_all(CONTENT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|token| token.trim().to_ascii_lowercase())
+        .filter(|token| !token.is_empty() && token != "identity")
+        .collect();
+    (!tokens.is_empty()).then(|| tokens.join(", "))
+}
+
+impl BrowserCore {
+    /// Offers `codings` in the `Accept-Encoding` of requests from now on.
+    /// With none, servers are asked for bodies sent as they are.
+    pub fn set_content_codings(&self, codings: Vec<ContentCoding>) {
+        self.state.write().content_codings = codings;
+    }
+
+    pub fn content_codings(&self) -> Vec<ContentCoding> {
+        self.state.read().content_codings.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+    use super::*;
+
+    #[test]
+    fn decodes_zstd_frames_back_to_back() {
+        let mut body = compress_to_vec(&b"hello, "[..], CompressionLevel::Fastest);
+        body.extend(compress_to_vec(&b"world"[..], CompressionLevel::Fastest));
+        assert_eq!(
+            ContentCoding::Zstd
+                .decode(&body, MAX_DECODED_BYTES)
+                .unwrap(),
+            b"hello, world"
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_zstd() {
+        assert!(ContentCoding::Zstd
+            .decode(b"not zstd at all", MAX_DECODED_BYTES)
+            .is_err());
+        let body = compress_to_vec(&b"truncated body"[..], CompressionLevel::Fastest);
+        assert!(ContentCoding::Zstd
+            .decode(&body[..body.len() - 3], MAX_DECODED_BYTES)
+            .is_err());
+    }
+
+    #[test]
+    fn stops_bodies_that_decode_past_the_limit() {
+        let zeros = vec![0u8; 64 * 1024];
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        std::io::Write::write_all(&mut gzip, &zeros).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let mut zstd = compress_to_vec(zeros.as_slice(), CompressionLevel::Fastest);
+        zstd.extend(compress_to_vec(zeros.as_slice(), CompressionLevel::Fastest));
+
+        for (coding, body) in [(ContentCoding::Gzip, &gzip), (ContentCoding::Zstd, &zstd)] {
+            let err = coding.decode(body, 1024).unwrap_err();
+            assert!(err.contains("more than 1024 bytes"), "{coding:?}: {err}");
+        }
+        assert_eq!(
+            ContentCoding::Gzip.decode(&gzip, zeros.len()).unwrap(),
+            zeros
+        );
+        let both = ContentCoding::Zstd.decode(&zstd, 2 * zeros.len()).unwrap();
+        assert_eq!(both.len(), 2 * zeros.len());
+        assert!(ContentCoding::Zstd
+            .decode(&zstd, 2 * zeros.len() - 1)
+            .is_err());
+    }
+
+    #[test]
+    fn advertises_every_coding_by_default() {
+        assert_eq!(
+            accept_encoding(&ContentCoding::DEFAULT),
+            "gzip, deflate, br, zstd"
+        );
+        assert_eq!(accept_encoding(&[]), "identity");
+    }
+}
@@ -0,0 +1,36 @@
+use asterix_storage::{StorageError, StoredCookie};
+use url::Url;
+
+use crate::BrowserCore;
+
+impl BrowserCore {
+    /// Every cookie in the jar, ordered by domain and name.
+    pub fn cookies(&self) -> Vec<StoredCookie> {
+        self.cookie_jar.cookies()
+    }
+
+    /// Cookies that a request to `url` would carry.
+    pub fn cookies_for(&self, url: &Url) -> Vec<StoredCookie> {
+        self.cookie_jar.cookies_for(url)
+    }
+
+    /// Deletes one cookie, returning whether it was in the jar.
+    pub fn remove_cookie(&self, cookie: &StoredCookie) -> bool {
+        self.cookie_jar.remove(cookie)
+    }
+
+    /// Deletes the cookies set for `domain` and its subdomains, returning
+    /// how many were removed.
+    pub fn remove_cookies_for(&self, domain: &str) -> usize {
+        self.cookie_jar.remove_domain(domain)
+    }
+
+    pub fn clear_cookies(&self) {
+        self.cookie_jar.clear();
+    }
+
+    /// Writes the jar to its file; a no-op for an in-memory jar.
+    pub fn save_cookies(&self) -> Result<(), StorageError> {
+        self.cookie_jar.save()
+    }
+}
@@ -0,0 +1,72 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use anyhow::Context;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::{BrowserCore, BrowserError};
+
+/// Threads for CPU-heavy work such as parsing pages and decoding images,
+/// kept apart from the async runtime so long jobs never hold up network
+/// I/O. The default shares rayon's global pool.
+#[derive(Clone, Default)]
+pub struct CpuPool(Option<Arc<ThreadPool>>);
+
+impl CpuPool {
+    /// Dedicated pool of `threads` threads, one per CPU when 0, with stacks
+    /// of `stack_size` bytes or the platform default.
+    pub fn new(threads: usize, stack_size: Option<usize>) -> anyhow::Result<Self> {
+        let mut builder = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("asterix-cpu-{index}"));
+        if let Some(stack_size) = stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        let pool = builder.build().context("failed to start the CPU pool")?;
+        Ok(Self(Some(Arc::new(pool))))
+    }
+
+    /// Threads the pool runs work on.
+    pub fn threads(&self) -> usize {
+        match &self.0 {
+            Some(pool) => pool.current_num_threads(),
+            None => rayon::current_num_threads(),
+        }
+    }
+
+    /// Runs `work` on the pool and waits for its result without blocking
+    /// the calling task. Work that panics fails with
+    /// [`BrowserError::Cancelled`], as a lost blocking task would.
+    pub(crate) async fn run<T, F>(&self, work: F) -> Result<T, BrowserError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = move || match panic::catch_unwind(AssertUnwindSafe(work)) {
+            Ok(value) => {
+                let _ = tx.send(value);
+            }
+            Err(_) => warn!("work on the CPU pool panicked"),
+        };
+        match &self.0 {
+            Some(pool) => pool.spawn(job),
+            None => rayon::spawn(job),
+        }
+        rx.await.map_err(|_| BrowserError::Cancelled)
+    }
+}
+
+impl BrowserCore {
+    /// Moves parsing and image decoding onto `pool`.
+    pub fn with_cpu_pool(mut self, pool: CpuPool) -> Self {
+        self.cpu = pool;
+        self
+    }
+
+    pub fn cpu_pool(&self) -> &CpuPool {
+        &self.cpu
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use url::Url;
+
+use crate::charset::decode_body;
+use crate::{document_links, BrowserCore, RequestKind, RobotsTxt};
+
+/// Product token matched against `User-agent` lines in robots.txt.
+const CRAWLER_AGENT: &str = "ASTERIX";
+
+/// Upper bound for fetching a single page, redirects included.
+const CRAWL_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Limits and filters of a crawl.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlOptions {
+    /// Links followed away from the start page; 0 fetches only the start.
+    pub max_depth: u32,
+    pub max_pages: usize,
+    /// Only follow links on the start page's scheme, host and port.
+    pub same_origin: bool,
+    /// When non-empty, only URLs containing one of these are fetched.
+    pub include: Vec<String>,
+    /// URLs containing any of these are never fetched.
+    pub exclude: Vec<String>,
+    /// Pause between two requests; a longer robots.txt `Crawl-delay` wins.
+    pub delay: Duration,
+    pub respect_robots: bool,
+    /// Directory the fetched pages are mirrored to, if any.
+    pub output_dir: Option<PathBuf>,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            max_pages: 100,
+            same_origin: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            delay: Duration::from_secs(1),
+            respect_robots: true,
+            output_dir: None,
+        }
+    }
+}
+
+impl CrawlOptions {
+    /// Whether `url`, found while crawling from `start`, passes the scope
+    /// and URL filters.
+    pub fn in_scope(&self, start: &Url, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        if self.same_origin && url.origin() != start.origin() {
+            return false;
+        }
+        let url = url.as_str();
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| url.contains(pattern.as_str()));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| url.contains(pattern.as_str()))
+    }
+}
+
+/// One page visited by a crawl.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawledPage {
+    pub url: Url,
+    pub depth: u32,
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    /// In-scope links found on the page, whether or not they were fetched.
+    pub links: Vec<Url>,
+    /// Where the page was mirrored to.
+    pub saved_to: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+/// Totals reported when a crawl ends.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrawlSummary {
+    pub fetched: usize,
+    pub failed: usize,
+    /// URLs left out because robots.txt disallows them.
+    pub disallowed: usize,
+    /// The page limit was reached with URLs still queued.
+    pub truncated: bool,
+    /// The consumer went away before the crawl finished.
+    pub cancelled: bool,
+}
+
+impl BrowserCore {
+    /// Crawls breadth-first from `start`, one request at a time, sending each
+    /// visited page to `progress`. Dropping the receiving end stops the crawl.
+    ///
+    /// Requests go through the cookie-less client so a crawl never touches the
+    /// session's cookie jar.
+    pub async fn crawl(
+        &self,
+        start: Url,
+        options: CrawlOptions,
+        progress: mpsc::UnboundedSender<CrawledPage>,
+    ) -> CrawlSummary {
+        let mut summary = CrawlSummary::default();
+        let mut robots: HashMap<String, Option<RobotsTxt>> = HashMap::new();
+        let mut seen: HashSet<Url> = HashSet::from([without_fragment(&start)]);
+        let mut queue = VecDeque::from([(without_fragment(&start), 0)]);
+        let mut first = true;
+
+        while let Some((url, depth)) = queue.pop_front() {
+            if progress.is_closed() {
+                summary.cancelled = true;
+                break;
+            }
+            if summary.fetched + summary.failed >= options.max_pages {
+                summary.truncated = true;
+                break;
+            }
+
+            let mut delay = options.delay;
+            if options.respect_robots {
+                let origin = url.origin().ascii_serialization();
+                if !robots.contains_key(&origin) {
+                    let rules = self.fetch_robots(&url).await;
+                    robots.insert(origin.clone(), rules);
+                }
+                if let Some(rules) = robots.get(&origin).and_then(Option::as_ref) {
+                    let path = match url.query() {
+                        Some(query) => format!("{}?{query}", url.path()),
+                        None => url.path().to_owned(),
+                    };
+                    if !rules.is_allowed(CRAWLER_AGENT, &path) {
+                        summary.disallowed += 1;
+                        continue;
+                    }
+                    let crawl_delay = rules
+                        .group_for(CRAWLER_AGENT)
+                        .and_then(|group| group.crawl_delay)
+                        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+                    if let Some(crawl_delay) = crawl_delay {
+                        delay = delay.max(crawl_delay);
+                    }
+                }
+            }
+            if !first {
+                tokio::time::sleep(delay).await;
+            }
+            first = false;
+
+            let page = self.crawl_page(&start, url, depth, &options).await;
+            if page.error.is_some() {
+                summary.failed += 1;
+            } else {
+                summary.fetched += 1;
+            }
+            if depth < options.max_depth {
+                for link in &page.links {
+                    if seen.insert(link.clone()) {
+                        queue.push_back((link.clone(), depth + 1));
+                    }
+                }
+            }
+            if progress.send(page).is_err() {
+                summary.cancelled = true;
+                break;
+            }
+        }
+        summary
+    }
+
+    async fn fetch_robots(&self, url: &Url) -> Option<RobotsTxt> {
+        let robots_url = url.join("/robots.txt").ok()?;
+        let builder = self
+            .client_for(&robots_url, true)
+            .get(robots_url.clone())
+            .timeout(CRAWL_TIMEOUT);
+        let response = self
+            .send_logged(builder, RequestKind::Crawl, None)
+            .await
+            .ok()?;
+        if !response.status.is_success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&response.body);
+        Some(RobotsTxt::parse(&text, &robots_url))
+    }
+
+    async fn crawl_page(
+        &self,
+        start: &Url,
+        url: Url,
+        depth: u32,
+        options: &CrawlOptions,
+    ) -> CrawledPage {
+        let mut page = CrawledPage {
+            url: url.clone(),
+            depth,
+            status: None,
+            content_type: None,
+            links: Vec::new(),
+            saved_to: None,
+            error: None,
+        };
+        let builder = self.client_for(&url, true).get(url).timeout(CRAWL_TIMEOUT);
+        let response = match self.send_logged(builder, RequestKind::Crawl, None).await {
+            Ok(response) => response,
+            Err(err) => {
+                page.error = Some(err.to_string());
+                return page;
+            }
+        };
+
+        page.status = Some(response.status.as_u16());
+        page.content_type = response
+            .headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        if !response.status.is_success() {
+            page.error = Some(format!("HTTP {}", response.status));
+            return page;
+        }
+
+        let is_html = page
+            .content_type
+            .as_deref()
+            .is_none_or(|mime| mime.starts_with("text/html"));
+        if is_html {
+            let (text, _) = decode_body(&response.body, page.content_type.as_deref());
+            let document = scraper::Html::parse_document(&text);
+            let mut links: Vec<Url> = document_links(&document, &response.url)
+                .into_iter()
+                .map(|link| without_fragment(&link.url))
+                .filter(|link| options.in_scope(start, link))
+                .collect();
+            let mut unique = HashSet::new();
+            links.retain(|link| unique.insert(link.clone()));
+            page.links = links;
+        }
+
+        if let Some(dir) = &options.output_dir {
+            let path = mirror_path(dir, &response.url, is_html);
+            let written = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::write(&path, &response.body));
+            match written {
+                Ok(()) => page.saved_to = Some(path),
+                Err(err) => page.error = Some(format!("could not save {}: {err}", path.display())),
+            }
+        }
+        page
+    }
+}
+
+/// File a crawled URL is mirrored to: `dir/host/path`, with `index.html` for
+/// directory-like paths and the query folded into the file name.
+fn mirror_path(dir: &Path, url: &Url, is_html: bool) -> PathBuf {
+    let mut path = dir.join(sanitize(url.host_str().unwrap_or("local")));
+    let segments: Vec<&str> = url
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "..")
+        .collect();
+    let directory_like = url.path().ends_with('/') || segments.is_empty();
+    for segment in &segments {
+        path.push(sanitize(segment));
+    }
+    if directory_like || (is_html && path.extension().is_none()) {
+        path.push("index.html");
+    }
+    if let Some(query) = url.query() {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (stem, extension) = name.rsplit_once('.').unwrap_or((&name, ""));
+        let name = format!("{stem}_{}.{extension}", sanitize(query));
+        path.set_file_name(name.trim_end_matches('.'));
+    }
+    path
+}
+
+fn sanitize(segment: &str) -> String {
+    segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
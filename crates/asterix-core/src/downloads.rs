@@ -0,0 +1,265 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, BrowserError, PageResponse, RequestKind};
+
+/// File name used when neither the response nor the URL suggests one.
+const FALLBACK_FILE_NAME: &str = "download";
+
+/// Where a download stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DownloadStatus {
+    InProgress,
+    Completed,
+    Failed(String),
+}
+
+impl DownloadStatus {
+    pub fn label(&self) -> &str {
+        match self {
+            DownloadStatus::InProgress => "Downloading",
+            DownloadStatus::Completed => "Completed",
+            DownloadStatus::Failed(_) => "Failed",
+        }
+    }
+}
+
+/// A file saved from the web, kept after the file itself is gone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadEntry {
+    pub id: u64,
+    pub url: Url,
+    /// File the body is written to; the name is settled once the response
+    /// headers arrive.
+    pub path: PathBuf,
+    /// Bytes written, zero until the download completes.
+    pub size: u64,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub status: DownloadStatus,
+}
+
+impl DownloadEntry {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+impl BrowserCore {
+    /// Fetches `url` with the regular session and saves the body into `dir`
+    /// under a name that does not clash with existing files. The download is
+    /// listed from the start and keeps its entry when it fails.
+    pub async fn download(&self, url: Url, dir: &Path) -> Result<DownloadEntry, BrowserError> {
+        let url = self.resolve_protocol(&url).await?;
+        let id = {
+            let mut guard = self.state.write();
+            let id = guard.next_download_id;
+            guard.next_download_id += 1;
+            guard.downloads.push(DownloadEntry {
+                id,
+                path: dir.join(file_name_from_url(&url)),
+                url: url.clone(),
+                size: 0,
+                mime_type: None,
+                started_at: Utc::now(),
+                status: DownloadStatus::InProgress,
+            });
+            id
+        };
+
+        let result = self.fetch_download(&url, dir).await;
+        let mut guard = self.state.write();
+        let entry = guard.downloads.iter_mut().find(|entry| entry.id == id);
+        match (result, entry) {
+            (Ok((path, size, mime_type)), Some(entry)) => {
+                entry.path = path;
+                entry.size = size;
+                entry.mime_type = mime_type;
+                entry.status = DownloadStatus::Completed;
+                Ok(entry.clone())
+            }
+            (Err(err), Some(entry)) => {
+                entry.status = DownloadStatus::Failed(err.to_string());
+                Err(err)
+            }
+            // The list was cleared while the download ran.
+            (result, None) => result.map(|(path, size, mime_type)| DownloadEntry {
+                id,
+                url,
+                path,
+                size,
+                mime_type,
+                started_at: Utc::now(),
+                status: DownloadStatus::Completed,
+            }),
+        }
+    }
+
+    /// Saves a page that was already fetched into `dir` instead of fetching
+    /// it again, e.g. one the file-type rules route to the downloads.
+    pub fn save_download(
+        &self,
+        page: &PageResponse,
+        dir: &Path,
+    ) -> Result<DownloadEntry, BrowserError> {
+        let path = std::fs::create_dir_all(dir)
+            .and_then(|()| write_new_file(dir, &file_name_from_url(&page.url), &page.bytes))
+            .map_err(|err| BrowserError::Download(err.to_string()))?;
+        let mut guard = self.state.write();
+        let entry = DownloadEntry {
+            id: guard.next_download_id,
+            url: page.url.clone(),
+            path,
+            size: page.bytes.len() as u64,
+            mime_type: page
+                .mime_type
+                .as_deref()
+                .and_then(|value| value.split(';').next())
+                .map(|mime| mime.trim().to_owned()),
+            started_at: page.received_at,
+            status: DownloadStatus::Completed,
+        };
+        guard.next_download_id += 1;
+        guard.downloads.push(entry.clone());
+        Ok(entry)
+    }
+
+    async fn fetch_download(
+        &self,
+        url: &Url,
+        dir: &Path,
+    ) -> Result<(PathBuf, u64, Option<String>), BrowserError> {
+        let mut builder = self.client_for(url, false).get(url.clone());
+        if let Some(ua) = self.user_agent_for(None, url) {
+            builder = builder.header(reqwest::header::USER_AGENT, ua);
+        }
+        let response = self
+            .send_logged(builder, RequestKind::Download, None)
+            .await?;
+        if !response.status.is_success() {
+            return Err(BrowserError::Status(response.status.as_u16()));
+        }
+
+        let name = response
+            .headers
+            .get(reqwest::header::CONTENT_DISPOSITION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(file_name_from_disposition)
+            .unwrap_or_else(|| file_name_from_url(&response.url));
+        let mime_type = response
+            .headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|mime| mime.trim().to_owned());
+        let path = std::fs::create_dir_all(dir)
+            .and_then(|()| write_new_file(dir, &name, &response.body))
+            .map_err(|err| BrowserError::Download(err.to_string()))?;
+        Ok((path, response.body.len() as u64, mime_type))
+    }
+
+    /// Downloads, most recently started first.
+    pub fn downloads(&self) -> Vec<DownloadEntry> {
+        let mut downloads = self.state.read().downloads.clone();
+        downloads.sort_by_key(|entry| std::cmp::Reverse(entry.started_at));
+        downloads
+    }
+
+    /// Forgets a download; the file stays where it was saved.
+    pub fn remove_download(&self, id: u64) {
+        self.state.write().downloads.retain(|entry| entry.id != id);
+    }
+
+    /// Forgets every finished download, leaving those still running.
+    pub fn clear_downloads(&self) {
+        self.state
+            .write()
+            .downloads
+            .retain(|entry| entry.status == DownloadStatus::InProgress);
+    }
+
+    /// Replaces the downloads with saved ones. Downloads that were running
+    /// when they were saved count as failed, since nothing resumes them.
+    pub fn import_downloads(&self, mut entries: Vec<DownloadEntry>) {
+        for entry in &mut entries {
+            if entry.status == DownloadStatus::InProgress {
+                entry.status = DownloadStatus::Failed("interrupted".to_owned());
+            }
+        }
+        let mut guard = self.state.write();
+        guard.next_download_id = entries.iter().map(|entry| entry.id + 1).max().unwrap_or(0);
+        guard.downloads = entries;
+    }
+}
+
+/// Writes `body` to `dir/name`, numbering the name `name (1).ext` and up
+/// when a file by that name already exists.
+fn write_new_file(dir: &Path, name: &str, body: &[u8]) -> std::io::Result<PathBuf> {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    };
+    for attempt in 0.. {
+        let candidate = match (attempt, extension) {
+            (0, _) => name.to_owned(),
+            (n, Some(extension)) => format!("{stem} ({n}).{extension}"),
+            (n, None) => format!("{stem} ({n})"),
+        };
+        let path = dir.join(candidate);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                std::io::Write::write_all(&mut file, body)?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("ran out of file names")
+}
+
+/// `filename` parameter of a `Content-Disposition` header.
+fn file_name_from_disposition(header: &str) -> Option<String> {
+    header
+        .split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("filename"))
+        .map(|(_, value)| sanitize_file_name(value.trim().trim_matches('"')))
+        .filter(|name| !name.is_empty())
+}
+
+/// Last path segment of `url`, or a generic name for bare hosts.
+fn file_name_from_url(url: &Url) -> String {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(|segment| sanitize_file_name(&segment.replace("%20", " ")))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| FALLBACK_FILE_NAME.to_owned())
+}
+
+/// Drops path separators and control characters so a server-chosen name
+/// cannot leave the download directory.
+fn sanitize_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    name.trim_matches(['.', ' ']).to_owned()
+}
@@ -0,0 +1,74 @@
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BrowserCore;
+
+/// Which IP versions connections use on dual-stack hosts.
+///
+/// The clients race the two address families as RFC 8305 (Happy Eyeballs
+/// v2) describes: the first family gets a head start of 300 ms, after
+/// which the other one is tried alongside it and the first connection to
+/// succeed wins.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpPreference {
+    /// Try IPv6 first, falling back to IPv4 when it is slow to connect.
+    #[default]
+    PreferIpv6,
+    /// Try IPv4 first, falling back to IPv6.
+    PreferIpv4,
+    /// Never connect over IPv6, for networks where it is broken.
+    Ipv4Only,
+}
+
+impl IpPreference {
+    pub const ALL: [IpPreference; 3] = [
+        IpPreference::PreferIpv6,
+        IpPreference::PreferIpv4,
+        IpPreference::Ipv4Only,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IpPreference::PreferIpv6 => "Prefer IPv6",
+            IpPreference::PreferIpv4 => "Prefer IPv4",
+            IpPreference::Ipv4Only => "IPv4 only",
+        }
+    }
+
+    /// Orders resolved addresses for connecting: families alternate,
+    /// starting with the preferred one, and each keeps the order the
+    /// resolver gave it. The connector races the family of the first
+    /// address against the other.
+    pub(crate) fn sort(self, addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        let (v6, v4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+            addrs.into_iter().partition(SocketAddr::is_ipv6);
+        let (first, second) = match self {
+            IpPreference::PreferIpv6 => (v6, v4),
+            IpPreference::PreferIpv4 => (v4, v6),
+            IpPreference::Ipv4Only => (v4, Vec::new()),
+        };
+        let mut sorted = Vec::with_capacity(first.len() + second.len());
+        let mut first = first.into_iter();
+        let mut second = second.into_iter();
+        loop {
+            match (first.next(), second.next()) {
+                (None, None) => break,
+                (a, b) => sorted.extend(a.into_iter().chain(b)),
+            }
+        }
+        sorted
+    }
+}
+
+impl BrowserCore {
+    /// Changes which IP versions new connections use. Connections already
+    /// open stay as they are until the pool drops them.
+    pub fn set_ip_preference(&self, preference: IpPreference) {
+        self.resolver.set_preference(preference);
+    }
+
+    pub fn ip_preference(&self) -> IpPreference {
+        self.resolver.preference()
+    }
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BlockRule, BrowserCore, TabId};
+
+/// Device viewport emulated by a tab in responsive design mode.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    /// Width in CSS pixels.
+    pub width: u32,
+    /// Height in CSS pixels.
+    pub height: u32,
+    pub device_pixel_ratio: f32,
+}
+
+impl Viewport {
+    /// Sends the viewport as client hints so servers that adapt their markup
+    /// to the device can do so.
+    pub(crate) fn apply_hints(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let width = self.width.to_string();
+        let dpr = format!("{:.2}", self.device_pixel_ratio);
+        builder
+            .header("Viewport-Width", &width)
+            .header("Sec-CH-Viewport-Width", &width)
+            .header("Sec-CH-Viewport-Height", self.height.to_string())
+            .header("DPR", &dpr)
+            .header("Sec-CH-DPR", &dpr)
+    }
+}
+
+/// User agent sent to one site, with its subdomains, from every tab that
+/// does not set its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteUserAgent {
+    /// Host such as `example.com`.
+    pub site: String,
+    pub user_agent: String,
+}
+
+impl SiteUserAgent {
+    pub fn matches(&self, url: &Url) -> bool {
+        BlockRule::Domain(self.site.clone()).matches(url)
+    }
+}
+
+impl BrowserCore {
+    /// Changes the user agent `tab` sends from its next request on; `None`
+    /// restores the runtime default.
+    pub fn set_user_agent(&self, tab: TabId, user_agent: Option<String>) {
+        let mut guard = self.state.write();
+        if let Some(snapshot) = guard.tabs.iter_mut().find(|snapshot| snapshot.id == tab) {
+            snapshot.user_agent = user_agent;
+        }
+    }
+
+    /// Replaces the per-site user agents. Later requests pick them up; the
+    /// clients are not rebuilt.
+    pub fn set_site_user_agents(&self, overrides: Vec<SiteUserAgent>) {
+        self.state.write().site_user_agents = overrides;
+    }
+
+    pub fn site_user_agents(&self) -> Vec<SiteUserAgent> {
+        self.state.read().site_user_agents.clone()
+    }
+
+    /// User agent to send with a request for `url`: the tab's own, then the
+    /// first site override matching `url`. `None` leaves the runtime default.
+    pub(crate) fn user_agent_for(&self, tab_user_agent: Option<&str>, url: &Url) -> Option<String> {
+        if let Some(user_agent) = tab_user_agent {
+            return Some(user_agent.to_owned());
+        }
+        self.state
+            .read()
+            .site_user_agents
+            .iter()
+            .find(|site| site.matches(url))
+            .map(|site| site.user_agent.clone())
+    }
+
+    /// Emulates `viewport` in `tab`, or stops emulating with `None`.
+    pub fn set_tab_viewport(&self, tab: TabId, viewport: Option<Viewport>) {
+        let mut guard = self.state.write();
+        if let Some(snapshot) = guard.tabs.iter_mut().find(|snapshot| snapshot.id == tab) {
+            snapshot.viewport = viewport;
+        }
+    }
+
+    pub(crate) fn tab_viewport(&self, tab: TabId) -> Option<Viewport> {
+        self.state
+            .read()
+            .tabs
+            .iter()
+            .find(|snapshot| snapshot.id == tab)
+            .and_then(|snapshot| snapshot.viewport)
+    }
+}
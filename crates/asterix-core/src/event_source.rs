@@ -0,0 +1,392 @@
+use std::time::{Duration, Instant};
+
+use reqwest::header::{HeaderValue, ACCEPT, CACHE_CONTROL, CONTENT_TYPE, USER_AGENT};
+use reqwest::StatusCode;
+use tokio::sync::mpsc;
+use tracing::{debug, instrument};
+use url::Url;
+
+use crate::intercept::Interception;
+use crate::network::{self, header_pairs};
+use crate::shield::intercept_request;
+use crate::{BrowserCore, BrowserError, RequestKind, TabId};
+
+/// Wait before reconnecting until the server asks for another with `retry:`.
+const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+
+/// Longest line kept while waiting for its end, so a server that never sends
+/// one cannot grow the buffer without bound.
+const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Event received from a `text/event-stream`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerSentEvent {
+    /// Type from the `event:` field; `message` when the server named none.
+    pub event: String,
+    pub data: String,
+    /// Last event ID seen on the stream, sent back as `Last-Event-ID` when
+    /// reconnecting.
+    pub id: Option<String>,
+}
+
+/// What happened on an event stream, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventSourceEvent {
+    /// The stream is connected, initially or after reconnecting.
+    Opened,
+    Message(ServerSentEvent),
+    /// The connection dropped; another attempt follows after `retry`.
+    Reconnecting {
+        error: String,
+        retry: Duration,
+    },
+}
+
+/// Incremental parser for the `text/event-stream` format.
+#[derive(Default)]
+struct EventStreamParser {
+    /// Bytes of the line still waiting for its end.
+    line: Vec<u8>,
+    /// The last chunk ended in a CR, so a leading LF belongs to it.
+    after_cr: bool,
+    /// Whether the byte order mark, if any, has been skipped.
+    started: bool,
+    event: String,
+    data: String,
+    last_event_id: String,
+    retry: Option<Duration>,
+}
+
+impl EventStreamParser {
+    /// Parses `chunk`, returning the events it completes.
+    fn feed(&mut self, mut chunk: &[u8]) -> Result<Vec<ServerSentEvent>, BrowserError> {
+        if !self.started {
+            let bom = b"\xEF\xBB\xBF";
+            let known = self.line.len() + chunk.len();
+            if known < bom.len() && bom.starts_with(&[&self.line[..], chunk].concat()) {
+                self.line.extend_from_slice(chunk);
+                return Ok(Vec::new());
+            }
+            let mut head = std::mem::take(&mut self.line);
+            head.extend_from_slice(chunk);
+            self.started = true;
+            let skip = if head.starts_with(bom) { bom.len() } else { 0 };
+            return self.feed(&head[skip..]);
+        }
+        if self.after_cr && chunk.first() == Some(&b'\n') {
+            chunk = &chunk[1..];
+        }
+        self.after_cr = false;
+        let mut events = Vec::new();
+        while let Some(end) = chunk.iter().position(|byte| matches!(byte, b'\r' | b'\n')) {
+            self.line.extend_from_slice(&chunk[..end]);
+            let line = std::mem::take(&mut self.line);
+            if let Some(event) = self.process_line(&String::from_utf8_lossy(&line)) {
+                events.push(event);
+            }
+            let crlf = chunk[end] == b'\r' && chunk.get(end + 1) == Some(&b'\n');
+            self.after_cr = chunk[end] == b'\r' && end + 1 == chunk.len();
+            chunk = &chunk[end + if crlf { 2 } else { 1 }..];
+        }
+        self.line.extend_from_slice(chunk);
+        if self.line.len() > MAX_LINE_BYTES {
+            return Err(BrowserError::EventStream(
+                "server sent an overlong line".to_owned(),
+            ));
+        }
+        Ok(events)
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<ServerSentEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = value.to_owned(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_event_id = value.to_owned(),
+            "retry" if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) => {
+                if let Ok(millis) = value.parse() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<ServerSentEvent> {
+        let event = std::mem::take(&mut self.event);
+        if self.data.is_empty() {
+            return None;
+        }
+        let mut data = std::mem::take(&mut self.data);
+        data.pop();
+        Some(ServerSentEvent {
+            event: if event.is_empty() {
+                "message".to_owned()
+            } else {
+                event
+            },
+            data,
+            id: (!self.last_event_id.is_empty()).then(|| self.last_event_id.clone()),
+        })
+    }
+
+    /// Drops the half-read event of a connection that went away, keeping
+    /// the last event ID and retry time for the next one.
+    fn reset(&mut self) {
+        *self = EventStreamParser {
+            last_event_id: std::mem::take(&mut self.last_event_id),
+            retry: self.retry,
+            ..EventStreamParser::default()
+        };
+    }
+}
+
+impl BrowserCore {
+    /// Follows the event stream at `url` on behalf of `tab`, passing its
+    /// events to `events` and reconnecting whenever the connection drops.
+    /// Runs until `events` is closed or the server refuses the stream.
+    #[instrument(skip(self, events))]
+    pub async fn run_event_source(
+        &self,
+        tab: TabId,
+        mut url: Url,
+        events: mpsc::UnboundedSender<EventSourceEvent>,
+    ) -> Result<(), BrowserError> {
+        self.secure_subresource(tab, &mut url, false)?;
+        let mut parser = EventStreamParser::default();
+        loop {
+            let dropped = tokio::select! {
+                result = self.follow_event_stream(tab, &url, &mut parser, &events) => result?,
+                () = events.closed() => None,
+            };
+            let Some(error) = dropped else {
+                return Ok(());
+            };
+            parser.reset();
+            let retry = parser.retry.unwrap_or(DEFAULT_RETRY);
+            debug!("event stream {url} dropped ({error}), reconnecting in {retry:?}");
+            if events
+                .send(EventSourceEvent::Reconnecting { error, retry })
+                .is_err()
+            {
+                return Ok(());
+            }
+            tokio::select! {
+                () = tokio::time::sleep(retry) => {}
+                () = events.closed() => return Ok(()),
+            }
+        }
+    }
+
+    /// Connects once and reads events until the connection ends. Returns why
+    /// it ended when reconnecting makes sense, `None` when nobody listens any
+    /// more, and an error when the server refused the stream.
+    async fn follow_event_stream(
+        &self,
+        tab: TabId,
+        url: &Url,
+        parser: &mut EventStreamParser,
+        events: &mpsc::UnboundedSender<EventSourceEvent>,
+    ) -> Result<Option<String>, BrowserError> {
+        match intercept_request(&self.state.read(), url, RequestKind::EventStream, Some(tab)) {
+            Interception::Continue | Interception::Override(_) => {}
+            Interception::Block(reason) => return Err(BrowserError::Blocked(reason)),
+            Interception::Restrict => return Err(BrowserError::Restricted(url.clone())),
+        }
+        // Going offline drops the stream as a lost connection would, so it
+        // is picked up again once the network is back.
+        if self.state.read().is_offline() {
+            return Ok(Some(BrowserError::Offline.to_string()));
+        }
+        let options = self.tab_options(tab);
+        let withheld = self.withholds_cookies(tab, RequestKind::EventStream, url);
+        let client = self.client_for(url, options.private || withheld);
+        let mut builder = client
+            .get(url.clone())
+            .header(ACCEPT, "text/event-stream")
+            .header(CACHE_CONTROL, "no-cache");
+        if !parser.last_event_id.is_empty() {
+            if let Ok(id) = HeaderValue::from_str(&parser.last_event_id) {
+                builder = builder.header("Last-Event-ID", id);
+            }
+        }
+        if let Some(ua) = self.user_agent_for(options.user_agent.as_deref(), url) {
+            builder = builder.header(USER_AGENT, ua);
+        }
+        let mut request = builder.build()?;
+        self.state
+            .read()
+            .privacy
+            .apply_opt_out(request.headers_mut());
+        let mut entry = network::stream_entry(RequestKind::EventStream, tab, url, &request);
+
+        let started = Instant::now();
+        let result = network::execute_guarded(
+            &self.state,
+            &client,
+            request,
+            RequestKind::EventStream,
+            Some(tab),
+        )
+        .await;
+        entry.duration_ms = Some(started.elapsed().as_millis() as u64);
+        let mut response = match result {
+            Ok(response) => response,
+            Err(err) => {
+                entry.error = Some(err.to_string());
+                network::start_entry(&self.state, entry);
+                // A redirect the browser refuses stays refused.
+                return match err {
+                    BrowserError::Network(err) => Ok(Some(err.to_string())),
+                    err => Err(err),
+                };
+            }
+        };
+        entry.status = Some(response.status().as_u16());
+        entry.response_headers = header_pairs(response.headers());
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|mime| mime.trim_start().starts_with("text/event-stream"));
+        // Anything but a stream is final; retrying would get the same answer.
+        let refusal = if response.status() != StatusCode::OK {
+            Some(BrowserError::Status(response.status().as_u16()))
+        } else if !is_event_stream {
+            Some(BrowserError::EventStream(format!(
+                "{url} is not an event stream"
+            )))
+        } else {
+            None
+        };
+        if let Some(err) = refusal {
+            entry.error = Some(err.to_string());
+            network::start_entry(&self.state, entry);
+            return Err(err);
+        }
+        network::start_entry(&self.state, entry);
+        if events.send(EventSourceEvent::Opened).is_err() {
+            return Ok(None);
+        }
+
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => return Ok(Some("server ended the stream".to_owned())),
+                Err(err) => return Ok(Some(err.to_string())),
+            };
+            for event in parser.feed(&chunk)? {
+                if events.send(EventSourceEvent::Message(event)).is_err() {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(parser: &mut EventStreamParser, chunks: &[&[u8]]) -> Vec<ServerSentEvent> {
+        chunks
+            .iter()
+            .flat_map(|chunk| parser.feed(chunk).unwrap())
+            .collect()
+    }
+
+    fn data(events: &[ServerSentEvent]) -> Vec<&str> {
+        events.iter().map(|event| event.data.as_str()).collect()
+    }
+
+    #[test]
+    fn skips_a_byte_order_mark_split_across_chunks() {
+        let mut parser = EventStreamParser::default();
+        let events = feed_all(&mut parser, &[b"\xEF", b"\xBB", b"\xBFdata: hi\n\n"]);
+        assert_eq!(data(&events), ["hi"]);
+
+        // Only the first one is a mark; later ones are data.
+        let events = feed_all(&mut parser, &[b"data: \xEF\xBB\xBFx\n\n"]);
+        assert_eq!(data(&events), ["\u{FEFF}x"]);
+
+        // Half a mark is not one, and garbles the first field name.
+        let mut parser = EventStreamParser::default();
+        let events = feed_all(&mut parser, &[b"\xEF", b"data: y\n\ndata: z\n\n"]);
+        assert_eq!(data(&events), ["z"]);
+    }
+
+    #[test]
+    fn treats_crlf_split_across_chunks_as_one_line_end() {
+        let mut parser = EventStreamParser::default();
+        let events = feed_all(
+            &mut parser,
+            &[b"data: a\r", b"\ndata: b\r\n\r", b"\n", b"data: c\r\r"],
+        );
+        assert_eq!(data(&events), ["a\nb", "c"]);
+    }
+
+    #[test]
+    fn joins_multi_line_data() {
+        let mut parser = EventStreamParser::default();
+        let events = feed_all(
+            &mut parser,
+            &[b"event: update\ndata: one\ndata:two\ndata\n\ndata: plain\n\n"],
+        );
+        assert_eq!(data(&events), ["one\ntwo\n", "plain"]);
+        assert_eq!(events[0].event, "update");
+        assert_eq!(events[1].event, "message");
+    }
+
+    #[test]
+    fn keeps_ids_and_retry_times() {
+        let mut parser = EventStreamParser::default();
+        let events = feed_all(
+            &mut parser,
+            &[b"id: 7\nretry: 2500\ndata: x\n\nretry: 1x\nid: a\0b\ndata: y\n\n"],
+        );
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+        assert_eq!(events[1].id.as_deref(), Some("7"));
+        assert_eq!(parser.retry, Some(Duration::from_millis(2500)));
+
+        // A dropped connection loses the half-read event but not the ID.
+        feed_all(&mut parser, &[b"data: lost\n"]);
+        parser.reset();
+        let events = feed_all(&mut parser, &[b"data: z\n\nid\ndata: w\n\n"]);
+        assert_eq!(data(&events), ["z", "w"]);
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+        assert_eq!(events[1].id, None);
+        assert_eq!(parser.retry, Some(Duration::from_millis(2500)));
+    }
+
+    #[test]
+    fn ignores_comments_and_empty_events() {
+        let mut parser = EventStreamParser::default();
+        let events = feed_all(
+            &mut parser,
+            &[b": keep-alive\n\n:data: hidden\n\nevent: ping\n\ndata: shown\n\n"],
+        );
+        assert_eq!(data(&events), ["shown"]);
+        assert_eq!(events[0].event, "message");
+    }
+
+    #[test]
+    fn refuses_overlong_lines() {
+        let mut parser = EventStreamParser::default();
+        let line = vec![b'a'; MAX_LINE_BYTES + 1];
+        assert!(parser.feed(b"data: ").unwrap().is_empty());
+        assert!(parser.feed(&line).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+use std::sync::Arc;
+
+use reqwest::header::{ACCEPT, USER_AGENT};
+use tracing::{debug, instrument};
+use url::Url;
+
+use crate::image_cache::ImageCache;
+use crate::network;
+use crate::{
+    map_elements, BrowserCore, BrowserError, DecodedImage, PageResponse, RequestKind, TabId,
+};
+
+/// Origins whose icon is remembered; past that, the icons are fetched anew.
+const MAX_FAVICONS: usize = 512;
+
+/// Icon size the tab strip draws; ICO files offer several.
+const PREFERRED_SIZE: u32 = 32;
+
+/// Address of the icon of an HTML `page`: the first `<link rel=icon>` in a
+/// format that can be decoded, or `/favicon.ico` of its origin.
+pub fn favicon_url(page: &PageResponse) -> Option<Url> {
+    if !matches!(page.url.scheme(), "http" | "https") {
+        return None;
+    }
+    let is_html = page
+        .mime_type
+        .as_deref()
+        .is_none_or(|mime| mime.starts_with("text/html"));
+    if !is_html {
+        return None;
+    }
+    let elements = map_elements(&page.body);
+    let base = elements
+        .iter()
+        .find(|element| element.tag == "base")
+        .and_then(|element| element.attribute("href"))
+        .and_then(|href| page.url.join(href.trim()).ok())
+        .unwrap_or_else(|| page.url.clone());
+    let linked = elements
+        .iter()
+        .filter(|element| element.tag == "link")
+        .filter(|element| {
+            element.attribute("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("icon"))
+            })
+        })
+        .filter(|element| {
+            !element
+                .attribute("type")
+                .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("image/svg+xml"))
+        })
+        .filter_map(|element| base.join(element.attribute("href")?.trim()).ok())
+        .find(|url| {
+            matches!(url.scheme(), "http" | "https")
+                && !url.path().to_ascii_lowercase().ends_with(".svg")
+        });
+    linked.or_else(|| page.url.join("/favicon.ico").ok())
+}
+
+/// Key icons are remembered under.
+fn origin_key(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+impl BrowserCore {
+    /// Icon remembered for the origin of `url`, if it has been fetched.
+    pub fn favicon_for(&self, url: &Url) -> Option<Arc<DecodedImage>> {
+        self.state
+            .read()
+            .favicons
+            .get(&origin_key(url))
+            .cloned()
+            .flatten()
+    }
+
+    /// Fetches and decodes the icon at `icon` for the page at `page` shown
+    /// in `tab`, unless the icon of its origin is already known, and puts
+    /// it on the tabs showing that origin. Origins without a usable icon
+    /// are remembered too, so they are not asked again.
+    #[instrument(skip(self))]
+    pub async fn load_favicon(
+        &self,
+        tab: TabId,
+        page: &Url,
+        icon: Url,
+    ) -> Result<Option<Arc<DecodedImage>>, BrowserError> {
+        let origin = origin_key(page);
+        let known = self.state.read().favicons.get(&origin).cloned();
+        let favicon = match known {
+            Some(favicon) => favicon,
+            None => {
+                let fetched = self.fetch_favicon(tab, icon).await;
+                if let Err(err) = &fetched {
+                    debug!("no icon for {origin}: {err}");
+                }
+                let favicon = fetched.ok();
+                let mut guard = self.state.write();
+                if guard.favicons.len() >= MAX_FAVICONS {
+                    guard.favicons.clear();
+                }
+                guard.favicons.insert(origin.clone(), favicon.clone());
+                favicon
+            }
+        };
+        let mut guard = self.state.write();
+        for snapshot in &mut guard.tabs {
+            if snapshot.url.as_ref().map(origin_key).as_ref() == Some(&origin) {
+                snapshot.favicon = favicon.clone();
+            }
+        }
+        Ok(favicon)
+    }
+
+    async fn fetch_favicon(
+        &self,
+        tab: TabId,
+        mut url: Url,
+    ) -> Result<Arc<DecodedImage>, BrowserError> {
+        self.secure_subresource(tab, &mut url, true)?;
+        let options = self.tab_options(tab);
+        let client = self.client_for(&url, options.private);
+        let mut builder = client.get(url.clone()).header(
+            ACCEPT,
+            "image/x-icon,image/vnd.microsoft.icon,image/png,image/*;q=0.8",
+        );
+        if let Some(ua) = self.user_agent_for(options.user_agent.as_deref(), &url) {
+            builder = builder.header(USER_AGENT, ua);
+        }
+        let response =
+            network::send_logged(&self.state, builder, RequestKind::Image, Some(tab)).await?;
+        if !response.status.is_success() {
+            return Err(BrowserError::Status(response.status.as_u16()));
+        }
+        let images = Arc::clone(&self.images);
+        self.cpu
+            .run(move || decode_favicon(&images, &response.body))
+            .await?
+    }
+}
+
+/// Decodes an icon: an ICO file, or an image the shared cache decodes.
+fn decode_favicon(images: &ImageCache, encoded: &[u8]) -> Result<Arc<DecodedImage>, BrowserError> {
+    if !encoded.starts_with(&[0, 0, 1, 0]) {
+        return images.get_or_decode(encoded);
+    }
+    let entry = ico_entry(encoded)?;
+    if entry.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return images.get_or_decode(entry);
+    }
+    decode_dib(entry).map(Arc::new)
+}
+
+/// Image of an ICO file closest to [`PREFERRED_SIZE`], preferring larger
+/// images to smaller ones and more colors to fewer.
+fn ico_entry(ico: &[u8]) -> Result<&[u8], BrowserError> {
+    let invalid = || BrowserError::Image("malformed ICO file".to_owned());
+    let count = usize::from(read_u16(ico, 4).ok_or_else(invalid)?);
+    let mut best = None;
+    let mut best_rank = None;
+    for index in 0..count {
+        let header = 6 + index * 16;
+        let Some(entry) = ico.get(header..header + 16) else {
+            break;
+        };
+        let size = match entry[0] {
+            0 => 256,
+            width => u32::from(width),
+        };
+        let bits = read_u16(entry, 6).unwrap_or_default();
+        let length = read_u32(entry, 8).unwrap_or_default() as usize;
+        let offset = read_u32(entry, 12).unwrap_or_default() as usize;
+        let Some(data) = offset
+            .checked_add(length)
+            .and_then(|end| ico.get(offset..end))
+        else {
+            continue;
+        };
+        // Large enough first, then closest to the preferred size, then
+        // most colors.
+        let rank = (
+            size >= PREFERRED_SIZE,
+            u32::MAX - size.abs_diff(PREFERRED_SIZE),
+            bits,
+        );
+        if best_rank.is_none_or(|best| rank > best) {
+            best_rank = Some(rank);
+            best = Some(data);
+        }
+    }
+    best.ok_or_else(invalid)
+}
+
+/// Decodes the bottom-up bitmap of an ICO entry, whose transparency comes
+/// from its alpha channel or the 1-bit mask after the pixels.
+fn decode_dib(dib: &[u8]) -> Result<DecodedImage, BrowserError> {
+    let invalid = |what: &str| BrowserError::Image(format!("malformed icon bitmap: {what}"));
+    let header = read_u32(dib, 0).ok_or_else(|| invalid("no header"))? as usize;
+    let width = read_i32(dib, 4).ok_or_else(|| invalid("no width"))?;
+    // The height covers the pixels and the mask below them.
+    let height = read_i32(dib, 8).ok_or_else(|| invalid("no height"))? / 2;
+    let bits = read_u16(dib, 14).ok_or_else(|| invalid("no bit depth"))?;
+    let compression = read_u32(dib, 16).unwrap_or_default();
+    if !(1..=256).contains(&width) || !(1..=256).contains(&height.abs()) {
+        return Err(invalid("bad dimensions"));
+    }
+    // Only uncompressed pixels, or 32-bit ones with the usual bit fields.
+    if compression != 0 && !(compression == 3 && bits == 32) {
+        return Err(BrowserError::Image(format!(
+            "compressed icon bitmaps are not supported ({compression})"
+        )));
+    }
+    let (width, height) = (width as usize, height.unsigned_abs() as usize);
+    let palette_len = if bits <= 8 {
+        match read_u32(dib, 32).unwrap_or_default() {
+            0 => 1usize << bits,
+            used => used as usize,
+        }
+    } else {
+        0
+    };
+    let masks = if compression == 3 && header == 40 {
+        12
+    } else {
+        0
+    };
+    let palette_start = header + masks;
+    let palette = dib
+        .get(palette_start..palette_start + palette_len * 4)
+        .ok_or_else(|| invalid("truncated palette"))?;
+    let stride = (width * usize::from(bits)).div_ceil(32) * 4;
+    let pixels_start = palette_start + palette.len();
+    let mask_stride = width.div_ceil(32) * 4;
+    let mask_start = pixels_start + stride * height;
+    let rows = dib
+        .get(pixels_start..mask_start)
+        .ok_or_else(|| invalid("truncated pixels"))?;
+    // Some 32-bit icons leave the mask out.
+    let mask = dib.get(mask_start..mask_start + mask_stride * height);
+
+    let mut pixels = vec![0; width * height * 4];
+    let mut any_alpha = false;
+    for y in 0..height {
+        let row = &rows[(height - 1 - y) * stride..][..stride];
+        for x in 0..width {
+            let [b, g, r, a] = match bits {
+                32 => [row[x * 4], row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]],
+                24 => [row[x * 3], row[x * 3 + 1], row[x * 3 + 2], 0xFF],
+                1 | 4 | 8 => {
+                    let bit = x * usize::from(bits);
+                    let shift = 8 - usize::from(bits) - bit % 8;
+                    let index = usize::from(row[bit / 8] >> shift) & ((1 << bits) - 1);
+                    let color = palette
+                        .get(index * 4..index * 4 + 3)
+                        .ok_or_else(|| invalid("color outside the palette"))?;
+                    [color[0], color[1], color[2], 0xFF]
+                }
+                other => {
+                    return Err(BrowserError::Image(format!(
+                        "{other}-bit icon bitmaps are not supported"
+                    )))
+                }
+            };
+            any_alpha |= bits == 32 && a != 0;
+            let offset = (y * width + x) * 4;
+            pixels[offset..offset + 4].copy_from_slice(&[r, g, b, a]);
+        }
+    }
+    // Without an alpha channel, or with one left empty, the mask marks
+    // the transparent pixels.
+    if !any_alpha {
+        for y in 0..height {
+            let mask_row = mask.map(|mask| &mask[(height - 1 - y) * mask_stride..][..mask_stride]);
+            for x in 0..width {
+                let transparent = mask_row.is_some_and(|row| row[x / 8] & (0x80 >> (x % 8)) != 0);
+                pixels[(y * width + x) * 4 + 3] = if transparent { 0 } else { 0xFF };
+            }
+        }
+    }
+    Ok(DecodedImage {
+        width: width as u32,
+        height: height as u32,
+        pixels,
+    })
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_i32(bytes: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Icon bitmap with a 40-byte header; `rows` and `mask` are bottom-up
+    /// and already padded to four bytes per row.
+    fn bitmap(
+        width: i32,
+        height: i32,
+        bits: u16,
+        palette: &[u8],
+        rows: &[u8],
+        mask: &[u8],
+    ) -> Vec<u8> {
+        let mut dib = Vec::new();
+        dib.extend_from_slice(&40u32.to_le_bytes());
+        dib.extend_from_slice(&width.to_le_bytes());
+        dib.extend_from_slice(&(height * 2).to_le_bytes());
+        dib.extend_from_slice(&1u16.to_le_bytes());
+        dib.extend_from_slice(&bits.to_le_bytes());
+        dib.extend_from_slice(&[0; 16]);
+        dib.extend_from_slice(&(palette.len() as u32 / 4).to_le_bytes());
+        dib.extend_from_slice(&[0; 4]);
+        dib.extend_from_slice(palette);
+        dib.extend_from_slice(rows);
+        dib.extend_from_slice(mask);
+        dib
+    }
+
+    /// ICO file of `entries`, given as width, bit depth and image data.
+    fn ico(entries: &[(u8, u16, &[u8])]) -> Vec<u8> {
+        let mut file = vec![0, 0, 1, 0];
+        file.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        let mut offset = 6 + entries.len() * 16;
+        for (width, bits, data) in entries {
+            file.extend_from_slice(&[*width, *width, 0, 0, 1, 0]);
+            file.extend_from_slice(&bits.to_le_bytes());
+            file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            file.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += data.len();
+        }
+        for (_, _, data) in entries {
+            file.extend_from_slice(data);
+        }
+        file
+    }
+
+    fn pixel(image: &DecodedImage, x: usize, y: usize) -> [u8; 4] {
+        let offset = (y * image.width as usize + x) * 4;
+        image.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn decodes_32_bit_icons_with_alpha() {
+        // Bottom row first, in BGRA; the mask would hide everything but
+        // the alpha channel takes precedence.
+        let rows = [
+            [1, 2, 3, 0xFF, 4, 5, 6, 0x80],
+            [7, 8, 9, 0x00, 10, 11, 12, 0x40],
+        ]
+        .concat();
+        let image = decode_dib(&bitmap(
+            2,
+            2,
+            32,
+            &[],
+            &rows,
+            &[0xC0, 0, 0, 0, 0xC0, 0, 0, 0],
+        ))
+        .unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(pixel(&image, 0, 0), [9, 8, 7, 0x00]);
+        assert_eq!(pixel(&image, 1, 0), [12, 11, 10, 0x40]);
+        assert_eq!(pixel(&image, 0, 1), [3, 2, 1, 0xFF]);
+        assert_eq!(pixel(&image, 1, 1), [6, 5, 4, 0x80]);
+
+        // Icons leaving the mask out are opaque where the alpha is empty.
+        let image = decode_dib(&bitmap(1, 1, 32, &[], &[1, 2, 3, 0], &[])).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [3, 2, 1, 0xFF]);
+    }
+
+    #[test]
+    fn decodes_24_bit_icons_through_the_mask() {
+        let rows = [1, 2, 3, 4, 5, 6, 0, 0];
+        let mask = [0b0100_0000, 0, 0, 0];
+        let image = decode_dib(&bitmap(2, 1, 24, &[], &rows, &mask)).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [3, 2, 1, 0xFF]);
+        assert_eq!(pixel(&image, 1, 0), [6, 5, 4, 0]);
+    }
+
+    #[test]
+    fn decodes_palette_icons() {
+        let palette = [0, 0, 0, 0, 0, 0, 0xFF, 0, 0, 0xFF, 0, 0];
+        let red = [0xFF, 0, 0, 0xFF];
+        let green = [0, 0xFF, 0, 0xFF];
+        let black = [0, 0, 0, 0xFF];
+        let no_mask = [0; 4];
+
+        let image = decode_dib(&bitmap(
+            3,
+            1,
+            1,
+            &palette[..8],
+            &[0b1010_0000, 0, 0, 0],
+            &no_mask,
+        ))
+        .unwrap();
+        let row: Vec<_> = (0..3).map(|x| pixel(&image, x, 0)).collect();
+        assert_eq!(row, [red, black, red]);
+
+        let image = decode_dib(&bitmap(2, 1, 4, &palette, &[0x21, 0, 0, 0], &no_mask)).unwrap();
+        assert_eq!([pixel(&image, 0, 0), pixel(&image, 1, 0)], [green, red]);
+
+        let image = decode_dib(&bitmap(2, 1, 8, &palette, &[2, 0, 0, 0], &no_mask)).unwrap();
+        assert_eq!([pixel(&image, 0, 0), pixel(&image, 1, 0)], [green, black]);
+
+        let err = decode_dib(&bitmap(1, 1, 4, &palette, &[0x50, 0, 0, 0], &no_mask)).unwrap_err();
+        assert!(err.to_string().contains("outside the palette"), "{err}");
+    }
+
+    #[test]
+    fn rejects_truncated_icons() {
+        let dib = bitmap(2, 2, 32, &[], &[0; 16], &[0; 8]);
+        assert!(decode_dib(&dib[..dib.len() - 12]).is_err());
+        assert!(decode_dib(&dib[..30]).is_err());
+        assert!(decode_dib(&[]).is_err());
+
+        // Entries pointing past the end are skipped, and so is a directory
+        // cut short.
+        let mut file = ico(&[(16, 32, &dib), (32, 32, &dib)]);
+        file.truncate(file.len() - 1);
+        assert_eq!(ico_entry(&file).unwrap(), dib.as_slice());
+        let mut file = ico(&[(32, 32, &dib)]);
+        file.truncate(file.len() - 1);
+        assert!(ico_entry(&file).is_err());
+        assert!(ico_entry(&file[..10]).is_err());
+        assert!(ico_entry(&[0, 0, 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_depths_and_compression() {
+        let err = decode_dib(&bitmap(1, 1, 16, &[], &[0; 4], &[0; 4])).unwrap_err();
+        assert!(err.to_string().contains("16-bit"), "{err}");
+        let err = decode_dib(&bitmap(1, 1, 2, &[0; 16], &[0; 4], &[0; 4])).unwrap_err();
+        assert!(err.to_string().contains("2-bit"), "{err}");
+
+        let mut compressed = bitmap(1, 1, 8, &[0; 4], &[0; 4], &[0; 4]);
+        compressed[16] = 1;
+        let err = decode_dib(&compressed).unwrap_err();
+        assert!(err.to_string().contains("compressed"), "{err}");
+        assert!(decode_dib(&bitmap(0, 1, 32, &[], &[], &[])).is_err());
+        assert!(decode_dib(&bitmap(257, 1, 32, &[], &[0; 1028], &[])).is_err());
+    }
+
+    #[test]
+    fn picks_the_entry_closest_to_the_tab_size() {
+        let small = bitmap(16, 16, 32, &[], &[], &[]);
+        let exact_few_colors = bitmap(32, 32, 8, &[], &[], &[]);
+        let exact = bitmap(32, 32, 32, &[], &[], &[]);
+        let large = bitmap(48, 48, 32, &[], &[], &[]);
+        let file = ico(&[
+            (16, 32, &small),
+            (32, 8, &exact_few_colors),
+            (32, 32, &exact),
+            (48, 32, &large),
+        ]);
+        assert_eq!(ico_entry(&file).unwrap(), exact.as_slice());
+        let file = ico(&[(16, 32, &small), (48, 32, &large)]);
+        assert_eq!(ico_entry(&file).unwrap(), large.as_slice());
+    }
+}
@@ -0,0 +1,86 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, BrowserError, RequestKind};
+
+/// One item of an RSS or Atom feed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedEntry {
+    /// The entry's `guid` or `id`, falling back to its link.
+    pub id: String,
+    pub title: String,
+}
+
+/// Parses `xml` when its root is an RSS `<rss>`/`<rdf:RDF>` or Atom `<feed>`.
+pub fn parse_feed(xml: &str) -> Option<Vec<FeedEntry>> {
+    let head = xml
+        .chars()
+        .take(1024)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let entry = if head.contains("<rss") || head.contains("<rdf:rdf") {
+        "item"
+    } else if head.contains("<feed") {
+        "entry"
+    } else {
+        return None;
+    };
+
+    let document = Html::parse_document(xml);
+    let entries = Selector::parse(entry).ok()?;
+    Some(
+        document
+            .select(&entries)
+            .filter_map(|element| {
+                let title = child_text(element, "title").unwrap_or_default();
+                let id = child_text(element, "guid")
+                    .or_else(|| child_text(element, "id"))
+                    .or_else(|| child_text(element, "link"))
+                    .or_else(|| child_attr(element, "link", "href"))
+                    .or_else(|| (!title.is_empty()).then(|| title.clone()))?;
+                Some(FeedEntry { id, title })
+            })
+            .collect(),
+    )
+}
+
+impl BrowserCore {
+    /// Fetches the feed at `url` and returns its entries, newest first as the
+    /// feed lists them.
+    pub async fn fetch_feed(&self, url: &Url) -> Result<Vec<FeedEntry>, BrowserError> {
+        let builder = self.client_for(url, false).get(url.clone());
+        let response = self
+            .send_logged(builder, RequestKind::Scheduled, None)
+            .await?;
+        if !response.status.is_success() {
+            return Err(BrowserError::Status(response.status.as_u16()));
+        }
+        parse_feed(&String::from_utf8_lossy(&response.body)).ok_or_else(|| {
+            BrowserError::InvalidRequest(format!("{url} is not an RSS or Atom feed"))
+        })
+    }
+}
+
+fn child<'a>(element: ElementRef<'a>, name: &str) -> Option<ElementRef<'a>> {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == name)
+}
+
+fn child_text(element: ElementRef, name: &str) -> Option<String> {
+    let text = child(element, name)?
+        .text()
+        .collect::<String>()
+        .trim()
+        .to_owned();
+    (!text.is_empty()).then_some(text)
+}
+
+fn child_attr(element: ElementRef, name: &str, attribute: &str) -> Option<String> {
+    child(element, name)?
+        .value()
+        .attr(attribute)
+        .map(str::to_owned)
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, PageResponse, TabId};
+
+/// What happens to a response once it has arrived.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileAction {
+    /// Show it in the tab.
+    #[default]
+    Open,
+    /// Save it to the downloads without leaving the current page.
+    Download,
+    /// Let the user pick between opening and downloading.
+    Ask,
+}
+
+impl FileAction {
+    pub const ALL: [FileAction; 3] = [FileAction::Open, FileAction::Download, FileAction::Ask];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileAction::Open => "Open in viewer",
+            FileAction::Download => "Always download",
+            FileAction::Ask => "Always ask",
+        }
+    }
+}
+
+/// How to handle responses of one file type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTypeRule {
+    /// MIME type such as `application/pdf`, a family such as `image/*`, or
+    /// a file extension such as `.zip`.
+    pub pattern: String,
+    pub action: FileAction,
+}
+
+impl FileTypeRule {
+    /// Whether a response with `mime_type` served from `url` is of this
+    /// rule's type. Parameters such as `charset` are ignored.
+    pub fn matches(&self, mime_type: Option<&str>, url: &Url) -> bool {
+        let pattern = self.pattern.trim().to_ascii_lowercase();
+        if let Some(extension) = pattern.strip_prefix('.') {
+            return url_extension(url).is_some_and(|ext| ext == extension);
+        }
+        let Some(mime_type) = mime_type else {
+            return false;
+        };
+        let essence = mime_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match pattern.strip_suffix("/*") {
+            Some(family) => essence
+                .split_once('/')
+                .is_some_and(|(kind, _)| kind == family),
+            None => essence == pattern,
+        }
+    }
+}
+
+/// Lowercased extension of the last path segment of `url`.
+fn url_extension(url: &Url) -> Option<String> {
+    let name = url.path_segments()?.next_back()?;
+    let (stem, extension) = name.rsplit_once('.')?;
+    (!stem.is_empty() && !extension.is_empty()).then(|| extension.to_ascii_lowercase())
+}
+
+impl BrowserCore {
+    /// Replaces the per-type rules consulted before a response is shown.
+    pub fn set_file_type_rules(&self, rules: Vec<FileTypeRule>) {
+        self.state.write().file_types = rules;
+    }
+
+    pub fn file_type_rules(&self) -> Vec<FileTypeRule> {
+        self.state.read().file_types.clone()
+    }
+
+    /// Action of the first rule matching `page`, opening it when none does.
+    pub(crate) fn route_response(&self, page: &PageResponse) -> FileAction {
+        self.state
+            .read()
+            .file_types
+            .iter()
+            .find(|rule| rule.matches(page.mime_type.as_deref(), &page.url))
+            .map_or(FileAction::Open, |rule| rule.action)
+    }
+
+    /// Shows a page that was held back for the user to decide on, as if it
+    /// had been opened straight away.
+    pub fn open_routed_page(&self, tab: TabId, requested: &Url, page: &PageResponse) {
+        self.update_tab_after_fetch(tab, requested, page);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use parking_lot::RwLock;
+
+/// Experimental subsystem that can be switched on or off per profile
+/// without rebuilding, from `about:flags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flag {
+    /// Stable key the choice is stored under, such as `js-engine`.
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    /// Whether the experiment is on when the profile does not say.
+    pub default: bool,
+    /// Whether this build contains the subsystem at all. Flags of missing
+    /// subsystems are listed but stay off.
+    pub available: bool,
+}
+
+/// Flags known to every build.
+const BUILT_IN: [Flag; 2] = [Flag::JS_ENGINE, Flag::GPU_RASTER];
+
+/// Flags registered so far and the choices the profile made for them,
+/// shared by every crate of the process.
+struct Registry {
+    flags: Vec<Flag>,
+    /// Choices by flag name, kept for flags this build does not know so a
+    /// profile shared with another build keeps them.
+    overrides: BTreeMap<String, bool>,
+}
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(|| {
+    RwLock::new(Registry {
+        flags: BUILT_IN.to_vec(),
+        overrides: BTreeMap::new(),
+    })
+});
+
+/// A registered flag with whether it is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagState {
+    pub flag: Flag,
+    pub enabled: bool,
+    /// Whether the profile chose a value rather than taking the default.
+    pub overridden: bool,
+}
+
+impl Flag {
+    /// Page scripts run by the JavaScript engine.
+    pub const JS_ENGINE: Self = Self {
+        name: "js-engine",
+        title: "JavaScript engine",
+        description: "Runs page scripts. Pages are shown as their markup describes them otherwise.",
+        default: false,
+        available: false,
+    };
+
+    /// Page rendering rasterized on the GPU.
+    pub const GPU_RASTER: Self = Self {
+        name: "gpu-raster",
+        title: "GPU rasterization",
+        description: "Draws rendered pages on the graphics card instead of the processor.",
+        default: false,
+        available: false,
+    };
+
+    /// Whether the experiment is on, as the profile chose or by default.
+    pub fn is_enabled(&self) -> bool {
+        self.available
+            && REGISTRY
+                .read()
+                .overrides
+                .get(self.name)
+                .copied()
+                .unwrap_or(self.default)
+    }
+}
+
+/// Makes `flag` known to `about:flags`, replacing one of the same name.
+/// Crates with experiments of their own register them at startup.
+pub fn register_flag(flag: Flag) {
+    let mut registry = REGISTRY.write();
+    match registry
+        .flags
+        .iter_mut()
+        .find(|known| known.name == flag.name)
+    {
+        Some(known) => *known = flag,
+        None => registry.flags.push(flag),
+    }
+}
+
+/// Every registered flag, in registration order.
+pub fn flags() -> Vec<FlagState> {
+    let registry = REGISTRY.read();
+    registry
+        .flags
+        .iter()
+        .map(|flag| {
+            let choice = registry.overrides.get(flag.name).copied();
+            FlagState {
+                flag: *flag,
+                enabled: flag.available && choice.unwrap_or(flag.default),
+                overridden: choice.is_some(),
+            }
+        })
+        .collect()
+}
+
+/// Whether the registered flag called `name` is on; unknown flags are off.
+pub fn flag_enabled(name: &str) -> bool {
+    let flag = REGISTRY
+        .read()
+        .flags
+        .iter()
+        .find(|flag| flag.name == name)
+        .copied();
+    flag.is_some_and(|flag| flag.is_enabled())
+}
+
+/// Turns the flag called `name` on or off, or back to its default with
+/// `None`.
+pub fn set_flag(name: &str, enabled: Option<bool>) {
+    let mut registry = REGISTRY.write();
+    match enabled {
+        Some(enabled) => {
+            registry.overrides.insert(name.to_owned(), enabled);
+        }
+        None => {
+            registry.overrides.remove(name);
+        }
+    }
+}
+
+/// Choices the profile made, by flag name, for saving with it.
+pub fn flag_overrides() -> BTreeMap<String, bool> {
+    REGISTRY.read().overrides.clone()
+}
+
+/// Replaces every choice with those of a profile.
+pub fn set_flag_overrides(overrides: BTreeMap<String, bool>) {
+    REGISTRY.write().overrides = overrides;
+}
@@ -0,0 +1,330 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{MultipartPart, PageRequest, RequestBody, TabId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FormMethod {
+    Get,
+    Post,
+}
+
+/// Body format named by a form's `enctype`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FormEncoding {
+    #[default]
+    UrlEncoded,
+    Multipart,
+    TextPlain,
+}
+
+/// An HTML form found in a page's source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageForm {
+    /// CSS selector that finds this form again in the same page.
+    pub selector: String,
+    pub action: Url,
+    pub method: FormMethod,
+    /// How a `POST` submission encodes the fields.
+    #[serde(default)]
+    pub encoding: FormEncoding,
+    /// Controls that take part in submission, in document order.
+    pub fields: Vec<FormField>,
+}
+
+/// Named control of a form with the value it would submit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormField {
+    pub name: String,
+    /// The input `type`, or `select` and `textarea`.
+    pub kind: String,
+    pub value: String,
+    /// Values a `<select>` offers.
+    pub options: Vec<String>,
+}
+
+/// Controls that never carry a value of their own.
+const SKIPPED_INPUTS: [&str; 5] = ["submit", "button", "reset", "image", "file"];
+
+/// Forms in `source`, with actions resolved against `base`.
+pub fn extract_forms(source: &str, base: &Url) -> Vec<PageForm> {
+    let document = Html::parse_document(source);
+    let forms = all_forms(&document);
+    forms
+        .iter()
+        .map(|form| read_form(&document, &forms, *form, base))
+        .collect()
+}
+
+/// The form `selector` matches in `source`, or the form around the element it
+/// matches.
+pub fn find_form(source: &str, base: &Url, selector: &str) -> Result<PageForm, String> {
+    let document = Html::parse_document(source);
+    let target = select_first(&document, selector)?;
+    let form = std::iter::once(target)
+        .chain(target.ancestors().filter_map(ElementRef::wrap))
+        .find(|element| element.value().name() == "form")
+        .ok_or_else(|| format!("`{selector}` is not in a form"))?;
+    Ok(read_form(&document, &all_forms(&document), form, base))
+}
+
+/// The `name` of the form control `selector` matches in `source`.
+pub fn control_name(source: &str, selector: &str) -> Result<String, String> {
+    let document = Html::parse_document(source);
+    select_first(&document, selector)?
+        .value()
+        .attr("name")
+        .map(str::to_owned)
+        .ok_or_else(|| format!("`{selector}` has no name, so it is never submitted"))
+}
+
+/// What clicking an element does in a page without scripts.
+#[derive(Debug, Clone)]
+pub enum ClickTarget {
+    /// A link to follow.
+    Link(Url),
+    /// A submit button of this form.
+    Submit(PageForm),
+}
+
+/// Resolves a click on the element `selector` matches in `source`.
+pub fn click_target(source: &str, base: &Url, selector: &str) -> Result<ClickTarget, String> {
+    let document = Html::parse_document(source);
+    let target = select_first(&document, selector)?;
+    let attrs = target.value();
+    if let Some(href) = attrs.attr("href").filter(|_| attrs.name() == "a") {
+        return base
+            .join(href.trim())
+            .map(ClickTarget::Link)
+            .map_err(|err| format!("`{selector}` links to an invalid URL: {err}"));
+    }
+    let kind = attrs.attr("type").map(str::to_ascii_lowercase);
+    let submits = match attrs.name() {
+        "button" => kind.as_deref().is_none_or(|kind| kind == "submit"),
+        "input" => matches!(kind.as_deref(), Some("submit" | "image")),
+        _ => false,
+    };
+    if !submits {
+        return Err(format!(
+            "`{selector}` is neither a link nor a submit button"
+        ));
+    }
+    let form = target
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|element| element.value().name() == "form")
+        .ok_or_else(|| format!("`{selector}` is not in a form"))?;
+    let mut form = read_form(&document, &all_forms(&document), form, base);
+    if let Some(name) = attrs.attr("name").filter(|name| !name.is_empty()) {
+        form.set_value(name, attrs.attr("value").unwrap_or_default());
+    }
+    Ok(ClickTarget::Submit(form))
+}
+
+/// Selector for the first link in `source` that leads to `url`.
+pub fn link_selector(source: &str, base: &Url, url: &Url) -> Option<String> {
+    let document = Html::parse_document(source);
+    let links = Selector::parse("a[href]").ok()?;
+    let href = document
+        .select(&links)
+        .filter_map(|link| link.value().attr("href"))
+        .find(|href| base.join(href.trim()).is_ok_and(|target| target == *url))?;
+    Some(format!("a[href=\"{}\"]", escape(href)))
+}
+
+impl PageForm {
+    /// Selector for this form's control called `name`.
+    pub fn field_selector(&self, name: &str) -> String {
+        format!("{} [name=\"{}\"]", self.selector, escape(name))
+    }
+
+    /// Sets the value submitted for `name`, adding the field if the form
+    /// would not send it yet, such as an unticked checkbox.
+    pub fn set_value(&mut self, name: &str, value: &str) {
+        match self.fields.iter_mut().find(|field| field.name == name) {
+            Some(field) => field.value = value.to_owned(),
+            None => self.fields.push(FormField {
+                name: name.to_owned(),
+                kind: "hidden".to_owned(),
+                value: value.to_owned(),
+                options: Vec::new(),
+            }),
+        }
+    }
+
+    /// Navigation submitting the form's current values in `tab`.
+    pub fn request(&self, tab: TabId) -> PageRequest {
+        let pairs: Vec<(String, String)> = self
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), field.value.clone()))
+            .collect();
+        match self.method {
+            FormMethod::Get => {
+                let mut url = self.action.clone();
+                url.query_pairs_mut().clear().extend_pairs(&pairs);
+                PageRequest::new(tab, url)
+            }
+            FormMethod::Post => {
+                let body = match self.encoding {
+                    FormEncoding::UrlEncoded => RequestBody::UrlEncoded(pairs),
+                    FormEncoding::Multipart => RequestBody::Multipart(
+                        pairs
+                            .into_iter()
+                            .map(|(name, value)| MultipartPart::text(name, value))
+                            .collect(),
+                    ),
+                    FormEncoding::TextPlain => RequestBody::Raw {
+                        content_type: "text/plain".to_owned(),
+                        data: pairs
+                            .iter()
+                            .map(|(name, value)| format!("{name}={value}\r\n"))
+                            .collect::<String>()
+                            .into_bytes(),
+                    },
+                };
+                PageRequest::post(tab, self.action.clone(), body)
+            }
+        }
+    }
+}
+
+fn all_forms(document: &Html) -> Vec<ElementRef<'_>> {
+    match Selector::parse("form") {
+        Ok(selector) => document.select(&selector).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn select_first<'a>(document: &'a Html, selector: &str) -> Result<ElementRef<'a>, String> {
+    let parsed =
+        Selector::parse(selector).map_err(|err| format!("invalid selector `{selector}`: {err}"))?;
+    document
+        .select(&parsed)
+        .next()
+        .ok_or_else(|| format!("nothing on the page matches `{selector}`"))
+}
+
+fn read_form(document: &Html, forms: &[ElementRef], form: ElementRef, base: &Url) -> PageForm {
+    let attrs = form.value();
+    let action = attrs
+        .attr("action")
+        .map(str::trim)
+        .filter(|action| !action.is_empty())
+        .and_then(|action| base.join(action).ok())
+        .unwrap_or_else(|| base.clone());
+    let method = match attrs.attr("method") {
+        Some(method) if method.eq_ignore_ascii_case("post") => FormMethod::Post,
+        _ => FormMethod::Get,
+    };
+    let encoding = match attrs.attr("enctype").map(str::trim) {
+        Some(enctype) if enctype.eq_ignore_ascii_case("multipart/form-data") => {
+            FormEncoding::Multipart
+        }
+        Some(enctype) if enctype.eq_ignore_ascii_case("text/plain") => FormEncoding::TextPlain,
+        _ => FormEncoding::UrlEncoded,
+    };
+    let mut fields = Vec::new();
+    if let Ok(controls) = Selector::parse("input, select, textarea") {
+        fields.extend(form.select(&controls).filter_map(read_field));
+    }
+    PageForm {
+        selector: form_selector(document, forms, form),
+        action,
+        method,
+        encoding,
+        fields,
+    }
+}
+
+fn read_field(control: ElementRef) -> Option<FormField> {
+    let attrs = control.value();
+    let name = attrs.attr("name").filter(|name| !name.is_empty())?;
+    if attrs.attr("disabled").is_some() {
+        return None;
+    }
+    let (kind, value, options) = match attrs.name() {
+        "textarea" => ("textarea".to_owned(), control.text().collect(), Vec::new()),
+        "select" => {
+            let options: Vec<(String, bool)> = Selector::parse("option")
+                .map(|option| {
+                    control
+                        .select(&option)
+                        .map(|option| {
+                            let value = option
+                                .value()
+                                .attr("value")
+                                .map(str::to_owned)
+                                .unwrap_or_else(|| {
+                                    option.text().collect::<String>().trim().to_owned()
+                                });
+                            (value, option.value().attr("selected").is_some())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let value = options
+                .iter()
+                .find(|(_, selected)| *selected)
+                .or(options.first())
+                .map(|(value, _)| value.clone())
+                .unwrap_or_default();
+            let options = options.into_iter().map(|(value, _)| value).collect();
+            ("select".to_owned(), value, options)
+        }
+        _ => {
+            let kind = attrs.attr("type").unwrap_or("text").to_ascii_lowercase();
+            if SKIPPED_INPUTS.contains(&kind.as_str()) {
+                return None;
+            }
+            if matches!(kind.as_str(), "checkbox" | "radio") && attrs.attr("checked").is_none() {
+                return None;
+            }
+            let default = if matches!(kind.as_str(), "checkbox" | "radio") {
+                "on"
+            } else {
+                ""
+            };
+            let value = attrs.attr("value").unwrap_or(default).to_owned();
+            (kind, value, Vec::new())
+        }
+    };
+    Some(FormField {
+        name: name.to_owned(),
+        kind,
+        value,
+        options,
+    })
+}
+
+/// A selector whose first match is `form`: by id, name or action when one
+/// of them singles it out, by position among sibling forms otherwise.
+fn form_selector(document: &Html, forms: &[ElementRef], form: ElementRef) -> String {
+    let attrs = form.value();
+    let position = forms
+        .iter()
+        .position(|other| other.id() == form.id())
+        .unwrap_or_default();
+    let mut candidates: Vec<String> = ["id", "name", "action"]
+        .into_iter()
+        .filter_map(|attribute| {
+            let value = attrs.attr(attribute)?;
+            Some(format!("form[{attribute}=\"{}\"]", escape(value)))
+        })
+        .collect();
+    candidates.push(format!("form{}", " ~ form".repeat(position)));
+    candidates
+        .into_iter()
+        .find(|candidate| {
+            Selector::parse(candidate)
+                .ok()
+                .and_then(|selector| document.select(&selector).next())
+                .is_some_and(|first| first.id() == form.id())
+        })
+        .unwrap_or_else(|| "form".to_owned())
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
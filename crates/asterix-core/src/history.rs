@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, PageResponse};
+
+/// Most pages kept in history; the least recently visited are dropped first.
+const HISTORY_CAPACITY: usize = 20_000;
+
+/// A page visited in a regular tab.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub url: Url,
+    pub title: String,
+    pub last_visit: DateTime<Utc>,
+    pub visit_count: u32,
+}
+
+impl BrowserCore {
+    /// History, most recently visited first.
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        let mut history = self.state.read().history.clone();
+        history.sort_by_key(|entry| std::cmp::Reverse(entry.last_visit));
+        history
+    }
+
+    pub fn remove_history_entry(&self, url: &Url) {
+        self.state.write().history.retain(|entry| &entry.url != url);
+    }
+
+    pub fn clear_history(&self) {
+        self.state.write().history.clear();
+    }
+
+    /// Folds `entries` into history, adding up visit counts for pages known
+    /// on both sides. Returns how many pages were new.
+    pub fn merge_history(&self, entries: Vec<HistoryEntry>) -> usize {
+        let mut guard = self.state.write();
+        let before = guard.history.len();
+        for entry in entries {
+            match guard
+                .history
+                .iter_mut()
+                .find(|known| known.url == entry.url)
+            {
+                Some(known) => {
+                    known.visit_count += entry.visit_count;
+                    if entry.last_visit > known.last_visit {
+                        known.last_visit = entry.last_visit;
+                        known.title = entry.title;
+                    }
+                }
+                None => guard.history.push(entry),
+            }
+        }
+        let added = guard.history.len().saturating_sub(before);
+        trim_history(&mut guard.history);
+        added
+    }
+
+    pub fn import_history(&self, mut entries: Vec<HistoryEntry>) {
+        trim_history(&mut entries);
+        self.state.write().history = entries;
+    }
+
+    pub(crate) fn record_visit(&self, page: &PageResponse, title: &str) {
+        let mut guard = self.state.write();
+        match guard.history.iter_mut().find(|entry| entry.url == page.url) {
+            Some(entry) => {
+                entry.visit_count += 1;
+                entry.last_visit = page.received_at;
+                entry.title = title.to_owned();
+            }
+            None => {
+                guard.history.push(HistoryEntry {
+                    url: page.url.clone(),
+                    title: title.to_owned(),
+                    last_visit: page.received_at,
+                    visit_count: 1,
+                });
+                trim_history(&mut guard.history);
+            }
+        }
+    }
+}
+
+fn trim_history(history: &mut Vec<HistoryEntry>) {
+    if history.len() > HISTORY_CAPACITY {
+        history.sort_by_key(|entry| std::cmp::Reverse(entry.last_visit));
+        history.truncate(HISTORY_CAPACITY);
+    }
+}
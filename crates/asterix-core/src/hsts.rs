@@ -0,0 +1,292 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Duration, Utc};
+use reqwest::header::{HeaderMap, STRICT_TRANSPORT_SECURITY};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+use crate::{BrowserCore, BrowserState, TabId};
+
+/// Longest protection a site can ask for; longer `max-age` values are cut
+/// to this, as other browsers do.
+const MAX_AGE_CAP_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// Host that asked, through `Strict-Transport-Security`, to only ever be
+/// reached over HTTPS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HstsEntry {
+    pub host: String,
+    pub include_subdomains: bool,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl HstsEntry {
+    fn covers(&self, host: &str) -> bool {
+        host == self.host
+            || (self.include_subdomains
+                && host
+                    .strip_suffix(self.host.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    }
+}
+
+/// Directives of a `Strict-Transport-Security` value: `max-age` in seconds
+/// and `includeSubDomains`. `None` for values RFC 6797 says to ignore, such
+/// as ones without `max-age` or with a directive given twice.
+fn parse_header(value: &str) -> Option<(i64, bool)> {
+    let mut max_age = None;
+    let mut include_subdomains = false;
+    for directive in value.split(';') {
+        let directive = directive.trim();
+        if directive.is_empty() {
+            continue;
+        }
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (directive, None),
+        };
+        if name.eq_ignore_ascii_case("max-age") {
+            let value = value.filter(|value| {
+                !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit())
+            })?;
+            if max_age.is_some() {
+                return None;
+            }
+            // Absurdly long values are capped rather than refused.
+            max_age = Some(value.parse::<i64>().unwrap_or(i64::MAX));
+        } else if name.eq_ignore_ascii_case("includeSubDomains") {
+            if include_subdomains {
+                return None;
+            }
+            include_subdomains = true;
+        }
+    }
+    Some((max_age?, include_subdomains))
+}
+
+impl BrowserState {
+    /// Notes the `Strict-Transport-Security` header of a response from
+    /// `url`. Only headers received over HTTPS from a named host count.
+    pub(crate) fn record_hsts(&mut self, url: &Url, headers: &HeaderMap) {
+        let Some(value) = headers.get(STRICT_TRANSPORT_SECURITY) else {
+            return;
+        };
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        if url.scheme() != "https" || host.trim_matches(['[', ']']).parse::<IpAddr>().is_ok() {
+            return;
+        }
+        let Some((max_age, include_subdomains)) = value.to_str().ok().and_then(parse_header) else {
+            debug!("ignoring malformed Strict-Transport-Security from {host}");
+            return;
+        };
+        if max_age == 0 {
+            if self.hsts.remove(host).is_some() {
+                debug!("{host} withdrew its HSTS policy");
+            }
+            return;
+        }
+        let entry = HstsEntry {
+            host: host.to_owned(),
+            include_subdomains,
+            expires_at: Utc::now() + Duration::seconds(max_age.min(MAX_AGE_CAP_SECS)),
+        };
+        self.hsts.insert(entry.host.clone(), entry);
+    }
+
+    /// [`record_hsts`](Self::record_hsts) for a response to a request made
+    /// by `tab`; private tabs leave no trace in the store.
+    pub(crate) fn record_tab_hsts(&mut self, tab: Option<TabId>, url: &Url, headers: &HeaderMap) {
+        let private = self
+            .tabs
+            .iter()
+            .any(|snapshot| Some(snapshot.id) == tab && snapshot.private);
+        if !private {
+            self.record_hsts(url, headers);
+        }
+    }
+
+    /// `url` switched to `https:` when it is an `http:` URL of a host under
+    /// HSTS, which must not be reached in cleartext.
+    pub(crate) fn hsts_upgrade(&self, url: &Url) -> Option<Url> {
+        if url.scheme() != "http" {
+            return None;
+        }
+        self.hsts_entry_for(url.host_str()?)?;
+        let mut upgraded = url.clone();
+        to_https(&mut upgraded).then_some(upgraded)
+    }
+
+    /// The unexpired entry protecting `host`, if any.
+    fn hsts_entry_for(&self, host: &str) -> Option<&HstsEntry> {
+        let now = Utc::now();
+        let mut domain = host;
+        loop {
+            if let Some(entry) = self
+                .hsts
+                .get(domain)
+                .filter(|entry| entry.expires_at > now && entry.covers(host))
+            {
+                return Some(entry);
+            }
+            domain = domain.split_once('.')?.1;
+        }
+    }
+}
+
+/// Switches an `http:` URL to `https:`. The default HTTP port maps to the
+/// default HTTPS port; other ports stay.
+pub(crate) fn to_https(url: &mut Url) -> bool {
+    if url.set_scheme("https").is_err() {
+        return false;
+    }
+    if url.port() == Some(80) {
+        let _ = url.set_port(None);
+    }
+    true
+}
+
+impl BrowserCore {
+    /// Rewrites an `http:` URL of a host under HSTS to `https:`, before it
+    /// reaches the network. Returns whether it did.
+    pub(crate) fn upgrade_to_https(&self, url: &mut Url) -> bool {
+        let Some(upgraded) = self.state.read().hsts_upgrade(url) else {
+            return false;
+        };
+        debug!("upgraded {url} to HTTPS for HSTS");
+        *url = upgraded;
+        true
+    }
+
+    /// Whether `host` may only be reached over HTTPS, which also rules out
+    /// proceeding past certificate errors.
+    pub fn is_hsts_host(&self, host: &str) -> bool {
+        self.state.read().hsts_entry_for(host).is_some()
+    }
+
+    /// Unexpired HSTS entries, sorted by host.
+    pub fn hsts_entries(&self) -> Vec<HstsEntry> {
+        let now = Utc::now();
+        let mut entries: Vec<HstsEntry> = self
+            .state
+            .read()
+            .hsts
+            .values()
+            .filter(|entry| entry.expires_at > now)
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| a.host.cmp(&b.host));
+        entries
+    }
+
+    /// Replaces the store, e.g. with entries saved by an earlier session.
+    pub fn import_hsts(&self, entries: Vec<HstsEntry>) {
+        let now = Utc::now();
+        self.state.write().hsts = entries
+            .into_iter()
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| (entry.host.clone(), entry))
+            .collect();
+    }
+
+    /// Forgets the HSTS policy of `host`; it applies again the next time the
+    /// site sends its header.
+    pub fn remove_hsts_entry(&self, host: &str) {
+        self.state.write().hsts.remove(host);
+    }
+
+    pub fn clear_hsts(&self) {
+        self.state.write().hsts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            STRICT_TRANSPORT_SECURITY,
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn parses_directives() {
+        assert_eq!(parse_header("max-age=31536000"), Some((31_536_000, false)));
+        assert_eq!(
+            parse_header(" Max-Age=\"600\" ; includeSubDomains ; preload"),
+            Some((600, true))
+        );
+        assert_eq!(parse_header("max-age=0"), Some((0, false)));
+        assert_eq!(
+            parse_header("max-age=99999999999999999999999"),
+            Some((i64::MAX, false))
+        );
+    }
+
+    #[test]
+    fn ignores_malformed_values() {
+        assert_eq!(parse_header(""), None);
+        assert_eq!(parse_header("includeSubDomains"), None);
+        assert_eq!(parse_header("max-age="), None);
+        assert_eq!(parse_header("max-age=-1"), None);
+        assert_eq!(parse_header("max-age=1.5"), None);
+        assert_eq!(parse_header("max-age=1; max-age=2"), None);
+        assert_eq!(
+            parse_header("max-age=1; includeSubDomains; includeSubDomains"),
+            None
+        );
+    }
+
+    #[test]
+    fn records_only_https_responses_from_named_hosts() {
+        let mut state = BrowserState::default();
+        let policy = headers("max-age=600; includeSubDomains");
+        state.record_hsts(&Url::parse("http://plain.test/").unwrap(), &policy);
+        state.record_hsts(&Url::parse("https://127.0.0.1/").unwrap(), &policy);
+        state.record_hsts(&Url::parse("https://[::1]/").unwrap(), &policy);
+        assert!(state.hsts.is_empty());
+
+        state.record_hsts(&Url::parse("https://secure.test/").unwrap(), &policy);
+        assert!(state.hsts_entry_for("secure.test").is_some());
+        assert!(state.hsts_entry_for("a.b.secure.test").is_some());
+        assert!(state.hsts_entry_for("insecure.test").is_none());
+        assert!(state.hsts_entry_for("plain.test").is_none());
+    }
+
+    #[test]
+    fn max_age_zero_withdraws_the_policy() {
+        let mut state = BrowserState::default();
+        let url = Url::parse("https://secure.test/").unwrap();
+        state.record_hsts(&url, &headers("max-age=600"));
+        state.record_hsts(&url, &headers("max-age=0"));
+        assert!(state.hsts_entry_for("secure.test").is_none());
+    }
+
+    #[test]
+    fn upgrades_only_covered_hosts() {
+        let mut state = BrowserState::default();
+        state.record_hsts(
+            &Url::parse("https://secure.test/").unwrap(),
+            &headers("max-age=600"),
+        );
+        let upgraded = state.hsts_upgrade(&Url::parse("http://secure.test:80/a?b").unwrap());
+        assert_eq!(upgraded.unwrap().as_str(), "https://secure.test/a?b");
+        let custom_port = state.hsts_upgrade(&Url::parse("http://secure.test:8080/").unwrap());
+        assert_eq!(custom_port.unwrap().as_str(), "https://secure.test:8080/");
+        // Without includeSubDomains, subdomains are on their own.
+        assert!(state
+            .hsts_upgrade(&Url::parse("http://www.secure.test/").unwrap())
+            .is_none());
+        assert!(state
+            .hsts_upgrade(&Url::parse("https://secure.test/").unwrap())
+            .is_none());
+    }
+}
@@ -0,0 +1,555 @@
+use std::collections::HashMap;
+
+use base64::Engine;
+use md5::Md5;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::BrowserCore;
+
+/// Authentication scheme a server asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthScheme {
+    /// Username and password sent with every request, readable by anyone
+    /// on the way unless the connection is encrypted.
+    Basic,
+    /// Proof of knowing the password, without sending it.
+    Digest,
+}
+
+/// Request for a username and password from a server, answered with
+/// [`BrowserCore::answer_auth_challenge`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthChallenge {
+    /// Address that was refused.
+    pub url: Url,
+    pub scheme: AuthScheme,
+    /// Name the server gives the protected area, shown to the user so they
+    /// know which password it wants.
+    pub realm: String,
+}
+
+impl AuthChallenge {
+    /// Challenge of the `401 Unauthorized` response from `url`, if it asks
+    /// for a scheme the browser speaks.
+    pub(crate) fn of_response(url: &Url, status: StatusCode, headers: &HeaderMap) -> Option<Self> {
+        if status != StatusCode::UNAUTHORIZED {
+            return None;
+        }
+        let challenge = Challenge::preferred(headers)?;
+        Some(Self {
+            url: url.clone(),
+            scheme: challenge.scheme,
+            realm: challenge.param("realm").unwrap_or_default().to_owned(),
+        })
+    }
+}
+
+/// One challenge of a `WWW-Authenticate` header.
+#[derive(Debug, Clone)]
+struct Challenge {
+    scheme: AuthScheme,
+    /// Parameters by lowercase name.
+    params: HashMap<String, String>,
+}
+
+impl Challenge {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// Strongest supported challenge among those `headers` offer: Digest
+    /// with an algorithm and protection the browser knows, else Basic.
+    fn preferred(headers: &HeaderMap) -> Option<Self> {
+        let challenges: Vec<Self> = headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(parse_challenges)
+            .collect();
+        let digest = challenges.iter().find(|challenge| {
+            challenge.scheme == AuthScheme::Digest
+                && DigestAlgorithm::of(challenge).is_some()
+                && challenge.param("qop").is_none_or(|qop| {
+                    qop.split(',')
+                        .any(|option| option.trim().eq_ignore_ascii_case("auth"))
+                })
+        });
+        digest
+            .or_else(|| {
+                challenges
+                    .iter()
+                    .find(|challenge| challenge.scheme == AuthScheme::Basic)
+            })
+            .cloned()
+    }
+}
+
+/// Splits a `WWW-Authenticate` value into its challenges, skipping schemes
+/// the browser does not speak.
+fn parse_challenges(value: &str) -> Vec<Challenge> {
+    let mut challenges: Vec<Option<Challenge>> = Vec::new();
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        if rest.is_empty() {
+            break;
+        }
+        let end = rest
+            .find(|c: char| c == '=' || c == ',' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let (token, after) = rest.split_at(end);
+        let after = after.trim_start();
+        let Some(value) = after.strip_prefix('=') else {
+            // A token on its own starts the next challenge.
+            let scheme = if token.eq_ignore_ascii_case("basic") {
+                Some(AuthScheme::Basic)
+            } else if token.eq_ignore_ascii_case("digest") {
+                Some(AuthScheme::Digest)
+            } else {
+                None
+            };
+            challenges.push(scheme.map(|scheme| Challenge {
+                scheme,
+                params: HashMap::new(),
+            }));
+            rest = after;
+            continue;
+        };
+        let (param, after) = parse_param_value(value.trim_start());
+        if let Some(Some(challenge)) = challenges.last_mut() {
+            challenge.params.insert(token.to_ascii_lowercase(), param);
+        }
+        rest = after;
+    }
+    challenges.into_iter().flatten().collect()
+}
+
+/// Reads a token or quoted string off the front of `input`.
+fn parse_param_value(input: &str) -> (String, &str) {
+    let Some(quoted) = input.strip_prefix('"') else {
+        let end = input
+            .find(|c: char| c == ',' || c.is_whitespace())
+            .unwrap_or(input.len());
+        return (input[..end].to_owned(), &input[end..]);
+    };
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, escaped)) = chars.next() {
+                    value.push(escaped);
+                }
+            }
+            '"' => return (value, &quoted[index + 1..]),
+            c => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DigestAlgorithm {
+    Md5,
+    Md5Session,
+    Sha256,
+    Sha256Session,
+}
+
+impl DigestAlgorithm {
+    /// Algorithm `challenge` asks for, MD5 when it names none.
+    fn of(challenge: &Challenge) -> Option<Self> {
+        match challenge
+            .param("algorithm")
+            .unwrap_or("MD5")
+            .to_ascii_uppercase()
+            .as_str()
+        {
+            "MD5" => Some(Self::Md5),
+            "MD5-SESS" => Some(Self::Md5Session),
+            "SHA-256" => Some(Self::Sha256),
+            "SHA-256-SESS" => Some(Self::Sha256Session),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Session => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Session => "SHA-256-sess",
+        }
+    }
+
+    fn hash(self, input: &str) -> String {
+        let digest: Vec<u8> = match self {
+            Self::Md5 | Self::Md5Session => Md5::digest(input.as_bytes()).to_vec(),
+            Self::Sha256 | Self::Sha256Session => Sha256::digest(input.as_bytes()).to_vec(),
+        };
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+/// Username and password given for a protected area.
+#[derive(Debug, Clone)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+/// Credentials given for one realm of one origin, used for every request
+/// below the directory that first asked for them.
+#[derive(Debug)]
+struct ProtectionSpace {
+    /// Scheme, host and port, as serialized by [`Url::origin`].
+    origin: String,
+    realm: String,
+    /// Path prefix requests are answered for ahead of a challenge.
+    directory: String,
+    scheme: AuthScheme,
+    credentials: Credentials,
+    /// Last Digest challenge of the realm, once one has been answered.
+    digest: Option<Challenge>,
+    /// Requests sent with the current Digest nonce.
+    nonce_count: u32,
+}
+
+impl ProtectionSpace {
+    fn covers(&self, url: &Url) -> bool {
+        url.origin().ascii_serialization() == self.origin && url.path().starts_with(&self.directory)
+    }
+
+    /// `Authorization` value for a `method` request to `url`, or `None`
+    /// while no Digest challenge has been seen to answer.
+    fn authorization(&mut self, method: &str, url: &Url) -> Option<String> {
+        if self.scheme == AuthScheme::Basic {
+            let Credentials { username, password } = &self.credentials;
+            let token =
+                base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+            return Some(format!("Basic {token}"));
+        }
+        let challenge = self.digest.as_ref()?;
+        let algorithm = DigestAlgorithm::of(challenge)?;
+        self.nonce_count += 1;
+        let nonce = challenge.param("nonce").unwrap_or_default();
+        let realm = challenge.param("realm").unwrap_or_default();
+        let uri = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_owned(),
+        };
+        let cnonce = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        let nc = format!("{:08x}", self.nonce_count);
+        let Credentials { username, password } = &self.credentials;
+        let mut ha1 = algorithm.hash(&format!("{username}:{realm}:{password}"));
+        if matches!(
+            algorithm,
+            DigestAlgorithm::Md5Session | DigestAlgorithm::Sha256Session
+        ) {
+            ha1 = algorithm.hash(&format!("{ha1}:{nonce}:{cnonce}"));
+        }
+        let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+        let with_qop = challenge.param("qop").is_some();
+        let response = if with_qop {
+            algorithm.hash(&format!("{ha1}:{nonce}:{nc}:{cnonce}:auth:{ha2}"))
+        } else {
+            algorithm.hash(&format!("{ha1}:{nonce}:{ha2}"))
+        };
+        let quote = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{response}\"",
+            quote(username),
+            quote(realm),
+            quote(nonce),
+            quote(&uri),
+            algorithm.name(),
+        );
+        if let Some(opaque) = challenge.param("opaque") {
+            header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+        }
+        if with_qop {
+            header.push_str(&format!(", qop=auth, nc={nc}, cnonce=\"{cnonce}\""));
+        }
+        Some(header)
+    }
+}
+
+/// Credentials given this session, by protection space.
+#[derive(Debug, Default)]
+pub(crate) struct HttpAuth {
+    spaces: Vec<ProtectionSpace>,
+}
+
+impl HttpAuth {
+    /// Adds the credentials known for `request`'s address, so areas the
+    /// user signed in to are not challenged again on every request.
+    pub(crate) fn authorize(&mut self, request: &mut reqwest::Request) {
+        if request.headers().contains_key(AUTHORIZATION) {
+            return;
+        }
+        let method = request.method().to_string();
+        let url = request.url().clone();
+        let authorization = self
+            .spaces
+            .iter_mut()
+            .filter(|space| space.covers(&url))
+            .max_by_key(|space| space.directory.len())
+            .and_then(|space| space.authorization(&method, &url));
+        if let Some(value) = authorization.and_then(|value| HeaderValue::from_str(&value).ok()) {
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+    }
+
+    /// Answers the challenge of a `401` response to `request` with
+    /// credentials given earlier for its realm, returning whether there
+    /// is an answer worth sending. Credentials the server just refused are
+    /// not tried again, unless all it refused was an expired nonce.
+    pub(crate) fn answer(&mut self, request: &mut reqwest::Request, headers: &HeaderMap) -> bool {
+        let Some(challenge) = Challenge::preferred(headers) else {
+            return false;
+        };
+        let url = request.url().clone();
+        let origin = url.origin().ascii_serialization();
+        let realm = challenge.param("realm").unwrap_or_default();
+        let Some(space) = self
+            .spaces
+            .iter_mut()
+            .find(|space| space.origin == origin && space.realm == realm)
+        else {
+            return false;
+        };
+        let stale = challenge
+            .param("stale")
+            .is_some_and(|stale| stale.eq_ignore_ascii_case("true"));
+        if request.headers().contains_key(AUTHORIZATION) && !stale {
+            return false;
+        }
+        space.scheme = challenge.scheme;
+        space.digest = (challenge.scheme == AuthScheme::Digest).then_some(challenge);
+        space.nonce_count = 0;
+        let method = request.method().to_string();
+        let Some(value) = space
+            .authorization(&method, &url)
+            .and_then(|value| HeaderValue::from_str(&value).ok())
+        else {
+            return false;
+        };
+        request.headers_mut().insert(AUTHORIZATION, value);
+        true
+    }
+}
+
+/// Directory of `url`'s path, including the trailing slash.
+fn directory_of(url: &Url) -> String {
+    let path = url.path();
+    path[..path.rfind('/').map_or(0, |slash| slash + 1)].to_owned()
+}
+
+impl BrowserCore {
+    /// Remembers `username` and `password` for the realm `challenge` came
+    /// from until the browser exits. Loading the refused address again
+    /// signs in with them.
+    pub fn answer_auth_challenge(&self, challenge: &AuthChallenge, username: &str, password: &str) {
+        let origin = challenge.url.origin().ascii_serialization();
+        let mut guard = self.state.write();
+        let spaces = &mut guard.http_auth.spaces;
+        spaces.retain(|space| !(space.origin == origin && space.realm == challenge.realm));
+        spaces.push(ProtectionSpace {
+            origin,
+            realm: challenge.realm.clone(),
+            directory: directory_of(&challenge.url),
+            scheme: challenge.scheme,
+            credentials: Credentials {
+                username: username.to_owned(),
+                password: password.to_owned(),
+            },
+            digest: None,
+            nonce_count: 0,
+        });
+    }
+
+    /// Forgets every password given to sites this session.
+    pub fn clear_http_credentials(&self) {
+        self.state.write().http_auth.spaces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Method;
+
+    use super::*;
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(WWW_AUTHENTICATE, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn space(url: &str, scheme: AuthScheme) -> ProtectionSpace {
+        let url = Url::parse(url).unwrap();
+        ProtectionSpace {
+            origin: url.origin().ascii_serialization(),
+            realm: "testrealm@host.com".to_owned(),
+            directory: directory_of(&url),
+            scheme,
+            credentials: Credentials {
+                username: "Mufasa".to_owned(),
+                password: "CircleOfLife".to_owned(),
+            },
+            digest: None,
+            nonce_count: 0,
+        }
+    }
+
+    #[test]
+    fn parses_several_challenges_in_one_value() {
+        let challenges = parse_challenges(
+            r#"Newauth realm="apps", type=1, Basic realm="simple", Digest realm="a \"quoted\" realm", nonce=abc, qop="auth,auth-int""#,
+        );
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].scheme, AuthScheme::Basic);
+        assert_eq!(challenges[0].param("realm"), Some("simple"));
+        assert_eq!(challenges[1].scheme, AuthScheme::Digest);
+        assert_eq!(challenges[1].param("realm"), Some("a \"quoted\" realm"));
+        assert_eq!(challenges[1].param("nonce"), Some("abc"));
+        assert_eq!(challenges[1].param("qop"), Some("auth,auth-int"));
+    }
+
+    #[test]
+    fn prefers_digest_it_can_answer() {
+        let both = headers(&[r#"Basic realm="r""#, r#"Digest realm="r", nonce="n""#]);
+        let challenge = Challenge::preferred(&both).unwrap();
+        assert_eq!(challenge.scheme, AuthScheme::Digest);
+
+        for digest in [
+            r#"Digest realm="r", nonce="n", algorithm=SHA-512-256"#,
+            r#"Digest realm="r", nonce="n", qop="auth-int""#,
+        ] {
+            let challenge = Challenge::preferred(&headers(&[digest, r#"Basic realm="r""#]));
+            assert_eq!(challenge.unwrap().scheme, AuthScheme::Basic);
+        }
+        assert!(
+            Challenge::preferred(&headers(&["Bearer realm=\"api\"", "Negotiate abc=="])).is_none()
+        );
+    }
+
+    #[test]
+    fn malformed_and_truncated_values_do_not_panic() {
+        for value in [
+            "",
+            ",,,",
+            "=",
+            "\"",
+            "Basic realm=",
+            "Basic =x",
+            r#"Digest realm="unterminated"#,
+            r#"Digest realm="escape at the end\"#,
+            "Digest realm=\"\u{e9}t\u{e9}\", nonce",
+        ] {
+            let _ = parse_challenges(value);
+        }
+        let challenges = parse_challenges(r#"Digest realm="unterminated"#);
+        assert_eq!(challenges[0].param("realm"), Some("unterminated"));
+        let challenges = parse_challenges("Basic realm=");
+        assert_eq!(challenges[0].param("realm"), Some(""));
+    }
+
+    #[test]
+    fn only_unauthorized_responses_challenge() {
+        let url = Url::parse("https://host.test/private/").unwrap();
+        let offered = headers(&[r#"Basic realm="staff""#]);
+        let challenge = AuthChallenge::of_response(&url, StatusCode::UNAUTHORIZED, &offered);
+        assert_eq!(challenge.unwrap().realm, "staff");
+        assert!(AuthChallenge::of_response(&url, StatusCode::FORBIDDEN, &offered).is_none());
+        assert!(
+            AuthChallenge::of_response(&url, StatusCode::UNAUTHORIZED, &HeaderMap::new()).is_none()
+        );
+    }
+
+    #[test]
+    fn answers_digest_as_rfc_2069_does() {
+        let url = "http://host.test/dir/index.html";
+        let mut space = space(url, AuthScheme::Digest);
+        space.digest = parse_challenges(
+            r#"Digest realm="testrealm@host.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        )
+        .pop();
+        let header = space
+            .authorization("GET", &Url::parse(url).unwrap())
+            .unwrap();
+        assert!(header.contains(r#"response="1949323746fe6a43ef61f9606e7febea""#));
+        assert!(header.contains(r#"uri="/dir/index.html""#));
+        assert!(header.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+        assert!(!header.contains("qop="));
+    }
+
+    #[test]
+    fn counts_requests_per_nonce() {
+        let url = Url::parse("http://host.test/dir/").unwrap();
+        let mut space = space(url.as_str(), AuthScheme::Digest);
+        assert!(space.authorization("GET", &url).is_none());
+        space.digest = parse_challenges(r#"Digest realm="r", nonce="n", qop="auth""#).pop();
+        assert!(space
+            .authorization("GET", &url)
+            .unwrap()
+            .contains("nc=00000001"));
+        assert!(space
+            .authorization("GET", &url)
+            .unwrap()
+            .contains("nc=00000002"));
+    }
+
+    #[test]
+    fn signs_in_below_the_challenged_directory_only() {
+        let mut auth = HttpAuth::default();
+        auth.spaces.push(space(
+            "https://host.test/private/index.html",
+            AuthScheme::Basic,
+        ));
+        let mut inside = reqwest::Request::new(
+            Method::GET,
+            Url::parse("https://host.test/private/a/b").unwrap(),
+        );
+        auth.authorize(&mut inside);
+        assert_eq!(
+            inside.headers()[AUTHORIZATION],
+            "Basic TXVmYXNhOkNpcmNsZU9mTGlmZQ=="
+        );
+        for outside in ["https://host.test/public/", "http://host.test/private/"] {
+            let mut request = reqwest::Request::new(Method::GET, Url::parse(outside).unwrap());
+            auth.authorize(&mut request);
+            assert!(!request.headers().contains_key(AUTHORIZATION));
+        }
+    }
+
+    #[test]
+    fn refused_credentials_are_retried_only_for_a_stale_nonce() {
+        let mut auth = HttpAuth::default();
+        auth.spaces
+            .push(space("https://host.test/dir/", AuthScheme::Digest));
+        let url = Url::parse("https://host.test/dir/page").unwrap();
+        let challenge = r#"Digest realm="testrealm@host.com", nonce="one""#;
+        let mut request = reqwest::Request::new(Method::GET, url.clone());
+        assert!(auth.answer(&mut request, &headers(&[challenge])));
+        // The server refused the answer it was just sent.
+        assert!(!auth.answer(&mut request, &headers(&[challenge])));
+        let stale = r#"Digest realm="testrealm@host.com", nonce="two", stale=true"#;
+        assert!(auth.answer(&mut request, &headers(&[stale])));
+        assert!(request.headers()[AUTHORIZATION]
+            .to_str()
+            .unwrap()
+            .contains(r#"nonce="two""#));
+        // Realms nobody signed in to are left to the user.
+        let other = r#"Basic realm="elsewhere""#;
+        let mut request = reqwest::Request::new(Method::GET, url);
+        assert!(!auth.answer(&mut request, &headers(&[other])));
+    }
+}
@@ -0,0 +1,479 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::cache_store::BlockStore;
+use crate::network::{self, LoggedResponse};
+use crate::{
+    sniff_mime_type, BrowserCore, BrowserError, CachePolicy, FetchProgress, PageRefresh,
+    PageRequest, PageResponse, RequestKind, TabOptions,
+};
+
+/// Total body size the cache keeps before dropping its oldest entries.
+const MAX_CACHE_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Most stale documents revalidated in one go after the network returns.
+const MAX_REVALIDATIONS: usize = 50;
+
+/// Upper bound on the freshness guessed from `Last-Modified`.
+const MAX_HEURISTIC_FRESHNESS_SECS: i64 = 24 * 60 * 60;
+
+/// How a page was obtained, with respect to the HTTP cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheStatus {
+    /// Fetched in full from the network.
+    #[default]
+    Miss,
+    /// Served from a stored or preloaded copy without a request.
+    Hit,
+    /// Served from a stored copy the server confirmed unchanged.
+    Revalidated,
+}
+
+/// Stored response, kept in front of its body in a block file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CacheEntry {
+    pub(crate) url: Url,
+    status: u16,
+    headers: Vec<(String, String)>,
+    pub(crate) stored_at: DateTime<Utc>,
+    /// Body size in bytes.
+    pub(crate) size: u64,
+}
+
+impl CacheEntry {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn date(&self, name: &str) -> Option<DateTime<Utc>> {
+        let value = self.header(name)?;
+        DateTime::parse_from_rfc2822(value)
+            .ok()
+            .map(|date| date.with_timezone(&Utc))
+    }
+
+    /// How long the response may be served without asking the server.
+    fn freshness_lifetime(&self) -> Duration {
+        let directives = cache_directives(self.header("cache-control"));
+        if directives.iter().any(|(name, _)| name == "no-cache") {
+            return Duration::zero();
+        }
+        if let Some(max_age) = directives
+            .iter()
+            .find(|(name, _)| name == "max-age")
+            .and_then(|(_, value)| value.as_deref()?.parse::<i64>().ok())
+        {
+            return Duration::seconds(max_age);
+        }
+        let date = self.date("date").unwrap_or(self.stored_at);
+        if self.header("expires").is_some() {
+            // An unparsable `Expires`, such as `0`, means already expired.
+            return self
+                .date("expires")
+                .map_or_else(Duration::zero, |expires| expires - date);
+        }
+        match self.date("last-modified") {
+            Some(modified) if modified < date => {
+                ((date - modified) / 10).min(Duration::seconds(MAX_HEURISTIC_FRESHNESS_SECS))
+            }
+            _ => Duration::zero(),
+        }
+    }
+
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        let age = self
+            .header("age")
+            .and_then(|age| age.trim().parse::<i64>().ok())
+            .map_or_else(Duration::zero, Duration::seconds);
+        (now - self.stored_at) + age < self.freshness_lifetime()
+    }
+
+    fn add_validators(&self, mut builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(etag) = self.header("etag") {
+            builder = builder.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = self.header("last-modified") {
+            builder = builder.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+        }
+        builder
+    }
+
+    fn has_validators(&self) -> bool {
+        self.header("etag").is_some() || self.header("last-modified").is_some()
+    }
+}
+
+/// Summary of a response kept in the HTTP cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedDocument {
+    pub url: Url,
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Body size in bytes.
+    pub size: u64,
+    pub stored_at: DateTime<Utc>,
+    /// Whether the copy can still be served without asking the server.
+    pub fresh: bool,
+}
+
+/// On-disk store of fetched documents, reused and revalidated following
+/// their `Cache-Control`, `Expires`, `ETag` and `Last-Modified` headers.
+///
+/// Documents are packed into memory-mapped block files (see
+/// [`BlockStore`]); the least recently used are evicted past
+/// [`MAX_CACHE_BYTES`], and files left mostly dead are compacted in the
+/// background.
+#[derive(Debug)]
+pub struct HttpCache {
+    dir: PathBuf,
+    store: Arc<Mutex<BlockStore>>,
+    compacting: Arc<AtomicBool>,
+}
+
+impl HttpCache {
+    /// Opens the cache kept in `dir`, creating the directory if needed.
+    pub fn open(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        let store = BlockStore::open(&dir)?;
+        Ok(Self {
+            dir,
+            store: Arc::new(Mutex::new(store)),
+            compacting: Arc::default(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of stored responses and the size of their bodies in bytes.
+    pub fn usage(&self) -> (usize, u64) {
+        let store = self.store.lock();
+        let sizes: Vec<u64> = store.entries().map(|entry| entry.size).collect();
+        (sizes.len(), sizes.iter().sum())
+    }
+
+    /// Every stored response, most recently stored first.
+    pub fn documents(&self) -> Vec<CachedDocument> {
+        let now = Utc::now();
+        let mut documents: Vec<CachedDocument> = self
+            .store
+            .lock()
+            .entries()
+            .map(|entry| CachedDocument {
+                url: entry.url.clone(),
+                status: entry.status,
+                content_type: entry.header("content-type").map(ToOwned::to_owned),
+                size: entry.size,
+                stored_at: entry.stored_at,
+                fresh: entry.is_fresh(now),
+            })
+            .collect();
+        documents.sort_by_key(|document| std::cmp::Reverse(document.stored_at));
+        documents
+    }
+
+    /// Whether a copy of `url` can be served without asking the server.
+    pub(crate) fn is_fresh(&self, url: &Url) -> bool {
+        self.store
+            .lock()
+            .entry(url)
+            .is_some_and(|entry| entry.is_fresh(Utc::now()))
+    }
+
+    /// Deletes every stored response.
+    pub fn clear(&self) {
+        self.store.lock().clear();
+    }
+
+    fn get(&self, url: &Url) -> Option<(CacheEntry, Bytes)> {
+        self.store.lock().get(url)
+    }
+
+    /// Stores `response` for `url` if its headers allow it, replacing any
+    /// earlier copy either way.
+    fn store(&self, url: &Url, response: &LoggedResponse) {
+        if !is_storable(response.status, &response.headers) {
+            self.store.lock().remove(url);
+            return;
+        }
+        let entry = CacheEntry {
+            url: url.clone(),
+            status: response.status.as_u16(),
+            headers: network::header_pairs(&response.headers),
+            stored_at: Utc::now(),
+            size: response.body.len() as u64,
+        };
+        {
+            let mut store = self.store.lock();
+            if let Err(err) = store.insert(entry, &response.body) {
+                warn!("failed to cache {url}: {err}");
+                store.remove(url);
+                return;
+            }
+            store.evict_to(MAX_CACHE_BYTES);
+        }
+        self.compact_in_background();
+    }
+
+    /// Refreshes `entry` with the headers of a `304 Not Modified` answer.
+    fn revalidate(&self, mut entry: CacheEntry, headers: &HeaderMap) -> CacheEntry {
+        for (name, value) in network::header_pairs(headers) {
+            entry
+                .headers
+                .retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+            entry.headers.push((name, value));
+        }
+        entry.stored_at = Utc::now();
+        self.store.lock().update(entry.clone());
+        entry
+    }
+
+    /// Moves the live records out of mostly dead block files and deletes
+    /// them, off the caller's thread when there is a runtime to run it on.
+    fn compact_in_background(&self) {
+        if self.store.lock().compaction_candidate().is_none()
+            || self.compacting.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        let store = Arc::clone(&self.store);
+        let compacting = Arc::clone(&self.compacting);
+        let compact = move || {
+            // The lock is taken per record so that pages keep loading.
+            loop {
+                let candidate = store.lock().compaction_candidate();
+                let Some(file) = candidate else {
+                    break;
+                };
+                let urls = store.lock().urls_in(file);
+                debug!(
+                    "compacting cache block file {file} with {} documents",
+                    urls.len()
+                );
+                for url in urls {
+                    store.lock().relocate(&url, file);
+                }
+                store.lock().delete_file(file);
+            }
+            compacting.store(false, Ordering::Release);
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(compact);
+            }
+            Err(_) => compact(),
+        }
+    }
+}
+
+impl Drop for HttpCache {
+    fn drop(&mut self) {
+        let mut store = self.store.lock();
+        if store.has_unflushed_changes() {
+            store.flush();
+        }
+    }
+}
+
+impl BrowserCore {
+    /// The HTTP cache documents are stored in, if the browser keeps one.
+    pub fn http_cache(&self) -> Option<&HttpCache> {
+        self.http_cache.as_ref()
+    }
+
+    /// Documents preloaded for upcoming navigations, with when each stops
+    /// being used.
+    pub fn preloaded_documents(&self) -> Vec<(Url, DateTime<Utc>)> {
+        let now = Utc::now();
+        let mut preloaded: Vec<(Url, DateTime<Utc>)> = self
+            .state
+            .read()
+            .preloaded
+            .iter()
+            .filter(|(_, (_, expires_at))| *expires_at > now)
+            .map(|(url, (_, expires_at))| (url.clone(), *expires_at))
+            .collect();
+        preloaded.sort_by_key(|(_, expires_at)| *expires_at);
+        preloaded
+    }
+
+    /// Empties the HTTP cache and drops preloaded documents.
+    pub fn clear_http_cache(&self) {
+        if let Some(cache) = &self.http_cache {
+            cache.clear();
+        }
+        self.state.write().preloaded.clear();
+    }
+
+    /// Asks the servers whether stale cached documents that carry validators
+    /// have changed, refreshing the stored copies, e.g. once the network is
+    /// back after an outage. Returns how many copies were brought up to date.
+    pub async fn revalidate_stale_documents(&self) -> usize {
+        let Some(cache) = &self.http_cache else {
+            return 0;
+        };
+        let now = Utc::now();
+        let mut stale: Vec<CacheEntry> = cache
+            .store
+            .lock()
+            .entries()
+            .filter(|entry| entry.has_validators() && !entry.is_fresh(now))
+            .cloned()
+            .collect();
+        stale.sort_by_key(|entry| std::cmp::Reverse(entry.stored_at));
+        let mut refreshed = 0;
+        for entry in stale.into_iter().take(MAX_REVALIDATIONS) {
+            let url = entry.url.clone();
+            let mut builder = entry.add_validators(self.client_for(&url, false).get(url.clone()));
+            if let Some(ua) = self.user_agent_for(None, &url) {
+                builder = builder.header(reqwest::header::USER_AGENT, ua);
+            }
+            match network::send_logged(&self.state, builder, RequestKind::Revalidation, None).await
+            {
+                Ok(response) if response.status == StatusCode::NOT_MODIFIED => {
+                    cache.revalidate(entry, &response.headers);
+                    refreshed += 1;
+                }
+                Ok(response) => {
+                    cache.store(&url, &response);
+                    refreshed += 1;
+                }
+                Err(err) => debug!("revalidating {url} failed: {err}"),
+            }
+        }
+        refreshed
+    }
+
+    /// Fetches `request` through the HTTP cache: fresh copies are served as
+    /// they are, stale ones are revalidated and new responses stored.
+    pub(crate) async fn fetch_through_cache(
+        &self,
+        cache: &HttpCache,
+        request: &PageRequest,
+        options: &TabOptions,
+        progress: &FetchProgress,
+    ) -> Result<PageResponse, BrowserError> {
+        let cached = match request.cache {
+            CachePolicy::Reload => None,
+            CachePolicy::Default | CachePolicy::OnlyIfCached => cache.get(&request.url),
+        };
+        match cached {
+            Some((entry, body))
+                if request.cache == CachePolicy::OnlyIfCached || entry.is_fresh(Utc::now()) =>
+            {
+                progress.complete(body.len());
+                return Ok(cached_page(&request.url, &entry, body, CacheStatus::Hit));
+            }
+            None if request.cache == CachePolicy::OnlyIfCached => {
+                return Err(BrowserError::NotCached(request.url.clone()));
+            }
+            _ => {}
+        }
+
+        let mut builder = self.request_builder(request, options)?;
+        if let Some((entry, _)) = cached.as_ref().filter(|(entry, _)| entry.has_validators()) {
+            builder = entry.add_validators(builder);
+        }
+        let response = network::send_tracked(
+            &self.state,
+            builder,
+            RequestKind::Document,
+            Some(request.tab),
+            Some(progress),
+        )
+        .await?;
+        if let (StatusCode::NOT_MODIFIED, Some((entry, body))) = (response.status, cached) {
+            let entry = cache.revalidate(entry, &response.headers);
+            progress.complete(body.len());
+            return Ok(cached_page(
+                &request.url,
+                &entry,
+                body,
+                CacheStatus::Revalidated,
+            ));
+        }
+        cache.store(&request.url, &response);
+        Ok(PageResponse::from_network(&request.url, response))
+    }
+}
+
+fn cached_page(url: &Url, entry: &CacheEntry, body: Bytes, cache: CacheStatus) -> PageResponse {
+    let no_sniff = entry
+        .header("x-content-type-options")
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("nosniff"));
+    let mime_type = sniff_mime_type(entry.header("content-type"), no_sniff, &body);
+    PageResponse {
+        cache,
+        refresh: entry
+            .header("refresh")
+            .and_then(|value| PageRefresh::parse(value, url)),
+        ..PageResponse::from_bytes(url.clone(), entry.status, mime_type, body)
+    }
+}
+
+/// Whether a browser cache may keep a response with `status` and `headers`.
+fn is_storable(status: StatusCode, headers: &HeaderMap) -> bool {
+    if status != StatusCode::OK {
+        return false;
+    }
+    let header = |name| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let cache_control = header(reqwest::header::CACHE_CONTROL);
+    let directives = cache_directives(Some(&cache_control));
+    if directives.iter().any(|(name, _)| name == "no-store") {
+        return false;
+    }
+    // Requests only ever differ by encoding, which the client undoes.
+    let vary = header(reqwest::header::VARY);
+    if vary
+        .split(',')
+        .map(str::trim)
+        .any(|field| !field.is_empty() && !field.eq_ignore_ascii_case("accept-encoding"))
+    {
+        return false;
+    }
+    [
+        reqwest::header::ETAG,
+        reqwest::header::LAST_MODIFIED,
+        reqwest::header::EXPIRES,
+    ]
+    .iter()
+    .any(|name| headers.contains_key(name))
+        || directives.iter().any(|(name, _)| name == "max-age")
+}
+
+/// Lowercased `Cache-Control` directives with their unquoted values.
+fn cache_directives(value: Option<&str>) -> Vec<(String, Option<String>)> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|directive| {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name, Some(value.trim().trim_matches('"').to_owned())),
+                None => (directive, None),
+            };
+            let name = name.trim().to_ascii_lowercase();
+            (!name.is_empty()).then_some((name, value))
+        })
+        .collect()
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::BrowserCore;
+
+/// HTTP versions the clients offer servers, for telling apart problems of a
+/// site from problems of its HTTP/2 setup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HttpVersionPolicy {
+    /// Speak HTTP/1.1 only, also over TLS.
+    Http1Only,
+    /// Offer HTTP/2 during the TLS handshake and fall back to HTTP/1.1 for
+    /// servers that do not pick it. Plain-HTTP requests use HTTP/1.1.
+    #[default]
+    PreferHttp2,
+}
+
+impl HttpVersionPolicy {
+    pub const ALL: [HttpVersionPolicy; 2] =
+        [HttpVersionPolicy::Http1Only, HttpVersionPolicy::PreferHttp2];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HttpVersionPolicy::Http1Only => "HTTP/1.1 only",
+            HttpVersionPolicy::PreferHttp2 => "Prefer HTTP/2",
+        }
+    }
+
+    /// Protocols offered through ALPN in the TLS handshake, preferred first.
+    pub(crate) fn alpn_protocols(self) -> Vec<Vec<u8>> {
+        match self {
+            HttpVersionPolicy::Http1Only => vec![b"http/1.1".to_vec()],
+            HttpVersionPolicy::PreferHttp2 => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+        }
+    }
+
+    pub(crate) fn apply(self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        match self {
+            HttpVersionPolicy::Http1Only => builder.http1_only(),
+            HttpVersionPolicy::PreferHttp2 => builder,
+        }
+    }
+}
+
+impl BrowserCore {
+    /// Changes the HTTP versions later requests may use. The clients are
+    /// rebuilt, so pooled connections speaking another version are dropped.
+    pub fn set_http_version(&self, policy: HttpVersionPolicy) -> anyhow::Result<()> {
+        self.rebuild_clients(self.proxy_settings(), policy)
+    }
+
+    pub fn http_version(&self) -> HttpVersionPolicy {
+        self.clients.read().http_version
+    }
+}
@@ -0,0 +1,110 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::PageForm;
+
+const FIRST_NAMES: &[&str] = &[
+    "Alex", "Anna", "Ben", "Chloe", "Daniel", "Emma", "Felix", "Hannah", "Jonas", "Julia", "Leo",
+    "Lena", "Lucas", "Maria", "Max", "Mia", "Noah", "Nora", "Oliver", "Paul", "Sara", "Sofia",
+    "Tom", "Zoe",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Bauer", "Berg", "Carter", "Fischer", "Garcia", "Hansen", "Keller", "Klein", "Larsen",
+    "Martin", "Meyer", "Miller", "Moreau", "Novak", "Olsen", "Parker", "Rossi", "Schmidt", "Silva",
+    "Walker", "Weber", "Wood",
+];
+
+/// Public disposable inboxes used when no alias domain is configured.
+const DISPOSABLE_DOMAINS: &[&str] = &["mailinator.com", "maildrop.cc", "sharklasers.com"];
+
+const PASSWORD_LENGTH: usize = 20;
+const PASSWORD_CHARS: &[u8] = b"abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789-_.!@#%";
+
+/// A made-up person for signing up to sites without giving away real
+/// details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThrowawayIdentity {
+    pub first_name: String,
+    pub last_name: String,
+    pub username: String,
+    pub email: String,
+    pub password: String,
+}
+
+impl ThrowawayIdentity {
+    /// Generates a random identity. Its address is on `alias_domain` when
+    /// given, a catch-all domain of an alias service that forwards to the
+    /// user, and on a public disposable inbox otherwise.
+    pub fn generate(alias_domain: Option<&str>) -> Self {
+        let mut rng = rand::thread_rng();
+        let first_name = FIRST_NAMES.choose(&mut rng).copied().unwrap_or("Alex");
+        let last_name = LAST_NAMES.choose(&mut rng).copied().unwrap_or("Walker");
+        let username = format!(
+            "{}.{}{}",
+            first_name.to_ascii_lowercase(),
+            last_name.to_ascii_lowercase(),
+            rng.gen_range(10..10_000)
+        );
+        let domain = alias_domain
+            .map(|domain| domain.trim().trim_start_matches('@'))
+            .filter(|domain| !domain.is_empty())
+            .or_else(|| DISPOSABLE_DOMAINS.choose(&mut rng).copied())
+            .unwrap_or("mailinator.com");
+        let password = (0..PASSWORD_LENGTH)
+            .map(|_| char::from(PASSWORD_CHARS[rng.gen_range(0..PASSWORD_CHARS.len())]))
+            .collect();
+        Self {
+            email: format!("{username}@{domain}"),
+            first_name: first_name.to_owned(),
+            last_name: last_name.to_owned(),
+            username,
+            password,
+        }
+    }
+
+    pub fn full_name(&self) -> String {
+        format!("{} {}", self.first_name, self.last_name)
+    }
+
+    /// The value for a sign-up field, judged by its name and input type.
+    fn value_for(&self, name: &str, kind: &str) -> Option<String> {
+        let name = name.to_ascii_lowercase();
+        let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+        let value = if kind == "email" || has(&["email", "e-mail", "mail"]) {
+            self.email.clone()
+        } else if kind == "password" || has(&["password", "passwd"]) {
+            self.password.clone()
+        } else if has(&["first", "given", "fname", "forename"]) {
+            self.first_name.clone()
+        } else if has(&["last", "family", "surname", "lname"]) {
+            self.last_name.clone()
+        } else if has(&["user", "login", "nick", "handle", "account"]) {
+            self.username.clone()
+        } else if has(&["name"]) {
+            self.full_name()
+        } else {
+            return None;
+        };
+        Some(value)
+    }
+}
+
+impl PageForm {
+    /// Puts `identity` into the form's name, username, email and password
+    /// fields, returning how many were filled.
+    pub fn fill_identity(&mut self, identity: &ThrowawayIdentity) -> usize {
+        let mut filled = 0;
+        for field in &mut self.fields {
+            if !matches!(field.kind.as_str(), "text" | "email" | "password") {
+                continue;
+            }
+            if let Some(value) = identity.value_for(&field.name, &field.kind) {
+                field.value = value;
+                filled += 1;
+            }
+        }
+        filled
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use reqwest::header::{ACCEPT, USER_AGENT};
+use sha2::{Digest, Sha256};
+use tracing::{debug, instrument};
+use url::Url;
+use zune_core::bytestream::ZCursor;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::DecoderOptions;
+use zune_jpeg::JpegDecoder;
+
+use crate::network;
+use crate::{BrowserCore, BrowserError, RequestKind, TabId};
+
+/// Memory decoded images may take until the budget is changed.
+pub const DEFAULT_IMAGE_CACHE_BYTES: usize = 128 * 1024 * 1024;
+
+/// Widest or tallest image decoded; larger ones are refused before their
+/// pixels are allocated.
+const MAX_DIMENSION: u32 = 16_384;
+
+/// Largest decoded image, in bytes.
+const MAX_DECODED_BYTES: usize = 256 * 1024 * 1024;
+
+/// Image decoded to 8-bit RGBA pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    /// Four bytes per pixel, row by row from the top left.
+    pub pixels: Vec<u8>,
+}
+
+impl DecodedImage {
+    /// Memory the pixels take.
+    pub fn size(&self) -> usize {
+        self.pixels.len()
+    }
+}
+
+/// Usage of the decoded-image cache, for `about:` pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageCacheStats {
+    pub images: usize,
+    pub bytes: usize,
+    pub budget: usize,
+    /// Lookups answered without decoding since startup.
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Decoded images shared by every tab, keyed by the SHA-256 of their encoded
+/// bytes, so the same logo on many pages is decoded and held once. The least
+/// recently used images go when the budget is exceeded.
+pub(crate) struct ImageCache {
+    inner: Mutex<CacheInner>,
+}
+
+struct CacheInner {
+    entries: HashMap<[u8; 32], CachedImage>,
+    bytes: usize,
+    budget: usize,
+    /// Advances on every lookup, ordering entries by last use.
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+struct CachedImage {
+    image: Arc<DecodedImage>,
+    last_used: u64,
+}
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        ImageCache {
+            inner: Mutex::new(CacheInner {
+                entries: HashMap::new(),
+                bytes: 0,
+                budget: DEFAULT_IMAGE_CACHE_BYTES,
+                clock: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+}
+
+impl ImageCache {
+    /// The decoded form of `encoded`, decoding it only when no tab has
+    /// needed the same bytes recently.
+    pub(crate) fn get_or_decode(&self, encoded: &[u8]) -> Result<Arc<DecodedImage>, BrowserError> {
+        let key: [u8; 32] = Sha256::digest(encoded).into();
+        {
+            let mut inner = self.inner.lock();
+            inner.clock += 1;
+            let now = inner.clock;
+            if let Some(entry) = inner.entries.get_mut(&key) {
+                entry.last_used = now;
+                let image = Arc::clone(&entry.image);
+                inner.hits += 1;
+                return Ok(image);
+            }
+            inner.misses += 1;
+        }
+        // Decoding can take a while; other tabs keep using the cache
+        // meanwhile, at the risk of decoding the same image twice.
+        let image = Arc::new(decode(encoded)?);
+        let mut inner = self.inner.lock();
+        if image.size() <= inner.budget && !inner.entries.contains_key(&key) {
+            let last_used = inner.clock;
+            inner.bytes += image.size();
+            inner.entries.insert(
+                key,
+                CachedImage {
+                    image: Arc::clone(&image),
+                    last_used,
+                },
+            );
+            inner.evict_to_budget();
+        }
+        Ok(image)
+    }
+}
+
+impl CacheInner {
+    fn evict_to_budget(&mut self) {
+        while self.bytes > self.budget {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.bytes -= entry.image.size();
+            }
+        }
+    }
+}
+
+/// Decodes a PNG or JPEG, recognised by its signature.
+fn decode(encoded: &[u8]) -> Result<DecodedImage, BrowserError> {
+    if encoded.starts_with(b"\x89PNG\r\n\x1a\n") {
+        decode_png(encoded)
+    } else if encoded.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        decode_jpeg(encoded)
+    } else {
+        Err(BrowserError::Image("unsupported image format".to_owned()))
+    }
+}
+
+fn decode_png(encoded: &[u8]) -> Result<DecodedImage, BrowserError> {
+    let invalid = |err: png::DecodingError| BrowserError::Image(err.to_string());
+    let mut decoder = png::Decoder::new(encoded);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    check_dimensions(width, height)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(invalid)?;
+    buffer.truncate(frame.buffer_size());
+    let pixels = match frame.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, 0xFF]).collect(),
+        // Expanded to RGB(A) by the transformations above.
+        png::ColorType::Indexed => {
+            return Err(BrowserError::Image("unexpanded palette image".to_owned()))
+        }
+    };
+    Ok(DecodedImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+fn decode_jpeg(encoded: &[u8]) -> Result<DecodedImage, BrowserError> {
+    let options = DecoderOptions::default()
+        .jpeg_set_out_colorspace(ColorSpace::RGBA)
+        .set_max_width(MAX_DIMENSION as usize)
+        .set_max_height(MAX_DIMENSION as usize);
+    let mut decoder = JpegDecoder::new_with_options(ZCursor::new(encoded), options);
+    decoder
+        .decode_headers()
+        .map_err(|err| BrowserError::Image(err.to_string()))?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| BrowserError::Image("JPEG without a frame header".to_owned()))?;
+    let (width, height) = (u32::from(info.width), u32::from(info.height));
+    check_dimensions(width, height)?;
+    let pixels = decoder
+        .decode()
+        .map_err(|err| BrowserError::Image(err.to_string()))?;
+    Ok(DecodedImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+fn check_dimensions(width: u32, height: u32) -> Result<(), BrowserError> {
+    let bytes = width as usize * height as usize * 4;
+    if width > MAX_DIMENSION || height > MAX_DIMENSION || bytes > MAX_DECODED_BYTES {
+        return Err(BrowserError::Image(format!(
+            "{width}\u{d7}{height} pixels is too large to decode"
+        )));
+    }
+    Ok(())
+}
+
+impl BrowserCore {
+    /// Decodes a PNG or JPEG through the cache shared by all tabs.
+    pub fn decode_image(&self, encoded: &[u8]) -> Result<Arc<DecodedImage>, BrowserError> {
+        self.images.get_or_decode(encoded)
+    }
+
+    /// Fetches the image at `url` for `tab` and decodes it through the
+    /// shared cache, off the async workers.
+    #[instrument(skip(self))]
+    pub async fn fetch_image(
+        &self,
+        tab: TabId,
+        mut url: Url,
+    ) -> Result<Arc<DecodedImage>, BrowserError> {
+        self.secure_subresource(tab, &mut url, true)?;
+        let options = self.tab_options(tab);
+        let client = self.client_for(&url, options.private);
+        let mut builder = client
+            .get(url.clone())
+            .header(ACCEPT, "image/png,image/jpeg;q=0.9,*/*;q=0.5");
+        if let Some(ua) = self.user_agent_for(options.user_agent.as_deref(), &url) {
+            builder = builder.header(USER_AGENT, ua);
+        }
+        let response =
+            network::send_logged(&self.state, builder, RequestKind::Image, Some(tab)).await?;
+        if !response.status.is_success() {
+            return Err(BrowserError::Status(response.status.as_u16()));
+        }
+        let images = Arc::clone(&self.images);
+        let image = self
+            .cpu
+            .run(move || images.get_or_decode(&response.body))
+            .await??;
+        debug!("{url} is {}\u{d7}{}", image.width, image.height);
+        Ok(image)
+    }
+
+    pub fn image_cache_stats(&self) -> ImageCacheStats {
+        let inner = self.images.inner.lock();
+        ImageCacheStats {
+            images: inner.entries.len(),
+            bytes: inner.bytes,
+            budget: inner.budget,
+            hits: inner.hits,
+            misses: inner.misses,
+        }
+    }
+
+    /// Limits the memory decoded images may take, evicting the least
+    /// recently used ones right away when they take more.
+    pub fn set_image_cache_budget(&self, bytes: usize) {
+        let mut inner = self.images.inner.lock();
+        inner.budget = bytes;
+        inner.evict_to_budget();
+    }
+}
@@ -0,0 +1,243 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::intern::{ArenaText, Name, TextArena};
+
+/// Elements that never have content or a closing tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is raw text that must not be scanned for tags.
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// Elements implicitly closed when a sibling of the same kind opens.
+const SELF_ENDING_ELEMENTS: [&str; 8] = ["p", "li", "dt", "dd", "option", "tr", "td", "th"];
+
+/// Element located in the source text of a page, as shown by the inspector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceElement {
+    /// Lowercase tag name.
+    pub tag: Name,
+    /// Attributes in source order with lowercase names. Their values share
+    /// the text arena of the whole document.
+    pub attributes: Box<[(Name, ArenaText)]>,
+    /// Byte offset of the opening `<`.
+    pub start: usize,
+    /// Byte offset just past the closing tag, or where the element was
+    /// implicitly closed.
+    pub end: usize,
+    /// Index of the enclosing element in the same list.
+    pub parent: Option<usize>,
+    pub depth: usize,
+}
+
+impl SourceElement {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.attribute("id").filter(|id| !id.is_empty())
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        self.attribute("class")
+            .unwrap_or_default()
+            .split_whitespace()
+    }
+
+    /// CSS-like label such as `div#main.content.wide`.
+    pub fn label(&self) -> String {
+        let mut label = self.tag.to_string();
+        if let Some(id) = self.id() {
+            label.push('#');
+            label.push_str(id);
+        }
+        for class in self.classes() {
+            label.push('.');
+            label.push_str(class);
+        }
+        label
+    }
+}
+
+/// Scans `source` for elements in document order.
+///
+/// This is a forgiving tag scanner rather than a full HTML parser: unclosed
+/// elements end where their parent does, and stray closing tags are ignored.
+/// Tag and attribute names are interned, and attribute values are copied
+/// into one arena for the whole document.
+pub fn map_elements(source: &str) -> Vec<SourceElement> {
+    let mut elements: Vec<SourceElement> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let mut pos = 0;
+    let mut arena = TextArena::default();
+    // Attributes of every element, with the range of each element's own.
+    let mut attributes: Vec<(Name, Range<u32>)> = Vec::new();
+    let mut element_attributes: Vec<Range<usize>> = Vec::new();
+
+    while let Some(offset) = source[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &source[start..];
+        if rest.starts_with("<!--") {
+            pos = rest.find("-->").map_or(source.len(), |end| start + end + 3);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            pos = rest.find('>').map_or(source.len(), |end| start + end + 1);
+            continue;
+        }
+        let Some(tag_end) = tag_end(rest) else {
+            break;
+        };
+        let inner = &rest[1..tag_end];
+        let after = start + tag_end + 1;
+
+        if let Some(name) = inner.strip_prefix('/') {
+            let name = name.trim();
+            if let Some(level) = open
+                .iter()
+                .rposition(|&index| elements[index].tag.eq_ignore_ascii_case(name))
+            {
+                for &index in &open[level + 1..] {
+                    elements[index].end = start;
+                }
+                elements[open[level]].end = after;
+                open.truncate(level);
+            }
+            pos = after;
+            continue;
+        }
+
+        let name_len = inner
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(inner.len());
+        if !inner.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            pos = start + 1;
+            continue;
+        }
+        let tag = Name::new(&inner[..name_len]);
+
+        if SELF_ENDING_ELEMENTS.contains(&tag.as_str())
+            && open.last().is_some_and(|&index| elements[index].tag == tag)
+        {
+            if let Some(index) = open.pop() {
+                elements[index].end = start;
+            }
+        }
+
+        let index = elements.len();
+        let self_closing = inner.trim_end().ends_with('/') || VOID_ELEMENTS.contains(&tag.as_str());
+        let first = attributes.len();
+        parse_attributes(&inner[name_len..], &mut arena, &mut attributes);
+        element_attributes.push(first..attributes.len());
+        elements.push(SourceElement {
+            attributes: Box::default(),
+            start,
+            end: after,
+            parent: open.last().copied(),
+            depth: open.len(),
+            tag,
+        });
+        pos = after;
+        if self_closing {
+            continue;
+        }
+
+        open.push(index);
+        let tag = elements[index].tag.as_str();
+        if RAW_TEXT_ELEMENTS.contains(&tag) {
+            let closing = format!("</{tag}");
+            pos = source[pos..]
+                .to_ascii_lowercase()
+                .find(&closing)
+                .map_or(source.len(), |offset| pos + offset);
+        }
+    }
+
+    for index in open {
+        elements[index].end = source.len();
+    }
+    let arena = arena.finish();
+    for (element, range) in elements.iter_mut().zip(element_attributes) {
+        element.attributes = attributes[range]
+            .iter()
+            .map(|(name, span)| (name.clone(), arena.text(span.clone())))
+            .collect();
+    }
+    elements.shrink_to_fit();
+    elements
+}
+
+/// Index of the innermost element whose source contains byte `offset`.
+pub fn element_at(elements: &[SourceElement], offset: usize) -> Option<usize> {
+    elements
+        .iter()
+        .enumerate()
+        .filter(|(_, element)| element.start <= offset && offset < element.end)
+        .max_by_key(|(_, element)| element.depth)
+        .map(|(index, _)| index)
+}
+
+/// Position of the `>` ending the tag that starts `rest`, skipping quoted
+/// attribute values.
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in rest.char_indices().skip(1) {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return Some(index),
+            None => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(
+    mut text: &str,
+    arena: &mut TextArena,
+    attributes: &mut Vec<(Name, Range<u32>)>,
+) {
+    loop {
+        text = text.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if text.is_empty() {
+            return;
+        }
+        let name_len = text
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(text.len());
+        let name = &text[..name_len];
+        text = text[name_len..].trim_start();
+
+        let mut value = "";
+        if let Some(rest) = text.strip_prefix('=') {
+            let rest = rest.trim_start();
+            let (raw, remaining) = match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &rest[1..];
+                    match body.find(quote) {
+                        Some(end) => (&body[..end], &body[end + 1..]),
+                        None => (body, ""),
+                    }
+                }
+                _ => {
+                    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    (&rest[..end], &rest[end..])
+                }
+            };
+            value = raw;
+            text = remaining;
+        }
+        if !name.is_empty() {
+            attributes.push((Name::new(name), arena.push(value)));
+        }
+    }
+}
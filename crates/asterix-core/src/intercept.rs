@@ -0,0 +1,213 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, BrowserState};
+
+/// File extensions treated as images by [`BlockRule::Images`].
+const IMAGE_EXTENSIONS: [&str; 9] = [
+    "png", "jpg", "jpeg", "gif", "webp", "svg", "ico", "avif", "bmp",
+];
+
+/// Devtools rule that stops matching requests before they reach the network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockRule {
+    /// Exactly this URL, ignoring the fragment.
+    Url(Url),
+    /// This host and all of its subdomains.
+    Domain(String),
+    /// Anything whose path looks like an image.
+    Images,
+}
+
+impl BlockRule {
+    pub fn matches(&self, url: &Url) -> bool {
+        match self {
+            BlockRule::Url(blocked) => {
+                blocked.as_str().split('#').next() == url.as_str().split('#').next()
+            }
+            BlockRule::Domain(domain) => url.host_str().is_some_and(|host| {
+                host.eq_ignore_ascii_case(domain)
+                    || host
+                        .to_ascii_lowercase()
+                        .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+            }),
+            BlockRule::Images => url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .and_then(|name| name.rsplit_once('.'))
+                .is_some_and(|(_, extension)| {
+                    IMAGE_EXTENSIONS
+                        .iter()
+                        .any(|image| extension.eq_ignore_ascii_case(image))
+                }),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            BlockRule::Url(url) => format!("URL {url}"),
+            BlockRule::Domain(domain) => format!("domain {domain}"),
+            BlockRule::Images => "all images".to_owned(),
+        }
+    }
+}
+
+/// Serves requests whose URL matches `pattern` from a local file instead of
+/// the network. `*` in the pattern matches any run of characters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseOverride {
+    pub pattern: String,
+    pub path: PathBuf,
+}
+
+impl ResponseOverride {
+    pub fn matches(&self, url: &Url) -> bool {
+        let url = url.as_str().split('#').next().unwrap_or_default();
+        wildcard_match(self.pattern.trim(), url)
+    }
+
+    /// Content type announced for the file, guessed from its extension.
+    pub(crate) fn content_type(&self) -> &'static str {
+        content_type_for(&self.path)
+    }
+}
+
+/// Session-only request rules consulted before every logged request.
+#[derive(Default)]
+pub(crate) struct Interceptors {
+    pub(crate) block_rules: Vec<BlockRule>,
+    pub(crate) overrides: Vec<ResponseOverride>,
+}
+
+/// Decision taken by the interceptor chain for an outgoing request.
+pub(crate) enum Interception {
+    Continue,
+    Block(String),
+    /// Restricted mode is on and the URL is not on the allowlist.
+    Restrict,
+    Override(ResponseOverride),
+}
+
+/// Runs `url` through the interceptor chain in order.
+pub(crate) fn intercept(state: &BrowserState, url: &Url) -> Interception {
+    if state
+        .allowlist
+        .as_ref()
+        .is_some_and(|allowlist| !allowlist.allows(url))
+    {
+        return Interception::Restrict;
+    }
+    let interceptors = &state.interceptors;
+    if let Some(rule) = interceptors
+        .block_rules
+        .iter()
+        .find(|rule| rule.matches(url))
+    {
+        return Interception::Block(format!("blocked by rule for {}", rule.describe()));
+    }
+    if let Some(rule) = interceptors.overrides.iter().find(|rule| rule.matches(url)) {
+        return Interception::Override(rule.clone());
+    }
+    Interception::Continue
+}
+
+/// Matches `text` against `pattern`, where `*` stands for any (possibly
+/// empty) run of characters and everything else must match literally.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+pub(crate) fn content_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "md" | "markdown" => "text/markdown; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "avif" => "image/avif",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+impl BrowserCore {
+    pub fn block_rules(&self) -> Vec<BlockRule> {
+        self.state.read().interceptors.block_rules.clone()
+    }
+
+    pub fn add_block_rule(&self, rule: BlockRule) {
+        let mut guard = self.state.write();
+        if !guard.interceptors.block_rules.contains(&rule) {
+            guard.interceptors.block_rules.push(rule);
+        }
+    }
+
+    pub fn remove_block_rule(&self, rule: &BlockRule) {
+        self.state
+            .write()
+            .interceptors
+            .block_rules
+            .retain(|existing| existing != rule);
+    }
+
+    pub fn response_overrides(&self) -> Vec<ResponseOverride> {
+        self.state.read().interceptors.overrides.clone()
+    }
+
+    /// Adds a local override, replacing any existing one for the same pattern.
+    pub fn add_response_override(&self, rule: ResponseOverride) {
+        let mut guard = self.state.write();
+        let overrides = &mut guard.interceptors.overrides;
+        match overrides
+            .iter_mut()
+            .find(|existing| existing.pattern == rule.pattern)
+        {
+            Some(existing) => *existing = rule,
+            None => overrides.push(rule),
+        }
+    }
+
+    pub fn remove_response_override(&self, pattern: &str) {
+        self.state
+            .write()
+            .interceptors
+            .overrides
+            .retain(|existing| existing.pattern != pattern);
+    }
+}
@@ -0,0 +1,222 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::sync::{Arc, LazyLock};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Longest name interned; longer ones are rare enough to allocate each time.
+const MAX_INTERNED_LEN: usize = 32;
+
+/// Most names the interner keeps, so pages inventing endless attribute
+/// names cannot grow it for the life of the process.
+const MAX_INTERNED_NAMES: usize = 4096;
+
+/// Names every page shares, interned up front.
+const KNOWN_NAMES: [&str; 47] = [
+    "a", "alt", "body", "button", "charset", "class", "content", "div", "em", "footer", "form",
+    "h1", "h2", "h3", "head", "header", "height", "href", "html", "id", "img", "input", "label",
+    "lang", "li", "link", "main", "meta", "name", "nav", "p", "rel", "role", "script", "section",
+    "span", "src", "strong", "style", "table", "td", "title", "tr", "type", "ul", "value", "width",
+];
+
+static NAMES: LazyLock<Mutex<HashSet<Arc<str>>>> =
+    LazyLock::new(|| Mutex::new(KNOWN_NAMES.iter().map(|name| Arc::from(*name)).collect()));
+
+/// Lowercase tag or attribute name, shared by every element with the same
+/// name instead of allocated for each.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Name(Arc<str>);
+
+impl Name {
+    /// Interns `name` lowercased.
+    pub fn new(name: &str) -> Self {
+        if name.len() > MAX_INTERNED_LEN {
+            return Name(Arc::from(name.to_ascii_lowercase()));
+        }
+        let mut buffer = [0; MAX_INTERNED_LEN];
+        let lower = &mut buffer[..name.len()];
+        lower.copy_from_slice(name.as_bytes());
+        lower.make_ascii_lowercase();
+        // Lowercasing ASCII bytes keeps the text valid UTF-8.
+        let lower = std::str::from_utf8(lower).unwrap_or(name);
+
+        let mut names = NAMES.lock();
+        if let Some(interned) = names.get(lower) {
+            return Name(Arc::clone(interned));
+        }
+        let interned: Arc<str> = Arc::from(lower);
+        if names.len() < MAX_INTERNED_NAMES {
+            names.insert(Arc::clone(&interned));
+        }
+        Name(interned)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Name::new(&name))
+    }
+}
+
+/// Text kept in the arena of the document it came from: one allocation
+/// holds the text of every node, and each node only points into it.
+#[derive(Clone)]
+pub struct ArenaText {
+    arena: Arc<str>,
+    start: u32,
+    end: u32,
+}
+
+impl ArenaText {
+    pub fn as_str(&self) -> &str {
+        &self.arena[self.start as usize..self.end as usize]
+    }
+}
+
+impl From<&str> for ArenaText {
+    /// Text in an arena of its own.
+    fn from(text: &str) -> Self {
+        ArenaText {
+            arena: Arc::from(text),
+            start: 0,
+            end: text.len() as u32,
+        }
+    }
+}
+
+impl Deref for ArenaText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for ArenaText {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ArenaText {}
+
+impl PartialEq<str> for ArenaText {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ArenaText {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for ArenaText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ArenaText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ArenaText {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ArenaText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(ArenaText::from(text.as_str()))
+    }
+}
+
+/// Arena a document's text is collected into while it is parsed. Text
+/// pushed gets a span, turned into [`ArenaText`] once parsing is done.
+#[derive(Default)]
+pub(crate) struct TextArena {
+    text: String,
+}
+
+impl TextArena {
+    pub(crate) fn push(&mut self, text: &str) -> Range<u32> {
+        let start = self.text.len() as u32;
+        self.text.push_str(text);
+        start..self.text.len() as u32
+    }
+
+    /// Freezes the arena; `text` turns the spans it handed out into text.
+    pub(crate) fn finish(self) -> FrozenArena {
+        FrozenArena(Arc::from(self.text))
+    }
+}
+
+pub(crate) struct FrozenArena(Arc<str>);
+
+impl FrozenArena {
+    pub(crate) fn text(&self, span: Range<u32>) -> ArenaText {
+        ArenaText {
+            arena: Arc::clone(&self.0),
+            start: span.start,
+            end: span.end,
+        }
+    }
+}
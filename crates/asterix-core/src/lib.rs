@@ -1,14 +1,190 @@
+mod allowlist;
+mod annotations;
+mod archive;
+mod article;
+mod audit;
+mod bookmarks;
+mod breach;
+mod cache_store;
+mod captive;
+mod charset;
+mod citation;
+mod client_cert;
+mod composer;
+mod connection_limits;
+mod connections;
+mod consent;
+mod console;
+mod content_coding;
+mod cookies;
+mod cpu;
+mod crawl;
+mod downloads;
+mod dual_stack;
+mod emulation;
+mod event_source;
+mod favicon;
+mod feed;
+mod file_types;
+mod flags;
+mod forms;
+mod history;
+mod hsts;
+mod http_auth;
+mod http_cache;
+mod http_version;
+mod identity;
+mod image_cache;
+mod inspect;
+mod intercept;
+mod intern;
+mod link_check;
+mod local_files;
+mod logins;
+mod magnet;
+mod markdown;
+mod metadata;
+mod mime_sniff;
+mod mixed_content;
+mod network;
+mod offline;
+mod prefetch;
+mod prettify;
+mod privacy;
+mod progress;
+mod protocols;
+mod proxy;
+mod qr;
+mod refresh;
+mod render;
+mod request_body;
+mod select;
+mod seo;
+mod shield;
+mod site_files;
+mod style;
+mod subresources;
+mod tab_history;
+mod throttle;
+mod tls;
+mod totp;
+mod watch;
+mod websocket;
+mod workspace;
+
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::Context;
-use chrono::{DateTime, Utc};
+use asterix_storage::CookieJar;
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
 use parking_lot::RwLock;
-use reqwest::redirect::Policy;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::instrument;
 use url::Url;
 
+use crate::charset::decode_body;
+use crate::client_cert::IdentityClients;
+use crate::connection_limits::RequestLimiter;
+use crate::connections::protocol_label;
+use crate::http_auth::HttpAuth;
+use crate::image_cache::ImageCache;
+use crate::intercept::Interceptors;
+use crate::network::{LoggedCookieJar, LoggedResponse, NetworkLog, TimedResolver};
+use crate::privacy::Privacy;
+use crate::shield::Shield;
+use crate::tab_history::TabHistory;
+use crate::tls::TlsObserver;
+
+pub use allowlist::Allowlist;
+pub use annotations::{AnnotationStore, Highlight, PageAnnotations};
+pub use archive::is_dead_status;
+pub use article::{extract_article, Article, ArticleBlock};
+pub use audit::{audit_page, AuditFinding, AuditRule};
+pub use bookmarks::Bookmark;
+pub use breach::PasswordReport;
+pub use captive::{network_address, Connectivity, CONNECTIVITY_PROBE};
+pub use charset::{PageText, TEXT_ENCODINGS};
+pub use citation::{format_citation, CitationStyle};
+pub use client_cert::{ClientIdentity, HostIdentity, IdentityError, IdentityFile};
+pub use composer::{ComposedRequest, RawResponse, RequestAuth};
+pub use connection_limits::{BackgroundGate, BackgroundTurn, ConnectionLimits};
+pub use connections::{ConnectionUse, HostConnections};
+pub use consent::{ConsentPlatform, ConsentSettings, CONSENT_PLATFORMS};
+pub use console::{ConsoleCommand, CONSOLE_HELP};
+pub use content_coding::{ContentCoding, ContentDecoding};
+pub use cpu::CpuPool;
+pub use crawl::{CrawlOptions, CrawlSummary, CrawledPage};
+pub use downloads::{DownloadEntry, DownloadStatus};
+pub use dual_stack::IpPreference;
+pub use emulation::{SiteUserAgent, Viewport};
+pub use event_source::{EventSourceEvent, ServerSentEvent};
+pub use favicon::favicon_url;
+pub use feed::{parse_feed, FeedEntry};
+pub use file_types::{FileAction, FileTypeRule};
+pub use flags::{
+    flag_enabled, flag_overrides, flags, register_flag, set_flag, set_flag_overrides, Flag,
+    FlagState,
+};
+pub use forms::{
+    click_target, control_name, extract_forms, find_form, link_selector, ClickTarget, FormEncoding,
+    FormField, FormMethod, PageForm,
+};
+pub use history::HistoryEntry;
+pub use hsts::HstsEntry;
+pub use http_auth::{AuthChallenge, AuthScheme};
+pub use http_cache::{CacheStatus, CachedDocument, HttpCache};
+pub use http_version::HttpVersionPolicy;
+pub use identity::ThrowawayIdentity;
+pub use image_cache::{DecodedImage, ImageCacheStats, DEFAULT_IMAGE_CACHE_BYTES};
+pub use inspect::{element_at, map_elements, SourceElement};
+pub use intercept::{BlockRule, ResponseOverride};
+pub use intern::{ArenaText, Name};
+pub use link_check::{LinkReport, LinkStatus};
+pub use logins::SavedLogin;
+pub use magnet::{parse_magnet, MagnetLink};
+pub use markdown::html_to_markdown;
+pub use metadata::{extract_metadata, PageMetadata};
+pub use mime_sniff::sniff_mime_type;
+pub use mixed_content::{MixedContent, MixedContentAction, NavigationSecurity};
+pub use network::{NetworkEntry, RedirectHop, RequestKind, Timings};
+pub use prefetch::prefetch_hints;
+pub use prettify::{prettify, SourceLanguage};
+pub use privacy::{PrivacyReport, PrivacySettings};
+pub use progress::FetchProgress;
+pub use protocols::{IpfsSettings, SchemeFuture, SchemeHandler, LOCAL_IPFS_GATEWAY};
+pub use proxy::{ProxyMode, ProxySettings};
+pub use qr::{decode_qr, QrError};
+pub use refresh::{page_refresh, PageRefresh};
+pub use render::{render_document, render_with_stylesheets, RenderOptions, RenderedPage};
+pub use request_body::{MultipartPart, RequestBody};
+pub use select::{select_attribute, select_text};
+pub use seo::{
+    alternate_languages, extract_seo, AlternateLanguage, SeoSummary, ShareCard, StructuredData,
+    StructuredFormat,
+};
+pub use shield::{FilterList, FilterListInfo};
+pub use site_files::{
+    parse_site_file, RobotsGroup, RobotsRule, RobotsTxt, SiteFile, Sitemap, SitemapEntry,
+    SitemapKind,
+};
+pub use style::{
+    BoxModel, ColorScheme, ComputedProperty, ComputedStyle, Declaration, DeclarationId,
+    MatchedRule, MediaFeatures, PropertyOrigin, RuleSource, StyleSheet,
+};
+pub use subresources::{
+    subresource_refs, PageResource, PageResources, ResourceKind, SubresourceRef,
+};
+pub use tab_history::NavigationEntry;
+pub use throttle::NetworkConditions;
+pub use tls::{CertErrorDetails, CertificateInfo, ConnectionInfo};
+pub use totp::{Totp, TotpError};
+pub use watch::{DiffLine, PageWatch};
+pub use websocket::{WebSocketClose, WebSocketCommand, WebSocketEvent, WebSocketMessage};
+pub use workspace::{SavedTab, SavedWorkspace, Workspace, WorkspaceId, WorkspaceSession};
+
 /// Identifier for a logical browser tab.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TabId(u64);
@@ -21,11 +197,83 @@ impl TabId {
     }
 }
 
+/// Controls whether a navigation may be satisfied from previously fetched data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CachePolicy {
+    /// Reuse preloaded documents and fresh cached copies, revalidating
+    /// stale ones.
+    #[default]
+    Default,
+    /// Always hit the network and ask intermediaries to revalidate.
+    Reload,
+    /// Serve a cached copy however old, failing when there is none.
+    OnlyIfCached,
+}
+
 /// Represents a navigation request initiated by the UI.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageRequest {
     pub tab: TabId,
     pub url: Url,
+    pub cache: CachePolicy,
+    /// HTTP method, such as `GET` or `POST`.
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub body: Option<RequestBody>,
+    /// Headers sent in addition to the client's defaults.
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// Redirects followed before giving up.
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+}
+
+fn default_method() -> String {
+    "GET".to_owned()
+}
+
+fn default_max_redirects() -> usize {
+    network::MAX_REDIRECTS
+}
+
+impl PageRequest {
+    pub fn new(tab: TabId, url: Url) -> Self {
+        Self {
+            tab,
+            url,
+            cache: CachePolicy::Default,
+            method: default_method(),
+            body: None,
+            headers: Vec::new(),
+            max_redirects: default_max_redirects(),
+        }
+    }
+
+    /// `POST` of `body` to `url`, e.g. to submit a form.
+    pub fn post(tab: TabId, url: Url, body: RequestBody) -> Self {
+        Self {
+            method: "POST".to_owned(),
+            body: Some(body),
+            ..Self::new(tab, url)
+        }
+    }
+
+    /// Adds a header to send with the request.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Whether the request only reads, so its response may be cached and
+    /// reused.
+    fn is_safe(&self) -> bool {
+        self.body.is_none()
+            && matches!(
+                self.method.trim().to_ascii_uppercase().as_str(),
+                "GET" | "HEAD"
+            )
+    }
 }
 
 /// Minimal representation of a fetched document.
@@ -34,11 +282,129 @@ pub struct PageResponse {
     pub url: Url,
     pub status: u16,
     pub mime_type: Option<String>,
+    /// Title of an HTML page, or the address of any other document.
     pub title: Option<String>,
-    pub body: String,
+    /// Body exactly as received.
+    pub bytes: Bytes,
+    /// `bytes` decoded as text with `charset`, minus rejected cookie banners.
+    /// Shares the buffer of `bytes` when they were already UTF-8.
+    pub body: PageText,
+    /// Name of the character encoding the body was decoded with.
+    pub charset: String,
+    /// Consent platforms whose banner was removed and whose non-essential
+    /// cookies were refused.
+    #[serde(default)]
+    pub consent_rejected: Vec<String>,
+    /// Whether the page came from the network or the cache.
+    #[serde(default)]
+    pub cache: CacheStatus,
+    /// TLS details of the latest connection to the page's host, for pages
+    /// loaded over HTTPS.
+    #[serde(default)]
+    pub connection: Option<ConnectionInfo>,
+    /// HTTP version the page was received over, such as `HTTP/2`; `None`
+    /// for pages served from a cache or loaded without the network.
+    #[serde(default)]
+    pub http_version: Option<String>,
+    /// Redirects followed to reach `url`, oldest first.
+    #[serde(default)]
+    pub redirects: Vec<RedirectHop>,
+    /// What the file-type rules say to do with the page. Only pages that
+    /// are opened become the tab's current page.
+    #[serde(default)]
+    pub action: FileAction,
+    /// Whether the page was received securely, for the address bar.
+    #[serde(default)]
+    pub security: NavigationSecurity,
+    /// Plain-HTTP subresources of a page received over HTTPS, and whether
+    /// each is upgraded or blocked.
+    #[serde(default)]
+    pub mixed_content: Vec<MixedContent>,
+    /// What tracker protection removed or added for the request.
+    #[serde(default)]
+    pub privacy: PrivacyReport,
+    /// Credentials the server asked for when it refused the request.
+    #[serde(default)]
+    pub auth_challenge: Option<AuthChallenge>,
+    /// Sizes of a body that arrived compressed, before and after decoding.
+    #[serde(default)]
+    pub content_decoding: Option<ContentDecoding>,
+    /// Navigation the `Refresh` header asks for; see [`page_refresh`] for
+    /// the `<meta>` element too.
+    #[serde(default)]
+    pub refresh: Option<PageRefresh>,
     pub received_at: DateTime<Utc>,
 }
 
+impl PageResponse {
+    /// Page for `bytes` received from `url`, decoded as `mime_type` says.
+    pub fn from_bytes(
+        url: Url,
+        status: u16,
+        mime_type: Option<String>,
+        bytes: impl Into<Bytes>,
+    ) -> Self {
+        let bytes = bytes.into();
+        let (body, charset) = decode_body(&bytes, mime_type.as_deref());
+        Self {
+            security: NavigationSecurity::of(&url),
+            url,
+            status,
+            mime_type,
+            title: None,
+            bytes,
+            body,
+            charset: charset.name().to_owned(),
+            consent_rejected: Vec::new(),
+            cache: CacheStatus::Miss,
+            connection: None,
+            http_version: None,
+            redirects: Vec::new(),
+            action: FileAction::Open,
+            mixed_content: Vec::new(),
+            privacy: PrivacyReport::default(),
+            auth_challenge: None,
+            content_decoding: None,
+            refresh: None,
+            received_at: Utc::now(),
+        }
+    }
+
+    pub(crate) fn from_network(url: &Url, response: LoggedResponse) -> Self {
+        let header = |name| {
+            response
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let no_sniff = header(reqwest::header::X_CONTENT_TYPE_OPTIONS)
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("nosniff"));
+        let mime_type = sniff_mime_type(
+            header(reqwest::header::CONTENT_TYPE),
+            no_sniff,
+            &response.body,
+        );
+        Self {
+            auth_challenge: AuthChallenge::of_response(
+                &response.url,
+                response.status,
+                &response.headers,
+            ),
+            http_version: Some(protocol_label(response.version).to_owned()),
+            redirects: response.redirects,
+            content_decoding: response.decoding,
+            refresh: header(reqwest::header::REFRESH)
+                .and_then(|value| PageRefresh::parse(value, url)),
+            ..Self::from_bytes(
+                url.clone(),
+                response.status.as_u16(),
+                mime_type,
+                response.body,
+            )
+        }
+    }
+}
+
 /// Snapshot of the current tab state used by higher layers.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TabSnapshot {
@@ -46,14 +412,130 @@ pub struct TabSnapshot {
     pub title: String,
     pub url: Option<Url>,
     pub last_loaded: Option<DateTime<Utc>>,
+    pub private: bool,
+    pub user_agent: Option<String>,
+    pub workspace: WorkspaceId,
+    /// Device viewport emulated in responsive design mode.
+    #[serde(default)]
+    pub viewport: Option<Viewport>,
+    /// Icon of the site shown, once fetched.
+    #[serde(skip)]
+    pub favicon: Option<Arc<DecodedImage>>,
+    /// Refreshes pages ask for are not followed in this tab.
+    #[serde(default)]
+    pub auto_refresh_disabled: bool,
+}
+
+/// Settings applied when opening a tab.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TabOptions {
+    /// Private tabs never share cookies with regular tabs.
+    pub private: bool,
+    /// Overrides the runtime's default user agent for this tab's requests.
+    pub user_agent: Option<String>,
+}
+
+/// Hyperlink discovered in a fetched document, resolved against the page URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageLink {
+    pub url: Url,
+    pub text: String,
 }
 
-#[derive(Default)]
 struct BrowserState {
     next_tab_id: u64,
     tabs: Vec<TabSnapshot>,
+    tab_history: HashMap<TabId, TabHistory>,
+    /// Prefetched documents with the time they stop being served.
+    preloaded: HashMap<Url, (PageResponse, DateTime<Utc>)>,
+    next_workspace_id: u64,
+    workspaces: Vec<Workspace>,
+    active_workspace: WorkspaceId,
+    annotations: AnnotationStore,
+    watches: Vec<PageWatch>,
+    bookmarks: Vec<Bookmark>,
+    history: Vec<HistoryEntry>,
+    next_download_id: u64,
+    downloads: Vec<DownloadEntry>,
+    logins: Vec<SavedLogin>,
+    network: NetworkLog,
+    interceptors: Interceptors,
+    /// Set while restricted mode is on.
+    allowlist: Option<Allowlist>,
+    /// Set while cookie banners are rejected automatically.
+    consent: Option<ConsentSettings>,
+    file_types: Vec<FileTypeRule>,
+    site_user_agents: Vec<SiteUserAgent>,
+    ipfs: IpfsSettings,
+    /// Outcome of the last local IPFS node probe and when it ran.
+    local_ipfs_node: Option<(bool, DateTime<Utc>)>,
+    /// Handlers embedders registered, by lowercase scheme.
+    scheme_handlers: HashMap<String, Arc<dyn SchemeHandler>>,
+    /// Hosts that asked to be reached over HTTPS only, by host name.
+    hsts: HashMap<String, HstsEntry>,
+    shield: Shield,
+    privacy: Privacy,
+    limiter: RequestLimiter,
+    background_gate: Option<Arc<dyn BackgroundGate>>,
+    network_conditions: NetworkConditions,
+    /// Set while offline mode is on.
+    offline: bool,
+    http_auth: HttpAuth,
+    /// Codings offered in `Accept-Encoding`.
+    content_codings: Vec<ContentCoding>,
+    /// Icons by origin, `None` for origins without a usable one.
+    favicons: HashMap<String, Option<Arc<DecodedImage>>>,
+}
+
+impl Default for BrowserState {
+    fn default() -> Self {
+        let mut next_workspace_id = 0;
+        let default_workspace = Workspace {
+            id: WorkspaceId::next(&mut next_workspace_id),
+            name: "Default".to_owned(),
+        };
+
+        Self {
+            next_tab_id: 0,
+            tabs: Vec::new(),
+            tab_history: HashMap::new(),
+            preloaded: HashMap::new(),
+            next_workspace_id,
+            active_workspace: default_workspace.id,
+            workspaces: vec![default_workspace],
+            annotations: AnnotationStore::default(),
+            watches: Vec::new(),
+            bookmarks: Vec::new(),
+            history: Vec::new(),
+            next_download_id: 0,
+            downloads: Vec::new(),
+            logins: Vec::new(),
+            network: NetworkLog::default(),
+            interceptors: Interceptors::default(),
+            allowlist: None,
+            consent: None,
+            file_types: Vec::new(),
+            site_user_agents: Vec::new(),
+            ipfs: IpfsSettings::default(),
+            local_ipfs_node: None,
+            scheme_handlers: HashMap::new(),
+            hsts: HashMap::new(),
+            shield: Shield::default(),
+            privacy: Privacy::default(),
+            limiter: RequestLimiter::default(),
+            background_gate: None,
+            network_conditions: NetworkConditions::default(),
+            offline: false,
+            http_auth: HttpAuth::default(),
+            content_codings: ContentCoding::DEFAULT.to_vec(),
+            favicons: HashMap::new(),
+        }
+    }
 }
 
+/// How long a preloaded document stays eligible to satisfy a navigation.
+const PRELOAD_TTL_SECS: i64 = 60;
+
 /// Errors surfaced by the browser core when satisfying network requests.
 #[derive(Debug, Error)]
 pub enum BrowserError {
@@ -63,36 +545,140 @@ pub enum BrowserError {
     InvalidBody,
     #[error("navigation was cancelled before completion")]
     Cancelled,
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("server responded with HTTP {0}")]
+    Status(u16),
+    #[error("request {0}")]
+    Blocked(String),
+    #[error("{0} is not on the allowlist")]
+    Restricted(Url),
+    #[error("local override failed: {0}")]
+    LocalOverride(String),
+    #[error("could not open local file: {0}")]
+    LocalFile(String),
+    #[error("{0} is not in the cache")]
+    NotCached(Url),
+    #[error("certificate of {} is not trusted: {}", .0.host, .0.reason)]
+    Tls(CertErrorDetails),
+    #[error("could not save download: {0}")]
+    Download(String),
+    #[error(
+        "redirect loop back to {}",
+        .0.last().map_or("the same page", |hop| hop.location.as_str())
+    )]
+    RedirectLoop(Vec<RedirectHop>),
+    #[error("WebSocket failed: {0}")]
+    WebSocket(String),
+    #[error("event stream failed: {0}")]
+    EventStream(String),
+    #[error("could not decode image: {0}")]
+    Image(String),
+    #[error("the network is offline")]
+    Offline,
+    #[error("could not decode the response body: {0}")]
+    ContentDecoding(String),
 }
 
 /// Core runtime responsible for performing network requests and tracking tab metadata.
 pub struct BrowserCore {
-    client: reqwest::Client,
+    clients: RwLock<Clients>,
+    user_agent: Option<String>,
+    cookie_jar: Arc<CookieJar>,
+    http_cache: Option<HttpCache>,
+    /// Decoded images, shared by all tabs.
+    images: Arc<ImageCache>,
+    /// Where pages are parsed and images decoded.
+    cpu: CpuPool,
+    tls: Arc<TlsObserver>,
+    resolver: Arc<TimedResolver>,
     state: Arc<RwLock<BrowserState>>,
 }
 
-impl BrowserCore {
-    pub fn new(user_agent: Option<&str>) -> anyhow::Result<Self> {
-        let mut client_builder = reqwest::Client::builder()
-            .redirect(Policy::limited(10))
-            .cookie_store(true);
-
-        if let Some(ua) = user_agent {
-            client_builder = client_builder.user_agent(ua);
-        }
+/// HTTP clients built for the current proxy settings.
+struct Clients {
+    regular: reqwest::Client,
+    /// Shares no cookies with `regular`.
+    private: reqwest::Client,
+    proxy: ProxySettings,
+    http_version: HttpVersionPolicy,
+    /// Clients presenting a client certificate, for the hosts set up with
+    /// one.
+    identities: Vec<IdentityClients>,
+}
 
-        let client = client_builder
-            .build()
-            .context("failed to initialise HTTP client")?;
+impl BrowserCore {
+    /// Creates the core around `cookie_jar`, which the regular client reads
+    /// and fills, and `http_cache` for documents; private tabs use neither.
+    /// All traffic is routed as `proxy` says.
+    pub fn new(
+        user_agent: Option<&str>,
+        cookie_jar: Arc<CookieJar>,
+        http_cache: Option<HttpCache>,
+        proxy: ProxySettings,
+    ) -> anyhow::Result<Self> {
+        let tls = Arc::default();
+        let resolver = Arc::default();
+        let http_version = HttpVersionPolicy::default();
+        let regular = build_client(
+            user_agent,
+            Some(Arc::clone(&cookie_jar)),
+            &proxy,
+            http_version,
+            &tls,
+            &resolver,
+            None,
+        )
+        .context("failed to initialise HTTP client")?;
+        let private = build_client(
+            user_agent,
+            None,
+            &proxy,
+            http_version,
+            &tls,
+            &resolver,
+            None,
+        )
+        .context("failed to initialise private HTTP client")?;
+        let mut state = BrowserState::default();
+        state.network.set_user_agent(user_agent);
 
         Ok(Self {
-            client,
-            state: Arc::default(),
+            clients: RwLock::new(Clients {
+                regular,
+                private,
+                proxy,
+                http_version,
+                identities: Vec::new(),
+            }),
+            user_agent: user_agent.map(ToOwned::to_owned),
+            cookie_jar,
+            http_cache,
+            images: Arc::default(),
+            cpu: CpuPool::default(),
+            tls,
+            resolver,
+            state: Arc::new(RwLock::new(state)),
         })
     }
 
+    /// User agent requests send unless a tab or site overrides it.
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    /// Client for private tabs and lookups that should not carry cookies.
+    fn private_client(&self) -> reqwest::Client {
+        self.clients.read().private.clone()
+    }
+
     /// Creates a new logical tab and returns its identifier along with a snapshot.
     pub fn create_tab(&self, title: impl Into<String>) -> TabSnapshot {
+        self.create_tab_with(title, TabOptions::default())
+    }
+
+    /// Creates a new logical tab using the provided options.
+    pub fn create_tab_with(&self, title: impl Into<String>, options: TabOptions) -> TabSnapshot {
         let mut guard = self.state.write();
         let id = TabId::next(&mut guard.next_tab_id);
         let snapshot = TabSnapshot {
@@ -100,59 +686,347 @@ impl BrowserCore {
             title: title.into(),
             url: None,
             last_loaded: None,
+            private: options.private,
+            user_agent: options.user_agent,
+            workspace: guard.active_workspace,
+            viewport: None,
+            favicon: None,
+            auto_refresh_disabled: false,
         };
         guard.tabs.push(snapshot.clone());
         snapshot
     }
 
+    /// Opens a copy of `tab` next to it, preserving its location and settings.
+    pub fn duplicate_tab(&self, tab: TabId) -> Option<TabSnapshot> {
+        let mut guard = self.state.write();
+        let position = guard.tabs.iter().position(|snapshot| snapshot.id == tab)?;
+        let id = TabId::next(&mut guard.next_tab_id);
+        let copy = TabSnapshot {
+            id,
+            ..guard.tabs[position].clone()
+        };
+        guard.tabs.insert(position + 1, copy.clone());
+        if let Some(history) = guard.tab_history.get(&tab).cloned() {
+            guard.tab_history.insert(id, history);
+        }
+        Some(copy)
+    }
+
     /// Returns a lightweight snapshot of all tabs for UI consumption.
     pub fn snapshot_tabs(&self) -> Vec<TabSnapshot> {
         self.state.read().tabs.clone()
     }
 
     /// Fetches the provided page request and returns the resulting document.
-    #[instrument(skip(self))]
     pub async fn fetch_page(&self, request: PageRequest) -> Result<PageResponse, BrowserError> {
-        let response = self
-            .client
-            .get(request.url.clone())
-            .send()
+        self.fetch_page_with_progress(request, &FetchProgress::new())
+            .await
+    }
+
+    /// [`fetch_page`](Self::fetch_page) that streams the body, reporting to
+    /// `progress` as it arrives and giving up once `progress` is cancelled.
+    #[instrument(skip(self, progress))]
+    pub async fn fetch_page_with_progress(
+        &self,
+        request: PageRequest,
+        progress: &FetchProgress,
+    ) -> Result<PageResponse, BrowserError> {
+        if let Some(page) = self.load_with_handler(&request.url).await {
+            return Ok(self.show_page_without_network(&request, page?, progress));
+        }
+        let mut request = PageRequest {
+            url: self.resolve_protocol(&request.url).await?,
+            ..request
+        };
+        let upgraded = self.upgrade_to_https(&mut request.url);
+        if request.url.scheme() == "file" {
+            let page = self.open_local_file(&request.url)?;
+            return Ok(self.show_page_without_network(&request, page, progress));
+        }
+        let privacy = self.sanitize_request(&mut request.url);
+        let offline = self.is_offline();
+        if offline {
+            request.cache = CachePolicy::OnlyIfCached;
+        }
+        // Offline, a page missing from the cache is missing for good.
+        let unavailable = |err| match err {
+            BrowserError::NotCached(_) if offline => BrowserError::Offline,
+            err => err,
+        };
+        let options = self.tab_options(request.tab);
+        let viewport = self.tab_viewport(request.tab);
+        let cacheable = request.is_safe() && !options.private && viewport.is_none();
+        let preloaded = (cacheable && request.cache != CachePolicy::Reload)
+            .then(|| self.take_preloaded(&request.url))
+            .flatten();
+        let http_cache = self.http_cache.as_ref().filter(|_| cacheable);
+        let mut page = match (preloaded, http_cache) {
+            (Some(page), _) => {
+                progress.complete(page.bytes.len());
+                PageResponse {
+                    cache: CacheStatus::Hit,
+                    ..page
+                }
+            }
+            (None, Some(cache)) => network::with_redirect_limit(
+                request.max_redirects,
+                self.fetch_through_cache(cache, &request, &options, progress),
+            )
+            .await
+            .map_err(|err| unavailable(self.certificate_error(&request.url, err)))?,
+            (None, None) if request.cache == CachePolicy::OnlyIfCached => {
+                return Err(unavailable(BrowserError::NotCached(request.url)));
+            }
+            (None, None) => {
+                let mut builder = self.request_builder(&request, &options)?;
+                if let Some(viewport) = &viewport {
+                    builder = viewport.apply_hints(builder);
+                }
+                let fetch = self.fetch_document(
+                    &request.url,
+                    builder,
+                    RequestKind::Document,
+                    Some(request.tab),
+                    Some(progress),
+                );
+                network::with_redirect_limit(request.max_redirects, fetch)
+                    .await
+                    .map_err(|err| self.certificate_error(&request.url, err))?
+            }
+        };
+        page.connection = self.tls.connection(&page.url);
+        page.privacy = privacy;
+        let banners = self.answer_consent(&mut page, options.private);
+        page.security = match NavigationSecurity::of(&page.url) {
+            NavigationSecurity::Secure if upgraded => NavigationSecurity::Upgraded,
+            security => security,
+        };
+        let mut page = self
+            .cpu
+            .run(move || {
+                consent::strip_banners(&mut page, &banners);
+                page.mixed_content = mixed_content::find_mixed_content(&page);
+                page.title = derive_title(&page);
+                page
+            })
             .await?;
 
-        let status = response.status().as_u16();
-        let mime_type = response
-            .headers()
-            .get(reqwest::header::CONTENT_TYPE)
-            .and_then(|value| value.to_str().ok())
-            .map(ToOwned::to_owned);
+        page.action = self.route_response(&page);
+        if page.action == FileAction::Open {
+            self.update_tab_after_fetch(request.tab, &request.url, &page);
+        }
 
-        let bytes = response.bytes().await?;
-        let body = String::from_utf8(bytes.to_vec()).map_err(|_| BrowserError::InvalidBody)?;
+        Ok(page)
+    }
 
-        let page = PageResponse {
-            url: request.url.clone(),
-            status,
-            mime_type,
-            title: None,
-            body,
-            received_at: Utc::now(),
-        };
+    /// Routes `page`, loaded from disk or by a scheme handler, and shows it
+    /// in the tab of `request` as a fetched one would be.
+    fn show_page_without_network(
+        &self,
+        request: &PageRequest,
+        mut page: PageResponse,
+        progress: &FetchProgress,
+    ) -> PageResponse {
+        progress.complete(page.bytes.len());
+        page.action = self.route_response(&page);
+        if page.action == FileAction::Open {
+            self.update_tab_after_fetch(request.tab, &request.url, &page);
+        }
+        page
+    }
 
-        self.update_tab_after_fetch(request.tab, &page);
+    /// Fetches a document ahead of navigation so a later `fetch_page` for the
+    /// same URL can be answered without touching the network.
+    #[instrument(skip(self))]
+    pub async fn preload(&self, url: Url) -> Result<(), BrowserError> {
+        let now = Utc::now();
+        let cached = self
+            .state
+            .read()
+            .preloaded
+            .get(&url)
+            .is_some_and(|(_, expires_at)| *expires_at > now);
+        if cached {
+            return Ok(());
+        }
+        self.fetch_into_cache(
+            url,
+            RequestKind::Preload,
+            Duration::seconds(PRELOAD_TTL_SECS),
+        )
+        .await
+    }
 
-        Ok(page)
+    /// Refetches `url` into the preload cache and keeps it for `ttl`, so the
+    /// next navigation to a pinned site is answered instantly.
+    #[instrument(skip(self))]
+    pub async fn warm_cache(&self, url: Url, ttl: Duration) -> Result<(), BrowserError> {
+        self.fetch_into_cache(url, RequestKind::Scheduled, ttl)
+            .await
     }
 
-    fn update_tab_after_fetch(&self, tab: TabId, page: &PageResponse) {
+    async fn fetch_into_cache(
+        &self,
+        mut url: Url,
+        kind: RequestKind,
+        ttl: Duration,
+    ) -> Result<(), BrowserError> {
+        self.upgrade_to_https(&mut url);
+        self.sanitize_request(&mut url);
+        let mut builder = self.client_for(&url, false).get(url.clone());
+        if let Some(ua) = self.user_agent_for(None, &url) {
+            builder = builder.header(reqwest::header::USER_AGENT, ua);
+        }
+        let page = self.fetch_document(&url, builder, kind, None, None).await?;
         let mut guard = self.state.write();
+        let now = Utc::now();
+        guard
+            .preloaded
+            .retain(|_, (_, expires_at)| *expires_at > now);
+        guard.preloaded.insert(url, (page, now + ttl));
+        Ok(())
+    }
+
+    fn take_preloaded(&self, url: &Url) -> Option<PageResponse> {
+        let (page, expires_at) = self.state.write().preloaded.remove(url)?;
+        (expires_at > Utc::now()).then_some(page)
+    }
+
+    fn tab_options(&self, tab: TabId) -> TabOptions {
+        self.state
+            .read()
+            .tabs
+            .iter()
+            .find(|snapshot| snapshot.id == tab)
+            .map(|snapshot| TabOptions {
+                private: snapshot.private,
+                user_agent: snapshot.user_agent.clone(),
+            })
+            .unwrap_or_default()
+    }
+
+    fn request_builder(
+        &self,
+        request: &PageRequest,
+        options: &TabOptions,
+    ) -> Result<reqwest::RequestBuilder, BrowserError> {
+        let client = self.client_for(&request.url, options.private);
+        let method = request_body::parse_method(&request.method)?;
+        let mut builder = client.request(method, request.url.clone());
+        if let Some(ua) = self.user_agent_for(options.user_agent.as_deref(), &request.url) {
+            builder = builder.header(reqwest::header::USER_AGENT, ua);
+        }
+        if request.cache == CachePolicy::Reload {
+            builder = builder
+                .header(reqwest::header::CACHE_CONTROL, "no-cache")
+                .header(reqwest::header::PRAGMA, "no-cache");
+        }
+        builder = request_body::apply_headers(builder, &request.headers)?;
+        if let Some(body) = &request.body {
+            builder = request_body::apply_body(builder, body, &request.headers);
+        }
+        Ok(builder)
+    }
+
+    async fn fetch_document(
+        &self,
+        url: &Url,
+        builder: reqwest::RequestBuilder,
+        kind: RequestKind,
+        tab: Option<TabId>,
+        progress: Option<&FetchProgress>,
+    ) -> Result<PageResponse, BrowserError> {
+        let response = network::send_tracked(&self.state, builder, kind, tab, progress).await?;
+        Ok(PageResponse::from_network(url, response))
+    }
+
+    /// Points `tab` at a page the shell draws itself, such as `about:jobs`,
+    /// without going to the network.
+    pub fn show_internal_page(&self, tab: TabId, url: Url, title: &str) {
+        let mut guard = self.state.write();
+        let now = Utc::now();
         if let Some(existing) = guard.tabs.iter_mut().find(|snapshot| snapshot.id == tab) {
-            existing.url = Some(page.url.clone());
-            existing.last_loaded = Some(page.received_at);
-            existing.title = derive_title(page).unwrap_or_else(|| existing.title.clone());
+            existing.url = Some(url.clone());
+            existing.last_loaded = Some(now);
+            existing.title = title.to_owned();
+            existing.favicon = None;
+            guard.commit_navigation(tab, &url, &url, title, now);
+        }
+    }
+
+    /// Retitles `tab` after `page`, without recording a navigation.
+    fn retitle_tab(&self, tab: TabId, page: &PageResponse) {
+        let mut guard = self.state.write();
+        if let Some(existing) = guard.tabs.iter_mut().find(|snapshot| snapshot.id == tab) {
+            if let Some(title) = derive_title(page) {
+                existing.title = title;
+            }
+        }
+    }
+
+    fn update_tab_after_fetch(&self, tab: TabId, requested: &Url, page: &PageResponse) {
+        let mut guard = self.state.write();
+        // The icon of the origin if it is known; otherwise it is fetched
+        // once the page is shown.
+        let favicon = guard
+            .favicons
+            .get(&page.url.origin().ascii_serialization())
+            .cloned()
+            .flatten();
+        let Some(existing) = guard.tabs.iter_mut().find(|snapshot| snapshot.id == tab) else {
+            return;
+        };
+        existing.url = Some(page.url.clone());
+        existing.last_loaded = Some(page.received_at);
+        existing.favicon = favicon;
+        existing.title = page
+            .title
+            .clone()
+            .or_else(|| derive_title(page))
+            .unwrap_or_else(|| existing.title.clone());
+        let title = existing.title.clone();
+        // Private tabs leave no trace in history.
+        let visit = (!existing.private).then(|| title.clone());
+        guard.commit_navigation(tab, requested, &page.url, &title, page.received_at);
+        guard.shield.reset_blocked(tab);
+        guard.privacy.reset_withheld(tab);
+        drop(guard);
+        if let Some(title) = visit {
+            self.record_visit(page, &title);
         }
     }
 }
 
+fn build_client(
+    user_agent: Option<&str>,
+    cookie_jar: Option<Arc<CookieJar>>,
+    proxy: &ProxySettings,
+    http_version: HttpVersionPolicy,
+    tls_observer: &Arc<TlsObserver>,
+    resolver: &Arc<TimedResolver>,
+    identity: Option<&ClientIdentity>,
+) -> reqwest::Result<reqwest::Client> {
+    let client_builder = reqwest::Client::builder()
+        .redirect(network::redirect_policy())
+        .dns_resolver(Arc::clone(resolver))
+        .use_preconfigured_tls(tls::client_config(tls_observer, http_version, identity))
+        // Bodies are decoded once read, so their sizes on the wire are known.
+        .no_gzip()
+        .no_brotli();
+    let mut client_builder = proxy.apply(http_version.apply(client_builder))?;
+
+    if let Some(jar) = cookie_jar {
+        client_builder = client_builder.cookie_provider(Arc::new(LoggedCookieJar(jar)));
+    }
+
+    if let Some(ua) = user_agent {
+        client_builder = client_builder.user_agent(ua);
+    }
+
+    client_builder.build()
+}
+
 fn derive_title(page: &PageResponse) -> Option<String> {
     if let Some(mime) = &page.mime_type {
         if !mime.starts_with("text/html") {
@@ -169,3 +1043,59 @@ fn derive_title(page: &PageResponse) -> Option<String> {
         .map(|title| title.trim().to_owned())
         .filter(|title| !title.is_empty())
 }
+
+/// Extracts the navigable `<a href>` links of an HTML document.
+///
+/// Relative references are resolved against the page URL; fragments-only,
+/// `javascript:` and other targets that are neither HTTP, IPFS nor magnet
+/// links are skipped.
+pub fn extract_links(page: &PageResponse) -> Vec<PageLink> {
+    if let Some(mime) = &page.mime_type {
+        if !mime.starts_with("text/html") {
+            return Vec::new();
+        }
+    }
+
+    let document = scraper::Html::parse_document(&page.body);
+    document_links(&document, &page.url)
+}
+
+/// Link extraction behind [`extract_links`] for an already parsed document.
+pub(crate) fn document_links(document: &scraper::Html, base: &Url) -> Vec<PageLink> {
+    let Ok(selector) = scraper::Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+
+    let mut links: Vec<PageLink> = Vec::new();
+    for element in document.select(&selector) {
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+        let Ok(mut url) = base.join(href.trim()) else {
+            continue;
+        };
+        // Pages may link within their own scheme, such as local files to
+        // each other or Gemini capsules served by a scheme handler.
+        let same_scheme = url.scheme() == base.scheme();
+        if !matches!(url.scheme(), "http" | "https" | "magnet")
+            && !protocols::is_gateway_scheme(&url)
+            && !same_scheme
+        {
+            continue;
+        }
+        url.set_fragment(None);
+        if url == *base || links.iter().any(|link| link.url == url) {
+            continue;
+        }
+
+        let text = element.text().collect::<Vec<_>>().join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let text = if text.is_empty() {
+            url.to_string()
+        } else {
+            text
+        };
+        links.push(PageLink { url, text });
+    }
+    links
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use url::Url;
+
+use crate::network::{self, LoggedResponse};
+use crate::{BrowserCore, BrowserError, BrowserState, PageLink, RequestKind};
+
+/// Maximum number of link checks in flight at once.
+const LINK_CHECK_CONCURRENCY: usize = 6;
+
+/// Minimum spacing between the start of two link checks.
+const LINK_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound for a single link check, redirects included.
+const LINK_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Outcome of checking a single link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkStatus {
+    Ok { status: u16 },
+    Redirected { status: u16, location: Url },
+    Broken { status: u16 },
+    Failed(String),
+}
+
+/// Result row produced by [`BrowserCore::check_links`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkReport {
+    pub link: PageLink,
+    pub status: LinkStatus,
+    /// The link, or the page it redirects to, is served over plain HTTP.
+    pub insecure: bool,
+}
+
+impl LinkReport {
+    pub fn is_broken(&self) -> bool {
+        matches!(
+            self.status,
+            LinkStatus::Broken { .. } | LinkStatus::Failed(_)
+        )
+    }
+
+    pub fn is_redirected(&self) -> bool {
+        matches!(self.status, LinkStatus::Redirected { .. })
+    }
+}
+
+impl BrowserCore {
+    /// Checks every link with a HEAD request, falling back to GET for servers
+    /// that reject HEAD. Requests are paced and capped so a link-heavy page does
+    /// not hammer its hosts. Reports come back in the order of `links`.
+    ///
+    /// Checks go through the cookie-less client so they never touch the
+    /// session's cookie jar. IPFS and magnet links are left out.
+    pub async fn check_links(&self, links: Vec<PageLink>) -> Vec<LinkReport> {
+        let links: Vec<PageLink> = links
+            .into_iter()
+            .filter(|link| matches!(link.url.scheme(), "http" | "https"))
+            .collect();
+        let semaphore = Arc::new(Semaphore::new(LINK_CHECK_CONCURRENCY));
+        let mut pacing = tokio::time::interval(LINK_CHECK_INTERVAL);
+        let mut tasks = JoinSet::new();
+        let total = links.len();
+
+        for (index, link) in links.into_iter().enumerate() {
+            pacing.tick().await;
+            let Ok(permit) = Arc::clone(&semaphore).acquire_owned().await else {
+                break;
+            };
+            let client = self.client_for(&link.url, true);
+            let state = Arc::clone(&self.state);
+            tasks.spawn(async move {
+                let report = check_link(&client, &state, link).await;
+                drop(permit);
+                (index, report)
+            });
+        }
+
+        let mut reports = Vec::with_capacity(total);
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok(entry) = joined {
+                reports.push(entry);
+            }
+        }
+        reports.sort_by_key(|(index, _)| *index);
+        reports.into_iter().map(|(_, report)| report).collect()
+    }
+}
+
+async fn check_link(
+    client: &reqwest::Client,
+    state: &Arc<RwLock<BrowserState>>,
+    link: PageLink,
+) -> LinkReport {
+    let mut response = probe(client, state, Method::HEAD, &link.url).await;
+    if let Ok(head) = &response {
+        if matches!(
+            head.status,
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            response = probe(client, state, Method::GET, &link.url).await;
+        }
+    }
+
+    let mut insecure = link.url.scheme() == "http";
+    let status = match response {
+        Ok(response) => {
+            let status = response.status;
+            let location = response.url;
+            insecure |= location.scheme() == "http";
+            if status.is_client_error() || status.is_server_error() {
+                LinkStatus::Broken {
+                    status: status.as_u16(),
+                }
+            } else if strip_fragment(&location) != strip_fragment(&link.url) {
+                LinkStatus::Redirected {
+                    status: status.as_u16(),
+                    location,
+                }
+            } else {
+                LinkStatus::Ok {
+                    status: status.as_u16(),
+                }
+            }
+        }
+        Err(err) => LinkStatus::Failed(err.to_string()),
+    };
+
+    LinkReport {
+        link,
+        status,
+        insecure,
+    }
+}
+
+async fn probe(
+    client: &reqwest::Client,
+    state: &Arc<RwLock<BrowserState>>,
+    method: Method,
+    url: &Url,
+) -> Result<LoggedResponse, BrowserError> {
+    let builder = client
+        .request(method, url.clone())
+        .timeout(LINK_CHECK_TIMEOUT);
+    network::send_logged(state, builder, RequestKind::LinkCheck, None).await
+}
+
+fn strip_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::Path;
+
+use bytes::Bytes;
+use chrono::{DateTime, Local};
+use url::Url;
+
+use crate::intercept::content_type_for;
+use crate::{BrowserCore, BrowserError, PageResponse};
+
+impl BrowserCore {
+    /// Loads a `file:` URL from disk. Restricted mode keeps local files out
+    /// like any other site off the allowlist.
+    pub(crate) fn open_local_file(&self, url: &Url) -> Result<PageResponse, BrowserError> {
+        if self.state.read().allowlist.is_some() {
+            return Err(BrowserError::Restricted(url.clone()));
+        }
+        load_local(url)
+    }
+}
+
+/// Loads the file or directory `url` points to. Files are served with a
+/// content type guessed from their extension; directories get an HTML
+/// index, at their URL with a trailing slash so relative links resolve
+/// inside them.
+fn load_local(url: &Url) -> Result<PageResponse, BrowserError> {
+    let path = url
+        .to_file_path()
+        .map_err(|()| BrowserError::LocalFile(format!("{url} is not a local path")))?;
+    let metadata = fs::metadata(&path)
+        .map_err(|err| BrowserError::LocalFile(format!("{}: {err}", path.display())))?;
+    if metadata.is_dir() {
+        let url = Url::from_directory_path(&path).unwrap_or_else(|()| url.clone());
+        let listing = directory_listing(&path)
+            .map_err(|err| BrowserError::LocalFile(format!("{}: {err}", path.display())))?;
+        return Ok(PageResponse::from_bytes(
+            url,
+            200,
+            Some("text/html; charset=utf-8".to_owned()),
+            Bytes::from(listing),
+        ));
+    }
+    let body = fs::read(&path)
+        .map_err(|err| BrowserError::LocalFile(format!("{}: {err}", path.display())))?;
+    Ok(PageResponse::from_bytes(
+        url.clone(),
+        200,
+        Some(content_type_for(&path).to_owned()),
+        Bytes::from(body),
+    ))
+}
+
+/// One row of a directory index.
+struct ListingEntry {
+    name: String,
+    link: Url,
+    is_dir: bool,
+    size: Option<u64>,
+    modified: Option<DateTime<Local>>,
+}
+
+/// HTML index of `dir`: folders first, then files, each alphabetically.
+fn directory_listing(dir: &Path) -> std::io::Result<String> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Follows symlinks, so links to folders list as folders.
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let is_dir = metadata.is_dir();
+        let link = if is_dir {
+            Url::from_directory_path(&path)
+        } else {
+            Url::from_file_path(&path)
+        };
+        let Ok(link) = link else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let size = (!is_dir).then_some(metadata.len());
+        let modified = metadata.modified().ok().map(DateTime::<Local>::from);
+        entries.push(ListingEntry {
+            name,
+            link,
+            is_dir,
+            size,
+            modified,
+        });
+    }
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    let title = escape_html(&dir.display().to_string());
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Index of {title}</title>\n</head>\n<body>\n<h1>Index of {title}</h1>\n\
+         <table>\n<tr><th>Name</th><th>Size</th><th>Modified</th></tr>\n"
+    );
+    if let Some(parent) = dir
+        .parent()
+        .and_then(|parent| Url::from_directory_path(parent).ok())
+    {
+        html.push_str(&format!(
+            "<tr><td><a href=\"{parent}\">Parent folder</a></td><td></td><td></td></tr>\n"
+        ));
+    }
+    for entry in entries {
+        let name = escape_html(&entry.name);
+        let name = if entry.is_dir {
+            format!("{name}/")
+        } else {
+            name
+        };
+        let link = entry.link;
+        let size = entry.size.map(format_size).unwrap_or_default();
+        let modified = entry
+            .modified
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td><a href=\"{link}\">{name}</a></td><td>{size}</td><td>{modified}</td></tr>\n"
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    Ok(html)
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, Totp, TotpError};
+
+/// Credentials for a site, as imported from another browser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedLogin {
+    /// Scheme, host and port the login belongs to, e.g. `https://example.com`.
+    pub origin: String,
+    pub username: String,
+    pub password: String,
+    /// Two-factor secret as entered: a base32 key or an `otpauth://` URI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp: Option<String>,
+}
+
+impl SavedLogin {
+    /// Generator for the login's one-time codes, if it has a valid secret.
+    pub fn totp(&self) -> Option<Totp> {
+        Totp::parse(self.totp.as_deref()?).ok()
+    }
+}
+
+impl BrowserCore {
+    pub fn saved_logins(&self) -> Vec<SavedLogin> {
+        self.state.read().logins.clone()
+    }
+
+    /// Adds `logins`, replacing the password of logins already saved for the
+    /// same origin and user name. Returns how many were new.
+    pub fn merge_logins(&self, logins: Vec<SavedLogin>) -> usize {
+        let mut guard = self.state.write();
+        let before = guard.logins.len();
+        for login in logins {
+            match guard
+                .logins
+                .iter_mut()
+                .find(|known| known.origin == login.origin && known.username == login.username)
+            {
+                Some(known) => known.password = login.password,
+                None => guard.logins.push(login),
+            }
+        }
+        guard.logins.len() - before
+    }
+
+    /// Logins saved for the origin of `url`.
+    pub fn logins_for(&self, url: &Url) -> Vec<SavedLogin> {
+        let origin = url.origin().ascii_serialization();
+        self.state
+            .read()
+            .logins
+            .iter()
+            .filter(|login| login.origin == origin)
+            .cloned()
+            .collect()
+    }
+
+    /// Stores the two-factor secret of a login, or forgets it with `None`.
+    /// The secret is checked before it is saved.
+    pub fn set_login_totp(
+        &self,
+        origin: &str,
+        username: &str,
+        secret: Option<&str>,
+    ) -> Result<(), TotpError> {
+        if let Some(secret) = secret {
+            Totp::parse(secret)?;
+        }
+        let mut guard = self.state.write();
+        if let Some(login) = guard
+            .logins
+            .iter_mut()
+            .find(|login| login.origin == origin && login.username == username)
+        {
+            login.totp = secret.map(|secret| secret.trim().to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn remove_login(&self, origin: &str, username: &str) {
+        self.state
+            .write()
+            .logins
+            .retain(|login| login.origin != origin || login.username != username);
+    }
+
+    pub fn import_logins(&self, logins: Vec<SavedLogin>) {
+        self.state.write().logins = logins;
+    }
+}
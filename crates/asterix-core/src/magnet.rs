@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// What a `magnet:` link says about the torrent it points to, read without
+/// contacting any peer or tracker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MagnetLink {
+    /// Info hash from the `xt` parameter: 40 hex or 32 base32 characters
+    /// for BitTorrent v1, a multihash for v2.
+    pub info_hash: String,
+    /// Whether the hash is a BitTorrent v2 (`btmh`) one.
+    pub v2: bool,
+    /// Suggested name (`dn`).
+    pub name: Option<String>,
+    /// Total size in bytes (`xl`).
+    pub size: Option<u64>,
+    /// Tracker announce URLs (`tr`), in the order given.
+    pub trackers: Vec<String>,
+    /// HTTP sources of the same files (`ws`).
+    pub web_seeds: Vec<String>,
+}
+
+impl MagnetLink {
+    /// Name to show for the torrent, falling back to its hash.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.info_hash)
+    }
+}
+
+/// Reads `url` when it is a magnet link with a BitTorrent info hash.
+pub fn parse_magnet(url: &Url) -> Option<MagnetLink> {
+    if url.scheme() != "magnet" {
+        return None;
+    }
+    let mut topic = None;
+    let mut name = None;
+    let mut size = None;
+    let mut trackers = Vec::new();
+    let mut web_seeds = Vec::new();
+    for (key, value) in url.query_pairs() {
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        // Clients number repeated parameters as `xt.1`, `tr.2` and so on.
+        let key = key.split('.').next().unwrap_or_default();
+        match key {
+            "xt" if topic.is_none() => topic = info_hash(value),
+            "dn" if name.is_none() => name = Some(value.to_owned()),
+            "xl" if size.is_none() => size = value.parse().ok(),
+            "tr" if !trackers.iter().any(|tracker| tracker == value) => {
+                trackers.push(value.to_owned());
+            }
+            "ws" => web_seeds.push(value.to_owned()),
+            _ => {}
+        }
+    }
+    let (info_hash, v2) = topic?;
+    Some(MagnetLink {
+        info_hash,
+        v2,
+        name,
+        size,
+        trackers,
+        web_seeds,
+    })
+}
+
+/// Hash named by an `xt` value and whether it is a v2 one.
+fn info_hash(topic: &str) -> Option<(String, bool)> {
+    let (v2, hash) = match topic.get(..9).map(str::to_ascii_lowercase).as_deref() {
+        Some("urn:btih:") => (false, &topic[9..]),
+        Some("urn:btmh:") => (true, &topic[9..]),
+        _ => return None,
+    };
+    let hex = hash.chars().all(|c| c.is_ascii_hexdigit());
+    let base32 = hash
+        .chars()
+        .all(|c| matches!(c.to_ascii_uppercase(), 'A'..='Z' | '2'..='7'));
+    let hash = match (v2, hash.len()) {
+        (false, 40) | (true, 1..) if hex => hash.to_ascii_lowercase(),
+        (false, 32) if base32 => hash.to_ascii_uppercase(),
+        _ => return None,
+    };
+    Some((hash, v2))
+}
@@ -0,0 +1,232 @@
+use ego_tree::NodeRef;
+use scraper::{Html, Node};
+use url::Url;
+
+/// Converts an HTML fragment to Markdown, keeping headings, links, lists,
+/// emphasis and code blocks. Relative links are resolved against `base`.
+pub fn html_to_markdown(html: &str, base: &Url) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut writer = MarkdownWriter {
+        out: String::new(),
+        base,
+        list_stack: Vec::new(),
+        in_pre: false,
+    };
+    for child in fragment.root_element().children() {
+        writer.node(child);
+    }
+    normalize_blank_lines(&writer.out)
+}
+
+enum ListKind {
+    Unordered,
+    Ordered(usize),
+}
+
+struct MarkdownWriter<'a> {
+    out: String,
+    base: &'a Url,
+    list_stack: Vec<ListKind>,
+    in_pre: bool,
+}
+
+impl MarkdownWriter<'_> {
+    fn node(&mut self, node: NodeRef<'_, Node>) {
+        match node.value() {
+            Node::Text(text) => self.text(text),
+            Node::Element(element) => self.element(node, element.name()),
+            _ => {}
+        }
+    }
+
+    fn children(&mut self, node: NodeRef<'_, Node>) {
+        for child in node.children() {
+            self.node(child);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.in_pre {
+            self.out.push_str(text);
+            return;
+        }
+        let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            if text.chars().next().is_some_and(char::is_whitespace) && !self.at_line_start() {
+                self.out.push(' ');
+            }
+            return;
+        }
+        if text.starts_with(char::is_whitespace)
+            && !self.at_line_start()
+            && !self.out.ends_with(' ')
+        {
+            self.out.push(' ');
+        }
+        self.out.push_str(&collapsed);
+        if text.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+    }
+
+    fn element(&mut self, node: NodeRef<'_, Node>, name: &str) {
+        match name {
+            "script" | "style" | "head" | "noscript" | "template" => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                self.block_break();
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+                self.children(node);
+                self.block_break();
+            }
+            "p" | "div" | "section" | "article" | "header" | "footer" | "main" | "figure"
+            | "table" | "tr" => {
+                self.block_break();
+                self.children(node);
+                self.block_break();
+            }
+            "br" => self.out.push_str("  \n"),
+            "hr" => {
+                self.block_break();
+                self.out.push_str("---");
+                self.block_break();
+            }
+            "a" => {
+                let href = attr(node, "href").and_then(|href| self.base.join(href).ok());
+                match href {
+                    Some(href) => {
+                        self.out.push('[');
+                        self.children(node);
+                        self.out.push_str(&format!("]({href})"));
+                    }
+                    None => self.children(node),
+                }
+            }
+            "img" => {
+                if let Some(src) = attr(node, "src").and_then(|src| self.base.join(src).ok()) {
+                    let alt = attr(node, "alt").unwrap_or_default();
+                    self.out.push_str(&format!("![{alt}]({src})"));
+                }
+            }
+            "strong" | "b" => self.wrap(node, "**"),
+            "em" | "i" => self.wrap(node, "_"),
+            "code" if !self.in_pre => self.wrap(node, "`"),
+            "pre" => {
+                self.block_break();
+                self.out.push_str("```\n");
+                self.in_pre = true;
+                self.children(node);
+                self.in_pre = false;
+                if !self.out.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.out.push_str("```");
+                self.block_break();
+            }
+            "ul" | "ol" => {
+                let kind = if name == "ol" {
+                    ListKind::Ordered(1)
+                } else {
+                    ListKind::Unordered
+                };
+                if self.list_stack.is_empty() {
+                    self.block_break();
+                }
+                self.list_stack.push(kind);
+                self.children(node);
+                self.list_stack.pop();
+                if self.list_stack.is_empty() {
+                    self.block_break();
+                }
+            }
+            "li" => {
+                self.line_break();
+                let depth = self.list_stack.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                let marker = match self.list_stack.last_mut() {
+                    Some(ListKind::Ordered(index)) => {
+                        let marker = format!("{index}. ");
+                        *index += 1;
+                        marker
+                    }
+                    _ => "- ".to_owned(),
+                };
+                self.out.push_str(&marker);
+                self.children(node);
+            }
+            "blockquote" => {
+                let mut inner = MarkdownWriter {
+                    out: String::new(),
+                    base: self.base,
+                    list_stack: Vec::new(),
+                    in_pre: false,
+                };
+                inner.children(node);
+                let quoted = normalize_blank_lines(&inner.out)
+                    .lines()
+                    .map(|line| format!("> {line}").trim_end().to_owned())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.block_break();
+                self.out.push_str(&quoted);
+                self.block_break();
+            }
+            _ => self.children(node),
+        }
+    }
+
+    fn wrap(&mut self, node: NodeRef<'_, Node>, marker: &str) {
+        self.out.push_str(marker);
+        self.children(node);
+        self.out.push_str(marker);
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    fn line_break(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+
+    fn block_break(&mut self) {
+        self.line_break();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+}
+
+fn attr<'a>(node: NodeRef<'a, Node>, name: &str) -> Option<&'a str> {
+    match node.value() {
+        Node::Element(element) => element.attr(name),
+        _ => None,
+    }
+}
+
+fn normalize_blank_lines(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut blank_run = 0;
+    for line in markdown.lines() {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+            out.push('\n');
+        } else {
+            blank_run = 0;
+            out.push_str(line.trim_end_matches(' ').trim_end_matches('\t'));
+            if line.ends_with("  ") {
+                out.push_str("  ");
+            }
+            out.push('\n');
+        }
+    }
+    out.trim().to_owned()
+}
@@ -0,0 +1,90 @@
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::PageResponse;
+
+/// Bibliographic metadata advertised by a document through its `<head>`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    pub site_name: Option<String>,
+    /// Publication date as written by the page, usually ISO 8601.
+    pub published: Option<String>,
+}
+
+/// Reads title, author, site and date information from common meta tags
+/// (Highwire `citation_*`, Open Graph, Dublin Core and plain `<meta name>`).
+pub fn extract_metadata(page: &PageResponse) -> PageMetadata {
+    if let Some(mime) = &page.mime_type {
+        if !mime.starts_with("text/html") {
+            return PageMetadata::default();
+        }
+    }
+
+    let document = Html::parse_document(&page.body);
+    let title = first_meta(&document, &["citation_title", "og:title", "dc.title"])
+        .or_else(|| element_text(&document, "title"));
+
+    let mut authors = all_meta(&document, &["citation_author", "dc.creator"]);
+    if authors.is_empty() {
+        authors = all_meta(&document, &["author", "article:author"])
+            .into_iter()
+            .filter(|author| !author.starts_with("http"))
+            .collect();
+    }
+
+    PageMetadata {
+        title,
+        authors,
+        site_name: first_meta(
+            &document,
+            &["og:site_name", "citation_journal_title", "application-name"],
+        ),
+        published: first_meta(
+            &document,
+            &[
+                "citation_publication_date",
+                "citation_date",
+                "article:published_time",
+                "dc.date",
+                "date",
+            ],
+        ),
+    }
+}
+
+/// Returns the content of every `<meta>` whose `name` or `property` matches one
+/// of `keys` (case-insensitively), in document order.
+pub(crate) fn all_meta(document: &Html, keys: &[&str]) -> Vec<String> {
+    let Ok(selector) = Selector::parse("meta[content]") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .filter(|meta| {
+            let element = meta.value();
+            let key = element.attr("name").or_else(|| element.attr("property"));
+            key.is_some_and(|key| keys.iter().any(|wanted| key.eq_ignore_ascii_case(wanted)))
+        })
+        .filter_map(|meta| meta.value().attr("content"))
+        .map(|content| content.trim().to_owned())
+        .filter(|content| !content.is_empty())
+        .collect()
+}
+
+pub(crate) fn first_meta(document: &Html, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| all_meta(document, &[key]).into_iter().next())
+}
+
+pub(crate) fn element_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    let element = document.select(&selector).next()?;
+    let text = element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
@@ -0,0 +1,353 @@
+/// Bytes of a body the patterns are matched against.
+const RESOURCE_HEADER_LEN: usize = 1445;
+
+/// Byte pattern a body may start with, with the bits that count.
+struct Pattern {
+    bytes: &'static [u8],
+    mask: &'static [u8],
+    /// Skip whitespace before matching.
+    leading_whitespace: bool,
+    mime: &'static str,
+}
+
+const fn exact(bytes: &'static [u8], mime: &'static str) -> Pattern {
+    Pattern {
+        bytes,
+        mask: &[0xFF; 16],
+        leading_whitespace: false,
+        mime,
+    }
+}
+
+impl Pattern {
+    fn matches(&self, header: &[u8]) -> bool {
+        let header = if self.leading_whitespace {
+            let start = header
+                .iter()
+                .position(|byte| !is_whitespace(*byte))
+                .unwrap_or(header.len());
+            &header[start..]
+        } else {
+            header
+        };
+        header.len() >= self.bytes.len()
+            && self
+                .bytes
+                .iter()
+                .zip(self.mask)
+                .zip(header)
+                .all(|((byte, mask), actual)| actual & mask == *byte)
+    }
+}
+
+/// Elements an HTML document commonly starts with, each followed by a
+/// space or `>`. Matched without regard to case.
+const HTML_TAGS: [&[u8]; 17] = [
+    b"<!DOCTYPE HTML",
+    b"<HTML",
+    b"<HEAD",
+    b"<SCRIPT",
+    b"<IFRAME",
+    b"<H1",
+    b"<DIV",
+    b"<FONT",
+    b"<TABLE",
+    b"<A",
+    b"<STYLE",
+    b"<TITLE",
+    b"<B",
+    b"<BODY",
+    b"<BR",
+    b"<P",
+    b"<!--",
+];
+
+/// Documents that are not HTML but may still run script.
+const SCRIPTABLE: [Pattern; 2] = [
+    Pattern {
+        bytes: b"<?xml",
+        mask: &[0xFF; 5],
+        leading_whitespace: true,
+        mime: "text/xml",
+    },
+    exact(b"%PDF-", "application/pdf"),
+];
+
+/// Text recognized by its byte order mark, and PostScript.
+const TEXT: [Pattern; 4] = [
+    exact(b"%!PS-Adobe-", "application/postscript"),
+    exact(b"\xFE\xFF", "text/plain"),
+    exact(b"\xFF\xFE", "text/plain"),
+    exact(b"\xEF\xBB\xBF", "text/plain"),
+];
+
+const IMAGES: [Pattern; 8] = [
+    exact(b"\x00\x00\x01\x00", "image/x-icon"),
+    exact(b"\x00\x00\x02\x00", "image/x-icon"),
+    exact(b"BM", "image/bmp"),
+    exact(b"GIF87a", "image/gif"),
+    exact(b"GIF89a", "image/gif"),
+    Pattern {
+        bytes: b"RIFF\x00\x00\x00\x00WEBPVP",
+        mask: b"\xFF\xFF\xFF\xFF\x00\x00\x00\x00\xFF\xFF\xFF\xFF\xFF\xFF",
+        leading_whitespace: false,
+        mime: "image/webp",
+    },
+    exact(b"\x89PNG\r\n\x1A\n", "image/png"),
+    exact(b"\xFF\xD8\xFF", "image/jpeg"),
+];
+
+const MEDIA: [Pattern; 6] = [
+    Pattern {
+        bytes: b"FORM\x00\x00\x00\x00AIFF",
+        mask: b"\xFF\xFF\xFF\xFF\x00\x00\x00\x00\xFF\xFF\xFF\xFF",
+        leading_whitespace: false,
+        mime: "audio/aiff",
+    },
+    exact(b"ID3", "audio/mpeg"),
+    exact(b"OggS\x00", "application/ogg"),
+    exact(b"MThd\x00\x00\x00\x06", "audio/midi"),
+    Pattern {
+        bytes: b"RIFF\x00\x00\x00\x00AVI ",
+        mask: b"\xFF\xFF\xFF\xFF\x00\x00\x00\x00\xFF\xFF\xFF\xFF",
+        leading_whitespace: false,
+        mime: "video/avi",
+    },
+    Pattern {
+        bytes: b"RIFF\x00\x00\x00\x00WAVE",
+        mask: b"\xFF\xFF\xFF\xFF\x00\x00\x00\x00\xFF\xFF\xFF\xFF",
+        leading_whitespace: false,
+        mime: "audio/wave",
+    },
+];
+
+const ARCHIVES: [Pattern; 3] = [
+    exact(b"\x1F\x8B\x08", "application/x-gzip"),
+    exact(b"PK\x03\x04", "application/zip"),
+    exact(b"Rar \x1A\x07\x00", "application/x-rar-compressed"),
+];
+
+/// Type to treat a response as: the `Content-Type` it was `supplied`
+/// with, or, when that is missing or says nothing about the content, the
+/// type sniffed from the start of `body`. With `no_sniff`, from
+/// `X-Content-Type-Options: nosniff`, a supplied type is always kept and
+/// a missing one is never sniffed as HTML, XML or PDF.
+pub fn sniff_mime_type(supplied: Option<&str>, no_sniff: bool, body: &[u8]) -> Option<String> {
+    let essence = supplied.map(|mime| {
+        mime.split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+    });
+    let unknown = match essence.as_deref() {
+        None | Some("") => true,
+        Some("unknown/unknown" | "application/unknown" | "*/*") => true,
+        Some("application/octet-stream") => !no_sniff,
+        Some(_) => false,
+    };
+    if !unknown {
+        return supplied.map(ToOwned::to_owned);
+    }
+    let sniffed = sniff_unknown(&body[..body.len().min(RESOURCE_HEADER_LEN)], !no_sniff);
+    match supplied {
+        // A binary guess adds nothing to what the server said.
+        Some(supplied) if sniffed == "application/octet-stream" => Some(supplied.to_owned()),
+        _ => Some(sniffed.to_owned()),
+    }
+}
+
+/// The WHATWG MIME Sniffing standard's rules for identifying an unknown
+/// MIME type, on the first bytes of a body.
+fn sniff_unknown(header: &[u8], sniff_scriptable: bool) -> &'static str {
+    if sniff_scriptable {
+        if is_html(header) {
+            return "text/html";
+        }
+        if let Some(pattern) = SCRIPTABLE.iter().find(|pattern| pattern.matches(header)) {
+            return pattern.mime;
+        }
+    }
+    let matched = TEXT
+        .iter()
+        .chain(&IMAGES)
+        .chain(&MEDIA)
+        .chain(&ARCHIVES)
+        .find(|pattern| pattern.matches(header));
+    if let Some(pattern) = matched {
+        return pattern.mime;
+    }
+    if is_mp4(header) {
+        return "video/mp4";
+    }
+    if header.starts_with(b"\x1A\x45\xDF\xA3") {
+        return "video/webm";
+    }
+    if header.iter().any(|byte| is_binary(*byte)) {
+        "application/octet-stream"
+    } else {
+        "text/plain"
+    }
+}
+
+fn is_html(header: &[u8]) -> bool {
+    let start = header
+        .iter()
+        .position(|byte| !is_whitespace(*byte))
+        .unwrap_or(header.len());
+    let header = &header[start..];
+    HTML_TAGS.iter().any(|tag| {
+        header.len() > tag.len()
+            && header[..tag.len()].eq_ignore_ascii_case(tag)
+            && matches!(header[tag.len()], b' ' | b'>')
+    })
+}
+
+/// Whether `header` starts with an ISO media `ftyp` box naming an MP4
+/// brand.
+fn is_mp4(header: &[u8]) -> bool {
+    let Some(size) = header.get(..4) else {
+        return false;
+    };
+    let size = u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize;
+    if header.len() < size
+        || !size.is_multiple_of(4)
+        || size < 12
+        || header.get(4..8) != Some(&b"ftyp"[..])
+    {
+        return false;
+    }
+    // The major brand, then the compatible brands after the minor version.
+    std::iter::once(8)
+        .chain((16..size).step_by(4))
+        .any(|offset| header.get(offset..offset + 3) == Some(&b"mp4"[..]))
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | 0x0C | b'\r' | b' ')
+}
+
+/// Control characters that do not occur in text.
+fn is_binary(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0B | 0x0E..=0x1A | 0x1C..=0x1F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff(body: &[u8]) -> String {
+        sniff_mime_type(None, false, body).unwrap()
+    }
+
+    fn mp4_header() -> Vec<u8> {
+        let mut header = vec![0, 0, 0, 0x1C];
+        header.extend_from_slice(b"ftypisom\0\0\x02\0isomiso2mp41");
+        header
+    }
+
+    #[test]
+    fn recognizes_documents_images_media_and_archives() {
+        for (body, mime) in [
+            (&b"  <!DOCTYPE html><html>"[..], "text/html"),
+            (b"\n<HTML lang=en>", "text/html"),
+            (b"<!-- comment -->", "text/html"),
+            (b"\t<?xml version=\"1.0\"?>", "text/xml"),
+            (b"%PDF-1.7", "application/pdf"),
+            (b"%!PS-Adobe-3.0", "application/postscript"),
+            (b"\xEF\xBB\xBFplain", "text/plain"),
+            (b"GIF89a\x01\x00", "image/gif"),
+            (b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR", "image/png"),
+            (b"\xFF\xD8\xFF\xE0", "image/jpeg"),
+            (b"RIFF\x10\0\0\0WEBPVP8 ", "image/webp"),
+            (b"RIFF\x10\0\0\0WAVEfmt ", "audio/wave"),
+            (b"ID3\x04", "audio/mpeg"),
+            (b"OggS\0\x02", "application/ogg"),
+            (b"\x1A\x45\xDF\xA3\x01", "video/webm"),
+            (b"\x1F\x8B\x08\0", "application/x-gzip"),
+            (b"PK\x03\x04\x14", "application/zip"),
+            (b"just some words", "text/plain"),
+            (b"\0\x01\x02binary", "application/octet-stream"),
+        ] {
+            assert_eq!(sniff(body), mime, "{body:?}");
+        }
+        assert_eq!(sniff(&mp4_header()), "video/mp4");
+    }
+
+    #[test]
+    fn truncated_signatures_are_not_recognized() {
+        for (body, mime) in [
+            (&b""[..], "text/plain"),
+            (b"<html", "text/plain"),
+            (b"<p", "text/plain"),
+            (b"GIF8", "text/plain"),
+            (b"\x89PNG\r\n", "text/plain"),
+            (b"RIFF\x10\0\0\0WEB", "application/octet-stream"),
+            (b"%PDF", "text/plain"),
+            (b"\xFF\xD8", "text/plain"),
+        ] {
+            assert_eq!(sniff(body), mime, "{body:?}");
+        }
+        // A box size past the end, or not a whole number of brands.
+        let mp4 = mp4_header();
+        assert_ne!(sniff(&mp4[..mp4.len() - 1]), "video/mp4");
+        let mut oversized = mp4.clone();
+        oversized[..4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_ne!(sniff(&oversized), "video/mp4");
+        let mut misaligned = mp4;
+        misaligned[3] = 0x1B;
+        assert_ne!(sniff(&misaligned), "video/mp4");
+    }
+
+    #[test]
+    fn keeps_meaningful_supplied_types() {
+        let html = b"<html><body>";
+        assert_eq!(
+            sniff_mime_type(Some("text/plain; charset=utf-8"), false, html).as_deref(),
+            Some("text/plain; charset=utf-8")
+        );
+        for generic in ["", "unknown/unknown", "application/unknown", "*/*"] {
+            assert_eq!(
+                sniff_mime_type(Some(generic), false, html).as_deref(),
+                Some("text/html")
+            );
+        }
+        assert_eq!(
+            sniff_mime_type(Some("application/octet-stream"), false, b"GIF89a").as_deref(),
+            Some("image/gif")
+        );
+        // A binary guess keeps what the server said.
+        assert_eq!(
+            sniff_mime_type(Some("*/*"), false, b"\0\x01").as_deref(),
+            Some("*/*")
+        );
+    }
+
+    #[test]
+    fn nosniff_never_makes_documents_scriptable() {
+        let html = b"<html><body>";
+        assert_eq!(
+            sniff_mime_type(Some("application/octet-stream"), true, html).as_deref(),
+            Some("application/octet-stream")
+        );
+        assert_eq!(
+            sniff_mime_type(None, true, html).as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            sniff_mime_type(None, true, b"%PDF-1.4").as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            sniff_mime_type(None, true, b"GIF89a").as_deref(),
+            Some("image/gif")
+        );
+    }
+
+    #[test]
+    fn looks_only_at_the_resource_header() {
+        let mut body = vec![b'a'; RESOURCE_HEADER_LEN];
+        body.push(0);
+        assert_eq!(sniff(&body), "text/plain");
+    }
+}
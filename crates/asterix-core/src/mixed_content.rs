@@ -0,0 +1,181 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+use crate::hsts::to_https;
+use crate::{map_elements, BrowserCore, BrowserError, PageResponse, TabId};
+
+/// Most insecure subresources listed for one page.
+const MAX_REPORTED: usize = 100;
+
+/// How the document of a navigation was reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NavigationSecurity {
+    /// Over HTTPS, or without crossing a network: local files and loopback
+    /// servers.
+    Secure,
+    /// Over plain HTTP, readable and changeable by anyone on the way.
+    #[default]
+    Insecure,
+    /// Asked for over HTTP but sent over HTTPS, because the site is under
+    /// HSTS.
+    Upgraded,
+}
+
+impl NavigationSecurity {
+    /// Security of a document received from `url`.
+    pub fn of(url: &Url) -> Self {
+        if is_insecure(url) {
+            NavigationSecurity::Insecure
+        } else {
+            NavigationSecurity::Secure
+        }
+    }
+
+    pub fn is_secure(self) -> bool {
+        self != NavigationSecurity::Insecure
+    }
+}
+
+/// What a secure page's plain-HTTP subresource gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MixedContentAction {
+    /// Images, audio and video are fetched over HTTPS instead.
+    Upgraded,
+    /// Scripts, styles, frames and the like could take over the page, so
+    /// they are not fetched at all.
+    Blocked,
+}
+
+/// Plain-HTTP subresource referenced by a secure page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MixedContent {
+    /// The resource as the page names it, before any upgrade.
+    pub url: Url,
+    /// Tag of the element referencing it.
+    pub element: String,
+    pub action: MixedContentAction,
+}
+
+/// Whether `url` travels over the network unencrypted.
+fn is_insecure(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "ws") && !is_loopback(url)
+}
+
+fn is_loopback(url: &Url) -> bool {
+    match url.host_str() {
+        Some(host) if host.eq_ignore_ascii_case("localhost") => true,
+        Some(host) => host
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    }
+}
+
+/// Action for the resource an element fetches through `attribute`, or
+/// `None` when the attribute does not name one.
+fn subresource_action(tag: &str, attribute: &str, rel: &str) -> Option<MixedContentAction> {
+    let rel_has = |kind: &str| {
+        rel.split_ascii_whitespace()
+            .any(|value| value.eq_ignore_ascii_case(kind))
+    };
+    match (tag, attribute) {
+        ("img" | "audio" | "video" | "source" | "track", "src") | ("video", "poster") => {
+            Some(MixedContentAction::Upgraded)
+        }
+        ("script" | "iframe" | "frame" | "embed", "src") | ("object", "data") => {
+            Some(MixedContentAction::Blocked)
+        }
+        ("link", "href") if rel_has("icon") => Some(MixedContentAction::Upgraded),
+        ("link", "href")
+            if ["stylesheet", "preload", "modulepreload", "manifest"]
+                .iter()
+                .any(|kind| rel_has(kind)) =>
+        {
+            Some(MixedContentAction::Blocked)
+        }
+        _ => None,
+    }
+}
+
+/// Plain-HTTP subresources of an HTML `page` received over HTTPS, in
+/// document order.
+pub(crate) fn find_mixed_content(page: &PageResponse) -> Vec<MixedContent> {
+    let is_html = page
+        .mime_type
+        .as_deref()
+        .is_none_or(|mime| mime.starts_with("text/html"));
+    if page.url.scheme() != "https" || !is_html {
+        return Vec::new();
+    }
+    let elements = map_elements(&page.body);
+    let base = elements
+        .iter()
+        .find(|element| element.tag == "base")
+        .and_then(|element| element.attribute("href"))
+        .and_then(|href| page.url.join(href.trim()).ok())
+        .unwrap_or_else(|| page.url.clone());
+    let mut found: Vec<MixedContent> = Vec::new();
+    for element in &elements {
+        let rel = element.attribute("rel").unwrap_or_default();
+        for (name, value) in element.attributes.iter() {
+            let Some(action) = subresource_action(&element.tag, name, rel) else {
+                continue;
+            };
+            let Ok(url) = base.join(value.trim()) else {
+                continue;
+            };
+            if url.scheme() != "http" || is_loopback(&url) || found.iter().any(|m| m.url == url) {
+                continue;
+            }
+            found.push(MixedContent {
+                url,
+                element: element.tag.to_string(),
+                action,
+            });
+            if found.len() == MAX_REPORTED {
+                return found;
+            }
+        }
+    }
+    found
+}
+
+impl BrowserCore {
+    /// Applies the mixed-content policy to a subresource at `url` that
+    /// `tab` asks for. While the tab shows a page received over HTTPS,
+    /// plain-HTTP resources are upgraded to HTTPS when `upgradeable` and refused
+    /// otherwise.
+    pub(crate) fn secure_subresource(
+        &self,
+        tab: TabId,
+        url: &mut Url,
+        upgradeable: bool,
+    ) -> Result<(), BrowserError> {
+        let page = self
+            .state
+            .read()
+            .tabs
+            .iter()
+            .find(|snapshot| snapshot.id == tab)
+            .and_then(|snapshot| snapshot.url.clone());
+        let Some(page) = page.filter(|page| page.scheme() == "https") else {
+            return Ok(());
+        };
+        if !is_insecure(url) {
+            return Ok(());
+        }
+        let insecure = url.to_string();
+        if upgradeable && to_https(url) {
+            debug!("upgraded mixed content {insecure} on {page}");
+            return Ok(());
+        }
+        debug!("blocked mixed content {insecure} on {page}");
+        Err(BrowserError::Blocked(format!(
+            "blocked as mixed content on {page}"
+        )))
+    }
+}
@@ -0,0 +1,1227 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use std::sync::Arc;
+
+use asterix_storage::CookieJar;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use hyper::client::connect::dns::Name;
+use hyper::client::connect::HttpInfo;
+use parking_lot::RwLock;
+use reqwest::cookie::CookieStore;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::header::{
+    HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING,
+    CONTENT_LENGTH, CONTENT_TYPE, COOKIE, PROXY_AUTHORIZATION, RANGE, USER_AGENT,
+};
+use reqwest::redirect::{Attempt, Policy};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::connections::{protocol_label, ConnectionTracker, ConnectionUse, SocketPair};
+use crate::content_coding::{accept_encoding, decode_response, ContentDecoding};
+use crate::dual_stack::IpPreference;
+use crate::intercept::{Interception, ResponseOverride};
+use crate::privacy;
+use crate::progress::FetchProgress;
+use crate::shield::intercept_request;
+use crate::{
+    BrowserCore, BrowserError, BrowserState, ComposedRequest, NetworkConditions, RequestAuth, TabId,
+};
+
+/// Number of requests kept in the network log before the oldest are dropped.
+const NETWORK_LOG_CAPACITY: usize = 500;
+
+/// Marks responses served from a local override, naming the file used.
+const OVERRIDE_HEADER: &str = "x-asterix-override";
+
+/// Redirects a request follows before failing, unless it sets its own
+/// limit.
+pub(crate) const MAX_REDIRECTS: usize = 10;
+
+tokio::task_local! {
+    /// Connection phases and redirects of the exchange running on this
+    /// task. The resolver, TLS verifier, cookie store and redirect policy
+    /// are called while the request future is polled, so they report here.
+    static EXCHANGE: RefCell<ExchangeRecorder>;
+    /// Redirects requests made on this task may follow.
+    static REDIRECT_LIMIT: usize;
+    /// Checks every redirect hop of the requests made on this task must
+    /// pass before it is requested.
+    static HOP_GUARD: RefCell<HopGuard>;
+}
+
+/// What issued a request recorded in the network log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequestKind {
+    Document,
+    Preload,
+    /// Document a page hinted at with `<link rel="next">` or similar.
+    Prefetch,
+    LinkCheck,
+    Watch,
+    Archive,
+    Composer,
+    Crawl,
+    /// Work run by the background job scheduler.
+    Scheduled,
+    BreachCheck,
+    Download,
+    /// Captive portal probe.
+    Connectivity,
+    /// Cached document checked for changes after the network returned.
+    Revalidation,
+    /// Opening handshake of a WebSocket.
+    WebSocket,
+    /// Server-sent event stream, logged once per connection.
+    EventStream,
+    /// Image fetched to be decoded, e.g. for a share card preview, or
+    /// referenced by a page.
+    Image,
+    /// Stylesheet linked from a page.
+    Stylesheet,
+    /// External script of a page.
+    Script,
+    /// Content blocker filter list subscribed to by URL.
+    FilterList,
+}
+
+impl RequestKind {
+    /// Whether requests of this kind come from jobs nobody is waiting on,
+    /// and so wait for a turn from the [`BackgroundGate`](crate::BackgroundGate).
+    pub fn is_background(self) -> bool {
+        matches!(
+            self,
+            RequestKind::Crawl
+                | RequestKind::LinkCheck
+                | RequestKind::BreachCheck
+                | RequestKind::Revalidation
+        )
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RequestKind::Document => "document",
+            RequestKind::Preload => "preload",
+            RequestKind::Prefetch => "prefetch",
+            RequestKind::LinkCheck => "link check",
+            RequestKind::Watch => "watch",
+            RequestKind::Archive => "archive",
+            RequestKind::Composer => "composer",
+            RequestKind::Crawl => "crawl",
+            RequestKind::Scheduled => "scheduled",
+            RequestKind::BreachCheck => "breach check",
+            RequestKind::Download => "download",
+            RequestKind::Connectivity => "connectivity",
+            RequestKind::Revalidation => "revalidation",
+            RequestKind::WebSocket => "websocket",
+            RequestKind::EventStream => "event stream",
+            RequestKind::Image => "image",
+            RequestKind::Stylesheet => "stylesheet",
+            RequestKind::Script => "script",
+            RequestKind::FilterList => "filter list",
+        }
+    }
+}
+
+/// A request captured by the network log together with its outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkEntry {
+    pub id: u64,
+    pub tab: Option<TabId>,
+    pub kind: RequestKind,
+    pub method: String,
+    pub url: Url,
+    /// Headers set on the request itself; client defaults such as the user
+    /// agent are added on the wire and not listed.
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: String,
+    /// `None` while the request is in flight or when it failed.
+    pub status: Option<u16>,
+    pub response_headers: Vec<(String, String)>,
+    /// Redirects followed before the final response, in order.
+    #[serde(default)]
+    pub redirects: Vec<RedirectHop>,
+    /// Body size once decoded.
+    pub size: usize,
+    /// Body size as received, for bodies that arrived compressed.
+    #[serde(default)]
+    pub encoded_size: Option<usize>,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: Option<u64>,
+    /// Phase breakdown, available once a response has been received.
+    pub timings: Option<Timings>,
+    /// Socket the response came back on; `None` for local overrides and
+    /// failed requests.
+    #[serde(default)]
+    pub connection: Option<ConnectionUse>,
+    pub error: Option<String>,
+}
+
+/// Phases of a completed request, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timings {
+    /// Connecting, sending and waiting for the response headers, from the
+    /// start of the request.
+    pub waiting_ms: u64,
+    /// Reading the response body.
+    pub download_ms: u64,
+    /// Following redirects before the final request was sent.
+    #[serde(default)]
+    pub redirect_ms: u64,
+    /// Resolving the host name; `None` when an open connection was reused.
+    #[serde(default)]
+    pub dns_ms: Option<u64>,
+    /// Opening the TCP connection, measured for HTTPS where the TLS
+    /// handshake marks its end.
+    #[serde(default)]
+    pub connect_ms: Option<u64>,
+    /// TLS handshake up to verifying the server's certificate.
+    #[serde(default)]
+    pub tls_ms: Option<u64>,
+    /// From the connection being ready to the response headers arriving.
+    #[serde(default)]
+    pub ttfb_ms: u64,
+    /// Waiting for a free slot under the connection limits before the
+    /// request was sent.
+    #[serde(default)]
+    pub queued_ms: u64,
+}
+
+/// A redirect response passed through on the way to the final one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectHop {
+    pub url: Url,
+    pub status: u16,
+    pub location: Url,
+    pub duration_ms: u64,
+}
+
+impl NetworkEntry {
+    pub fn is_pending(&self) -> bool {
+        self.duration_ms.is_none()
+    }
+
+    /// Converts the captured request into a form the composer can edit and resend.
+    pub fn to_composed(&self) -> ComposedRequest {
+        ComposedRequest {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers: self.request_headers.clone(),
+            body: self.request_body.clone(),
+            auth: RequestAuth::None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct NetworkLog {
+    next_id: u64,
+    entries: VecDeque<NetworkEntry>,
+    /// Headers the clients add on the wire to requests that leave them out.
+    client_headers: Vec<(String, String)>,
+    pub(crate) connections: ConnectionTracker,
+}
+
+impl NetworkLog {
+    /// Records what the clients send by default, so logged requests show
+    /// the headers that actually went out.
+    pub(crate) fn set_user_agent(&mut self, user_agent: Option<&str>) {
+        let mut headers = Vec::new();
+        if let Some(user_agent) = user_agent {
+            headers.push((USER_AGENT.to_string(), user_agent.to_owned()));
+        }
+        headers.push((ACCEPT.to_string(), "*/*".to_owned()));
+        self.client_headers = headers;
+    }
+}
+
+/// What happened on the way to a response, as reported by the client's
+/// hooks while it was running.
+struct ExchangeRecorder {
+    /// Start of the request currently being made, moved on by redirects.
+    hop_started: Instant,
+    dns_started: Option<Instant>,
+    dns_done: Option<Instant>,
+    tls_started: Option<Instant>,
+    tls_done: Option<Instant>,
+    /// `Cookie` header the jar supplied for the first request.
+    cookies: Option<String>,
+    /// Site whose cookies the exchange may send and store, when tracker
+    /// protection keeps third-party cookies out.
+    first_party: Option<String>,
+    /// Set when cookies were kept from a third-party request or response.
+    withheld_cookies: bool,
+    redirects: Vec<RedirectHop>,
+    /// Set when a redirect led back to a URL the chain already passed.
+    looped: bool,
+}
+
+impl ExchangeRecorder {
+    fn new(started: Instant) -> Self {
+        Self {
+            hop_started: started,
+            dns_started: None,
+            dns_done: None,
+            tls_started: None,
+            tls_done: None,
+            cookies: None,
+            first_party: None,
+            withheld_cookies: false,
+            redirects: Vec::new(),
+            looped: false,
+        }
+    }
+
+    /// Starts timing the request for the next redirect hop.
+    fn next_hop(&mut self, now: Instant) {
+        self.hop_started = now;
+        self.dns_started = None;
+        self.dns_done = None;
+        self.tls_started = None;
+        self.tls_done = None;
+    }
+
+    /// Timings of the final request, which received its headers at
+    /// `headers_at` and its whole body at `done`.
+    fn timings(&self, started: Instant, headers_at: Instant, done: Instant) -> Timings {
+        let ms = |from: Instant, to: Instant| to.saturating_duration_since(from).as_millis() as u64;
+        let connected = self.dns_done.unwrap_or(self.hop_started);
+        let ready = self
+            .tls_done
+            .or(self.tls_started)
+            .or(self.dns_done)
+            .unwrap_or(self.hop_started);
+        Timings {
+            waiting_ms: ms(started, headers_at),
+            download_ms: ms(headers_at, done),
+            redirect_ms: ms(started, self.hop_started),
+            dns_ms: self
+                .dns_started
+                .zip(self.dns_done)
+                .map(|(from, to)| ms(from, to)),
+            connect_ms: self.tls_started.map(|to| ms(connected, to)),
+            tls_ms: self
+                .tls_started
+                .zip(self.tls_done)
+                .map(|(from, to)| ms(from, to)),
+            ttfb_ms: ms(ready, headers_at),
+            queued_ms: 0,
+        }
+    }
+}
+
+/// Connection phase reported to the network log by the client's hooks.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Phase {
+    DnsStarted,
+    DnsDone,
+    TlsStarted,
+    TlsDone,
+}
+
+/// Notes that `phase` was reached by the request running on this task, if
+/// it is being logged.
+pub(crate) fn record_phase(phase: Phase) {
+    let now = Instant::now();
+    let _ = EXCHANGE.try_with(|exchange| {
+        let mut exchange = exchange.borrow_mut();
+        let slot = match phase {
+            Phase::DnsStarted => &mut exchange.dns_started,
+            Phase::DnsDone => &mut exchange.dns_done,
+            Phase::TlsStarted => &mut exchange.tls_started,
+            Phase::TlsDone => &mut exchange.tls_done,
+        };
+        *slot = Some(now);
+    });
+}
+
+/// Request whose redirects the policy checks like the request itself, so a
+/// hop the browser would refuse to request directly never reaches the
+/// network.
+pub(crate) struct HopGuard {
+    state: Arc<RwLock<BrowserState>>,
+    kind: RequestKind,
+    tab: Option<TabId>,
+    /// Why the policy stopped following, for the request's sender to act on.
+    stopped: Option<HopStop>,
+}
+
+/// Redirect hop the policy would not follow.
+pub(crate) enum HopStop {
+    /// The interceptor chain or the content blocker refused the hop.
+    Refused(BrowserError),
+    /// The hop is to an HSTS host over `http:`; the request is to be sent
+    /// again to this `https:` URL instead.
+    Upgrade(Url),
+}
+
+impl HopGuard {
+    /// What stops the chain from going on to `url`, if anything. Streams
+    /// are never sent again, so for them a downgrade is refused outright.
+    fn check(&self, url: &Url) -> Option<HopStop> {
+        let guard = self.state.read();
+        match intercept_request(&guard, url, self.kind, self.tab) {
+            Interception::Block(reason) => {
+                return Some(HopStop::Refused(BrowserError::Blocked(reason)))
+            }
+            Interception::Restrict => {
+                return Some(HopStop::Refused(BrowserError::Restricted(url.clone())))
+            }
+            Interception::Continue | Interception::Override(_) => {}
+        }
+        let upgraded = guard.hsts_upgrade(url)?;
+        if matches!(self.kind, RequestKind::WebSocket | RequestKind::EventStream) {
+            let reason = format!("redirected in cleartext to HSTS host {url}");
+            return Some(HopStop::Refused(BrowserError::Blocked(reason)));
+        }
+        Some(HopStop::Upgrade(upgraded))
+    }
+}
+
+/// Runs `future` with the redirect policy checking each hop of the `kind`
+/// requests it makes for `tab` against the interceptor chain, the content
+/// blocker and the HSTS store.
+pub(crate) async fn with_hop_guard<F: Future>(
+    state: &Arc<RwLock<BrowserState>>,
+    kind: RequestKind,
+    tab: Option<TabId>,
+    future: F,
+) -> F::Output {
+    let guard = HopGuard {
+        state: Arc::clone(state),
+        kind,
+        tab,
+        stopped: None,
+    };
+    HOP_GUARD.scope(RefCell::new(guard), future).await
+}
+
+/// Takes the reason the policy stopped following redirects for the request
+/// that just ran on this task.
+fn take_hop_stop() -> Option<HopStop> {
+    HOP_GUARD
+        .try_with(|guard| guard.borrow_mut().stopped.take())
+        .ok()
+        .flatten()
+}
+
+/// The error to report for a request of this task that failed with `err`:
+/// the hop the policy refused, when that is why.
+fn hop_error(err: reqwest::Error) -> BrowserError {
+    match take_hop_stop() {
+        Some(HopStop::Refused(refusal)) => refusal,
+        _ => BrowserError::Network(err),
+    }
+}
+
+/// Sends `request` on `client` with its redirects checked as
+/// [`with_hop_guard`] does, for requests whose body is read elsewhere.
+pub(crate) async fn execute_guarded(
+    state: &Arc<RwLock<BrowserState>>,
+    client: &reqwest::Client,
+    request: reqwest::Request,
+    kind: RequestKind,
+    tab: Option<TabId>,
+) -> Result<reqwest::Response, BrowserError> {
+    with_hop_guard(state, kind, tab, async {
+        client.execute(request).await.map_err(hop_error)
+    })
+    .await
+}
+
+/// Runs `future` with requests following at most `limit` redirects.
+pub(crate) async fn with_redirect_limit<F: Future>(limit: usize, future: F) -> F::Output {
+    REDIRECT_LIMIT.scope(limit, future).await
+}
+
+/// Redirect policy of the clients: follows up to [`MAX_REDIRECTS`] hops, or
+/// the limit set with [`with_redirect_limit`], records each in the network
+/// log and stops at redirect loops. Under a [`HopGuard`], hops it refuses
+/// fail the request and hops to be upgraded to HTTPS end the chain for the
+/// sender to go on with.
+pub(crate) fn redirect_policy() -> Policy {
+    Policy::custom(move |attempt: Attempt| {
+        let now = Instant::now();
+        let limit = REDIRECT_LIMIT
+            .try_with(|limit| *limit)
+            .unwrap_or(MAX_REDIRECTS);
+        // What the guard, if any, says of the hop: refused, or to be
+        // requested over HTTPS by the sender.
+        let (refused, upgrade) = HOP_GUARD
+            .try_with(|guard| {
+                let mut guard = guard.borrow_mut();
+                guard.stopped = guard.check(attempt.url());
+                match &guard.stopped {
+                    Some(HopStop::Refused(_)) => (true, None),
+                    Some(HopStop::Upgrade(url)) => (false, Some(url.clone())),
+                    None => (false, None),
+                }
+            })
+            .unwrap_or((false, None));
+        // Hops of requests sent again after an upgrade count towards the
+        // same chain; the exchange remembers them.
+        let recorded = EXCHANGE.try_with(|exchange| {
+            let mut exchange = exchange.borrow_mut();
+            if let Some(url) = attempt.previous().last() {
+                let hop = RedirectHop {
+                    url: url.clone(),
+                    status: attempt.status().as_u16(),
+                    location: upgrade.clone().unwrap_or_else(|| attempt.url().clone()),
+                    duration_ms: now
+                        .saturating_duration_since(exchange.hop_started)
+                        .as_millis() as u64,
+                };
+                exchange.redirects.push(hop);
+            }
+            // The first URL may come back once, e.g. after a form posts to
+            // itself; any other repeat would go round forever.
+            exchange.looped = exchange
+                .redirects
+                .iter()
+                .skip(1)
+                .any(|hop| hop.url == *attempt.url());
+            exchange.next_hop(now);
+            (exchange.looped, exchange.redirects.len())
+        });
+        let (looped, hops) = recorded.unwrap_or_else(|_| {
+            let looped = attempt
+                .previous()
+                .get(1..)
+                .is_some_and(|seen| seen.contains(attempt.url()));
+            (looped, attempt.previous().len())
+        });
+        if refused {
+            attempt.error("redirect refused")
+        } else if looped {
+            attempt.error("redirect loop")
+        } else if hops > limit {
+            attempt.error("too many redirects")
+        } else if upgrade.is_some() {
+            attempt.stop()
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// The request to send after `response` redirected `sent` to `location`,
+/// as the client would have built it: `303`, and `301`/`302` after a
+/// `POST`, turn into a bodiless `GET`, and credentials stay with the host
+/// they were meant for. `None` when the body cannot be sent again.
+fn redirected_request(
+    sent: reqwest::Request,
+    response: &reqwest::Response,
+    location: Url,
+) -> Option<reqwest::Request> {
+    let status = response.status();
+    let method = sent.method().clone();
+    let to_get = match status {
+        StatusCode::SEE_OTHER => method != Method::HEAD,
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => method == Method::POST,
+        _ => false,
+    };
+    let mut headers = sent.headers().clone();
+    let same_host = response.url().host_str() == location.host_str()
+        && response.url().port_or_known_default() == location.port_or_known_default();
+    if !same_host {
+        for name in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+            headers.remove(name);
+        }
+    }
+    let mut request = if to_get {
+        for name in [CONTENT_TYPE, CONTENT_LENGTH, CONTENT_ENCODING] {
+            headers.remove(name);
+        }
+        reqwest::Request::new(Method::GET, location)
+    } else {
+        let mut request = sent.try_clone()?;
+        *request.url_mut() = location;
+        request
+    };
+    *request.headers_mut() = headers;
+    *request.timeout_mut() = sent.timeout().copied();
+    Some(request)
+}
+
+/// Resolves host names like the client's default resolver, timing the
+/// lookup for the network log and ordering the addresses by the IP version
+/// preference.
+#[derive(Default)]
+pub(crate) struct TimedResolver {
+    preference: RwLock<IpPreference>,
+}
+
+impl TimedResolver {
+    pub(crate) fn set_preference(&self, preference: IpPreference) {
+        *self.preference.write() = preference;
+    }
+
+    pub(crate) fn preference(&self) -> IpPreference {
+        *self.preference.read()
+    }
+}
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let preference = self.preference();
+        Box::pin(async move {
+            record_phase(Phase::DnsStarted);
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            record_phase(Phase::DnsDone);
+            let resolved = !addrs.is_empty();
+            let addrs = preference.sort(addrs);
+            if resolved && addrs.is_empty() {
+                let message = format!("{} has no IPv4 address and IPv6 is off", name.as_str());
+                return Err(message.into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Cookie jar of the regular client, noting the `Cookie` header it adds to
+/// a logged request.
+pub(crate) struct LoggedCookieJar(pub(crate) Arc<CookieJar>);
+
+/// Whether the exchange on this task keeps cookies away from `url`,
+/// noting it if so.
+fn withholds_cookies(url: &Url) -> bool {
+    EXCHANGE
+        .try_with(|exchange| {
+            let mut exchange = exchange.borrow_mut();
+            let withheld = exchange
+                .first_party
+                .as_deref()
+                .is_some_and(|site| privacy::is_third_party(site, url));
+            exchange.withheld_cookies |= withheld;
+            withheld
+        })
+        .unwrap_or(false)
+}
+
+impl CookieStore for LoggedCookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        if withholds_cookies(url) {
+            return;
+        }
+        CookieStore::set_cookies(&*self.0, cookie_headers, url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = CookieStore::cookies(&*self.0, url);
+        if header.is_some() && withholds_cookies(url) {
+            return None;
+        }
+        if let Some(value) = &header {
+            let _ = EXCHANGE.try_with(|exchange| {
+                let mut exchange = exchange.borrow_mut();
+                if exchange.redirects.is_empty() {
+                    exchange.cookies = Some(String::from_utf8_lossy(value.as_bytes()).into_owned());
+                }
+            });
+        }
+        header
+    }
+}
+
+/// Response whose body has been read in full while being logged.
+pub(crate) struct LoggedResponse {
+    pub(crate) url: Url,
+    pub(crate) status: reqwest::StatusCode,
+    pub(crate) version: reqwest::Version,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Bytes,
+    pub(crate) timings: Timings,
+    pub(crate) redirects: Vec<RedirectHop>,
+    /// `Cookie` header the client's jar added to the request.
+    pub(crate) cookies: Option<String>,
+    /// Whether tracker protection kept third-party cookies out.
+    pub(crate) withheld_cookies: bool,
+    pub(crate) socket: Option<SocketPair>,
+    /// Set once a compressed body has been decoded.
+    pub(crate) decoding: Option<ContentDecoding>,
+}
+
+impl BrowserCore {
+    /// Requests captured since startup, oldest first.
+    pub fn network_log(&self) -> Vec<NetworkEntry> {
+        self.state.read().network.entries.iter().cloned().collect()
+    }
+
+    /// Requests made for `tab` that have not finished yet.
+    pub fn pending_requests(&self, tab: TabId) -> usize {
+        self.state
+            .read()
+            .network
+            .entries
+            .iter()
+            .filter(|entry| entry.tab == Some(tab) && entry.is_pending())
+            .count()
+    }
+
+    /// Empties the log and resets the connection statistics.
+    pub fn clear_network_log(&self) {
+        let mut guard = self.state.write();
+        guard.network.entries.clear();
+        guard.network.connections.clear();
+    }
+
+    /// Sends `builder`, reads the whole body and records the exchange in the
+    /// network log.
+    pub(crate) async fn send_logged(
+        &self,
+        builder: reqwest::RequestBuilder,
+        kind: RequestKind,
+        tab: Option<TabId>,
+    ) -> Result<LoggedResponse, BrowserError> {
+        send_logged(&self.state, builder, kind, tab).await
+    }
+}
+
+/// Free-standing version of [`BrowserCore::send_logged`] for spawned tasks
+/// that only hold on to the shared state. Requests pass through the
+/// interceptor chain first and may be blocked without touching the network.
+pub(crate) async fn send_logged(
+    state: &Arc<RwLock<BrowserState>>,
+    builder: reqwest::RequestBuilder,
+    kind: RequestKind,
+    tab: Option<TabId>,
+) -> Result<LoggedResponse, BrowserError> {
+    send_tracked(state, builder, kind, tab, None).await
+}
+
+/// [`send_logged`] that reports how much of the body has arrived to
+/// `progress` and stops early when it is cancelled.
+pub(crate) async fn send_tracked(
+    state: &Arc<RwLock<BrowserState>>,
+    builder: reqwest::RequestBuilder,
+    kind: RequestKind,
+    tab: Option<TabId>,
+    progress: Option<&FetchProgress>,
+) -> Result<LoggedResponse, BrowserError> {
+    let (client, request) = builder.build_split();
+    let mut request = request?;
+    state.write().http_auth.authorize(&mut request);
+    let retry = request.try_clone();
+    let response = send_request(state, &client, request, kind, tab, progress).await?;
+    let Some(mut retry) = retry.filter(|_| response.status == reqwest::StatusCode::UNAUTHORIZED)
+    else {
+        return Ok(response);
+    };
+    // Challenges for a realm the user already signed in to are answered
+    // here; the rest reach the page for the user to answer.
+    if !state
+        .write()
+        .http_auth
+        .answer(&mut retry, &response.headers)
+    {
+        return Ok(response);
+    }
+    send_request(state, &client, retry, kind, tab, progress).await
+}
+
+/// Sends `request` once, logging the exchange.
+async fn send_request(
+    state: &Arc<RwLock<BrowserState>>,
+    client: &reqwest::Client,
+    mut request: reqwest::Request,
+    kind: RequestKind,
+    tab: Option<TabId>,
+    progress: Option<&FetchProgress>,
+) -> Result<LoggedResponse, BrowserError> {
+    let (first_party, conditions) = {
+        let guard = state.read();
+        guard.privacy.apply_opt_out(request.headers_mut());
+        // Ranges are of the body as stored, so they are asked for plain.
+        if !request.headers().contains_key(RANGE) {
+            request
+                .headers_mut()
+                .entry(ACCEPT_ENCODING)
+                .or_insert_with(|| accept_encoding(&guard.content_codings));
+        }
+        (
+            privacy::first_party(&guard, kind, tab),
+            guard.network_conditions,
+        )
+    };
+    let mut request_headers = header_pairs(request.headers());
+    let sends_cookies = request.headers().contains_key(COOKIE);
+    {
+        let guard = state.read();
+        for (name, value) in &guard.network.client_headers {
+            if !request.headers().contains_key(name.as_str()) {
+                request_headers.push((name.clone(), value.clone()));
+            }
+        }
+    }
+    let id = start_entry(
+        state,
+        NetworkEntry {
+            id: 0,
+            tab,
+            kind,
+            method: request.method().to_string(),
+            url: request.url().clone(),
+            request_headers,
+            request_body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+                .unwrap_or_default(),
+            status: None,
+            response_headers: Vec::new(),
+            redirects: Vec::new(),
+            size: 0,
+            encoded_size: None,
+            started_at: Utc::now(),
+            duration_ms: None,
+            timings: None,
+            connection: None,
+            error: None,
+        },
+    );
+
+    let started = Instant::now();
+    let _unfinished = Unfinished { state, id, started };
+    let interception = intercept_request(&state.read(), request.url(), kind, tab);
+    let result = match interception {
+        Interception::Block(reason) => Err(BrowserError::Blocked(reason)),
+        Interception::Restrict => Err(BrowserError::Restricted(request.url().clone())),
+        Interception::Override(rule) => serve_override(&rule, request.url()),
+        Interception::Continue if state.read().is_offline() => Err(BrowserError::Offline),
+        Interception::Continue => {
+            let (gate, admission) = {
+                let guard = state.read();
+                let gate = guard
+                    .background_gate
+                    .clone()
+                    .filter(|_| kind.is_background());
+                (gate, guard.limiter.admit(request.url()))
+            };
+            let admission = async move {
+                // Background work waits its turn before taking a slot.
+                let turn = match gate {
+                    Some(gate) => Some(gate.admit().await),
+                    None => None,
+                };
+                (turn, admission.await)
+            };
+            let exchange = execute_admitted(
+                admission,
+                client,
+                request,
+                progress,
+                first_party,
+                conditions,
+            );
+            let exchange = with_hop_guard(state, kind, tab, exchange);
+            tokio::select! {
+                response = exchange => response,
+                () = cancelled(progress) => Err(BrowserError::Cancelled),
+            }
+        }
+    };
+    let result = result.and_then(decode_response);
+    // Overrides never stream their body; show every success as finished.
+    if let (Some(progress), Ok(response)) = (progress, &result) {
+        progress.complete(response.body.len());
+    }
+
+    let elapsed = started.elapsed().as_millis() as u64;
+    let mut guard = state.write();
+    if let Ok(response) = &result {
+        if let Some(tab) = tab.filter(|_| response.withheld_cookies) {
+            guard.privacy.count_withheld(tab);
+        }
+        guard.record_tab_hsts(tab, &response.url, &response.headers);
+    }
+    let connection = result.as_ref().ok().and_then(|response| {
+        let socket = response.socket?;
+        let host = response.url.host_str().unwrap_or_default();
+        let protocol = protocol_label(response.version);
+        Some(guard.network.connections.record(socket, host, protocol))
+    });
+    if let Some(entry) = guard
+        .network
+        .entries
+        .iter_mut()
+        .rev()
+        .find(|entry| entry.id == id)
+    {
+        entry.duration_ms = Some(elapsed);
+        match &result {
+            Ok(response) => {
+                entry.status = Some(response.status.as_u16());
+                entry.response_headers = header_pairs(&response.headers);
+                entry.size = response.body.len();
+                entry.encoded_size = response
+                    .decoding
+                    .as_ref()
+                    .map(|decoding| decoding.encoded_size);
+                entry.timings = Some(response.timings);
+                entry.connection = connection;
+                entry.redirects = response.redirects.clone();
+                if let Some(cookies) = response.cookies.as_ref().filter(|_| !sends_cookies) {
+                    entry
+                        .request_headers
+                        .push((COOKIE.to_string(), cookies.clone()));
+                }
+            }
+            Err(err) => {
+                if let BrowserError::RedirectLoop(redirects) = err {
+                    entry.redirects = redirects.clone();
+                }
+                entry.error = Some(err.to_string());
+            }
+        }
+    }
+    result
+}
+
+/// Closes the log entry of a request whose task is dropped before the
+/// exchange ends, as when the page it was for is abandoned.
+struct Unfinished<'a> {
+    state: &'a Arc<RwLock<BrowserState>>,
+    id: u64,
+    started: Instant,
+}
+
+impl Drop for Unfinished<'_> {
+    fn drop(&mut self) {
+        let mut guard = self.state.write();
+        let entry = guard
+            .network
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.id == self.id);
+        if let Some(entry) = entry.filter(|entry| entry.duration_ms.is_none()) {
+            entry.duration_ms = Some(self.started.elapsed().as_millis() as u64);
+            entry.error = Some(BrowserError::Cancelled.to_string());
+        }
+    }
+}
+
+/// Waits for `progress` to be cancelled, or forever without one.
+async fn cancelled(progress: Option<&FetchProgress>) {
+    match progress {
+        Some(progress) => progress.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// [`execute`] once `admission` grants the request its slots, holding them
+/// until the body has been read. Emulated latency and upload time are
+/// spent holding the slot, as a slow connection would be.
+async fn execute_admitted<A>(
+    admission: impl Future<Output = A>,
+    client: &reqwest::Client,
+    request: reqwest::Request,
+    progress: Option<&FetchProgress>,
+    first_party: Option<String>,
+    conditions: NetworkConditions,
+) -> Result<LoggedResponse, BrowserError> {
+    let queued_at = Instant::now();
+    let _admission = admission.await;
+    let started = Instant::now();
+    if conditions.is_throttled() {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map_or(0, <[u8]>::len);
+        tokio::time::sleep(conditions.send_delay(body)).await;
+    }
+    let mut response = execute(client, request, started, progress, first_party, conditions).await?;
+    response.timings.queued_ms = started.duration_since(queued_at).as_millis() as u64;
+    Ok(response)
+}
+
+async fn execute(
+    client: &reqwest::Client,
+    request: reqwest::Request,
+    started: Instant,
+    progress: Option<&FetchProgress>,
+    first_party: Option<String>,
+    conditions: NetworkConditions,
+) -> Result<LoggedResponse, BrowserError> {
+    let exchange = RefCell::new(ExchangeRecorder {
+        first_party,
+        ..ExchangeRecorder::new(started)
+    });
+    EXCHANGE
+        .scope(exchange, async {
+            execute_recorded(client, request, started, progress, conditions)
+                .await
+                .map_err(|err| {
+                    EXCHANGE.with(|exchange| {
+                        let exchange = exchange.borrow();
+                        if exchange.looped {
+                            BrowserError::RedirectLoop(exchange.redirects.clone())
+                        } else {
+                            hop_error(err)
+                        }
+                    })
+                })
+        })
+        .await
+}
+
+async fn execute_recorded(
+    client: &reqwest::Client,
+    request: reqwest::Request,
+    started: Instant,
+    progress: Option<&FetchProgress>,
+    conditions: NetworkConditions,
+) -> reqwest::Result<LoggedResponse> {
+    let mut request = request;
+    let mut response = loop {
+        let sent = request.try_clone();
+        let response = client.execute(request).await?;
+        let Some(HopStop::Upgrade(location)) = take_hop_stop() else {
+            break response;
+        };
+        // The redirect itself arrived, so its own policy counts.
+        note_hop_hsts(&response);
+        match sent.and_then(|sent| redirected_request(sent, &response, location)) {
+            Some(next) => request = next,
+            None => break response,
+        }
+    };
+    let headers_at = Instant::now();
+    let url = response.url().clone();
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+    let socket = response
+        .extensions()
+        .get::<HttpInfo>()
+        .map(|info| (info.local_addr(), info.remote_addr()));
+    if let Some(progress) = progress {
+        progress.start_body(response.content_length());
+    }
+    let mut chunks = Vec::new();
+    let mut received = 0;
+    while let Some(chunk) = response.chunk().await? {
+        received += chunk.len();
+        if conditions.download_bytes_per_sec > 0 {
+            // Hold the chunk back until a throttled download would have it.
+            let due = headers_at + conditions.receive_time(received);
+            tokio::time::sleep_until(due.into()).await;
+        }
+        if let Some(progress) = progress {
+            progress.add_received(chunk.len());
+        }
+        chunks.push(chunk);
+    }
+    let body = join_chunks(chunks);
+    let exchange = EXCHANGE.with(|exchange| exchange.replace(ExchangeRecorder::new(started)));
+    Ok(LoggedResponse {
+        url,
+        status,
+        version,
+        headers,
+        body,
+        timings: exchange.timings(started, headers_at, Instant::now()),
+        redirects: exchange.redirects,
+        cookies: exchange.cookies,
+        withheld_cookies: exchange.withheld_cookies,
+        socket,
+        decoding: None,
+    })
+}
+
+/// Notes the `Strict-Transport-Security` header of a redirect the policy
+/// stopped at, for the request guarded on this task.
+fn note_hop_hsts(response: &reqwest::Response) {
+    let _ = HOP_GUARD.try_with(|guard| {
+        let guard = guard.borrow();
+        guard
+            .state
+            .write()
+            .record_tab_hsts(guard.tab, response.url(), response.headers());
+    });
+}
+
+/// Body received as `chunks`. A body that arrived in one chunk is kept as
+/// it is; others are copied once into a buffer of their exact size.
+fn join_chunks(mut chunks: Vec<Bytes>) -> Bytes {
+    if chunks.len() <= 1 {
+        return chunks.pop().unwrap_or_default();
+    }
+    let mut body = BytesMut::with_capacity(chunks.iter().map(Bytes::len).sum());
+    for chunk in chunks {
+        body.extend_from_slice(&chunk);
+    }
+    body.freeze()
+}
+
+/// Answers a request from the local file mapped to its URL.
+fn serve_override(rule: &ResponseOverride, url: &Url) -> Result<LoggedResponse, BrowserError> {
+    let body = std::fs::read(&rule.path)
+        .map(Bytes::from)
+        .map_err(|err| BrowserError::LocalOverride(format!("{}: {err}", rule.path.display())))?;
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(rule.content_type()));
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+    if let Ok(path) = HeaderValue::from_str(&rule.path.display().to_string()) {
+        headers.insert(OVERRIDE_HEADER, path);
+    }
+    Ok(LoggedResponse {
+        url: url.clone(),
+        status: reqwest::StatusCode::OK,
+        version: reqwest::Version::HTTP_11,
+        headers,
+        body,
+        timings: Timings::default(),
+        redirects: Vec::new(),
+        cookies: None,
+        withheld_cookies: false,
+        socket: None,
+        decoding: None,
+    })
+}
+
+pub(crate) fn start_entry(state: &Arc<RwLock<BrowserState>>, mut entry: NetworkEntry) -> u64 {
+    let mut guard = state.write();
+    let log = &mut guard.network;
+    let id = log.next_id;
+    log.next_id += 1;
+    entry.id = id;
+    if log.entries.len() == NETWORK_LOG_CAPACITY {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(entry);
+    id
+}
+
+/// Entry for the opening request of a long-lived connection, such as a
+/// WebSocket or an event stream, whose body is never read in one piece.
+/// Logged with [`start_entry`] once the response headers are in.
+pub(crate) fn stream_entry(
+    kind: RequestKind,
+    tab: TabId,
+    url: &Url,
+    request: &reqwest::Request,
+) -> NetworkEntry {
+    NetworkEntry {
+        id: 0,
+        tab: Some(tab),
+        kind,
+        method: request.method().to_string(),
+        url: url.clone(),
+        request_headers: header_pairs(request.headers()),
+        request_body: String::new(),
+        status: None,
+        response_headers: Vec::new(),
+        redirects: Vec::new(),
+        size: 0,
+        encoded_size: None,
+        started_at: Utc::now(),
+        duration_ms: None,
+        timings: None,
+        connection: None,
+        error: None,
+    }
+}
+
+pub(crate) fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{BlockRule, HstsEntry, PageRequest, ProxySettings};
+
+    fn core() -> BrowserCore {
+        BrowserCore::new(None, Arc::default(), None, ProxySettings::default()).unwrap()
+    }
+
+    fn guard(core: &BrowserCore, kind: RequestKind) -> HopGuard {
+        HopGuard {
+            state: Arc::clone(&core.state),
+            kind,
+            tab: None,
+            stopped: None,
+        }
+    }
+
+    #[test]
+    fn upgrades_cleartext_hops_to_hsts_hosts() {
+        let core = core();
+        core.import_hsts(vec![HstsEntry {
+            host: "example.com".to_owned(),
+            include_subdomains: true,
+            expires_at: Utc::now() + chrono::Duration::days(1),
+        }]);
+        let hop = Url::parse("http://www.example.com:80/next").unwrap();
+        match guard(&core, RequestKind::Document).check(&hop) {
+            Some(HopStop::Upgrade(url)) => assert_eq!(url.as_str(), "https://www.example.com/next"),
+            _ => panic!("hop was not upgraded"),
+        }
+        assert!(matches!(
+            guard(&core, RequestKind::WebSocket).check(&hop),
+            Some(HopStop::Refused(BrowserError::Blocked(_)))
+        ));
+        let other = Url::parse("http://example.org/").unwrap();
+        assert!(guard(&core, RequestKind::Document).check(&other).is_none());
+    }
+
+    #[tokio::test]
+    async fn blocked_redirect_hops_are_never_requested() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        let blocked_hits = Arc::new(AtomicUsize::new(0));
+        let hits = Arc::clone(&blocked_hits);
+        let location = format!("{origin}/blocked");
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0; 4096];
+                let read = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..read]);
+                let response = if request.starts_with("GET /blocked") {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    "HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n".to_owned()
+                } else {
+                    format!(
+                        "HTTP/1.1 302 Found\r\nlocation: {location}\r\ncontent-length: 0\r\n\r\n"
+                    )
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let core = core();
+        core.add_block_rule(BlockRule::Url(
+            Url::parse(&format!("{origin}/blocked")).unwrap(),
+        ));
+        let tab = core.create_tab("test").id;
+        let url = Url::parse(&format!("{origin}/start")).unwrap();
+        let result = core.fetch_page(PageRequest::new(tab, url)).await;
+        assert!(
+            matches!(result, Err(BrowserError::Blocked(_))),
+            "{result:?}"
+        );
+        assert_eq!(blocked_hits.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{BrowserCore, BrowserState};
+
+impl BrowserState {
+    /// Whether requests must stay off the network, because offline mode is
+    /// on or an outage is being emulated.
+    pub(crate) fn is_offline(&self) -> bool {
+        self.offline || self.network_conditions.offline
+    }
+}
+
+impl BrowserCore {
+    /// Turns offline mode on or off. While it is on nothing reaches the
+    /// network: navigations are answered from the HTTP cache however old
+    /// the copy, and everything else fails with
+    /// [`BrowserError::Offline`](crate::BrowserError::Offline).
+    pub fn set_offline(&self, offline: bool) {
+        self.state.write().offline = offline;
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.state.read().offline
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{Duration, Utc};
+use scraper::{Html, Selector};
+use tracing::instrument;
+use url::Url;
+
+use crate::{BrowserCore, BrowserError, PageResponse, RequestKind};
+
+/// Most hinted documents fetched for one page.
+const MAX_PREFETCH_HINTS: usize = 4;
+
+/// How long a prefetched document waits to be navigated to. Longer than a
+/// preload's, since the next page is usually wanted once this one is read.
+const PREFETCH_TTL_SECS: i64 = 10 * 60;
+
+/// Documents `page` suggests fetching ahead of time through its
+/// `<link rel="next">`, `rel="prev"` and `rel="prefetch"` hints, in the
+/// order they appear.
+pub fn prefetch_hints(page: &PageResponse) -> Vec<Url> {
+    if page
+        .mime_type
+        .as_deref()
+        .is_some_and(|mime| !mime.starts_with("text/html"))
+    {
+        return Vec::new();
+    }
+    let Ok(selector) = Selector::parse("link[rel][href]") else {
+        return Vec::new();
+    };
+    let document = Html::parse_document(&page.body);
+    let mut hints: Vec<Url> = Vec::new();
+    for element in document.select(&selector) {
+        let hinted = element
+            .value()
+            .attr("rel")
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+            .any(|rel| {
+                ["next", "prev", "previous", "prefetch"]
+                    .iter()
+                    .any(|hint| rel.eq_ignore_ascii_case(hint))
+            });
+        if !hinted {
+            continue;
+        }
+        let href = element.value().attr("href").unwrap_or_default();
+        let Ok(mut url) = page.url.join(href.trim()) else {
+            continue;
+        };
+        url.set_fragment(None);
+        if !matches!(url.scheme(), "http" | "https") || url == page.url || hints.contains(&url) {
+            continue;
+        }
+        hints.push(url);
+        if hints.len() == MAX_PREFETCH_HINTS {
+            break;
+        }
+    }
+    hints
+}
+
+impl BrowserCore {
+    /// Fetches a document a page hinted at into the preload cache, so
+    /// following the hint is answered without touching the network. Does
+    /// nothing when a usable copy is already at hand.
+    #[instrument(skip(self))]
+    pub async fn prefetch(&self, url: Url) -> Result<(), BrowserError> {
+        let now = Utc::now();
+        let preloaded = self
+            .state
+            .read()
+            .preloaded
+            .get(&url)
+            .is_some_and(|(_, expires_at)| *expires_at > now);
+        let cached = self
+            .http_cache
+            .as_ref()
+            .is_some_and(|cache| cache.is_fresh(&url));
+        if preloaded || cached {
+            return Ok(());
+        }
+        self.fetch_into_cache(
+            url,
+            RequestKind::Prefetch,
+            Duration::seconds(PREFETCH_TTL_SECS),
+        )
+        .await
+    }
+}
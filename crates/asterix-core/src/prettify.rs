@@ -0,0 +1,808 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Indentation added per nesting level.
+const INDENT: &str = "  ";
+
+/// JavaScript keywords after which a `/` starts a regular expression rather
+/// than a division.
+const REGEX_KEYWORDS: [&str; 9] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+];
+
+/// Keywords written with a space before their parenthesised condition.
+const CONTROL_KEYWORDS: [&str; 6] = ["if", "for", "while", "switch", "catch", "with"];
+
+/// Keywords that continue the statement a closing brace ends.
+const CONTINUATION_KEYWORDS: [&str; 4] = ["else", "catch", "finally", "while"];
+
+/// Languages the source viewers can pretty print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceLanguage {
+    Css,
+    JavaScript,
+    Json,
+}
+
+impl SourceLanguage {
+    /// Language of a resource by its MIME type, or by the extension of
+    /// `url` when the type is missing or too generic to tell.
+    pub fn detect(mime_type: Option<&str>, url: &Url) -> Option<Self> {
+        let essence = mime_type
+            .and_then(|mime| mime.split(';').next())
+            .map(|mime| mime.trim().to_ascii_lowercase());
+        let by_mime = essence.as_deref().and_then(|mime| match mime {
+            "text/css" => Some(SourceLanguage::Css),
+            "text/javascript"
+            | "application/javascript"
+            | "application/x-javascript"
+            | "application/ecmascript"
+            | "text/ecmascript" => Some(SourceLanguage::JavaScript),
+            _ if mime == "application/json" || mime.ends_with("+json") => {
+                Some(SourceLanguage::Json)
+            }
+            _ => None,
+        });
+        by_mime.or_else(|| {
+            let generic = essence
+                .as_deref()
+                .is_none_or(|mime| mime == "text/plain" || mime == "application/octet-stream");
+            if !generic {
+                return None;
+            }
+            let extension = url.path().rsplit_once('.')?.1.to_ascii_lowercase();
+            match extension.as_str() {
+                "css" => Some(SourceLanguage::Css),
+                "js" | "mjs" | "cjs" => Some(SourceLanguage::JavaScript),
+                "json" | "map" | "webmanifest" => Some(SourceLanguage::Json),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SourceLanguage::Css => "CSS",
+            SourceLanguage::JavaScript => "JavaScript",
+            SourceLanguage::Json => "JSON",
+        }
+    }
+}
+
+/// Reformats minified `source` one statement or declaration per line,
+/// indented by nesting. The result is meant for reading; comments and
+/// string contents are kept as they are.
+pub fn prettify(source: &str, language: SourceLanguage) -> String {
+    match language {
+        SourceLanguage::Css => prettify_css(source),
+        SourceLanguage::JavaScript => prettify_js(source),
+        SourceLanguage::Json => prettify_json(source),
+    }
+}
+
+fn push_line(out: &mut String, depth: usize, line: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    for _ in 0..depth {
+        out.push_str(INDENT);
+    }
+    out.push_str(line);
+}
+
+/// Index just past the string literal opening at `start`.
+fn string_end(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return i + 1,
+            '\n' if quote != '`' => return i,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Index just past the `/* */` comment opening at `start`.
+fn block_comment_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 2;
+    while i + 1 < chars.len() {
+        if chars[i] == '*' && chars[i + 1] == '/' {
+            return i + 2;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+fn prettify_json(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::with_capacity(source.len() * 2);
+    let mut line = String::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' => {
+                let end = string_end(&chars, i);
+                line.extend(&chars[i..end]);
+                i = end;
+                continue;
+            }
+            '{' | '[' => {
+                // Empty containers stay on one line.
+                let close = if c == '{' { '}' } else { ']' };
+                let next = chars[i + 1..].iter().position(|c| !c.is_whitespace());
+                if next.is_some_and(|offset| chars[i + 1 + offset] == close) {
+                    line.push(c);
+                    line.push(close);
+                    i += next.unwrap_or(0) + 2;
+                    continue;
+                }
+                line.push(c);
+                push_line(&mut out, depth, &line);
+                line.clear();
+                depth += 1;
+            }
+            '}' | ']' => {
+                if !line.is_empty() {
+                    push_line(&mut out, depth, &line);
+                    line.clear();
+                }
+                depth = depth.saturating_sub(1);
+                line.push(c);
+            }
+            ',' => {
+                line.push(',');
+                push_line(&mut out, depth, &line);
+                line.clear();
+            }
+            ':' => line.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => line.push(c),
+        }
+        i += 1;
+    }
+    if !line.is_empty() {
+        push_line(&mut out, depth, &line);
+    }
+    out
+}
+
+fn prettify_css(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::with_capacity(source.len() * 2);
+    let mut statement = String::new();
+    let mut depth = 0usize;
+    let mut parens = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                let end = string_end(&chars, i);
+                statement.extend(&chars[i..end]);
+                i = end;
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                let end = block_comment_end(&chars, i);
+                flush_declaration(&mut out, depth, &mut statement, false);
+                let comment: String = chars[i..end].iter().collect();
+                push_line(&mut out, depth, comment.trim());
+                i = end;
+                continue;
+            }
+            '(' => {
+                parens += 1;
+                statement.push(c);
+            }
+            ')' => {
+                parens = parens.saturating_sub(1);
+                statement.push(c);
+            }
+            '{' if parens == 0 => {
+                let prelude = collapse_whitespace(&statement);
+                statement.clear();
+                if depth == 0 && !out.is_empty() {
+                    out.push('\n');
+                }
+                push_line(&mut out, depth, &format!("{prelude} {{"));
+                depth += 1;
+            }
+            ',' if parens == 0 => statement.push_str(", "),
+            ';' if parens == 0 => flush_declaration(&mut out, depth, &mut statement, true),
+            '}' if parens == 0 => {
+                flush_declaration(&mut out, depth, &mut statement, true);
+                depth = depth.saturating_sub(1);
+                push_line(&mut out, depth, "}");
+            }
+            c if c.is_whitespace() => {
+                if !statement.is_empty() && !statement.ends_with(' ') {
+                    statement.push(' ');
+                }
+            }
+            c => statement.push(c),
+        }
+        i += 1;
+    }
+    flush_declaration(&mut out, depth, &mut statement, false);
+    out
+}
+
+/// Writes the declaration (or at-rule such as `@import`) collected in
+/// `statement` on its own line as `property: value;`.
+fn flush_declaration(out: &mut String, depth: usize, statement: &mut String, terminated: bool) {
+    let text = collapse_whitespace(statement);
+    statement.clear();
+    if text.is_empty() {
+        return;
+    }
+    let mut line = match text.split_once(':') {
+        Some((property, value)) if !text.starts_with('@') => {
+            format!("{}: {}", property.trim_end(), value.trim_start())
+        }
+        _ => text,
+    };
+    if terminated {
+        line.push(';');
+    }
+    push_line(out, depth, &line);
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lexical class of a JavaScript token, as far as layout needs to know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Literal,
+    Punct,
+    Comment,
+}
+
+struct Token {
+    kind: TokenKind,
+    text: String,
+    /// Whether a line break separated this token from the one before.
+    newline_before: bool,
+}
+
+fn tokenize_js(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut newline_before = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            newline_before |= c == '\n';
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let kind = if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            TokenKind::Comment
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i = block_comment_end(&chars, i);
+            TokenKind::Comment
+        } else if matches!(c, '"' | '\'' | '`') {
+            i = string_end(&chars, i);
+            TokenKind::Literal
+        } else if c == '/' && regex_allowed(tokens.last()) {
+            i = regex_end(&chars, i);
+            TokenKind::Literal
+        } else if c.is_alphanumeric() || matches!(c, '_' | '$') {
+            let number = c.is_ascii_digit();
+            while i < chars.len() {
+                let part = chars[i].is_alphanumeric()
+                    || matches!(chars[i], '_' | '$')
+                    || number && chars[i] == '.'
+                    || number && matches!(chars[i], '+' | '-') && matches!(chars[i - 1], 'e' | 'E');
+                if !part {
+                    break;
+                }
+                i += 1;
+            }
+            TokenKind::Word
+        } else {
+            i += punct_len(&chars[i..]);
+            TokenKind::Punct
+        };
+        tokens.push(Token {
+            kind,
+            text: chars[start..i].iter().collect(),
+            newline_before,
+        });
+        newline_before = false;
+    }
+    tokens
+}
+
+/// Whether a `/` after `previous` opens a regular expression literal.
+fn regex_allowed(previous: Option<&Token>) -> bool {
+    match previous {
+        None => true,
+        Some(token) => match token.kind {
+            TokenKind::Word => REGEX_KEYWORDS.contains(&token.text.as_str()),
+            TokenKind::Literal => false,
+            TokenKind::Comment => true,
+            TokenKind::Punct => !matches!(token.text.as_str(), ")" | "]" | "}"),
+        },
+    }
+}
+
+/// Index just past the regular expression literal opening at `start`.
+fn regex_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut in_class = false;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => {
+                i += 1;
+                while i < chars.len() && chars[i].is_alphabetic() {
+                    i += 1;
+                }
+                return i;
+            }
+            '\n' => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Length of the operator or punctuator at the start of `chars`.
+fn punct_len(chars: &[char]) -> usize {
+    const OPERATORS: [&str; 28] = [
+        ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>", "==",
+        "!=", "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "**",
+    ];
+    OPERATORS
+        .iter()
+        .find(|op| op.chars().count() <= chars.len() && op.chars().zip(chars).all(|(a, &b)| a == b))
+        .map_or(1, |op| op.chars().count())
+}
+
+/// Whether `op` is written with spaces on both sides.
+fn is_spaced_operator(op: &str) -> bool {
+    matches!(
+        op,
+        "=" | "=="
+            | "==="
+            | "!="
+            | "!=="
+            | "<"
+            | ">"
+            | "<="
+            | ">="
+            | "&&"
+            | "||"
+            | "??"
+            | "=>"
+            | "+="
+            | "-="
+            | "*="
+            | "/="
+            | "%="
+            | "**="
+            | "<<="
+            | ">>="
+            | ">>>="
+            | "&&="
+            | "||="
+            | "??="
+            | "?"
+            | "+"
+            | "-"
+            | "*"
+            | "/"
+            | "%"
+            | "**"
+            | "&"
+            | "|"
+            | "^"
+            | "<<"
+            | ">>"
+            | ">>>"
+    )
+}
+
+/// Whether the `+` or `-` at `index` is a sign rather than an operator.
+fn is_unary(tokens: &[Token], index: usize) -> bool {
+    match index.checked_sub(1).map(|i| &tokens[i]) {
+        None => true,
+        Some(previous) => match previous.kind {
+            TokenKind::Punct => !matches!(previous.text.as_str(), ")" | "]" | "}" | "++" | "--"),
+            TokenKind::Word => REGEX_KEYWORDS.contains(&previous.text.as_str()),
+            TokenKind::Literal | TokenKind::Comment => false,
+        },
+    }
+}
+
+/// Whether a space separates `token` from `previous` on the same line.
+/// Operators that are followed by a space add it themselves.
+fn space_between(previous: &Token, token: &Token, ternary: bool) -> bool {
+    let previous_text = previous.text.as_str();
+    let keyword = previous.kind == TokenKind::Word
+        && (REGEX_KEYWORDS.contains(&previous_text)
+            || CONTROL_KEYWORDS.contains(&previous_text)
+            || previous_text == "function");
+    match token.kind {
+        TokenKind::Comment => true,
+        TokenKind::Word | TokenKind::Literal => match previous.kind {
+            TokenKind::Punct => !matches!(
+                previous_text,
+                "(" | "[" | "." | "?." | "!" | "~" | "..." | "++" | "--" | "+" | "-"
+            ),
+            _ => true,
+        },
+        TokenKind::Punct => match token.text.as_str() {
+            ";" | "," | ")" | "]" | "." | "?." => false,
+            "{" => !matches!(previous_text, "(" | "["),
+            ":" => ternary,
+            "++" | "--" => keyword,
+            op if is_spaced_operator(op) => true,
+            _ => keyword,
+        },
+    }
+}
+
+fn prettify_js(source: &str) -> String {
+    let tokens = tokenize_js(source);
+    let mut out = String::with_capacity(source.len() * 2);
+    let mut line = String::new();
+    let mut depth = 0usize;
+    // Open parentheses, brackets and braces, innermost last. Statements
+    // inside parentheses or brackets stay on one line.
+    let mut groups: Vec<char> = Vec::new();
+    // Group depth of parentheses opened by a `for` header, whose `;` stay
+    // inline.
+    let mut for_headers: Vec<usize> = Vec::new();
+    let mut pending_for = false;
+    // Group depth of each open `switch` body, and whether the statements of
+    // a case are being indented under their label.
+    let mut switches: Vec<(usize, bool)> = Vec::new();
+    let mut pending_switch = false;
+    let mut in_case = false;
+    let mut ternaries = 0usize;
+
+    let flush = |out: &mut String, line: &mut String, depth: usize| {
+        let trimmed = line.trim_end();
+        if !trimmed.is_empty() {
+            push_line(out, depth, trimmed);
+        }
+        line.clear();
+    };
+
+    for (index, token) in tokens.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|i| &tokens[i]);
+        let next = tokens.get(index + 1);
+        let text = token.text.as_str();
+
+        // Keep line breaks of code that was not minified, except inside
+        // parentheses and after operators that continue the expression.
+        if token.newline_before && open_groups(&groups) == 0 && !line.is_empty() {
+            let continued = previous.is_some_and(|previous| {
+                previous.kind == TokenKind::Punct
+                    && !matches!(previous.text.as_str(), ")" | "]" | "}" | "++" | "--")
+            });
+            if !continued || token.kind == TokenKind::Comment {
+                flush(&mut out, &mut line, depth);
+            }
+        }
+        if let Some(previous) = previous {
+            if !line.is_empty()
+                && !line.ends_with(' ')
+                && space_between(previous, token, ternaries > 0 && text == ":")
+            {
+                line.push(' ');
+            }
+        }
+
+        match token.kind {
+            TokenKind::Comment => {
+                line.push_str(text);
+                if text.starts_with("//") {
+                    flush(&mut out, &mut line, depth);
+                }
+            }
+            TokenKind::Word => {
+                match text {
+                    "for" => pending_for = true,
+                    "switch" => pending_switch = true,
+                    "case" | "default" => {
+                        if let Some((body, indented)) = switches.last_mut() {
+                            if *body == groups.len() && line.is_empty() {
+                                in_case = true;
+                                if *indented {
+                                    *indented = false;
+                                    depth = depth.saturating_sub(1);
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                line.push_str(text);
+            }
+            TokenKind::Literal => line.push_str(text),
+            TokenKind::Punct => match text {
+                "{" => {
+                    line.push('{');
+                    groups.push('{');
+                    if pending_switch {
+                        pending_switch = false;
+                        switches.push((groups.len(), false));
+                    }
+                    if next.is_none_or(|next| next.text != "}") {
+                        flush(&mut out, &mut line, depth);
+                        depth += 1;
+                    }
+                }
+                "}" => {
+                    if switches
+                        .last()
+                        .is_some_and(|&(body, _)| body == groups.len())
+                    {
+                        if let Some((_, true)) = switches.pop() {
+                            flush(&mut out, &mut line, depth);
+                            depth = depth.saturating_sub(1);
+                        }
+                    }
+                    if previous.is_none_or(|previous| previous.text != "{") {
+                        flush(&mut out, &mut line, depth);
+                        depth = depth.saturating_sub(1);
+                    }
+                    groups.pop();
+                    line.push('}');
+                    let continued = next.is_some_and(|next| {
+                        matches!(
+                            next.text.as_str(),
+                            "(" | ")" | "]" | "," | ";" | "." | "?." | ":"
+                        ) || next.kind == TokenKind::Word
+                            && CONTINUATION_KEYWORDS.contains(&next.text.as_str())
+                    });
+                    if !continued && open_groups(&groups) == 0 {
+                        flush(&mut out, &mut line, depth);
+                    }
+                }
+                "(" | "[" => {
+                    line.push_str(text);
+                    groups.push(if text == "(" { '(' } else { '[' });
+                    if text == "(" && pending_for {
+                        for_headers.push(groups.len());
+                        pending_for = false;
+                    }
+                }
+                ")" | "]" => {
+                    if for_headers.last() == Some(&groups.len()) {
+                        for_headers.pop();
+                    }
+                    groups.pop();
+                    line.push_str(text);
+                }
+                ";" => {
+                    line.push(';');
+                    if for_headers.last() == Some(&groups.len()) {
+                        line.push(' ');
+                    } else if open_groups(&groups) == 0 {
+                        flush(&mut out, &mut line, depth);
+                    }
+                }
+                "," => {
+                    // Object members and declarators go one per line.
+                    if open_groups(&groups) == 0 && !groups.is_empty() {
+                        line.push(',');
+                        flush(&mut out, &mut line, depth);
+                    } else {
+                        line.push_str(", ");
+                    }
+                }
+                ":" => {
+                    line.push(':');
+                    if ternaries > 0 {
+                        ternaries -= 1;
+                        line.push(' ');
+                    } else if in_case {
+                        in_case = false;
+                        flush(&mut out, &mut line, depth);
+                        if let Some((_, indented)) = switches.last_mut() {
+                            *indented = true;
+                            depth += 1;
+                        }
+                    } else {
+                        line.push(' ');
+                    }
+                }
+                op if is_spaced_operator(op) => {
+                    line.push_str(op);
+                    if op == "?" {
+                        ternaries += 1;
+                    }
+                    if !(matches!(op, "+" | "-") && is_unary(&tokens, index)) {
+                        line.push(' ');
+                    }
+                }
+                other => line.push_str(other),
+            },
+        }
+    }
+    flush(&mut out, &mut line, depth);
+    out
+}
+
+/// Parentheses and brackets open since the innermost brace block.
+fn open_groups(groups: &[char]) -> usize {
+    groups
+        .iter()
+        .rev()
+        .take_while(|&&group| group != '{')
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(address: &str) -> Url {
+        Url::parse(address).unwrap()
+    }
+
+    #[test]
+    fn detects_languages_by_type_then_extension() {
+        let page = url("https://example.com/app.js");
+        for (mime, language) in [
+            (Some("text/css; charset=utf-8"), Some(SourceLanguage::Css)),
+            (
+                Some("application/javascript"),
+                Some(SourceLanguage::JavaScript),
+            ),
+            (Some("application/ld+json"), Some(SourceLanguage::Json)),
+            (Some("text/plain"), Some(SourceLanguage::JavaScript)),
+            (None, Some(SourceLanguage::JavaScript)),
+            (Some("text/html"), None),
+        ] {
+            assert_eq!(SourceLanguage::detect(mime, &page), language, "{mime:?}");
+        }
+        let detect = |address| SourceLanguage::detect(None, &url(address));
+        assert_eq!(
+            detect("https://example.com/a.map"),
+            Some(SourceLanguage::Json)
+        );
+        assert_eq!(
+            detect("https://example.com/STYLE.CSS"),
+            Some(SourceLanguage::Css)
+        );
+        assert_eq!(detect("https://example.com/"), None);
+        assert_eq!(detect("https://example.com/archive.tar"), None);
+    }
+
+    #[test]
+    fn indents_json_by_nesting() {
+        let pretty = prettify(
+            r#"{"a":1,"b":[true,null,{"c":"x,y"}],"d":{}}"#,
+            SourceLanguage::Json,
+        );
+        assert_eq!(
+            pretty,
+            "{\n  \"a\": 1,\n  \"b\": [\n    true,\n    null,\n    {\n      \"c\": \"x,y\"\n    }\n  ],\n  \"d\": {}\n}"
+        );
+        assert_eq!(prettify(&pretty, SourceLanguage::Json), pretty);
+    }
+
+    #[test]
+    fn puts_css_declarations_on_their_own_lines() {
+        let pretty = prettify(
+            "a{color:red;margin:0 auto}@media (max-width:600px){.b>p{display:none}}",
+            SourceLanguage::Css,
+        );
+        assert_eq!(
+            pretty,
+            "a {\n  color: red;\n  margin: 0 auto;\n}\n\n@media (max-width:600px) {\n  .b>p {\n    display: none;\n  }\n}"
+        );
+        assert_eq!(prettify(&pretty, SourceLanguage::Css), pretty);
+    }
+
+    #[test]
+    fn lays_out_javascript_statements() {
+        let pretty = prettify(
+            r"function f(a,b){if(a>b){return a}else{return b/2}}var r=/a\/b/g;x=a?b:c;for(var i=0;i<3;i++)f(i,-1);",
+            SourceLanguage::JavaScript,
+        );
+        assert_eq!(
+            pretty,
+            "function f(a, b) {\n  if (a > b) {\n    return a\n  } else {\n    return b / 2\n  }\n}\n\
+             var r = /a\\/b/g;\nx = a ? b : c;\nfor (var i = 0; i < 3; i++) f(i, -1);"
+        );
+    }
+
+    #[test]
+    fn keeps_strings_and_comments_as_they_are() {
+        let pretty = prettify(
+            r#"var s="a{b;c}",t='/* no */';/* keep {this} */f(s)"#,
+            SourceLanguage::JavaScript,
+        );
+        assert!(pretty.contains(r#""a{b;c}""#));
+        assert!(pretty.contains("'/* no */'"));
+        assert!(pretty.contains("/* keep {this} */"));
+        let css = prettify(r#"a{content:"x;y{z}"}"#, SourceLanguage::Css);
+        assert!(css.contains(r#"content: "x;y{z}";"#));
+    }
+
+    #[test]
+    fn truncated_sources_keep_their_text() {
+        for (source, language, tail) in [
+            (
+                r#"{"a":[1,{"b":"unterminated"#,
+                SourceLanguage::Json,
+                r#""unterminated"#,
+            ),
+            (r#"a{color:"red"#, SourceLanguage::Css, r#""red"#),
+            (
+                "function f(){var s='abc",
+                SourceLanguage::JavaScript,
+                "'abc",
+            ),
+            (
+                "f(/* open comment",
+                SourceLanguage::JavaScript,
+                "/* open comment",
+            ),
+            (
+                "x = /unterminated",
+                SourceLanguage::JavaScript,
+                "/unterminated",
+            ),
+            (
+                "s = 'escape at the end\\",
+                SourceLanguage::JavaScript,
+                "end\\",
+            ),
+        ] {
+            let pretty = prettify(source, language);
+            assert!(pretty.ends_with(tail), "{source}: {pretty}");
+        }
+    }
+
+    #[test]
+    fn unbalanced_brackets_do_not_underflow() {
+        for language in [
+            SourceLanguage::Css,
+            SourceLanguage::JavaScript,
+            SourceLanguage::Json,
+        ] {
+            let pretty = prettify("}}}]])));{", language);
+            let closing: Vec<&str> = pretty
+                .lines()
+                .filter(|line| line.trim_start().starts_with(['}', ']', ')']))
+                .collect();
+            assert!(!closing.is_empty());
+            assert!(
+                closing.iter().all(|line| !line.starts_with(' ')),
+                "{pretty}"
+            );
+        }
+        assert_eq!(prettify("", SourceLanguage::JavaScript), "");
+    }
+}
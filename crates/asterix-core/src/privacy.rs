@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::shield::site_of;
+use crate::{BrowserCore, BrowserState, RequestKind, TabId};
+
+/// Query parameters that only tell a site where a visitor came from.
+const TRACKING_PARAMETERS: &[&str] = &[
+    "fbclid",
+    "gclid",
+    "gclsrc",
+    "dclid",
+    "gbraid",
+    "wbraid",
+    "msclkid",
+    "yclid",
+    "twclid",
+    "ttclid",
+    "li_fat_id",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "_hsenc",
+    "_hsmi",
+    "mkt_tok",
+    "oly_anon_id",
+    "oly_enc_id",
+    "vero_id",
+    "rb_clickid",
+];
+
+/// Prefixes of tracking parameter families, such as `utm_source`.
+const TRACKING_PREFIXES: &[&str] = &["utm_", "pk_", "mtm_"];
+
+/// What tracker protection does while it is on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacySettings {
+    /// Removes click identifiers and campaign tags like `utm_source` from
+    /// addresses before requesting them.
+    pub strip_tracking_parameters: bool,
+    /// Sends `Sec-GPC: 1` and `DNT: 1`, asking sites not to track, sell or
+    /// share what they learn.
+    pub send_opt_out: bool,
+    /// Keeps cookies away from requests a page makes to other sites, and
+    /// ignores the cookies those sites set.
+    pub block_third_party_cookies: bool,
+}
+
+impl Default for PrivacySettings {
+    fn default() -> Self {
+        Self {
+            strip_tracking_parameters: true,
+            send_opt_out: true,
+            block_third_party_cookies: true,
+        }
+    }
+}
+
+/// What tracker protection changed while fetching a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivacyReport {
+    /// Tracking parameters removed from the address asked for, as
+    /// `name=value`.
+    pub stripped_parameters: Vec<String>,
+    /// Whether the request asked not to be tracked.
+    pub opt_out_sent: bool,
+    /// Whether requests the page makes to other sites go without cookies.
+    pub third_party_cookies_blocked: bool,
+}
+
+impl PrivacyReport {
+    /// Whether tracker protection was on for the document.
+    pub fn is_protected(&self) -> bool {
+        self.opt_out_sent
+            || self.third_party_cookies_blocked
+            || !self.stripped_parameters.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Privacy {
+    /// Set while tracker protection is on.
+    settings: Option<PrivacySettings>,
+    /// Third-party requests sent without their cookies, by tab, since it
+    /// last navigated.
+    withheld: HashMap<TabId, usize>,
+}
+
+impl Privacy {
+    fn settings(&self) -> Option<&PrivacySettings> {
+        self.settings.as_ref()
+    }
+
+    /// Adds the opt-out headers to `headers` when they are to be sent.
+    pub(crate) fn apply_opt_out(&self, headers: &mut HeaderMap) {
+        if self
+            .settings()
+            .is_some_and(|settings| settings.send_opt_out)
+        {
+            headers.insert("Sec-GPC", HeaderValue::from_static("1"));
+            headers.insert("DNT", HeaderValue::from_static("1"));
+        }
+    }
+
+    pub(crate) fn count_withheld(&mut self, tab: TabId) {
+        *self.withheld.entry(tab).or_default() += 1;
+    }
+
+    pub(crate) fn reset_withheld(&mut self, tab: TabId) {
+        self.withheld.remove(&tab);
+    }
+}
+
+/// Site whose cookies a request `tab` makes for `kind` may carry, or `None`
+/// when any may: documents are first-party wherever they are.
+pub(crate) fn first_party(
+    state: &BrowserState,
+    kind: RequestKind,
+    tab: Option<TabId>,
+) -> Option<String> {
+    if !state
+        .privacy
+        .settings()
+        .is_some_and(|settings| settings.block_third_party_cookies)
+    {
+        return None;
+    }
+    if matches!(
+        kind,
+        RequestKind::Document | RequestKind::Preload | RequestKind::Prefetch
+    ) {
+        return None;
+    }
+    let tab = tab?;
+    let page = state
+        .tabs
+        .iter()
+        .find(|snapshot| snapshot.id == tab)?
+        .url
+        .as_ref()?;
+    Some(site_of(page.host_str()?).to_owned())
+}
+
+/// Whether `url` belongs to a site other than `first_party`.
+pub(crate) fn is_third_party(first_party: &str, url: &Url) -> bool {
+    url.host_str()
+        .is_some_and(|host| site_of(host) != first_party)
+}
+
+/// Removes tracking parameters from the query of `url`, returning them as
+/// `name=value`.
+fn strip_tracking_parameters(url: &mut Url) -> Vec<String> {
+    let is_tracking = |name: &str| {
+        let name = name.to_ascii_lowercase();
+        TRACKING_PARAMETERS.contains(&name.as_str())
+            || TRACKING_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+    };
+    let Some(query) = url.query() else {
+        return Vec::new();
+    };
+    let (stripped, kept): (Vec<&str>, Vec<&str>) = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .partition(|pair| is_tracking(pair.split_once('=').map_or(*pair, |(name, _)| name)));
+    if stripped.is_empty() {
+        return Vec::new();
+    }
+    let stripped = stripped.into_iter().map(ToOwned::to_owned).collect();
+    let kept = kept.join("&");
+    url.set_query((!kept.is_empty()).then_some(kept.as_str()));
+    stripped
+}
+
+impl BrowserCore {
+    /// Turns tracker protection on with `settings`, or off with `None`.
+    pub fn set_tracker_protection(&self, settings: Option<PrivacySettings>) {
+        self.state.write().privacy.settings = settings;
+    }
+
+    pub fn tracker_protection(&self) -> Option<PrivacySettings> {
+        self.state.read().privacy.settings.clone()
+    }
+
+    /// Requests the page in `tab` made to other sites that went without
+    /// their cookies.
+    pub fn withheld_cookies(&self, tab: TabId) -> usize {
+        self.state
+            .read()
+            .privacy
+            .withheld
+            .get(&tab)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Strips tracking parameters from `url` when tracker protection is on,
+    /// returning what protection applies to the request.
+    pub(crate) fn sanitize_request(&self, url: &mut Url) -> PrivacyReport {
+        let Some(settings) = self.tracker_protection() else {
+            return PrivacyReport::default();
+        };
+        PrivacyReport {
+            stripped_parameters: if settings.strip_tracking_parameters {
+                strip_tracking_parameters(url)
+            } else {
+                Vec::new()
+            },
+            opt_out_sent: settings.send_opt_out,
+            third_party_cookies_blocked: settings.block_third_party_cookies,
+        }
+    }
+
+    /// Whether a streaming request `tab` makes to `url` must leave its
+    /// cookies behind, counting it if so. Streams bypass the logged
+    /// exchange, so they are sent through the cookieless client instead.
+    pub(crate) fn withholds_cookies(&self, tab: TabId, kind: RequestKind, url: &Url) -> bool {
+        let withheld = first_party(&self.state.read(), kind, Some(tab))
+            .is_some_and(|site| is_third_party(&site, url));
+        if withheld && !self.cookie_jar.cookies_for(url).is_empty() {
+            self.state.write().privacy.count_withheld(tab);
+        }
+        withheld
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+use tokio::sync::Notify;
+
+/// Marks a response that did not announce its length.
+const UNKNOWN_LENGTH: u64 = u64::MAX;
+
+/// Live progress of one document fetch, shared between the task reading the
+/// body and whoever shows it. Also carries the request to stop early.
+#[derive(Debug)]
+pub struct FetchProgress {
+    received: AtomicU64,
+    /// `Content-Length` of the response, or [`UNKNOWN_LENGTH`].
+    total: AtomicU64,
+    started: Instant,
+    cancelled: AtomicBool,
+    cancel: Notify,
+}
+
+impl Default for FetchProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FetchProgress {
+    pub fn new() -> Self {
+        Self {
+            received: AtomicU64::new(0),
+            total: AtomicU64::new(UNKNOWN_LENGTH),
+            started: Instant::now(),
+            cancelled: AtomicBool::new(false),
+            cancel: Notify::new(),
+        }
+    }
+
+    /// Body bytes read so far.
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Body length the server announced, if it did.
+    pub fn total(&self) -> Option<u64> {
+        Some(self.total.load(Ordering::Relaxed)).filter(|total| *total != UNKNOWN_LENGTH)
+    }
+
+    /// Share of the body received, between 0 and 1, when the length is known.
+    pub fn fraction(&self) -> Option<f32> {
+        let total = self.total()?;
+        if total == 0 {
+            return Some(1.0);
+        }
+        Some((self.received() as f64 / total as f64).min(1.0) as f32)
+    }
+
+    /// Average transfer rate since the request started, in bytes per second.
+    pub fn bytes_per_second(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        self.received() as f64 / elapsed
+    }
+
+    /// Asks the fetch to stop; it then fails with
+    /// [`BrowserError::Cancelled`](crate::BrowserError::Cancelled).
+    pub fn cancel(&self) {
+        if !self.cancelled.swap(true, Ordering::Relaxed) {
+            self.cancel.notify_one();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn start_body(&self, total: Option<u64>) {
+        self.received.store(0, Ordering::Relaxed);
+        self.total
+            .store(total.unwrap_or(UNKNOWN_LENGTH), Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_received(&self, bytes: usize) {
+        self.received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records a body that arrived in one piece, e.g. from the preload cache.
+    pub(crate) fn complete(&self, bytes: usize) {
+        self.start_body(Some(bytes as u64));
+        self.add_received(bytes);
+    }
+
+    /// Resolves once [`cancel`](Self::cancel) has been called.
+    pub(crate) async fn cancelled(&self) {
+        // `notify_one` stores a permit, so a cancel before this point counts.
+        self.cancel.notified().await;
+    }
+}
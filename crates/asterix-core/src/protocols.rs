@@ -0,0 +1,226 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, BrowserError, PageResponse};
+
+/// Gateway a local IPFS node (Kubo, IPFS Desktop) serves on by default.
+pub const LOCAL_IPFS_GATEWAY: &str = "http://127.0.0.1:8080/";
+
+/// Public gateway used until the user picks another.
+const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io/";
+
+/// Identity CID of the empty file, which a running node serves without
+/// touching the network.
+const LOCAL_NODE_PROBE: &str = "ipfs/bafkqaaa";
+
+/// How long a local node probe may take before the node counts as absent.
+const LOCAL_NODE_TIMEOUT: StdDuration = StdDuration::from_millis(500);
+
+/// How long the outcome of a local node probe is trusted.
+const LOCAL_NODE_RECHECK_SECS: i64 = 60;
+
+/// Schemes the network stack serves, which handlers cannot take over.
+const NETWORK_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// Page being loaded by a [`SchemeHandler`].
+pub type SchemeFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<PageResponse, BrowserError>> + Send + 'a>>;
+
+/// Loads URLs of a scheme the browser does not speak itself, such as
+/// `gemini:` or an application's internal scheme. Register one with
+/// [`BrowserCore::register_scheme_handler`].
+pub trait SchemeHandler: Send + Sync {
+    /// Fetches `url`, building the page with [`PageResponse::from_bytes`].
+    fn load<'a>(&'a self, url: &'a Url) -> SchemeFuture<'a>;
+}
+
+/// How `ipfs://` and `ipns://` URLs are fetched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpfsSettings {
+    /// HTTP gateway serving `/ipfs/<cid>` and `/ipns/<name>` paths.
+    pub gateway: Url,
+    /// Use the gateway of a node running on this machine when one answers.
+    pub prefer_local_node: bool,
+}
+
+impl Default for IpfsSettings {
+    fn default() -> Self {
+        Self {
+            gateway: Url::parse(DEFAULT_IPFS_GATEWAY).expect("default gateway is a valid URL"),
+            prefer_local_node: true,
+        }
+    }
+}
+
+/// Whether navigating to `url` needs its scheme translated before it can
+/// be fetched over HTTP.
+pub(crate) fn is_gateway_scheme(url: &Url) -> bool {
+    matches!(url.scheme(), "ipfs" | "ipns")
+}
+
+/// `url` rewritten to the path form `gateway` serves, keeping any path,
+/// query and fragment after the content identifier.
+fn gateway_url(gateway: &Url, url: &Url) -> Result<Url, BrowserError> {
+    // `ipfs://<cid>/path` puts the CID in the host; `ipfs:<cid>/path` in
+    // the path.
+    let (root, rest) = match url.host_str().filter(|host| !host.is_empty()) {
+        Some(host) => (host, url.path()),
+        None => {
+            let path = url.path().trim_start_matches('/');
+            path.find('/')
+                .map_or((path, ""), |slash| path.split_at(slash))
+        }
+    };
+    if root.is_empty() {
+        return Err(BrowserError::InvalidRequest(format!(
+            "{url} names no content to load"
+        )));
+    }
+    let mut translated = gateway.clone();
+    let base = gateway.path().trim_end_matches('/');
+    translated.set_path(&format!("{base}/{}/{root}{rest}", url.scheme()));
+    translated.set_query(url.query());
+    translated.set_fragment(url.fragment());
+    Ok(translated)
+}
+
+impl BrowserCore {
+    /// Replaces how IPFS URLs are resolved and forgets whether a local node
+    /// was found.
+    pub fn set_ipfs_settings(&self, settings: IpfsSettings) {
+        let mut guard = self.state.write();
+        guard.ipfs = settings;
+        guard.local_ipfs_node = None;
+    }
+
+    pub fn ipfs_settings(&self) -> IpfsSettings {
+        self.state.read().ipfs.clone()
+    }
+
+    /// Whether the last probe found a local IPFS node, `None` before one
+    /// has run.
+    pub fn local_ipfs_node(&self) -> Option<bool> {
+        self.state
+            .read()
+            .local_ipfs_node
+            .map(|(running, _)| running)
+    }
+
+    /// Has `handler` load every URL with `scheme`, replacing the handler
+    /// registered before and the browser's own handling of `file:`, `ipfs:`,
+    /// `ipns:` and `magnet:`. HTTP and HTTPS cannot be taken over.
+    pub fn register_scheme_handler(
+        &self,
+        scheme: &str,
+        handler: impl SchemeHandler + 'static,
+    ) -> Result<(), BrowserError> {
+        let scheme = scheme.to_ascii_lowercase();
+        if NETWORK_SCHEMES.contains(&scheme.as_str()) {
+            return Err(BrowserError::InvalidRequest(format!(
+                "{scheme} is handled by the network stack"
+            )));
+        }
+        self.state
+            .write()
+            .scheme_handlers
+            .insert(scheme, Arc::new(handler));
+        Ok(())
+    }
+
+    /// Drops the handler of `scheme`, returning whether there was one.
+    pub fn unregister_scheme_handler(&self, scheme: &str) -> bool {
+        self.state
+            .write()
+            .scheme_handlers
+            .remove(&scheme.to_ascii_lowercase())
+            .is_some()
+    }
+
+    /// Schemes with a registered handler, sorted.
+    pub fn scheme_handlers(&self) -> Vec<String> {
+        let mut schemes: Vec<String> = self.state.read().scheme_handlers.keys().cloned().collect();
+        schemes.sort();
+        schemes
+    }
+
+    /// Loads `url` with the handler registered for its scheme, if any.
+    /// Restricted mode applies as it does to websites.
+    pub(crate) async fn load_with_handler(
+        &self,
+        url: &Url,
+    ) -> Option<Result<PageResponse, BrowserError>> {
+        let (handler, restricted) = {
+            let guard = self.state.read();
+            let handler = Arc::clone(guard.scheme_handlers.get(url.scheme())?);
+            let restricted = guard
+                .allowlist
+                .as_ref()
+                .is_some_and(|allowlist| !allowlist.allows(url));
+            (handler, restricted)
+        };
+        if restricted {
+            return Some(Err(BrowserError::Restricted(url.clone())));
+        }
+        Some(handler.load(url).await)
+    }
+
+    /// URL to fetch for `url`: IPFS and IPNS addresses go through the local
+    /// node or the configured gateway, magnet links are refused and
+    /// everything else is left as it is.
+    pub async fn resolve_protocol(&self, url: &Url) -> Result<Url, BrowserError> {
+        if url.scheme() == "magnet" {
+            return Err(BrowserError::InvalidRequest(
+                "magnet links are opened in a torrent client".to_owned(),
+            ));
+        }
+        if !is_gateway_scheme(url) {
+            return Ok(url.clone());
+        }
+        let settings = self.ipfs_settings();
+        let gateway = if settings.prefer_local_node && self.local_node_running().await {
+            Url::parse(LOCAL_IPFS_GATEWAY).expect("local gateway is a valid URL")
+        } else {
+            settings.gateway
+        };
+        gateway_url(&gateway, url)
+    }
+
+    /// Probes the local gateway, reusing a recent answer.
+    async fn local_node_running(&self) -> bool {
+        let now = Utc::now();
+        let cached = self.state.read().local_ipfs_node;
+        if let Some((running, checked_at)) = cached {
+            if now - checked_at < Duration::seconds(LOCAL_NODE_RECHECK_SECS) {
+                return running;
+            }
+        }
+        let running = probe_local_node().await;
+        self.state.write().local_ipfs_node = Some((running, now));
+        running
+    }
+}
+
+/// Asks the default local gateway for the empty file. The request skips
+/// any proxy, which could not reach this machine's loopback.
+async fn probe_local_node() -> bool {
+    let Ok(client) = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(LOCAL_NODE_TIMEOUT)
+        .build()
+    else {
+        return false;
+    };
+    let probe = format!("{LOCAL_IPFS_GATEWAY}{LOCAL_NODE_PROBE}");
+    client
+        .get(probe)
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success())
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+
+use crate::{build_client, BrowserCore, Clients, HttpVersionPolicy};
+
+/// Where requests go to reach the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProxyMode {
+    /// Connect to every site directly.
+    Direct,
+    /// Follow the system configuration: `HTTP_PROXY`, `HTTPS_PROXY`,
+    /// `ALL_PROXY` and `NO_PROXY`, or the Windows Internet settings.
+    #[default]
+    System,
+    /// Use the proxies in [`ProxySettings`].
+    Manual,
+}
+
+/// Proxy configuration shared by regular and private tabs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    /// Proxy for `http://` URLs, such as `http://proxy.corp:3128` or
+    /// `socks5h://127.0.0.1:9050` for SOCKS5 with remote name resolution.
+    pub http: Option<String>,
+    /// Proxy for `https://` URLs, in the same forms.
+    pub https: Option<String>,
+    /// Hosts reached directly in manual mode: domains with their
+    /// subdomains, IP addresses and CIDR ranges.
+    pub bypass: Vec<String>,
+}
+
+impl ProxySettings {
+    /// Configures `builder` to send its requests as these settings say.
+    pub(crate) fn apply(&self, builder: ClientBuilder) -> reqwest::Result<ClientBuilder> {
+        match self.mode {
+            ProxyMode::Direct => Ok(builder.no_proxy()),
+            // The client reads the system settings unless told otherwise.
+            ProxyMode::System => Ok(builder),
+            ProxyMode::Manual => {
+                let mut builder = builder.no_proxy();
+                let bypass = self.bypass.join(",");
+                if let Some(url) = non_empty(&self.http) {
+                    builder =
+                        builder.proxy(Proxy::http(url)?.no_proxy(NoProxy::from_string(&bypass)));
+                }
+                if let Some(url) = non_empty(&self.https) {
+                    builder =
+                        builder.proxy(Proxy::https(url)?.no_proxy(NoProxy::from_string(&bypass)));
+                }
+                Ok(builder)
+            }
+        }
+    }
+}
+
+fn non_empty(url: &Option<String>) -> Option<&str> {
+    url.as_deref().map(str::trim).filter(|url| !url.is_empty())
+}
+
+impl BrowserCore {
+    /// Routes every later request through `settings`. Requests already on
+    /// their way keep the previous route.
+    pub fn set_proxy(&self, settings: ProxySettings) -> anyhow::Result<()> {
+        self.rebuild_clients(settings, self.http_version())
+            .context("invalid proxy settings")
+    }
+
+    /// Replaces the clients with ones built for `proxy` and `http_version`.
+    pub(crate) fn rebuild_clients(
+        &self,
+        proxy: ProxySettings,
+        http_version: HttpVersionPolicy,
+    ) -> anyhow::Result<()> {
+        let user_agent = self.user_agent.as_deref();
+        let regular = build_client(
+            user_agent,
+            Some(Arc::clone(&self.cookie_jar)),
+            &proxy,
+            http_version,
+            &self.tls,
+            &self.resolver,
+            None,
+        )?;
+        let private = build_client(
+            user_agent,
+            None,
+            &proxy,
+            http_version,
+            &self.tls,
+            &self.resolver,
+            None,
+        )?;
+        let identities = self.identity_clients(self.client_identities(), &proxy, http_version)?;
+        *self.clients.write() = Clients {
+            regular,
+            private,
+            proxy,
+            http_version,
+            identities,
+        };
+        Ok(())
+    }
+
+    pub fn proxy_settings(&self) -> ProxySettings {
+        self.clients.read().proxy.clone()
+    }
+}
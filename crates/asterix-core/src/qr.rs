@@ -0,0 +1,882 @@
+use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+use thiserror::Error;
+
+/// Error correction codewords per block, by level (L, M, Q, H) and version.
+const ECC_CODEWORDS_PER_BLOCK: [[u8; 41]; 4] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28,
+        30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30,
+        30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+/// Error correction blocks, by level (L, M, Q, H) and version.
+const ERROR_CORRECTION_BLOCKS: [[u8; 41]; 4] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32,
+        35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+const ALPHANUMERIC_CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// How many finder pattern candidates are tried in combination.
+const MAX_CANDIDATES: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum QrError {
+    #[error("no QR code found in the image")]
+    NotFound,
+    #[error("the QR code could not be read")]
+    Unreadable,
+}
+
+/// Reads the QR code in an image, such as a screenshot, returning the text
+/// it holds. `rgba` has `width` × `height` pixels row by row, four bytes
+/// each, as clipboards hand images over.
+pub fn decode_qr(rgba: &[u8], width: usize, height: usize) -> Result<String, QrError> {
+    let bitmap = Bitmap::from_rgba(rgba, width, height).ok_or(QrError::NotFound)?;
+    let mut candidates = find_finder_patterns(&bitmap);
+    if candidates.len() < 3 {
+        return Err(QrError::NotFound);
+    }
+    candidates.sort_by_key(|pattern| std::cmp::Reverse(pattern.hits));
+    candidates.truncate(MAX_CANDIDATES);
+
+    for (i, a) in candidates.iter().enumerate() {
+        for (j, b) in candidates.iter().enumerate().skip(i + 1) {
+            for c in candidates.iter().skip(j + 1) {
+                let Some(corners) = Corners::arrange(*a, *b, *c) else {
+                    continue;
+                };
+                for version in corners.versions() {
+                    let grid = corners.sample(&bitmap, version);
+                    if let Ok(text) = grid.decode() {
+                        return Ok(text);
+                    }
+                }
+            }
+        }
+    }
+    Err(QrError::Unreadable)
+}
+
+/// Image reduced to dark and light pixels.
+struct Bitmap {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Bitmap {
+    fn from_rgba(rgba: &[u8], width: usize, height: usize) -> Option<Self> {
+        let pixels = width.checked_mul(height)?;
+        if pixels == 0 || rgba.len() < pixels.checked_mul(4)? {
+            return None;
+        }
+        let luma: Vec<u8> = rgba
+            .chunks_exact(4)
+            .take(pixels)
+            .map(|pixel| {
+                let [r, g, b, alpha] = [pixel[0], pixel[1], pixel[2], pixel[3]].map(u32::from);
+                let gray = (299 * r + 587 * g + 114 * b) / 1000;
+                // Transparent pixels show the light page behind them.
+                ((gray * alpha + 255 * (255 - alpha)) / 255) as u8
+            })
+            .collect();
+        let min = *luma.iter().min()?;
+        let max = *luma.iter().max()?;
+        let threshold = (u16::from(min) + u16::from(max)) / 2;
+        Some(Self {
+            width,
+            height,
+            dark: luma
+                .iter()
+                .map(|&value| u16::from(value) < threshold)
+                .collect(),
+        })
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.dark[y * self.width + x]
+    }
+
+    fn is_dark_at(&self, x: f32, y: f32) -> bool {
+        x >= 0.0 && y >= 0.0 && self.is_dark(x as usize, y as usize)
+    }
+}
+
+/// Centre of one of the three square markers in the corners of a code.
+#[derive(Debug, Clone, Copy)]
+struct FinderPattern {
+    x: f32,
+    y: f32,
+    /// Estimated module size in pixels.
+    module: f32,
+    /// Scan lines the pattern was seen on.
+    hits: u32,
+}
+
+/// Whether runs of dark, light, dark, light and dark pixels have the
+/// 1:1:3:1:1 proportions of a finder pattern.
+fn is_finder_ratio(counts: &[usize; 5]) -> bool {
+    let total: usize = counts.iter().sum();
+    if total < 7 {
+        return false;
+    }
+    let module = total as f32 / 7.0;
+    let variance = module / 2.0;
+    counts
+        .iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&count, modules)| (count as f32 - modules * module).abs() < modules * variance)
+}
+
+/// Runs of a finder pattern crossing `(x, y)` in a row or column, and the
+/// position of its centre along that line.
+fn cross_runs(bitmap: &Bitmap, x: usize, y: usize, horizontal: bool) -> Option<([usize; 5], f32)> {
+    let (start, len) = if horizontal {
+        (x, bitmap.width)
+    } else {
+        (y, bitmap.height)
+    };
+    let dark = |i: usize| {
+        if horizontal {
+            bitmap.is_dark(i, y)
+        } else {
+            bitmap.is_dark(x, i)
+        }
+    };
+    if !dark(start) {
+        return None;
+    }
+    let mut counts = [0; 5];
+    let mut i = start;
+    for (slot, want) in [(2, true), (1, false), (0, true)] {
+        while dark(i) == want {
+            counts[slot] += 1;
+            if i == 0 {
+                break;
+            }
+            i -= 1;
+        }
+    }
+    let mut i = start + 1;
+    for (slot, want) in [(2, true), (3, false), (4, true)] {
+        while i < len && dark(i) == want {
+            counts[slot] += 1;
+            i += 1;
+        }
+    }
+    if !is_finder_ratio(&counts) {
+        return None;
+    }
+    let centre = i as f32 - counts[4] as f32 - counts[3] as f32 - counts[2] as f32 / 2.0;
+    Some((counts, centre))
+}
+
+fn find_finder_patterns(bitmap: &Bitmap) -> Vec<FinderPattern> {
+    let mut found: Vec<FinderPattern> = Vec::new();
+    for y in 0..bitmap.height {
+        // (start, length, dark) of each run of pixels in the row.
+        let mut runs: Vec<(usize, usize, bool)> = Vec::new();
+        for x in 0..bitmap.width {
+            let dark = bitmap.is_dark(x, y);
+            match runs.last_mut() {
+                Some((_, len, run_dark)) if *run_dark == dark => *len += 1,
+                _ => runs.push((x, 1, dark)),
+            }
+        }
+        for window in runs.windows(5).filter(|window| window[0].2) {
+            let counts = [0, 1, 2, 3, 4].map(|i| window[i].1);
+            if !is_finder_ratio(&counts) {
+                continue;
+            }
+            let centre_x = window[2].0 + window[2].1 / 2;
+            let Some((vertical, centre_y)) = cross_runs(bitmap, centre_x, y, false) else {
+                continue;
+            };
+            let Some((horizontal, centre_x)) =
+                cross_runs(bitmap, centre_x, centre_y as usize, true)
+            else {
+                continue;
+            };
+            let vertical: usize = vertical.iter().sum();
+            let horizontal: usize = horizontal.iter().sum();
+            if vertical.abs_diff(horizontal) * 2 > horizontal {
+                continue;
+            }
+            let module = (vertical + horizontal) as f32 / 14.0;
+            let same = found.iter_mut().find(|pattern| {
+                (pattern.x - centre_x).abs() <= pattern.module * 2.0
+                    && (pattern.y - centre_y).abs() <= pattern.module * 2.0
+                    && (pattern.module - module).abs() <= pattern.module / 2.0
+            });
+            match same {
+                Some(pattern) => {
+                    let hits = pattern.hits as f32;
+                    pattern.x = (pattern.x * hits + centre_x) / (hits + 1.0);
+                    pattern.y = (pattern.y * hits + centre_y) / (hits + 1.0);
+                    pattern.module = (pattern.module * hits + module) / (hits + 1.0);
+                    pattern.hits += 1;
+                }
+                None => found.push(FinderPattern {
+                    x: centre_x,
+                    y: centre_y,
+                    module,
+                    hits: 1,
+                }),
+            }
+        }
+    }
+    found.retain(|pattern| pattern.hits >= 2);
+    found
+}
+
+/// Three finder patterns that could be the corners of one code.
+struct Corners {
+    top_left: FinderPattern,
+    top_right: FinderPattern,
+    bottom_left: FinderPattern,
+}
+
+impl Corners {
+    fn arrange(a: FinderPattern, b: FinderPattern, c: FinderPattern) -> Option<Self> {
+        let distance = |p: FinderPattern, q: FinderPattern| (p.x - q.x).hypot(p.y - q.y);
+        let (ab, bc, ac) = (distance(a, b), distance(b, c), distance(a, c));
+        // The top-left pattern is opposite the diagonal.
+        let (top_left, p, q) = if bc >= ab && bc >= ac {
+            (a, b, c)
+        } else if ac >= ab {
+            (b, a, c)
+        } else {
+            (c, a, b)
+        };
+        let (px, py) = (p.x - top_left.x, p.y - top_left.y);
+        let (qx, qy) = (q.x - top_left.x, q.y - top_left.y);
+        let (p_len, q_len) = (px.hypot(py), qx.hypot(qy));
+        let modules = [a.module, b.module, c.module];
+        let smallest = modules.iter().copied().fold(f32::MAX, f32::min);
+        let largest = modules.iter().copied().fold(0.0, f32::max);
+        let square =
+            (px * qx + py * qy).abs() / (p_len * q_len) < 0.2 && (p_len / q_len - 1.0).abs() < 0.25;
+        if !square || largest > smallest * 1.5 {
+            return None;
+        }
+        // With y pointing down, the top-right pattern is clockwise from the
+        // bottom-left one.
+        let (top_right, bottom_left) = if px * qy - py * qx > 0.0 {
+            (p, q)
+        } else {
+            (q, p)
+        };
+        Some(Self {
+            top_left,
+            top_right,
+            bottom_left,
+        })
+    }
+
+    /// Versions whose size fits the distance between the patterns, most
+    /// likely first.
+    fn versions(&self) -> Vec<usize> {
+        let module = (self.top_left.module + self.top_right.module + self.bottom_left.module) / 3.0;
+        let across = (self.top_right.x - self.top_left.x).hypot(self.top_right.y - self.top_left.y);
+        let down =
+            (self.bottom_left.x - self.top_left.x).hypot(self.bottom_left.y - self.top_left.y);
+        let size = (across + down) / 2.0 / module + 7.0;
+        let estimate = ((size - 17.0) / 4.0).round() as i64;
+        [estimate, estimate - 1, estimate + 1]
+            .into_iter()
+            .filter(|version| (1..=40).contains(version))
+            .map(|version| version as usize)
+            .collect()
+    }
+
+    /// Reads the modules of a code of `version`, mapping the centres of the
+    /// finder patterns onto the image.
+    fn sample(&self, bitmap: &Bitmap, version: usize) -> Grid {
+        let size = 17 + 4 * version;
+        let span = (size - 7) as f32;
+        let (origin, right, down) = (self.top_left, self.top_right, self.bottom_left);
+        let mut modules = Vec::with_capacity(size * size);
+        for row in 0..size {
+            for col in 0..size {
+                let u = (col as f32 - 3.0) / span;
+                let v = (row as f32 - 3.0) / span;
+                let x = origin.x + u * (right.x - origin.x) + v * (down.x - origin.x);
+                let y = origin.y + u * (right.y - origin.y) + v * (down.y - origin.y);
+                modules.push(bitmap.is_dark_at(x, y));
+            }
+        }
+        Grid {
+            version,
+            size,
+            modules,
+        }
+    }
+}
+
+/// Modules of a code, dark ones `true`.
+struct Grid {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl Grid {
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn decode(&self) -> Result<String, QrError> {
+        let (level, mask) = self.format()?;
+        let codewords = self.codewords(mask);
+        let data = correct_blocks(&codewords, self.version, level)?;
+        parse_segments(&data, self.version)
+    }
+
+    /// Error correction level, as an index into the block tables, and mask
+    /// from the format information closest to either of its two copies.
+    fn format(&self) -> Result<(usize, u8), QrError> {
+        let size = self.size;
+        let mut first = 0;
+        let mut second = 0;
+        for i in 0..15 {
+            let (x, y) = match i {
+                0..=5 => (8, i),
+                6 => (8, 7),
+                7 => (8, 8),
+                8 => (7, 8),
+                _ => (14 - i, 8),
+            };
+            first |= u32::from(self.get(x, y)) << i;
+            let (x, y) = if i < 8 {
+                (size - 1 - i, 8)
+            } else {
+                (8, size - 15 + i)
+            };
+            second |= u32::from(self.get(x, y)) << i;
+        }
+        let (data, distance) = (0..32)
+            .map(|data| {
+                let bits = format_bits(data);
+                let distance = (bits ^ first)
+                    .count_ones()
+                    .min((bits ^ second).count_ones());
+                (data, distance)
+            })
+            .min_by_key(|&(_, distance)| distance)
+            .ok_or(QrError::Unreadable)?;
+        if distance > 3 {
+            return Err(QrError::Unreadable);
+        }
+        let level = match data >> 3 {
+            1 => 0,
+            0 => 1,
+            3 => 2,
+            _ => 3,
+        };
+        Ok((level, (data & 7) as u8))
+    }
+
+    /// The codewords in the zigzag order they were placed in, unmasked.
+    fn codewords(&self, mask: u8) -> Vec<u8> {
+        let size = self.size;
+        let function = function_modules(self.version);
+        let mut codewords = vec![0u8; raw_data_modules(self.version) / 8];
+        let bits = codewords.len() * 8;
+        let mut i = 0;
+        let mut right = size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vertical in 0..size {
+                let y = if upward {
+                    size - 1 - vertical
+                } else {
+                    vertical
+                };
+                for x in [right, right - 1] {
+                    if function[y * size + x] || i >= bits {
+                        continue;
+                    }
+                    if self.get(x, y) != is_masked(mask, x, y) {
+                        codewords[i / 8] |= 0x80 >> (i % 8);
+                    }
+                    i += 1;
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+        codewords
+    }
+}
+
+fn format_bits(data: u32) -> u32 {
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    ((data << 10) | remainder) ^ 0x5412
+}
+
+fn is_masked(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+    }
+}
+
+/// Centres of the alignment patterns along either axis.
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let size = 17 + 4 * version;
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// Which modules are finder, timing, alignment, format and version
+/// patterns rather than data.
+fn function_modules(version: usize) -> Vec<bool> {
+    let size = 17 + 4 * version;
+    let mut function = vec![false; size * size];
+    let mut mark = |left: usize, top: usize, width: usize, height: usize| {
+        for y in top..top + height {
+            for x in left..left + width {
+                function[y * size + x] = true;
+            }
+        }
+    };
+    mark(6, 0, 1, size);
+    mark(0, 6, size, 1);
+    mark(0, 0, 9, 9);
+    mark(size - 8, 0, 8, 9);
+    mark(0, size - 8, 9, 8);
+    let positions = alignment_positions(version);
+    let last = positions.len().saturating_sub(1);
+    for (i, &x) in positions.iter().enumerate() {
+        for (j, &y) in positions.iter().enumerate() {
+            let corner = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+            if !corner {
+                mark(x - 2, y - 2, 5, 5);
+            }
+        }
+    }
+    if version >= 7 {
+        mark(size - 11, 0, 3, 6);
+        mark(0, size - 11, 6, 3);
+    }
+    function
+}
+
+/// Modules available for codewords, including leftover remainder bits.
+fn raw_data_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignment = version / 7 + 2;
+        modules -= (25 * alignment - 10) * alignment - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+/// Splits the interleaved codewords into their blocks, fixes errors in
+/// each and returns the data codewords in order.
+fn correct_blocks(codewords: &[u8], version: usize, level: usize) -> Result<Vec<u8>, QrError> {
+    let blocks_count = usize::from(ERROR_CORRECTION_BLOCKS[level][version]);
+    let ecc_len = usize::from(ECC_CODEWORDS_PER_BLOCK[level][version]);
+    let short_blocks = blocks_count - codewords.len() % blocks_count;
+    let short_len = codewords.len() / blocks_count;
+    let mut blocks = vec![Vec::with_capacity(short_len + 1); blocks_count];
+    let mut codewords = codewords.iter();
+    for i in 0..=short_len {
+        for (j, block) in blocks.iter_mut().enumerate() {
+            // Short blocks have one data codeword fewer.
+            if i != short_len - ecc_len || j >= short_blocks {
+                block.push(*codewords.next().ok_or(QrError::Unreadable)?);
+            }
+        }
+    }
+    let mut data = Vec::new();
+    for mut block in blocks {
+        correct_errors(&mut block, ecc_len)?;
+        data.extend_from_slice(&block[..block.len() - ecc_len]);
+    }
+    Ok(data)
+}
+
+/// Product in GF(2^8) with the QR code polynomial x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let mut product: u8 = 0;
+    for i in (0..8).rev() {
+        product = (product << 1) ^ ((product >> 7) * 0x1d);
+        product ^= ((y >> i) & 1) * x;
+    }
+    product
+}
+
+fn gf_pow(x: u8, exponent: usize) -> u8 {
+    (0..exponent).fold(1, |power, _| gf_mul(power, x))
+}
+
+fn gf_inverse(x: u8) -> u8 {
+    gf_pow(x, 254)
+}
+
+/// Value of `poly`, lowest degree first, at `x`.
+fn evaluate(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |sum, &c| gf_mul(sum, x) ^ c)
+}
+
+/// Fixes up to half as many wrong codewords in `block` as it has error
+/// correction codewords (Berlekamp-Massey, Chien search and Forney).
+fn correct_errors(block: &mut [u8], ecc_len: usize) -> Result<(), QrError> {
+    let syndromes = |block: &[u8]| -> Vec<u8> {
+        (0..ecc_len)
+            .map(|j| {
+                let x = gf_pow(2, j);
+                block.iter().fold(0, |sum, &c| gf_mul(sum, x) ^ c)
+            })
+            .collect()
+    };
+    let syndrome = syndromes(block);
+    if syndrome.iter().all(|&s| s == 0) {
+        return Ok(());
+    }
+
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut errors = 0;
+    let mut shift = 1;
+    let mut last_discrepancy = 1u8;
+    for n in 0..ecc_len {
+        let discrepancy = (1..=errors.min(locator.len() - 1))
+            .fold(syndrome[n], |d, i| d ^ gf_mul(locator[i], syndrome[n - i]));
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let scale = gf_mul(discrepancy, gf_inverse(last_discrepancy));
+        let mut next = locator.clone();
+        next.resize(next.len().max(previous.len() + shift), 0);
+        for (i, &c) in previous.iter().enumerate() {
+            next[i + shift] ^= gf_mul(scale, c);
+        }
+        if 2 * errors <= n {
+            previous = std::mem::replace(&mut locator, next);
+            errors = n + 1 - errors;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = next;
+            shift += 1;
+        }
+    }
+    if 2 * errors > ecc_len {
+        return Err(QrError::Unreadable);
+    }
+
+    // Codeword i is the coefficient of x^(len - 1 - i).
+    let len = block.len();
+    let positions: Vec<usize> = (0..len)
+        .filter(|&i| evaluate(&locator, gf_inverse(gf_pow(2, len - 1 - i))) == 0)
+        .collect();
+    if positions.len() != errors {
+        return Err(QrError::Unreadable);
+    }
+    let mut evaluator = vec![0u8; ecc_len];
+    for (i, &s) in syndrome.iter().enumerate() {
+        for (j, &l) in locator.iter().enumerate().take(ecc_len - i) {
+            evaluator[i + j] ^= gf_mul(s, l);
+        }
+    }
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+        .collect();
+    for i in positions {
+        let x = gf_pow(2, len - 1 - i);
+        let x_inverse = gf_inverse(x);
+        let denominator = evaluate(&derivative, x_inverse);
+        if denominator == 0 {
+            return Err(QrError::Unreadable);
+        }
+        let magnitude = gf_mul(evaluate(&evaluator, x_inverse), gf_inverse(denominator));
+        block[i] ^= gf_mul(x, magnitude);
+    }
+    if syndromes(block).iter().any(|&s| s != 0) {
+        return Err(QrError::Unreadable);
+    }
+    Ok(())
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    fn read(&mut self, bits: usize) -> Result<u32, QrError> {
+        if bits > self.remaining() {
+            return Err(QrError::Unreadable);
+        }
+        let mut value = 0;
+        for _ in 0..bits {
+            let bit = (self.bytes[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn read_count(&mut self, version: usize, bits: [usize; 3]) -> Result<usize, QrError> {
+        let class = match version {
+            1..=9 => 0,
+            10..=26 => 1,
+            _ => 2,
+        };
+        Ok(self.read(bits[class])? as usize)
+    }
+}
+
+/// Text of the numeric, alphanumeric, byte and kanji segments in `data`.
+fn parse_segments(data: &[u8], version: usize) -> Result<String, QrError> {
+    let mut reader = BitReader {
+        bytes: data,
+        position: 0,
+    };
+    let mut text = String::new();
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0b0000 => break,
+            0b0001 => {
+                let mut count = reader.read_count(version, [10, 12, 14])?;
+                while count > 0 {
+                    let (digits, bits) = match count {
+                        1 => (1, 4),
+                        2 => (2, 7),
+                        _ => (3, 10),
+                    };
+                    let value = reader.read(bits)?;
+                    if value >= 10u32.pow(digits as u32) {
+                        return Err(QrError::Unreadable);
+                    }
+                    text.push_str(&format!("{value:0digits$}"));
+                    count -= digits;
+                }
+            }
+            0b0010 => {
+                let mut count = reader.read_count(version, [9, 11, 13])?;
+                let char_at = |index: u32| {
+                    ALPHANUMERIC_CHARS
+                        .get(index as usize)
+                        .map(|&c| char::from(c))
+                        .ok_or(QrError::Unreadable)
+                };
+                while count >= 2 {
+                    let value = reader.read(11)?;
+                    text.push(char_at(value / 45)?);
+                    text.push(char_at(value % 45)?);
+                    count -= 2;
+                }
+                if count == 1 {
+                    text.push(char_at(reader.read(6)?)?);
+                }
+            }
+            0b0100 => {
+                let count = reader.read_count(version, [8, 16, 16])?;
+                let bytes = (0..count)
+                    .map(|_| reader.read(8).map(|byte| byte as u8))
+                    .collect::<Result<Vec<u8>, _>>()?;
+                // Byte segments are ISO-8859-1 by the standard but UTF-8 in
+                // practice.
+                match std::str::from_utf8(&bytes) {
+                    Ok(segment) => text.push_str(segment),
+                    Err(_) => text.push_str(&WINDOWS_1252.decode(&bytes).0),
+                }
+            }
+            0b0111 => {
+                // Extended channel interpretation; the designator is skipped
+                // and byte segments read as above.
+                let first = reader.read(8)?;
+                if first & 0x80 != 0 {
+                    let extra = if first & 0xc0 == 0x80 { 8 } else { 16 };
+                    reader.read(extra)?;
+                }
+            }
+            0b1000 => {
+                let count = reader.read_count(version, [8, 10, 12])?;
+                let mut bytes = Vec::with_capacity(count * 2);
+                for _ in 0..count {
+                    let value = reader.read(13)?;
+                    let packed = ((value / 0xc0) << 8) | (value % 0xc0);
+                    let shift_jis = if packed + 0x8140 <= 0x9ffc {
+                        packed + 0x8140
+                    } else {
+                        packed + 0xc140
+                    };
+                    bytes.extend_from_slice(&(shift_jis as u16).to_be_bytes());
+                }
+                text.push_str(&SHIFT_JIS.decode(&bytes).0);
+            }
+            _ => return Err(QrError::Unreadable),
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::{Color, EcLevel, QrCode};
+
+    /// Pixels per module of the rendered codes.
+    const SCALE: usize = 4;
+    /// Light modules around a code, as the standard asks for.
+    const QUIET_ZONE: usize = 4;
+
+    /// `code` drawn black on white, with `flipped` modules inverted.
+    fn render(code: &QrCode, flipped: &[(usize, usize)]) -> (Vec<u8>, usize) {
+        let modules = code.width();
+        let colors = code.to_colors();
+        let size = (modules + 2 * QUIET_ZONE) * SCALE;
+        let mut rgba = vec![0xff; size * size * 4];
+        for y in 0..modules {
+            for x in 0..modules {
+                let dark = (colors[y * modules + x] == Color::Dark) != flipped.contains(&(x, y));
+                if !dark {
+                    continue;
+                }
+                for py in 0..SCALE {
+                    for px in 0..SCALE {
+                        let row = (y + QUIET_ZONE) * SCALE + py;
+                        let column = (x + QUIET_ZONE) * SCALE + px;
+                        let offset = (row * size + column) * 4;
+                        rgba[offset..offset + 3].fill(0);
+                    }
+                }
+            }
+        }
+        (rgba, size)
+    }
+
+    fn round_trip(text: &str, level: EcLevel) -> String {
+        let code = QrCode::with_error_correction_level(text, level).unwrap();
+        let (rgba, size) = render(&code, &[]);
+        decode_qr(&rgba, size, size).unwrap()
+    }
+
+    #[test]
+    fn decodes_codes_of_each_mode_and_level() {
+        for level in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            for text in [
+                "https://example.com/path?query=1",
+                "0123456789012345",
+                "HELLO WORLD $%*+-./:",
+                "grüße, 世界",
+            ] {
+                assert_eq!(round_trip(text, level), text);
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_larger_versions() {
+        let text = "https://example.com/".repeat(12);
+        assert_eq!(round_trip(&text, EcLevel::M), text);
+    }
+
+    #[test]
+    fn corrects_damaged_modules() {
+        let text = "https://example.com/shared";
+        let code = QrCode::with_error_correction_level(text, EcLevel::H).unwrap();
+        // A few modules in the data area, away from the finder patterns.
+        let middle = code.width() / 2;
+        let flipped = [(middle, middle), (middle + 1, middle), (middle, middle + 2)];
+        let (rgba, size) = render(&code, &flipped);
+        assert_eq!(decode_qr(&rgba, size, size).unwrap(), text);
+    }
+
+    #[test]
+    fn finds_nothing_in_blank_or_noisy_images() {
+        let blank = vec![0xff; 64 * 64 * 4];
+        assert!(matches!(decode_qr(&blank, 64, 64), Err(QrError::NotFound)));
+        let mut seed = 0x2545_f491_u32;
+        let noise: Vec<u8> = (0..64 * 64 * 4)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                seed as u8
+            })
+            .collect();
+        assert!(decode_qr(&noise, 64, 64).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_images() {
+        let code = QrCode::new("https://example.com/").unwrap();
+        let (rgba, size) = render(&code, &[]);
+        assert!(matches!(
+            decode_qr(&rgba[..rgba.len() - 1], size, size),
+            Err(QrError::NotFound)
+        ));
+        assert!(decode_qr(&[], 0, 0).is_err());
+        assert!(decode_qr(&rgba, usize::MAX / 2, 2).is_err());
+        // Half a code is not read as anything.
+        assert!(decode_qr(&rgba[..rgba.len() / 2], size, size / 2).is_err());
+    }
+}
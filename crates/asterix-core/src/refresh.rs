@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{map_elements, BrowserCore, PageResponse, TabId};
+
+/// Shortest wait before a page that refreshes itself is loaded again, so a
+/// zero delay does not reload it as fast as the server answers.
+const MIN_RELOAD_DELAY: Duration = Duration::from_secs(1);
+
+/// Navigation a page asks for after a delay, with a `Refresh` header or a
+/// `<meta http-equiv="refresh">` element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRefresh {
+    /// Whole seconds to wait, as the page gave them.
+    pub delay_secs: u64,
+    /// Page to load, the page itself when none was given.
+    pub url: Url,
+}
+
+impl PageRefresh {
+    /// Parses a `Refresh` header or `content` attribute such as
+    /// `5; url=/next`, resolving the address against `base`. Fractions of
+    /// a second are ignored, as browsers do.
+    pub fn parse(value: &str, base: &Url) -> Option<Self> {
+        let value = value.trim_start_matches(is_whitespace);
+        let digits = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (seconds, rest) = value.split_at(digits);
+        let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        if seconds.is_empty() && rest.len() == value.len() {
+            return None;
+        }
+        // `.5` waits no time at all; an overlong number waits for ever.
+        let delay_secs = match seconds {
+            "" => 0,
+            seconds => seconds.parse().unwrap_or(u64::MAX),
+        };
+        if rest.is_empty() {
+            return Some(Self {
+                delay_secs,
+                url: base.clone(),
+            });
+        }
+        if !rest.starts_with([';', ',']) && !rest.starts_with(is_whitespace) {
+            return None;
+        }
+        let rest = rest.trim_start_matches(is_whitespace);
+        let rest = rest
+            .strip_prefix([';', ','])
+            .unwrap_or(rest)
+            .trim_start_matches(is_whitespace);
+        let target = match rest.get(..3) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("url") => {
+                match rest[3..]
+                    .trim_start_matches(is_whitespace)
+                    .strip_prefix('=')
+                {
+                    Some(after) => after.trim_start_matches(is_whitespace),
+                    None => rest,
+                }
+            }
+            _ => rest,
+        };
+        let target = match target.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let inner = &target[1..];
+                inner.find(quote).map_or(inner, |end| &inner[..end])
+            }
+            _ => target,
+        };
+        let target = target.trim_end_matches(is_whitespace);
+        let url = if target.is_empty() {
+            base.clone()
+        } else {
+            base.join(target).ok()?
+        };
+        Some(Self { delay_secs, url })
+    }
+
+    /// How long to wait before following the refresh from `page`.
+    pub fn delay(&self, page: &Url) -> Duration {
+        let delay = Duration::from_secs(self.delay_secs);
+        if self.url == *page {
+            delay.max(MIN_RELOAD_DELAY)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Refresh `page` asks for: its `Refresh` header, or else the first
+/// `<meta http-equiv="refresh">` of an HTML page. Only HTTP(S) targets
+/// are followed.
+pub fn page_refresh(page: &PageResponse) -> Option<PageRefresh> {
+    let refresh = page.refresh.clone().or_else(|| {
+        let is_html = page
+            .mime_type
+            .as_deref()
+            .is_none_or(|mime| mime.starts_with("text/html"));
+        if !is_html {
+            return None;
+        }
+        let elements = map_elements(&page.body);
+        let base = elements
+            .iter()
+            .find(|element| element.tag == "base")
+            .and_then(|element| element.attribute("href"))
+            .and_then(|href| page.url.join(href.trim()).ok())
+            .unwrap_or_else(|| page.url.clone());
+        elements
+            .iter()
+            .filter(|element| element.tag == "meta")
+            .find(|element| {
+                element
+                    .attribute("http-equiv")
+                    .is_some_and(|equiv| equiv.trim().eq_ignore_ascii_case("refresh"))
+            })
+            .and_then(|element| PageRefresh::parse(element.attribute("content")?, &base))
+    })?;
+    matches!(refresh.url.scheme(), "http" | "https").then_some(refresh)
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ')
+}
+
+impl BrowserCore {
+    /// Stops `tab` from following the refreshes pages ask for, or lets it
+    /// again.
+    pub fn set_tab_auto_refresh(&self, tab: TabId, enabled: bool) {
+        let mut guard = self.state.write();
+        if let Some(snapshot) = guard.tabs.iter_mut().find(|snapshot| snapshot.id == tab) {
+            snapshot.auto_refresh_disabled = !enabled;
+        }
+    }
+
+    /// Whether `tab` follows the refreshes pages ask for.
+    pub fn tab_auto_refresh(&self, tab: TabId) -> bool {
+        self.state
+            .read()
+            .tabs
+            .iter()
+            .find(|snapshot| snapshot.id == tab)
+            .is_some_and(|snapshot| !snapshot.auto_refresh_disabled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://example.com/dir/page").unwrap()
+    }
+
+    fn parse(value: &str) -> Option<(u64, String)> {
+        PageRefresh::parse(value, &base()).map(|refresh| (refresh.delay_secs, refresh.url.into()))
+    }
+
+    fn html(body: &str) -> PageResponse {
+        PageResponse::from_bytes(base(), 200, Some("text/html".to_owned()), body.to_owned())
+    }
+
+    #[test]
+    fn parses_delays_and_targets() {
+        let page = base().to_string();
+        for (value, delay, url) in [
+            ("5", 5, page.as_str()),
+            ("  0 ", 0, page.as_str()),
+            ("3; url=/next", 3, "https://example.com/next"),
+            (
+                "3;URL = 'next?a=1' trailing",
+                3,
+                "https://example.com/dir/next?a=1",
+            ),
+            ("3, url=\"https://other.test/\"", 3, "https://other.test/"),
+            ("3 https://other.test/x", 3, "https://other.test/x"),
+            ("1.5; url=next", 1, "https://example.com/dir/next"),
+            (".5;url=next", 0, "https://example.com/dir/next"),
+            ("2; url=", 2, page.as_str()),
+        ] {
+            assert_eq!(parse(value), Some((delay, url.to_owned())), "{value}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_and_truncated_values() {
+        for value in [
+            "",
+            "   ",
+            "url=/next",
+            "abc",
+            "5abc",
+            "5;url=\u{0}http://[::1",
+        ] {
+            assert_eq!(parse(value), None, "{value}");
+        }
+        // An unterminated quote runs to the end.
+        assert_eq!(
+            parse("1; url='next"),
+            Some((1, "https://example.com/dir/next".to_owned()))
+        );
+        assert_eq!(
+            parse("99999999999999999999999; url=next").map(|(delay, _)| delay),
+            Some(u64::MAX)
+        );
+        for value in ["1; u", "1; ur", "1; url", "1;\u{e9}", "1 ;"] {
+            let _ = parse(value);
+        }
+    }
+
+    #[test]
+    fn reloads_of_the_same_page_wait_a_moment() {
+        let now = PageRefresh::parse("0", &base()).unwrap();
+        assert_eq!(now.delay(&base()), MIN_RELOAD_DELAY);
+        let elsewhere = PageRefresh::parse("0; url=/next", &base()).unwrap();
+        assert_eq!(elsewhere.delay(&base()), Duration::ZERO);
+        let later = PageRefresh::parse("30", &base()).unwrap();
+        assert_eq!(later.delay(&base()), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn finds_meta_refresh_against_the_base() {
+        let page = html(
+            r#"<head><base href="https://cdn.test/root/">
+               <meta name="refresh" content="1; url=wrong">
+               <meta http-equiv=" Refresh " content="4; url=next"></head>"#,
+        );
+        let refresh = page_refresh(&page).unwrap();
+        assert_eq!(refresh.delay_secs, 4);
+        assert_eq!(refresh.url.as_str(), "https://cdn.test/root/next");
+    }
+
+    #[test]
+    fn prefers_the_header_and_follows_only_http() {
+        let mut page = html(r#"<meta http-equiv="refresh" content="1; url=/meta">"#);
+        page.refresh = PageRefresh::parse("2; url=/header", &base());
+        assert_eq!(page_refresh(&page).unwrap().url.path(), "/header");
+
+        let script = html(r#"<meta http-equiv="refresh" content="0; url=javascript:alert(1)">"#);
+        assert!(page_refresh(&script).is_none());
+        let broken = html(r#"<meta http-equiv="refresh" content="soon">"#);
+        assert!(page_refresh(&broken).is_none());
+        let mut text = html(r#"<meta http-equiv="refresh" content="0">"#);
+        text.mime_type = Some("text/plain".to_owned());
+        assert!(page_refresh(&text).is_none());
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{background_paint, parse_color, strip_comments, Paint};
+use crate::{ComputedStyle, MediaFeatures, SourceElement, StyleSheet};
+
+/// Display values laid out on lines of their own.
+const BLOCK_DISPLAYS: [&str; 8] = [
+    "block",
+    "flex",
+    "grid",
+    "list-item",
+    "table",
+    "table-row",
+    "table-caption",
+    "flow-root",
+];
+
+/// How a page is rendered for preview.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RenderOptions {
+    pub media: MediaFeatures,
+    /// Whether images are shown; when off, only their alt text remains.
+    pub images: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            media: MediaFeatures::default(),
+            images: true,
+        }
+    }
+}
+
+/// Text of a page as laid out by its styles for one set of
+/// [`RenderOptions`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderedPage {
+    pub text: String,
+    /// Page background and text color, when the styles set them.
+    pub background: Option<[u8; 3]>,
+    pub color: Option<[u8; 3]>,
+    /// Elements left out because their computed `display` is `none`.
+    pub hidden: usize,
+}
+
+/// Cascades the page's embedded styles for `options` and lays out the text
+/// of the elements that remain displayed. The source is not refetched, so
+/// linked stylesheets stay unapplied.
+pub fn render_document(
+    source: &str,
+    elements: &[SourceElement],
+    options: &RenderOptions,
+) -> RenderedPage {
+    render_with_stylesheets(source, elements, options, &HashMap::new())
+}
+
+/// Like [`render_document`], also cascading the linked stylesheets fetched
+/// for the page, keyed by `href` as written; see
+/// [`PageResources::stylesheets`](crate::PageResources::stylesheets).
+pub fn render_with_stylesheets(
+    source: &str,
+    elements: &[SourceElement],
+    options: &RenderOptions,
+    linked: &HashMap<String, String>,
+) -> RenderedPage {
+    let sheet = StyleSheet::for_media_linked(source, elements, &options.media, linked);
+    let mut children = vec![Vec::new(); elements.len()];
+    let mut roots = Vec::new();
+    for (index, element) in elements.iter().enumerate() {
+        match element.parent {
+            Some(parent) => children[parent].push(index),
+            None => roots.push(index),
+        }
+    }
+
+    let styles = sheet.compute_all(elements, &HashSet::new());
+    let mut renderer = Renderer {
+        source,
+        elements,
+        styles: &styles,
+        children,
+        images: options.images,
+        text: String::new(),
+        hidden: 0,
+    };
+    for root in roots {
+        renderer.element(root, false);
+    }
+
+    // The canvas takes the root element's background, or the body's when
+    // the root has none.
+    let mut background = None;
+    let mut color = None;
+    for tag in ["body", "html"] {
+        let Some(index) = elements.iter().position(|element| element.tag == tag) else {
+            continue;
+        };
+        let style = &styles[index];
+        let paint = style
+            .property("background-color")
+            .or_else(|| style.property("background"))
+            .and_then(background_paint);
+        if let Some(Paint::Color(rgb)) = paint {
+            background = Some(rgb);
+        }
+        if let Some(Paint::Color(rgb)) = style.property("color").and_then(parse_color) {
+            color = color.or(Some(rgb));
+        }
+    }
+
+    RenderedPage {
+        text: renderer.text.trim().to_owned(),
+        background,
+        color,
+        hidden: renderer.hidden,
+    }
+}
+
+struct Renderer<'a> {
+    source: &'a str,
+    elements: &'a [SourceElement],
+    styles: &'a [ComputedStyle],
+    children: Vec<Vec<usize>>,
+    images: bool,
+    text: String,
+    hidden: usize,
+}
+
+impl Renderer<'_> {
+    fn element(&mut self, index: usize, preformatted: bool) {
+        let element = &self.elements[index];
+        let style = &self.styles[index];
+        let display = style.property("display").unwrap_or("inline");
+        if display == "none" || element.attribute("hidden").is_some() {
+            self.hidden += 1;
+            return;
+        }
+        match element.tag.as_str() {
+            "img" => {
+                let alt = element.attribute("alt").unwrap_or_default().trim();
+                if self.images {
+                    self.text.push_str(&format!("[image: {alt}]"));
+                } else {
+                    self.push_text(alt, false);
+                }
+                return;
+            }
+            "br" => {
+                self.text.push('\n');
+                return;
+            }
+            _ => {}
+        }
+        let preformatted = preformatted
+            || matches!(element.tag.as_str(), "pre" | "textarea")
+            || style
+                .property("white-space")
+                .is_some_and(|value| value.starts_with("pre"));
+        let block = BLOCK_DISPLAYS.contains(&display);
+        if block {
+            self.break_line();
+        }
+        if display == "list-item" {
+            self.text.push_str("• ");
+        }
+        if display == "table-cell" && !self.text.ends_with(['\n', '\t']) {
+            self.text.push('\t');
+        }
+
+        // Text runs between the element's children, which are rendered in
+        // between.
+        let region = &self.source[element.start..element.end];
+        if let Some(open_end) = region.find('>') {
+            let mut content = &region[open_end + 1..];
+            if let Some(close) = content.rfind("</") {
+                content = &content[..close];
+            }
+            let content_start = element.start + open_end + 1;
+            let content_end = content_start + content.len();
+            let mut pos = content_start;
+            for child in self.children[index].clone() {
+                let child_element = &self.elements[child];
+                let stop = child_element.start.min(content_end);
+                if stop > pos {
+                    self.push_text(&self.source[pos..stop], preformatted);
+                }
+                self.element(child, preformatted);
+                pos = pos.max(child_element.end);
+            }
+            if pos < content_end {
+                self.push_text(&self.source[pos..content_end], preformatted);
+            }
+        }
+
+        if block {
+            self.break_line();
+        }
+    }
+
+    fn push_text(&mut self, raw: &str, preformatted: bool) {
+        let text = decode_entities(&strip_comments(raw));
+        if preformatted {
+            self.text.push_str(&text);
+            return;
+        }
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !self.text.is_empty() && !self.text.ends_with(char::is_whitespace) {
+                    self.text.push(' ');
+                }
+            } else {
+                self.text.push(c);
+            }
+        }
+    }
+
+    fn break_line(&mut self) {
+        let trimmed = self.text.trim_end_matches([' ', '\t']).len();
+        self.text.truncate(trimmed);
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+}
+
+/// Replaces numeric character references and the common named ones.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(';').filter(|&end| end <= 10);
+        let character = end.and_then(|end| {
+            let name = &rest[1..end];
+            match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                "mdash" => Some('—'),
+                "ndash" => Some('–'),
+                "hellip" => Some('…'),
+                "copy" => Some('©'),
+                _ => {
+                    let number = name.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code)
+                }
+            }
+        });
+        match (character, end) {
+            (Some(character), Some(end)) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
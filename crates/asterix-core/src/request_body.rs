@@ -0,0 +1,162 @@
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::BrowserError;
+
+/// Body sent with a [`PageRequest`](crate::PageRequest).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequestBody {
+    /// `application/x-www-form-urlencoded` fields, as HTML forms send them
+    /// by default.
+    UrlEncoded(Vec<(String, String)>),
+    /// `multipart/form-data` parts, as forms with `enctype="multipart/form-data"`
+    /// send them.
+    Multipart(Vec<MultipartPart>),
+    /// Bytes sent as they are.
+    Raw { content_type: String, data: Vec<u8> },
+}
+
+/// One named part of a multipart body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultipartPart {
+    pub name: String,
+    /// Set for file uploads.
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl MultipartPart {
+    pub fn text(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            file_name: None,
+            content_type: None,
+            data: value.into().into_bytes(),
+        }
+    }
+
+    pub fn file(
+        name: impl Into<String>,
+        file_name: impl Into<String>,
+        content_type: impl Into<String>,
+        data: Vec<u8>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            file_name: Some(file_name.into()),
+            content_type: Some(content_type.into()),
+            data,
+        }
+    }
+}
+
+impl RequestBody {
+    /// Content type and encoded bytes of the body.
+    pub fn encode(&self) -> (String, Vec<u8>) {
+        match self {
+            RequestBody::UrlEncoded(fields) => {
+                let encoded = url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(fields)
+                    .finish();
+                (
+                    "application/x-www-form-urlencoded".to_owned(),
+                    encoded.into_bytes(),
+                )
+            }
+            RequestBody::Multipart(parts) => {
+                let boundary: String = rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(24)
+                    .map(char::from)
+                    .collect();
+                let boundary = format!("----AsterixFormBoundary{boundary}");
+                (
+                    format!("multipart/form-data; boundary={boundary}"),
+                    encode_multipart(parts, &boundary),
+                )
+            }
+            RequestBody::Raw { content_type, data } => (content_type.clone(), data.clone()),
+        }
+    }
+}
+
+fn encode_multipart(parts: &[MultipartPart], boundary: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        let mut disposition = format!(
+            "Content-Disposition: form-data; name=\"{}\"",
+            escape_quoted(&part.name)
+        );
+        if let Some(file_name) = &part.file_name {
+            disposition.push_str(&format!("; filename=\"{}\"", escape_quoted(file_name)));
+        }
+        body.extend_from_slice(disposition.as_bytes());
+        body.extend_from_slice(b"\r\n");
+        if let Some(content_type) = &part.content_type {
+            body.extend_from_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+        body.extend_from_slice(&part.data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    body
+}
+
+/// Percent-encodes the characters that would end a quoted parameter, the
+/// way browsers write form field and file names.
+fn escape_quoted(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Method named by `method`, in any case.
+pub(crate) fn parse_method(method: &str) -> Result<Method, BrowserError> {
+    Method::from_bytes(method.trim().to_ascii_uppercase().as_bytes())
+        .map_err(|_| BrowserError::InvalidRequest(format!("invalid method {method:?}")))
+}
+
+/// Adds `headers` to `builder`, skipping blank names and refusing ones that
+/// are not valid HTTP.
+pub(crate) fn apply_headers(
+    mut builder: reqwest::RequestBuilder,
+    headers: &[(String, String)],
+) -> Result<reqwest::RequestBuilder, BrowserError> {
+    for (name, value) in headers {
+        if name.trim().is_empty() {
+            continue;
+        }
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| BrowserError::InvalidRequest(format!("invalid header name {name:?}")))?;
+        let value = HeaderValue::from_str(value).map_err(|_| {
+            BrowserError::InvalidRequest(format!("invalid value for header {name}"))
+        })?;
+        builder = builder.header(name, value);
+    }
+    Ok(builder)
+}
+
+/// Attaches `body` with its content type unless `headers` set one already.
+pub(crate) fn apply_body(
+    builder: reqwest::RequestBuilder,
+    body: &RequestBody,
+    headers: &[(String, String)],
+) -> reqwest::RequestBuilder {
+    let (content_type, data) = body.encode();
+    let has_content_type = headers
+        .iter()
+        .any(|(name, _)| name.trim().eq_ignore_ascii_case(CONTENT_TYPE.as_str()));
+    let builder = if has_content_type {
+        builder
+    } else {
+        builder.header(CONTENT_TYPE, content_type)
+    };
+    builder.body(data)
+}
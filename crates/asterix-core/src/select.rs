@@ -0,0 +1,38 @@
+use scraper::{Html, Selector};
+
+/// Text of every element in `source` matching the CSS `selector`, with
+/// whitespace collapsed.
+pub fn select_text(source: &str, selector: &str) -> Result<Vec<String>, String> {
+    let selector = parse(selector)?;
+    let document = Html::parse_document(source);
+    Ok(document
+        .select(&selector)
+        .map(|element| {
+            element
+                .text()
+                .flat_map(str::split_whitespace)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect())
+}
+
+/// Value of `attribute` on every element in `source` matching the CSS
+/// `selector`; elements without the attribute are skipped.
+pub fn select_attribute(
+    source: &str,
+    selector: &str,
+    attribute: &str,
+) -> Result<Vec<String>, String> {
+    let selector = parse(selector)?;
+    let document = Html::parse_document(source);
+    Ok(document
+        .select(&selector)
+        .filter_map(|element| element.value().attr(attribute))
+        .map(str::to_owned)
+        .collect())
+}
+
+fn parse(selector: &str) -> Result<Selector, String> {
+    Selector::parse(selector).map_err(|err| format!("invalid selector `{selector}`: {err}"))
+}
@@ -0,0 +1,307 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::metadata::{element_text, first_meta};
+use crate::SourceElement;
+
+/// Meta names that carry robots directives.
+const ROBOTS_AGENTS: [&str; 4] = ["robots", "googlebot", "bingbot", "googlebot-news"];
+
+/// Search and sharing metadata declared by a page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeoSummary {
+    /// Text of the `<title>` element.
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub canonical: Option<Url>,
+    /// Robots meta tags as `(agent, directives)`.
+    pub robots: Vec<(String, String)>,
+    /// `og:*` and `article:*` properties in document order.
+    pub open_graph: Vec<(String, String)>,
+    /// `twitter:*` properties in document order.
+    pub twitter: Vec<(String, String)>,
+    pub card: ShareCard,
+    pub structured_data: Vec<StructuredData>,
+}
+
+impl SeoSummary {
+    /// Whether any robots tag keeps the page out of search results.
+    pub fn is_noindex(&self) -> bool {
+        self.robots.iter().any(|(_, directives)| {
+            directives.split(',').map(str::trim).any(|directive| {
+                directive.eq_ignore_ascii_case("noindex") || directive.eq_ignore_ascii_case("none")
+            })
+        })
+    }
+}
+
+/// How the page would look when shared, resolved the way social sites do:
+/// Twitter properties first, then Open Graph, then the plain document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShareCard {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<Url>,
+    pub site_name: Option<String>,
+    /// The `twitter:card` type, such as `summary_large_image`.
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StructuredFormat {
+    JsonLd,
+    Microdata,
+}
+
+impl StructuredFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            StructuredFormat::JsonLd => "JSON-LD",
+            StructuredFormat::Microdata => "Microdata",
+        }
+    }
+}
+
+/// One block of schema.org-style structured data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuredData {
+    pub format: StructuredFormat,
+    /// Declared item types, such as `Article` or `https://schema.org/Product`.
+    pub types: Vec<String>,
+    /// Pretty-printed JSON, or `itemprop: value` lines for microdata.
+    pub content: String,
+    /// Why a JSON-LD block could not be parsed; `content` is then the raw text.
+    pub error: Option<String>,
+}
+
+/// Language version of a page, declared with `<link rel="alternate" hreflang>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlternateLanguage {
+    /// Language tag such as `de` or `pt-BR`; `x-default` marks the version
+    /// for unmatched languages.
+    pub hreflang: String,
+    pub url: Url,
+}
+
+/// Lists the other language versions a page links to, in source order and
+/// without duplicates.
+pub fn alternate_languages(elements: &[SourceElement], base: &Url) -> Vec<AlternateLanguage> {
+    let mut alternates: Vec<AlternateLanguage> = Vec::new();
+    for element in elements.iter().filter(|element| element.tag == "link") {
+        let is_alternate = element.attribute("rel").is_some_and(|rel| {
+            rel.split_whitespace()
+                .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+        });
+        let (Some(hreflang), Some(href)) =
+            (element.attribute("hreflang"), element.attribute("href"))
+        else {
+            continue;
+        };
+        let Ok(url) = base.join(href.trim()) else {
+            continue;
+        };
+        let alternate = AlternateLanguage {
+            hreflang: hreflang.trim().to_owned(),
+            url,
+        };
+        if is_alternate && !alternate.hreflang.is_empty() && !alternates.contains(&alternate) {
+            alternates.push(alternate);
+        }
+    }
+    alternates
+}
+
+/// Collects the title, description, canonical URL, robots directives,
+/// social cards and structured data of the page at `url`.
+pub fn extract_seo(source: &str, url: &Url) -> SeoSummary {
+    let document = Html::parse_document(source);
+    let resolve = |href: &str| url.join(href.trim()).ok();
+
+    let canonical = select_all(&document, "link[rel][href]")
+        .into_iter()
+        .find(|link| {
+            link.value().attr("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("canonical"))
+            })
+        })
+        .and_then(|link| link.value().attr("href"))
+        .and_then(resolve);
+
+    let mut robots = Vec::new();
+    let mut open_graph = Vec::new();
+    let mut twitter = Vec::new();
+    for meta in select_all(&document, "meta[content]") {
+        let element = meta.value();
+        let Some(key) = element.attr("property").or_else(|| element.attr("name")) else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let content = element
+            .attr("content")
+            .unwrap_or_default()
+            .trim()
+            .to_owned();
+        if ROBOTS_AGENTS.contains(&key.as_str()) {
+            robots.push((key, content));
+        } else if key.starts_with("og:") || key.starts_with("article:") {
+            open_graph.push((key, content));
+        } else if key.starts_with("twitter:") {
+            twitter.push((key, content));
+        }
+    }
+
+    let title = element_text(&document, "title");
+    let description = first_meta(&document, &["description"]);
+    let card = ShareCard {
+        title: first_meta(&document, &["twitter:title", "og:title"]).or_else(|| title.clone()),
+        description: first_meta(&document, &["twitter:description", "og:description"])
+            .or_else(|| description.clone()),
+        image: first_meta(
+            &document,
+            &["twitter:image", "twitter:image:src", "og:image"],
+        )
+        .and_then(|image| resolve(&image)),
+        site_name: first_meta(&document, &["og:site_name", "twitter:site"])
+            .or_else(|| url.host_str().map(str::to_owned)),
+        kind: first_meta(&document, &["twitter:card"]).unwrap_or_else(|| "summary".to_owned()),
+    };
+
+    let mut structured_data: Vec<StructuredData> = select_all(&document, "script[type]")
+        .into_iter()
+        .filter(|script| {
+            script
+                .value()
+                .attr("type")
+                .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"))
+        })
+        .map(|script| json_ld(&script.text().collect::<String>()))
+        .collect();
+    structured_data.extend(
+        select_all(&document, "[itemscope]")
+            .into_iter()
+            .filter(|item| {
+                !item
+                    .ancestors()
+                    .filter_map(ElementRef::wrap)
+                    .any(|ancestor| ancestor.value().attr("itemscope").is_some())
+            })
+            .map(microdata),
+    );
+
+    SeoSummary {
+        title,
+        description,
+        canonical,
+        robots,
+        open_graph,
+        twitter,
+        card,
+        structured_data,
+    }
+}
+
+fn select_all<'a>(document: &'a Html, selector: &str) -> Vec<ElementRef<'a>> {
+    match Selector::parse(selector) {
+        Ok(selector) => document.select(&selector).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn json_ld(text: &str) -> StructuredData {
+    let parsed = serde_json::from_str::<serde_json::Value>(text.trim());
+    match parsed {
+        Ok(value) => {
+            let mut types = Vec::new();
+            collect_types(&value, &mut types);
+            StructuredData {
+                format: StructuredFormat::JsonLd,
+                types,
+                content: serde_json::to_string_pretty(&value).unwrap_or_default(),
+                error: None,
+            }
+        }
+        Err(err) => StructuredData {
+            format: StructuredFormat::JsonLd,
+            types: Vec::new(),
+            content: text.trim().to_owned(),
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+/// Gathers the top-level `@type`s of a JSON-LD value, looking through
+/// arrays and `@graph`.
+fn collect_types(value: &serde_json::Value, types: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_types(item, types);
+            }
+        }
+        serde_json::Value::Object(object) => {
+            match object.get("@type") {
+                Some(serde_json::Value::String(kind)) => types.push(kind.clone()),
+                Some(serde_json::Value::Array(kinds)) => {
+                    types.extend(
+                        kinds
+                            .iter()
+                            .filter_map(|kind| kind.as_str().map(str::to_owned)),
+                    );
+                }
+                _ => {}
+            }
+            if let Some(graph) = object.get("@graph") {
+                collect_types(graph, types);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn microdata(item: ElementRef) -> StructuredData {
+    let types = item
+        .value()
+        .attr("itemtype")
+        .map(|types| types.split_whitespace().map(str::to_owned).collect())
+        .unwrap_or_default();
+    let content = select_within(item, "[itemprop]")
+        .into_iter()
+        .map(|property| {
+            let element = property.value();
+            // Nested items are listed by type; their own properties follow.
+            let nested = element
+                .attr("itemscope")
+                .map(|_| element.attr("itemtype").unwrap_or("(item)"));
+            let value = nested
+                .or_else(|| element.attr("content"))
+                .or_else(|| element.attr("href"))
+                .or_else(|| element.attr("src"))
+                .or_else(|| element.attr("datetime"))
+                .map(str::to_owned)
+                .unwrap_or_else(|| {
+                    property
+                        .text()
+                        .flat_map(str::split_whitespace)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                });
+            format!("{}: {value}", element.attr("itemprop").unwrap_or_default())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    StructuredData {
+        format: StructuredFormat::Microdata,
+        types,
+        content,
+        error: None,
+    }
+}
+
+fn select_within<'a>(element: ElementRef<'a>, selector: &str) -> Vec<ElementRef<'a>> {
+    match Selector::parse(selector) {
+        Ok(selector) => element.select(&selector).collect(),
+        Err(_) => Vec::new(),
+    }
+}
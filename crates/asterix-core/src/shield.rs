@@ -0,0 +1,998 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::{Position, Url};
+
+use crate::intercept::{intercept, Interception};
+use crate::{BrowserCore, BrowserError, BrowserState, RequestKind, TabId};
+
+// Request types, as named by filter options such as `$script`.
+const SCRIPT: u16 = 1 << 0;
+const IMAGE: u16 = 1 << 1;
+const STYLESHEET: u16 = 1 << 2;
+const OBJECT: u16 = 1 << 3;
+const XHR: u16 = 1 << 4;
+const SUBDOCUMENT: u16 = 1 << 5;
+const PING: u16 = 1 << 6;
+const WEBSOCKET: u16 = 1 << 7;
+const FONT: u16 = 1 << 8;
+const MEDIA: u16 = 1 << 9;
+const OTHER: u16 = 1 << 10;
+const DOCUMENT: u16 = 1 << 11;
+const POPUP: u16 = 1 << 12;
+const ALL_TYPES: u16 = (1 << 13) - 1;
+/// Types a filter without type options applies to: everything a page loads,
+/// but not the page itself.
+const DEFAULT_TYPES: u16 = ALL_TYPES & !(DOCUMENT | POPUP);
+
+const TYPE_OPTIONS: [(&str, u16); 20] = [
+    ("script", SCRIPT),
+    ("image", IMAGE),
+    ("stylesheet", STYLESHEET),
+    ("css", STYLESHEET),
+    ("object", OBJECT),
+    ("xmlhttprequest", XHR),
+    ("xhr", XHR),
+    ("subdocument", SUBDOCUMENT),
+    ("frame", SUBDOCUMENT),
+    ("ping", PING),
+    ("beacon", PING),
+    ("websocket", WEBSOCKET),
+    ("font", FONT),
+    ("media", MEDIA),
+    ("other", OTHER),
+    ("document", DOCUMENT),
+    ("doc", DOCUMENT),
+    ("popup", POPUP),
+    ("all", ALL_TYPES),
+    ("xbl", OTHER),
+];
+
+/// Tokens so common in URLs that indexing filters under them saves little.
+const COMMON_TOKENS: [&str; 4] = ["http", "https", "www", "com"];
+
+/// Filter list the content blocker applies, in Adblock Plus syntax as
+/// EasyList and most other lists publish it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterList {
+    pub name: String,
+    /// Where the list was subscribed from, for lists fetched by URL.
+    pub source: Option<Url>,
+    pub text: String,
+}
+
+/// Filter list in use, without its text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterListInfo {
+    pub name: String,
+    pub source: Option<Url>,
+    /// Request filters in effect.
+    pub filters: usize,
+    /// Lines left out: element hiding, regular expressions and filters with
+    /// options the blocker does not support.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    None,
+    /// `|`: the start of the address.
+    Start,
+    /// `||`: the start of the host or of one of its labels.
+    Host,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    /// `*`
+    Wildcard,
+    /// `^`: one character that cannot be part of a name, or the end.
+    Separator,
+}
+
+#[derive(Debug)]
+struct Filter {
+    /// The line as the list has it, shown when the filter blocks something.
+    text: String,
+    exception: bool,
+    important: bool,
+    match_case: bool,
+    anchor: Anchor,
+    parts: Vec<Part>,
+    /// Trailing `|`: the pattern has to reach the end of the address.
+    end_anchor: bool,
+    types: u16,
+    /// `||host^` without options, which blocks navigating to the site as
+    /// well as loading from it.
+    whole_site: bool,
+    /// `$third-party` when `Some(true)`, `$~third-party` when `Some(false)`.
+    third_party: Option<bool>,
+    /// `$domain=` entries, `false` for the excluded `~domain` ones.
+    domains: Vec<(String, bool)>,
+}
+
+/// Outcome of parsing one line of a list.
+enum Line {
+    Filter(Filter),
+    /// Blank lines, comments and the list header.
+    Ignored,
+    Unsupported,
+}
+
+fn parse_line(line: &str) -> Line {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+        return Line::Ignored;
+    }
+    if ["##", "#@#", "#?#", "#$#", "#%#", "$$", "$@$"]
+        .iter()
+        .any(|marker| line.contains(marker))
+    {
+        return Line::Unsupported;
+    }
+    match parse_filter(line) {
+        Some(filter) => Line::Filter(filter),
+        None => Line::Unsupported,
+    }
+}
+
+fn parse_filter(line: &str) -> Option<Filter> {
+    let (exception, rest) = match line.strip_prefix("@@") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // Options follow the last `$`, unless it belongs to the pattern.
+    let (pattern, options) = match rest.rsplit_once('$') {
+        Some((pattern, options)) if !options.contains('/') || options.contains("domain=") => {
+            (pattern, Some(options))
+        }
+        _ => (rest, None),
+    };
+    if pattern.len() > 1 && pattern.starts_with('/') && pattern.ends_with('/') {
+        // Regular expressions.
+        return None;
+    }
+
+    let mut filter = Filter {
+        text: line.to_owned(),
+        exception,
+        important: false,
+        match_case: false,
+        anchor: Anchor::None,
+        parts: Vec::new(),
+        end_anchor: false,
+        types: DEFAULT_TYPES,
+        whole_site: false,
+        third_party: None,
+        domains: Vec::new(),
+    };
+    let mut included = 0;
+    let mut excluded = 0;
+    for option in options.into_iter().flat_map(|options| options.split(',')) {
+        let option = option.trim().to_ascii_lowercase();
+        let (negated, name) = match option.strip_prefix('~') {
+            Some(name) => (true, name),
+            None => (false, option.as_str()),
+        };
+        if let Some((_, types)) = TYPE_OPTIONS
+            .iter()
+            .find(|(type_name, _)| *type_name == name)
+        {
+            if negated {
+                excluded |= types;
+            } else {
+                included |= types;
+            }
+            continue;
+        }
+        match name {
+            "third-party" | "3p" => filter.third_party = Some(!negated),
+            "first-party" | "1p" => filter.third_party = Some(negated),
+            "match-case" if !negated => filter.match_case = true,
+            "important" if !negated => filter.important = true,
+            _ => {
+                let domains = name
+                    .strip_prefix("domain=")
+                    .or_else(|| name.strip_prefix("from="))
+                    .filter(|_| !negated)?;
+                for domain in domains.split('|').filter(|domain| !domain.is_empty()) {
+                    filter.domains.push(match domain.strip_prefix('~') {
+                        Some(domain) => (domain.to_owned(), false),
+                        None => (domain.to_owned(), true),
+                    });
+                }
+            }
+        }
+    }
+    if included != 0 || excluded != 0 {
+        let base = if included == 0 {
+            DEFAULT_TYPES
+        } else {
+            included
+        };
+        filter.types = base & !excluded;
+        if filter.types == 0 {
+            return None;
+        }
+    }
+
+    let mut pattern = pattern;
+    if let Some(rest) = pattern.strip_prefix("||") {
+        filter.anchor = Anchor::Host;
+        pattern = rest;
+    } else if let Some(rest) = pattern.strip_prefix('|') {
+        filter.anchor = Anchor::Start;
+        pattern = rest;
+    }
+    if let Some(rest) = pattern.strip_suffix('|') {
+        filter.end_anchor = true;
+        pattern = rest;
+    }
+    let mut literal = String::new();
+    for c in pattern.chars() {
+        let part = match c {
+            '*' => Part::Wildcard,
+            '^' => Part::Separator,
+            c => {
+                literal.push(if filter.match_case {
+                    c
+                } else {
+                    c.to_ascii_lowercase()
+                });
+                continue;
+            }
+        };
+        if !literal.is_empty() {
+            filter
+                .parts
+                .push(Part::Literal(std::mem::take(&mut literal)));
+        }
+        if part == Part::Wildcard && filter.parts.last() == Some(&Part::Wildcard) {
+            continue;
+        }
+        filter.parts.push(part);
+    }
+    if !literal.is_empty() {
+        filter.parts.push(Part::Literal(literal));
+    }
+    // Wildcards at either end of an unanchored pattern match nothing extra.
+    if filter.parts.first() == Some(&Part::Wildcard) && filter.anchor != Anchor::Host {
+        filter.parts.remove(0);
+        filter.anchor = Anchor::None;
+    }
+    if filter.parts.last() == Some(&Part::Wildcard) && !filter.end_anchor {
+        filter.parts.pop();
+    }
+
+    filter.whole_site = filter.anchor == Anchor::Host
+        && options.is_none()
+        && match filter.parts.as_slice() {
+            [Part::Literal(host)] | [Part::Literal(host), Part::Separator] => !host.contains('/'),
+            _ => false,
+        };
+    Some(filter)
+}
+
+fn is_token_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'%'
+}
+
+/// Runs of name characters in `text`, with where each starts.
+fn tokens(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let bytes = text.as_bytes();
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        while at < bytes.len() && !is_token_byte(bytes[at]) {
+            at += 1;
+        }
+        let start = at;
+        while at < bytes.len() && is_token_byte(bytes[at]) {
+            at += 1;
+        }
+        (at > start).then(|| (start, &text[start..at]))
+    })
+}
+
+impl Filter {
+    /// Token every address the filter matches contains whole, to index the
+    /// filter under. `None` when the pattern pins down no such token.
+    fn index_token(&self) -> Option<String> {
+        let mut best: Option<&str> = None;
+        for (index, part) in self.parts.iter().enumerate() {
+            let Part::Literal(literal) = part else {
+                continue;
+            };
+            let before_is_boundary = match index {
+                0 => self.anchor != Anchor::None,
+                _ => self.parts[index - 1] == Part::Separator,
+            };
+            let after_is_boundary = match self.parts.get(index + 1) {
+                None => self.end_anchor,
+                Some(next) => *next == Part::Separator,
+            };
+            for (start, token) in tokens(literal) {
+                let whole = (start > 0 || before_is_boundary)
+                    && (start + token.len() < literal.len() || after_is_boundary);
+                if !whole {
+                    continue;
+                }
+                let better = match best {
+                    None => true,
+                    Some(current) => {
+                        let common = COMMON_TOKENS.contains(&current);
+                        common && !COMMON_TOKENS.contains(&token)
+                            || common == COMMON_TOKENS.contains(&token)
+                                && token.len() > current.len()
+                    }
+                };
+                if better {
+                    best = Some(token);
+                }
+            }
+        }
+        best.map(str::to_ascii_lowercase)
+    }
+
+    fn matches(&self, request: &Request) -> bool {
+        if self.types & request.kind == 0 && !(request.kind == DOCUMENT && self.whole_site) {
+            return false;
+        }
+        if self
+            .third_party
+            .is_some_and(|third_party| request.third_party != Some(third_party))
+        {
+            return false;
+        }
+        if !self.domains.is_empty() && !self.applies_on(request.page_host) {
+            return false;
+        }
+        let address = if self.match_case {
+            request.original
+        } else {
+            request.lower
+        }
+        .as_bytes();
+        let tail = |start: usize| match_parts(&self.parts, address, start, self.end_anchor);
+        match self.anchor {
+            Anchor::Start => tail(0),
+            Anchor::Host => {
+                let (host_start, host_end) = request.host;
+                tail(host_start)
+                    || (host_start..host_end)
+                        .filter(|&index| address[index] == b'.')
+                        .any(|index| tail(index + 1))
+            }
+            Anchor::None => match self.parts.first() {
+                Some(Part::Literal(literal)) => {
+                    let text = if self.match_case {
+                        request.original
+                    } else {
+                        request.lower
+                    };
+                    text.match_indices(literal.as_str())
+                        .any(|(start, _)| tail(start))
+                }
+                _ => (0..=address.len()).any(tail),
+            },
+        }
+    }
+
+    /// Whether the `$domain=` option lets the filter apply on pages of
+    /// `host`. The most specific entry covering the host decides.
+    fn applies_on(&self, host: Option<&str>) -> bool {
+        let decided = host.and_then(|host| {
+            self.domains
+                .iter()
+                .filter(|(domain, _)| {
+                    host == domain
+                        || host
+                            .strip_suffix(domain.as_str())
+                            .is_some_and(|prefix| prefix.ends_with('.'))
+                })
+                .max_by_key(|(domain, _)| domain.len())
+        });
+        match decided {
+            Some((_, included)) => *included,
+            None => !self.domains.iter().any(|(_, included)| *included),
+        }
+    }
+}
+
+fn is_separator(byte: u8) -> bool {
+    !(byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.' | b'%'))
+}
+
+fn match_parts(parts: &[Part], address: &[u8], at: usize, end_anchor: bool) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return !end_anchor || at == address.len();
+    };
+    match part {
+        Part::Literal(literal) => {
+            address[at..].starts_with(literal.as_bytes())
+                && match_parts(rest, address, at + literal.len(), end_anchor)
+        }
+        Part::Separator if at == address.len() => match_parts(rest, address, at, end_anchor),
+        Part::Separator => {
+            is_separator(address[at]) && match_parts(rest, address, at + 1, end_anchor)
+        }
+        Part::Wildcard => (at..=address.len()).any(|at| match_parts(rest, address, at, end_anchor)),
+    }
+}
+
+/// Request as the filters see it.
+struct Request<'a> {
+    original: &'a str,
+    lower: &'a str,
+    /// Byte range of the host in the address.
+    host: (usize, usize),
+    kind: u16,
+    page_host: Option<&'a str>,
+    /// `None` when there is no page to compare with.
+    third_party: Option<bool>,
+}
+
+/// Registrable part of `host`, close enough to tell first-party requests
+/// from third-party ones: `news.example.co.uk` gives `example.co.uk`.
+pub(crate) fn site_of(host: &str) -> &str {
+    if host.trim_matches(['[', ']']).parse::<IpAddr>().is_ok() {
+        return host;
+    }
+    let labels: Vec<&str> = host.rsplitn(4, '.').collect();
+    let keep = match labels.as_slice() {
+        [tld, second, _, ..]
+            if tld.len() == 2
+                && matches!(*second, "co" | "com" | "org" | "net" | "ac" | "gov" | "edu") =>
+        {
+            3
+        }
+        _ => 2,
+    };
+    let suffix_len: usize = labels.iter().take(keep).map(|label| label.len() + 1).sum();
+    &host[host.len().saturating_sub(suffix_len - 1)..]
+}
+
+/// Filters of one kind, indexed by a token each is sure to need.
+#[derive(Default)]
+struct FilterIndex {
+    by_token: HashMap<String, Vec<(usize, usize)>>,
+    /// Filters with no usable token, tried for every request.
+    untokened: Vec<(usize, usize)>,
+}
+
+impl FilterIndex {
+    fn insert(&mut self, filter: &Filter, at: (usize, usize)) {
+        match filter.index_token() {
+            Some(token) => self.by_token.entry(token).or_default().push(at),
+            None => self.untokened.push(at),
+        }
+    }
+}
+
+struct CompiledList {
+    list: FilterList,
+    filters: Vec<Filter>,
+    skipped: usize,
+}
+
+impl CompiledList {
+    fn new(list: FilterList) -> Self {
+        let mut filters = Vec::new();
+        let mut skipped = 0;
+        for line in list.text.lines() {
+            match parse_line(line) {
+                Line::Filter(filter) => filters.push(filter),
+                Line::Ignored => {}
+                Line::Unsupported => skipped += 1,
+            }
+        }
+        CompiledList {
+            list,
+            filters,
+            skipped,
+        }
+    }
+
+    fn info(&self) -> FilterListInfo {
+        FilterListInfo {
+            name: self.list.name.clone(),
+            source: self.list.source.clone(),
+            filters: self.filters.len(),
+            skipped: self.skipped,
+        }
+    }
+}
+
+/// Content blocker: filter lists compiled into an index of their request
+/// filters, consulted before requests pages make reach the network.
+#[derive(Default)]
+pub(crate) struct Shield {
+    paused: bool,
+    lists: Vec<CompiledList>,
+    blocks: FilterIndex,
+    exceptions: FilterIndex,
+    /// Requests blocked for each tab since its page last changed. Locked on
+    /// its own so requests can be counted while the state is only read.
+    blocked: Mutex<HashMap<TabId, usize>>,
+}
+
+impl Shield {
+    fn rebuild_index(&mut self) {
+        self.blocks = FilterIndex::default();
+        self.exceptions = FilterIndex::default();
+        for (list_index, compiled) in self.lists.iter().enumerate() {
+            for (filter_index, filter) in compiled.filters.iter().enumerate() {
+                let index = if filter.exception {
+                    &mut self.exceptions
+                } else {
+                    &mut self.blocks
+                };
+                index.insert(filter, (list_index, filter_index));
+            }
+        }
+    }
+
+    /// First filter in `index` that matches `request` and passes `accept`.
+    fn find(
+        &self,
+        index: &FilterIndex,
+        request: &Request,
+        accept: impl Fn(&Filter) -> bool,
+    ) -> Option<(&Filter, &str)> {
+        let candidates = tokens(request.lower)
+            .filter_map(|(_, token)| index.by_token.get(token))
+            .flatten()
+            .chain(&index.untokened);
+        for &(list_index, filter_index) in candidates {
+            let compiled = &self.lists[list_index];
+            let filter = &compiled.filters[filter_index];
+            if accept(filter) && filter.matches(request) {
+                return Some((filter, &compiled.list.name));
+            }
+        }
+        None
+    }
+
+    /// Why a `kind` request for `url` made by a page at `page` is blocked,
+    /// or `None` when it may go ahead.
+    fn check(&self, url: &Url, kind: u16, page: Option<&Url>) -> Option<String> {
+        if self.paused || self.lists.is_empty() {
+            return None;
+        }
+        let lower = url.as_str().to_ascii_lowercase();
+        let request = Request {
+            original: url.as_str(),
+            lower: &lower,
+            host: (
+                url[..Position::BeforeHost].len(),
+                url[..Position::AfterHost].len(),
+            ),
+            kind,
+            page_host: page.and_then(Url::host_str),
+            third_party: page.and_then(Url::host_str).and_then(|page_host| {
+                let host = url.host_str()?;
+                Some(site_of(page_host) != site_of(host))
+            }),
+        };
+        // `@@||site^$document` turns blocking off for the whole page.
+        let page_lower;
+        if let Some(page) = page.filter(|page| kind != DOCUMENT || *page != url) {
+            page_lower = page.as_str().to_ascii_lowercase();
+            let page_request = Request {
+                original: page.as_str(),
+                lower: &page_lower,
+                host: (
+                    page[..Position::BeforeHost].len(),
+                    page[..Position::AfterHost].len(),
+                ),
+                kind: DOCUMENT,
+                page_host: page.host_str(),
+                third_party: Some(false),
+            };
+            if self
+                .find(&self.exceptions, &page_request, |filter| {
+                    filter.types & DOCUMENT != 0
+                })
+                .is_some()
+            {
+                return None;
+            }
+        }
+
+        let (filter, list) = self.find(&self.blocks, &request, |_| true)?;
+        if !filter.important {
+            if let Some((exception, _)) = self.find(&self.exceptions, &request, |_| true) {
+                debug!("{url} allowed by filter {}", exception.text);
+                return None;
+            }
+        }
+        Some(format!("blocked by filter {} from {list}", filter.text))
+    }
+
+    pub(crate) fn reset_blocked(&self, tab: TabId) {
+        self.blocked.lock().remove(&tab);
+    }
+}
+
+/// Request type filters know a request of `kind` as, for the requests a
+/// page makes. The browser's own requests are never filtered.
+fn request_type(kind: RequestKind) -> Option<u16> {
+    match kind {
+        RequestKind::Document | RequestKind::Preload | RequestKind::Prefetch => Some(DOCUMENT),
+        RequestKind::Image => Some(IMAGE),
+        RequestKind::Stylesheet => Some(STYLESHEET),
+        RequestKind::Script => Some(SCRIPT),
+        RequestKind::WebSocket => Some(WEBSOCKET),
+        RequestKind::EventStream => Some(OTHER),
+        _ => None,
+    }
+}
+
+/// Runs a `kind` request for `url` made by `tab` through the interceptor
+/// chain, then the content blocker.
+pub(crate) fn intercept_request(
+    state: &BrowserState,
+    url: &Url,
+    kind: RequestKind,
+    tab: Option<TabId>,
+) -> Interception {
+    let interception = intercept(state, url);
+    if !matches!(interception, Interception::Continue) {
+        return interception;
+    }
+    let Some(request_type) = request_type(kind) else {
+        return interception;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return interception;
+    }
+    let page = if request_type == DOCUMENT {
+        Some(url)
+    } else {
+        tab.and_then(|tab| state.tabs.iter().find(|snapshot| snapshot.id == tab))
+            .and_then(|snapshot| snapshot.url.as_ref())
+    };
+    let Some(reason) = state.shield.check(url, request_type, page) else {
+        return interception;
+    };
+    debug!("{url} {reason}");
+    if let Some(tab) = tab {
+        *state.shield.blocked.lock().entry(tab).or_default() += 1;
+    }
+    Interception::Block(reason)
+}
+
+/// Name a list gives itself in its `! Title:` header.
+fn list_title(text: &str) -> Option<String> {
+    text.lines()
+        .take_while(|line| line.starts_with('!') || line.starts_with('[') || line.is_empty())
+        .find_map(|line| line.strip_prefix('!')?.trim().strip_prefix("Title:"))
+        .map(|title| title.trim().to_owned())
+        .filter(|title| !title.is_empty())
+}
+
+impl BrowserCore {
+    /// Starts applying `list`, replacing any list of the same name.
+    pub fn add_filter_list(&self, list: FilterList) -> FilterListInfo {
+        let compiled = CompiledList::new(list);
+        let info = compiled.info();
+        let mut guard = self.state.write();
+        let shield = &mut guard.shield;
+        match shield
+            .lists
+            .iter_mut()
+            .find(|existing| existing.list.name == info.name)
+        {
+            Some(existing) => *existing = compiled,
+            None => shield.lists.push(compiled),
+        }
+        shield.rebuild_index();
+        info
+    }
+
+    /// Fetches the filter list at `url` and adds it under the title it
+    /// gives itself.
+    pub async fn subscribe_filter_list(&self, url: &Url) -> Result<FilterListInfo, BrowserError> {
+        let builder = self.client_for(url, false).get(url.clone());
+        let response = self
+            .send_logged(builder, RequestKind::FilterList, None)
+            .await?;
+        if !response.status.is_success() {
+            return Err(BrowserError::Status(response.status.as_u16()));
+        }
+        let text = String::from_utf8_lossy(&response.body).into_owned();
+        let list = FilterList {
+            name: list_title(&text).unwrap_or_else(|| url.to_string()),
+            source: Some(url.clone()),
+            text,
+        };
+        let compiled = CompiledList::new(list);
+        if compiled.filters.is_empty() {
+            return Err(BrowserError::InvalidRequest(format!(
+                "{url} has no request filters"
+            )));
+        }
+        Ok(self.add_filter_list(compiled.list))
+    }
+
+    pub fn remove_filter_list(&self, name: &str) {
+        let mut guard = self.state.write();
+        guard
+            .shield
+            .lists
+            .retain(|compiled| compiled.list.name != name);
+        guard.shield.rebuild_index();
+    }
+
+    /// Lists in use, in the order they were added.
+    pub fn filter_lists(&self) -> Vec<FilterListInfo> {
+        self.state
+            .read()
+            .shield
+            .lists
+            .iter()
+            .map(CompiledList::info)
+            .collect()
+    }
+
+    pub fn export_filter_lists(&self) -> Vec<FilterList> {
+        self.state
+            .read()
+            .shield
+            .lists
+            .iter()
+            .map(|compiled| compiled.list.clone())
+            .collect()
+    }
+
+    /// Replaces the lists in use, e.g. with ones saved by an earlier session.
+    pub fn import_filter_lists(&self, lists: Vec<FilterList>) {
+        let compiled: Vec<CompiledList> = lists.into_iter().map(CompiledList::new).collect();
+        let mut guard = self.state.write();
+        guard.shield.lists = compiled;
+        guard.shield.rebuild_index();
+    }
+
+    /// Turns the content blocker on or off; lists stay in place while off.
+    pub fn set_content_blocking(&self, enabled: bool) {
+        self.state.write().shield.paused = !enabled;
+    }
+
+    pub fn content_blocking(&self) -> bool {
+        !self.state.read().shield.paused
+    }
+
+    /// Requests blocked for `tab` since it last navigated.
+    pub fn blocked_requests(&self, tab: TabId) -> usize {
+        self.state
+            .read()
+            .shield
+            .blocked
+            .lock()
+            .get(&tab)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shield(text: &str) -> Shield {
+        let mut shield = Shield::default();
+        shield.lists.push(CompiledList::new(FilterList {
+            name: "test".to_owned(),
+            source: None,
+            text: text.to_owned(),
+        }));
+        shield.rebuild_index();
+        shield
+    }
+
+    fn url(address: &str) -> Url {
+        Url::parse(address).unwrap()
+    }
+
+    /// Whether `shield` blocks a `kind` request for `address` made by the
+    /// page at `page`.
+    fn blocks(shield: &Shield, address: &str, kind: u16, page: &str) -> bool {
+        shield
+            .check(&url(address), kind, Some(&url(page)))
+            .is_some()
+    }
+
+    const PAGE: &str = "https://news.example/article";
+
+    #[test]
+    fn host_anchors_match_the_host_and_its_subdomains() {
+        let shield = shield("||ads.test^");
+        assert!(blocks(&shield, "https://ads.test/banner.js", SCRIPT, PAGE));
+        assert!(blocks(&shield, "https://cdn.ads.test/x.png", IMAGE, PAGE));
+        assert!(!blocks(&shield, "https://badads.test/x.png", IMAGE, PAGE));
+        assert!(!blocks(&shield, "https://ads.test.example/x", IMAGE, PAGE));
+        // Without options the whole site is off limits, pages included.
+        assert!(blocks(
+            &shield,
+            "https://ads.test/",
+            DOCUMENT,
+            "https://ads.test/"
+        ));
+    }
+
+    #[test]
+    fn wildcards_separators_and_anchors() {
+        let shield = shield("/banner/*/img^\n|https://track.\n.gif|\nswf$~third-party");
+        assert!(blocks(
+            &shield,
+            "https://a.example/banner/big/img?x=1",
+            IMAGE,
+            PAGE
+        ));
+        assert!(blocks(
+            &shield,
+            "https://a.example/banner/big/img",
+            IMAGE,
+            PAGE
+        ));
+        assert!(!blocks(
+            &shield,
+            "https://a.example/banner/img",
+            IMAGE,
+            PAGE
+        ));
+        assert!(blocks(&shield, "https://track.example/p", PING, PAGE));
+        assert!(!blocks(
+            &shield,
+            "https://a.example/?u=https://track.x",
+            PING,
+            PAGE
+        ));
+        assert!(blocks(&shield, "https://a.example/pixel.gif", IMAGE, PAGE));
+        assert!(!blocks(
+            &shield,
+            "https://a.example/pixel.gif?x",
+            IMAGE,
+            PAGE
+        ));
+        assert!(blocks(
+            &shield,
+            "https://cdn.news.example/a.swf",
+            OBJECT,
+            PAGE
+        ));
+        assert!(!blocks(
+            &shield,
+            "https://cdn.other.example/a.swf",
+            OBJECT,
+            PAGE
+        ));
+    }
+
+    #[test]
+    fn case_is_ignored_unless_asked_for() {
+        let shield = shield("/Track.js\n/Beacon.js$match-case");
+        assert!(blocks(&shield, "https://a.example/TRACK.JS", SCRIPT, PAGE));
+        assert!(blocks(&shield, "https://a.example/Beacon.js", SCRIPT, PAGE));
+        assert!(!blocks(
+            &shield,
+            "https://a.example/beacon.js",
+            SCRIPT,
+            PAGE
+        ));
+    }
+
+    #[test]
+    fn type_and_domain_options_narrow_filters() {
+        let shield = shield(
+            "||cdn.test^$script\n\
+             ||media.test^$~image\n\
+             ||widgets.test^$domain=news.example|~sports.news.example",
+        );
+        assert!(blocks(&shield, "https://cdn.test/a.js", SCRIPT, PAGE));
+        assert!(!blocks(&shield, "https://cdn.test/a.png", IMAGE, PAGE));
+        assert!(blocks(&shield, "https://media.test/a.mp4", MEDIA, PAGE));
+        assert!(!blocks(&shield, "https://media.test/a.png", IMAGE, PAGE));
+        assert!(blocks(&shield, "https://widgets.test/w.js", SCRIPT, PAGE));
+        let sports = "https://sports.news.example/";
+        assert!(!blocks(
+            &shield,
+            "https://widgets.test/w.js",
+            SCRIPT,
+            sports
+        ));
+        assert!(!blocks(
+            &shield,
+            "https://widgets.test/w.js",
+            SCRIPT,
+            "https://other.example/"
+        ));
+    }
+
+    #[test]
+    fn exceptions_and_important_filters() {
+        let shield = shield(
+            "||ads.test^\n\
+             @@||ads.test/allowed/\n\
+             ||tracker.test^$important\n\
+             @@||tracker.test^\n\
+             @@||trusted.example^$document",
+        );
+        assert!(!blocks(
+            &shield,
+            "https://ads.test/allowed/a.js",
+            SCRIPT,
+            PAGE
+        ));
+        assert!(blocks(&shield, "https://ads.test/other/a.js", SCRIPT, PAGE));
+        assert!(blocks(&shield, "https://tracker.test/t.js", SCRIPT, PAGE));
+        // Blocking is off on pages the list trusts.
+        let trusted = "https://trusted.example/";
+        assert!(!blocks(&shield, "https://ads.test/a.js", SCRIPT, trusted));
+    }
+
+    #[test]
+    fn first_and_third_party_are_told_apart_by_site() {
+        let shield = shield("||stats.news.example^$third-party");
+        let stats = "https://stats.news.example/s.js";
+        assert!(!blocks(&shield, stats, SCRIPT, PAGE));
+        assert!(blocks(&shield, stats, SCRIPT, "https://other.example/"));
+        assert_eq!(site_of("news.example.co.uk"), "example.co.uk");
+        assert_eq!(site_of("a.b.example.com"), "example.com");
+        assert_eq!(site_of("127.0.0.1"), "127.0.0.1");
+        assert_eq!(site_of("localhost"), "localhost");
+    }
+
+    #[test]
+    fn skips_unsupported_and_malformed_lines() {
+        let list = CompiledList::new(FilterList {
+            name: "test".to_owned(),
+            source: None,
+            text: "[Adblock Plus 2.0]\n\
+                   ! Title: Test\n\
+                   \n\
+                   example.com##.ad\n\
+                   /ads[0-9]+/\n\
+                   ||ads.test^$unknown-option\n\
+                   ||ads.test^$script,~script\n\
+                   ||ads.test^$~domain=example.com\n\
+                   ||good.test^"
+                .to_owned(),
+        });
+        let info = list.info();
+        assert_eq!(info.filters, 1);
+        assert_eq!(info.skipped, 5);
+    }
+
+    #[test]
+    fn truncated_filters_do_not_panic() {
+        for text in [
+            "|",
+            "||",
+            "@@",
+            "$",
+            "^",
+            "*",
+            "|*|",
+            "@@||$",
+            "||a$domain=",
+            "a|",
+        ] {
+            let shield = shield(text);
+            for address in ["https://a.example/", "https://a/", "http://[::1]/"] {
+                let _ = shield.check(&url(address), SCRIPT, Some(&url(PAGE)));
+                let _ = shield.check(&url(address), DOCUMENT, None);
+            }
+        }
+    }
+
+    #[test]
+    fn paused_blocker_lets_everything_through() {
+        let mut shield = shield("||ads.test^");
+        shield.paused = true;
+        assert!(!blocks(&shield, "https://ads.test/a.js", SCRIPT, PAGE));
+    }
+}
@@ -0,0 +1,234 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::PageResponse;
+
+/// Crawler-facing file served by a site, parsed for display.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SiteFile {
+    Robots(RobotsTxt),
+    Sitemap(Sitemap),
+}
+
+/// Recognises `robots.txt` by its path and sitemaps by their root element.
+pub fn parse_site_file(page: &PageResponse) -> Option<SiteFile> {
+    if page.url.path() == "/robots.txt" {
+        return Some(SiteFile::Robots(RobotsTxt::parse(&page.body, &page.url)));
+    }
+    let is_html = page
+        .mime_type
+        .as_deref()
+        .is_some_and(|mime| mime.starts_with("text/html"));
+    if is_html {
+        return None;
+    }
+    Sitemap::parse(&page.body, &page.url).map(SiteFile::Sitemap)
+}
+
+/// Rules of a `robots.txt` file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RobotsTxt {
+    pub groups: Vec<RobotsGroup>,
+    /// Sitemaps announced with `Sitemap:` lines.
+    pub sitemaps: Vec<Url>,
+    /// Non-empty lines that are neither comments nor known fields.
+    pub unknown_lines: Vec<String>,
+}
+
+/// Rules that apply to the user agents listed together.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RobotsGroup {
+    pub user_agents: Vec<String>,
+    pub rules: Vec<RobotsRule>,
+    pub crawl_delay: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RobotsRule {
+    pub allow: bool,
+    /// Path pattern; `*` matches any run of characters and a trailing `$`
+    /// anchors the end.
+    pub path: String,
+}
+
+impl RobotsTxt {
+    pub fn parse(text: &str, base: &Url) -> Self {
+        let mut robots = RobotsTxt::default();
+        // Consecutive user-agent lines open one group; a rule ends the run.
+        let mut collecting_agents = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((field, value)) = line.split_once(':') else {
+                robots.unknown_lines.push(line.to_owned());
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !collecting_agents || robots.groups.is_empty() {
+                        robots.groups.push(RobotsGroup::default());
+                    }
+                    collecting_agents = true;
+                    if let Some(group) = robots.groups.last_mut() {
+                        group.user_agents.push(value.to_owned());
+                    }
+                }
+                field @ ("allow" | "disallow") => {
+                    collecting_agents = false;
+                    // An empty `Disallow:` allows everything and adds no rule.
+                    if let (Some(group), false) = (robots.groups.last_mut(), value.is_empty()) {
+                        group.rules.push(RobotsRule {
+                            allow: field == "allow",
+                            path: value.to_owned(),
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    collecting_agents = false;
+                    if let Some(group) = robots.groups.last_mut() {
+                        group.crawl_delay = value.parse().ok();
+                    }
+                }
+                "sitemap" => match base.join(value) {
+                    Ok(url) => robots.sitemaps.push(url),
+                    Err(_) => robots.unknown_lines.push(line.to_owned()),
+                },
+                _ => robots.unknown_lines.push(line.to_owned()),
+            }
+        }
+        robots
+    }
+
+    /// The group that applies to `user_agent`: the one naming the longest
+    /// matching product token, falling back to `*`.
+    pub fn group_for(&self, user_agent: &str) -> Option<&RobotsGroup> {
+        let user_agent = user_agent.to_ascii_lowercase();
+        self.groups
+            .iter()
+            .filter_map(|group| {
+                group
+                    .user_agents
+                    .iter()
+                    .map(|agent| agent.to_ascii_lowercase())
+                    .filter(|agent| agent != "*" && user_agent.contains(agent.as_str()))
+                    .map(|agent| agent.len())
+                    .max()
+                    .map(|length| (length, group))
+            })
+            .max_by_key(|(length, _)| *length)
+            .map(|(_, group)| group)
+            .or_else(|| {
+                self.groups
+                    .iter()
+                    .find(|group| group.user_agents.iter().any(|agent| agent == "*"))
+            })
+    }
+
+    /// Whether `user_agent` may fetch `path` (including any query). The
+    /// longest matching rule wins, and `Allow` wins ties.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        let Some(group) = self.group_for(user_agent) else {
+            return true;
+        };
+        group
+            .rules
+            .iter()
+            .filter(|rule| path_matches(&rule.path, path))
+            .max_by_key(|rule| (rule.path.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        let last = index + 1 == parts.len();
+        if last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(found) => rest = &rest[found + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SitemapKind {
+    /// A `<urlset>` listing pages.
+    Pages,
+    /// A `<sitemapindex>` listing further sitemaps.
+    Index,
+}
+
+/// Entries of an XML sitemap or sitemap index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sitemap {
+    pub kind: SitemapKind,
+    pub entries: Vec<SitemapEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SitemapEntry {
+    pub loc: Url,
+    pub lastmod: Option<String>,
+    pub changefreq: Option<String>,
+    pub priority: Option<String>,
+}
+
+impl Sitemap {
+    /// Parses `xml` when its root is `<urlset>` or `<sitemapindex>`.
+    pub fn parse(xml: &str, base: &Url) -> Option<Self> {
+        let head = xml
+            .chars()
+            .take(1024)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let (kind, entry) = if head.contains("<urlset") {
+            (SitemapKind::Pages, "url")
+        } else if head.contains("<sitemapindex") {
+            (SitemapKind::Index, "sitemap")
+        } else {
+            return None;
+        };
+
+        let document = Html::parse_document(xml);
+        let entries = Selector::parse(entry).ok()?;
+        let entries = document
+            .select(&entries)
+            .filter_map(|element| {
+                let loc = child_text(element, "loc")?;
+                Some(SitemapEntry {
+                    loc: base.join(&loc).ok()?,
+                    lastmod: child_text(element, "lastmod"),
+                    changefreq: child_text(element, "changefreq"),
+                    priority: child_text(element, "priority"),
+                })
+            })
+            .collect();
+        Some(Sitemap { kind, entries })
+    }
+}
+
+fn child_text(element: ElementRef, name: &str) -> Option<String> {
+    let child = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == name)?;
+    let text = child.text().collect::<String>().trim().to_owned();
+    (!text.is_empty()).then_some(text)
+}
@@ -0,0 +1,996 @@
+use std::collections::{HashMap, HashSet};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::SourceElement;
+
+/// Properties a child takes from its parent when it declares nothing itself.
+const INHERITED_PROPERTIES: [&str; 18] = [
+    "color",
+    "cursor",
+    "direction",
+    "font",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "letter-spacing",
+    "line-height",
+    "list-style",
+    "list-style-type",
+    "text-align",
+    "text-indent",
+    "text-transform",
+    "visibility",
+    "white-space",
+];
+
+/// Elements displayed as blocks by the default stylesheet.
+const BLOCK_ELEMENTS: [&str; 32] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "ul",
+];
+
+/// Elements hidden by the default stylesheet.
+const HIDDEN_ELEMENTS: [&str; 9] = [
+    "head", "link", "meta", "noscript", "script", "style", "template", "title", "base",
+];
+
+const BORDER_STYLES: [&str; 10] = [
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+/// Fewest elements matched per parallel task; fewer are not worth the
+/// hand-off between threads.
+const MIN_ELEMENTS_PER_TASK: usize = 64;
+
+/// Where a declaration was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RuleSource {
+    /// The element's own `style` attribute.
+    Inline,
+    /// A rule of the page's stylesheet, by position.
+    Rule(usize),
+}
+
+/// Identifies one declaration so the inspector can switch it off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeclarationId {
+    pub source: RuleSource,
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Declaration {
+    pub property: String,
+    pub value: String,
+    pub important: bool,
+}
+
+/// A rule that applies to the inspected element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchedRule {
+    pub source: RuleSource,
+    /// Selector text as written, or `style` for inline declarations.
+    pub selector: String,
+    pub declarations: Vec<Declaration>,
+}
+
+/// Why a computed property has its value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PropertyOrigin {
+    /// Declared by the rule with this selector, or `style` when inline.
+    Declared(String),
+    /// Taken over from the ancestor with this label.
+    Inherited(String),
+    /// Default for the element.
+    Initial,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComputedProperty {
+    pub name: String,
+    pub value: String,
+    pub origin: PropertyOrigin,
+}
+
+/// Edge values of the CSS box model, in top, right, bottom, left order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoxModel {
+    pub margin: [String; 4],
+    pub border: [String; 4],
+    pub padding: [String; 4],
+    pub width: String,
+    pub height: String,
+}
+
+/// Result of cascading the page's styles onto one element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComputedStyle {
+    /// Matching rules, highest precedence first.
+    pub rules: Vec<MatchedRule>,
+    /// Winning value of every declared, inherited or defaulted property,
+    /// sorted by name.
+    pub properties: Vec<ComputedProperty>,
+    pub box_model: BoxModel,
+}
+
+impl ComputedStyle {
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+            .map(|property| property.value.as_str())
+    }
+}
+
+/// Color scheme a page is asked to prefer through `prefers-color-scheme`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    fn keyword(self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+}
+
+/// The medium styles are cascaded for, as seen by `@media` queries.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MediaFeatures {
+    /// Print media instead of a screen.
+    pub print: bool,
+    pub color_scheme: ColorScheme,
+    /// Viewport size in CSS pixels.
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for MediaFeatures {
+    fn default() -> Self {
+        Self {
+            print: false,
+            color_scheme: ColorScheme::Light,
+            width: 1280.0,
+            height: 800.0,
+        }
+    }
+}
+
+impl MediaFeatures {
+    /// Whether a media query list such as `screen and (max-width: 600px),
+    /// print` applies. Queries using unknown features never match.
+    pub fn matches(&self, queries: &str) -> bool {
+        let queries = queries.to_ascii_lowercase();
+        if queries.trim().is_empty() {
+            return true;
+        }
+        queries.split(',').any(|query| {
+            let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+            let (negated, query) = match query.strip_prefix("not ") {
+                Some(rest) => (true, rest),
+                None => (false, query.strip_prefix("only ").unwrap_or(&query)),
+            };
+            let matched = query
+                .split(" and ")
+                .all(|condition| self.matches_condition(condition.trim()));
+            matched != negated
+        })
+    }
+
+    fn matches_condition(&self, condition: &str) -> bool {
+        if let Some(feature) = condition
+            .strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return self.matches_feature(feature);
+        }
+        match condition {
+            "all" => true,
+            "screen" => !self.print,
+            "print" => self.print,
+            _ => false,
+        }
+    }
+
+    fn matches_feature(&self, feature: &str) -> bool {
+        let Some((name, value)) = feature.split_once(':') else {
+            return match feature.trim() {
+                "color" => true,
+                "hover" | "pointer" => !self.print,
+                _ => false,
+            };
+        };
+        let (name, value) = (name.trim(), value.trim());
+        match name {
+            "prefers-color-scheme" => value == self.color_scheme.keyword(),
+            "prefers-reduced-motion" | "prefers-contrast" => value == "no-preference",
+            "orientation" => {
+                let portrait = self.height >= self.width;
+                value == if portrait { "portrait" } else { "landscape" }
+            }
+            "hover" | "any-hover" => value == if self.print { "none" } else { "hover" },
+            "pointer" | "any-pointer" => value == if self.print { "none" } else { "fine" },
+            _ => {
+                let (bound, dimension) = match name.split_once('-') {
+                    Some((bound @ ("min" | "max"), dimension)) => (bound, dimension),
+                    _ => ("", name),
+                };
+                let actual = match dimension {
+                    "width" => self.width,
+                    "height" => self.height,
+                    _ => return false,
+                };
+                let Some(length) = css_pixels(value) else {
+                    return false;
+                };
+                match bound {
+                    "min" => actual >= length,
+                    "max" => actual <= length,
+                    _ => actual == length,
+                }
+            }
+        }
+    }
+}
+
+/// The `<style>` rules embedded in a page.
+#[derive(Debug, Clone, Default)]
+pub struct StyleSheet {
+    rules: Vec<StyleRule>,
+    /// Links to stylesheets that were not fetched, as written.
+    pub external: Vec<String>,
+    /// At-rules and selectors the matcher does not understand, and media
+    /// blocks that do not apply, which are left out of the cascade.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone)]
+struct StyleRule {
+    text: String,
+    selector: Selector,
+    declarations: Vec<Declaration>,
+}
+
+/// A complex selector stored right to left: the subject comes first, and each
+/// step carries the combinator linking it to the step after it.
+#[derive(Debug, Clone)]
+struct Selector {
+    steps: Vec<(Compound, Combinator)>,
+    specificity: (u32, u32, u32),
+    /// Hashes of the tags, IDs, classes and attribute names the subject's
+    /// ancestors must have, checked against an [`AncestorFilter`].
+    ancestor_hashes: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl StyleSheet {
+    /// Collects the rules of every `<style>` element in `source` as they
+    /// apply to a default screen.
+    pub fn from_page(source: &str, elements: &[SourceElement]) -> Self {
+        Self::for_media(source, elements, &MediaFeatures::default())
+    }
+
+    /// Collects the rules of every `<style>` element in `source`, keeping
+    /// only the `@media` blocks and `media` attributes that match `media`.
+    pub fn for_media(source: &str, elements: &[SourceElement], media: &MediaFeatures) -> Self {
+        Self::for_media_linked(source, elements, media, &HashMap::new())
+    }
+
+    /// Like [`Self::for_media`], with the fetched stylesheets of `linked`,
+    /// keyed by `href` as written, cascaded where their `<link>` stands.
+    pub fn for_media_linked(
+        source: &str,
+        elements: &[SourceElement],
+        media: &MediaFeatures,
+        linked: &HashMap<String, String>,
+    ) -> Self {
+        let mut sheet = StyleSheet::default();
+        for element in elements {
+            match element.tag.as_str() {
+                "style"
+                    if element
+                        .attribute("media")
+                        .is_some_and(|q| !media.matches(q)) =>
+                {
+                    sheet.skipped += 1;
+                }
+                "style" => {
+                    let css = &source[element.start..element.end];
+                    let body = css
+                        .find('>')
+                        .map(|open| &css[open + 1..])
+                        .unwrap_or_default();
+                    let body = body
+                        .to_ascii_lowercase()
+                        .rfind("</style")
+                        .map_or(body, |close| &body[..close]);
+                    sheet.parse(body, media);
+                }
+                "link" => {
+                    let is_stylesheet = element.attribute("rel").is_some_and(|rel| {
+                        rel.split_whitespace()
+                            .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+                    });
+                    let Some(href) = element.attribute("href").filter(|_| is_stylesheet) else {
+                        continue;
+                    };
+                    match linked.get(href) {
+                        Some(_)
+                            if element
+                                .attribute("media")
+                                .is_some_and(|q| !media.matches(q)) =>
+                        {
+                            sheet.skipped += 1;
+                        }
+                        Some(css) => sheet.parse(css, media),
+                        None => sheet.external.push(href.to_owned()),
+                    }
+                }
+                _ => {}
+            }
+        }
+        sheet
+    }
+
+    fn parse(&mut self, css: &str, media: &MediaFeatures) {
+        let css = strip_comments(css);
+        let mut rest = css.as_str();
+        while let Some(open) = rest.find('{') {
+            // Statement at-rules such as `@import url(a.css);` end at a semicolon.
+            let prelude = rest[..open].rsplit(';').next().unwrap_or_default().trim();
+            let Some(close) = block_end(&rest[open..]) else {
+                break;
+            };
+            let body = &rest[open + 1..open + close];
+            rest = &rest[open + close + 1..];
+
+            if let Some(query) = prelude.strip_prefix("@media") {
+                if media.matches(query) {
+                    self.parse(body, media);
+                } else {
+                    self.skipped += 1;
+                }
+                continue;
+            }
+            if prelude.starts_with('@') {
+                self.skipped += 1;
+                continue;
+            }
+            let declarations = parse_declarations(body);
+            for text in prelude.split(',').map(str::trim) {
+                match parse_selector(text) {
+                    Some(selector) => self.rules.push(StyleRule {
+                        text: text.to_owned(),
+                        selector,
+                        declarations: declarations.clone(),
+                    }),
+                    None => self.skipped += 1,
+                }
+            }
+        }
+    }
+
+    /// Cascades the stylesheet, the inline style and inherited values onto
+    /// `elements[index]`. Declarations in `disabled` are ignored.
+    pub fn compute(
+        &self,
+        elements: &[SourceElement],
+        index: usize,
+        disabled: &HashSet<DeclarationId>,
+    ) -> ComputedStyle {
+        let matched = self.matching_rules(elements, index, None);
+        let (rules, declared) = self.declare(elements, index, &matched, disabled);
+        let parent = elements[index]
+            .parent
+            .map(|parent| self.compute(elements, parent, disabled));
+        cascade(elements, index, rules, declared, parent.as_ref())
+    }
+
+    /// [`compute`](Self::compute) for every element at once, as laying out
+    /// a whole page needs. Selectors are matched on all cores, skipping
+    /// rules whose ancestors an element cannot have, and each element then
+    /// inherits from its parent's finished style instead of recomputing it.
+    pub fn compute_all(
+        &self,
+        elements: &[SourceElement],
+        disabled: &HashSet<DeclarationId>,
+    ) -> Vec<ComputedStyle> {
+        let filters = ancestor_filters(elements);
+        let declared: Vec<_> = (0..elements.len())
+            .into_par_iter()
+            .with_min_len(MIN_ELEMENTS_PER_TASK)
+            .map(|index| {
+                let matched = self.matching_rules(elements, index, Some(&filters[index]));
+                self.declare(elements, index, &matched, disabled)
+            })
+            .collect();
+        drop(filters);
+
+        // Parents come before their children, so each parent is finished
+        // by the time its children inherit from it.
+        let mut styles: Vec<ComputedStyle> = Vec::with_capacity(elements.len());
+        for (index, (rules, declared)) in declared.into_iter().enumerate() {
+            let parent = elements[index].parent.map(|parent| &styles[parent]);
+            let style = cascade(elements, index, rules, declared, parent);
+            styles.push(style);
+        }
+        styles
+    }
+
+    /// Positions of the rules matching `elements[index]`, in ascending
+    /// precedence. With the element's `ancestors` filter, rules needing
+    /// ancestors it lacks are rejected without walking up the tree.
+    fn matching_rules(
+        &self,
+        elements: &[SourceElement],
+        index: usize,
+        ancestors: Option<&AncestorFilter>,
+    ) -> Vec<usize> {
+        let mut matched: Vec<usize> = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| {
+                ancestors.is_none_or(|filter| {
+                    rule.selector
+                        .ancestor_hashes
+                        .iter()
+                        .all(|&hash| filter.may_contain(hash))
+                })
+            })
+            .filter(|(_, rule)| rule.selector.matches(elements, index))
+            .map(|(position, _)| position)
+            .collect();
+        matched.sort_by_key(|&position| (self.rules[position].selector.specificity, position));
+        matched
+    }
+
+    /// Rules applying to `elements[index]`, in ascending precedence with
+    /// the inline style last, and the properties they declare.
+    fn declare(
+        &self,
+        elements: &[SourceElement],
+        index: usize,
+        matched: &[usize],
+        disabled: &HashSet<DeclarationId>,
+    ) -> (Vec<MatchedRule>, Vec<ComputedProperty>) {
+        let inline = elements[index]
+            .attribute("style")
+            .map(parse_declarations)
+            .unwrap_or_default();
+        let mut rules: Vec<MatchedRule> = matched
+            .iter()
+            .map(|&position| MatchedRule {
+                source: RuleSource::Rule(position),
+                selector: self.rules[position].text.clone(),
+                declarations: self.rules[position].declarations.clone(),
+            })
+            .collect();
+        if !inline.is_empty() {
+            rules.push(MatchedRule {
+                source: RuleSource::Inline,
+                selector: "style".to_owned(),
+                declarations: inline,
+            });
+        }
+
+        // Rules are in ascending precedence here; later writes win, and
+        // important declarations are applied in a second pass on top.
+        let mut declared: Vec<ComputedProperty> = Vec::new();
+        for important in [false, true] {
+            for rule in &rules {
+                for (position, declaration) in rule.declarations.iter().enumerate() {
+                    let id = DeclarationId {
+                        source: rule.source,
+                        index: position,
+                    };
+                    if declaration.important != important || disabled.contains(&id) {
+                        continue;
+                    }
+                    for (name, value) in expand_shorthand(declaration) {
+                        declared.retain(|property| property.name != name);
+                        declared.push(ComputedProperty {
+                            name,
+                            value,
+                            origin: PropertyOrigin::Declared(rule.selector.clone()),
+                        });
+                    }
+                }
+            }
+        }
+        (rules, declared)
+    }
+}
+
+/// Completes the style of `elements[index]` from its `rules` and
+/// `declared` properties, inheriting from the style of its `parent`.
+fn cascade(
+    elements: &[SourceElement],
+    index: usize,
+    mut rules: Vec<MatchedRule>,
+    declared: Vec<ComputedProperty>,
+    parent: Option<&ComputedStyle>,
+) -> ComputedStyle {
+    let element = &elements[index];
+    let mut properties = declared;
+    if let Some((parent, inherited)) = element.parent.zip(parent) {
+        for property in &inherited.properties {
+            let inheritable = INHERITED_PROPERTIES.contains(&property.name.as_str());
+            if !inheritable || properties.iter().any(|own| own.name == property.name) {
+                continue;
+            }
+            let origin = match &property.origin {
+                PropertyOrigin::Declared(_) => PropertyOrigin::Inherited(elements[parent].label()),
+                origin => origin.clone(),
+            };
+            properties.push(ComputedProperty {
+                origin,
+                ..property.clone()
+            });
+        }
+    }
+    if !properties.iter().any(|property| property.name == "display") {
+        properties.push(ComputedProperty {
+            name: "display".to_owned(),
+            value: default_display(&element.tag).to_owned(),
+            origin: PropertyOrigin::Initial,
+        });
+    }
+    properties.sort_by(|a, b| a.name.cmp(&b.name));
+
+    rules.reverse();
+    let value = |name: String, initial: &str| {
+        properties
+            .iter()
+            .find(|property| property.name == name)
+            .map_or_else(|| initial.to_owned(), |property| property.value.clone())
+    };
+    let edges = |prefix: &str, suffix: &str, initial: &str| {
+        SIDES.map(|side| value(format!("{prefix}-{side}{suffix}"), initial))
+    };
+    let has_border = |side: &str| {
+        let style = value(format!("border-{side}-style"), "none");
+        style != "none" && style != "hidden"
+    };
+    let mut border = edges("border", "-width", "medium");
+    for (width, side) in border.iter_mut().zip(SIDES) {
+        if !has_border(side) {
+            *width = "0".to_owned();
+        }
+    }
+    let box_model = BoxModel {
+        margin: edges("margin", "", "0"),
+        border,
+        padding: edges("padding", "", "0"),
+        width: value("width".to_owned(), "auto"),
+        height: value("height".to_owned(), "auto"),
+    };
+
+    ComputedStyle {
+        rules,
+        properties,
+        box_model,
+    }
+}
+
+impl Selector {
+    fn matches(&self, elements: &[SourceElement], index: usize) -> bool {
+        self.matches_from(0, elements, index)
+    }
+
+    fn matches_from(&self, step: usize, elements: &[SourceElement], index: usize) -> bool {
+        let (compound, combinator) = &self.steps[step];
+        if !compound.matches(&elements[index]) {
+            return false;
+        }
+        if step + 1 == self.steps.len() {
+            return true;
+        }
+        let mut ancestor = elements[index].parent;
+        while let Some(candidate) = ancestor {
+            if self.matches_from(step + 1, elements, candidate) {
+                return true;
+            }
+            if *combinator == Combinator::Child {
+                return false;
+            }
+            ancestor = elements[candidate].parent;
+        }
+        false
+    }
+}
+
+impl Compound {
+    fn matches(&self, element: &SourceElement) -> bool {
+        self.tag
+            .as_ref()
+            .is_none_or(|tag| element.tag == tag.as_str())
+            && self.id.as_deref().is_none_or(|id| element.id() == Some(id))
+            && self
+                .classes
+                .iter()
+                .all(|class| element.classes().any(|own| own == class))
+            && self
+                .attributes
+                .iter()
+                .all(|(name, value)| match (element.attribute(name), value) {
+                    (Some(own), Some(value)) => own == value,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                })
+    }
+}
+
+impl Compound {
+    fn for_each_hash(&self, mut f: impl FnMut(u32)) {
+        if let Some(tag) = &self.tag {
+            f(selector_hash(b't', tag));
+        }
+        if let Some(id) = &self.id {
+            f(selector_hash(b'#', id));
+        }
+        for class in &self.classes {
+            f(selector_hash(b'.', class));
+        }
+        for (name, _) in &self.attributes {
+            f(selector_hash(b'[', name));
+        }
+    }
+}
+
+/// Bloom filter of the tags, IDs, classes and attribute names of an
+/// element's ancestors. It can tell that an element has no ancestor a
+/// selector needs, so most descendant selectors are rejected without
+/// walking up the tree; a hit still needs the full match.
+#[derive(Clone, Copy, Default)]
+struct AncestorFilter([u64; 4]);
+
+impl AncestorFilter {
+    fn insert(&mut self, hash: u32) {
+        for bit in filter_bits(hash) {
+            self.0[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    fn may_contain(&self, hash: u32) -> bool {
+        filter_bits(hash)
+            .iter()
+            .all(|bit| self.0[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// The two bits of the 256-bit filter a hash sets.
+fn filter_bits(hash: u32) -> [usize; 2] {
+    [(hash & 0xFF) as usize, ((hash >> 16) & 0xFF) as usize]
+}
+
+/// FNV-1a hash of `name` in the namespace of `kind`, so a class and a tag
+/// of the same name differ.
+fn selector_hash(kind: u8, name: &str) -> u32 {
+    let mut hash: u32 = 0x811C_9DC5;
+    for byte in std::iter::once(kind).chain(name.bytes()) {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// The ancestor filter of every element, built top down from its parent's.
+fn ancestor_filters(elements: &[SourceElement]) -> Vec<AncestorFilter> {
+    let mut filters: Vec<AncestorFilter> = Vec::with_capacity(elements.len());
+    for element in elements {
+        let Some(parent) = element.parent else {
+            filters.push(AncestorFilter::default());
+            continue;
+        };
+        let mut filter = filters[parent];
+        let parent = &elements[parent];
+        filter.insert(selector_hash(b't', &parent.tag));
+        if let Some(id) = parent.id() {
+            filter.insert(selector_hash(b'#', id));
+        }
+        for class in parent.classes() {
+            filter.insert(selector_hash(b'.', class));
+        }
+        for (name, _) in parent.attributes.iter() {
+            filter.insert(selector_hash(b'[', name));
+        }
+        filters.push(filter);
+    }
+    filters
+}
+
+/// Parses the selector subset the matcher supports: type, universal, id,
+/// class and attribute selectors joined by descendant or child combinators.
+fn parse_selector(text: &str) -> Option<Selector> {
+    let spaced = text.replace('>', " > ");
+    let mut steps: Vec<(Compound, Combinator)> = Vec::new();
+    let mut combinator = Combinator::Descendant;
+    let mut specificity = (0, 0, 0);
+    for token in spaced.split_whitespace() {
+        if token == ">" {
+            if steps.is_empty() {
+                return None;
+            }
+            combinator = Combinator::Child;
+            continue;
+        }
+        let compound = parse_compound(token, &mut specificity)?;
+        steps.push((compound, combinator));
+        combinator = Combinator::Descendant;
+    }
+    if steps.is_empty() || combinator == Combinator::Child {
+        return None;
+    }
+
+    steps.reverse();
+    let mut ancestor_hashes: Vec<u32> = Vec::new();
+    for (compound, _) in &steps[1..] {
+        compound.for_each_hash(|hash| {
+            if !ancestor_hashes.contains(&hash) {
+                ancestor_hashes.push(hash);
+            }
+        });
+    }
+    Some(Selector {
+        steps,
+        specificity,
+        ancestor_hashes,
+    })
+}
+
+fn parse_compound(token: &str, specificity: &mut (u32, u32, u32)) -> Option<Compound> {
+    let mut compound = Compound::default();
+    let mut rest = token;
+    let name_len = rest.find(['#', '.', '[']).unwrap_or(rest.len());
+    match &rest[..name_len] {
+        "" | "*" => {}
+        name if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+            compound.tag = Some(name.to_ascii_lowercase());
+            specificity.2 += 1;
+        }
+        _ => return None,
+    }
+    rest = &rest[name_len..];
+
+    while let Some(marker) = rest.chars().next() {
+        rest = &rest[1..];
+        if marker == '[' {
+            let close = rest.find(']')?;
+            let inner = &rest[..close];
+            rest = &rest[close + 1..];
+            let attribute = match inner.split_once('=') {
+                Some((name, value)) => {
+                    let value = value.trim().trim_matches(['"', '\'']);
+                    (name.trim().to_ascii_lowercase(), Some(value.to_owned()))
+                }
+                None => (inner.trim().to_ascii_lowercase(), None),
+            };
+            if !attribute
+                .0
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            {
+                return None;
+            }
+            compound.attributes.push(attribute);
+            specificity.1 += 1;
+            continue;
+        }
+
+        let len = rest.find(['#', '.', '[']).unwrap_or(rest.len());
+        let name = &rest[..len];
+        rest = &rest[len..];
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return None;
+        }
+        match marker {
+            '#' => {
+                compound.id = Some(name.to_owned());
+                specificity.0 += 1;
+            }
+            '.' => {
+                compound.classes.push(name.to_owned());
+                specificity.1 += 1;
+            }
+            _ => return None,
+        }
+    }
+    Some(compound)
+}
+
+fn parse_declarations(body: &str) -> Vec<Declaration> {
+    body.split(';')
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            let property = property.trim().to_ascii_lowercase();
+            let value = value.trim();
+            if property.is_empty() || value.is_empty() {
+                return None;
+            }
+            let (value, important) = match value.to_ascii_lowercase().rfind("!important") {
+                Some(marker) => (value[..marker].trim(), true),
+                None => (value, false),
+            };
+            Some(Declaration {
+                property,
+                value: value.to_owned(),
+                important,
+            })
+        })
+        .collect()
+}
+
+/// Splits the box model shorthands into their per-side longhands; other
+/// declarations are returned unchanged.
+fn expand_shorthand(declaration: &Declaration) -> Vec<(String, String)> {
+    let sides = |prefix: &str, suffix: &str, values: [String; 4]| {
+        SIDES
+            .iter()
+            .zip(values)
+            .map(|(side, value)| (format!("{prefix}-{side}{suffix}"), value))
+            .collect()
+    };
+    match declaration.property.as_str() {
+        "margin" | "padding" => sides(&declaration.property, "", edge_values(&declaration.value)),
+        "border-width" => sides("border", "-width", edge_values(&declaration.value)),
+        "border-style" => sides("border", "-style", edge_values(&declaration.value)),
+        "border-color" => sides("border", "-color", edge_values(&declaration.value)),
+        "border" => {
+            let mut expanded = Vec::new();
+            for token in declaration.value.split_whitespace() {
+                let part = if BORDER_STYLES.contains(&token) {
+                    "style"
+                } else if is_border_width(token) {
+                    "width"
+                } else {
+                    "color"
+                };
+                for side in SIDES {
+                    expanded.push((format!("border-{side}-{part}"), token.to_owned()));
+                }
+            }
+            expanded
+        }
+        _ => vec![(declaration.property.clone(), declaration.value.clone())],
+    }
+}
+
+/// Expands the one to four values of an edge shorthand to all four sides.
+fn edge_values(value: &str) -> [String; 4] {
+    let values: Vec<&str> = value.split_whitespace().collect();
+    let [top, right, bottom, left] = match values.as_slice() {
+        [all] => [all, all, all, all],
+        [vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+        [top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+        [top, right, bottom, left, ..] => [top, right, bottom, left],
+        [] => [&"0"; 4],
+    };
+    [top, right, bottom, left].map(|value| (*value).to_owned())
+}
+
+fn is_border_width(token: &str) -> bool {
+    matches!(token, "thin" | "medium" | "thick")
+        || token.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+/// Converts a `px`, `em` or `rem` length to pixels.
+fn css_pixels(value: &str) -> Option<f32> {
+    let (number, scale) = if let Some(px) = value.strip_suffix("px") {
+        (px, 1.0)
+    } else if let Some(em) = value
+        .strip_suffix("rem")
+        .or_else(|| value.strip_suffix("em"))
+    {
+        (em, 16.0)
+    } else {
+        (value, 1.0)
+    };
+    number
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .map(|length| length * scale)
+}
+
+fn default_display(tag: &str) -> &'static str {
+    match tag {
+        _ if HIDDEN_ELEMENTS.contains(&tag) => "none",
+        _ if BLOCK_ELEMENTS.contains(&tag) => "block",
+        "li" => "list-item",
+        "table" => "table",
+        "tr" => "table-row",
+        "td" | "th" => "table-cell",
+        _ => "inline",
+    }
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Length of the block opening at the start of `text`, up to and excluding
+/// its matching `}`.
+fn block_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
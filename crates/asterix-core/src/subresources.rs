@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, instrument};
+use url::Url;
+
+use crate::network::{self, Timings};
+use crate::{map_elements, BrowserCore, PageResponse, RequestKind, TabId};
+
+/// Most subresources fetched for one page.
+const MAX_SUBRESOURCES: usize = 200;
+
+/// Maximum number of subresource requests in flight at once, as browsers
+/// allow per page.
+const SUBRESOURCE_CONCURRENCY: usize = 6;
+
+/// Kind of resource a page pulls in while it loads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceKind {
+    Image,
+    Stylesheet,
+    Script,
+}
+
+impl ResourceKind {
+    pub const ALL: [Self; 3] = [Self::Image, Self::Stylesheet, Self::Script];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Stylesheet => "stylesheet",
+            Self::Script => "script",
+        }
+    }
+
+    fn request_kind(self) -> RequestKind {
+        match self {
+            Self::Image => RequestKind::Image,
+            Self::Stylesheet => RequestKind::Stylesheet,
+            Self::Script => RequestKind::Script,
+        }
+    }
+
+    fn accept(self) -> &'static str {
+        match self {
+            Self::Image => "image/avif,image/webp,image/png,image/*;q=0.8,*/*;q=0.5",
+            Self::Stylesheet => "text/css,*/*;q=0.1",
+            Self::Script => "*/*",
+        }
+    }
+}
+
+/// Resource an element of a page refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubresourceRef {
+    /// Attribute value as written in the page.
+    pub href: String,
+    /// `href` resolved against the page's base URL.
+    pub url: Url,
+    pub kind: ResourceKind,
+}
+
+/// Outcome of fetching one subresource.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageResource {
+    pub href: String,
+    /// URL requested, after a mixed-content upgrade.
+    pub url: Url,
+    pub kind: ResourceKind,
+    /// `None` when no response arrived.
+    pub status: Option<u16>,
+    pub mime_type: Option<String>,
+    /// Body as decoded; empty for failed requests.
+    pub body: Bytes,
+    #[serde(default)]
+    pub timings: Timings,
+    /// Why the resource could not be fetched or was refused.
+    pub error: Option<String>,
+}
+
+impl PageResource {
+    /// Whether a successful response arrived.
+    pub fn is_loaded(&self) -> bool {
+        self.error.is_none()
+            && self
+                .status
+                .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+/// Subresources fetched for a page, in document order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageResources {
+    pub page: Url,
+    pub resources: Vec<PageResource>,
+}
+
+impl PageResources {
+    pub fn of_kind(&self, kind: ResourceKind) -> impl Iterator<Item = &PageResource> {
+        self.resources
+            .iter()
+            .filter(move |resource| resource.kind == kind)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.resources
+            .iter()
+            .filter(|resource| !resource.is_loaded())
+            .count()
+    }
+
+    /// Decoded size of every body received.
+    pub fn total_size(&self) -> usize {
+        self.resources
+            .iter()
+            .map(|resource| resource.body.len())
+            .sum()
+    }
+
+    /// Text of the stylesheets that loaded, keyed by `href` as written, for
+    /// [`render_with_stylesheets`](crate::render_with_stylesheets).
+    pub fn stylesheets(&self) -> HashMap<String, String> {
+        self.of_kind(ResourceKind::Stylesheet)
+            .filter(|resource| resource.is_loaded())
+            .map(|resource| {
+                let css = String::from_utf8_lossy(&resource.body).into_owned();
+                (resource.href.clone(), css)
+            })
+            .collect()
+    }
+}
+
+/// Images, stylesheets and external scripts an HTML `page` refers to, in
+/// document order, resolved against its `<base href>`. Only HTTP(S)
+/// resources are listed, each once.
+pub fn subresource_refs(page: &PageResponse) -> Vec<SubresourceRef> {
+    let is_html = page
+        .mime_type
+        .as_deref()
+        .is_none_or(|mime| mime.starts_with("text/html"));
+    if !is_html {
+        return Vec::new();
+    }
+    let elements = map_elements(&page.body);
+    let base = elements
+        .iter()
+        .find(|element| element.tag == "base")
+        .and_then(|element| element.attribute("href"))
+        .and_then(|href| page.url.join(href.trim()).ok())
+        .unwrap_or_else(|| page.url.clone());
+    let mut found: Vec<SubresourceRef> = Vec::new();
+    for element in &elements {
+        let reference = match element.tag.as_str() {
+            "img" => element
+                .attribute("src")
+                .map(|src| (src, ResourceKind::Image)),
+            "script" => element
+                .attribute("src")
+                .map(|src| (src, ResourceKind::Script)),
+            "link"
+                if element.attribute("rel").is_some_and(|rel| {
+                    rel.split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+                }) =>
+            {
+                element
+                    .attribute("href")
+                    .map(|href| (href, ResourceKind::Stylesheet))
+            }
+            _ => None,
+        };
+        let Some((href, kind)) = reference.filter(|(href, _)| !href.trim().is_empty()) else {
+            continue;
+        };
+        let Ok(mut url) = base.join(href.trim()) else {
+            continue;
+        };
+        url.set_fragment(None);
+        if !matches!(url.scheme(), "http" | "https") || found.iter().any(|r| r.url == url) {
+            continue;
+        }
+        found.push(SubresourceRef {
+            href: href.to_owned(),
+            url,
+            kind,
+        });
+        if found.len() == MAX_SUBRESOURCES {
+            break;
+        }
+    }
+    found
+}
+
+impl BrowserCore {
+    /// Fetches the images, stylesheets and scripts `page` refers to for
+    /// `tab`, a few at a time. Each request is logged for the tab and goes
+    /// through the mixed-content policy and the content blocker, so a
+    /// refused resource is listed with the reason. Results come back in
+    /// document order.
+    #[instrument(skip(self, page), fields(page = %page.url))]
+    pub async fn fetch_subresources(&self, tab: TabId, page: &PageResponse) -> PageResources {
+        let refs = subresource_refs(page);
+        let options = self.tab_options(tab);
+        let semaphore = Arc::new(Semaphore::new(SUBRESOURCE_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        let mut resources = Vec::with_capacity(refs.len());
+
+        for (index, reference) in refs.into_iter().enumerate() {
+            let SubresourceRef {
+                href,
+                mut url,
+                kind,
+            } = reference;
+            let upgradeable = kind == ResourceKind::Image;
+            if let Err(err) = self.secure_subresource(tab, &mut url, upgradeable) {
+                resources.push((index, failed(href, url, kind, err.to_string())));
+                continue;
+            }
+            let Ok(permit) = Arc::clone(&semaphore).acquire_owned().await else {
+                break;
+            };
+            let client = self.client_for(&url, options.private);
+            let mut builder = client.get(url.clone()).header(ACCEPT, kind.accept());
+            if let Some(ua) = self.user_agent_for(options.user_agent.as_deref(), &url) {
+                builder = builder.header(USER_AGENT, ua);
+            }
+            let state = Arc::clone(&self.state);
+            tasks.spawn(async move {
+                let response =
+                    network::send_logged(&state, builder, kind.request_kind(), Some(tab)).await;
+                drop(permit);
+                let resource = match response {
+                    Ok(response) => PageResource {
+                        href,
+                        url,
+                        kind,
+                        status: Some(response.status.as_u16()),
+                        mime_type: response
+                            .headers
+                            .get(CONTENT_TYPE)
+                            .and_then(|value| value.to_str().ok())
+                            .map(ToOwned::to_owned),
+                        body: response.body,
+                        timings: response.timings,
+                        error: None,
+                    },
+                    Err(err) => failed(href, url, kind, err.to_string()),
+                };
+                (index, resource)
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok(entry) = joined {
+                resources.push(entry);
+            }
+        }
+        resources.sort_by_key(|(index, _)| *index);
+        let resources: Vec<PageResource> = resources
+            .into_iter()
+            .map(|(_, resource)| resource)
+            .collect();
+        debug!(
+            "fetched {} subresources, {} failed",
+            resources.len(),
+            resources.iter().filter(|r| !r.is_loaded()).count()
+        );
+        PageResources {
+            page: page.url.clone(),
+            resources,
+        }
+    }
+}
+
+fn failed(href: String, url: Url, kind: ResourceKind, error: String) -> PageResource {
+    PageResource {
+        href,
+        url,
+        kind,
+        status: None,
+        mime_type: None,
+        body: Bytes::new(),
+        timings: Timings::default(),
+        error: Some(error),
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, BrowserState, PageResponse, TabId};
+
+/// One page in a tab's back/forward list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavigationEntry {
+    pub url: Url,
+    pub title: String,
+    pub visited_at: DateTime<Utc>,
+    /// Vertical scroll position the page was left at, in points.
+    pub scroll_offset: f32,
+}
+
+/// Back/forward list of one tab.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TabHistory {
+    entries: Vec<NavigationEntry>,
+    /// Index of the entry the tab is showing.
+    current: usize,
+    /// URL a back or forward step is loading, so the load moves the cursor
+    /// instead of adding an entry.
+    traversing: Option<Url>,
+}
+
+impl TabHistory {
+    fn current(&self) -> Option<&NavigationEntry> {
+        self.entries.get(self.current)
+    }
+
+    /// Records that the tab now shows `url`, which was loaded for `requested`.
+    fn commit(&mut self, requested: &Url, url: &Url, title: &str, at: DateTime<Utc>) {
+        let traversal = self
+            .traversing
+            .take()
+            .is_some_and(|target| &target == requested);
+        let reload = self.current().is_some_and(|entry| &entry.url == url);
+        if let Some(entry) = self
+            .entries
+            .get_mut(self.current)
+            .filter(|_| traversal || reload)
+        {
+            entry.url = url.clone();
+            entry.title = title.to_owned();
+            entry.visited_at = at;
+            return;
+        }
+        // A new page drops whatever could have been reached with Forward.
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.current + 1);
+        }
+        self.entries.push(NavigationEntry {
+            url: url.clone(),
+            title: title.to_owned(),
+            visited_at: at,
+            scroll_offset: 0.0,
+        });
+        self.current = self.entries.len() - 1;
+    }
+
+    fn step(&mut self, forward: bool) -> Option<NavigationEntry> {
+        let target = if forward {
+            self.current
+                .checked_add(1)
+                .filter(|index| *index < self.entries.len())?
+        } else {
+            self.current.checked_sub(1)?
+        };
+        self.current = target;
+        let entry = self.entries[target].clone();
+        self.traversing = Some(entry.url.clone());
+        Some(entry)
+    }
+}
+
+impl BrowserState {
+    pub(crate) fn commit_navigation(
+        &mut self,
+        tab: TabId,
+        requested: &Url,
+        url: &Url,
+        title: &str,
+        at: DateTime<Utc>,
+    ) {
+        self.tab_history
+            .entry(tab)
+            .or_default()
+            .commit(requested, url, title, at);
+    }
+}
+
+impl BrowserCore {
+    /// Moves `tab` one entry back and returns the page to load, or `None` at
+    /// the start of its history.
+    pub fn go_back(&self, tab: TabId) -> Option<NavigationEntry> {
+        self.state.write().tab_history.get_mut(&tab)?.step(false)
+    }
+
+    /// Moves `tab` one entry forward and returns the page to load, or `None`
+    /// when it is already showing its newest entry.
+    pub fn go_forward(&self, tab: TabId) -> Option<NavigationEntry> {
+        self.state.write().tab_history.get_mut(&tab)?.step(true)
+    }
+
+    /// Shows `page`, kept from an earlier visit to `url`, in `tab` after a
+    /// back or forward step, without fetching it again.
+    pub fn restore_page(&self, tab: TabId, url: &Url, page: &PageResponse) {
+        self.update_tab_after_fetch(tab, url, page);
+    }
+
+    pub fn can_go_back(&self, tab: TabId) -> bool {
+        self.state
+            .read()
+            .tab_history
+            .get(&tab)
+            .is_some_and(|history| history.current > 0)
+    }
+
+    pub fn can_go_forward(&self, tab: TabId) -> bool {
+        self.state
+            .read()
+            .tab_history
+            .get(&tab)
+            .is_some_and(|history| history.current + 1 < history.entries.len())
+    }
+
+    /// Pages visited in `tab`, oldest first, with the index of the one shown.
+    pub fn history_entries(&self, tab: TabId) -> (Vec<NavigationEntry>, usize) {
+        self.state
+            .read()
+            .tab_history
+            .get(&tab)
+            .map(|history| (history.entries.clone(), history.current))
+            .unwrap_or_default()
+    }
+
+    /// Remembers how far the page shown in `tab` is scrolled, so going back
+    /// to it can restore the position.
+    pub fn set_scroll_offset(&self, tab: TabId, offset: f32) {
+        let mut guard = self.state.write();
+        let Some(history) = guard.tab_history.get_mut(&tab) else {
+            return;
+        };
+        let current = history.current;
+        if let Some(entry) = history.entries.get_mut(current) {
+            entry.scroll_offset = offset;
+        }
+    }
+}
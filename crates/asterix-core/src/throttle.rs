@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BrowserCore;
+
+/// Network conditions to emulate, so page loads can be tried on a slow or
+/// missing connection. Limits of 0 leave that side of the exchange alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConditions {
+    /// Fails every request as if the machine had no network.
+    pub offline: bool,
+    /// Added before each request is sent.
+    pub latency_ms: u64,
+    /// Rate response bodies arrive at.
+    pub download_bytes_per_sec: u64,
+    /// Rate request bodies leave at.
+    pub upload_bytes_per_sec: u64,
+}
+
+impl NetworkConditions {
+    /// The network as it is.
+    pub const NONE: Self = Self {
+        offline: false,
+        latency_ms: 0,
+        download_bytes_per_sec: 0,
+        upload_bytes_per_sec: 0,
+    };
+
+    pub const FAST_3G: Self = Self {
+        offline: false,
+        latency_ms: 563,
+        download_bytes_per_sec: 180_000,
+        upload_bytes_per_sec: 84_375,
+    };
+
+    pub const SLOW_3G: Self = Self {
+        offline: false,
+        latency_ms: 2_000,
+        download_bytes_per_sec: 50_000,
+        upload_bytes_per_sec: 50_000,
+    };
+
+    pub const OFFLINE: Self = Self {
+        offline: true,
+        ..Self::NONE
+    };
+
+    /// Named conditions to pick from, the real network first.
+    pub const PRESETS: [(&'static str, Self); 4] = [
+        ("No throttling", Self::NONE),
+        ("Fast 3G", Self::FAST_3G),
+        ("Slow 3G", Self::SLOW_3G),
+        ("Offline", Self::OFFLINE),
+    ];
+
+    /// Whether any emulation is in effect.
+    pub fn is_throttled(&self) -> bool {
+        *self != Self::NONE
+    }
+
+    /// How long before a request carrying `body` bytes reaches the server.
+    pub(crate) fn send_delay(&self, body: usize) -> Duration {
+        Duration::from_millis(self.latency_ms) + transfer_time(body, self.upload_bytes_per_sec)
+    }
+
+    /// How long after the first byte `received` bytes of a response body
+    /// may have arrived.
+    pub(crate) fn receive_time(&self, received: usize) -> Duration {
+        transfer_time(received, self.download_bytes_per_sec)
+    }
+}
+
+fn transfer_time(bytes: usize, bytes_per_sec: u64) -> Duration {
+    if bytes_per_sec == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(bytes as f64 / bytes_per_sec as f64)
+}
+
+impl BrowserCore {
+    /// Emulates `conditions` for every request from now on. Requests
+    /// already running keep the conditions they started under.
+    pub fn set_network_conditions(&self, conditions: NetworkConditions) {
+        self.state.write().network_conditions = conditions;
+    }
+
+    pub fn network_conditions(&self) -> NetworkConditions {
+        self.state.read().network_conditions
+    }
+}
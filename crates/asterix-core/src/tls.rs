@@ -0,0 +1,764 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use parking_lot::Mutex;
+use rustls::client::{
+    ClientSessionMemoryCache, ClientSessionStore, HandshakeSignatureValid, Resumption,
+    ServerCertVerified, ServerCertVerifier, Tls12ClientSessionValue, Tls13ClientSessionValue,
+    WebPkiVerifier,
+};
+use rustls::{
+    Certificate, CertificateError, ClientConfig, DigitallySignedStruct, NamedGroup,
+    OwnedTrustAnchor, RootCertStore, ServerName, SignatureScheme,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use url::Url;
+
+use crate::network::{record_phase, Phase};
+use crate::{BrowserCore, BrowserError, ClientIdentity, HttpVersionPolicy};
+
+/// Sessions remembered for resumption, as in rustls' default.
+const SESSION_CACHE_SIZE: usize = 256;
+
+pub(crate) const SEQUENCE: u8 = 0x30;
+pub(crate) const SET: u8 = 0x31;
+pub(crate) const OBJECT_IDENTIFIER: u8 = 0x06;
+pub(crate) const OCTET_STRING: u8 = 0x04;
+pub(crate) const INTEGER: u8 = 0x02;
+const BOOLEAN: u8 = 0x01;
+const EXPLICIT_VERSION: u8 = 0xa0;
+const EXPLICIT_EXTENSIONS: u8 = 0xa3;
+const SAN_DNS_NAME: u8 = 0x82;
+const SAN_IP_ADDRESS: u8 = 0x87;
+
+/// `2.5.29.17`, the subject alternative name extension.
+const SUBJECT_ALT_NAME: [u8; 3] = [0x55, 0x1d, 0x11];
+
+/// Short names of the attributes commonly found in certificate names.
+const NAME_ATTRIBUTES: [(&[u8], &str); 9] = [
+    (&[0x55, 0x04, 0x03], "CN"),
+    (&[0x55, 0x04, 0x05], "serialNumber"),
+    (&[0x55, 0x04, 0x06], "C"),
+    (&[0x55, 0x04, 0x07], "L"),
+    (&[0x55, 0x04, 0x08], "ST"),
+    (&[0x55, 0x04, 0x0a], "O"),
+    (&[0x55, 0x04, 0x0b], "OU"),
+    (&[0x55, 0x04, 0x61], "organizationIdentifier"),
+    (
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01],
+        "emailAddress",
+    ),
+];
+
+/// Negotiated parameters and certificate chain of the latest TLS connection
+/// to a host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    pub host: String,
+    /// Protocol version such as `TLS 1.3`.
+    pub protocol: Option<String>,
+    /// Known for TLS 1.3 connections whose server issues session tickets.
+    pub cipher_suite: Option<String>,
+    /// Key exchange group, known for TLS 1.3.
+    pub key_exchange: Option<String>,
+    /// Certificates as sent by the server, leaf first.
+    pub certificates: Vec<CertificateInfo>,
+    /// Why the chain failed verification, when it was accepted through a
+    /// certificate exception.
+    #[serde(default)]
+    pub overridden_error: Option<String>,
+    pub established_at: DateTime<Utc>,
+}
+
+/// A server certificate chain that failed verification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertErrorDetails {
+    pub host: String,
+    /// What is wrong with the chain, e.g. that it has expired.
+    pub reason: String,
+    /// Certificates as sent by the server, leaf first.
+    pub certificates: Vec<CertificateInfo>,
+}
+
+/// Fields of an X.509 certificate shown by the certificate viewer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    /// Distinguished name such as `CN=example.com, O=Example, C=US`.
+    pub subject: String,
+    pub issuer: String,
+    /// Serial number as colon-separated hex.
+    pub serial: String,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+    /// DNS names and IP addresses the certificate is valid for.
+    pub subject_alt_names: Vec<String>,
+    /// SHA-256 of the DER encoding as colon-separated hex.
+    pub sha256_fingerprint: String,
+}
+
+impl CertificateInfo {
+    /// Parses a DER-encoded certificate, or `None` when it is malformed.
+    pub fn from_der(der: &[u8]) -> Option<Self> {
+        let certificate = Der(der).expect(SEQUENCE)?;
+        let mut tbs = Der(Der(certificate).expect(SEQUENCE)?);
+        if tbs.peek() == Some(EXPLICIT_VERSION) {
+            tbs.next()?;
+        }
+        // Positive serials get a leading zero byte when their top bit is set.
+        let serial = match tbs.expect(INTEGER)? {
+            [0, rest @ ..] if !rest.is_empty() => hex(rest),
+            serial => hex(serial),
+        };
+        tbs.expect(SEQUENCE)?;
+        let issuer = format_name(tbs.expect(SEQUENCE)?);
+        let mut validity = Der(tbs.expect(SEQUENCE)?);
+        let not_before = validity.next().and_then(parse_time);
+        let not_after = validity.next().and_then(parse_time);
+        let subject = format_name(tbs.expect(SEQUENCE)?);
+        tbs.expect(SEQUENCE)?;
+
+        let mut subject_alt_names = Vec::new();
+        while let Some((tag, value)) = tbs.next() {
+            if tag != EXPLICIT_EXTENSIONS {
+                continue;
+            }
+            let mut extensions = Der(Der(value).expect(SEQUENCE)?);
+            while let Some(extension) = extensions.expect(SEQUENCE) {
+                let mut extension = Der(extension);
+                let oid = extension.expect(OBJECT_IDENTIFIER)?;
+                if extension.peek() == Some(BOOLEAN) {
+                    extension.next()?;
+                }
+                let value = extension.expect(OCTET_STRING)?;
+                if oid == SUBJECT_ALT_NAME {
+                    subject_alt_names = alt_names(value).unwrap_or_default();
+                }
+            }
+        }
+
+        Some(Self {
+            subject,
+            issuer,
+            serial,
+            not_before,
+            not_after,
+            subject_alt_names,
+            sha256_fingerprint: hex(&Sha256::digest(der)),
+        })
+    }
+
+    /// Whether `now` falls inside the validity period.
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        self.not_before.is_none_or(|start| start <= now)
+            && self.not_after.is_none_or(|end| now <= end)
+    }
+
+    /// Common name of the subject, falling back to the whole name.
+    pub fn subject_name(&self) -> &str {
+        self.subject
+            .split(", ")
+            .find_map(|part| part.strip_prefix("CN="))
+            .unwrap_or(&self.subject)
+    }
+}
+
+impl BrowserCore {
+    /// TLS details of the latest connection to the host `tab` shows, or
+    /// `None` when it was not loaded over HTTPS.
+    pub fn connection_info(&self, tab: crate::TabId) -> Option<ConnectionInfo> {
+        let url = self
+            .state
+            .read()
+            .tabs
+            .iter()
+            .find(|snapshot| snapshot.id == tab)
+            .and_then(|snapshot| snapshot.url.clone())?;
+        self.tls.connection(&url)
+    }
+
+    /// Accepts the certificate in `error` for its host until the browser
+    /// exits. A different certificate on the same host is still refused, and
+    /// hosts under HSTS get no exception at all.
+    pub fn add_certificate_exception(&self, error: &CertErrorDetails) {
+        if self.is_hsts_host(&error.host) {
+            warn!(
+                "not accepting the certificate of {}, which uses HSTS",
+                error.host
+            );
+            return;
+        }
+        if let Some(leaf) = error.certificates.first() {
+            self.tls
+                .exceptions
+                .lock()
+                .insert(error.host.clone(), leaf.sha256_fingerprint.clone());
+        }
+    }
+
+    /// Verifies certificates of `host` strictly again. The clients are
+    /// rebuilt so that pooled connections and resumable sessions made under
+    /// the exception are not reused.
+    pub fn remove_certificate_exception(&self, host: &str) {
+        if self.tls.exceptions.lock().remove(host).is_none() {
+            return;
+        }
+        if let Err(err) = self.set_proxy(self.proxy_settings()) {
+            warn!("failed to reset connections to {host}: {err:#}");
+        }
+    }
+
+    /// Hosts whose certificate has been accepted despite failing
+    /// verification.
+    pub fn certificate_exceptions(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = self.tls.exceptions.lock().keys().cloned().collect();
+        hosts.sort();
+        hosts
+    }
+
+    /// Turns a failed request to `url` into [`BrowserError::Tls`] when the
+    /// handshake refused the server's certificate.
+    pub(crate) fn certificate_error(&self, url: &Url, err: BrowserError) -> BrowserError {
+        match (&err, self.tls.take_failure(url)) {
+            (BrowserError::Network(_), Some(details)) => BrowserError::Tls(details),
+            _ => err,
+        }
+    }
+}
+
+/// Records what the HTTP clients' TLS handshakes negotiated, per host.
+#[derive(Default)]
+pub(crate) struct TlsObserver {
+    connections: Mutex<HashMap<String, ConnectionInfo>>,
+    /// Host a verified leaf certificate was presented by, until the
+    /// handshake signature reveals the protocol version.
+    pending: Mutex<HashMap<Vec<u8>, String>>,
+    /// Latest verification failure per host, until a request claims it.
+    failures: Mutex<HashMap<String, CertErrorDetails>>,
+    /// Leaf certificate fingerprint accepted per host despite failing
+    /// verification.
+    exceptions: Mutex<HashMap<String, String>>,
+}
+
+impl TlsObserver {
+    pub(crate) fn connection(&self, url: &Url) -> Option<ConnectionInfo> {
+        self.connections.lock().get(&https_host(url)?).cloned()
+    }
+
+    fn take_failure(&self, url: &Url) -> Option<CertErrorDetails> {
+        self.failures.lock().remove(&https_host(url)?)
+    }
+
+    fn update(&self, host: String, apply: impl FnOnce(&mut ConnectionInfo)) {
+        let mut connections = self.connections.lock();
+        let info = connections
+            .entry(host.clone())
+            .or_insert_with(|| ConnectionInfo {
+                host,
+                protocol: None,
+                cipher_suite: None,
+                key_exchange: None,
+                certificates: Vec::new(),
+                overridden_error: None,
+                established_at: Utc::now(),
+            });
+        apply(info);
+    }
+
+    fn record_protocol(&self, leaf: &Certificate, protocol: &str) {
+        if let Some(host) = self.pending.lock().remove(&leaf.0) {
+            self.update(host, |info| info.protocol = Some(protocol.to_owned()));
+        }
+    }
+}
+
+/// TLS configuration for an HTTP client: the bundled web PKI roots, with
+/// handshakes reported to `observer` and ALPN offering the protocols
+/// `http_version` allows. `identity` is presented to servers asking for a
+/// client certificate. Each configuration keeps its own
+/// resumption sessions, so private clients share none with regular ones.
+pub(crate) fn client_config(
+    observer: &Arc<TlsObserver>,
+    http_version: HttpVersionPolicy,
+    identity: Option<&ClientIdentity>,
+) -> ClientConfig {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+    let verifier = ObservingVerifier {
+        inner: WebPkiVerifier::new(roots, None),
+        observer: Arc::clone(observer),
+    };
+    let builder = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(verifier));
+    let mut config = match identity {
+        Some(identity) => builder.with_client_cert_resolver(Arc::new(identity.clone())),
+        None => builder.with_no_client_auth(),
+    };
+    config.resumption = Resumption::store(Arc::new(ObservingSessionStore {
+        inner: ClientSessionMemoryCache::new(SESSION_CACHE_SIZE),
+        observer: Arc::clone(observer),
+    }));
+    config.alpn_protocols = http_version.alpn_protocols();
+    config
+}
+
+/// Host of an `https` URL as handshakes see it, without IPv6 brackets.
+fn https_host(url: &Url) -> Option<String> {
+    if url.scheme() != "https" {
+        return None;
+    }
+    let host = url.host_str()?;
+    Some(
+        host.trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_owned(),
+    )
+}
+
+fn host_name(server_name: &ServerName) -> Option<String> {
+    match server_name {
+        ServerName::DnsName(name) => Some(name.as_ref().to_ascii_lowercase()),
+        ServerName::IpAddress(address) => Some(address.to_string()),
+        _ => None,
+    }
+}
+
+/// Explains a certificate verification failure to the user.
+fn describe(err: &rustls::Error) -> String {
+    let reason = match err {
+        rustls::Error::InvalidCertificate(CertificateError::Expired) => {
+            "the certificate has expired"
+        }
+        rustls::Error::InvalidCertificate(CertificateError::NotValidYet) => {
+            "the certificate is not valid yet"
+        }
+        rustls::Error::InvalidCertificate(CertificateError::UnknownIssuer) => {
+            "the certificate was issued by an authority that is not trusted"
+        }
+        rustls::Error::InvalidCertificate(CertificateError::NotValidForName) => {
+            "the certificate belongs to a different site"
+        }
+        rustls::Error::InvalidCertificate(CertificateError::Revoked) => {
+            "the certificate has been revoked"
+        }
+        rustls::Error::InvalidCertificate(CertificateError::BadSignature) => {
+            "the certificate signature is invalid"
+        }
+        other => return other.to_string(),
+    };
+    reason.to_owned()
+}
+
+/// Web PKI verification that remembers the chains it accepted and refused,
+/// and lets certificates with an exception through.
+struct ObservingVerifier {
+    inner: WebPkiVerifier,
+    observer: Arc<TlsObserver>,
+}
+
+impl ServerCertVerifier for ObservingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let result = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        );
+        let Some(host) = host_name(server_name) else {
+            return result;
+        };
+        let certificates: Vec<CertificateInfo> = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(|certificate| CertificateInfo::from_der(&certificate.0))
+            .collect();
+
+        let overridden_error = match &result {
+            Ok(_) => {
+                self.observer.failures.lock().remove(&host);
+                None
+            }
+            Err(err) => {
+                let reason = describe(err);
+                let excepted = certificates.first().is_some_and(|leaf| {
+                    self.observer.exceptions.lock().get(&host) == Some(&leaf.sha256_fingerprint)
+                });
+                if !excepted {
+                    self.observer.failures.lock().insert(
+                        host.clone(),
+                        CertErrorDetails {
+                            host,
+                            reason,
+                            certificates,
+                        },
+                    );
+                    return result;
+                }
+                Some(reason)
+            }
+        };
+        self.observer
+            .pending
+            .lock()
+            .insert(end_entity.0.clone(), host.clone());
+        self.observer.update(host, |info| {
+            info.certificates = certificates;
+            info.overridden_error = overridden_error;
+            info.established_at = Utc::now();
+        });
+        Ok(result.unwrap_or_else(|_| ServerCertVerified::assertion()))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        let valid = self.inner.verify_tls12_signature(message, cert, dss)?;
+        record_phase(Phase::TlsDone);
+        self.observer.record_protocol(cert, "TLS 1.2");
+        Ok(valid)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        let valid = self.inner.verify_tls13_signature(message, cert, dss)?;
+        record_phase(Phase::TlsDone);
+        self.observer.record_protocol(cert, "TLS 1.3");
+        Ok(valid)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+
+    fn request_scts(&self) -> bool {
+        self.inner.request_scts()
+    }
+}
+
+/// In-memory session cache that notes the key exchange and cipher suite
+/// servers negotiate.
+struct ObservingSessionStore {
+    inner: ClientSessionMemoryCache,
+    observer: Arc<TlsObserver>,
+}
+
+impl ClientSessionStore for ObservingSessionStore {
+    fn set_kx_hint(&self, server_name: &ServerName, group: NamedGroup) {
+        if let Some(host) = host_name(server_name) {
+            self.observer
+                .update(host, |info| info.key_exchange = Some(format!("{group:?}")));
+        }
+        self.inner.set_kx_hint(server_name, group);
+    }
+
+    fn kx_hint(&self, server_name: &ServerName) -> Option<NamedGroup> {
+        self.inner.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: &ServerName, value: Tls12ClientSessionValue) {
+        self.inner.set_tls12_session(server_name, value);
+    }
+
+    fn tls12_session(&self, server_name: &ServerName) -> Option<Tls12ClientSessionValue> {
+        self.inner.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName) {
+        self.inner.remove_tls12_session(server_name);
+    }
+
+    fn insert_tls13_ticket(&self, server_name: &ServerName, value: Tls13ClientSessionValue) {
+        if let Some(host) = host_name(server_name) {
+            let suite = format!("{:?}", value.suite().common.suite);
+            self.observer
+                .update(host, |info| info.cipher_suite = Some(suite));
+        }
+        self.inner.insert_tls13_ticket(server_name, value);
+    }
+
+    fn take_tls13_ticket(&self, server_name: &ServerName) -> Option<Tls13ClientSessionValue> {
+        // Looked up first thing when a handshake starts.
+        record_phase(Phase::TlsStarted);
+        self.inner.take_tls13_ticket(server_name)
+    }
+}
+
+/// Reads DER tag-length-value items off the front of a buffer.
+pub(crate) struct Der<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Der<'a> {
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    pub(crate) fn next(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.0.split_first()?;
+        let (&first, mut rest) = rest.split_first()?;
+        let len = if first < 0x80 {
+            first as usize
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return None;
+            }
+            let (bytes, tail) = rest.split_at(count);
+            rest = tail;
+            bytes
+                .iter()
+                .fold(0, |len, &byte| (len << 8) | byte as usize)
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let (value, tail) = rest.split_at(len);
+        self.0 = tail;
+        Some((tag, value))
+    }
+
+    pub(crate) fn expect(&mut self, tag: u8) -> Option<&'a [u8]> {
+        let (found, value) = self.next()?;
+        (found == tag).then_some(value)
+    }
+}
+
+/// Formats a distinguished name as `CN=…, O=…` in encoded order.
+fn format_name(name: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut sets = Der(name);
+    while let Some(set) = sets.expect(SET) {
+        let mut attributes = Der(set);
+        while let Some(attribute) = attributes.expect(SEQUENCE) {
+            let mut attribute = Der(attribute);
+            let (Some(oid), Some((tag, value))) =
+                (attribute.expect(OBJECT_IDENTIFIER), attribute.next())
+            else {
+                continue;
+            };
+            let label = NAME_ATTRIBUTES
+                .iter()
+                .find(|(known, _)| *known == oid)
+                .map_or_else(|| dotted_oid(oid), |(_, label)| (*label).to_owned());
+            parts.push(format!("{label}={}", decode_string(tag, value)));
+        }
+    }
+    parts.join(", ")
+}
+
+fn decode_string(tag: u8, value: &[u8]) -> String {
+    match tag {
+        // BMPString is big-endian UTF-16.
+        0x1e => {
+            let units: Vec<u16> = value
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(value).into_owned(),
+    }
+}
+
+fn dotted_oid(oid: &[u8]) -> String {
+    let Some((&first, rest)) = oid.split_first() else {
+        return String::new();
+    };
+    let mut arcs = vec![u64::from(first / 40), u64::from(first % 40)];
+    let mut arc = 0u64;
+    for &byte in rest {
+        arc = (arc << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    arcs.iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Parses a UTCTime or GeneralizedTime in the `Z` form certificates use.
+fn parse_time((tag, value): (u8, &[u8])) -> Option<DateTime<Utc>> {
+    let text = std::str::from_utf8(value).ok()?;
+    let full = match tag {
+        0x17 => {
+            let year: u32 = text.get(..2)?.parse().ok()?;
+            let century = if year < 50 { "20" } else { "19" };
+            format!("{century}{text}")
+        }
+        0x18 => text.to_owned(),
+        _ => return None,
+    };
+    NaiveDateTime::parse_from_str(&full, "%Y%m%d%H%M%SZ")
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+fn alt_names(extension: &[u8]) -> Option<Vec<String>> {
+    let mut names = Der(Der(extension).expect(SEQUENCE)?);
+    let mut found = Vec::new();
+    while let Some((tag, value)) = names.next() {
+        match (tag, value.len()) {
+            (SAN_DNS_NAME, _) => found.push(String::from_utf8_lossy(value).into_owned()),
+            (SAN_IP_ADDRESS, 4) => {
+                let octets: [u8; 4] = value.try_into().ok()?;
+                found.push(std::net::Ipv4Addr::from(octets).to_string());
+            }
+            (SAN_IP_ADDRESS, 16) => {
+                let octets: [u8; 16] = value.try_into().ok()?;
+                found.push(std::net::Ipv6Addr::from(octets).to_string());
+            }
+            _ => {}
+        }
+    }
+    Some(found)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Leaf for `www.example.test` issued by [`ROOT`], valid from
+    /// 2025-03-01 12:00 to 2050-06-01, the end encoded as a GeneralizedTime.
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf-cert.der");
+    /// Self-signed root without extensions besides the key identifiers.
+    const ROOT: &[u8] = include_bytes!("../testdata/root-cert.der");
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn summarizes_a_leaf_certificate() {
+        let leaf = CertificateInfo::from_der(LEAF).unwrap();
+        assert_eq!(
+            leaf.subject,
+            "C=DE, ST=Berlin, O=Example GmbH, CN=www.example.test"
+        );
+        assert_eq!(leaf.issuer, "C=US, O=Example Trust, CN=Example Root CA");
+        assert_eq!(leaf.subject_name(), "www.example.test");
+        // The leading zero keeping the serial positive is dropped.
+        assert_eq!(leaf.serial, "A1:B2:C3:D4:E5:F6:07:18");
+        assert_eq!(
+            leaf.subject_alt_names,
+            [
+                "www.example.test",
+                "example.test",
+                "192.0.2.7",
+                "2001:db8::1"
+            ]
+        );
+        assert_eq!(
+            leaf.sha256_fingerprint,
+            "2B:FC:DC:1C:AD:0B:E1:85:95:3D:89:04:F1:34:3D:3B:\
+             65:B5:07:D0:2C:9F:A7:33:9B:ED:85:C8:68:74:34:E5"
+        );
+    }
+
+    #[test]
+    fn summarizes_a_root_certificate() {
+        let root = CertificateInfo::from_der(ROOT).unwrap();
+        assert_eq!(root.subject, root.issuer);
+        assert_eq!(root.subject_name(), "Example Root CA");
+        assert_eq!(
+            root.serial,
+            "63:94:73:3B:24:11:1B:B9:2C:63:BE:7B:1B:DB:7C:33:73:BA:19:FD"
+        );
+        assert!(root.subject_alt_names.is_empty());
+        assert_eq!(root.not_before, Some(at(2024, 1, 1, 0)));
+        assert_eq!(root.not_after, Some(at(2034, 1, 1, 0)));
+    }
+
+    #[test]
+    fn checks_the_validity_period() {
+        let leaf = CertificateInfo::from_der(LEAF).unwrap();
+        assert_eq!(leaf.not_before, Some(at(2025, 3, 1, 12)));
+        assert_eq!(leaf.not_after, Some(at(2050, 6, 1, 0)));
+        assert!(!leaf.is_valid_at(at(2025, 3, 1, 11)));
+        assert!(leaf.is_valid_at(at(2025, 3, 1, 12)));
+        assert!(leaf.is_valid_at(at(2040, 1, 1, 0)));
+        assert!(leaf.is_valid_at(at(2050, 6, 1, 0)));
+        assert!(!leaf.is_valid_at(at(2050, 6, 1, 1)));
+
+        // Missing bounds leave that side open.
+        let open = CertificateInfo {
+            not_before: None,
+            not_after: None,
+            ..leaf
+        };
+        assert!(open.is_valid_at(at(1990, 1, 1, 0)));
+    }
+
+    #[test]
+    fn reads_both_time_encodings() {
+        assert_eq!(
+            parse_time((0x17, b"491231235959Z")),
+            Some(Utc.with_ymd_and_hms(2049, 12, 31, 23, 59, 59).unwrap())
+        );
+        assert_eq!(
+            parse_time((0x17, b"500101000000Z")),
+            Some(at(1950, 1, 1, 0))
+        );
+        assert_eq!(
+            parse_time((0x18, b"20500101000000Z")),
+            Some(at(2050, 1, 1, 0))
+        );
+        assert_eq!(parse_time((0x18, b"2050010100Z")), None);
+        assert_eq!(parse_time((0x04, b"500101000000Z")), None);
+    }
+
+    #[test]
+    fn refuses_malformed_certificates() {
+        assert!(CertificateInfo::from_der(&LEAF[..LEAF.len() / 2]).is_none());
+        assert!(CertificateInfo::from_der(&[]).is_none());
+        assert!(CertificateInfo::from_der(&[SEQUENCE, 0x84, 0xff, 0xff, 0xff, 0xff]).is_none());
+        assert!(CertificateInfo::from_der(&[SEQUENCE, 0x80]).is_none());
+    }
+
+    #[test]
+    fn describes_verification_failures() {
+        let expired = rustls::Error::InvalidCertificate(CertificateError::Expired);
+        assert_eq!(describe(&expired), "the certificate has expired");
+        let name = rustls::Error::InvalidCertificate(CertificateError::NotValidForName);
+        assert_eq!(
+            describe(&name),
+            "the certificate belongs to a different site"
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use chrono::{DateTime, Utc};
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use thiserror::Error;
+use url::Url;
+
+use crate::PageForm;
+
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD_SECS: u64 = 30;
+
+/// Parts of the names sites give one-time code fields.
+const OTP_FIELD_NAMES: &[&str] = &[
+    "otp",
+    "one_time",
+    "onetime",
+    "one-time",
+    "2fa",
+    "mfa",
+    "twofactor",
+    "two_factor",
+    "verification",
+    "code",
+];
+
+/// Code-like names that are about something else.
+const NOT_OTP_FIELD_NAMES: &[&str] = &["post", "zip", "country", "promo", "coupon", "voucher"];
+
+#[derive(Debug, Error)]
+pub enum TotpError {
+    #[error("the secret is not valid base32")]
+    InvalidSecret,
+    #[error("invalid otpauth URI: {0}")]
+    InvalidUri(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Generator of time-based one-time passwords (RFC 6238), as used by
+/// authenticator apps for two-factor sign-in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Totp {
+    secret: Vec<u8>,
+    algorithm: Algorithm,
+    digits: u32,
+    period: u64,
+}
+
+impl Totp {
+    /// Reads the base32 secret sites show next to their setup QR code, or
+    /// the `otpauth://totp/` URI the QR code contains.
+    pub fn parse(input: &str) -> Result<Self, TotpError> {
+        let input = input.trim();
+        if !input.starts_with("otpauth:") {
+            return Ok(Self {
+                secret: decode_base32(input)?,
+                algorithm: Algorithm::Sha1,
+                digits: DEFAULT_DIGITS,
+                period: DEFAULT_PERIOD_SECS,
+            });
+        }
+        let uri = Url::parse(input).map_err(|err| TotpError::InvalidUri(err.to_string()))?;
+        if uri.host_str() != Some("totp") {
+            return Err(TotpError::InvalidUri(
+                "only time-based codes are supported".to_owned(),
+            ));
+        }
+        let mut totp = Self {
+            secret: Vec::new(),
+            algorithm: Algorithm::Sha1,
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD_SECS,
+        };
+        for (key, value) in uri.query_pairs() {
+            let invalid = || TotpError::InvalidUri(format!("bad {key}: {value}"));
+            match key.as_ref() {
+                "secret" => totp.secret = decode_base32(&value)?,
+                "algorithm" => {
+                    totp.algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => Algorithm::Sha1,
+                        "SHA256" => Algorithm::Sha256,
+                        "SHA512" => Algorithm::Sha512,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "digits" => {
+                    totp.digits = value
+                        .parse()
+                        .ok()
+                        .filter(|digits| (6..=8).contains(digits))
+                        .ok_or_else(invalid)?;
+                }
+                "period" => {
+                    totp.period = value
+                        .parse()
+                        .ok()
+                        .filter(|period| *period > 0)
+                        .ok_or_else(invalid)?;
+                }
+                _ => {}
+            }
+        }
+        if totp.secret.is_empty() {
+            return Err(TotpError::InvalidUri("no secret".to_owned()));
+        }
+        Ok(totp)
+    }
+
+    /// The code valid at `time`.
+    pub fn code_at(&self, time: DateTime<Utc>) -> String {
+        let counter = (time.timestamp().max(0) as u64 / self.period).to_be_bytes();
+        let digest = match self.algorithm {
+            Algorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(&self.secret, &counter),
+            Algorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(&self.secret, &counter),
+            Algorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(&self.secret, &counter),
+        };
+        // Dynamic truncation from RFC 4226.
+        let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+        let value = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        let code = value % 10u32.pow(self.digits);
+        format!("{code:0width$}", width = self.digits as usize)
+    }
+
+    /// The code valid now and the seconds until it changes.
+    pub fn current(&self) -> (String, u64) {
+        let now = Utc::now();
+        let remaining = self.period - now.timestamp().max(0) as u64 % self.period;
+        (self.code_at(now), remaining)
+    }
+}
+
+impl PageForm {
+    /// Puts `code` into the form's one-time code field, returning whether
+    /// it has one.
+    pub fn fill_one_time_code(&mut self, code: &str) -> bool {
+        let field = self.fields.iter_mut().find(|field| {
+            let name = field.name.to_ascii_lowercase();
+            matches!(field.kind.as_str(), "text" | "number" | "tel" | "password")
+                && OTP_FIELD_NAMES.iter().any(|part| name.contains(part))
+                && !NOT_OTP_FIELD_NAMES.iter().any(|part| name.contains(part))
+        });
+        match field {
+            Some(field) => {
+                field.value = code.to_owned();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn hmac_digest<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Decodes RFC 4648 base32, ignoring case, spaces, dashes and padding.
+fn decode_base32(text: &str) -> Result<Vec<u8>, TotpError> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.chars().filter(|c| !matches!(c, ' ' | '-' | '=')) {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return Err(TotpError::InvalidSecret),
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if bytes.is_empty() {
+        return Err(TotpError::InvalidSecret);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Times and codes of the RFC 6238 appendix B table, for SHA1, SHA256
+    /// and SHA512 in that order.
+    const RFC_6238: [(i64, [&str; 3]); 6] = [
+        (59, ["94287082", "46119246", "90693936"]),
+        (1111111109, ["07081804", "68084774", "25091201"]),
+        (1111111111, ["14050471", "67062674", "99943326"]),
+        (1234567890, ["89005924", "91819424", "93441116"]),
+        (2000000000, ["69279037", "90698825", "38618901"]),
+        (20000000000, ["65353130", "77737706", "47863826"]),
+    ];
+
+    fn rfc_totp(algorithm: Algorithm) -> Totp {
+        let seed = match algorithm {
+            Algorithm::Sha1 => "12345678901234567890".to_owned(),
+            Algorithm::Sha256 => "12345678901234567890123456789012".to_owned(),
+            Algorithm::Sha512 => "1234567890".repeat(6) + "1234",
+        };
+        Totp {
+            secret: seed.into_bytes(),
+            algorithm,
+            digits: 8,
+            period: 30,
+        }
+    }
+
+    #[test]
+    fn matches_the_rfc_6238_vectors() {
+        let algorithms = [Algorithm::Sha1, Algorithm::Sha256, Algorithm::Sha512];
+        for (column, algorithm) in algorithms.into_iter().enumerate() {
+            let totp = rfc_totp(algorithm);
+            for (time, codes) in RFC_6238 {
+                let time = DateTime::from_timestamp(time, 0).unwrap();
+                assert_eq!(totp.code_at(time), codes[column], "{algorithm:?} at {time}");
+            }
+        }
+    }
+
+    #[test]
+    fn reads_otpauth_uris() {
+        let totp = Totp::parse(
+            "otpauth://totp/Example:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &algorithm=sha1&digits=8&period=30&issuer=Example",
+        )
+        .unwrap();
+        assert_eq!(totp, rfc_totp(Algorithm::Sha1));
+        let time = DateTime::from_timestamp(59, 0).unwrap();
+        assert_eq!(totp.code_at(time), "94287082");
+
+        let six = Totp::parse("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+        assert_eq!(six.code_at(time), "287082");
+
+        for bad in [
+            "otpauth://hotp/x?secret=GEZDGNBV",
+            "otpauth://totp/x?secret=GEZDGNBV&digits=12",
+            "otpauth://totp/x?secret=GEZDGNBV&period=0",
+            "otpauth://totp/x?secret=GEZDGNBV&algorithm=MD5",
+            "otpauth://totp/x?issuer=Example",
+        ] {
+            assert!(Totp::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn decodes_padded_and_lowercase_secrets() {
+        assert_eq!(decode_base32("MZXW6===").unwrap(), b"foo");
+        assert_eq!(decode_base32("MZXW6YQ=").unwrap(), b"foob");
+        assert_eq!(decode_base32("mzxw6ytb").unwrap(), b"fooba");
+        assert_eq!(decode_base32("mzxw 6ytb-oi======").unwrap(), b"foobar");
+        assert_eq!(
+            Totp::parse(" gezdgnbvgy3tqojqgezdgnbvgy3tqojq ")
+                .unwrap()
+                .secret,
+            b"12345678901234567890"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_secrets() {
+        for bad in ["", "====", "MZXW1", "MZXW8", "MZ_W6"] {
+            assert!(decode_base32(bad).is_err(), "{bad:?}");
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use chrono::{DateTime, Duration, Utc};
+use scraper::{Html, Node};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{extract_article, ArticleBlock, BrowserCore, BrowserError, PageResponse, RequestKind};
+
+/// Smallest number of added or removed words that counts as a real change;
+/// anything below is treated as noise such as a ticking clock.
+const MIN_CHANGED_WORDS: usize = 3;
+
+/// Longest text, in lines, that is diffed line by line.
+const MAX_DIFF_LINES: usize = 1500;
+
+/// A URL the runtime refetches periodically to detect content changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageWatch {
+    pub url: Url,
+    pub title: String,
+    pub interval_minutes: u32,
+    /// Extracted text as of the last check, `None` until the baseline is taken.
+    pub snapshot: Option<String>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_changed: Option<DateTime<Utc>>,
+    /// Line diff recorded for the most recent meaningful change.
+    pub changes: Vec<DiffLine>,
+    /// Set when a change was recorded that the user has not looked at yet.
+    pub unseen: bool,
+}
+
+/// Line of a text diff between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+impl BrowserCore {
+    /// Starts watching `url`. The first check only records a baseline.
+    pub fn watch_page(&self, url: Url, title: impl Into<String>, interval_minutes: u32) {
+        let mut guard = self.state.write();
+        if guard.watches.iter().any(|watch| watch.url == url) {
+            return;
+        }
+        guard.watches.push(PageWatch {
+            url,
+            title: title.into(),
+            interval_minutes: interval_minutes.max(1),
+            snapshot: None,
+            last_checked: None,
+            last_changed: None,
+            changes: Vec::new(),
+            unseen: false,
+        });
+    }
+
+    pub fn unwatch_page(&self, url: &Url) {
+        self.state.write().watches.retain(|watch| &watch.url != url);
+    }
+
+    pub fn set_watch_interval(&self, url: &Url, interval_minutes: u32) {
+        self.update_watch(url, |watch| {
+            watch.interval_minutes = interval_minutes.max(1)
+        });
+    }
+
+    /// Clears the unseen flag once the user has opened the diff.
+    pub fn mark_watch_seen(&self, url: &Url) {
+        self.update_watch(url, |watch| watch.unseen = false);
+    }
+
+    pub fn watches(&self) -> Vec<PageWatch> {
+        self.state.read().watches.clone()
+    }
+
+    pub fn import_watches(&self, watches: Vec<PageWatch>) {
+        self.state.write().watches = watches;
+    }
+
+    /// Returns the watches whose interval has elapsed and stamps them as
+    /// checked so the next poll does not pick them up again while in flight.
+    pub fn claim_due_watches(&self) -> Vec<Url> {
+        let now = Utc::now();
+        let mut guard = self.state.write();
+        guard
+            .watches
+            .iter_mut()
+            .filter(|watch| {
+                watch.last_checked.is_none_or(|checked| {
+                    now - checked >= Duration::minutes(i64::from(watch.interval_minutes))
+                })
+            })
+            .map(|watch| {
+                watch.last_checked = Some(now);
+                watch.url.clone()
+            })
+            .collect()
+    }
+
+    /// Refetches a watched page and diffs it against the last snapshot.
+    /// Returns `true` when a meaningful change was recorded.
+    pub async fn check_watch(&self, url: &Url) -> Result<bool, BrowserError> {
+        let page = self
+            .fetch_document(
+                url,
+                self.client_for(url, false).get(url.clone()),
+                RequestKind::Watch,
+                None,
+                None,
+            )
+            .await?;
+        let text = page_text(&page);
+        let now = Utc::now();
+
+        let mut guard = self.state.write();
+        let Some(watch) = guard.watches.iter_mut().find(|watch| &watch.url == url) else {
+            return Ok(false);
+        };
+        watch.last_checked = Some(now);
+        let Some(previous) = &watch.snapshot else {
+            watch.snapshot = Some(text);
+            return Ok(false);
+        };
+
+        let diff = diff_lines(previous, &text);
+        if changed_words(&diff) < MIN_CHANGED_WORDS {
+            return Ok(false);
+        }
+        watch.snapshot = Some(text);
+        watch.changes = diff;
+        watch.last_changed = Some(now);
+        watch.unseen = true;
+        Ok(true)
+    }
+
+    fn update_watch(&self, url: &Url, update: impl FnOnce(&mut PageWatch)) {
+        let mut guard = self.state.write();
+        if let Some(watch) = guard.watches.iter_mut().find(|watch| &watch.url == url) {
+            update(watch);
+        }
+    }
+}
+
+/// Readable text of a page, one block per line.
+fn page_text(page: &PageResponse) -> String {
+    if let Some(article) = extract_article(page) {
+        return article
+            .blocks
+            .into_iter()
+            .map(|block| match block {
+                ArticleBlock::Heading { text, .. } | ArticleBlock::Paragraph(text) => text,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    let document = Html::parse_document(&page.body);
+    let mut lines = Vec::new();
+    for node in document.tree.nodes() {
+        let Node::Text(text) = node.value() else {
+            continue;
+        };
+        let hidden = node.ancestors().any(|ancestor| {
+            ancestor
+                .value()
+                .as_element()
+                .is_some_and(|element| matches!(element.name(), "script" | "style" | "noscript"))
+        });
+        if hidden {
+            continue;
+        }
+        let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+/// Longest-common-subsequence line diff of `old` and `new`.
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().take(MAX_DIFF_LINES).collect();
+    let new: Vec<&str> = new.lines().take(MAX_DIFF_LINES).collect();
+
+    // lcs[i][j] is the LCS length of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i].to_owned()));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            diff.push(DiffLine::Removed(old[i].to_owned()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j].to_owned()));
+            j += 1;
+        }
+    }
+    diff.extend(
+        old[i..]
+            .iter()
+            .map(|line| DiffLine::Removed((*line).to_owned())),
+    );
+    diff.extend(
+        new[j..]
+            .iter()
+            .map(|line| DiffLine::Added((*line).to_owned())),
+    );
+    diff
+}
+
+fn changed_words(diff: &[DiffLine]) -> usize {
+    diff.iter()
+        .map(|line| match line {
+            DiffLine::Added(text) | DiffLine::Removed(text) => text.split_whitespace().count(),
+            DiffLine::Same(_) => 0,
+        })
+        .sum()
+}
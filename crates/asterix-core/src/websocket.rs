@@ -0,0 +1,667 @@
+use std::time::{Duration, Instant};
+
+use base64::Engine as _;
+use bytes::Bytes;
+use rand::Rng;
+use reqwest::header::{
+    CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION, UPGRADE, USER_AGENT,
+};
+use reqwest::StatusCode;
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tracing::{debug, instrument};
+use url::Url;
+
+use crate::intercept::Interception;
+use crate::network::{self, header_pairs};
+use crate::shield::intercept_request;
+use crate::{build_client, BrowserCore, BrowserError, HttpVersionPolicy, RequestKind, TabId};
+
+/// Appended to the handshake key before hashing, fixed by RFC 6455.
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Largest message accepted from a server, across all of its fragments.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// How long to wait for the server to answer a close frame before dropping
+/// the connection.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Close code sent when the job is dropped without closing the socket.
+const GOING_AWAY: u16 = 1001;
+
+/// Close code sent when the server breaks the framing rules.
+const PROTOCOL_ERROR: u16 = 1002;
+
+/// Close code sent when a message exceeds [`MAX_MESSAGE_BYTES`].
+const MESSAGE_TOO_BIG: u16 = 1009;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Data message sent or received over a WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketMessage {
+    Text(String),
+    Binary(Bytes),
+}
+
+/// Instruction for an open WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketCommand {
+    Send(WebSocketMessage),
+    /// Starts the closing handshake; the socket ends once the server answers.
+    Close {
+        code: u16,
+        reason: String,
+    },
+}
+
+/// What happened on an open WebSocket, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketEvent {
+    /// The handshake succeeded; messages may be sent from now on.
+    Opened {
+        url: Url,
+    },
+    Message(WebSocketMessage),
+}
+
+/// How a WebSocket ended.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebSocketClose {
+    /// Code from the close frame; `None` when the server sent none or the
+    /// connection dropped without one.
+    pub code: Option<u16>,
+    pub reason: String,
+}
+
+/// Frame read off the connection.
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+impl BrowserCore {
+    /// Connects to the `ws:` or `wss:` endpoint at `url` on behalf of `tab`
+    /// and relays `commands` to it and its messages to `events` until either
+    /// side closes the socket. Dropping the command sender closes it too.
+    #[instrument(skip(self, commands, events))]
+    pub async fn run_websocket(
+        &self,
+        tab: TabId,
+        url: Url,
+        mut commands: mpsc::UnboundedReceiver<WebSocketCommand>,
+        events: mpsc::UnboundedSender<WebSocketEvent>,
+    ) -> Result<WebSocketClose, BrowserError> {
+        let upgraded = self.websocket_handshake(tab, &url).await?;
+        debug!("WebSocket to {url} is open");
+        let _ = events.send(WebSocketEvent::Opened { url: url.clone() });
+
+        let (reader, mut writer) = tokio::io::split(upgraded);
+        let (frames_tx, mut frames) = mpsc::unbounded_channel();
+        let reader = tokio::spawn(read_frames(BufReader::new(reader), frames_tx));
+        let result = relay(&mut writer, &mut commands, &mut frames, &events).await;
+        reader.abort();
+        let _ = writer.shutdown().await;
+        debug!("WebSocket to {url} closed: {result:?}");
+        result
+    }
+
+    /// Sends the upgrade request and checks the server agreed to it, logging
+    /// the exchange with the rest of the tab's requests.
+    async fn websocket_handshake(
+        &self,
+        tab: TabId,
+        url: &Url,
+    ) -> Result<reqwest::Upgraded, BrowserError> {
+        let mut http_url = url.clone();
+        let scheme = match url.scheme() {
+            "ws" => "http",
+            "wss" => "https",
+            other => {
+                return Err(BrowserError::InvalidRequest(format!(
+                    "{other}: is not a WebSocket scheme"
+                )))
+            }
+        };
+        http_url
+            .set_scheme(scheme)
+            .map_err(|()| BrowserError::InvalidRequest(format!("invalid WebSocket URL {url}")))?;
+        http_url.set_fragment(None);
+        self.secure_subresource(tab, &mut http_url, false)?;
+        match intercept_request(
+            &self.state.read(),
+            &http_url,
+            RequestKind::WebSocket,
+            Some(tab),
+        ) {
+            Interception::Continue | Interception::Override(_) => {}
+            Interception::Block(reason) => return Err(BrowserError::Blocked(reason)),
+            Interception::Restrict => return Err(BrowserError::Restricted(url.clone())),
+        }
+        if self.state.read().is_offline() {
+            return Err(BrowserError::Offline);
+        }
+
+        // The upgrade only exists in HTTP/1.1, so the pooled clients, which
+        // may have negotiated HTTP/2, cannot be used.
+        let options = self.tab_options(tab);
+        let with_cookies =
+            !options.private && !self.withholds_cookies(tab, RequestKind::WebSocket, &http_url);
+        let cookie_jar = with_cookies.then(|| std::sync::Arc::clone(&self.cookie_jar));
+        let client = build_client(
+            self.user_agent.as_deref(),
+            cookie_jar,
+            &self.proxy_settings(),
+            HttpVersionPolicy::Http1Only,
+            &self.tls,
+            &self.resolver,
+            self.client_identity(&http_url).as_ref(),
+        )?;
+        let key =
+            base64::engine::general_purpose::STANDARD.encode(rand::thread_rng().gen::<[u8; 16]>());
+        let mut builder = client
+            .get(http_url)
+            .header(CONNECTION, "Upgrade")
+            .header(UPGRADE, "websocket")
+            .header(SEC_WEBSOCKET_VERSION, "13")
+            .header(SEC_WEBSOCKET_KEY, &key);
+        if let Some(ua) = self.user_agent_for(options.user_agent.as_deref(), url) {
+            builder = builder.header(USER_AGENT, ua);
+        }
+        let mut request = builder.build()?;
+        self.state
+            .read()
+            .privacy
+            .apply_opt_out(request.headers_mut());
+        let mut entry = network::stream_entry(RequestKind::WebSocket, tab, url, &request);
+
+        let started = Instant::now();
+        let result = network::execute_guarded(
+            &self.state,
+            &client,
+            request,
+            RequestKind::WebSocket,
+            Some(tab),
+        )
+        .await;
+        entry.duration_ms = Some(started.elapsed().as_millis() as u64);
+        let response = match result {
+            Ok(response) => response,
+            Err(err) => {
+                entry.error = Some(err.to_string());
+                network::start_entry(&self.state, entry);
+                return Err(err);
+            }
+        };
+        entry.status = Some(response.status().as_u16());
+        entry.response_headers = header_pairs(response.headers());
+        let expected = accept_key(&key);
+        let failure = if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            Some(BrowserError::Status(response.status().as_u16()))
+        } else if response
+            .headers()
+            .get(SEC_WEBSOCKET_ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            != Some(expected.as_str())
+        {
+            Some(BrowserError::WebSocket(
+                "server answered the handshake with the wrong accept key".to_owned(),
+            ))
+        } else {
+            None
+        };
+        if let Some(err) = failure {
+            entry.error = Some(err.to_string());
+            network::start_entry(&self.state, entry);
+            return Err(err);
+        }
+        network::start_entry(&self.state, entry);
+        Ok(response.upgrade().await?)
+    }
+}
+
+/// Value the server must answer `key` with in `Sec-WebSocket-Accept`.
+fn accept_key(key: &str) -> String {
+    let digest = Sha1::new()
+        .chain_update(key.as_bytes())
+        .chain_update(ACCEPT_GUID.as_bytes())
+        .finalize();
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+/// Passes commands to the server and its messages on until the socket closes.
+async fn relay(
+    writer: &mut (impl AsyncWrite + Unpin),
+    commands: &mut mpsc::UnboundedReceiver<WebSocketCommand>,
+    frames: &mut mpsc::UnboundedReceiver<Result<Frame, String>>,
+    events: &mpsc::UnboundedSender<WebSocketEvent>,
+) -> Result<WebSocketClose, BrowserError> {
+    // Opcode and payload of a message whose later fragments are still due.
+    let mut partial: Option<(u8, Vec<u8>)> = None;
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let (code, reason) = match command {
+                    Some(WebSocketCommand::Send(message)) => {
+                        let (opcode, payload) = match message {
+                            WebSocketMessage::Text(text) => (OPCODE_TEXT, Bytes::from(text)),
+                            WebSocketMessage::Binary(bytes) => (OPCODE_BINARY, bytes),
+                        };
+                        write_frame(writer, opcode, &payload).await?;
+                        continue;
+                    }
+                    Some(WebSocketCommand::Close { code, reason }) => (code, reason),
+                    None => (GOING_AWAY, String::new()),
+                };
+                write_frame(writer, OPCODE_CLOSE, &close_payload(code, &reason)).await?;
+                return Ok(await_close(frames).await.unwrap_or(WebSocketClose {
+                    code: Some(code),
+                    reason,
+                }));
+            }
+            frame = frames.recv() => {
+                let frame = match frame {
+                    Some(Ok(frame)) => frame,
+                    Some(Err(err)) => {
+                        let _ = write_frame(writer, OPCODE_CLOSE, &close_payload(PROTOCOL_ERROR, "")).await;
+                        return Err(BrowserError::WebSocket(err));
+                    }
+                    None => return Ok(WebSocketClose {
+                        code: None,
+                        reason: "connection closed without a close frame".to_owned(),
+                    }),
+                };
+                match frame.opcode {
+                    OPCODE_PING => write_frame(writer, OPCODE_PONG, &frame.payload).await?,
+                    OPCODE_PONG => {}
+                    OPCODE_CLOSE => {
+                        let close = parse_close(&frame.payload);
+                        let echo = close.code.map(|code| close_payload(code, "")).unwrap_or_default();
+                        let _ = write_frame(writer, OPCODE_CLOSE, &echo).await;
+                        return Ok(close);
+                    }
+                    opcode => {
+                        let (opcode, payload) = match (opcode, partial.take()) {
+                            (OPCODE_CONTINUATION, Some((opcode, mut payload))) => {
+                                payload.extend_from_slice(&frame.payload);
+                                (opcode, payload)
+                            }
+                            (OPCODE_TEXT | OPCODE_BINARY, None) => (opcode, frame.payload),
+                            _ => return Err(BrowserError::WebSocket(
+                                "server sent an unexpected fragment".to_owned(),
+                            )),
+                        };
+                        if payload.len() > MAX_MESSAGE_BYTES {
+                            let _ = write_frame(writer, OPCODE_CLOSE, &close_payload(MESSAGE_TOO_BIG, "")).await;
+                            return Err(BrowserError::WebSocket("message too large".to_owned()));
+                        }
+                        if !frame.fin {
+                            partial = Some((opcode, payload));
+                            continue;
+                        }
+                        let message = if opcode == OPCODE_TEXT {
+                            let text = String::from_utf8(payload).map_err(|_| {
+                                BrowserError::WebSocket("server sent invalid UTF-8 text".to_owned())
+                            })?;
+                            WebSocketMessage::Text(text)
+                        } else {
+                            WebSocketMessage::Binary(Bytes::from(payload))
+                        };
+                        let _ = events.send(WebSocketEvent::Message(message));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Waits for the server to answer our close frame, skipping data still in
+/// flight. `None` when it does not answer in time.
+async fn await_close(
+    frames: &mut mpsc::UnboundedReceiver<Result<Frame, String>>,
+) -> Option<WebSocketClose> {
+    let answer = async {
+        while let Some(Ok(frame)) = frames.recv().await {
+            if frame.opcode == OPCODE_CLOSE {
+                return Some(parse_close(&frame.payload));
+            }
+        }
+        None
+    };
+    tokio::time::timeout(CLOSE_TIMEOUT, answer)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Reads frames until the connection ends or breaks the framing rules,
+/// which is reported as the last item.
+async fn read_frames(
+    mut reader: impl AsyncRead + Unpin,
+    frames: mpsc::UnboundedSender<Result<Frame, String>>,
+) {
+    loop {
+        let frame = match read_frame(&mut reader).await {
+            Ok(Some(frame)) => Ok(frame),
+            Ok(None) => return,
+            Err(err) => Err(err),
+        };
+        let failed = frame.is_err();
+        if frames.send(frame).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Next frame, or `None` once the connection is closed.
+async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> Result<Option<Frame>, String> {
+    let mut head = [0u8; 2];
+    match reader.read_exact(&mut head).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    }
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0F;
+    if head[0] & 0x70 != 0 {
+        return Err("server used an extension that was not negotiated".to_owned());
+    }
+    // Only clients mask their frames.
+    if head[1] & 0x80 != 0 {
+        return Err("server sent a masked frame".to_owned());
+    }
+    let len = match head[1] & 0x7F {
+        126 => u64::from(reader.read_u16().await.map_err(|err| err.to_string())?),
+        127 => reader.read_u64().await.map_err(|err| err.to_string())?,
+        len => u64::from(len),
+    };
+    if opcode >= OPCODE_CLOSE && (len > 125 || !fin) {
+        return Err("server sent an invalid control frame".to_owned());
+    }
+    if len > MAX_MESSAGE_BYTES as u64 {
+        return Err("message too large".to_owned());
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader
+        .read_exact(&mut payload)
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(Some(Frame {
+        fin,
+        opcode,
+        payload,
+    }))
+}
+
+/// Writes `payload` as one final frame, masked as clients must.
+async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    opcode: u8,
+    payload: &[u8],
+) -> Result<(), BrowserError> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(0x80 | len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    let mask: [u8; 4] = rand::thread_rng().gen();
+    frame.extend_from_slice(&mask);
+    let start = frame.len();
+    frame.extend_from_slice(payload);
+    apply_mask(&mut frame[start..], mask);
+    let written = match writer.write_all(&frame).await {
+        Ok(()) => writer.flush().await,
+        Err(err) => Err(err),
+    };
+    written.map_err(|err| BrowserError::WebSocket(err.to_string()))
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (index, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[index % 4];
+    }
+}
+
+fn close_payload(code: u16, reason: &str) -> Vec<u8> {
+    let mut payload = code.to_be_bytes().to_vec();
+    // Control frames carry at most 125 bytes.
+    let mut end = reason.len().min(123);
+    while !reason.is_char_boundary(end) {
+        end -= 1;
+    }
+    payload.extend_from_slice(&reason.as_bytes()[..end]);
+    payload
+}
+
+fn parse_close(payload: &[u8]) -> WebSocketClose {
+    match payload {
+        [high, low, reason @ ..] => WebSocketClose {
+            code: Some(u16::from_be_bytes([*high, *low])),
+            reason: String::from_utf8_lossy(reason).into_owned(),
+        },
+        _ => WebSocketClose::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(fin: bool, opcode: u8, payload: &[u8]) -> Frame {
+        Frame {
+            fin,
+            opcode,
+            payload: payload.to_vec(),
+        }
+    }
+
+    /// Opcodes and unmasked payloads of the frames the client wrote.
+    fn client_frames(mut written: &[u8]) -> Vec<(u8, Vec<u8>)> {
+        let mut frames = Vec::new();
+        while let [first, second, rest @ ..] = written {
+            assert_ne!(second & 0x80, 0, "client frames must be masked");
+            let (len, rest) = match second & 0x7F {
+                126 => (u16::from_be_bytes([rest[0], rest[1]]) as usize, &rest[2..]),
+                127 => (
+                    u64::from_be_bytes(rest[..8].try_into().unwrap()) as usize,
+                    &rest[8..],
+                ),
+                len => (len as usize, rest),
+            };
+            let mask = [rest[0], rest[1], rest[2], rest[3]];
+            let mut payload = rest[4..4 + len].to_vec();
+            apply_mask(&mut payload, mask);
+            frames.push((first & 0x0F, payload));
+            written = &rest[4 + len..];
+        }
+        frames
+    }
+
+    /// Relays `frames` from the server, returning how the socket ended,
+    /// the messages passed on and what the client wrote back.
+    async fn relay_frames(
+        frames: Vec<Frame>,
+    ) -> (
+        Result<WebSocketClose, BrowserError>,
+        Vec<WebSocketEvent>,
+        Vec<u8>,
+    ) {
+        let (_commands_tx, mut commands) = mpsc::unbounded_channel();
+        let (frames_tx, mut frames_rx) = mpsc::unbounded_channel();
+        for frame in frames {
+            frames_tx.send(Ok(frame)).unwrap();
+        }
+        drop(frames_tx);
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let mut written = Vec::new();
+        let result = relay(&mut written, &mut commands, &mut frames_rx, &events_tx).await;
+        let mut events = Vec::new();
+        while let Ok(event) = events_rx.try_recv() {
+            events.push(event);
+        }
+        (result, events, written)
+    }
+
+    #[tokio::test]
+    async fn joins_fragments_around_control_frames() {
+        let (result, events, written) = relay_frames(vec![
+            frame(false, OPCODE_TEXT, b"hel"),
+            frame(true, OPCODE_PING, b"are you there"),
+            frame(false, OPCODE_CONTINUATION, b"lo "),
+            frame(true, OPCODE_PONG, b""),
+            frame(true, OPCODE_CONTINUATION, b"world"),
+            frame(true, OPCODE_BINARY, &[1, 2, 3]),
+            frame(true, OPCODE_CLOSE, &close_payload(1000, "bye")),
+        ])
+        .await;
+
+        let close = result.unwrap();
+        assert_eq!(close.code, Some(1000));
+        assert_eq!(close.reason, "bye");
+        assert_eq!(
+            events,
+            [
+                WebSocketEvent::Message(WebSocketMessage::Text("hello world".to_owned())),
+                WebSocketEvent::Message(WebSocketMessage::Binary(Bytes::from_static(&[1, 2, 3]))),
+            ]
+        );
+        assert_eq!(
+            client_frames(&written),
+            [
+                (OPCODE_PONG, b"are you there".to_vec()),
+                (OPCODE_CLOSE, close_payload(1000, "")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_fragments_out_of_order() {
+        let (result, _, _) = relay_frames(vec![frame(true, OPCODE_CONTINUATION, b"x")]).await;
+        assert!(result.is_err());
+        let (result, _, _) = relay_frames(vec![
+            frame(false, OPCODE_TEXT, b"a"),
+            frame(true, OPCODE_TEXT, b"b"),
+        ])
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn closes_on_messages_over_the_cap() {
+        let half = vec![0u8; MAX_MESSAGE_BYTES / 2 + 1];
+        let (result, events, written) = relay_frames(vec![
+            frame(false, OPCODE_BINARY, &half),
+            frame(true, OPCODE_CONTINUATION, &half),
+        ])
+        .await;
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("too large"), "{err}");
+        assert!(events.is_empty());
+        assert_eq!(
+            client_frames(&written),
+            [(OPCODE_CLOSE, close_payload(MESSAGE_TOO_BIG, ""))]
+        );
+
+        // A single frame claiming more is refused before it is read.
+        let mut head = vec![0x82, 127];
+        head.extend_from_slice(&(MAX_MESSAGE_BYTES as u64 + 1).to_be_bytes());
+        let err = read_frame(&mut head.as_slice()).await.err().unwrap();
+        assert!(err.contains("too large"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn reads_16_and_64_bit_lengths() {
+        for len in [126usize, 0xFFFF, 0x1_0000, 70_000] {
+            let mut wire = vec![0x82];
+            if len <= 0xFFFF {
+                wire.push(126);
+                wire.extend_from_slice(&(len as u16).to_be_bytes());
+            } else {
+                wire.push(127);
+                wire.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+            wire.extend(std::iter::repeat_n(7u8, len));
+            let frame = read_frame(&mut wire.as_slice()).await.unwrap().unwrap();
+            assert!(frame.fin);
+            assert_eq!(frame.opcode, OPCODE_BINARY);
+            assert_eq!(frame.payload.len(), len);
+        }
+
+        // Cut off inside the payload.
+        let mut wire = vec![0x82, 127];
+        wire.extend_from_slice(&70_000u64.to_be_bytes());
+        wire.extend_from_slice(&[0; 100]);
+        assert!(read_frame(&mut wire.as_slice()).await.is_err());
+        // A clean end between frames.
+        assert!(read_frame(&mut &[][..]).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_masked_and_malformed_frames() {
+        let masked = [0x81, 0x80 | 2, 1, 2, 3, 4, b'h' ^ 1, b'i' ^ 2];
+        let err = read_frame(&mut &masked[..]).await.err().unwrap();
+        assert!(err.contains("masked"), "{err}");
+
+        let fragmented_ping = [OPCODE_PING, 0];
+        assert!(read_frame(&mut &fragmented_ping[..]).await.is_err());
+        let mut long_close = vec![0x80 | OPCODE_CLOSE, 126];
+        long_close.extend_from_slice(&126u16.to_be_bytes());
+        long_close.extend_from_slice(&[0; 126]);
+        assert!(read_frame(&mut long_close.as_slice()).await.is_err());
+        let extension = [0xC1, 0];
+        assert!(read_frame(&mut &extension[..]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn masks_written_frames_with_the_right_length() {
+        for len in [0, 125, 126, 0xFFFF, 0x1_0000] {
+            let payload: Vec<u8> = (0..len).map(|index| index as u8).collect();
+            let mut written = Vec::new();
+            write_frame(&mut written, OPCODE_BINARY, &payload)
+                .await
+                .unwrap();
+            assert_eq!(written[0], 0x80 | OPCODE_BINARY);
+            assert_eq!(client_frames(&written), [(OPCODE_BINARY, payload)]);
+        }
+    }
+
+    #[test]
+    fn answers_the_rfc_handshake_key() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn parses_close_payloads() {
+        assert_eq!(
+            parse_close(&close_payload(1001, "going")),
+            WebSocketClose {
+                code: Some(1001),
+                reason: "going".to_owned(),
+            }
+        );
+        assert_eq!(parse_close(&[]), WebSocketClose::default());
+        let long = "é".repeat(100);
+        let payload = close_payload(1000, &long);
+        assert!(payload.len() <= 125);
+        assert!(std::str::from_utf8(&payload[2..]).is_ok());
+    }
+}
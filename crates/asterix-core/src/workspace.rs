@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BrowserCore, TabId, TabSnapshot};
+
+/// Identifier for a named group of tabs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WorkspaceId(u64);
+
+impl WorkspaceId {
+    pub fn next(counter: &mut u64) -> Self {
+        let id = *counter;
+        *counter += 1;
+        WorkspaceId(id)
+    }
+}
+
+/// Named set of tabs, e.g. "Work" or "Research".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: WorkspaceId,
+    pub name: String,
+}
+
+/// Persistable description of every workspace and the tabs it holds.
+///
+/// Private tabs are never written to a session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceSession {
+    pub active: usize,
+    pub workspaces: Vec<SavedWorkspace>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedWorkspace {
+    pub name: String,
+    pub tabs: Vec<SavedTab>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTab {
+    pub title: String,
+    pub url: Option<Url>,
+    pub user_agent: Option<String>,
+}
+
+impl BrowserCore {
+    /// Returns all workspaces in display order.
+    pub fn workspaces(&self) -> Vec<Workspace> {
+        self.state.read().workspaces.clone()
+    }
+
+    /// Returns the workspace new tabs are opened in.
+    pub fn active_workspace(&self) -> WorkspaceId {
+        self.state.read().active_workspace
+    }
+
+    pub fn create_workspace(&self, name: impl Into<String>) -> Workspace {
+        let mut guard = self.state.write();
+        let workspace = Workspace {
+            id: WorkspaceId::next(&mut guard.next_workspace_id),
+            name: name.into(),
+        };
+        guard.workspaces.push(workspace.clone());
+        workspace
+    }
+
+    pub fn rename_workspace(&self, id: WorkspaceId, name: impl Into<String>) -> bool {
+        let mut guard = self.state.write();
+        match guard
+            .workspaces
+            .iter_mut()
+            .find(|workspace| workspace.id == id)
+        {
+            Some(workspace) => {
+                workspace.name = name.into();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Makes `id` the active workspace. Returns `false` if it does not exist.
+    pub fn switch_workspace(&self, id: WorkspaceId) -> bool {
+        let mut guard = self.state.write();
+        if !guard.workspaces.iter().any(|workspace| workspace.id == id) {
+            return false;
+        }
+        guard.active_workspace = id;
+        true
+    }
+
+    /// Returns the tabs that belong to `workspace`.
+    pub fn workspace_tabs(&self, workspace: WorkspaceId) -> Vec<TabSnapshot> {
+        self.state
+            .read()
+            .tabs
+            .iter()
+            .filter(|tab| tab.workspace == workspace)
+            .cloned()
+            .collect()
+    }
+
+    /// Captures the current workspaces and their non-private tabs.
+    pub fn export_session(&self) -> WorkspaceSession {
+        let guard = self.state.read();
+        let active = guard
+            .workspaces
+            .iter()
+            .position(|workspace| workspace.id == guard.active_workspace)
+            .unwrap_or_default();
+        let workspaces = guard
+            .workspaces
+            .iter()
+            .map(|workspace| SavedWorkspace {
+                name: workspace.name.clone(),
+                tabs: guard
+                    .tabs
+                    .iter()
+                    .filter(|tab| tab.workspace == workspace.id && !tab.private)
+                    .map(|tab| SavedTab {
+                        title: tab.title.clone(),
+                        url: tab.url.clone(),
+                        user_agent: tab.user_agent.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        WorkspaceSession { active, workspaces }
+    }
+
+    /// Replaces all workspaces and tabs with the contents of `session`.
+    ///
+    /// Restored tabs keep their URL but are not loaded; callers decide when to
+    /// fetch them. Returns the identifiers of the restored tabs.
+    pub fn restore_session(&self, session: WorkspaceSession) -> Vec<TabId> {
+        if session.workspaces.is_empty() {
+            return Vec::new();
+        }
+
+        let mut guard = self.state.write();
+        guard.workspaces.clear();
+        guard.tabs.clear();
+
+        let mut restored = Vec::new();
+        for (index, saved) in session.workspaces.into_iter().enumerate() {
+            let workspace = WorkspaceId::next(&mut guard.next_workspace_id);
+            guard.workspaces.push(Workspace {
+                id: workspace,
+                name: saved.name,
+            });
+            if index == session.active {
+                guard.active_workspace = workspace;
+            }
+            for tab in saved.tabs {
+                let id = TabId::next(&mut guard.next_tab_id);
+                guard.tabs.push(TabSnapshot {
+                    id,
+                    title: tab.title,
+                    url: tab.url,
+                    last_loaded: None,
+                    private: false,
+                    user_agent: tab.user_agent,
+                    workspace,
+                    viewport: None,
+                    favicon: None,
+                    auto_refresh_disabled: false,
+                });
+                restored.push(id);
+            }
+        }
+        if session.active >= guard.workspaces.len() {
+            guard.active_workspace = guard.workspaces[0].id;
+        }
+
+        restored
+    }
+}
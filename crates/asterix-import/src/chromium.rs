@@ -0,0 +1,417 @@
+use std::path::Path;
+
+use aes::Aes128;
+use asterix_core::{Bookmark, HistoryEntry, SavedLogin};
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use chrono::{DateTime, Utc};
+use sha1::Sha1;
+use url::Url;
+
+use crate::{DatabaseCopy, ImportError, MAX_IMPORTED_HISTORY};
+
+/// Microseconds between 1601-01-01, where Chromium counts time from, and the
+/// Unix epoch.
+const WINDOWS_EPOCH_OFFSET_MICROS: i64 = 11_644_473_600_000_000;
+
+/// Prefix of passwords encrypted with the fixed key Chromium uses on Linux
+/// when no system keyring is available.
+const V10_PREFIX: &[u8] = b"v10";
+
+/// `Login Data` schema version from which each decrypted password starts
+/// with a SHA-256 hash of its domain.
+const DOMAIN_HASH_VERSION: i64 = 24;
+
+pub(crate) fn bookmarks(profile: &Path) -> Result<Vec<Bookmark>, ImportError> {
+    let path = profile.join("Bookmarks");
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&path)?)
+        .map_err(|err| ImportError::Format(format!("Bookmarks: {err}")))?;
+    let mut bookmarks = Vec::new();
+    if let Some(roots) = json["roots"].as_object() {
+        for root in roots.values() {
+            collect_bookmarks(root, None, &mut bookmarks);
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// Walks a bookmark tree node, naming folders by their path from the root.
+fn collect_bookmarks(node: &serde_json::Value, folder: Option<&str>, out: &mut Vec<Bookmark>) {
+    let name = node["name"].as_str().unwrap_or_default();
+    match node["type"].as_str() {
+        Some("url") => {
+            let Some(url) = node["url"].as_str().and_then(web_url) else {
+                return;
+            };
+            let added = node["date_added"]
+                .as_str()
+                .and_then(|micros| micros.parse().ok())
+                .and_then(from_windows_micros);
+            out.push(Bookmark {
+                title: if name.is_empty() {
+                    url.to_string()
+                } else {
+                    name.to_owned()
+                },
+                folder: folder.map(str::to_owned),
+                added_at: added.unwrap_or_else(Utc::now),
+                url,
+            });
+        }
+        Some("folder") => {
+            let path = match folder {
+                Some(parent) => format!("{parent}/{name}"),
+                None => name.to_owned(),
+            };
+            for child in node["children"].as_array().into_iter().flatten() {
+                collect_bookmarks(child, Some(&path), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn history(profile: &Path) -> Result<Vec<HistoryEntry>, ImportError> {
+    let path = profile.join("History");
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let copy = DatabaseCopy::open(&path)?;
+    let mut statement = copy.connection().prepare(
+        "SELECT url, title, visit_count, last_visit_time FROM urls \
+         WHERE visit_count > 0 ORDER BY last_visit_time DESC LIMIT ?1",
+    )?;
+    let rows = statement.query_map([MAX_IMPORTED_HISTORY as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+    let mut history = Vec::new();
+    for row in rows {
+        let (url, title, visits, last_visit) = row?;
+        let (Some(url), Some(last_visit)) = (web_url(&url), from_windows_micros(last_visit)) else {
+            continue;
+        };
+        history.push(HistoryEntry {
+            title: if title.is_empty() {
+                url.to_string()
+            } else {
+                title
+            },
+            url,
+            last_visit,
+            visit_count: u32::try_from(visits).unwrap_or(1),
+        });
+    }
+    Ok(history)
+}
+
+/// Reads `Login Data`. Only passwords protected by Chromium's built-in Linux
+/// key can be decrypted; ones locked in the system keyring are counted in a
+/// warning.
+pub(crate) fn logins(
+    profile: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<SavedLogin>, ImportError> {
+    let path = profile.join("Login Data");
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let copy = DatabaseCopy::open(&path)?;
+    let db = copy.connection();
+    let version: i64 = db
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|version| version.parse().ok())
+        .unwrap_or_default();
+
+    let mut key = [0u8; 16];
+    pbkdf2::pbkdf2_hmac::<Sha1>(b"peanuts", b"saltysalt", 1, &mut key);
+
+    let mut statement = db.prepare(
+        "SELECT origin_url, username_value, password_value FROM logins \
+         WHERE blacklisted_by_user = 0",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Vec<u8>>(2)?,
+        ))
+    })?;
+    let mut logins = Vec::new();
+    let mut locked = 0;
+    for row in rows {
+        let (origin, username, encrypted) = row?;
+        let Some(password) = decrypt_v10(&key, &encrypted, version >= DOMAIN_HASH_VERSION) else {
+            locked += 1;
+            continue;
+        };
+        let origin = Url::parse(&origin)
+            .map(|url| url.origin().ascii_serialization())
+            .unwrap_or(origin);
+        logins.push(SavedLogin {
+            origin,
+            username,
+            password,
+            totp: None,
+        });
+    }
+    if locked > 0 {
+        warnings.push(format!(
+            "Passwords locked in the system keyring were not imported ({locked})"
+        ));
+    }
+    Ok(logins)
+}
+
+fn decrypt_v10(key: &[u8; 16], encrypted: &[u8], domain_hashed: bool) -> Option<String> {
+    let ciphertext = encrypted.strip_prefix(V10_PREFIX)?;
+    let plain = cbc::Decryptor::<Aes128>::new_from_slices(key, &[b' '; 16])
+        .ok()?
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .ok()?;
+    let plain = if domain_hashed {
+        plain.get(32..)?.to_vec()
+    } else {
+        plain
+    };
+    String::from_utf8(plain).ok()
+}
+
+fn web_url(url: &str) -> Option<Url> {
+    Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https" | "file" | "ftp"))
+}
+
+fn from_windows_micros(micros: i64) -> Option<DateTime<Utc>> {
+    if micros <= 0 {
+        return None;
+    }
+    DateTime::from_timestamp_micros(micros - WINDOWS_EPOCH_OFFSET_MICROS)
+}
+
+#[cfg(test)]
+mod tests {
+    use cbc::cipher::BlockEncryptMut;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    /// Unix time 1_700_000_000 as Chromium stores it.
+    const NOV_2023: i64 = 1_700_000_000_000_000 + WINDOWS_EPOCH_OFFSET_MICROS;
+
+    fn v10(plain: &[u8]) -> Vec<u8> {
+        let mut key = [0u8; 16];
+        pbkdf2::pbkdf2_hmac::<Sha1>(b"peanuts", b"saltysalt", 1, &mut key);
+        let mut out = V10_PREFIX.to_vec();
+        out.extend(
+            cbc::Encryptor::<Aes128>::new_from_slices(&key, &[b' '; 16])
+                .unwrap()
+                .encrypt_padded_vec_mut::<Pkcs7>(plain),
+        );
+        out
+    }
+
+    /// A `Login Data` at schema `version` holding `logins` as origin,
+    /// username and encrypted password.
+    fn write_login_data(profile: &Path, version: i64, logins: &[(&str, &str, Vec<u8>)]) {
+        let db = rusqlite::Connection::open(profile.join("Login Data")).unwrap();
+        db.execute_batch(
+            "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT);
+             CREATE TABLE logins (origin_url TEXT, username_value TEXT,
+                 password_value BLOB, blacklisted_by_user INTEGER);",
+        )
+        .unwrap();
+        db.execute(
+            "INSERT INTO meta VALUES ('version', ?1)",
+            [version.to_string()],
+        )
+        .unwrap();
+        for (origin, username, password) in logins {
+            db.execute(
+                "INSERT INTO logins VALUES (?1, ?2, ?3, 0)",
+                (origin, username, password),
+            )
+            .unwrap();
+        }
+        db.execute(
+            "INSERT INTO logins VALUES ('https://never.test/', '', x'', 1)",
+            [],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reads_bookmarks_with_folder_paths() {
+        let profile = tempfile::tempdir().unwrap();
+        let bookmarks_json = serde_json::json!({
+            "roots": {
+                "bookmark_bar": {
+                    "type": "folder",
+                    "name": "Bookmarks bar",
+                    "children": [
+                        {
+                            "type": "url",
+                            "name": "Example",
+                            "url": "https://example.com/",
+                            "date_added": NOV_2023.to_string(),
+                        },
+                        {
+                            "type": "folder",
+                            "name": "Work",
+                            "children": [
+                                { "type": "url", "name": "", "url": "https://work.test/" },
+                                { "type": "url", "name": "Settings", "url": "chrome://settings" },
+                            ],
+                        },
+                    ],
+                },
+            },
+            "version": 1,
+        });
+        std::fs::write(
+            profile.path().join("Bookmarks"),
+            serde_json::to_vec(&bookmarks_json).unwrap(),
+        )
+        .unwrap();
+
+        let bookmarks = bookmarks(profile.path()).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].title, "Example");
+        assert_eq!(bookmarks[0].folder.as_deref(), Some("Bookmarks bar"));
+        assert_eq!(bookmarks[0].added_at.timestamp(), 1_700_000_000);
+        assert_eq!(bookmarks[1].title, "https://work.test/");
+        assert_eq!(bookmarks[1].folder.as_deref(), Some("Bookmarks bar/Work"));
+    }
+
+    #[test]
+    fn rejects_a_corrupt_bookmarks_file() {
+        let profile = tempfile::tempdir().unwrap();
+        std::fs::write(profile.path().join("Bookmarks"), b"{\"roots\": {").unwrap();
+        assert!(matches!(
+            bookmarks(profile.path()),
+            Err(ImportError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn reads_visited_history_newest_first() {
+        let profile = tempfile::tempdir().unwrap();
+        let db = rusqlite::Connection::open(profile.path().join("History")).unwrap();
+        db.execute_batch(
+            "CREATE TABLE urls (id INTEGER PRIMARY KEY, url TEXT, title TEXT,
+                 visit_count INTEGER, last_visit_time INTEGER);",
+        )
+        .unwrap();
+        for (url, title, visits, last_visit) in [
+            ("https://old.test/", "Old", 2, NOV_2023 - 1_000_000),
+            ("https://example.com/", "", 5, NOV_2023),
+            ("https://typed.test/", "Typed", 0, NOV_2023),
+            ("chrome://history/", "History", 1, NOV_2023),
+        ] {
+            db.execute(
+                "INSERT INTO urls (url, title, visit_count, last_visit_time) \
+                 VALUES (?1, ?2, ?3, ?4)",
+                (url, title, visits, last_visit),
+            )
+            .unwrap();
+        }
+        drop(db);
+
+        let history = history(profile.path()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].url.as_str(), "https://example.com/");
+        assert_eq!(history[0].title, "https://example.com/");
+        assert_eq!(history[0].visit_count, 5);
+        assert_eq!(history[0].last_visit.timestamp(), 1_700_000_000);
+        assert_eq!(history[1].title, "Old");
+    }
+
+    #[test]
+    fn rejects_a_corrupt_history_database() {
+        let profile = tempfile::tempdir().unwrap();
+        std::fs::write(
+            profile.path().join("History"),
+            b"this is not a database at all, just some text",
+        )
+        .unwrap();
+        assert!(matches!(
+            history(profile.path()),
+            Err(ImportError::Database(_))
+        ));
+    }
+
+    #[test]
+    fn decrypts_v10_passwords() {
+        let profile = tempfile::tempdir().unwrap();
+        write_login_data(
+            profile.path(),
+            23,
+            &[("https://example.com/login", "alice", v10(b"hunter2"))],
+        );
+        let mut warnings = Vec::new();
+        let logins = logins(profile.path(), &mut warnings).unwrap();
+        assert_eq!(
+            logins,
+            [SavedLogin {
+                origin: "https://example.com".into(),
+                username: "alice".into(),
+                password: "hunter2".into(),
+                totp: None,
+            }]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn strips_the_domain_hash_from_newer_databases() {
+        let profile = tempfile::tempdir().unwrap();
+        let mut plain = Sha256::digest(b"example.com").to_vec();
+        plain.extend_from_slice(b"hunter2");
+        write_login_data(
+            profile.path(),
+            DOMAIN_HASH_VERSION,
+            &[("https://example.com/", "alice", v10(&plain))],
+        );
+        let logins = logins(profile.path(), &mut Vec::new()).unwrap();
+        assert_eq!(logins[0].password, "hunter2");
+    }
+
+    #[test]
+    fn counts_passwords_it_cannot_decrypt() {
+        let profile = tempfile::tempdir().unwrap();
+        let mut tampered = v10(b"hunter2");
+        *tampered.last_mut().unwrap() ^= 1;
+        write_login_data(
+            profile.path(),
+            23,
+            &[
+                (
+                    "https://keyring.test/",
+                    "alice",
+                    b"v11\x01\x02\x03".to_vec(),
+                ),
+                ("https://tampered.test/", "bob", tampered),
+                ("https://ok.test/", "carol", v10(b"s3cret")),
+            ],
+        );
+        let mut warnings = Vec::new();
+        let logins = logins(profile.path(), &mut warnings).unwrap();
+        assert_eq!(logins.len(), 1);
+        assert_eq!(logins[0].username, "carol");
+        assert_eq!(
+            warnings,
+            ["Passwords locked in the system keyring were not imported (2)"]
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use asterix_core::{Bookmark, HistoryEntry, SavedLogin};
+use chrono::{DateTime, Utc};
+use url::Url;
+
+use crate::nss::NssKey;
+use crate::{DatabaseCopy, ImportError, MAX_IMPORTED_HISTORY};
+
+/// `moz_bookmarks.type` of a bookmark and of a folder.
+const TYPE_BOOKMARK: i64 = 1;
+const TYPE_FOLDER: i64 = 2;
+
+/// Names Firefox shows for its built-in bookmark folders.
+const ROOT_FOLDERS: [(&str, &str); 4] = [
+    ("menu", "Bookmarks Menu"),
+    ("toolbar", "Bookmarks Toolbar"),
+    ("unfiled", "Other Bookmarks"),
+    ("mobile", "Mobile Bookmarks"),
+];
+
+pub(crate) fn bookmarks(profile: &Path) -> Result<Vec<Bookmark>, ImportError> {
+    let places = DatabaseCopy::open(&profile.join("places.sqlite"))?;
+    let db = places.connection();
+
+    let mut folders: HashMap<i64, (i64, String)> = HashMap::new();
+    let mut statement =
+        db.prepare("SELECT id, parent, title FROM moz_bookmarks WHERE type = ?1")?;
+    let rows = statement.query_map([TYPE_FOLDER], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+        ))
+    })?;
+    for row in rows {
+        let (id, parent, title) = row?;
+        folders.insert(id, (parent, title));
+    }
+
+    let mut statement = db.prepare(
+        "SELECT p.url, b.title, b.parent, b.dateAdded FROM moz_bookmarks b \
+         JOIN moz_places p ON p.id = b.fk WHERE b.type = ?1 ORDER BY b.parent, b.position",
+    )?;
+    let rows = statement.query_map([TYPE_BOOKMARK], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, Option<i64>>(3)?,
+        ))
+    })?;
+    let mut bookmarks = Vec::new();
+    for row in rows {
+        let (url, title, parent, added) = row?;
+        let Some(url) = web_url(&url) else {
+            continue;
+        };
+        bookmarks.push(Bookmark {
+            title: title.unwrap_or_else(|| url.to_string()),
+            folder: folder_path(&folders, parent),
+            added_at: added.and_then(from_micros).unwrap_or_else(Utc::now),
+            url,
+        });
+    }
+    Ok(bookmarks)
+}
+
+pub(crate) fn history(profile: &Path) -> Result<Vec<HistoryEntry>, ImportError> {
+    let places = DatabaseCopy::open(&profile.join("places.sqlite"))?;
+    let mut statement = places.connection().prepare(
+        "SELECT url, title, visit_count, last_visit_date FROM moz_places \
+         WHERE visit_count > 0 AND last_visit_date IS NOT NULL \
+         ORDER BY last_visit_date DESC LIMIT ?1",
+    )?;
+    let rows = statement.query_map([MAX_IMPORTED_HISTORY as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+    let mut history = Vec::new();
+    for row in rows {
+        let (url, title, visits, last_visit) = row?;
+        let (Some(url), Some(last_visit)) = (web_url(&url), from_micros(last_visit)) else {
+            continue;
+        };
+        history.push(HistoryEntry {
+            title: title.unwrap_or_else(|| url.to_string()),
+            url,
+            last_visit,
+            visit_count: u32::try_from(visits).unwrap_or(1),
+        });
+    }
+    Ok(history)
+}
+
+/// Reads `logins.json`, decrypting it with the key in `key4.db`. Logins that
+/// cannot be decrypted are counted in a warning.
+pub(crate) fn logins(
+    profile: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<SavedLogin>, ImportError> {
+    let path = profile.join("logins.json");
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&path)?)
+        .map_err(|err| ImportError::Format(format!("logins.json: {err}")))?;
+    let entries = json["logins"].as_array().cloned().unwrap_or_default();
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+    let key = NssKey::load(&profile.join("key4.db"))?;
+
+    let mut logins = Vec::new();
+    let mut failed = 0;
+    for entry in entries {
+        let origin = entry["hostname"].as_str().unwrap_or_default();
+        let decrypted = entry["encryptedUsername"]
+            .as_str()
+            .and_then(|value| key.decrypt(value))
+            .zip(
+                entry["encryptedPassword"]
+                    .as_str()
+                    .and_then(|value| key.decrypt(value)),
+            );
+        match decrypted {
+            Some((username, password)) if !origin.is_empty() => logins.push(SavedLogin {
+                origin: origin.to_owned(),
+                username,
+                password,
+                totp: None,
+            }),
+            _ => failed += 1,
+        }
+    }
+    if failed > 0 {
+        warnings.push(format!(
+            "Passwords that could not be decrypted were skipped ({failed})"
+        ));
+    }
+    Ok(logins)
+}
+
+/// Joins the titles of the folders above `parent`, skipping the unnamed root.
+fn folder_path(folders: &HashMap<i64, (i64, String)>, mut parent: i64) -> Option<String> {
+    let mut names = Vec::new();
+    while let Some((grandparent, title)) = folders.get(&parent) {
+        if !title.is_empty() {
+            let shown = ROOT_FOLDERS
+                .iter()
+                .find(|(root, _)| *grandparent == 1 && root == title)
+                .map_or(title.as_str(), |(_, shown)| shown);
+            names.push(shown.to_owned());
+        }
+        // Guard against a corrupt database pointing a folder at itself.
+        if *grandparent == parent || names.len() > 32 {
+            break;
+        }
+        parent = *grandparent;
+    }
+    names.reverse();
+    (!names.is_empty()).then(|| names.join("/"))
+}
+
+/// Parses `url` unless it is an internal `place:` query or similar.
+fn web_url(url: &str) -> Option<Url> {
+    Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https" | "file" | "ftp"))
+}
+
+/// Firefox stores times as microseconds since the Unix epoch.
+fn from_micros(micros: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_micros(micros)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nss::tests::{encrypt_field, write_key4};
+
+    /// A `places.sqlite` with a bookmark in the toolbar, one in a subfolder
+    /// of the menu, a `place:` query and three visited pages.
+    fn write_places(profile: &Path) {
+        let db = rusqlite::Connection::open(profile.join("places.sqlite")).unwrap();
+        db.execute_batch(
+            "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT,
+                 visit_count INTEGER, last_visit_date INTEGER);
+             CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER,
+                 parent INTEGER, position INTEGER, title TEXT, dateAdded INTEGER);
+             INSERT INTO moz_places VALUES
+                 (1, 'https://example.com/', 'Example', 3, 1700000000000000),
+                 (2, 'https://news.test/a', NULL, 1, 1710000000000000),
+                 (3, 'place:sort=8&maxResults=10', 'Recent Tags', 0, NULL),
+                 (4, 'https://old.test/', 'Old', 2, 1600000000000000),
+                 (5, 'https://never.test/', 'Never', 0, NULL);
+             INSERT INTO moz_bookmarks VALUES
+                 (1, 2, NULL, 0, 0, '', 0),
+                 (2, 2, NULL, 1, 0, 'menu', 0),
+                 (3, 2, NULL, 1, 1, 'toolbar', 0),
+                 (10, 2, NULL, 2, 0, 'News', 0),
+                 (20, 1, 1, 3, 0, 'Example site', 1690000000000000),
+                 (21, 1, 2, 10, 0, NULL, 1695000000000000),
+                 (22, 1, 3, 3, 1, 'Recent Tags', 0);",
+        )
+        .unwrap();
+    }
+
+    fn write_logins(profile: &Path, logins: serde_json::Value) {
+        std::fs::write(
+            profile.join("logins.json"),
+            serde_json::to_vec(&serde_json::json!({ "logins": logins })).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reads_bookmarks_with_folder_paths() {
+        let profile = tempfile::tempdir().unwrap();
+        write_places(profile.path());
+        let bookmarks = bookmarks(profile.path()).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+
+        let toolbar = &bookmarks[0];
+        assert_eq!(toolbar.title, "Example site");
+        assert_eq!(toolbar.folder.as_deref(), Some("Bookmarks Toolbar"));
+        assert_eq!(toolbar.added_at.timestamp(), 1_690_000_000);
+
+        let menu = &bookmarks[1];
+        assert_eq!(menu.url.as_str(), "https://news.test/a");
+        assert_eq!(menu.title, "https://news.test/a");
+        assert_eq!(menu.folder.as_deref(), Some("Bookmarks Menu/News"));
+        assert_eq!(menu.added_at.timestamp(), 1_695_000_000);
+    }
+
+    #[test]
+    fn reads_visited_history_newest_first() {
+        let profile = tempfile::tempdir().unwrap();
+        write_places(profile.path());
+        let history = history(profile.path()).unwrap();
+        let urls: Vec<_> = history.iter().map(|entry| entry.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://news.test/a",
+                "https://example.com/",
+                "https://old.test/"
+            ]
+        );
+        assert_eq!(history[1].visit_count, 3);
+        assert_eq!(history[1].last_visit.timestamp(), 1_700_000_000);
+    }
+
+    #[test]
+    fn rejects_a_corrupt_places_database() {
+        let profile = tempfile::tempdir().unwrap();
+        std::fs::write(
+            profile.path().join("places.sqlite"),
+            b"this is not a database at all, just some text",
+        )
+        .unwrap();
+        assert!(matches!(
+            bookmarks(profile.path()),
+            Err(ImportError::Database(_))
+        ));
+        assert!(matches!(
+            history(profile.path()),
+            Err(ImportError::Database(_))
+        ));
+    }
+
+    #[test]
+    fn decrypts_logins() {
+        let profile = tempfile::tempdir().unwrap();
+        write_key4(&profile.path().join("key4.db"), b"");
+        write_logins(
+            profile.path(),
+            serde_json::json!([
+                {
+                    "hostname": "https://example.com",
+                    "encryptedUsername": encrypt_field("alice", false),
+                    "encryptedPassword": encrypt_field("hunter2", false),
+                },
+                {
+                    "hostname": "https://mail.test",
+                    "encryptedUsername": encrypt_field("bob", true),
+                    "encryptedPassword": encrypt_field("s3cret", true),
+                },
+                {
+                    "hostname": "https://broken.test",
+                    "encryptedUsername": encrypt_field("carol", true),
+                    "encryptedPassword": "MAA=",
+                },
+            ]),
+        );
+        let mut warnings = Vec::new();
+        let logins = logins(profile.path(), &mut warnings).unwrap();
+        assert_eq!(
+            logins,
+            [
+                SavedLogin {
+                    origin: "https://example.com".into(),
+                    username: "alice".into(),
+                    password: "hunter2".into(),
+                    totp: None,
+                },
+                SavedLogin {
+                    origin: "https://mail.test".into(),
+                    username: "bob".into(),
+                    password: "s3cret".into(),
+                    totp: None,
+                },
+            ]
+        );
+        assert_eq!(
+            warnings,
+            ["Passwords that could not be decrypted were skipped (1)"]
+        );
+    }
+
+    #[test]
+    fn refuses_logins_behind_a_primary_password() {
+        let profile = tempfile::tempdir().unwrap();
+        write_key4(&profile.path().join("key4.db"), b"correct horse");
+        write_logins(
+            profile.path(),
+            serde_json::json!([{
+                "hostname": "https://example.com",
+                "encryptedUsername": encrypt_field("alice", true),
+                "encryptedPassword": encrypt_field("hunter2", true),
+            }]),
+        );
+        let err = logins(profile.path(), &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("primary password"), "{err}");
+    }
+
+    #[test]
+    fn rejects_a_corrupt_logins_file() {
+        let profile = tempfile::tempdir().unwrap();
+        std::fs::write(profile.path().join("logins.json"), b"{\"logins\": [").unwrap();
+        assert!(matches!(
+            logins(profile.path(), &mut Vec::new()),
+            Err(ImportError::Format(_))
+        ));
+
+        // Without saved logins the key database is never needed.
+        write_logins(profile.path(), serde_json::json!([]));
+        assert!(logins(profile.path(), &mut Vec::new()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+mod chromium;
+mod firefox;
+mod nss;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use asterix_core::{Bookmark, HistoryEntry, SavedLogin};
+use thiserror::Error;
+
+/// Most history entries read from another browser, newest first.
+const MAX_IMPORTED_HISTORY: usize = 20_000;
+
+/// Browsers whose profiles can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceBrowser {
+    Firefox,
+    Chrome,
+    Chromium,
+    Brave,
+    Edge,
+}
+
+impl SourceBrowser {
+    pub fn label(self) -> &'static str {
+        match self {
+            SourceBrowser::Firefox => "Firefox",
+            SourceBrowser::Chrome => "Google Chrome",
+            SourceBrowser::Chromium => "Chromium",
+            SourceBrowser::Brave => "Brave",
+            SourceBrowser::Edge => "Microsoft Edge",
+        }
+    }
+
+    fn is_chromium_based(self) -> bool {
+        self != SourceBrowser::Firefox
+    }
+}
+
+/// A browser profile found on this machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportSource {
+    pub browser: SourceBrowser,
+    /// Profile name as the browser shows it, e.g. `default-release`.
+    pub profile: String,
+    pub path: PathBuf,
+}
+
+/// What to read from a profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportSelection {
+    pub bookmarks: bool,
+    pub history: bool,
+    pub passwords: bool,
+}
+
+impl Default for ImportSelection {
+    fn default() -> Self {
+        Self {
+            bookmarks: true,
+            history: true,
+            passwords: false,
+        }
+    }
+}
+
+/// Everything read from a profile. Parts that could not be read are reported
+/// in `warnings` instead of failing the whole import.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedData {
+    pub bookmarks: Vec<Bookmark>,
+    pub history: Vec<HistoryEntry>,
+    pub logins: Vec<SavedLogin>,
+    pub warnings: Vec<String>,
+}
+
+/// Errors surfaced while reading another browser's profile.
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("could not read profile file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not read profile database: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("{0}")]
+    Format(String),
+}
+
+/// Where each browser keeps its profiles, relative to the home directory.
+const PROFILE_ROOTS: &[(SourceBrowser, &str)] = &[
+    (SourceBrowser::Firefox, ".mozilla/firefox"),
+    (
+        SourceBrowser::Firefox,
+        "snap/firefox/common/.mozilla/firefox",
+    ),
+    (
+        SourceBrowser::Firefox,
+        ".var/app/org.mozilla.firefox/.mozilla/firefox",
+    ),
+    (
+        SourceBrowser::Firefox,
+        "Library/Application Support/Firefox/Profiles",
+    ),
+    (SourceBrowser::Chrome, ".config/google-chrome"),
+    (
+        SourceBrowser::Chrome,
+        "Library/Application Support/Google/Chrome",
+    ),
+    (SourceBrowser::Chromium, ".config/chromium"),
+    (SourceBrowser::Chromium, "snap/chromium/common/chromium"),
+    (SourceBrowser::Brave, ".config/BraveSoftware/Brave-Browser"),
+    (
+        SourceBrowser::Brave,
+        "Library/Application Support/BraveSoftware/Brave-Browser",
+    ),
+    (SourceBrowser::Edge, ".config/microsoft-edge"),
+    (
+        SourceBrowser::Edge,
+        "Library/Application Support/Microsoft Edge",
+    ),
+];
+
+/// Lists the browser profiles found under `home`.
+pub fn discover_profiles(home: &Path) -> Vec<ImportSource> {
+    let mut sources = Vec::new();
+    for (browser, root) in PROFILE_ROOTS {
+        let Ok(entries) = std::fs::read_dir(home.join(root)) else {
+            continue;
+        };
+        let mut found: Vec<ImportSource> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| is_profile(*browser, path))
+            .map(|path| ImportSource {
+                browser: *browser,
+                profile: profile_name(*browser, &path),
+                path,
+            })
+            .collect();
+        found.sort_by(|a, b| a.profile.cmp(&b.profile));
+        sources.extend(found);
+    }
+    sources
+}
+
+fn is_profile(browser: SourceBrowser, path: &Path) -> bool {
+    if browser.is_chromium_based() {
+        path.join("History").is_file() || path.join("Bookmarks").is_file()
+    } else {
+        path.join("places.sqlite").is_file()
+    }
+}
+
+fn profile_name(browser: SourceBrowser, path: &Path) -> String {
+    let dir = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if browser.is_chromium_based() {
+        return dir;
+    }
+    // Firefox profile directories are named `<salt>.<name>`.
+    match dir.split_once('.') {
+        Some((_, name)) if !name.is_empty() => name.to_owned(),
+        _ => dir,
+    }
+}
+
+/// Reads the selected data from `source`.
+pub fn import_profile(
+    source: &ImportSource,
+    selection: ImportSelection,
+) -> Result<ImportedData, ImportError> {
+    if !source.path.is_dir() {
+        return Err(ImportError::Format(format!(
+            "{} no longer exists",
+            source.path.display()
+        )));
+    }
+    let mut data = ImportedData::default();
+    let parts: [(bool, &str, Part); 3] = [
+        (selection.bookmarks, "bookmarks", Part::Bookmarks),
+        (selection.history, "history", Part::History),
+        (selection.passwords, "passwords", Part::Passwords),
+    ];
+    for (wanted, name, part) in parts {
+        if !wanted {
+            continue;
+        }
+        let result =
+            match (source.browser.is_chromium_based(), part) {
+                (false, Part::Bookmarks) => {
+                    firefox::bookmarks(&source.path).map(|found| data.bookmarks = found)
+                }
+                (false, Part::History) => {
+                    firefox::history(&source.path).map(|found| data.history = found)
+                }
+                (false, Part::Passwords) => firefox::logins(&source.path, &mut data.warnings)
+                    .map(|found| data.logins = found),
+                (true, Part::Bookmarks) => {
+                    chromium::bookmarks(&source.path).map(|found| data.bookmarks = found)
+                }
+                (true, Part::History) => {
+                    chromium::history(&source.path).map(|found| data.history = found)
+                }
+                (true, Part::Passwords) => chromium::logins(&source.path, &mut data.warnings)
+                    .map(|found| data.logins = found),
+            };
+        if let Err(err) = result {
+            data.warnings
+                .push(format!("Could not import {name}: {err}"));
+        }
+    }
+    Ok(data)
+}
+
+#[derive(Clone, Copy)]
+enum Part {
+    Bookmarks,
+    History,
+    Passwords,
+}
+
+/// Copy of a browser database, so a browser that is still running and holding
+/// a lock on it does not get in the way. Removed when dropped.
+struct DatabaseCopy {
+    dir: PathBuf,
+    connection: Option<rusqlite::Connection>,
+}
+
+impl DatabaseCopy {
+    fn open(path: &Path) -> Result<Self, ImportError> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "asterix-import-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)?;
+        let name = path.file_name().unwrap_or_default();
+        let copy = dir.join(name);
+        std::fs::copy(path, &copy)?;
+        // Recent changes may still sit in the write-ahead log.
+        let mut wal = path.as_os_str().to_owned();
+        wal.push("-wal");
+        let wal = PathBuf::from(wal);
+        if wal.is_file() {
+            let mut wal_copy = copy.as_os_str().to_owned();
+            wal_copy.push("-wal");
+            std::fs::copy(&wal, PathBuf::from(wal_copy))?;
+        }
+        let connection = rusqlite::Connection::open(&copy)?;
+        Ok(Self {
+            dir,
+            connection: Some(connection),
+        })
+    }
+
+    fn connection(&self) -> &rusqlite::Connection {
+        self.connection
+            .as_ref()
+            .expect("the connection lives until the copy is dropped")
+    }
+}
+
+impl Drop for DatabaseCopy {
+    fn drop(&mut self) {
+        // Close the database before deleting it.
+        self.connection.take();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_unreadable_parts_as_warnings() {
+        let profile = tempfile::tempdir().unwrap();
+        std::fs::write(profile.path().join("places.sqlite"), b"not a database").unwrap();
+        std::fs::write(profile.path().join("logins.json"), b"{").unwrap();
+        let source = ImportSource {
+            browser: SourceBrowser::Firefox,
+            profile: "default-release".into(),
+            path: profile.path().to_owned(),
+        };
+        let selection = ImportSelection {
+            passwords: true,
+            ..ImportSelection::default()
+        };
+        let data = import_profile(&source, selection).unwrap();
+        assert!(data.bookmarks.is_empty() && data.history.is_empty() && data.logins.is_empty());
+        assert_eq!(data.warnings.len(), 3);
+        assert!(data.warnings[0].starts_with("Could not import bookmarks:"));
+        assert!(data.warnings[2].starts_with("Could not import passwords: logins.json"));
+
+        let gone = ImportSource {
+            path: profile.path().join("missing"),
+            ..source
+        };
+        assert!(import_profile(&gone, selection).is_err());
+    }
+}
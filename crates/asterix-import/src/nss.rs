@@ -0,0 +1,403 @@
+use std::path::Path;
+
+use aes::{Aes128, Aes256};
+use base64::Engine as _;
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use des::TdesEde3;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::{DatabaseCopy, ImportError};
+
+const OID_PBES2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0d];
+const OID_DES_EDE3_CBC: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x03, 0x07];
+const OID_AES256_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+
+/// What `key4.db` stores encrypted to tell whether the primary password is right.
+const PASSWORD_CHECK: &[u8] = b"password-check";
+
+/// The key the login fields of a Firefox profile are encrypted with. Firefox
+/// keeps it in `key4.db`, itself encrypted with a key derived from the
+/// primary password, which is empty unless the user set one.
+pub(crate) struct NssKey {
+    key: Vec<u8>,
+}
+
+impl NssKey {
+    /// Unlocks the key in `key4.db`, assuming no primary password is set.
+    pub(crate) fn load(key4: &Path) -> Result<Self, ImportError> {
+        if !key4.is_file() {
+            return Err(ImportError::Format("key4.db is missing".to_owned()));
+        }
+        let copy = DatabaseCopy::open(key4)?;
+        let db = copy.connection();
+        let (global_salt, check): (Vec<u8>, Vec<u8>) = db.query_row(
+            "SELECT item1, item2 FROM metaData WHERE id = 'password'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let unsupported = || {
+            ImportError::Format(
+                "the key database was written by an old Firefox version and is not supported"
+                    .to_owned(),
+            )
+        };
+        // With a primary password set, the check does not decrypt or does
+        // not decrypt to the expected text.
+        let check = Pbes2::parse(&check).ok_or_else(unsupported)?;
+        if !check
+            .decrypt(&global_salt)
+            .is_some_and(|check| check.starts_with(PASSWORD_CHECK))
+        {
+            return Err(ImportError::Format(
+                "Firefox passwords are protected by a primary password".to_owned(),
+            ));
+        }
+
+        let wrapped: Vec<u8> = db.query_row("SELECT a11 FROM nssPrivate", [], |row| row.get(0))?;
+        let key = Pbes2::parse(&wrapped)
+            .and_then(|wrapped| wrapped.decrypt(&global_salt))
+            .ok_or_else(unsupported)?;
+        Ok(Self { key })
+    }
+
+    /// Decrypts one base64 encoded login field.
+    pub(crate) fn decrypt(&self, value: &str) -> Option<String> {
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(value)
+            .ok()?;
+        // SEQUENCE { key id, SEQUENCE { algorithm, iv }, ciphertext }
+        let fields = sequence(&der)?;
+        let [_, (TAG_SEQUENCE, algorithm), (TAG_OCTET_STRING, ciphertext)] = fields.as_slice()
+        else {
+            return None;
+        };
+        let algorithm = children(algorithm)?;
+        let [(TAG_OID, oid), (TAG_OCTET_STRING, iv)] = algorithm.as_slice() else {
+            return None;
+        };
+        let plain = match *oid {
+            OID_DES_EDE3_CBC => {
+                cbc::Decryptor::<TdesEde3>::new_from_slices(self.key.get(..24)?, iv)
+                    .ok()?
+                    .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+                    .ok()?
+            }
+            OID_AES256_CBC => cbc::Decryptor::<Aes256>::new_from_slices(self.key.get(..32)?, iv)
+                .ok()?
+                .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+                .ok()?,
+            _ => return None,
+        };
+        String::from_utf8(plain).ok()
+    }
+}
+
+/// PBES2 blob from `key4.db`: PBKDF2-HMAC-SHA256 and AES-CBC.
+struct Pbes2<'a> {
+    salt: &'a [u8],
+    iterations: u32,
+    key_length: usize,
+    iv: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+impl<'a> Pbes2<'a> {
+    fn parse(der: &'a [u8]) -> Option<Self> {
+        // SEQUENCE { SEQUENCE { pbes2, SEQUENCE { kdf, cipher } }, ciphertext }
+        let outer = sequence(der)?;
+        let [(TAG_SEQUENCE, algorithm), (TAG_OCTET_STRING, ciphertext)] = outer.as_slice() else {
+            return None;
+        };
+        let algorithm = children(algorithm)?;
+        let [(TAG_OID, OID_PBES2), (TAG_SEQUENCE, parameters)] = algorithm.as_slice() else {
+            return None;
+        };
+        let parameters = children(parameters)?;
+        let [(TAG_SEQUENCE, kdf), (TAG_SEQUENCE, cipher)] = parameters.as_slice() else {
+            return None;
+        };
+        // kdf: SEQUENCE { pbkdf2, SEQUENCE { salt, iterations, key length, prf } }
+        let kdf = children(kdf)?;
+        let [_, (TAG_SEQUENCE, kdf_parameters)] = kdf.as_slice() else {
+            return None;
+        };
+        let kdf_parameters = children(kdf_parameters)?;
+        let [(TAG_OCTET_STRING, salt), (TAG_INTEGER, iterations), (TAG_INTEGER, key_length), ..] =
+            kdf_parameters.as_slice()
+        else {
+            return None;
+        };
+        let cipher = children(cipher)?;
+        let [_, (TAG_OCTET_STRING, iv)] = cipher.as_slice() else {
+            return None;
+        };
+        Some(Self {
+            salt,
+            iterations: u32::try_from(integer(iterations)?).ok()?,
+            // AES-128 or AES-256.
+            key_length: integer(key_length).filter(|len| matches!(len, 16 | 32))?,
+            iv,
+            ciphertext,
+        })
+    }
+
+    /// Decrypts the blob with an empty primary password.
+    fn decrypt(&self, global_salt: &[u8]) -> Option<Vec<u8>> {
+        let password_hash = Sha1::new().chain_update(global_salt).finalize();
+        let mut key = vec![0u8; self.key_length];
+        pbkdf2::pbkdf2_hmac::<Sha256>(&password_hash, self.salt, self.iterations, &mut key);
+        // NSS stores the IV without the two-byte OCTET STRING header it
+        // hashes in.
+        let mut full_iv = vec![TAG_OCTET_STRING, 0x0e];
+        full_iv.extend_from_slice(self.iv);
+        match key.len() {
+            32 => cbc::Decryptor::<Aes256>::new_from_slices(&key, &full_iv)
+                .ok()?
+                .decrypt_padded_vec_mut::<Pkcs7>(self.ciphertext)
+                .ok(),
+            16 => cbc::Decryptor::<Aes128>::new_from_slices(&key, &full_iv)
+                .ok()?
+                .decrypt_padded_vec_mut::<Pkcs7>(self.ciphertext)
+                .ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Elements of the DER `SEQUENCE` that makes up all of `der`.
+fn sequence(der: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let (TAG_SEQUENCE, content, []) = element(der)? else {
+        return None;
+    };
+    children(content)
+}
+
+/// Splits the content of a constructed element into its children.
+fn children(mut content: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let mut children = Vec::new();
+    while !content.is_empty() {
+        let (tag, value, rest) = element(content)?;
+        children.push((tag, value));
+        content = rest;
+    }
+    Some(children)
+}
+
+/// Reads one tag-length-value element, returning its tag, content and
+/// whatever follows it.
+fn element(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = der.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let length = if first & 0x80 == 0 {
+        usize::from(first)
+    } else {
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let (bytes, after) = rest.split_at(count);
+        rest = after;
+        bytes
+            .iter()
+            .fold(0usize, |length, byte| length << 8 | usize::from(*byte))
+    };
+    if rest.len() < length {
+        return None;
+    }
+    let (content, rest) = rest.split_at(length);
+    Some((tag, content, rest))
+}
+
+fn integer(bytes: &[u8]) -> Option<usize> {
+    if bytes.is_empty() || bytes.len() > 4 {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .fold(0usize, |value, byte| value << 8 | usize::from(*byte)),
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use cbc::cipher::BlockEncryptMut;
+
+    use super::*;
+
+    const OID_PBKDF2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0c];
+    const OID_HMAC_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x09];
+
+    /// Key the login fields of the test profiles are encrypted with.
+    pub(crate) const LOGIN_KEY: [u8; 32] = [0x5a; 32];
+
+    pub(crate) fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        match content.len() {
+            len @ 0..=0x7f => out.push(len as u8),
+            len @ 0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+            len => {
+                out.push(0x82);
+                out.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn seq(parts: &[Vec<u8>]) -> Vec<u8> {
+        der(TAG_SEQUENCE, &parts.concat())
+    }
+
+    /// `plain` sealed as `key4.db` seals its entries, under `password`.
+    fn pbes2(global_salt: &[u8], password: &[u8], plain: &[u8]) -> Vec<u8> {
+        let salt = [3u8; 32];
+        let iv = [4u8; 14];
+        let password_hash = Sha1::new()
+            .chain_update(global_salt)
+            .chain_update(password)
+            .finalize();
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(&password_hash, &salt, 1, &mut key);
+        let mut full_iv = vec![TAG_OCTET_STRING, 0x0e];
+        full_iv.extend_from_slice(&iv);
+        let ciphertext = cbc::Encryptor::<Aes256>::new_from_slices(&key, &full_iv)
+            .unwrap()
+            .encrypt_padded_vec_mut::<Pkcs7>(plain);
+        seq(&[
+            seq(&[
+                der(TAG_OID, OID_PBES2),
+                seq(&[
+                    seq(&[
+                        der(TAG_OID, OID_PBKDF2),
+                        seq(&[
+                            der(TAG_OCTET_STRING, &salt),
+                            der(TAG_INTEGER, &[1]),
+                            der(TAG_INTEGER, &[32]),
+                            seq(&[der(TAG_OID, OID_HMAC_SHA256)]),
+                        ]),
+                    ]),
+                    seq(&[der(TAG_OID, OID_AES256_CBC), der(TAG_OCTET_STRING, &iv)]),
+                ]),
+            ]),
+            der(TAG_OCTET_STRING, &ciphertext),
+        ])
+    }
+
+    /// Writes a `key4.db` holding [`LOGIN_KEY`] under `password`, empty
+    /// unless a primary password is set.
+    pub(crate) fn write_key4(path: &Path, password: &[u8]) {
+        let global_salt = [9u8; 20];
+        let db = rusqlite::Connection::open(path).unwrap();
+        db.execute_batch(
+            "CREATE TABLE metaData (id TEXT PRIMARY KEY, item1, item2);
+             CREATE TABLE nssPrivate (id INTEGER PRIMARY KEY, a11 BLOB);",
+        )
+        .unwrap();
+        let mut check = PASSWORD_CHECK.to_vec();
+        check.extend_from_slice(&[2, 2]);
+        db.execute(
+            "INSERT INTO metaData VALUES ('password', ?1, ?2)",
+            (
+                global_salt.as_slice(),
+                pbes2(&global_salt, password, &check),
+            ),
+        )
+        .unwrap();
+        db.execute(
+            "INSERT INTO nssPrivate (a11) VALUES (?1)",
+            [pbes2(&global_salt, password, &LOGIN_KEY)],
+        )
+        .unwrap();
+    }
+
+    /// `value` encrypted as a `logins.json` field, with Triple DES as older
+    /// profiles do or AES-256 as newer ones do.
+    pub(crate) fn encrypt_field(value: &str, aes: bool) -> String {
+        let (oid, ciphertext, iv) = if aes {
+            let iv = [6u8; 16];
+            let ciphertext = cbc::Encryptor::<Aes256>::new_from_slices(&LOGIN_KEY, &iv)
+                .unwrap()
+                .encrypt_padded_vec_mut::<Pkcs7>(value.as_bytes());
+            (OID_AES256_CBC, ciphertext, iv.to_vec())
+        } else {
+            let iv = [7u8; 8];
+            let ciphertext = cbc::Encryptor::<TdesEde3>::new_from_slices(&LOGIN_KEY[..24], &iv)
+                .unwrap()
+                .encrypt_padded_vec_mut::<Pkcs7>(value.as_bytes());
+            (OID_DES_EDE3_CBC, ciphertext, iv.to_vec())
+        };
+        let field = seq(&[
+            der(TAG_OCTET_STRING, &[0xf8; 16]),
+            seq(&[der(TAG_OID, oid), der(TAG_OCTET_STRING, &iv)]),
+            der(TAG_OCTET_STRING, &ciphertext),
+        ]);
+        base64::engine::general_purpose::STANDARD.encode(field)
+    }
+
+    #[test]
+    fn unlocks_the_key_and_decrypts_both_ciphers() {
+        let dir = tempfile::tempdir().unwrap();
+        let key4 = dir.path().join("key4.db");
+        write_key4(&key4, b"");
+        let key = NssKey::load(&key4).unwrap();
+        assert_eq!(key.key, LOGIN_KEY);
+        assert_eq!(
+            key.decrypt(&encrypt_field("alice", false)).as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            key.decrypt(&encrypt_field("hunter2", true)).as_deref(),
+            Some("hunter2")
+        );
+        assert_eq!(key.decrypt("not base64!"), None);
+        assert_eq!(key.decrypt("MAA="), None);
+    }
+
+    #[test]
+    fn reports_a_primary_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let key4 = dir.path().join("key4.db");
+        write_key4(&key4, b"correct horse");
+        let err = NssKey::load(&key4).err().unwrap();
+        assert!(err.to_string().contains("primary password"), "{err}");
+    }
+
+    #[test]
+    fn rejects_missing_and_corrupt_key_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let key4 = dir.path().join("key4.db");
+        assert!(NssKey::load(&key4).is_err());
+
+        std::fs::write(&key4, b"SQLite format 3\0 but not really").unwrap();
+        assert!(NssKey::load(&key4).is_err());
+
+        std::fs::remove_file(&key4).unwrap();
+        write_key4(&key4, b"");
+        let db = rusqlite::Connection::open(&key4).unwrap();
+        db.execute("UPDATE metaData SET item2 = x'3003020101'", [])
+            .unwrap();
+        drop(db);
+        let err = NssKey::load(&key4).err().unwrap();
+        assert!(err.to_string().contains("not supported"), "{err}");
+    }
+
+    #[test]
+    fn reads_der_lengths() {
+        let long = der(TAG_OCTET_STRING, &[1; 300]);
+        let (tag, content, rest) = element(&long).unwrap();
+        assert_eq!((tag, content.len(), rest.len()), (TAG_OCTET_STRING, 300, 0));
+        assert!(element(&long[..long.len() - 1]).is_none());
+        assert!(element(&[TAG_SEQUENCE, 0x80]).is_none());
+        assert!(element(&[TAG_SEQUENCE, 0x85, 0, 0, 0, 0, 1]).is_none());
+        assert_eq!(integer(&[0x01, 0x00]), Some(256));
+        assert_eq!(integer(&[]), None);
+    }
+}
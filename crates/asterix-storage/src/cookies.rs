@@ -0,0 +1,208 @@
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use cookie_store::{CookieDomain, CookieExpiration, CookieStore, RawCookie};
+use parking_lot::RwLock;
+use reqwest::header::HeaderValue;
+use tracing::debug;
+use url::Url;
+
+use crate::StorageError;
+
+/// One cookie as listed by [`CookieJar::cookies`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    /// Host the cookie belongs to, without a leading dot.
+    pub domain: String,
+    /// Also sent to subdomains of `domain`, as set by a `Domain` attribute.
+    pub include_subdomains: bool,
+    pub path: String,
+    /// When the cookie expires, or `None` if it ends with the session.
+    pub expires: Option<DateTime<Utc>>,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+/// Cookie jar of the regular tabs, kept on disk between sessions. Saving
+/// leaves out session cookies, just as closing the browser ends them.
+#[derive(Debug)]
+pub struct CookieJar {
+    store: RwLock<CookieStore>,
+    /// File the jar is saved to, `None` for one kept in memory only.
+    path: Option<PathBuf>,
+}
+
+impl Default for CookieJar {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl CookieJar {
+    /// Jar that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            store: RwLock::new(CookieStore::default()),
+            path: None,
+        }
+    }
+
+    /// Opens the jar saved at `path`, starting out empty when there is none.
+    /// Cookies that expired in the meantime are dropped.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, StorageError> {
+        let path = path.into();
+        let store = match std::fs::File::open(&path) {
+            Ok(file) => CookieStore::load_json(BufReader::new(file)).map_err(|err| {
+                StorageError::Corrupt {
+                    path: path.clone(),
+                    message: err.to_string(),
+                }
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => CookieStore::default(),
+            Err(source) => return Err(StorageError::Read { path, source }),
+        };
+        debug!(
+            "loaded {} cookies from {}",
+            store.iter_unexpired().count(),
+            path.display()
+        );
+        Ok(Self {
+            store: RwLock::new(store),
+            path: Some(path),
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Writes the persistent cookies that have not expired to the jar's
+    /// file, readable by the current user only.
+    pub fn save(&self) -> Result<(), StorageError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let write_error = |source| StorageError::Write {
+            path: path.clone(),
+            source,
+        };
+        let mut json = Vec::new();
+        self.store
+            .read()
+            .save_json(&mut json)
+            .map_err(|err| StorageError::Corrupt {
+                path: path.clone(),
+                message: err.to_string(),
+            })?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_error)?;
+        }
+        // Write next to the jar first so a crash never leaves half a file.
+        let staging = path.with_extension("json.tmp");
+        std::fs::write(&staging, json).map_err(write_error)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&staging, std::fs::Permissions::from_mode(0o600))
+                .map_err(write_error)?;
+        }
+        std::fs::rename(&staging, path).map_err(write_error)
+    }
+
+    /// Every cookie that has not expired, sorted by domain, path and name.
+    pub fn cookies(&self) -> Vec<StoredCookie> {
+        let mut cookies: Vec<StoredCookie> = self
+            .store
+            .read()
+            .iter_unexpired()
+            .map(stored_cookie)
+            .collect();
+        cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+        cookies
+    }
+
+    /// Cookies a request to `url` would carry.
+    pub fn cookies_for(&self, url: &Url) -> Vec<StoredCookie> {
+        self.store
+            .read()
+            .matches(url)
+            .into_iter()
+            .map(stored_cookie)
+            .collect()
+    }
+
+    /// Stores a cookie given in `Set-Cookie` syntax as if `url` had set it.
+    pub fn add_cookie_str(&self, cookie: &str, url: &Url) {
+        if let Err(err) = self.store.write().parse(cookie, url) {
+            debug!("ignored cookie for {url}: {err}");
+        }
+    }
+
+    /// Deletes `cookie`, returning whether it was in the jar.
+    pub fn remove(&self, cookie: &StoredCookie) -> bool {
+        self.store
+            .write()
+            .remove(&cookie.domain, &cookie.path, &cookie.name)
+            .is_some()
+    }
+
+    /// Deletes the cookies of `domain` and its subdomains, returning how
+    /// many there were.
+    pub fn remove_domain(&self, domain: &str) -> usize {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        let doomed: Vec<StoredCookie> = self
+            .cookies()
+            .into_iter()
+            .filter(|cookie| {
+                cookie.domain == domain || cookie.domain.ends_with(&format!(".{domain}"))
+            })
+            .collect();
+        doomed.iter().filter(|cookie| self.remove(cookie)).count()
+    }
+
+    pub fn clear(&self) {
+        self.store.write().clear();
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies = cookie_headers
+            .filter_map(|header| header.to_str().ok())
+            .filter_map(|header| RawCookie::parse(header.to_owned()).ok());
+        self.store.write().store_response_cookies(cookies, url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .store
+            .read()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
+fn stored_cookie(cookie: &cookie_store::Cookie<'static>) -> StoredCookie {
+    StoredCookie {
+        name: cookie.name().to_owned(),
+        value: cookie.value().to_owned(),
+        domain: String::from(&cookie.domain),
+        include_subdomains: matches!(cookie.domain, CookieDomain::Suffix(_)),
+        path: String::from(&cookie.path),
+        expires: match &cookie.expires {
+            CookieExpiration::AtUtc(at) => DateTime::from_timestamp(at.unix_timestamp(), 0),
+            CookieExpiration::SessionEnd => None,
+        },
+        secure: cookie.secure().unwrap_or(false),
+        http_only: cookie.http_only().unwrap_or(false),
+    }
+}
@@ -0,0 +1,24 @@
+mod cookies;
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub use crate::cookies::{CookieJar, StoredCookie};
+
+/// Failure to read or write one of the on-disk stores.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to write {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{} is damaged: {message}", path.display())]
+    Corrupt { path: PathBuf, message: String },
+}
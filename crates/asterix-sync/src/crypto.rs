@@ -0,0 +1,121 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use sha2::Sha256;
+use url::Url;
+
+/// Marks documents written by this version of the sync format.
+const MAGIC: &[u8; 4] = b"AXS1";
+
+/// PBKDF2 rounds for turning the passphrase into a key.
+const KEY_DERIVATION_ROUNDS: u32 = 210_000;
+
+const NONCE_LEN: usize = 24;
+
+/// Key every document is encrypted with before it leaves the device. The
+/// server only ever sees ciphertext.
+pub(crate) struct SyncKey {
+    cipher: XChaCha20Poly1305,
+}
+
+impl SyncKey {
+    /// Derives the key from `passphrase`, salted with the account so the same
+    /// passphrase gives different keys on different servers.
+    pub(crate) fn derive(passphrase: &str, username: &str, endpoint: &Url) -> Self {
+        let salt = format!("asterix-sync\0{username}\0{endpoint}");
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(
+            passphrase.as_bytes(),
+            salt.as_bytes(),
+            KEY_DERIVATION_ROUNDS,
+            &mut key,
+        );
+        Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key)),
+        }
+    }
+
+    /// Encrypts `plain` as `MAGIC || nonce || ciphertext`.
+    pub(crate) fn seal(&self, plain: &[u8]) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plain)
+            .expect("encrypting into a Vec cannot fail");
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Decrypts a document written by [`SyncKey::seal`], or `None` when it was
+    /// sealed with another key or tampered with.
+    pub(crate) fn open(&self, sealed: &[u8]) -> Option<Vec<u8>> {
+        let rest = sealed.strip_prefix(MAGIC)?;
+        if rest.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    fn endpoint() -> Url {
+        Url::parse("https://sync.example.test/alice/").unwrap()
+    }
+
+    /// Key derivation is deliberately slow, so the tests share one key.
+    fn key() -> &'static SyncKey {
+        static KEY: OnceLock<SyncKey> = OnceLock::new();
+        KEY.get_or_init(|| SyncKey::derive(PASSPHRASE, "alice", &endpoint()))
+    }
+
+    #[test]
+    fn round_trips() {
+        let key = key();
+        let plain = br#"[{"id":"a","modified":"2025-01-01T00:00:00Z"}]"#;
+        let sealed = key.seal(plain);
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(sealed.len(), MAGIC.len() + NONCE_LEN + plain.len() + 16);
+        assert_eq!(key.open(&sealed).as_deref(), Some(plain.as_slice()));
+        assert_eq!(key.open(&key.seal(b"")).as_deref(), Some(b"".as_slice()));
+
+        // A fresh nonce every time.
+        assert_ne!(key.seal(plain), sealed);
+    }
+
+    #[test]
+    fn rejects_another_passphrase_or_account() {
+        let sealed = key().seal(b"bookmarks");
+        let other_passphrase =
+            SyncKey::derive("Correct horse battery staple", "alice", &endpoint());
+        assert_eq!(other_passphrase.open(&sealed), None);
+
+        let other_user = SyncKey::derive(PASSPHRASE, "bob", &endpoint());
+        assert_eq!(other_user.open(&sealed), None);
+    }
+
+    #[test]
+    fn rejects_tampered_documents() {
+        let key = key();
+        let sealed = key.seal(b"bookmarks");
+        for at in [0, MAGIC.len(), MAGIC.len() + NONCE_LEN, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[at] ^= 0x01;
+            assert_eq!(key.open(&tampered), None, "flipped byte {at}");
+        }
+        assert_eq!(key.open(&sealed[..sealed.len() - 1]), None);
+        assert_eq!(key.open(&sealed[..MAGIC.len() + NONCE_LEN - 1]), None);
+        assert_eq!(key.open(b""), None);
+    }
+}
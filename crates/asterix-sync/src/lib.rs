@@ -0,0 +1,275 @@
+mod crypto;
+mod merge;
+mod remote;
+mod state;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, instrument};
+use url::Url;
+
+use crate::crypto::SyncKey;
+use crate::remote::Remote;
+
+pub use crate::merge::{merge, Merged};
+pub use crate::state::SyncState;
+
+/// Attempts at uploading a collection before giving up on a server that keeps
+/// changing underneath us.
+const MAX_UPLOAD_ATTEMPTS: usize = 3;
+
+/// Kinds of browser data that can be synced, each stored as its own encrypted
+/// document on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Collection {
+    Bookmarks,
+    History,
+    Tabs,
+    Settings,
+}
+
+impl Collection {
+    pub const ALL: [Collection; 4] = [
+        Collection::Bookmarks,
+        Collection::History,
+        Collection::Tabs,
+        Collection::Settings,
+    ];
+
+    /// Name of the collection's document on the server.
+    pub fn name(self) -> &'static str {
+        match self {
+            Collection::Bookmarks => "bookmarks",
+            Collection::History => "history",
+            Collection::Tabs => "tabs",
+            Collection::Settings => "settings",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Collection::Bookmarks => "Bookmarks",
+            Collection::History => "History",
+            Collection::Tabs => "Open tabs",
+            Collection::Settings => "Settings",
+        }
+    }
+}
+
+/// One synced item. Deleted items are kept as tombstones so the deletion
+/// reaches other devices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub id: String,
+    pub modified: DateTime<Utc>,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+/// How the server stores the encrypted documents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// Plain HTTP endpoint answering `GET`/`PUT` on `{endpoint}/{collection}`
+    /// with `ETag`s for optimistic locking.
+    #[default]
+    Http,
+    /// WebDAV share; documents are files in the endpoint directory.
+    WebDav,
+}
+
+/// Where and what to sync. Stored in the profile; secrets are kept apart in
+/// [`SyncSecrets`] and never written to disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub endpoint: Option<Url>,
+    pub backend: Backend,
+    /// User name for HTTP basic authentication, if the server wants one.
+    pub username: String,
+    /// Name other devices see this one's open tabs under.
+    pub device_name: String,
+    pub bookmarks: bool,
+    pub history: bool,
+    pub tabs: bool,
+    pub settings: bool,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            backend: Backend::default(),
+            username: String::new(),
+            device_name: "This device".to_owned(),
+            bookmarks: true,
+            history: true,
+            tabs: true,
+            settings: true,
+        }
+    }
+}
+
+impl SyncConfig {
+    pub fn is_enabled(&self, collection: Collection) -> bool {
+        match collection {
+            Collection::Bookmarks => self.bookmarks,
+            Collection::History => self.history,
+            Collection::Tabs => self.tabs,
+            Collection::Settings => self.settings,
+        }
+    }
+
+    pub fn enabled_mut(&mut self, collection: Collection) -> &mut bool {
+        match collection {
+            Collection::Bookmarks => &mut self.bookmarks,
+            Collection::History => &mut self.history,
+            Collection::Tabs => &mut self.tabs,
+            Collection::Settings => &mut self.settings,
+        }
+    }
+}
+
+/// Credentials entered for the session.
+#[derive(Clone, Default)]
+pub struct SyncSecrets {
+    /// Password for HTTP basic authentication; empty to send none.
+    pub password: String,
+    /// Passphrase the encryption key is derived from. Every device syncing
+    /// with the same account must use the same one.
+    pub passphrase: String,
+}
+
+impl std::fmt::Debug for SyncSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SyncSecrets { .. }")
+    }
+}
+
+/// Errors surfaced while syncing.
+#[derive(Debug, Error)]
+pub enum SyncError {
+    #[error("no sync server configured")]
+    NotConfigured,
+    #[error("enter the sync passphrase first")]
+    MissingPassphrase,
+    #[error("network request failed: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("server responded with HTTP {0}")]
+    Status(u16),
+    #[error("the server keeps changing {0}; try again later")]
+    Contended(&'static str),
+    #[error("could not decrypt {0}; check the passphrase")]
+    Decrypt(&'static str),
+    #[error("{0} on the server is not valid sync data")]
+    Corrupt(&'static str),
+}
+
+/// Local records of one collection to sync, with the base they were staged
+/// against.
+#[derive(Debug, Clone)]
+pub struct SyncBatch {
+    pub collection: Collection,
+    pub base: Vec<SyncRecord>,
+    pub local: Vec<SyncRecord>,
+}
+
+/// Result of syncing one collection.
+#[derive(Debug, Clone)]
+pub struct SyncOutcome {
+    pub collection: Collection,
+    /// Merged records, including tombstones, to apply locally and keep as the
+    /// new [`SyncState`].
+    pub records: Vec<SyncRecord>,
+    /// Records changed by other devices since the last sync.
+    pub pulled: usize,
+    /// Whether local changes were uploaded.
+    pub pushed: bool,
+    /// Items changed on both sides; the newer change was kept.
+    pub conflicts: usize,
+}
+
+/// Talks to one sync server with one encryption key.
+pub struct SyncClient {
+    remote: Remote,
+    key: SyncKey,
+}
+
+impl SyncClient {
+    pub fn new(config: &SyncConfig, secrets: &SyncSecrets) -> Result<Self, SyncError> {
+        let endpoint = config.endpoint.clone().ok_or(SyncError::NotConfigured)?;
+        if secrets.passphrase.is_empty() {
+            return Err(SyncError::MissingPassphrase);
+        }
+        let key = SyncKey::derive(&secrets.passphrase, &config.username, &endpoint);
+        let remote = Remote::new(
+            endpoint,
+            config.backend,
+            &config.username,
+            &secrets.password,
+        )?;
+        Ok(Self { remote, key })
+    }
+
+    /// Merges `local` with the server copy of `collection`, using `base` from
+    /// the last sync to spot conflicts, and uploads the result when it differs
+    /// from what the server had.
+    #[instrument(skip(self, base, local), fields(collection = collection.name()))]
+    pub async fn sync(
+        &self,
+        collection: Collection,
+        base: &[SyncRecord],
+        local: Vec<SyncRecord>,
+    ) -> Result<SyncOutcome, SyncError> {
+        for _ in 0..MAX_UPLOAD_ATTEMPTS {
+            let (remote_records, version) = match self.remote.fetch(collection).await? {
+                Some(document) => {
+                    let plain = self
+                        .key
+                        .open(&document.body)
+                        .ok_or(SyncError::Decrypt(collection.name()))?;
+                    let records: Vec<SyncRecord> = serde_json::from_slice(&plain)
+                        .map_err(|_| SyncError::Corrupt(collection.name()))?;
+                    (records, document.version)
+                }
+                None => (Vec::new(), None),
+            };
+
+            let merged = merge(base, &local, &remote_records, Utc::now());
+            if merged.records == remote_records {
+                return Ok(SyncOutcome {
+                    collection,
+                    records: merged.records,
+                    pulled: merged.pulled,
+                    pushed: false,
+                    conflicts: merged.conflicts,
+                });
+            }
+
+            let plain = serde_json::to_vec(&merged.records)
+                .map_err(|_| SyncError::Corrupt(collection.name()))?;
+            if self
+                .remote
+                .store(collection, self.key.seal(&plain), version.as_deref())
+                .await?
+            {
+                return Ok(SyncOutcome {
+                    collection,
+                    records: merged.records,
+                    pulled: merged.pulled,
+                    pushed: true,
+                    conflicts: merged.conflicts,
+                });
+            }
+            debug!(
+                "{} changed on the server during sync, retrying",
+                collection.name()
+            );
+        }
+        Err(SyncError::Contended(collection.name()))
+    }
+}
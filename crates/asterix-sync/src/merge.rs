@@ -0,0 +1,209 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::SyncRecord;
+
+/// Tombstones are dropped once every device has had this long to see them.
+const TOMBSTONE_LIFETIME_DAYS: i64 = 90;
+
+/// Records of a collection after merging both sides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    /// Winning version of every item, sorted by id.
+    pub records: Vec<SyncRecord>,
+    /// Items where the server's version replaced the local one.
+    pub pulled: usize,
+    /// Items changed both locally and on the server since `base`.
+    pub conflicts: usize,
+}
+
+/// Three-way merge of a collection. `base` is what both sides agreed on at the
+/// last sync; an item changed on both sides since then is a conflict and the
+/// more recent change wins, with the server winning ties so every device
+/// settles on the same result.
+pub fn merge(
+    base: &[SyncRecord],
+    local: &[SyncRecord],
+    remote: &[SyncRecord],
+    now: DateTime<Utc>,
+) -> Merged {
+    let base: HashMap<&str, &SyncRecord> = base
+        .iter()
+        .map(|record| (record.id.as_str(), record))
+        .collect();
+    let mut sides: BTreeMap<&str, (Option<&SyncRecord>, Option<&SyncRecord>)> = BTreeMap::new();
+    for record in local {
+        sides.entry(&record.id).or_default().0 = Some(record);
+    }
+    for record in remote {
+        sides.entry(&record.id).or_default().1 = Some(record);
+    }
+
+    let expired = now - Duration::days(TOMBSTONE_LIFETIME_DAYS);
+    let mut merged = Merged {
+        records: Vec::with_capacity(sides.len()),
+        pulled: 0,
+        conflicts: 0,
+    };
+    for (id, sides) in sides {
+        let winner = match sides {
+            (Some(local), None) => local,
+            (None, Some(remote)) => {
+                merged.pulled += 1;
+                remote
+            }
+            (Some(local), Some(remote)) if same_content(local, remote) => remote,
+            (Some(local), Some(remote)) => {
+                let ancestor = base.get(id).copied();
+                let changed_locally = ancestor.is_none_or(|base| !same_content(base, local));
+                let changed_remotely = ancestor.is_none_or(|base| !same_content(base, remote));
+                if changed_locally && changed_remotely {
+                    merged.conflicts += 1;
+                }
+                let local_wins = if changed_locally != changed_remotely {
+                    changed_locally
+                } else {
+                    local.modified > remote.modified
+                };
+                if local_wins {
+                    local
+                } else {
+                    merged.pulled += 1;
+                    remote
+                }
+            }
+            (None, None) => continue,
+        };
+        if winner.deleted && winner.modified < expired {
+            continue;
+        }
+        merged.records.push(winner.clone());
+    }
+    merged
+}
+
+fn same_content(a: &SyncRecord, b: &SyncRecord) -> bool {
+    a.deleted == b.deleted && a.data == b.data
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_750_000_000, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn record(id: &str, minutes: i64, data: serde_json::Value) -> SyncRecord {
+        SyncRecord {
+            id: id.to_owned(),
+            modified: at(minutes),
+            deleted: false,
+            data,
+        }
+    }
+
+    fn tombstone(id: &str, minutes: i64) -> SyncRecord {
+        SyncRecord {
+            id: id.to_owned(),
+            modified: at(minutes),
+            deleted: true,
+            data: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn takes_one_sided_changes_without_conflict() {
+        let base = [record("a", 0, json!(1)), record("b", 0, json!(1))];
+        let local = [record("a", 5, json!(2)), record("b", 0, json!(1))];
+        // The server copy of `b` is older than the local one but still wins,
+        // since only the server changed it.
+        let remote = [
+            record("a", 0, json!(1)),
+            record("b", -5, json!(3)),
+            record("c", 1, json!("new")),
+        ];
+        let merged = merge(&base, &local, &remote, at(10));
+        assert_eq!(
+            merged.records,
+            [
+                record("a", 5, json!(2)),
+                record("b", -5, json!(3)),
+                record("c", 1, json!("new")),
+            ]
+        );
+        assert_eq!((merged.pulled, merged.conflicts), (2, 0));
+    }
+
+    #[test]
+    fn newer_change_wins_a_conflict() {
+        let base = [record("a", 0, json!("base")), record("b", 0, json!("base"))];
+        let local = [
+            record("a", 5, json!("local")),
+            record("b", 5, json!("local")),
+        ];
+        let remote = [
+            record("a", 3, json!("remote")),
+            record("b", 7, json!("remote")),
+        ];
+        let merged = merge(&base, &local, &remote, at(10));
+        assert_eq!(
+            merged.records,
+            [
+                record("a", 5, json!("local")),
+                record("b", 7, json!("remote"))
+            ]
+        );
+        assert_eq!((merged.pulled, merged.conflicts), (1, 2));
+    }
+
+    #[test]
+    fn server_wins_a_tie_and_a_first_sync_counts_as_conflict() {
+        let local = [record("a", 5, json!("local"))];
+        let remote = [record("a", 5, json!("remote"))];
+        let merged = merge(&[], &local, &remote, at(10));
+        assert_eq!(merged.records, remote);
+        assert_eq!((merged.pulled, merged.conflicts), (1, 1));
+
+        // The same content on both sides is no conflict, whatever the times.
+        let remote = [record("a", 1, json!("local"))];
+        let merged = merge(&[], &local, &remote, at(10));
+        assert_eq!(merged.records, remote);
+        assert_eq!((merged.pulled, merged.conflicts), (0, 0));
+    }
+
+    #[test]
+    fn deletion_conflicts_with_an_edit() {
+        let base = [record("a", 0, json!(1)), record("b", 0, json!(1))];
+        let local = [tombstone("a", 5), tombstone("b", 5)];
+        let remote = [record("a", 3, json!(2)), record("b", 8, json!(2))];
+        let merged = merge(&base, &local, &remote, at(10));
+        assert_eq!(
+            merged.records,
+            [tombstone("a", 5), record("b", 8, json!(2))]
+        );
+        assert_eq!((merged.pulled, merged.conflicts), (1, 2));
+
+        // A deletion on one side alone goes through.
+        let local = [record("a", 0, json!(1))];
+        let remote = [tombstone("a", 2)];
+        let merged = merge(&base[..1], &local, &remote, at(10));
+        assert_eq!(merged.records, remote);
+        assert_eq!((merged.pulled, merged.conflicts), (1, 0));
+    }
+
+    #[test]
+    fn drops_expired_tombstones() {
+        let local = [tombstone("old", 0), tombstone("recent", 0)];
+        let remote = [tombstone("old", 0)];
+        let now = at(0) + Duration::days(TOMBSTONE_LIFETIME_DAYS);
+        let merged = merge(&[], &local, &remote, now + Duration::minutes(1));
+        assert!(merged.records.is_empty());
+
+        let merged = merge(&[], &local, &remote, now);
+        assert_eq!(merged.records, local);
+    }
+}
@@ -0,0 +1,123 @@
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Method, StatusCode};
+use url::Url;
+
+use crate::{Backend, Collection, SyncError};
+
+/// Encrypted document as stored on the server.
+pub(crate) struct Document {
+    pub(crate) body: Vec<u8>,
+    /// `ETag` to make the next upload conditional on.
+    pub(crate) version: Option<String>,
+}
+
+/// The server side of sync: one opaque document per collection.
+pub(crate) struct Remote {
+    client: reqwest::Client,
+    endpoint: Url,
+    backend: Backend,
+    username: String,
+    password: String,
+}
+
+impl Remote {
+    pub(crate) fn new(
+        mut endpoint: Url,
+        backend: Backend,
+        username: &str,
+        password: &str,
+    ) -> Result<Self, SyncError> {
+        // Resolve documents inside the endpoint rather than next to it.
+        if !endpoint.path().ends_with('/') {
+            endpoint.set_path(&format!("{}/", endpoint.path()));
+        }
+        Ok(Self {
+            client: reqwest::Client::builder().build()?,
+            endpoint,
+            backend,
+            username: username.to_owned(),
+            password: password.to_owned(),
+        })
+    }
+
+    fn url(&self, collection: Collection) -> Url {
+        let name = match self.backend {
+            Backend::Http => collection.name().to_owned(),
+            Backend::WebDav => format!("{}.axsync", collection.name()),
+        };
+        self.endpoint
+            .join(&name)
+            .expect("collection names are valid relative URLs")
+    }
+
+    fn request(&self, method: Method, url: Url) -> reqwest::RequestBuilder {
+        let builder = self.client.request(method, url);
+        if self.username.is_empty() {
+            builder
+        } else {
+            builder.basic_auth(&self.username, Some(&self.password))
+        }
+    }
+
+    /// Downloads the document of `collection`, or `None` if nothing was synced yet.
+    pub(crate) async fn fetch(
+        &self,
+        collection: Collection,
+    ) -> Result<Option<Document>, SyncError> {
+        let response = self
+            .request(Method::GET, self.url(collection))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(SyncError::Status(response.status().as_u16()));
+        }
+        let version = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        let body = response.bytes().await?.to_vec();
+        Ok(Some(Document { body, version }))
+    }
+
+    /// Uploads `body` unless the server copy changed since `version` was read.
+    /// Returns `false` when it did and the merge has to be redone.
+    pub(crate) async fn store(
+        &self,
+        collection: Collection,
+        body: Vec<u8>,
+        version: Option<&str>,
+    ) -> Result<bool, SyncError> {
+        if self.backend == Backend::WebDav {
+            self.create_directory().await?;
+        }
+        let request = self
+            .request(Method::PUT, self.url(collection))
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .body(body);
+        let request = match version {
+            Some(version) => request.header(IF_MATCH, version),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(SyncError::Status(status.as_u16())),
+        }
+    }
+
+    /// Makes sure the endpoint directory exists on a WebDAV share.
+    async fn create_directory(&self) -> Result<(), SyncError> {
+        let mkcol = Method::from_bytes(b"MKCOL").expect("MKCOL is a valid method");
+        let response = self.request(mkcol, self.endpoint.clone()).send().await?;
+        match response.status() {
+            // 405 means the directory is already there.
+            status if status.is_success() || status == StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            status => Err(SyncError::Status(status.as_u16())),
+        }
+    }
+}
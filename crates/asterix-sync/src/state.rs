@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Collection, SyncRecord};
+
+/// What every collection looked like after the last successful sync. Used as
+/// the merge base and to tell which local items changed since.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncState {
+    collections: BTreeMap<Collection, Vec<SyncRecord>>,
+    pub last_synced: Option<DateTime<Utc>>,
+}
+
+impl SyncState {
+    pub fn records(&self, collection: Collection) -> &[SyncRecord] {
+        self.collections
+            .get(&collection)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Turns the current local items of `collection` into records. Items whose
+    /// data is unchanged since the last sync keep their timestamp, changed and
+    /// new ones are stamped `now`, and synced items that are gone locally
+    /// become tombstones.
+    pub fn stage(
+        &self,
+        collection: Collection,
+        current: Vec<(String, serde_json::Value)>,
+        now: DateTime<Utc>,
+    ) -> Vec<SyncRecord> {
+        let synced = self.records(collection);
+        let mut records: Vec<SyncRecord> = current
+            .into_iter()
+            .map(|(id, data)| {
+                let unchanged = synced
+                    .iter()
+                    .find(|record| record.id == id)
+                    .filter(|record| !record.deleted && record.data == data);
+                SyncRecord {
+                    modified: unchanged.map_or(now, |record| record.modified),
+                    id,
+                    deleted: false,
+                    data,
+                }
+            })
+            .collect();
+        for record in synced {
+            if records.iter().any(|current| current.id == record.id) {
+                continue;
+            }
+            records.push(if record.deleted {
+                record.clone()
+            } else {
+                SyncRecord {
+                    id: record.id.clone(),
+                    modified: now,
+                    deleted: true,
+                    data: serde_json::Value::Null,
+                }
+            });
+        }
+        records
+    }
+
+    /// Remembers `records` as the agreed state of `collection`.
+    pub fn commit(&mut self, collection: Collection, records: Vec<SyncRecord>, now: DateTime<Utc>) {
+        self.collections.insert(collection, records);
+        self.last_synced = Some(now);
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use asterix_browser::{network_address, NetworkEntry, ProxyMode, TabSnapshot};
+use chrono::Utc;
+use eframe::egui;
+use url::Url;
+
+use crate::composer::format_size;
+use crate::{profile_dir, ShellApp};
+
+/// Most rows listed at once on `about:cache` and `about:network`.
+const MAX_ROWS: usize = 500;
+
+enum TabsAction {
+    Switch(TabSnapshot),
+}
+
+impl ShellApp {
+    /// `about:version`: build, platform and the settings requests go out with.
+    pub(crate) fn render_version_page(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!("ASTERIX {}", env!("CARGO_PKG_VERSION")));
+        ui.separator();
+        let proxy = self.handle.proxy_settings();
+        let rows = [
+            (
+                "Build",
+                if cfg!(debug_assertions) {
+                    "Debug".to_owned()
+                } else {
+                    "Release".to_owned()
+                },
+            ),
+            (
+                "Platform",
+                format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            ),
+            (
+                "User agent",
+                self.handle.user_agent().unwrap_or("Default").to_owned(),
+            ),
+            (
+                "Profile",
+                profile_dir().map_or_else(
+                    || "None, nothing is saved".to_owned(),
+                    |dir| dir.display().to_string(),
+                ),
+            ),
+            (
+                "HTTP cache",
+                self.handle.http_cache().map_or_else(
+                    || "Off".to_owned(),
+                    |cache| cache.dir().display().to_string(),
+                ),
+            ),
+            (
+                "Proxy",
+                match proxy.mode {
+                    ProxyMode::Direct => "None".to_owned(),
+                    ProxyMode::System => "System settings".to_owned(),
+                    ProxyMode::Manual => [proxy.http, proxy.https]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                },
+            ),
+            ("IP version", self.handle.ip_preference().label().to_owned()),
+            (
+                "HTTP version",
+                self.handle.http_version().label().to_owned(),
+            ),
+            ("Compression", {
+                let codings = self.handle.content_codings();
+                if codings.is_empty() {
+                    "None".to_owned()
+                } else {
+                    codings
+                        .iter()
+                        .map(|coding| coding.token())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            }),
+            ("Image cache", {
+                let images = self.handle.image_cache_stats();
+                format!(
+                    "{} images, {} of {}, {} reused",
+                    images.images,
+                    format_size(images.bytes),
+                    format_size(images.budget),
+                    images.hits
+                )
+            }),
+            (
+                "Local address",
+                network_address().map_or_else(|| "Offline".to_owned(), |addr| addr.to_string()),
+            ),
+        ];
+        egui::Grid::new("about_version")
+            .num_columns(2)
+            .spacing([24.0, 6.0])
+            .show(ui, |ui| {
+                for (name, value) in rows {
+                    ui.strong(name);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// `about:tabs`: every tab of every workspace and what state it is in.
+    pub(crate) fn render_tabs_page(&mut self, ui: &mut egui::Ui) {
+        ui.ctx().request_repaint_after(Duration::from_secs(1));
+        let tabs = self.handle.tabs();
+        let workspaces: HashMap<_, _> = self
+            .handle
+            .workspaces()
+            .into_iter()
+            .map(|workspace| (workspace.id, workspace.name))
+            .collect();
+        let mut action = None;
+
+        ui.heading("Tabs");
+        ui.weak(format!(
+            "{} tabs in {} workspaces",
+            tabs.len(),
+            workspaces.len()
+        ));
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("about_tabs")
+                .num_columns(5)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for header in ["Title", "Address", "Workspace", "State", "Last loaded"] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for tab in &tabs {
+                        let title = if tab.private {
+                            format!("{} (private)", tab.title)
+                        } else {
+                            tab.title.clone()
+                        };
+                        if ui.link(title).clicked() {
+                            action = Some(TabsAction::Switch(tab.clone()));
+                        }
+                        ui.label(tab.url.as_ref().map(Url::as_str).unwrap_or_default());
+                        ui.label(
+                            workspaces
+                                .get(&tab.workspace)
+                                .map(String::as_str)
+                                .unwrap_or_default(),
+                        );
+                        ui.label(self.tab_state(tab));
+                        ui.label(
+                            tab.last_loaded
+                                .map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+                                .unwrap_or_default(),
+                        );
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if let Some(TabsAction::Switch(tab)) = action {
+            self.switch_workspace(tab.workspace);
+            self.switch_to_tab(tab);
+            self.refresh_tabs();
+        }
+    }
+
+    fn tab_state(&self, tab: &TabSnapshot) -> &'static str {
+        if self.nav_jobs.iter().any(|job| job.tab() == tab.id) {
+            "Loading"
+        } else if self.pages.contains_key(&tab.id) {
+            "Loaded"
+        } else if self.deferred_pages.contains_key(&tab.id) {
+            "Loaded in background"
+        } else if tab.url.is_some() {
+            "Hibernated"
+        } else {
+            "Empty"
+        }
+    }
+
+    /// `about:cache`: documents in the HTTP cache and the preload cache.
+    pub(crate) fn render_cache_page(&mut self, ui: &mut egui::Ui) {
+        let mut open = None;
+        let mut clear = false;
+
+        ui.heading("Cache");
+        let Some(cache) = self.handle.http_cache() else {
+            ui.label("The HTTP cache is off, so pages are always fetched from the network.");
+            return;
+        };
+        let documents = cache.documents();
+        let total: u64 = documents.iter().map(|document| document.size).sum();
+        let preloaded = self.handle.preloaded_documents();
+        ui.horizontal(|ui| {
+            ui.weak(format!(
+                "{} documents, {} in {}",
+                documents.len(),
+                format_size(total as usize),
+                cache.dir().display()
+            ));
+            clear = ui
+                .add_enabled(
+                    !documents.is_empty() || !preloaded.is_empty(),
+                    egui::Button::new("Clear Cache"),
+                )
+                .clicked();
+        });
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            if !preloaded.is_empty() {
+                ui.strong("Preloaded");
+                let now = Utc::now();
+                for (url, expires_at) in &preloaded {
+                    ui.horizontal(|ui| {
+                        if ui.link(url.as_str()).clicked() {
+                            open = Some(url.clone());
+                        }
+                        ui.weak(format!(
+                            "used for {} more seconds",
+                            (*expires_at - now).num_seconds().max(0)
+                        ));
+                    });
+                }
+                ui.separator();
+            }
+            egui::Grid::new("about_cache")
+                .num_columns(6)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for header in ["Address", "Status", "Type", "Size", "Stored", ""] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for document in documents.iter().take(MAX_ROWS) {
+                        if ui.link(document.url.as_str()).clicked() {
+                            open = Some(document.url.clone());
+                        }
+                        ui.label(document.status.to_string());
+                        ui.label(document.content_type.as_deref().unwrap_or_default());
+                        ui.label(format_size(document.size as usize));
+                        ui.label(document.stored_at.format("%Y-%m-%d %H:%M").to_string());
+                        if document.fresh {
+                            ui.label("Fresh");
+                        } else {
+                            ui.weak("Stale");
+                        }
+                        ui.end_row();
+                    }
+                });
+            if documents.len() > MAX_ROWS {
+                ui.weak(format!("{} more not listed.", documents.len() - MAX_ROWS));
+            }
+        });
+
+        if clear {
+            self.handle.clear_http_cache();
+            self.status_line = "Cache cleared".to_owned();
+        } else if let Some(url) = open {
+            self.navigate_active(url);
+        }
+    }
+
+    /// `about:network`: totals of the network log, connection reuse per host
+    /// and the latest requests.
+    pub(crate) fn render_network_page(&mut self, ui: &mut egui::Ui) {
+        ui.ctx().request_repaint_after(Duration::from_secs(1));
+        let log = self.handle.network_log();
+        let hosts = self.handle.connection_stats();
+        let in_flight = log
+            .iter()
+            .filter(|entry| entry.status.is_none() && entry.error.is_none())
+            .count();
+        let failed = log.iter().filter(|entry| entry.error.is_some()).count();
+        let received: usize = log.iter().map(|entry| entry.size).sum();
+        let mut by_kind: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in &log {
+            *by_kind.entry(entry.kind.label()).or_default() += 1;
+        }
+        let mut clear = false;
+
+        ui.heading("Network");
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} requests, {in_flight} in flight, {failed} failed, {} received",
+                log.len(),
+                format_size(received)
+            ));
+            clear = ui
+                .add_enabled(!log.is_empty(), egui::Button::new("Clear Log"))
+                .clicked();
+        });
+        ui.weak(
+            by_kind
+                .iter()
+                .map(|(kind, count)| format!("{kind} {count}"))
+                .collect::<Vec<_>>()
+                .join(" \u{b7} "),
+        );
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.strong("Connections");
+            egui::Grid::new("about_network_hosts")
+                .num_columns(5)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for header in ["Host", "Requests", "Opened", "Reused", "Protocols"] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for host in &hosts {
+                        ui.label(&host.host);
+                        ui.label(host.requests.to_string());
+                        ui.label(format!("{} ({} open)", host.opened, host.open));
+                        ui.label(format!("{:.0}%", host.reuse_ratio() * 100.0));
+                        ui.label(host.protocols.join(", "));
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+            ui.strong("Latest requests");
+            egui::Grid::new("about_network_requests")
+                .num_columns(6)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for header in ["Time", "Kind", "Method", "Status", "Address", "Duration"] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for entry in log.iter().rev().take(MAX_ROWS) {
+                        request_row(ui, entry);
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if clear {
+            self.handle.clear_network_log();
+        }
+    }
+}
+
+fn request_row(ui: &mut egui::Ui, entry: &NetworkEntry) {
+    ui.label(entry.started_at.format("%H:%M:%S").to_string());
+    ui.label(entry.kind.label());
+    ui.label(&entry.method);
+    match (entry.status, &entry.error) {
+        (_, Some(err)) => {
+            ui.colored_label(ui.visuals().error_fg_color, "Failed")
+                .on_hover_text(err);
+        }
+        (Some(status), None) => {
+            ui.label(status.to_string());
+        }
+        (None, None) => {
+            ui.weak("Pending");
+        }
+    }
+    ui.label(entry.url.as_str());
+    ui.label(
+        entry
+            .duration_ms
+            .map(|ms| format!("{ms} ms"))
+            .unwrap_or_default(),
+    );
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use asterix_browser::{ArchiveLookupJob, TabId};
+use eframe::egui;
+use egui::{Context as EguiContext, RichText};
+use url::Url;
+
+use crate::ShellApp;
+
+/// Navigation that ended on a missing page or an unreachable host.
+pub(crate) struct DeadPage {
+    pub(crate) tab: TabId,
+    pub(crate) url: Url,
+    pub(crate) reason: String,
+    lookup: Option<ArchiveLookupJob>,
+}
+
+impl DeadPage {
+    pub(crate) fn new(tab: TabId, url: Url, reason: String) -> Self {
+        Self {
+            tab,
+            url,
+            reason,
+            lookup: None,
+        }
+    }
+}
+
+impl ShellApp {
+    /// Banner offering the Wayback Machine copy of a dead page.
+    pub(crate) fn render_archive_banner(&mut self, ctx: &EguiContext) {
+        let active = self.active_tab.as_ref().map(|tab| tab.id);
+        let Some(dead) = self
+            .dead_page
+            .as_mut()
+            .filter(|dead| Some(dead.tab) == active)
+        else {
+            return;
+        };
+
+        let mut snapshot = None;
+        if let Some(job) = &mut dead.lookup {
+            match job.try_complete() {
+                Some(Ok(Some(url))) => snapshot = Some(url),
+                Some(Ok(None)) => {
+                    self.status_line = format!("No archived copy of {}", dead.url);
+                    dead.lookup = None;
+                }
+                Some(Err(err)) => {
+                    self.status_line = format!("Archive lookup failed: {err}");
+                    dead.lookup = None;
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(200)),
+            }
+        }
+        if let Some(url) = snapshot {
+            let tab = dead.tab;
+            self.dead_page = None;
+            self.url_input = url.to_string();
+            self.navigate_tab(tab, url);
+            return;
+        }
+
+        let mut lookup = false;
+        let mut dismiss = false;
+        egui::TopBottomPanel::top("archive_banner").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(&dead.reason).strong());
+                if dead.lookup.is_some() {
+                    ui.spinner();
+                    ui.label("Searching the Wayback Machine...");
+                } else {
+                    lookup = ui.button("Load archived copy").clicked();
+                }
+                dismiss = ui.small_button("Dismiss").clicked();
+            });
+        });
+
+        if dismiss {
+            self.dead_page = None;
+        } else if lookup {
+            match self.handle.find_archived_copy(dead.url.clone()) {
+                Ok(job) => dead.lookup = Some(job),
+                Err(err) => self.status_line = format!("Archive lookup failed: {err}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use asterix_browser::{audit_page, AuditFinding, AuditRule, StyleSheet};
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use url::Url;
+
+use crate::devtools::DevToolsTab;
+use crate::{PageView, ShellApp};
+
+/// Accessibility findings for the page in the active tab.
+#[derive(Default)]
+pub(crate) struct AuditPanel {
+    /// Page the findings were collected from; they are dropped once a
+    /// different page or a reload is shown.
+    page_key: Option<(Url, DateTime<Utc>)>,
+    findings: Vec<AuditFinding>,
+    filter: Option<AuditRule>,
+}
+
+impl AuditPanel {
+    /// Shows the findings and returns the element the user asked to inspect.
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, page: Option<&PageView>) -> Option<usize> {
+        let page_key = page.map(|page| (page.url.clone(), page.received_at));
+        if self.page_key.is_some() && self.page_key != page_key {
+            self.page_key = None;
+            self.findings.clear();
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(page.is_some(), egui::Button::new("Run Audit"))
+                .clicked()
+            {
+                if let Some(page) = page {
+                    self.run(page);
+                }
+            }
+            if self.page_key.is_some() {
+                ui.weak(format!("{} issues", self.findings.len()));
+            }
+        });
+        ui.separator();
+
+        if self.page_key.is_none() {
+            ui.weak("Run the audit to check the page for missing alt text, low contrast, unlabelled form controls and skipped heading levels.");
+            return None;
+        }
+        if self.findings.is_empty() {
+            ui.label("No issues found.");
+            return None;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.filter, None, "All");
+            for rule in AuditRule::ALL {
+                let count = self
+                    .findings
+                    .iter()
+                    .filter(|finding| finding.rule == rule)
+                    .count();
+                ui.selectable_value(
+                    &mut self.filter,
+                    Some(rule),
+                    format!("{} ({count})", rule.label()),
+                );
+            }
+        });
+        ui.separator();
+
+        let elements = page.map_or(&[][..], |page| &page.elements);
+        let mut inspect = None;
+        egui::ScrollArea::vertical()
+            .id_source("audit_findings")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let findings = self
+                    .findings
+                    .iter()
+                    .filter(|finding| self.filter.is_none_or(|rule| finding.rule == rule));
+                for finding in findings {
+                    ui.horizontal(|ui| {
+                        ui.strong(finding.rule.label());
+                        if let Some(element) = elements.get(finding.element) {
+                            if ui
+                                .link(element.label())
+                                .on_hover_text("Show in the Elements tab")
+                                .clicked()
+                            {
+                                inspect = Some(finding.element);
+                            }
+                        }
+                    });
+                    ui.label(&finding.message);
+                    ui.separator();
+                }
+            });
+        inspect
+    }
+
+    pub(crate) fn run(&mut self, page: &PageView) {
+        let sheet = StyleSheet::from_page(&page.source, &page.elements);
+        self.findings = audit_page(&page.source, &page.elements, &sheet);
+        self.page_key = Some((page.url.clone(), page.received_at));
+    }
+}
+
+impl ShellApp {
+    /// Audits the page in the active tab and shows the findings.
+    pub(crate) fn run_accessibility_audit(&mut self) {
+        let page = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| self.pages.get(&tab.id));
+        let Some(page) = page else {
+            self.status_line = "Load a page before auditing it".to_owned();
+            return;
+        };
+        self.devtools.audit.run(page);
+        self.open_devtools(DevToolsTab::Accessibility);
+    }
+}
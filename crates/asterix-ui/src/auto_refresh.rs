@@ -0,0 +1,63 @@
+use asterix_browser::{BrowserError, BrowserHandle, PageResponse, RefreshJob, TabId};
+use tracing::{debug, warn};
+
+use crate::ShellApp;
+
+/// Starts waiting out the refresh `page`, just loaded in `tab`, asks for.
+pub(crate) fn schedule_refresh(
+    handle: &BrowserHandle,
+    tab: TabId,
+    page: &PageResponse,
+) -> Option<RefreshJob> {
+    match handle.schedule_refresh(tab, page) {
+        Ok(refresh) => refresh,
+        Err(err) => {
+            warn!("failed to schedule the refresh of {}: {err:#}", page.url);
+            None
+        }
+    }
+}
+
+impl ShellApp {
+    /// Loads the pages refreshes lead to once their delay has run out.
+    pub(crate) fn poll_refreshes(&mut self) {
+        let mut pending = Vec::with_capacity(self.refresh_jobs.len());
+        let mut due = Vec::new();
+        for mut job in self.refresh_jobs.drain(..) {
+            match job.try_complete() {
+                None => pending.push(job),
+                Some(Ok(url)) => due.push((job.tab(), url)),
+                Some(Err(BrowserError::Cancelled)) => {}
+                Some(Err(err)) => debug!("refresh failed: {err}"),
+            }
+        }
+        self.refresh_jobs = pending;
+        for (tab, url) in due {
+            // The toggle may have been flipped since the delay started.
+            if !self.handle.tab_auto_refresh(tab) {
+                continue;
+            }
+            self.status_line = format!("Refreshing to {url}");
+            if self.active_tab.as_ref().map(|active| active.id) == Some(tab) {
+                self.navigate_active(url);
+            } else {
+                self.navigate_tab(tab, url);
+            }
+        }
+    }
+
+    /// Turns following page refreshes in `tab` on or off, calling off the
+    /// one it is waiting for.
+    pub(crate) fn toggle_auto_refresh(&mut self, tab: TabId, title: &str) {
+        let enabled = !self.handle.tab_auto_refresh(tab);
+        self.handle.set_tab_auto_refresh(tab, enabled);
+        if !enabled {
+            self.refresh_jobs.retain(|job| job.tab() != tab);
+        }
+        self.status_line = if enabled {
+            format!("{title} follows page refreshes")
+        } else {
+            format!("{title} ignores page refreshes")
+        };
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{HashMap, VecDeque};
+
+use asterix_browser::TabId;
+use tracing::{debug, info};
+use url::Url;
+
+use crate::{PageView, ShellApp};
+
+/// Memory the cached pages may take, by [`PageView::approximate_size`].
+const BUDGET_BYTES: usize = 64 * 1024 * 1024;
+
+/// Pages kept per tab; Back rarely goes further than a few steps.
+const MAX_PAGES_PER_TAB: usize = 4;
+
+/// Below this much available system memory the cache is emptied.
+const LOW_MEMORY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Rough footprint of one mapped element, for estimating a view's size.
+const ELEMENT_BYTES: usize = 256;
+
+/// Pages left by navigating away, kept parsed and laid out so that Back and
+/// Forward show them again without fetching or parsing anything.
+#[derive(Default)]
+pub(crate) struct BackForwardCache {
+    /// Least recently left first.
+    entries: VecDeque<CachedPage>,
+    size: usize,
+}
+
+struct CachedPage {
+    tab: TabId,
+    view: PageView,
+    size: usize,
+}
+
+impl BackForwardCache {
+    /// Shows `view` in `tab`, keeping the page it replaces in `pages` for
+    /// Back and Forward.
+    pub(crate) fn replace(
+        &mut self,
+        pages: &mut HashMap<TabId, PageView>,
+        tab: TabId,
+        view: PageView,
+    ) {
+        let url = view.url.clone();
+        let Some(previous) = pages.insert(tab, view) else {
+            return;
+        };
+        // A reload replaces the page with a newer copy of itself.
+        if previous.url != url {
+            self.store(tab, previous);
+        }
+    }
+
+    /// Keeps `view`, which `tab` is leaving, evicting the pages left longest
+    /// ago to stay within budget.
+    pub(crate) fn store(&mut self, tab: TabId, view: PageView) {
+        self.take(tab, &view.url);
+        let size = view.approximate_size();
+        if size > BUDGET_BYTES {
+            return;
+        }
+        self.size += size;
+        self.entries.push_back(CachedPage { tab, view, size });
+        while self.entries.iter().filter(|entry| entry.tab == tab).count() > MAX_PAGES_PER_TAB {
+            let Some(oldest) = self.entries.iter().position(|entry| entry.tab == tab) else {
+                break;
+            };
+            self.remove(oldest);
+        }
+        while self.size > BUDGET_BYTES {
+            self.remove(0);
+        }
+    }
+
+    /// Takes the page `tab` left at `url`, if it is still kept.
+    fn take(&mut self, tab: TabId, url: &Url) -> Option<PageView> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.tab == tab && &entry.view.url == url)?;
+        self.remove(index)
+    }
+
+    fn remove(&mut self, index: usize) -> Option<PageView> {
+        let entry = self.entries.remove(index)?;
+        self.size -= entry.size;
+        Some(entry.view)
+    }
+
+    /// Drops the pages of tabs `keep` rejects, e.g. those of hibernated
+    /// workspaces.
+    pub(crate) fn retain_tabs(&mut self, mut keep: impl FnMut(TabId) -> bool) {
+        self.entries.retain(|entry| keep(entry.tab));
+        self.size = self.entries.iter().map(|entry| entry.size).sum();
+    }
+
+    /// Empties the cache when the system is running low on memory.
+    pub(crate) fn relieve_memory_pressure(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let Some(available) = available_memory() else {
+            return;
+        };
+        if available < LOW_MEMORY_BYTES {
+            info!(
+                target = "ui",
+                "memory is low, dropping {} pages kept for Back and Forward",
+                self.entries.len()
+            );
+            self.entries.clear();
+            self.size = 0;
+        }
+    }
+}
+
+impl PageView {
+    /// Estimate of the memory the view holds, dominated by the page's text
+    /// in its several forms.
+    fn approximate_size(&self) -> usize {
+        let rendered = self
+            .rendered
+            .as_ref()
+            .map_or(0, |(_, rendered)| rendered.text.len());
+        // The source shares the buffer of the response body.
+        self.response.bytes.len()
+            + self.response.body.len()
+            + self.preview.len()
+            + self.pretty.as_ref().map_or(0, String::len)
+            + rendered
+            + self.elements.len() * ELEMENT_BYTES
+    }
+}
+
+impl ShellApp {
+    /// Shows the page `tab` left at `url` as it was left, when it is still
+    /// kept. The tab's history must already point at `url`.
+    pub(crate) fn restore_page(&mut self, tab: TabId, url: &Url, scroll_offset: f32) -> bool {
+        let Some(view) = self.back_forward.take(tab, url) else {
+            return false;
+        };
+        debug!(target = "ui", "restoring {url} from the back-forward cache");
+        self.stop_loading(tab);
+        self.handle.restore_page(tab, url, &view.response);
+        if self.dead_page.as_ref().map(|dead| dead.tab) == Some(tab) {
+            self.dead_page = None;
+        }
+        self.deferred_pages.remove(&tab);
+        self.pending_scroll.remove(&tab);
+        self.status_line = format!("Loaded {} \u{b7} from back-forward cache", view.url);
+        let view = PageView {
+            scroll_offset,
+            restore_scroll: true,
+            ..view
+        };
+        self.back_forward.replace(&mut self.pages, tab, view);
+        self.refresh_tabs();
+        true
+    }
+}
+
+/// System memory available to new allocations, where the platform tells.
+#[cfg(target_os = "linux")]
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_memory() -> Option<u64> {
+    None
+}
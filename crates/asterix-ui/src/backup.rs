@@ -0,0 +1,215 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use asterix_browser::{BackupJob, BackupSummary, BACKUP_EXTENSION};
+use chrono::Local;
+use eframe::egui;
+use egui::Context as EguiContext;
+
+use crate::{profile_dir, ShellApp};
+
+/// Backup and restore window for the whole profile directory.
+#[derive(Default)]
+pub(crate) struct BackupPanel {
+    pub(crate) open: bool,
+    destination: String,
+    passphrase: String,
+    confirm: String,
+    archive: String,
+    restore_passphrase: String,
+    /// Running job and whether it is a restore.
+    job: Option<(BackupJob, bool)>,
+    result: Option<Result<String, String>>,
+    /// A backup was restored over the profile; the shell must not write its
+    /// own state back on exit.
+    pub(crate) restored: bool,
+}
+
+/// Where a new backup is offered to be written, e.g.
+/// `~/asterix-profile-2024-05-01.axbackup`.
+fn default_destination() -> Option<PathBuf> {
+    let name = format!(
+        "asterix-profile-{}.{BACKUP_EXTENSION}",
+        Local::now().format("%Y-%m-%d")
+    );
+    home::home_dir().map(|home| home.join(name))
+}
+
+fn describe(summary: &BackupSummary) -> String {
+    format!(
+        "{} files, {:.1} MB",
+        summary.files,
+        summary.bytes as f64 / (1024.0 * 1024.0)
+    )
+}
+
+impl ShellApp {
+    pub(crate) fn open_backup_window(&mut self) {
+        let panel = &mut self.backup;
+        if panel.destination.is_empty() {
+            panel.destination = default_destination()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+        }
+        panel.open = true;
+    }
+
+    fn start_backup(&mut self) {
+        let Some(profile) = profile_dir() else {
+            return;
+        };
+        // Flush what is only in memory so the backup is current.
+        self.save_profile();
+        let panel = &mut self.backup;
+        let destination = PathBuf::from(panel.destination.trim());
+        match self.handle.backup_profile(
+            profile,
+            destination,
+            std::mem::take(&mut panel.passphrase),
+        ) {
+            Ok(job) => {
+                panel.confirm.clear();
+                panel.job = Some((job, false));
+                panel.result = None;
+            }
+            Err(err) => panel.result = Some(Err(err.to_string())),
+        }
+    }
+
+    fn start_restore(&mut self) {
+        let Some(profile) = profile_dir() else {
+            return;
+        };
+        let panel = &mut self.backup;
+        let archive = PathBuf::from(panel.archive.trim());
+        match self.handle.restore_profile(
+            archive,
+            profile,
+            std::mem::take(&mut panel.restore_passphrase),
+        ) {
+            Ok(job) => {
+                panel.job = Some((job, true));
+                panel.result = None;
+            }
+            Err(err) => panel.result = Some(Err(err.to_string())),
+        }
+    }
+
+    pub(crate) fn render_backup_window(&mut self, ctx: &EguiContext) {
+        let panel = &mut self.backup;
+        if let Some((job, restoring)) = &mut panel.job {
+            match job.try_complete() {
+                Some(result) => {
+                    let restoring = *restoring;
+                    panel.job = None;
+                    panel.result = Some(match result {
+                        Ok(summary) if restoring => {
+                            panel.restored = true;
+                            Ok(format!(
+                                "Restored {}. Restart ASTERIX to use the restored profile.",
+                                describe(&summary)
+                            ))
+                        }
+                        Ok(summary) => Ok(format!("Backed up {}", describe(&summary))),
+                        Err(err) => Err(format!("{err:#}")),
+                    });
+                    if let Some(Ok(message)) = &panel.result {
+                        self.status_line = message.clone();
+                    }
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(200)),
+            }
+        }
+
+        let panel = &mut self.backup;
+        if !panel.open {
+            return;
+        }
+        let mut open = true;
+        let mut backup = false;
+        let mut restore = false;
+        let busy = panel.job.is_some();
+        egui::Window::new("Backup & Restore")
+            .open(&mut open)
+            .default_size([460.0, 360.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "Bookmarks, history, passwords, settings, sessions and everything else in \
+                     the profile but the page cache are packed into one file encrypted with \
+                     your passphrase.",
+                );
+                ui.separator();
+                ui.strong("Back up");
+                egui::Grid::new("backup_form")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Save to");
+                        ui.text_edit_singleline(&mut panel.destination);
+                        ui.end_row();
+                        ui.label("Passphrase");
+                        ui.add(egui::TextEdit::singleline(&mut panel.passphrase).password(true));
+                        ui.end_row();
+                        ui.label("Repeat");
+                        ui.add(egui::TextEdit::singleline(&mut panel.confirm).password(true));
+                        ui.end_row();
+                    });
+                let ready = !panel.destination.trim().is_empty()
+                    && !panel.passphrase.is_empty()
+                    && panel.passphrase == panel.confirm;
+                if ui
+                    .add_enabled(!busy && ready, egui::Button::new("Back Up Now"))
+                    .on_disabled_hover_text("Enter a file and the same passphrase twice")
+                    .clicked()
+                {
+                    backup = true;
+                }
+                ui.separator();
+
+                ui.strong("Restore");
+                egui::Grid::new("restore_form")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Backup file");
+                        ui.text_edit_singleline(&mut panel.archive);
+                        ui.end_row();
+                        ui.label("Passphrase");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.restore_passphrase)
+                                .password(true),
+                        );
+                        ui.end_row();
+                    });
+                ui.weak("The current profile is kept next to it as .asterix.before-restore.");
+                let ready =
+                    !panel.archive.trim().is_empty() && !panel.restore_passphrase.is_empty();
+                if ui
+                    .add_enabled(!busy && ready, egui::Button::new("Restore"))
+                    .clicked()
+                {
+                    restore = true;
+                }
+
+                if busy {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Working\u{2026}");
+                    });
+                } else if let Some(result) = &panel.result {
+                    ui.separator();
+                    match result {
+                        Ok(message) => ui.label(message),
+                        Err(err) => ui.colored_label(ui.visuals().error_fg_color, err),
+                    };
+                }
+            });
+        panel.open = open;
+
+        if backup {
+            self.start_backup();
+        }
+        if restore {
+            self.start_restore();
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+use asterix_browser::Bookmark;
+use eframe::egui;
+use tracing::warn;
+use url::Url;
+
+use crate::menu_bar::{Action, Menu};
+use crate::ShellApp;
+
+impl ShellApp {
+    /// Bookmarks the active page, or removes its bookmark.
+    pub(crate) fn toggle_bookmark(&mut self) {
+        let Some((url, title)) = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| Some((tab.url.clone()?, tab.title.clone())))
+        else {
+            return;
+        };
+        if self.handle.is_bookmarked(&url) {
+            self.handle.remove_bookmark(&url);
+            self.status_line = format!("Removed bookmark for {title}");
+        } else {
+            self.handle.add_bookmark(url, title.clone());
+            self.status_line = format!("Bookmarked {title}");
+        }
+        self.save_bookmarks();
+    }
+
+    /// Menu for bookmarking the active page and opening bookmarks.
+    pub(crate) fn render_bookmarks_menu(&mut self, ui: &mut egui::Ui) {
+        let mut open = None;
+        ui.menu_button(Menu::Bookmarks.label(), |ui| {
+            let bookmarked = self
+                .active_tab
+                .as_ref()
+                .and_then(|tab| tab.url.as_ref())
+                .is_some_and(|url| self.handle.is_bookmarked(url));
+            let label = if bookmarked {
+                "Remove Bookmark"
+            } else {
+                "Bookmark This Page"
+            };
+            self.action_item_labelled(ui, Action::BookmarkPage, label);
+            ui.separator();
+
+            let mut folders: BTreeMap<String, Vec<Bookmark>> = BTreeMap::new();
+            let mut loose = Vec::new();
+            for bookmark in self.handle.bookmarks() {
+                match &bookmark.folder {
+                    Some(folder) => folders.entry(folder.clone()).or_default().push(bookmark),
+                    None => loose.push(bookmark),
+                }
+            }
+            if folders.is_empty() && loose.is_empty() {
+                ui.weak("No bookmarks yet");
+            }
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    for (folder, bookmarks) in &folders {
+                        ui.menu_button(folder, |ui| {
+                            egui::ScrollArea::vertical()
+                                .max_height(400.0)
+                                .show(ui, |ui| {
+                                    if let Some(url) = bookmark_buttons(ui, bookmarks) {
+                                        open = Some(url);
+                                    }
+                                });
+                        });
+                    }
+                    if let Some(url) = bookmark_buttons(ui, &loose) {
+                        open = Some(url);
+                    }
+                });
+            if open.is_some() {
+                ui.close_menu();
+            }
+        });
+        if let Some(url) = open {
+            self.navigate_active(url);
+        }
+    }
+
+    pub(crate) fn load_library(&self) {
+        if let Some(path) = &self.bookmarks_path {
+            if let Err(err) = self.handle.load_bookmarks(path) {
+                warn!("failed to load bookmarks: {err:#}");
+            }
+        }
+        if let Some(path) = &self.history_path {
+            if let Err(err) = self.handle.load_history(path) {
+                warn!("failed to load history: {err:#}");
+            }
+        }
+        if let Some(path) = &self.downloads_path {
+            if let Err(err) = self.handle.load_downloads(path) {
+                warn!("failed to load downloads: {err:#}");
+            }
+        }
+        if let Some(path) = &self.logins_path {
+            if let Err(err) = self.handle.load_logins(path) {
+                warn!("failed to load saved passwords: {err:#}");
+            }
+        }
+        if let Some(path) = &self.hsts_path {
+            if let Err(err) = self.handle.load_hsts(path) {
+                warn!("failed to load HSTS hosts: {err:#}");
+            }
+        }
+        if let Some(path) = &self.filter_lists_path {
+            if let Err(err) = self.handle.load_filter_lists(path) {
+                warn!("failed to load filter lists: {err:#}");
+            }
+        }
+    }
+
+    pub(crate) fn save_bookmarks(&self) {
+        if let Some(path) = &self.bookmarks_path {
+            if let Err(err) = self.handle.save_bookmarks(path) {
+                warn!("failed to save bookmarks: {err:#}");
+            }
+        }
+    }
+
+    pub(crate) fn save_history(&self) {
+        if let Some(path) = &self.history_path {
+            if let Err(err) = self.handle.save_history(path) {
+                warn!("failed to save history: {err:#}");
+            }
+        }
+    }
+
+    pub(crate) fn save_logins(&self) {
+        if let Some(path) = &self.logins_path {
+            if let Err(err) = self.handle.save_logins(path) {
+                warn!("failed to save passwords: {err:#}");
+            }
+        }
+    }
+}
+
+/// One button per bookmark, returning the one that was clicked.
+fn bookmark_buttons(ui: &mut egui::Ui, bookmarks: &[Bookmark]) -> Option<Url> {
+    let mut clicked = None;
+    for bookmark in bookmarks {
+        if ui
+            .button(&bookmark.title)
+            .on_hover_text(bookmark.url.as_str())
+            .clicked()
+        {
+            clicked = Some(bookmark.url.clone());
+        }
+    }
+    clicked
+}
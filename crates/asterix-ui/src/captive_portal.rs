@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use asterix_browser::{network_address, Connectivity, ConnectivityJob, TabId};
+use eframe::egui;
+use egui::Context as EguiContext;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::ShellApp;
+
+/// How often the probe repeats while the user is signing in to a portal or
+/// the network is down.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Watches the network: probes for captive portals at startup, whenever the
+/// machine moves to another network and, once the login page is open, until
+/// it lets the probe through. Navigations that failed for want of a network
+/// are retried when it comes back.
+#[derive(Default)]
+pub(crate) struct ConnectivityMonitor {
+    job: Option<ConnectivityJob>,
+    /// Route address of the last probe; `None` before the first one.
+    network: Option<Option<IpAddr>>,
+    last_check: Option<Instant>,
+    last_result: Option<Connectivity>,
+    /// Probe on the next tick, e.g. after a site could not be reached.
+    check_soon: bool,
+    /// Set while the network is known to be down or behind a portal, or
+    /// after moving to another one.
+    disconnected: bool,
+    /// Pages that could not be reached, by tab, to reload once the network
+    /// is back.
+    failed_loads: HashMap<TabId, Url>,
+    /// Login page waiting for the user to open it.
+    prompt: Option<Url>,
+    /// Tab the portal's login page was opened in.
+    login_tab: Option<TabId>,
+}
+
+impl ConnectivityMonitor {
+    /// Notes that `url` could not be reached in `tab` and checks whether the
+    /// network is to blame.
+    pub(crate) fn load_failed(&mut self, tab: TabId, url: Url) {
+        self.failed_loads.insert(tab, url);
+        self.check_soon = true;
+    }
+
+    /// Forgets the failed load of `tab`, which went on to load something.
+    pub(crate) fn load_started(&mut self, tab: TabId) {
+        self.failed_loads.remove(&tab);
+    }
+}
+
+impl ShellApp {
+    /// Starts a probe when the network changed, a login is under way or the
+    /// network is down.
+    pub(crate) fn watch_network(&mut self) {
+        if self.connectivity.job.is_some() {
+            return;
+        }
+        // Closing the login tab gives up on signing in.
+        if let Some(login_tab) = self.connectivity.login_tab {
+            if !self.handle.tabs().iter().any(|tab| tab.id == login_tab) {
+                self.connectivity.login_tab = None;
+            }
+        }
+        let monitor = &mut self.connectivity;
+        let address = network_address();
+        let changed = monitor.network != Some(address);
+        if changed {
+            monitor.disconnected |= monitor.network.is_some();
+            monitor.network = Some(address);
+            monitor.last_result = None;
+            monitor.prompt = None;
+        }
+        if self.settings.skip_captive_portal_check {
+            // Without probes, a route showing up is the only sign that the
+            // network is back.
+            if changed && address.is_some() && self.connectivity.disconnected {
+                self.network_restored();
+            } else if self.connectivity.check_soon {
+                self.connectivity.check_soon = false;
+                self.connectivity.disconnected = true;
+            }
+            return;
+        }
+        let waiting =
+            monitor.login_tab.is_some() || monitor.last_result == Some(Connectivity::Offline);
+        let recheck = waiting
+            && monitor
+                .last_check
+                .is_none_or(|checked| checked.elapsed() >= RECHECK_INTERVAL);
+        if !changed && !recheck && !monitor.check_soon {
+            return;
+        }
+        monitor.check_soon = false;
+        monitor.last_check = Some(Instant::now());
+        match self.handle.check_connectivity() {
+            Ok(job) => self.connectivity.job = Some(job),
+            Err(err) => warn!("failed to check for a captive portal: {err:#}"),
+        }
+    }
+
+    pub(crate) fn poll_connectivity(&mut self) {
+        let monitor = &mut self.connectivity;
+        let Some(result) = monitor.job.as_mut().and_then(ConnectivityJob::try_complete) else {
+            return;
+        };
+        monitor.job = None;
+        let connectivity = match result {
+            Ok(connectivity) => connectivity,
+            Err(err) => {
+                warn!("captive portal check failed: {err}");
+                return;
+            }
+        };
+        let restored = connectivity == Connectivity::Online && monitor.disconnected;
+        if monitor.last_result.as_ref() != Some(&connectivity) {
+            match &connectivity {
+                Connectivity::Online => {
+                    if monitor.login_tab.take().is_some() {
+                        self.status_line = "Signed in to the network".to_owned();
+                    }
+                    monitor.prompt = None;
+                }
+                Connectivity::CaptivePortal { login } => {
+                    info!(target = "ui", "captive portal detected, login at {login}");
+                    if monitor.login_tab.is_none() {
+                        monitor.prompt = Some(login.clone());
+                    }
+                }
+                Connectivity::Offline => {
+                    monitor.prompt = None;
+                    self.status_line = "No internet connection".to_owned();
+                }
+            }
+        }
+        monitor.disconnected = connectivity != Connectivity::Online;
+        monitor.last_result = Some(connectivity);
+        if restored {
+            self.network_restored();
+        }
+    }
+
+    /// Reloads the pages that failed while the network was down and has the
+    /// HTTP cache check its stale copies.
+    fn network_restored(&mut self) {
+        let monitor = &mut self.connectivity;
+        monitor.disconnected = false;
+        let open: Vec<TabId> = self.handle.tabs().iter().map(|tab| tab.id).collect();
+        let failed: Vec<(TabId, Url)> = monitor
+            .failed_loads
+            .drain()
+            .filter(|(tab, _)| open.contains(tab))
+            .collect();
+        info!(
+            target = "ui",
+            "network is back, retrying {} pages",
+            failed.len()
+        );
+        if let Err(err) = self.handle.revalidate_stale_documents() {
+            warn!("failed to revalidate the cache: {err:#}");
+        }
+        let retried = failed.len();
+        for (tab, url) in failed {
+            self.load_tab(tab, url);
+        }
+        self.status_line = match retried {
+            0 => "Back online".to_owned(),
+            1 => "Back online, reloading the page that failed".to_owned(),
+            count => format!("Back online, reloading {count} pages that failed"),
+        };
+    }
+
+    pub(crate) fn render_captive_portal_prompt(&mut self, ctx: &EguiContext) {
+        let Some(login) = &self.connectivity.prompt else {
+            return;
+        };
+        let mut open = true;
+        let mut sign_in = false;
+        let mut dismiss = false;
+        egui::Window::new("Sign In to Network")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    "This network asks you to sign in before it lets traffic through, \
+                     for example on hotel or airport Wi-Fi.",
+                );
+                ui.monospace(login.as_str());
+                ui.horizontal(|ui| {
+                    sign_in = ui.button("Open Login Page").clicked();
+                    dismiss = ui.button("Not Now").clicked();
+                });
+            });
+        if !open || dismiss {
+            self.connectivity.prompt = None;
+            return;
+        }
+        if !sign_in {
+            return;
+        }
+        let Some(login) = self.connectivity.prompt.take() else {
+            return;
+        };
+        let tab = self.handle.create_tab("Network Login");
+        self.connectivity.login_tab = Some(tab.id);
+        self.navigate_tab(tab.id, login);
+        self.switch_to_tab(tab);
+        self.refresh_tabs();
+    }
+}
@@ -0,0 +1,279 @@
+use asterix_browser::{
+    CertificateInfo, ConnectionInfo, MixedContent, MixedContentAction, NavigationSecurity,
+};
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use egui::RichText;
+use url::Url;
+
+use crate::ShellApp;
+
+const PADLOCK: &str = "\u{1f512}";
+const WARNING: &str = "\u{26a0}";
+
+impl ShellApp {
+    /// Padlock in front of the address bar for HTTPS pages, opening the
+    /// connection details and certificate chain, or a warning for pages
+    /// received in the clear.
+    pub(crate) fn render_connection_menu(&mut self, ui: &mut egui::Ui) {
+        let Some((tab, url)) = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| Some((tab.id, tab.url.clone()?)))
+        else {
+            return;
+        };
+        if !NavigationSecurity::of(&url).is_secure() {
+            ui.colored_label(ui.visuals().warn_fg_color, "Not secure")
+                .on_hover_text("Anything sent to this site can be read or changed on the way");
+            return;
+        }
+        if url.scheme() != "https" {
+            return;
+        }
+        let (security, mixed_content) = self
+            .pages
+            .get(&tab)
+            .filter(|view| view.url == url)
+            .map(|view| (view.response.security, view.response.mixed_content.clone()))
+            .unwrap_or_default();
+        let info = self.handle.connection_info(tab);
+        let overridden = info.as_ref().and_then(|info| info.overridden_error.clone());
+        let (icon, hover) = match overridden {
+            Some(_) => (WARNING, "Certificate allowed by you"),
+            None if !mixed_content.is_empty() => (WARNING, "Parts of this page are not secure"),
+            None => (PADLOCK, "Connection is secure"),
+        };
+        let mut revoke = None;
+        ui.menu_button(icon, |ui| {
+            ui.set_min_width(360.0);
+            match &info {
+                Some(info) => {
+                    if let Some(reason) = &info.overridden_error {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!("You allowed this certificate although {reason}."),
+                        );
+                        if ui.button("Stop Allowing").clicked() {
+                            revoke = Some(info.host.clone());
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                    }
+                    if security == NavigationSecurity::Upgraded {
+                        ui.weak("Loaded over HTTPS because the site asked never to be visited over HTTP.");
+                    }
+                    mixed_content_details(ui, &mixed_content);
+                    connection_details(ui, info);
+                }
+                None => {
+                    ui.weak("No TLS details were recorded for this page.");
+                }
+            }
+        })
+        .response
+        .on_hover_text(hover);
+        if let Some(host) = revoke {
+            self.handle.remove_certificate_exception(&host);
+            self.status_line = format!("{host} must present a trusted certificate again");
+        }
+    }
+
+    /// `about:certerror` page explaining why a site's certificate was
+    /// refused, with a way back and one to proceed regardless.
+    pub(crate) fn render_cert_error_page(&mut self, ui: &mut egui::Ui, page: &Url) {
+        let target = page
+            .query_pairs()
+            .find(|(key, _)| key == "url")
+            .and_then(|(_, value)| Url::parse(&value).ok());
+        let details = target
+            .as_ref()
+            .and_then(Url::host_str)
+            .and_then(|host| self.cert_errors.get(host))
+            .cloned();
+        let mut go_back = false;
+        let mut proceed = false;
+        ui.vertical_centered(|ui| {
+            ui.add_space(40.0);
+            ui.heading("Your connection is not private");
+            ui.add_space(8.0);
+            if let Some(target) = &target {
+                ui.label(RichText::new(target.as_str()).monospace());
+                ui.add_space(8.0);
+            }
+            match &details {
+                Some(details) => {
+                    ui.label(format!(
+                        "The certificate of {} is not trusted: {}.",
+                        details.host, details.reason
+                    ));
+                    ui.weak("Someone may be trying to read or change what you send to this site.");
+                }
+                None => {
+                    ui.label("The site's certificate could not be verified.");
+                }
+            }
+            ui.add_space(12.0);
+            // Sites under HSTS forbid clicking through certificate errors.
+            let hsts = details
+                .as_ref()
+                .is_some_and(|details| self.handle.is_hsts_host(&details.host));
+            ui.horizontal(|ui| {
+                go_back = ui.button("Go Back").clicked();
+                match &details {
+                    Some(details) if !hsts => {
+                        proceed = ui
+                            .button(format!("Proceed to {} (unsafe)", details.host))
+                            .on_hover_text("Trusts this certificate until the browser is closed")
+                            .clicked();
+                    }
+                    Some(_) => {}
+                    None if target.is_some() => {
+                        proceed = ui.button("Try Again").clicked();
+                    }
+                    None => {}
+                }
+            });
+            if let Some(details) = details.as_ref().filter(|_| hsts) {
+                ui.add_space(8.0);
+                ui.weak(format!(
+                    "{} only accepts secure connections, so this warning cannot be bypassed.",
+                    details.host
+                ));
+            }
+        });
+        if let Some(details) = &details {
+            ui.add_space(12.0);
+            egui::CollapsingHeader::new(format!(
+                "Certificate chain ({})",
+                details.certificates.len()
+            ))
+            .id_source("cert_error_chain")
+            .show(ui, |ui| {
+                for (index, certificate) in details.certificates.iter().enumerate() {
+                    egui::CollapsingHeader::new(certificate.subject_name())
+                        .id_source(("cert_error_certificate", index))
+                        .default_open(index == 0)
+                        .show(ui, |ui| certificate_details(ui, certificate));
+                }
+            });
+        }
+
+        if go_back {
+            self.traverse_history(false);
+        } else if proceed {
+            if let Some(details) = details {
+                self.handle.add_certificate_exception(&details);
+                self.cert_errors.remove(&details.host);
+            }
+            if let Some(target) = target {
+                self.navigate_active(target);
+            }
+        }
+    }
+}
+
+/// Plain-HTTP resources of the page, grouped by what happened to them.
+fn mixed_content_details(ui: &mut egui::Ui, mixed_content: &[MixedContent]) {
+    if mixed_content.is_empty() {
+        return;
+    }
+    for (action, heading) in [
+        (MixedContentAction::Blocked, "Blocked insecure content"),
+        (MixedContentAction::Upgraded, "Upgraded to HTTPS"),
+    ] {
+        let resources: Vec<&MixedContent> = mixed_content
+            .iter()
+            .filter(|resource| resource.action == action)
+            .collect();
+        if resources.is_empty() {
+            continue;
+        }
+        egui::CollapsingHeader::new(format!("{heading} ({})", resources.len()))
+            .id_source(heading)
+            .show(ui, |ui| {
+                for resource in resources {
+                    ui.label(
+                        RichText::new(format!("<{}> {}", resource.element, resource.url))
+                            .monospace()
+                            .small(),
+                    );
+                }
+            });
+    }
+    ui.separator();
+}
+
+fn connection_details(ui: &mut egui::Ui, info: &ConnectionInfo) {
+    ui.strong(format!("Secure connection to {}", info.host));
+    egui::Grid::new("connection_details")
+        .num_columns(2)
+        .show(ui, |ui| {
+            let unknown = || "unknown".to_owned();
+            ui.label("Protocol");
+            ui.label(info.protocol.clone().unwrap_or_else(unknown));
+            ui.end_row();
+            ui.label("Cipher suite");
+            ui.label(info.cipher_suite.clone().unwrap_or_else(unknown));
+            ui.end_row();
+            ui.label("Key exchange");
+            ui.label(info.key_exchange.clone().unwrap_or_else(unknown));
+            ui.end_row();
+            ui.label("Verified");
+            ui.label(info.established_at.format("%Y-%m-%d %H:%M").to_string());
+            ui.end_row();
+        });
+    ui.separator();
+    ui.label(format!("Certificate chain ({})", info.certificates.len()));
+    for (index, certificate) in info.certificates.iter().enumerate() {
+        egui::CollapsingHeader::new(certificate.subject_name())
+            .id_source(("certificate", index))
+            .default_open(index == 0)
+            .show(ui, |ui| certificate_details(ui, certificate));
+    }
+}
+
+fn certificate_details(ui: &mut egui::Ui, certificate: &CertificateInfo) {
+    let date = |time: Option<DateTime<Utc>>| {
+        time.map_or_else(
+            || "unknown".to_owned(),
+            |time| time.format("%Y-%m-%d %H:%M UTC").to_string(),
+        )
+    };
+    egui::Grid::new(("certificate_fields", &certificate.sha256_fingerprint))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Subject");
+            ui.label(&certificate.subject);
+            ui.end_row();
+            ui.label("Issuer");
+            ui.label(&certificate.issuer);
+            ui.end_row();
+            ui.label("Valid from");
+            ui.label(date(certificate.not_before));
+            ui.end_row();
+            ui.label("Valid until");
+            ui.label(date(certificate.not_after));
+            ui.end_row();
+            if !certificate.subject_alt_names.is_empty() {
+                ui.label("Alternative names");
+                ui.add(egui::Label::new(certificate.subject_alt_names.join(", ")).wrap(true));
+                ui.end_row();
+            }
+            ui.label("Serial number");
+            ui.monospace(&certificate.serial);
+            ui.end_row();
+            ui.label("SHA-256");
+            ui.add(
+                egui::Label::new(egui::RichText::new(&certificate.sha256_fingerprint).monospace())
+                    .wrap(true),
+            );
+            ui.end_row();
+        });
+    if !certificate.is_valid_at(Utc::now()) {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            "This certificate is outside its validity period.",
+        );
+    }
+}
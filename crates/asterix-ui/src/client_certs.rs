@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+
+use asterix_browser::{HostIdentity, IdentityFile};
+use eframe::egui;
+use egui::Context as EguiContext;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::ShellApp;
+
+/// Client certificate presented to one host, with its subdomains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ClientCertificate {
+    /// Host such as `intranet.example.com`.
+    pub(crate) host: String,
+    pub(crate) file: IdentityFile,
+}
+
+/// Window managing the client certificates of mTLS-protected sites.
+#[derive(Default)]
+pub(crate) struct ClientCertsPanel {
+    pub(crate) open: bool,
+    host: String,
+    /// PEM certificate and key instead of a PKCS#12 bundle.
+    pem: bool,
+    /// PKCS#12 bundle, or the PEM certificate chain.
+    path: String,
+    /// PEM private key; the certificate file when empty.
+    key_path: String,
+    password: String,
+    error: Option<String>,
+}
+
+impl ClientCertsPanel {
+    fn file(&self) -> IdentityFile {
+        let path = PathBuf::from(self.path.trim());
+        if !self.pem {
+            return IdentityFile::Pkcs12 {
+                path,
+                password: self.password.clone(),
+            };
+        }
+        let key = match self.key_path.trim() {
+            "" => path.clone(),
+            key => PathBuf::from(key),
+        };
+        IdentityFile::Pem {
+            certificate: path,
+            key,
+        }
+    }
+}
+
+impl ShellApp {
+    /// Loads the client certificates in the settings and hands them to the
+    /// browser. One that fails to load is left out and reported.
+    pub(crate) fn apply_client_certificates(&mut self) {
+        let mut identities = Vec::new();
+        for certificate in &self.settings.client_certificates {
+            match certificate.file.load() {
+                Ok(identity) => identities.push(HostIdentity {
+                    host: certificate.host.clone(),
+                    identity,
+                }),
+                Err(err) => {
+                    warn!(
+                        "failed to load the client certificate for {}: {err}",
+                        certificate.host
+                    );
+                    self.status_line = format!(
+                        "Client certificate for {} not loaded: {err}",
+                        certificate.host
+                    );
+                }
+            }
+        }
+        if let Err(err) = self.handle.set_client_identities(identities) {
+            warn!("failed to apply client certificates: {err:#}");
+        }
+    }
+
+    fn add_client_certificate(&mut self) {
+        let panel = &mut self.client_certs;
+        let host = panel.host.trim().to_ascii_lowercase();
+        if host.is_empty() || panel.path.trim().is_empty() {
+            panel.error = Some("Enter a host and the certificate file".to_owned());
+            return;
+        }
+        let file = panel.file();
+        if let Err(err) = file.load() {
+            panel.error = Some(format!("Could not load the certificate: {err}"));
+            return;
+        }
+        *panel = ClientCertsPanel {
+            open: true,
+            ..ClientCertsPanel::default()
+        };
+        self.settings
+            .client_certificates
+            .retain(|certificate| certificate.host != host);
+        self.settings
+            .client_certificates
+            .push(ClientCertificate { host, file });
+        self.save_settings();
+        self.apply_client_certificates();
+    }
+
+    pub(crate) fn render_client_certs_window(&mut self, ctx: &EguiContext) {
+        if !self.client_certs.open {
+            return;
+        }
+        let loaded = self.handle.client_identities();
+        let panel = &mut self.client_certs;
+        let mut open = true;
+        let mut remove = None;
+        let mut add = false;
+        egui::Window::new("Client Certificates")
+            .open(&mut open)
+            .default_size([460.0, 300.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "Sites that ask the browser to identify itself during the TLS handshake, \
+                     such as intranet services, are shown the certificate set up for their \
+                     host or a parent domain.",
+                );
+                ui.separator();
+                egui::Grid::new("client_certificates")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (index, certificate) in
+                            self.settings.client_certificates.iter().enumerate()
+                        {
+                            ui.monospace(&certificate.host);
+                            let subject = loaded
+                                .iter()
+                                .find(|identity| identity.host == certificate.host)
+                                .and_then(|identity| identity.identity.certificate());
+                            match subject {
+                                Some(info) => ui.label(info.subject_name()),
+                                None => ui.colored_label(ui.visuals().error_fg_color, "Not loaded"),
+                            };
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if self.settings.client_certificates.is_empty() {
+                    ui.weak("No client certificates yet.");
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut panel.pem, false, "PKCS#12 (.p12, .pfx)");
+                    ui.radio_value(&mut panel.pem, true, "PEM certificate and key");
+                });
+                egui::Grid::new("client_certificate_form")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Host");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.host)
+                                .hint_text("intranet.example.com"),
+                        );
+                        ui.end_row();
+                        if panel.pem {
+                            ui.label("Certificate");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut panel.path)
+                                    .hint_text("/path/to/client.pem"),
+                            );
+                            ui.end_row();
+                            ui.label("Private key");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut panel.key_path)
+                                    .hint_text("In the certificate file"),
+                            );
+                        } else {
+                            ui.label("File");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut panel.path)
+                                    .hint_text("/path/to/client.p12"),
+                            );
+                            ui.end_row();
+                            ui.label("Password");
+                            ui.add(egui::TextEdit::singleline(&mut panel.password).password(true));
+                        }
+                        ui.end_row();
+                    });
+                if !panel.pem {
+                    ui.weak("The password is kept with the settings so the file can be reopened.");
+                }
+                if let Some(err) = &panel.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+                add = ui.button("Add").clicked();
+            });
+        self.client_certs.open &= open;
+
+        if let Some(index) = remove {
+            self.settings.client_certificates.remove(index);
+            self.save_settings();
+            self.apply_client_certificates();
+        }
+        if add {
+            self.add_client_certificate();
+        }
+    }
+}
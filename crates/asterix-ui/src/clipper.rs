@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use asterix_browser::html_to_markdown;
+use chrono::Utc;
+use eframe::egui;
+use egui::Context as EguiContext;
+use url::Url;
+
+use crate::ShellApp;
+
+impl ShellApp {
+    /// Controls for converting the selection (or whole page) to Markdown.
+    pub(crate) fn render_clipper(&mut self, ui: &mut egui::Ui, ctx: &EguiContext) {
+        ui.heading("Web Clipper");
+        ui.horizontal(|ui| {
+            ui.label("Notes folder");
+            let edit = ui.add_enabled(
+                !self.settings_locked(),
+                egui::TextEdit::singleline(&mut self.clip_dir_input)
+                    .hint_text("Copy to clipboard")
+                    .desired_width(f32::INFINITY),
+            );
+            if edit.lost_focus() {
+                let trimmed = self.clip_dir_input.trim();
+                self.settings.clip_directory =
+                    (!trimmed.is_empty()).then(|| PathBuf::from(trimmed));
+                self.save_settings();
+            }
+        });
+
+        let label = if self.preview_selection.is_some() {
+            "Clip selection"
+        } else {
+            "Clip page"
+        };
+        ui.horizontal(|ui| {
+            if ui.button(label).clicked() {
+                self.clip(ctx, false);
+            }
+            if ui.button("Copy as Markdown").clicked() {
+                self.clip(ctx, true);
+            }
+        });
+    }
+
+    fn clip(&mut self, ctx: &EguiContext, force_clipboard: bool) {
+        let Some(tab) = self.active_tab.clone() else {
+            return;
+        };
+        let Some(page) = self.pages.get(&tab.id) else {
+            self.status_line = "Nothing to clip yet".to_owned();
+            return;
+        };
+
+        let html = self
+            .preview_selection
+            .clone()
+            .unwrap_or_else(|| page.source.to_string());
+        let markdown = html_to_markdown(&html, &page.url);
+        if markdown.is_empty() {
+            self.status_line = "Selection contains no text to clip".to_owned();
+            return;
+        }
+        let note = clip_document(&tab.title, &page.url, &markdown);
+
+        let directory = self.settings.clip_directory.clone();
+        match directory.filter(|_| !force_clipboard) {
+            Some(directory) => {
+                let path = directory.join(clip_file_name(&tab.title));
+                let written =
+                    std::fs::create_dir_all(&directory).and_then(|()| std::fs::write(&path, note));
+                self.status_line = match written {
+                    Ok(()) => format!("Clipped to {}", path.display()),
+                    Err(err) => format!("Failed to save clip: {err}"),
+                };
+            }
+            None => {
+                ctx.output_mut(|output| output.copied_text = note);
+                self.status_line = "Clip copied to clipboard".to_owned();
+            }
+        }
+    }
+}
+
+/// Wraps clipped Markdown with front matter understood by Obsidian and friends.
+fn clip_document(title: &str, url: &Url, markdown: &str) -> String {
+    let title = title.replace('"', "'");
+    format!(
+        "---\ntitle: \"{title}\"\nsource: {url}\nclipped: {}\n---\n\n{markdown}\n",
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+    )
+}
+
+fn clip_file_name(title: &str) -> String {
+    let slug = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .take(8)
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() {
+        "clip".to_owned()
+    } else {
+        slug
+    };
+    format!("{}-{slug}.md", Utc::now().format("%Y-%m-%d-%H%M%S"))
+}
@@ -0,0 +1,321 @@
+use std::time::Duration;
+
+use asterix_browser::{
+    prettify, BrowserHandle, ComposedRequest, RawResponse, RequestAuth, RequestJob, SourceLanguage,
+};
+use eframe::egui;
+use egui::{Color32, RichText};
+use url::Url;
+
+use crate::{generate_preview, parse_user_url};
+
+const METHODS: [&str; 7] = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AuthKind {
+    None,
+    Basic,
+    Bearer,
+}
+
+/// How the response body is shown.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BodyView {
+    Raw,
+    Pretty,
+    Preview,
+}
+
+/// Form state of the request composer in the developer tools.
+pub(crate) struct Composer {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    auth: AuthKind,
+    username: String,
+    password: String,
+    token: String,
+    job: Option<RequestJob>,
+    response: Option<Result<RawResponse, String>>,
+    body_view: BodyView,
+}
+
+impl Default for Composer {
+    fn default() -> Self {
+        Self {
+            method: "GET".to_owned(),
+            url: String::new(),
+            headers: Vec::new(),
+            body: String::new(),
+            auth: AuthKind::None,
+            username: String::new(),
+            password: String::new(),
+            token: String::new(),
+            job: None,
+            response: None,
+            body_view: BodyView::Pretty,
+        }
+    }
+}
+
+impl Composer {
+    /// Pre-fills the URL with `url` unless the user already typed one.
+    pub(crate) fn suggest_url(&mut self, url: Option<&Url>) {
+        if self.url.is_empty() {
+            if let Some(url) = url {
+                self.url = url.to_string();
+            }
+        }
+    }
+
+    /// Replaces the form with `request`, e.g. to edit and resend it.
+    pub(crate) fn load(&mut self, request: ComposedRequest) {
+        self.method = request.method;
+        self.url = request.url.to_string();
+        self.headers = request.headers;
+        self.body = request.body;
+        (self.auth, self.username, self.password, self.token) = match request.auth {
+            RequestAuth::None => (AuthKind::None, String::new(), String::new(), String::new()),
+            RequestAuth::Basic { username, password } => {
+                (AuthKind::Basic, username, password, String::new())
+            }
+            RequestAuth::Bearer(token) => (AuthKind::Bearer, String::new(), String::new(), token),
+        };
+        self.response = None;
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, handle: &BrowserHandle) {
+        if let Some(job) = &mut self.job {
+            match job.try_complete() {
+                Some(result) => {
+                    self.response = Some(result.map_err(|err| err.to_string()));
+                    self.job = None;
+                }
+                None => ui.ctx().request_repaint_after(Duration::from_millis(100)),
+            }
+        }
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("composer_method")
+                .selected_text(self.method.clone())
+                .width(90.0)
+                .show_ui(ui, |ui| {
+                    for method in METHODS {
+                        ui.selectable_value(&mut self.method, method.to_owned(), method);
+                    }
+                });
+            let send = ui.add_enabled(self.job.is_none(), egui::Button::new("Send"));
+            let url_edit = ui.add(
+                egui::TextEdit::singleline(&mut self.url)
+                    .hint_text("https://api.example.com/items")
+                    .desired_width(f32::INFINITY),
+            );
+            let submitted = url_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if send.clicked() || (submitted && self.job.is_none()) {
+                self.send(handle);
+            }
+        });
+
+        egui::CollapsingHeader::new(format!("Headers ({})", self.headers.len()))
+            .id_source("composer_headers")
+            .default_open(true)
+            .show(ui, |ui| {
+                let mut remove = None;
+                for (index, (name, value)) in self.headers.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(name)
+                                .hint_text("Header")
+                                .desired_width(160.0),
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(value)
+                                .hint_text("Value")
+                                .desired_width(320.0),
+                        );
+                        if ui.small_button("x").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = remove {
+                    self.headers.remove(index);
+                }
+                if ui.small_button("Add header").clicked() {
+                    self.headers.push((String::new(), String::new()));
+                }
+            });
+
+        egui::CollapsingHeader::new("Authorization")
+            .id_source("composer_auth")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.auth, AuthKind::None, "None");
+                    ui.selectable_value(&mut self.auth, AuthKind::Basic, "Basic");
+                    ui.selectable_value(&mut self.auth, AuthKind::Bearer, "Bearer token");
+                });
+                match self.auth {
+                    AuthKind::None => {}
+                    AuthKind::Basic => {
+                        ui.horizontal(|ui| {
+                            ui.label("Username");
+                            ui.text_edit_singleline(&mut self.username);
+                            ui.label("Password");
+                            ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                        });
+                    }
+                    AuthKind::Bearer => {
+                        ui.horizontal(|ui| {
+                            ui.label("Token");
+                            ui.add(egui::TextEdit::singleline(&mut self.token).password(true));
+                        });
+                    }
+                }
+            });
+
+        egui::CollapsingHeader::new("Body")
+            .id_source("composer_body")
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.body)
+                        .code_editor()
+                        .desired_rows(6)
+                        .desired_width(f32::INFINITY),
+                );
+            });
+
+        ui.separator();
+        if self.job.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Waiting for response...");
+            });
+            return;
+        }
+        match &self.response {
+            Some(Ok(response)) => render_response(ui, response, &mut self.body_view),
+            Some(Err(err)) => {
+                ui.colored_label(Color32::from_rgb(220, 80, 80), err);
+            }
+            None => {
+                ui.weak("Send a request to inspect its response.");
+            }
+        }
+    }
+
+    fn send(&mut self, handle: &BrowserHandle) {
+        let url = match parse_user_url(&self.url) {
+            Ok(url) => url,
+            Err(err) => {
+                self.response = Some(Err(format!("Invalid URL: {err}")));
+                return;
+            }
+        };
+        let auth = match self.auth {
+            AuthKind::None => RequestAuth::None,
+            AuthKind::Basic => RequestAuth::Basic {
+                username: self.username.clone(),
+                password: self.password.clone(),
+            },
+            AuthKind::Bearer => RequestAuth::Bearer(self.token.trim().to_owned()),
+        };
+        let request = ComposedRequest {
+            method: self.method.clone(),
+            url,
+            headers: self.headers.clone(),
+            body: self.body.clone(),
+            auth,
+        };
+        match handle.send_request(request) {
+            Ok(job) => self.job = Some(job),
+            Err(err) => self.response = Some(Err(err.to_string())),
+        }
+    }
+}
+
+fn render_response(ui: &mut egui::Ui, response: &RawResponse, body_view: &mut BodyView) {
+    let status_color = match response.status {
+        200..=299 => Color32::from_rgb(90, 180, 110),
+        300..=399 => Color32::from_rgb(220, 170, 60),
+        _ => Color32::from_rgb(220, 80, 80),
+    };
+    ui.horizontal(|ui| {
+        let status = match &response.reason {
+            Some(reason) => format!("{} {reason}", response.status),
+            None => response.status.to_string(),
+        };
+        ui.label(RichText::new(status).strong().color(status_color));
+        ui.label(format!(
+            "{} | {} ms | {}",
+            response.version,
+            response.elapsed_ms,
+            format_size(response.size)
+        ));
+        ui.weak(response.url.as_str());
+    });
+
+    egui::CollapsingHeader::new(format!("Response headers ({})", response.headers.len()))
+        .id_source("composer_response_headers")
+        .show(ui, |ui| {
+            egui::Grid::new("composer_response_header_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, value) in &response.headers {
+                        ui.monospace(name);
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+        });
+
+    ui.horizontal(|ui| {
+        ui.selectable_value(body_view, BodyView::Pretty, "Pretty");
+        ui.selectable_value(body_view, BodyView::Raw, "Raw");
+        ui.selectable_value(body_view, BodyView::Preview, "Preview");
+    });
+    let mut body = match body_view {
+        BodyView::Raw => response.body.clone(),
+        BodyView::Pretty => pretty_body(response),
+        BodyView::Preview => generate_preview(&response.body),
+    };
+    egui::ScrollArea::vertical()
+        .id_source("composer_response_body")
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut body)
+                    .code_editor()
+                    .interactive(false)
+                    .desired_width(f32::INFINITY),
+            );
+        });
+}
+
+/// Re-indents JSON bodies and reformats CSS and JavaScript; anything else
+/// is shown as received.
+fn pretty_body(response: &RawResponse) -> String {
+    let is_json = response
+        .header("content-type")
+        .is_some_and(|mime| mime.contains("json"));
+    if is_json {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&response.body) {
+            if let Ok(pretty) = serde_json::to_string_pretty(&value) {
+                return pretty;
+            }
+        }
+    }
+    match SourceLanguage::detect(response.header("content-type"), &response.url) {
+        Some(language) => prettify(&response.body, language),
+        None => response.body.clone(),
+    }
+}
+
+pub(crate) fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
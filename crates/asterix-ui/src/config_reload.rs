@@ -0,0 +1,463 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use eframe::egui;
+use egui::Context as EguiContext;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+
+use crate::scripts::{list_scripts, scripts_dir};
+use crate::settings::Settings;
+use crate::theme::themes_dir;
+use crate::{profile_dir, ShellApp};
+
+/// Part of the configuration edited on disk, announced to whatever holds
+/// state derived from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConfigChange {
+    CookieBanners,
+    TrackerProtection,
+    /// Proxy, IP version preference, HTTP versions, compression and the
+    /// captive portal check.
+    Connections,
+    FileTypes,
+    Ipfs,
+    SiteUserAgents,
+    ContentBlocking,
+    ClipDirectory,
+    ClientCertificates,
+    /// Home page and what startup and new tabs show.
+    Startup,
+    Theme,
+    /// Settings the shell reads whenever it needs them, such as the
+    /// toolbar layout and the status bar, which need nothing more than
+    /// being loaded.
+    Shell,
+    FilterLists,
+    Scripts,
+}
+
+impl ConfigChange {
+    fn label(self) -> &'static str {
+        match self {
+            Self::CookieBanners => "cookie banners",
+            Self::TrackerProtection => "tracker protection",
+            Self::Connections => "connection settings",
+            Self::FileTypes => "file types",
+            Self::Ipfs => "IPFS",
+            Self::SiteUserAgents => "site user agents",
+            Self::ContentBlocking => "content blocking",
+            Self::ClipDirectory => "clip directory",
+            Self::ClientCertificates => "client certificates",
+            Self::Startup => "home and startup",
+            Self::Theme => "theme",
+            Self::Shell => "shell preferences",
+            Self::FilterLists => "filter lists",
+            Self::Scripts => "scripts",
+        }
+    }
+
+    /// Change announced when the `Settings` field serialized as `key` is
+    /// edited.
+    fn of_setting(key: &str) -> Self {
+        match key {
+            "reject_cookie_banners" | "cookie_banner_exceptions" => Self::CookieBanners,
+            "tracker_protection" => Self::TrackerProtection,
+            "proxy"
+            | "ip_preference"
+            | "http_version"
+            | "suppressed_content_codings"
+            | "skip_captive_portal_check" => Self::Connections,
+            "file_type_rules" => Self::FileTypes,
+            "ipfs" => Self::Ipfs,
+            "site_user_agents" => Self::SiteUserAgents,
+            "pause_content_blocking" => Self::ContentBlocking,
+            "clip_directory" => Self::ClipDirectory,
+            "client_certificates" => Self::ClientCertificates,
+            "home_page" | "startup" | "new_tabs_open_home" => Self::Startup,
+            "theme" => Self::Theme,
+            _ => Self::Shell,
+        }
+    }
+}
+
+/// Filesystem watcher over the profile, the scripts directory and the
+/// themes directory. Its events arrive on a thread of its own, which wakes
+/// the shell to pick them up.
+pub(crate) struct ConfigWatcher {
+    /// Stops watching when dropped.
+    _watcher: RecommendedWatcher,
+    paths: Receiver<PathBuf>,
+    /// Hash of the contents each configuration file had when last read or
+    /// written by the shell, so its own saves are not taken for edits.
+    contents: HashMap<PathBuf, Option<u64>>,
+}
+
+fn contents_hash(path: &Path) -> Option<u64> {
+    let contents = std::fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+impl ConfigWatcher {
+    fn start(ctx: &EguiContext) -> notify::Result<Self> {
+        let (sender, paths) = mpsc::channel();
+        let ctx = ctx.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                    ctx.request_repaint();
+                }
+                Ok(_) => {}
+                Err(err) => warn!("config watcher failed: {err}"),
+            })?;
+        let dirs = [
+            (profile_dir(), RecursiveMode::NonRecursive),
+            (scripts_dir(), RecursiveMode::Recursive),
+            (themes_dir(), RecursiveMode::Recursive),
+        ];
+        for (dir, mode) in dirs {
+            let Some(dir) = dir else {
+                continue;
+            };
+            // Watching needs the directory to be there; an empty one reads
+            // the same as a missing one everywhere else.
+            if let Err(err) = std::fs::create_dir_all(&dir) {
+                warn!("failed to create {}: {err}", dir.display());
+                continue;
+            }
+            watcher.watch(&dir, mode)?;
+        }
+        Ok(Self {
+            _watcher: watcher,
+            paths,
+            contents: HashMap::new(),
+        })
+    }
+
+    /// Paths written, created or removed since the last call.
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.paths.try_iter().collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Takes note of what `path` holds now, e.g. after the shell saved it.
+    pub(crate) fn take_note(&mut self, path: &Path) {
+        self.contents.insert(path.to_owned(), contents_hash(path));
+    }
+
+    /// Whether `path` holds something other than it did when last noted.
+    fn differs(&self, path: &Path) -> bool {
+        self.contents.get(path) != Some(&contents_hash(path))
+    }
+
+    /// Like [`ConfigWatcher::differs`], taking note of the new contents.
+    fn edited(&mut self, path: &Path) -> bool {
+        let hash = contents_hash(path);
+        self.contents.insert(path.to_owned(), hash) != Some(hash)
+    }
+}
+
+/// Top-level fields of `settings` as they are saved.
+fn setting_fields(settings: &Settings) -> Map<String, Value> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
+
+/// Parts of the settings that differ between `old` and `new`, by comparing
+/// every field as it is saved.
+fn settings_changes(old: &Settings, new: &Settings) -> Vec<ConfigChange> {
+    let old = setting_fields(old);
+    let mut changes = Vec::new();
+    for (key, value) in setting_fields(new) {
+        let change = ConfigChange::of_setting(&key);
+        if old.get(&key) != Some(&value) && !changes.contains(&change) {
+            changes.push(change);
+        }
+    }
+    changes
+}
+
+impl ShellApp {
+    /// Starts watching the configuration on disk, taking note of the files
+    /// as they are when the shell starts so only later edits are reloaded.
+    pub(crate) fn watch_config(&mut self, ctx: &EguiContext) {
+        let mut watcher = match ConfigWatcher::start(ctx) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                warn!("failed to watch the configuration: {err}");
+                return;
+            }
+        };
+        for path in [&self.settings_path, &self.filter_lists_path]
+            .into_iter()
+            .flatten()
+        {
+            watcher.take_note(path);
+        }
+        self.config_watcher = Some(watcher);
+    }
+
+    /// Takes note of a configuration file the shell saved itself, so the
+    /// watcher does not reload it.
+    pub(crate) fn note_config_saved(&mut self, path: &Path) {
+        if let Some(watcher) = &mut self.config_watcher {
+            watcher.take_note(path);
+        }
+    }
+
+    /// Applies edits made to the settings file, the filter lists, the
+    /// scripts and the themes since the last call, without a restart.
+    /// Settings the shell reads when it needs them take effect by being
+    /// loaded; the rest are handed to the browser and the panels that
+    /// copied them. Nothing is applied while the parental lock is on, and
+    /// edits to the settings file and the filter lists are undone so they
+    /// do not take effect at the next start instead.
+    pub(crate) fn reload_config(&mut self, ctx: &EguiContext) {
+        let locked = self.settings_locked();
+        let Some(watcher) = &mut self.config_watcher else {
+            return;
+        };
+        let mut edited_settings = None;
+        let mut restore_settings = false;
+        let mut restore_filter_lists = false;
+        let mut changes = Vec::new();
+        for path in watcher.changed_paths() {
+            if locked {
+                // Leave a refused edit unnoted; the file is written back below.
+                if self.settings_path.as_ref() == Some(&path) {
+                    restore_settings |= watcher.differs(&path);
+                    continue;
+                }
+                if self.filter_lists_path.as_ref() == Some(&path) {
+                    restore_filter_lists |= watcher.differs(&path);
+                    continue;
+                }
+            }
+            if self.settings_path.as_ref() == Some(&path) {
+                if watcher.edited(&path) {
+                    edited_settings = Some(path);
+                }
+                continue;
+            }
+            let change = if self.filter_lists_path.as_ref() == Some(&path) {
+                watcher.edited(&path).then_some(ConfigChange::FilterLists)
+            } else if scripts_dir().is_some_and(|dir| path.starts_with(dir)) {
+                Some(ConfigChange::Scripts)
+            } else if themes_dir().is_some_and(|dir| path.starts_with(dir)) {
+                Some(ConfigChange::Theme)
+            } else {
+                None
+            };
+            if let Some(change) = change.filter(|change| !changes.contains(change)) {
+                changes.push(change);
+            }
+        }
+
+        if locked {
+            let mut ignored = Vec::new();
+            if restore_settings {
+                self.save_settings();
+                ignored.push("settings");
+            }
+            if restore_filter_lists {
+                self.save_filter_lists();
+                ignored.push(ConfigChange::FilterLists.label());
+            }
+            ignored.extend(changes.iter().map(|change| change.label()));
+            if !ignored.is_empty() {
+                let labels = ignored.join(", ");
+                info!("ignored edits to {labels} while settings are locked");
+                self.status_line = format!("Settings are locked; ignored edits to {labels}");
+            }
+            return;
+        }
+
+        let mut settings = None;
+        if let Some(path) = edited_settings {
+            // A file caught halfway through being written fails to decode;
+            // the finished one changes again and is picked up then.
+            match Settings::load(&path) {
+                Ok(loaded) => {
+                    let edits = settings_changes(&self.settings, &loaded);
+                    changes.retain(|change| !edits.contains(change));
+                    changes.extend(edits);
+                    settings = Some(loaded);
+                }
+                Err(err) => warn!("failed to reload settings: {err:#}"),
+            }
+        }
+        if changes.is_empty() {
+            return;
+        }
+        let labels = changes
+            .iter()
+            .map(|change| change.label())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Some(settings) = settings {
+            self.settings = settings;
+        }
+        for change in &changes {
+            self.apply_config_change(ctx, *change);
+        }
+        info!("reloaded {labels} from disk");
+        self.status_line = format!("Reloaded {labels} from disk");
+    }
+
+    /// Replaces the settings with `settings` and hands the parts that
+    /// changed to the browser and the panels holding copies of them.
+    pub(crate) fn apply_settings(&mut self, ctx: &EguiContext, settings: Settings) {
+        let changes = settings_changes(&self.settings, &settings);
+        self.settings = settings;
+        for change in changes {
+            self.apply_config_change(ctx, change);
+        }
+    }
+
+    fn apply_config_change(&mut self, ctx: &EguiContext, change: ConfigChange) {
+        match change {
+            ConfigChange::CookieBanners => {
+                self.handle.set_consent_handling(self.settings.consent());
+            }
+            ConfigChange::TrackerProtection => {
+                self.handle.set_tracker_protection(self.settings.privacy());
+            }
+            ConfigChange::Connections => {
+                if let Err(err) = self.handle.set_proxy(self.settings.proxy.clone()) {
+                    warn!("failed to apply the proxy settings: {err:#}");
+                }
+                if let Err(err) = self.handle.set_http_version(self.settings.http_version) {
+                    warn!("failed to apply the HTTP version setting: {err:#}");
+                }
+                self.handle.set_ip_preference(self.settings.ip_preference);
+                self.handle
+                    .set_content_codings(self.settings.content_codings());
+                if self.proxy.open {
+                    self.open_proxy_window();
+                }
+            }
+            ConfigChange::FileTypes => {
+                self.handle
+                    .set_file_type_rules(self.settings.file_type_rules.clone());
+            }
+            ConfigChange::Ipfs => {
+                self.handle.set_ipfs_settings(self.settings.ipfs.clone());
+                if self.ipfs.open {
+                    self.open_ipfs_window();
+                }
+            }
+            ConfigChange::SiteUserAgents => {
+                self.handle
+                    .set_site_user_agents(self.settings.site_user_agents.clone());
+            }
+            ConfigChange::ContentBlocking => {
+                self.handle
+                    .set_content_blocking(!self.settings.pause_content_blocking);
+            }
+            ConfigChange::ClipDirectory => {
+                self.clip_dir_input = self
+                    .settings
+                    .clip_directory
+                    .as_ref()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default();
+            }
+            ConfigChange::ClientCertificates => self.apply_client_certificates(),
+            ConfigChange::Startup => {
+                if self.startup.open {
+                    self.open_startup_window();
+                }
+            }
+            ConfigChange::Theme => self.apply_theme(ctx),
+            ConfigChange::Shell => {}
+            ConfigChange::FilterLists => {
+                if let Some(path) = &self.filter_lists_path {
+                    if let Err(err) = self.handle.load_filter_lists(path) {
+                        warn!("failed to reload filter lists: {err:#}");
+                    }
+                }
+            }
+            ConfigChange::Scripts => {
+                self.scripts = scripts_dir()
+                    .as_deref()
+                    .map(list_scripts)
+                    .unwrap_or_default();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_edited_field_is_announced() {
+        let old = Settings::default();
+        let mut new = old.clone();
+        new.home_page = "https://example.com/".to_owned();
+        new.show_status_bar = !old.show_status_bar;
+        new.tracker_protection = !old.tracker_protection;
+        new.skip_captive_portal_check = !old.skip_captive_portal_check;
+        let changes = settings_changes(&old, &new);
+        assert_eq!(changes.len(), 4);
+        for change in [
+            ConfigChange::Startup,
+            ConfigChange::Shell,
+            ConfigChange::TrackerProtection,
+            ConfigChange::Connections,
+        ] {
+            assert!(changes.contains(&change), "{change:?} missing");
+        }
+    }
+
+    #[test]
+    fn unchanged_settings_announce_nothing() {
+        let settings = Settings::default();
+        assert!(settings_changes(&settings, &settings.clone()).is_empty());
+    }
+
+    #[test]
+    fn fields_of_one_part_are_announced_once() {
+        let old = Settings::default();
+        let mut new = old.clone();
+        new.home_page = "https://example.com/".to_owned();
+        new.new_tabs_open_home = !old.new_tabs_open_home;
+        assert_eq!(settings_changes(&old, &new), [ConfigChange::Startup]);
+    }
+
+    #[test]
+    fn checking_an_edit_leaves_it_unnoted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, "{}").unwrap();
+        let mut watcher = ConfigWatcher {
+            _watcher: notify::recommended_watcher(|_: notify::Result<Event>| {}).unwrap(),
+            paths: mpsc::channel().1,
+            contents: HashMap::new(),
+        };
+        watcher.take_note(&path);
+        assert!(!watcher.differs(&path));
+
+        std::fs::write(&path, r#"{"home_page":"https://example.com/"}"#).unwrap();
+        assert!(watcher.differs(&path));
+        // A refused edit is still there to be caught on the next check.
+        assert!(watcher.differs(&path));
+        assert!(watcher.edited(&path));
+        assert!(!watcher.edited(&path));
+        assert!(!watcher.differs(&path));
+    }
+}
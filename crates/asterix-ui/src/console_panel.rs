@@ -0,0 +1,134 @@
+use asterix_browser::{BrowserHandle, ConsoleCommand, TabId};
+use eframe::egui;
+use egui::{Color32, Key, RichText};
+
+use crate::PageView;
+
+/// Most commands remembered for recall with the arrow keys.
+const HISTORY_LIMIT: usize = 100;
+
+/// One evaluated command and what it printed.
+struct ConsoleEntry {
+    input: String,
+    output: Result<Vec<String>, String>,
+}
+
+/// Transcript and prompt of the developer tools console.
+#[derive(Default)]
+pub(crate) struct ConsolePanel {
+    input: String,
+    transcript: Vec<ConsoleEntry>,
+    history: Vec<String>,
+    /// Position while browsing `history`; `None` when editing a new command.
+    recall: Option<usize>,
+}
+
+impl ConsolePanel {
+    pub(crate) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &BrowserHandle,
+        page: Option<(TabId, &PageView)>,
+    ) {
+        let prompt_height = ui.spacing().interact_size.y + ui.spacing().item_spacing.y * 2.0;
+        egui::ScrollArea::vertical()
+            .id_source("console_transcript")
+            .max_height((ui.available_height() - prompt_height).max(0.0))
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                if self.transcript.is_empty() {
+                    ui.weak("Type help for the built-in commands.");
+                }
+                for entry in &self.transcript {
+                    ui.label(
+                        RichText::new(format!("> {}", entry.input))
+                            .monospace()
+                            .strong(),
+                    );
+                    match &entry.output {
+                        Ok(lines) => {
+                            for line in lines {
+                                ui.monospace(line);
+                            }
+                        }
+                        Err(error) => {
+                            ui.colored_label(
+                                Color32::from_rgb(220, 80, 80),
+                                RichText::new(error).monospace(),
+                            );
+                        }
+                    }
+                }
+            });
+        ui.separator();
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.input)
+                .font(egui::TextStyle::Monospace)
+                .hint_text("$('h1'), headers, cookies, timing, links")
+                .desired_width(f32::INFINITY),
+        );
+        if response.has_focus() {
+            if ui.input(|input| input.key_pressed(Key::ArrowUp)) {
+                self.recall_previous();
+            } else if ui.input(|input| input.key_pressed(Key::ArrowDown)) {
+                self.recall_next();
+            }
+        }
+        if response.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+            self.run(handle, page);
+            response.request_focus();
+        }
+    }
+
+    fn run(&mut self, handle: &BrowserHandle, page: Option<(TabId, &PageView)>) {
+        let input = std::mem::take(&mut self.input).trim().to_owned();
+        self.recall = None;
+        if input.is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&input) {
+            self.history.push(input.clone());
+            if self.history.len() > HISTORY_LIMIT {
+                self.history.remove(0);
+            }
+        }
+
+        let command = ConsoleCommand::parse(&input);
+        if command == Ok(ConsoleCommand::Clear) {
+            self.transcript.clear();
+            return;
+        }
+        let output = command.and_then(|command| {
+            let (tab, page) = page.ok_or_else(|| "no page is loaded in this tab".to_owned())?;
+            handle.run_console(tab, &page.url, &page.source, &command)
+        });
+        self.transcript.push(ConsoleEntry { input, output });
+    }
+
+    fn recall_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match self.recall {
+            Some(index) => index.saturating_sub(1),
+            None => self.history.len() - 1,
+        };
+        self.recall = Some(index);
+        self.input = self.history[index].clone();
+    }
+
+    fn recall_next(&mut self) {
+        let Some(index) = self.recall else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.recall = Some(index + 1);
+            self.input = self.history[index + 1].clone();
+        } else {
+            self.recall = None;
+            self.input.clear();
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use asterix_browser::{CrawlJob, CrawlOptions, CrawlSummary, CrawledPage};
+use eframe::egui;
+use egui::{Color32, Context as EguiContext, RichText};
+use url::Url;
+
+use crate::{profile_dir, ShellApp};
+
+const FAILED_COLOR: Color32 = Color32::from_rgb(220, 80, 80);
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum WizardStep {
+    #[default]
+    Scope,
+    Output,
+    Progress,
+}
+
+/// Site crawl wizard: scope, then output, then progress of the running crawl.
+#[derive(Default)]
+pub(crate) struct CrawlWizard {
+    open: bool,
+    step: WizardStep,
+    start: String,
+    options: CrawlOptions,
+    /// One filter per line.
+    include: String,
+    exclude: String,
+    delay_ms: u64,
+    mirror: bool,
+    /// Folder for mirrored pages and the link map.
+    output_dir: String,
+    link_map: bool,
+    job: Option<CrawlJob>,
+    pages: Vec<CrawledPage>,
+    summary: Option<CrawlSummary>,
+}
+
+impl ShellApp {
+    /// Opens the crawl wizard, starting from the active tab's page.
+    pub(crate) fn open_crawl_wizard(&mut self) {
+        let wizard = &mut self.crawl;
+        if wizard.job.is_none() {
+            let options = CrawlOptions::default();
+            let start = self.active_tab.as_ref().and_then(|tab| tab.url.as_ref());
+            let output_dir = start
+                .and_then(|url| url.host_str())
+                .and_then(|host| Some(profile_dir()?.join("crawls").join(host)));
+            *wizard = CrawlWizard {
+                start: start.map(Url::to_string).unwrap_or_default(),
+                delay_ms: options.delay.as_millis() as u64,
+                options,
+                output_dir: output_dir
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default(),
+                link_map: true,
+                ..CrawlWizard::default()
+            };
+        }
+        wizard.open = true;
+    }
+
+    pub(crate) fn render_crawl_wizard(&mut self, ctx: &EguiContext) {
+        let wizard = &mut self.crawl;
+        if !wizard.open {
+            return;
+        }
+        if let Some(job) = &mut wizard.job {
+            while let Some(page) = job.try_next() {
+                wizard.pages.push(page);
+            }
+            match job.try_complete() {
+                Some(summary) => {
+                    wizard.summary = Some(summary);
+                    wizard.job = None;
+                    self.status_line = match wizard.write_link_map() {
+                        Some(Ok(path)) => format!("Link map written to {}", path.display()),
+                        Some(Err(err)) => format!("Could not write the link map: {err}"),
+                        None => format!("Crawl finished: {} pages", wizard.pages.len()),
+                    };
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(200)),
+            }
+        }
+
+        let mut open = true;
+        let mut start = false;
+        let mut follow = None;
+        egui::Window::new("Crawl Site")
+            .open(&mut open)
+            .default_size([560.0, 420.0])
+            .show(ctx, |ui| match wizard.step {
+                WizardStep::Scope => wizard.scope_step(ui),
+                WizardStep::Output => start = wizard.output_step(ui),
+                WizardStep::Progress => follow = wizard.progress_step(ui),
+            });
+        // Closing the window drops the job, which stops the crawl.
+        if !open {
+            self.crawl = CrawlWizard::default();
+        }
+        if start {
+            self.start_crawl();
+        }
+        if let Some(url) = follow {
+            self.navigate_active(url);
+        }
+    }
+
+    fn start_crawl(&mut self) {
+        let wizard = &mut self.crawl;
+        let start = match Url::parse(wizard.start.trim()) {
+            Ok(start) => start,
+            Err(err) => {
+                self.status_line = format!("Invalid start URL: {err}");
+                wizard.step = WizardStep::Scope;
+                return;
+            }
+        };
+        let lines = |text: &str| {
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_owned)
+                .collect()
+        };
+        let mut options = wizard.options.clone();
+        options.include = lines(&wizard.include);
+        options.exclude = lines(&wizard.exclude);
+        options.delay = Duration::from_millis(wizard.delay_ms);
+        options.output_dir = wizard
+            .mirror
+            .then(|| PathBuf::from(wizard.output_dir.trim()))
+            .filter(|dir| !dir.as_os_str().is_empty());
+
+        match self.handle.crawl(start, options) {
+            Ok(job) => {
+                wizard.job = Some(job);
+                wizard.pages.clear();
+                wizard.summary = None;
+                wizard.step = WizardStep::Progress;
+            }
+            Err(err) => self.status_line = format!("Crawl failed to start: {err}"),
+        }
+    }
+}
+
+impl CrawlWizard {
+    /// Writes the visited pages and their links as `link-map.json` in the
+    /// output folder, when the user asked for one.
+    fn write_link_map(&self) -> Option<anyhow::Result<PathBuf>> {
+        if !self.link_map {
+            return None;
+        }
+        let path = PathBuf::from(self.output_dir.trim()).join("link-map.json");
+        Some((|| {
+            std::fs::create_dir_all(path.parent().unwrap_or(&path))?;
+            std::fs::write(&path, serde_json::to_string_pretty(&self.pages)?)?;
+            Ok(path)
+        })())
+    }
+
+    fn scope_step(&mut self, ui: &mut egui::Ui) {
+        ui.heading("1. What to crawl");
+        egui::Grid::new("crawl_scope")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Start URL");
+                ui.add(egui::TextEdit::singleline(&mut self.start).desired_width(360.0));
+                ui.end_row();
+                ui.label("Link depth");
+                ui.add(egui::DragValue::new(&mut self.options.max_depth).clamp_range(0..=10));
+                ui.end_row();
+                ui.label("Page limit");
+                ui.add(egui::DragValue::new(&mut self.options.max_pages).clamp_range(1..=10_000));
+                ui.end_row();
+                ui.label("Delay (ms)");
+                ui.add(
+                    egui::DragValue::new(&mut self.delay_ms)
+                        .clamp_range(0..=60_000)
+                        .speed(50),
+                );
+                ui.end_row();
+            });
+        ui.checkbox(
+            &mut self.options.same_origin,
+            "Stay on the start page's site",
+        );
+        ui.checkbox(&mut self.options.respect_robots, "Honour robots.txt");
+        ui.columns(2, |columns| {
+            columns[0].label("Only URLs containing (one per line)");
+            columns[0].text_edit_multiline(&mut self.include);
+            columns[1].label("Skip URLs containing (one per line)");
+            columns[1].text_edit_multiline(&mut self.exclude);
+        });
+        ui.separator();
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui
+                .add_enabled(
+                    Url::parse(self.start.trim()).is_ok(),
+                    egui::Button::new("Next"),
+                )
+                .clicked()
+            {
+                self.step = WizardStep::Output;
+            }
+        });
+    }
+
+    /// Returns whether the crawl should start.
+    fn output_step(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.heading("2. What to keep");
+        ui.checkbox(&mut self.mirror, "Mirror pages to disk");
+        ui.checkbox(&mut self.link_map, "Write a link map (link-map.json)");
+        ui.add_enabled_ui(self.mirror || self.link_map, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Folder");
+                ui.add(egui::TextEdit::singleline(&mut self.output_dir).desired_width(380.0));
+            });
+        });
+        ui.separator();
+        let mut start = false;
+        ui.horizontal(|ui| {
+            if ui.button("Back").clicked() {
+                self.step = WizardStep::Scope;
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let ready = !(self.mirror || self.link_map) || !self.output_dir.trim().is_empty();
+                start = ui
+                    .add_enabled(ready, egui::Button::new("Start Crawl"))
+                    .clicked();
+            });
+        });
+        start
+    }
+
+    /// Returns a crawled URL the user chose to open.
+    fn progress_step(&mut self, ui: &mut egui::Ui) -> Option<Url> {
+        ui.horizontal(|ui| {
+            if self.job.is_some() {
+                ui.spinner();
+                ui.label(format!(
+                    "Crawling\u{2026} {} pages so far",
+                    self.pages.len()
+                ));
+                if ui.button("Stop").clicked() {
+                    // Dropping the job stops the crawl after the current page.
+                    self.job = None;
+                    self.summary = Some(CrawlSummary {
+                        cancelled: true,
+                        ..CrawlSummary::default()
+                    });
+                }
+            } else if let Some(summary) = &self.summary {
+                let mut text = format!(
+                    "{} fetched, {} failed, {} disallowed by robots.txt",
+                    self.pages
+                        .iter()
+                        .filter(|page| page.error.is_none())
+                        .count(),
+                    self.pages
+                        .iter()
+                        .filter(|page| page.error.is_some())
+                        .count(),
+                    summary.disallowed
+                );
+                if summary.truncated {
+                    text.push_str("; page limit reached");
+                }
+                if summary.cancelled {
+                    text.push_str("; stopped");
+                }
+                ui.label(text);
+            }
+        });
+        if self.job.is_none() && ui.button("New Crawl").clicked() {
+            self.step = WizardStep::Scope;
+        }
+        ui.separator();
+
+        let mut follow = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                egui::Grid::new("crawl_pages")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for page in &self.pages {
+                            let status = page
+                                .status
+                                .map_or_else(|| "---".to_owned(), |status| status.to_string());
+                            let mut status = RichText::new(status).monospace();
+                            if page.error.is_some() {
+                                status = status.color(FAILED_COLOR);
+                            }
+                            ui.label(status);
+                            ui.label(page.depth.to_string());
+                            if ui.link(page.url.as_str()).clicked() {
+                                follow = Some(page.url.clone());
+                            }
+                            let links = ui.weak(format!("{} links", page.links.len()));
+                            match (&page.error, &page.saved_to) {
+                                (Some(err), _) => links.on_hover_text(err),
+                                (None, Some(path)) => {
+                                    links.on_hover_text(path.display().to_string())
+                                }
+                                (None, None) => links,
+                            };
+                            ui.end_row();
+                        }
+                    });
+            });
+        follow
+    }
+}
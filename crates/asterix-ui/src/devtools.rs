@@ -0,0 +1,165 @@
+use eframe::egui;
+use egui::Context as EguiContext;
+
+use crate::audit_panel::AuditPanel;
+use crate::composer::Composer;
+use crate::console_panel::ConsolePanel;
+use crate::eyedropper::EyedropperPanel;
+use crate::inspector::ElementsPanel;
+use crate::network_panel::{NetworkPanel, NetworkPanelAction};
+use crate::overrides_panel::OverridesPanel;
+use crate::performance_panel::PerformancePanel;
+use crate::seo_panel::SeoPanel;
+use crate::ShellApp;
+
+/// Panels available in the developer tools window.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum DevToolsTab {
+    #[default]
+    Network,
+    Composer,
+    Overrides,
+    Performance,
+    Console,
+    Elements,
+    Eyedropper,
+    Accessibility,
+    Seo,
+}
+
+impl DevToolsTab {
+    const ALL: [DevToolsTab; 9] = [
+        DevToolsTab::Elements,
+        DevToolsTab::Console,
+        DevToolsTab::Network,
+        DevToolsTab::Composer,
+        DevToolsTab::Overrides,
+        DevToolsTab::Performance,
+        DevToolsTab::Accessibility,
+        DevToolsTab::Seo,
+        DevToolsTab::Eyedropper,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            DevToolsTab::Network => "Network",
+            DevToolsTab::Composer => "Request Composer",
+            DevToolsTab::Overrides => "Overrides",
+            DevToolsTab::Performance => "Performance",
+            DevToolsTab::Console => "Console",
+            DevToolsTab::Elements => "Elements",
+            DevToolsTab::Eyedropper => "Eyedropper",
+            DevToolsTab::Accessibility => "Accessibility",
+            DevToolsTab::Seo => "SEO & Metadata",
+        }
+    }
+}
+
+/// State of the developer tools window.
+#[derive(Default)]
+pub(crate) struct DevTools {
+    pub(crate) open: bool,
+    pub(crate) tab: DevToolsTab,
+    pub(crate) composer: Composer,
+    pub(crate) network: NetworkPanel,
+    pub(crate) overrides: OverridesPanel,
+    pub(crate) performance: PerformancePanel,
+    pub(crate) console: ConsolePanel,
+    pub(crate) elements: ElementsPanel,
+    pub(crate) eyedropper: EyedropperPanel,
+    pub(crate) audit: AuditPanel,
+    pub(crate) seo: SeoPanel,
+}
+
+impl ShellApp {
+    /// Opens the developer tools on `tab`.
+    pub(crate) fn open_devtools(&mut self, tab: DevToolsTab) {
+        let active_url = self.active_tab.as_ref().and_then(|tab| tab.url.as_ref());
+        self.devtools.composer.suggest_url(active_url);
+        self.devtools.open = true;
+        self.devtools.tab = tab;
+    }
+
+    pub(crate) fn render_devtools(&mut self, ctx: &EguiContext) {
+        if !self.devtools.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut network_action = NetworkPanelAction::None;
+        let mut performance_export = None;
+        let mut inspect = None;
+        let page = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| Some((tab.id, self.pages.get(&tab.id)?)));
+        egui::Window::new("Developer Tools")
+            .open(&mut open)
+            .default_size([760.0, 540.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for tab in DevToolsTab::ALL {
+                        ui.selectable_value(&mut self.devtools.tab, tab, tab.label());
+                    }
+                });
+                ui.separator();
+                match self.devtools.tab {
+                    DevToolsTab::Network => {
+                        let entries = self.handle.network_log();
+                        let block_rules = self.handle.block_rules();
+                        let connections = self.handle.connection_stats();
+                        let conditions = self.handle.network_conditions();
+                        network_action = self.devtools.network.show(
+                            ui,
+                            &entries,
+                            &block_rules,
+                            &connections,
+                            conditions,
+                            page.and_then(|(_, page)| page.resources.as_ref()),
+                        );
+                    }
+                    DevToolsTab::Composer => self.devtools.composer.show(ui, &self.handle),
+                    DevToolsTab::Overrides => self.devtools.overrides.show(ui, &self.handle),
+                    DevToolsTab::Performance => {
+                        let entries = self.handle.network_log();
+                        performance_export = self.devtools.performance.show(ui, &entries, page);
+                    }
+                    DevToolsTab::Console => self.devtools.console.show(ui, &self.handle, page),
+                    DevToolsTab::Elements => {
+                        self.devtools.elements.show(ui, page.map(|(_, page)| page));
+                    }
+                    DevToolsTab::Eyedropper => self.devtools.eyedropper.show(ui),
+                    DevToolsTab::Accessibility => {
+                        inspect = self.devtools.audit.show(ui, page.map(|(_, page)| page));
+                    }
+                    DevToolsTab::Seo => self.devtools.seo.show(ui, &self.handle, page),
+                }
+            });
+        self.devtools.open = open;
+
+        if let Some(json) = performance_export {
+            self.export_performance(ctx, json);
+        }
+        if let Some(index) = inspect {
+            self.inspect_element(index);
+        }
+
+        match network_action {
+            NetworkPanelAction::None => {}
+            NetworkPanelAction::Clear => self.handle.clear_network_log(),
+            NetworkPanelAction::Resend(request) => {
+                self.devtools.composer.load(*request);
+                self.devtools.tab = DevToolsTab::Composer;
+            }
+            NetworkPanelAction::AddBlock(rule) => self.handle.add_block_rule(rule),
+            NetworkPanelAction::RemoveBlock(rule) => self.handle.remove_block_rule(&rule),
+            NetworkPanelAction::Override(url) => {
+                self.devtools.overrides.prefill(&url);
+                self.devtools.tab = DevToolsTab::Overrides;
+            }
+            NetworkPanelAction::SetConditions(conditions) => {
+                self.handle.set_network_conditions(conditions);
+            }
+        }
+    }
+}
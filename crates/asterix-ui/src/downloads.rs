@@ -0,0 +1,256 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use asterix_browser::{DownloadEntry, DownloadJob, DownloadStatus};
+use eframe::egui;
+use egui::Context as EguiContext;
+use tracing::warn;
+use url::Url;
+
+use crate::composer::format_size;
+use crate::ShellApp;
+
+/// Program that opens a file or folder with the desktop's default handler.
+#[cfg(target_os = "windows")]
+const OPENER: &str = "explorer";
+#[cfg(target_os = "macos")]
+const OPENER: &str = "open";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const OPENER: &str = "xdg-open";
+
+/// Downloads in flight and the search typed on `about:downloads`.
+#[derive(Default)]
+pub(crate) struct Downloads {
+    jobs: Vec<DownloadJob>,
+    filter: String,
+}
+
+enum DownloadAction {
+    OpenFile(PathBuf),
+    OpenFolder(PathBuf),
+    CopyLink(Url),
+    Redownload(Url),
+    Remove(u64),
+}
+
+impl ShellApp {
+    /// Saves `url` into the downloads folder in the background.
+    pub(crate) fn start_download(&mut self, url: Url) {
+        let Some(dir) = downloads_dir() else {
+            self.status_line = "No home folder to save downloads into".to_owned();
+            return;
+        };
+        match self.handle.download(url.clone(), dir) {
+            Ok(job) => {
+                self.downloads.jobs.push(job);
+                self.status_line = format!("Downloading {url}");
+            }
+            Err(err) => self.status_line = format!("Download failed to start: {err}"),
+        }
+    }
+
+    /// Downloads the page in the active tab.
+    pub(crate) fn download_active_page(&mut self) {
+        match self.active_tab.as_ref().and_then(|tab| tab.url.clone()) {
+            Some(url) if url.scheme() != "about" => self.start_download(url),
+            _ => self.status_line = "Open a web page to download it".to_owned(),
+        }
+    }
+
+    pub(crate) fn poll_downloads(&mut self, ctx: &EguiContext) {
+        if self.downloads.jobs.is_empty() {
+            return;
+        }
+        let mut finished = Vec::new();
+        self.downloads
+            .jobs
+            .retain_mut(|job| match job.try_complete() {
+                Some(result) => {
+                    finished.push(result);
+                    false
+                }
+                None => true,
+            });
+        for result in finished {
+            self.status_line = match result {
+                Ok(entry) => format!(
+                    "Saved {} ({})",
+                    entry.file_name(),
+                    format_size(entry.size as usize)
+                ),
+                Err(err) => format!("Download failed: {err}"),
+            };
+            self.save_downloads();
+        }
+        if !self.downloads.jobs.is_empty() {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        }
+    }
+
+    pub(crate) fn save_downloads(&self) {
+        if let Some(path) = &self.downloads_path {
+            if let Err(err) = self.handle.save_downloads(path) {
+                warn!("failed to save downloads: {err:#}");
+            }
+        }
+    }
+
+    pub(crate) fn render_downloads_page(&mut self, ui: &mut egui::Ui) {
+        let filter = self.downloads.filter.trim().to_lowercase();
+        let downloads = self.handle.downloads();
+        let matching: Vec<&DownloadEntry> = downloads
+            .iter()
+            .filter(|entry| {
+                filter.is_empty()
+                    || entry.file_name().to_lowercase().contains(&filter)
+                    || entry.url.as_str().to_lowercase().contains(&filter)
+            })
+            .collect();
+        let mut action = None;
+        let mut clear = false;
+
+        ui.heading("Downloads");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.downloads.filter)
+                    .hint_text("Search downloads")
+                    .desired_width(280.0),
+            );
+            ui.weak(format!(
+                "{} of {} downloads",
+                matching.len(),
+                downloads.len()
+            ));
+            if ui
+                .add_enabled(!downloads.is_empty(), egui::Button::new("Clear Downloads"))
+                .on_hover_text("Forgets finished downloads; the files are kept")
+                .clicked()
+            {
+                clear = true;
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("download_entries")
+                .num_columns(6)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for entry in &matching {
+                        ui.label(entry.file_name())
+                            .on_hover_text(entry.path.display().to_string());
+                        ui.add(egui::Label::new(entry.url.as_str()).truncate(true));
+                        ui.label(match entry.status {
+                            DownloadStatus::Completed => format_size(entry.size as usize),
+                            _ => String::new(),
+                        });
+                        ui.label(entry.started_at.format("%Y-%m-%d %H:%M").to_string());
+                        match &entry.status {
+                            DownloadStatus::InProgress => {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label(entry.status.label());
+                                });
+                            }
+                            DownloadStatus::Completed if !entry.path.exists() => {
+                                ui.weak("File deleted");
+                            }
+                            DownloadStatus::Completed => {
+                                ui.label(entry.status.label());
+                            }
+                            DownloadStatus::Failed(reason) => {
+                                ui.colored_label(ui.visuals().error_fg_color, entry.status.label())
+                                    .on_hover_text(reason);
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            let saved =
+                                entry.status == DownloadStatus::Completed && entry.path.exists();
+                            if ui
+                                .add_enabled(saved, egui::Button::new("Open").small())
+                                .clicked()
+                            {
+                                action = Some(DownloadAction::OpenFile(entry.path.clone()));
+                            }
+                            if ui.small_button("Show in Folder").clicked() {
+                                let folder = entry.path.parent().unwrap_or(&entry.path);
+                                action = Some(DownloadAction::OpenFolder(folder.to_owned()));
+                            }
+                            if ui.small_button("Copy Link").clicked() {
+                                action = Some(DownloadAction::CopyLink(entry.url.clone()));
+                            }
+                            if ui
+                                .add_enabled(
+                                    entry.status != DownloadStatus::InProgress,
+                                    egui::Button::new("Download Again").small(),
+                                )
+                                .clicked()
+                            {
+                                action = Some(DownloadAction::Redownload(entry.url.clone()));
+                            }
+                            if ui.small_button("Remove").clicked() {
+                                action = Some(DownloadAction::Remove(entry.id));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            if downloads.is_empty() {
+                ui.weak("Files you download appear here.");
+            }
+        });
+        if downloads
+            .iter()
+            .any(|entry| entry.status == DownloadStatus::InProgress)
+        {
+            ui.ctx().request_repaint_after(Duration::from_millis(200));
+        }
+
+        if clear {
+            self.handle.clear_downloads();
+            self.status_line = "Downloads cleared".to_owned();
+            self.save_downloads();
+            return;
+        }
+        match action {
+            Some(DownloadAction::OpenFile(path)) | Some(DownloadAction::OpenFolder(path)) => {
+                if let Err(err) = open_with_system(&path) {
+                    self.status_line = format!("Could not open {}: {err}", path.display());
+                }
+            }
+            Some(DownloadAction::CopyLink(url)) => {
+                ui.output_mut(|output| output.copied_text = url.to_string());
+                self.status_line = "Download link copied".to_owned();
+            }
+            Some(DownloadAction::Redownload(url)) => self.start_download(url),
+            Some(DownloadAction::Remove(id)) => {
+                self.handle.remove_download(id);
+                self.save_downloads();
+            }
+            None => {}
+        }
+    }
+}
+
+/// `~/Downloads`, or the home folder when there is none.
+pub(crate) fn downloads_dir() -> Option<PathBuf> {
+    let home = home::home_dir()?;
+    let downloads = home.join("Downloads");
+    Some(if downloads.is_dir() { downloads } else { home })
+}
+
+/// Opens `target`, a path or a URL, with the application the desktop
+/// associates with it.
+pub(crate) fn open_with_system(target: impl AsRef<OsStr>) -> std::io::Result<()> {
+    spawn_detached(Command::new(OPENER).arg(target))
+}
+
+/// Starts `command` without waiting for it, reaping it on a thread of its
+/// own once it exits so it does not linger as a zombie.
+pub(crate) fn spawn_detached(command: &mut Command) -> std::io::Result<()> {
+    let mut child = command.spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
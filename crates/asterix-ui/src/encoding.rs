@@ -0,0 +1,61 @@
+use asterix_browser::{TabId, TEXT_ENCODINGS};
+use eframe::egui;
+
+use crate::{PageView, ShellApp};
+
+impl ShellApp {
+    /// View > Text Encoding, forcing the active page to be decoded with a
+    /// chosen encoding when the detected one garbles it.
+    pub(crate) fn render_encoding_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(tab) = self.active_tab.as_ref().map(|tab| tab.id) else {
+            return;
+        };
+        let Some(page) = self.pages.get(&tab) else {
+            ui.add_enabled(false, egui::Button::new("Text Encoding"));
+            return;
+        };
+        let current = page.encoding_override;
+        let detected = page.response.charset.clone();
+        let mut choice = None;
+        ui.menu_button("Text Encoding", |ui| {
+            if ui
+                .radio(current.is_none(), "Auto-Detect")
+                .on_hover_text(format!("Currently {detected}"))
+                .clicked()
+            {
+                choice = Some(None);
+                ui.close_menu();
+            }
+            ui.separator();
+            for name in TEXT_ENCODINGS {
+                if ui.radio(current == Some(name), name).clicked() {
+                    choice = Some(Some(name));
+                    ui.close_menu();
+                }
+            }
+        });
+        if let Some(charset) = choice.filter(|&charset| charset != current) {
+            self.override_encoding(tab, charset);
+        }
+    }
+
+    /// Rebuilds the view of `tab` from the bytes it was received as,
+    /// decoded with `charset`, or detected again when `None`.
+    fn override_encoding(&mut self, tab: TabId, charset: Option<&'static str>) {
+        let Some(page) = self.pages.get(&tab) else {
+            return;
+        };
+        let Some(decoded) = self.handle.redecode_page(tab, &page.response, charset) else {
+            self.status_line = format!("Unknown text encoding {}", charset.unwrap_or_default());
+            return;
+        };
+        let mut view = PageView::new(&decoded).scrolled_to(Some(page.scroll_offset));
+        view.encoding_override = charset;
+        self.pages.insert(tab, view);
+        self.status_line = match charset {
+            Some(_) => format!("Decoded as {}", decoded.charset),
+            None => format!("Detected {}", decoded.charset),
+        };
+        self.refresh_tabs();
+    }
+}
@@ -0,0 +1,100 @@
+use asterix_browser::{flags, set_flag, FlagState};
+use eframe::egui;
+use egui::RichText;
+use tracing::warn;
+
+use crate::ShellApp;
+
+/// Label of the choice a profile made for a flag.
+fn choice_label(choice: Option<bool>) -> &'static str {
+    match choice {
+        None => "Default",
+        Some(true) => "Enabled",
+        Some(false) => "Disabled",
+    }
+}
+
+impl ShellApp {
+    pub(crate) fn save_flags(&self) {
+        if let Some(path) = &self.flags_path {
+            if let Err(err) = self.handle.save_flags(path) {
+                warn!("failed to save flags: {err:#}");
+            }
+        }
+    }
+
+    /// `about:flags`: experimental subsystems, switched per profile.
+    pub(crate) fn render_flags_page(&mut self, ui: &mut egui::Ui) {
+        let locked = self.settings_locked();
+        let mut changes: Vec<(&'static str, Option<bool>)> = Vec::new();
+
+        ui.heading("Experiments");
+        ui.label(
+            "Features still being worked on. They may break pages or the browser; turn one \
+             off again if something goes wrong. Choices are saved with the profile.",
+        );
+        if locked {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Restricted mode is on; experiments cannot be changed.",
+            );
+        }
+        let states = flags();
+        if ui
+            .add_enabled(
+                !locked && states.iter().any(|state| state.overridden),
+                egui::Button::new("Reset All"),
+            )
+            .clicked()
+        {
+            changes.extend(states.iter().map(|state| (state.flag.name, None)));
+        }
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for FlagState {
+                flag,
+                enabled,
+                overridden,
+            } in &states
+            {
+                // Flags that are available are on exactly when chosen so.
+                let mut choice = overridden.then_some(*enabled);
+                ui.horizontal(|ui| {
+                    let title = RichText::new(flag.title);
+                    ui.label(if *overridden { title.strong() } else { title });
+                    ui.weak(RichText::new(format!("#{}", flag.name)).monospace());
+                });
+                ui.label(flag.description);
+                if !flag.available {
+                    ui.weak("Not available in this build.");
+                    ui.add_space(8.0);
+                    continue;
+                }
+                let before = choice;
+                ui.add_enabled_ui(!locked, |ui| {
+                    egui::ComboBox::from_id_source(("flag", flag.name))
+                        .selected_text(choice_label(choice))
+                        .show_ui(ui, |ui| {
+                            for option in [None, Some(true), Some(false)] {
+                                ui.selectable_value(&mut choice, option, choice_label(option));
+                            }
+                        });
+                });
+                if choice != before {
+                    changes.push((flag.name, choice));
+                }
+                ui.add_space(8.0);
+            }
+        });
+
+        if changes.is_empty() {
+            return;
+        }
+        for (name, choice) in &changes {
+            set_flag(name, *choice);
+        }
+        self.save_flags();
+        self.status_line = "Experiments changed".to_owned();
+    }
+}
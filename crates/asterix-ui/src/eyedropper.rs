@@ -0,0 +1,197 @@
+use eframe::egui;
+use egui::{Color32, ColorImage, Context as EguiContext, CursorIcon, Pos2, Sense, Stroke, Vec2};
+
+use crate::ShellApp;
+
+const HISTORY_LIMIT: usize = 24;
+
+/// Eyedropper state: the picked color and the swatches picked before it.
+#[derive(Default)]
+pub(crate) struct EyedropperPanel {
+    pub(crate) picking: bool,
+    /// Point clicked while picking, sampled once the screenshot arrives.
+    pending: Option<Pos2>,
+    current: Option<Color32>,
+    history: Vec<Color32>,
+}
+
+impl EyedropperPanel {
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = if self.picking {
+                "Click anywhere\u{2026}"
+            } else {
+                "Pick Color"
+            };
+            if ui
+                .add_enabled(
+                    !self.picking && self.pending.is_none(),
+                    egui::Button::new(label),
+                )
+                .on_hover_text("Sample a pixel from the window; Escape cancels")
+                .clicked()
+            {
+                self.picking = true;
+            }
+            if !self.history.is_empty() && ui.button("Clear History").clicked() {
+                self.history.clear();
+            }
+        });
+        ui.separator();
+
+        let Some(color) = self.current else {
+            ui.label("No color picked yet.");
+            return;
+        };
+        ui.horizontal(|ui| {
+            let (rect, _) = ui.allocate_exact_size(Vec2::splat(64.0), Sense::hover());
+            ui.painter().rect(
+                rect,
+                4.0,
+                color,
+                Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color),
+            );
+            egui::Grid::new("eyedropper_values")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for (name, value) in [
+                        ("HEX", hex(color)),
+                        ("RGB", rgb(color)),
+                        ("HSL", hsl(color)),
+                    ] {
+                        ui.label(name);
+                        ui.monospace(&value);
+                        if ui.small_button("Copy").clicked() {
+                            ui.ctx().output_mut(|output| output.copied_text = value);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if self.history.is_empty() {
+            return;
+        }
+        ui.add_space(8.0);
+        ui.label("History");
+        ui.horizontal_wrapped(|ui| {
+            for swatch in self.history.clone() {
+                let (rect, response) = ui.allocate_exact_size(Vec2::splat(20.0), Sense::click());
+                let stroke = if Some(swatch) == self.current {
+                    ui.visuals().selection.stroke
+                } else {
+                    ui.visuals().widgets.noninteractive.bg_stroke
+                };
+                ui.painter().rect(rect, 2.0, swatch, stroke);
+                let response =
+                    response.on_hover_text(format!("{}\nRight-click to copy", hex(swatch)));
+                if response.clicked() {
+                    self.current = Some(swatch);
+                }
+                if response.secondary_clicked() {
+                    ui.ctx()
+                        .output_mut(|output| output.copied_text = hex(swatch));
+                }
+            }
+        });
+    }
+
+    fn remember(&mut self, color: Color32) {
+        self.current = Some(color);
+        self.history.retain(|swatch| *swatch != color);
+        self.history.insert(0, color);
+        self.history.truncate(HISTORY_LIMIT);
+    }
+}
+
+impl ShellApp {
+    /// Captures the click while the eyedropper is active and samples the
+    /// window screenshot requested for it.
+    pub(crate) fn run_eyedropper(&mut self, ctx: &EguiContext) {
+        let eyedropper = &mut self.devtools.eyedropper;
+        if let Some(pos) = eyedropper.pending {
+            let image = ctx.input(|input| {
+                input.events.iter().find_map(|event| match event {
+                    egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                    _ => None,
+                })
+            });
+            if let Some(image) = image {
+                eyedropper.pending = None;
+                if let Some(color) = sample(&image, pos, ctx.pixels_per_point()) {
+                    eyedropper.remember(color);
+                    self.status_line = format!("Picked {}", hex(color));
+                }
+            }
+            return;
+        }
+        if !eyedropper.picking {
+            return;
+        }
+        if ctx.input(|input| input.key_pressed(egui::Key::Escape)) {
+            eyedropper.picking = false;
+            return;
+        }
+
+        // A click-catching layer over the whole window keeps the picking click
+        // from also following links or pressing buttons underneath.
+        let screen = ctx.screen_rect();
+        let response = egui::Area::new(egui::Id::new("eyedropper_overlay"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(screen.min)
+            .show(ctx, |ui| ui.allocate_rect(screen, Sense::click()))
+            .inner
+            .on_hover_cursor(CursorIcon::Crosshair);
+        if let Some(pos) = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())
+        {
+            eyedropper.picking = false;
+            eyedropper.pending = Some(pos);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+            ctx.request_repaint();
+        }
+    }
+}
+
+fn sample(image: &ColorImage, pos: Pos2, pixels_per_point: f32) -> Option<Color32> {
+    let [width, height] = image.size;
+    let x = (pos.x * pixels_per_point).floor() as usize;
+    let y = (pos.y * pixels_per_point).floor() as usize;
+    (x < width && y < height).then(|| image.pixels[y * width + x])
+}
+
+fn hex(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn rgb(color: Color32) -> String {
+    format!("rgb({}, {}, {})", color.r(), color.g(), color.b())
+}
+
+fn hsl(color: Color32) -> String {
+    let [r, g, b] = [color.r(), color.g(), color.b()].map(|channel| channel as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    let (hue, saturation) = if delta == 0.0 {
+        (0.0, 0.0)
+    } else {
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+        (hue * 60.0, saturation)
+    };
+    format!(
+        "hsl({:.0}, {:.0}%, {:.0}%)",
+        hue,
+        saturation * 100.0,
+        lightness * 100.0
+    )
+}
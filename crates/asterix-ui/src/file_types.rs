@@ -0,0 +1,264 @@
+use asterix_browser::{FileAction, FileTypeRule, PageResponse, TabId};
+use eframe::egui;
+use egui::{Context as EguiContext, RichText};
+use tracing::info;
+use url::Url;
+
+use crate::composer::format_size;
+use crate::downloads::downloads_dir;
+use crate::{PageView, ShellApp};
+
+/// Window editing how responses are handled by file type.
+#[derive(Default)]
+pub(crate) struct FileTypesPanel {
+    pub(crate) open: bool,
+    pattern: String,
+    action: FileAction,
+}
+
+/// Response the rules held back until the user picks what to do with it.
+pub(crate) struct PendingFile {
+    tab: TabId,
+    requested: Url,
+    page: PageResponse,
+    remember: bool,
+}
+
+impl ShellApp {
+    /// Handles a loaded page the file-type rules did not let through to the
+    /// tab, leaving the page the tab was showing in place.
+    pub(crate) fn route_page(&mut self, tab: TabId, requested: Url, page: PageResponse) {
+        if let Some(active) = self.active_tab.as_ref().filter(|active| active.id == tab) {
+            self.url_input = active
+                .url
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+        }
+        match page.action {
+            FileAction::Download => self.save_routed_page(&page),
+            FileAction::Ask => {
+                self.pending_file = Some(PendingFile {
+                    tab,
+                    requested,
+                    page,
+                    remember: false,
+                });
+            }
+            FileAction::Open => self.show_routed_page(tab, &requested, page),
+        }
+    }
+
+    fn save_routed_page(&mut self, page: &PageResponse) {
+        let Some(dir) = downloads_dir() else {
+            self.status_line = "No home folder to save downloads into".to_owned();
+            return;
+        };
+        match self.handle.save_download(page, &dir) {
+            Ok(entry) => {
+                info!(
+                    target = "ui",
+                    "downloaded {} to {}",
+                    page.url,
+                    entry.path.display()
+                );
+                self.status_line = format!("Downloaded {}", entry.file_name());
+                self.save_downloads();
+            }
+            Err(err) => self.status_line = format!("Download failed: {err}"),
+        }
+    }
+
+    fn show_routed_page(&mut self, tab: TabId, requested: &Url, page: PageResponse) {
+        self.handle.open_routed_page(tab, requested, &page);
+        self.status_line = format!("Loaded {}", page.url);
+        if self.active_tab.as_ref().map(|tab| tab.id) == Some(tab) {
+            self.url_input = page.url.to_string();
+            let view = PageView::new(&page).scrolled_to(self.pending_scroll.remove(&tab));
+            self.back_forward.replace(&mut self.pages, tab, view);
+        } else {
+            self.deferred_pages.insert(tab, page);
+        }
+        self.refresh_tabs();
+    }
+
+    /// Asks whether to open or download the response held back by an
+    /// "Always ask" rule.
+    pub(crate) fn render_file_prompt(&mut self, ctx: &EguiContext) {
+        let Some(pending) = &mut self.pending_file else {
+            return;
+        };
+        let pattern = type_pattern(&pending.page);
+        let mut choice = None;
+        let mut open = true;
+        let mut cancel = false;
+        egui::Window::new("Open or Download?")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(pending.page.url.as_str()).monospace());
+                ui.weak(format!(
+                    "{} \u{b7} {}",
+                    pending.page.mime_type.as_deref().unwrap_or("unknown type"),
+                    format_size(pending.page.bytes.len())
+                ));
+                if let Some(pattern) = &pattern {
+                    ui.checkbox(
+                        &mut pending.remember,
+                        format!("Do this automatically for {pattern} files"),
+                    );
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        choice = Some(FileAction::Open);
+                    }
+                    if ui.button("Download").clicked() {
+                        choice = Some(FileAction::Download);
+                    }
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if !open || cancel {
+            self.pending_file = None;
+            return;
+        }
+        let Some(action) = choice else {
+            return;
+        };
+        let Some(pending) = self.pending_file.take() else {
+            return;
+        };
+        if let Some(pattern) = pattern.filter(|_| pending.remember) {
+            self.set_file_type_rule(pattern, action);
+        }
+        match action {
+            FileAction::Download => self.save_routed_page(&pending.page),
+            _ => self.show_routed_page(pending.tab, &pending.requested, pending.page),
+        }
+    }
+
+    /// Sets the action of the rule for `pattern`, adding one at the end when
+    /// there is none.
+    fn set_file_type_rule(&mut self, pattern: String, action: FileAction) {
+        let rules = &mut self.settings.file_type_rules;
+        match rules
+            .iter_mut()
+            .find(|rule| rule.pattern.eq_ignore_ascii_case(&pattern))
+        {
+            Some(rule) => rule.action = action,
+            None => rules.push(FileTypeRule { pattern, action }),
+        }
+        self.apply_file_type_rules();
+    }
+
+    fn apply_file_type_rules(&mut self) {
+        self.save_settings();
+        self.handle
+            .set_file_type_rules(self.settings.file_type_rules.clone());
+    }
+
+    pub(crate) fn render_file_types_window(&mut self, ctx: &EguiContext) {
+        if !self.file_types.open {
+            return;
+        }
+        let mut open = true;
+        let mut changed = false;
+        let mut remove = None;
+        let mut add = false;
+        let panel = &mut self.file_types;
+        let rules = &mut self.settings.file_type_rules;
+        egui::Window::new("File Types")
+            .open(&mut open)
+            .default_size([420.0, 280.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "Decide what happens to a file when it arrives. The first matching rule \
+                     wins; files without one open in the viewer.",
+                );
+                ui.separator();
+                egui::Grid::new("file_type_rules")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (index, rule) in rules.iter_mut().enumerate() {
+                            ui.monospace(&rule.pattern);
+                            changed |=
+                                action_picker(ui, ("file_type_action", index), &mut rule.action);
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if rules.is_empty() {
+                    ui.weak("No rules yet.");
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut panel.pattern)
+                            .hint_text("application/pdf, image/* or .zip")
+                            .desired_width(200.0),
+                    );
+                    action_picker(ui, "new_file_type_action", &mut panel.action);
+                    add = ui
+                        .add_enabled(is_valid_pattern(&panel.pattern), egui::Button::new("Add"))
+                        .clicked();
+                });
+            });
+        self.file_types.open &= open;
+
+        if let Some(index) = remove {
+            self.settings.file_type_rules.remove(index);
+            changed = true;
+        }
+        if add {
+            let pattern = self.file_types.pattern.trim().to_ascii_lowercase();
+            self.file_types.pattern.clear();
+            self.set_file_type_rule(pattern, self.file_types.action);
+        } else if changed {
+            self.apply_file_type_rules();
+        }
+    }
+}
+
+fn action_picker(ui: &mut egui::Ui, id: impl std::hash::Hash, action: &mut FileAction) -> bool {
+    let before = *action;
+    egui::ComboBox::from_id_source(id)
+        .selected_text(action.label())
+        .show_ui(ui, |ui| {
+            for option in FileAction::ALL {
+                ui.selectable_value(action, option, option.label());
+            }
+        });
+    *action != before
+}
+
+/// A MIME type with a slash, or a file extension with a leading dot.
+fn is_valid_pattern(pattern: &str) -> bool {
+    let pattern = pattern.trim();
+    match pattern.strip_prefix('.') {
+        Some(extension) => !extension.is_empty() && !extension.contains(['.', '/']),
+        None => pattern
+            .split_once('/')
+            .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty()),
+    }
+}
+
+/// Rule pattern a remembered choice for `page` is stored under: its MIME
+/// type, or its extension when the type says nothing about the file.
+fn type_pattern(page: &PageResponse) -> Option<String> {
+    let mime = page
+        .mime_type
+        .as_deref()
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase())
+        .filter(|mime| !mime.is_empty() && mime != "application/octet-stream");
+    mime.or_else(|| {
+        let name = page.url.path_segments()?.next_back()?;
+        let (stem, extension) = name.rsplit_once('.')?;
+        (!stem.is_empty() && !extension.is_empty())
+            .then(|| format!(".{}", extension.to_ascii_lowercase()))
+    })
+}
@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use asterix_browser::{
+    extract_forms, FormMethod, MacroStep, PageForm, SavedLogin, TabId, ThrowawayIdentity,
+};
+use eframe::egui;
+
+use crate::ShellApp;
+
+/// Current two-factor code of a login saved for the page's site.
+pub(crate) struct OneTimeCode {
+    username: String,
+    code: String,
+    remaining_secs: u64,
+}
+
+pub(crate) fn one_time_codes(logins: &[SavedLogin]) -> Vec<OneTimeCode> {
+    logins
+        .iter()
+        .filter_map(|login| {
+            let (code, remaining_secs) = login.totp()?.current();
+            Some(OneTimeCode {
+                username: login.username.clone(),
+                code,
+                remaining_secs,
+            })
+        })
+        .collect()
+}
+
+/// Two-factor codes for the site, each to copy or to fill into the first
+/// one-time code field of `forms`. Returns a status message for the action
+/// taken.
+pub(crate) fn show_one_time_codes(
+    ui: &mut egui::Ui,
+    codes: &[OneTimeCode],
+    forms: &mut [PageForm],
+) -> Option<String> {
+    let mut status = None;
+    for code in codes {
+        ui.horizontal(|ui| {
+            ui.label(format!("Two-factor code for {}", code.username));
+            ui.monospace(&code.code);
+            ui.weak(format!("{}s", code.remaining_secs));
+            if ui.small_button("Copy").clicked() {
+                ui.output_mut(|output| output.copied_text = code.code.clone());
+                status = Some(format!("Copied the two-factor code for {}", code.username));
+            }
+            if ui
+                .add_enabled(!forms.is_empty(), egui::Button::new("Fill").small())
+                .clicked()
+            {
+                let filled = forms
+                    .iter_mut()
+                    .any(|form| form.fill_one_time_code(&code.code));
+                status = Some(if filled {
+                    format!("Filled the two-factor code for {}", code.username)
+                } else {
+                    "No one-time code field in this page's forms".to_owned()
+                });
+            }
+        });
+    }
+    if let Some(remaining) = codes.iter().map(|code| code.remaining_secs).min() {
+        ui.ctx()
+            .request_repaint_after(Duration::from_secs(remaining));
+    }
+    status
+}
+
+/// What the user asked to do with one of the page's forms, by index.
+pub(crate) enum FormAction {
+    Submit(usize),
+    FillIdentity(usize),
+}
+
+/// Editable fields of the page's forms, shown above the preview. Forms in
+/// `private` tabs can be filled with a throwaway identity.
+pub(crate) fn show_forms(
+    ui: &mut egui::Ui,
+    tab_id: TabId,
+    private: bool,
+    forms: &mut [PageForm],
+) -> Option<FormAction> {
+    let mut action = None;
+    egui::CollapsingHeader::new(format!("Forms ({})", forms.len()))
+        .id_source(("page_forms", tab_id))
+        .show(ui, |ui| {
+            for (index, form) in forms.iter_mut().enumerate() {
+                let method = match form.method {
+                    FormMethod::Get => "GET",
+                    FormMethod::Post => "POST",
+                };
+                ui.horizontal(|ui| {
+                    ui.monospace(method);
+                    ui.label(form.action.as_str());
+                });
+                egui::Grid::new(("page_form", tab_id, index))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (field_index, field) in form.fields.iter_mut().enumerate() {
+                            ui.label(&field.name);
+                            match field.kind.as_str() {
+                                "hidden" => {
+                                    ui.weak(&field.value);
+                                }
+                                "select" => {
+                                    egui::ComboBox::from_id_source((
+                                        "form_select",
+                                        tab_id,
+                                        index,
+                                        field_index,
+                                    ))
+                                    .selected_text(field.value.clone())
+                                    .show_ui(ui, |ui| {
+                                        for option in &field.options {
+                                            ui.selectable_value(
+                                                &mut field.value,
+                                                option.clone(),
+                                                option,
+                                            );
+                                        }
+                                    });
+                                }
+                                "textarea" => {
+                                    ui.text_edit_multiline(&mut field.value);
+                                }
+                                "password" => {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut field.value).password(true),
+                                    );
+                                }
+                                _ => {
+                                    ui.text_edit_singleline(&mut field.value);
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button("Submit").clicked() {
+                        action = Some(FormAction::Submit(index));
+                    }
+                    if private
+                        && ui
+                            .button("Fill with Throwaway Identity")
+                            .on_hover_text("Random name, email address and password")
+                            .clicked()
+                    {
+                        action = Some(FormAction::FillIdentity(index));
+                    }
+                });
+                ui.separator();
+            }
+        });
+    action
+}
+
+impl ShellApp {
+    /// Fills form `index` of the page in `tab` with a newly generated
+    /// identity, for signing up without real details.
+    pub(crate) fn fill_throwaway_identity(&mut self, tab: TabId, index: usize) {
+        let domain = self.settings.throwaway_email_domain.trim();
+        let identity = ThrowawayIdentity::generate((!domain.is_empty()).then_some(domain));
+        let Some(form) = self
+            .pages
+            .get_mut(&tab)
+            .and_then(|page| page.forms.get_mut(index))
+        else {
+            return;
+        };
+        self.status_line = match form.fill_identity(&identity) {
+            0 => "No name, email or password fields in this form".to_owned(),
+            filled => format!(
+                "Filled {filled} fields as {} <{}>",
+                identity.full_name(),
+                identity.email
+            ),
+        };
+    }
+
+    /// View menu entry for the email domain of throwaway identities.
+    pub(crate) fn render_throwaway_identity_settings(&mut self, ui: &mut egui::Ui, locked: bool) {
+        ui.horizontal(|ui| {
+            ui.label("Throwaway email domain");
+            let edit = ui
+                .add_enabled(
+                    !locked,
+                    egui::TextEdit::singleline(&mut self.settings.throwaway_email_domain)
+                        .hint_text("disposable inbox")
+                        .desired_width(160.0),
+                )
+                .on_hover_text(
+                    "Catch-all domain of an email alias service, such as a SimpleLogin \
+                     or addy.io domain, for the identities filled into sign-up forms",
+                );
+            if edit.lost_focus() {
+                self.save_settings();
+            }
+        });
+    }
+
+    /// Submits form `index` of the page in `tab` with the values entered
+    /// above the preview.
+    pub(crate) fn submit_page_form(&mut self, tab: TabId, index: usize) {
+        let Some(page) = self.pages.get(&tab) else {
+            return;
+        };
+        let Some(form) = page.forms.get(index).cloned() else {
+            return;
+        };
+        if self.macros.is_recording() {
+            let defaults = extract_forms(&page.source, &page.url);
+            let defaults = defaults.get(index).map(|form| form.fields.as_slice());
+            for field in &form.fields {
+                let unchanged = defaults
+                    .and_then(|fields| fields.iter().find(|default| default.name == field.name))
+                    .is_some_and(|default| default.value == field.value);
+                if !unchanged {
+                    self.macros.record(MacroStep::Fill {
+                        selector: form.field_selector(&field.name),
+                        value: field.value.clone(),
+                    });
+                }
+            }
+            self.macros.record(MacroStep::Submit {
+                selector: form.selector.clone(),
+            });
+        }
+        match self.start_navigation(form.request(tab)) {
+            Ok(()) => self.status_line = format!("Submitting to {}", form.action),
+            Err(err) => self.status_line = format!("Navigation error: {err}"),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use asterix_browser::{
+    create_backup, restore_backup, BackupSummary, BrowserHandle, CrawlOptions, SCRIPT_API,
+};
+use url::Url;
+
+const USAGE: &str = "\
+usage: asterix [COMMAND]
+
+Without a command the desktop shell is started.
+
+commands:
+  crawl URL [options]     fetch URL and the pages it links to
+      --depth N           follow links N levels deep (default 2)
+      --max-pages N       stop after N pages (default 100)
+      --any-origin        also follow links to other sites
+      --include TEXT      only fetch URLs containing TEXT (repeatable)
+      --exclude TEXT      skip URLs containing TEXT (repeatable)
+      --delay-ms N        pause between requests (default 1000)
+      --ignore-robots     do not honour robots.txt
+      --output DIR        mirror fetched pages into DIR
+      --link-map FILE     write the pages and their links as JSON
+  run SCRIPT              run a Rhai automation script; relative paths it
+                          saves to are resolved against the current directory
+  scripting-help          list the functions available to scripts
+  backup FILE             write an encrypted backup of the profile to FILE
+  restore FILE            replace the profile with the backup in FILE; the
+                          current one is kept as .asterix.before-restore
+      --profile DIR       back up or restore DIR instead of ~/.asterix
+
+The backup passphrase is read from ASTERIX_BACKUP_PASSPHRASE, or asked for.";
+
+/// Work requested on the command line instead of the desktop shell.
+pub enum Command {
+    Crawl {
+        start: Url,
+        options: CrawlOptions,
+        link_map: Option<PathBuf>,
+    },
+    Run {
+        script: PathBuf,
+    },
+    Backup {
+        file: PathBuf,
+        profile: PathBuf,
+    },
+    Restore {
+        file: PathBuf,
+        profile: PathBuf,
+    },
+}
+
+impl Command {
+    /// Parses the arguments after the program name; `None` starts the shell.
+    pub fn parse(args: &[String]) -> anyhow::Result<Option<Self>> {
+        let Some((command, rest)) = args.split_first() else {
+            return Ok(None);
+        };
+        match command.as_str() {
+            "crawl" => parse_crawl(rest).map(Some),
+            "run" => match rest {
+                [script] => Ok(Some(Command::Run {
+                    script: PathBuf::from(script),
+                })),
+                _ => bail!("run needs exactly one script\n\n{USAGE}"),
+            },
+            "backup" => {
+                parse_backup(rest).map(|(file, profile)| Some(Command::Backup { file, profile }))
+            }
+            "restore" => {
+                parse_backup(rest).map(|(file, profile)| Some(Command::Restore { file, profile }))
+            }
+            "scripting-help" => {
+                println!("{SCRIPT_API}");
+                std::process::exit(0);
+            }
+            "-h" | "--help" | "help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            other => bail!("unknown command `{other}`\n\n{USAGE}"),
+        }
+    }
+
+    pub fn run(self, handle: &BrowserHandle) -> anyhow::Result<()> {
+        match self {
+            Command::Crawl {
+                start,
+                options,
+                link_map,
+            } => crawl(handle, start, options, link_map),
+            Command::Run { script } => run_script(handle, &script),
+            Command::Backup { file, profile } => {
+                let summary = create_backup(&profile, &file, &passphrase()?)?;
+                println!("backed up {} to {}", describe(&summary), file.display());
+                Ok(())
+            }
+            Command::Restore { file, profile } => {
+                let summary = restore_backup(&file, &profile, &passphrase()?)?;
+                println!("restored {} into {}", describe(&summary), profile.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+fn parse_crawl(args: &[String]) -> anyhow::Result<Command> {
+    let mut start = None;
+    let mut options = CrawlOptions::default();
+    let mut link_map = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .with_context(|| format!("{flag} needs a value\n\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--depth" => options.max_depth = value(arg)?.parse().context("invalid --depth")?,
+            "--max-pages" => {
+                options.max_pages = value(arg)?.parse().context("invalid --max-pages")?;
+            }
+            "--any-origin" => options.same_origin = false,
+            "--include" => options.include.push(value(arg)?.clone()),
+            "--exclude" => options.exclude.push(value(arg)?.clone()),
+            "--delay-ms" => {
+                let millis = value(arg)?.parse().context("invalid --delay-ms")?;
+                options.delay = Duration::from_millis(millis);
+            }
+            "--ignore-robots" => options.respect_robots = false,
+            "--output" => options.output_dir = Some(PathBuf::from(value(arg)?)),
+            "--link-map" => link_map = Some(PathBuf::from(value(arg)?)),
+            flag if flag.starts_with("--") => bail!("unknown option `{flag}`\n\n{USAGE}"),
+            url if start.is_none() => {
+                start = Some(Url::parse(url).with_context(|| format!("invalid URL `{url}`"))?);
+            }
+            extra => bail!("unexpected argument `{extra}`\n\n{USAGE}"),
+        }
+    }
+    let Some(start) = start else {
+        bail!("crawl needs a start URL\n\n{USAGE}");
+    };
+    Ok(Command::Crawl {
+        start,
+        options,
+        link_map,
+    })
+}
+
+/// Parses `FILE [--profile DIR]` for `backup` and `restore`.
+fn parse_backup(args: &[String]) -> anyhow::Result<(PathBuf, PathBuf)> {
+    let mut file = None;
+    let mut profile = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => {
+                let dir = args
+                    .next()
+                    .with_context(|| format!("--profile needs a value\n\n{USAGE}"))?;
+                profile = Some(PathBuf::from(dir));
+            }
+            flag if flag.starts_with("--") => bail!("unknown option `{flag}`\n\n{USAGE}"),
+            path if file.is_none() => file = Some(PathBuf::from(path)),
+            extra => bail!("unexpected argument `{extra}`\n\n{USAGE}"),
+        }
+    }
+    let Some(file) = file else {
+        bail!("a backup file is needed\n\n{USAGE}");
+    };
+    let Some(profile) = profile.or_else(asterix_ui::profile_dir) else {
+        bail!("no home directory to find the profile in; pass --profile");
+    };
+    Ok((file, profile))
+}
+
+fn passphrase() -> anyhow::Result<String> {
+    if let Ok(passphrase) = std::env::var("ASTERIX_BACKUP_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    eprint!("backup passphrase: ");
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .context("failed to read the passphrase")?;
+    let passphrase = line.trim_end_matches(['\r', '\n']).to_owned();
+    if passphrase.is_empty() {
+        bail!("no passphrase given");
+    }
+    Ok(passphrase)
+}
+
+fn describe(summary: &BackupSummary) -> String {
+    format!("{} files ({} bytes)", summary.files, summary.bytes)
+}
+
+fn crawl(
+    handle: &BrowserHandle,
+    start: Url,
+    options: CrawlOptions,
+    link_map: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let mut job = handle.crawl(start, options)?;
+    let mut pages = Vec::new();
+    while let Some(page) = job.blocking_next() {
+        let status = page
+            .status
+            .map_or_else(|| "---".to_owned(), |status| status.to_string());
+        match (&page.error, &page.saved_to) {
+            (Some(err), _) => println!("{status} {} ({err})", page.url),
+            (None, Some(path)) => println!("{status} {} -> {}", page.url, path.display()),
+            (None, None) => println!("{status} {}", page.url),
+        }
+        pages.push(page);
+    }
+    let summary = job.blocking_finish();
+
+    if let Some(path) = link_map {
+        let json = serde_json::to_string_pretty(&pages)?;
+        std::fs::write(&path, json)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        println!("link map written to {}", path.display());
+    }
+    println!(
+        "{} fetched, {} failed, {} disallowed by robots.txt{}",
+        summary.fetched,
+        summary.failed,
+        summary.disallowed,
+        if summary.truncated {
+            "; page limit reached"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+fn run_script(handle: &BrowserHandle, script: &Path) -> anyhow::Result<()> {
+    let source = std::fs::read_to_string(script)
+        .with_context(|| format!("failed to read {}", script.display()))?;
+    let name = script
+        .file_stem()
+        .map_or_else(|| "script".into(), |name| name.to_string_lossy());
+    let mut job = handle.run_script(&name, source, std::env::current_dir()?)?;
+    while let Some(line) = job.blocking_next() {
+        println!("{line}");
+    }
+    job.blocking_finish()
+        .map_err(|err| anyhow::anyhow!("{} failed: {err}", script.display()))
+}
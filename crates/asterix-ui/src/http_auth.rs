@@ -0,0 +1,91 @@
+use asterix_browser::{AuthChallenge, AuthScheme, TabId};
+use eframe::egui;
+use egui::Context as EguiContext;
+
+use crate::ShellApp;
+
+/// Sign-in a server asked for, waiting for the user's username and
+/// password.
+pub(crate) struct AuthPrompt {
+    tab: TabId,
+    challenge: AuthChallenge,
+    username: String,
+    password: String,
+}
+
+impl AuthPrompt {
+    /// Prompt for the credentials `challenge` wants. The refused page stays
+    /// on screen until they are given.
+    pub(crate) fn new(tab: TabId, challenge: AuthChallenge) -> Self {
+        Self {
+            tab,
+            challenge,
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+}
+
+impl ShellApp {
+    pub(crate) fn render_auth_prompt(&mut self, ctx: &EguiContext) {
+        let Some(prompt) = &mut self.pending_auth else {
+            return;
+        };
+        let url = &prompt.challenge.url;
+        let mut open = true;
+        let mut sign_in = false;
+        let mut cancel = false;
+        egui::Window::new("Sign In")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} asks for a username and password.",
+                    url.origin().ascii_serialization()
+                ));
+                if !prompt.challenge.realm.is_empty() {
+                    ui.label(format!(
+                        "The site says: \u{201c}{}\u{201d}",
+                        prompt.challenge.realm
+                    ));
+                }
+                if prompt.challenge.scheme == AuthScheme::Basic && url.scheme() != "https" {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "The password will be sent over an unencrypted connection.",
+                    );
+                }
+                egui::Grid::new("auth_prompt")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Username");
+                        ui.text_edit_singleline(&mut prompt.username);
+                        ui.end_row();
+                        ui.label("Password");
+                        let password =
+                            ui.add(egui::TextEdit::singleline(&mut prompt.password).password(true));
+                        sign_in =
+                            password.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        ui.end_row();
+                    });
+                ui.horizontal(|ui| {
+                    sign_in |= ui.button("Sign In").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if !open || cancel {
+            self.pending_auth = None;
+            return;
+        }
+        if !sign_in {
+            return;
+        }
+        let Some(prompt) = self.pending_auth.take() else {
+            return;
+        };
+        self.handle
+            .answer_auth_challenge(&prompt.challenge, &prompt.username, &prompt.password);
+        self.load_tab(prompt.tab, prompt.challenge.url);
+    }
+}
@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use asterix_browser::{discover_profiles, ImportJob, ImportSelection, ImportSource, ImportSummary};
+use eframe::egui;
+use egui::{Context as EguiContext, RichText};
+
+use crate::ShellApp;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum WizardStep {
+    #[default]
+    Source,
+    Data,
+    Result,
+}
+
+/// Import wizard: pick a profile, then what to bring over, then the outcome.
+#[derive(Default)]
+pub(crate) struct ImportWizard {
+    open: bool,
+    step: WizardStep,
+    sources: Vec<ImportSource>,
+    selected: usize,
+    selection: ImportSelection,
+    job: Option<ImportJob>,
+    result: Option<Result<ImportSummary, String>>,
+}
+
+impl ShellApp {
+    /// Opens the import wizard, looking for profiles of other browsers.
+    pub(crate) fn open_import_wizard(&mut self) {
+        if self.import.job.is_none() {
+            self.import = ImportWizard {
+                sources: home::home_dir()
+                    .map(|home| discover_profiles(&home))
+                    .unwrap_or_default(),
+                ..ImportWizard::default()
+            };
+        }
+        self.import.open = true;
+    }
+
+    pub(crate) fn render_import_wizard(&mut self, ctx: &EguiContext) {
+        let wizard = &mut self.import;
+        if let Some(job) = &mut wizard.job {
+            match job.try_complete() {
+                Some(result) => {
+                    wizard.job = None;
+                    self.status_line = match &result {
+                        Ok(summary) => format!(
+                            "Imported {} bookmarks, {} history entries and {} passwords",
+                            summary.bookmarks, summary.history, summary.passwords
+                        ),
+                        Err(err) => format!("Import failed: {err}"),
+                    };
+                    wizard.result = Some(result.map_err(|err| err.to_string()));
+                    self.save_bookmarks();
+                    self.save_history();
+                    self.save_logins();
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(200)),
+            }
+        }
+
+        let wizard = &mut self.import;
+        if !wizard.open {
+            return;
+        }
+        let mut open = true;
+        let mut start = false;
+        egui::Window::new("Import from Another Browser")
+            .open(&mut open)
+            .default_size([460.0, 320.0])
+            .show(ctx, |ui| match wizard.step {
+                WizardStep::Source => wizard.source_step(ui),
+                WizardStep::Data => start = wizard.data_step(ui),
+                WizardStep::Result => {
+                    if wizard.result_step(ui) {
+                        wizard.open = false;
+                    }
+                }
+            });
+        // A running import carries on once the window is closed; its job is
+        // still polled above so the stores get saved when it is done.
+        if !open {
+            wizard.open = false;
+        }
+        if start {
+            self.start_import();
+        }
+    }
+
+    fn start_import(&mut self) {
+        let wizard = &mut self.import;
+        let Some(source) = wizard.sources.get(wizard.selected).cloned() else {
+            return;
+        };
+        match self.handle.import_profile(source, wizard.selection) {
+            Ok(job) => {
+                wizard.job = Some(job);
+                wizard.result = None;
+                wizard.step = WizardStep::Result;
+            }
+            Err(err) => self.status_line = format!("Import failed to start: {err}"),
+        }
+    }
+}
+
+impl ImportWizard {
+    fn source_step(&mut self, ui: &mut egui::Ui) {
+        ui.label("Choose the browser profile to import from.");
+        ui.separator();
+        if self.sources.is_empty() {
+            ui.weak("No Firefox, Chrome, Chromium, Brave or Edge profiles were found.");
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for (index, source) in self.sources.iter().enumerate() {
+                    ui.radio_value(
+                        &mut self.selected,
+                        index,
+                        format!("{} \u{2014} {}", source.browser.label(), source.profile),
+                    )
+                    .on_hover_text(source.path.display().to_string());
+                }
+            });
+        ui.separator();
+        if ui.button("Next").clicked() {
+            self.step = WizardStep::Data;
+        }
+    }
+
+    /// Returns whether the import should start.
+    fn data_step(&mut self, ui: &mut egui::Ui) -> bool {
+        if let Some(source) = self.sources.get(self.selected) {
+            ui.label(format!(
+                "Import from {} ({})",
+                source.browser.label(),
+                source.profile
+            ));
+        }
+        ui.separator();
+        ui.checkbox(&mut self.selection.bookmarks, "Bookmarks");
+        ui.checkbox(&mut self.selection.history, "History");
+        ui.checkbox(&mut self.selection.passwords, "Saved passwords")
+            .on_hover_text(
+                "Only passwords not protected by a primary password or the system keyring \
+                 can be read",
+            );
+        ui.weak("Close the other browser first if it is running to import its latest changes.");
+        ui.separator();
+        let mut start = false;
+        ui.horizontal(|ui| {
+            if ui.button("Back").clicked() {
+                self.step = WizardStep::Source;
+            }
+            let any =
+                self.selection.bookmarks || self.selection.history || self.selection.passwords;
+            if ui.add_enabled(any, egui::Button::new("Import")).clicked() {
+                start = true;
+            }
+        });
+        start
+    }
+
+    /// Returns whether the wizard should close.
+    fn result_step(&mut self, ui: &mut egui::Ui) -> bool {
+        match &self.result {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Importing\u{2026}");
+                });
+                return false;
+            }
+            Some(Ok(summary)) => {
+                ui.label(format!("{} new bookmarks", summary.bookmarks));
+                ui.label(format!("{} new history entries", summary.history));
+                ui.label(format!("{} new saved passwords", summary.passwords));
+                for warning in &summary.warnings {
+                    ui.label(RichText::new(warning).color(ui.visuals().warn_fg_color));
+                }
+            }
+            Some(Err(err)) => {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+        }
+        ui.separator();
+        ui.button("Done").clicked()
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::HashSet;
+
+use asterix_browser::{
+    element_at, BoxModel, ComputedStyle, DeclarationId, MediaFeatures, PropertyOrigin,
+    SourceElement, StyleSheet,
+};
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use egui::text::CCursor;
+use egui::text_edit::TextEditOutput;
+use egui::{Align, Align2, Color32, FontId, Rect, Sense, Stroke};
+use url::Url;
+
+use crate::devtools::DevToolsTab;
+use crate::{PageView, ShellApp};
+
+const BOX_FILL: Color32 = Color32::from_rgba_premultiplied(60, 110, 180, 50);
+const BOX_STROKE: Color32 = Color32::from_rgb(80, 140, 220);
+
+/// Element tree of the page in the active tab and the element picked from it.
+#[derive(Default)]
+pub(crate) struct ElementsPanel {
+    /// Hovering the page preview highlights elements until one is clicked.
+    pub(crate) picking: bool,
+    pub(crate) selected: Option<usize>,
+    /// Page the selection and styles refer to; both are reset when a
+    /// different page or a reload is shown.
+    page_key: Option<(Url, DateTime<Utc>)>,
+    /// Medium the page preview is rendered for, which styles are cascaded
+    /// for as well.
+    pub(crate) media: MediaFeatures,
+    stylesheet: StyleSheet,
+    stylesheet_media: MediaFeatures,
+    /// Declarations switched off in the styles pane.
+    disabled: HashSet<DeclarationId>,
+    /// Set when the selection changed outside the tree so it gets expanded
+    /// and scrolled into view.
+    reveal: bool,
+}
+
+impl ElementsPanel {
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, page: Option<&PageView>) {
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.picking, "Pick Element")
+                .on_hover_text("Hover the page preview and click an element to select it");
+            if self.picking {
+                ui.weak("Press Escape to stop picking");
+            }
+        });
+        ui.separator();
+
+        let Some(page) = page.filter(|page| !page.elements.is_empty()) else {
+            ui.weak("No elements found in the page in this tab.");
+            return;
+        };
+        let page_key = (page.url.clone(), page.received_at);
+        if self.page_key.as_ref() != Some(&page_key) {
+            self.page_key = Some(page_key);
+            self.stylesheet = StyleSheet::for_media(&page.source, &page.elements, &self.media);
+            self.stylesheet_media = self.media;
+            self.disabled.clear();
+            if !self.reveal {
+                self.selected = None;
+            }
+        } else if self.stylesheet_media != self.media {
+            // Rule positions change with the media, so switched off
+            // declarations would point at other rules.
+            self.stylesheet = StyleSheet::for_media(&page.source, &page.elements, &self.media);
+            self.stylesheet_media = self.media;
+            self.disabled.clear();
+        }
+        let selected = self
+            .selected
+            .and_then(|index| page.elements.get(index).map(|element| (index, element)));
+
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); page.elements.len()];
+        let mut roots = Vec::new();
+        for (index, element) in page.elements.iter().enumerate() {
+            match element.parent {
+                Some(parent) => children[parent].push(index),
+                None => roots.push(index),
+            }
+        }
+        let mut ancestors = HashSet::new();
+        if self.reveal {
+            let mut current = selected.and_then(|(_, element)| element.parent);
+            while let Some(index) = current {
+                ancestors.insert(index);
+                current = page.elements[index].parent;
+            }
+        }
+
+        let tree_height = if selected.is_some() {
+            ui.available_height() * 0.6
+        } else {
+            ui.available_height()
+        };
+        egui::ScrollArea::both()
+            .id_source("elements_tree")
+            .max_height(tree_height)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let mut tree = ElementTree {
+                    elements: &page.elements,
+                    children: &children,
+                    ancestors: &ancestors,
+                    selected: &mut self.selected,
+                    reveal: self.reveal,
+                };
+                for root in roots {
+                    tree.show_node(ui, root);
+                }
+            });
+        self.reveal = false;
+
+        if let Some((index, element)) = selected {
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .id_source("elements_details")
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    element_details(ui, element);
+                    let style = self
+                        .stylesheet
+                        .compute(&page.elements, index, &self.disabled);
+                    self.style_details(ui, &style);
+                });
+        }
+    }
+
+    /// Matched rules with toggles, the resulting property values and the box
+    /// model of the selected element.
+    fn style_details(&mut self, ui: &mut egui::Ui, style: &ComputedStyle) {
+        egui::CollapsingHeader::new(format!("Styles ({} rules)", style.rules.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                if style.rules.is_empty() {
+                    ui.weak("No rules match this element.");
+                }
+                for rule in &style.rules {
+                    ui.monospace(format!("{} {{", rule.selector));
+                    for (index, declaration) in rule.declarations.iter().enumerate() {
+                        let id = DeclarationId {
+                            source: rule.source,
+                            index,
+                        };
+                        let mut enabled = !self.disabled.contains(&id);
+                        let important = if declaration.important {
+                            " !important"
+                        } else {
+                            ""
+                        };
+                        let text = format!(
+                            "{}: {}{important};",
+                            declaration.property, declaration.value
+                        );
+                        ui.horizontal(|ui| {
+                            ui.add_space(12.0);
+                            let text = if enabled {
+                                egui::RichText::new(text).monospace()
+                            } else {
+                                egui::RichText::new(text).monospace().strikethrough().weak()
+                            };
+                            if ui.checkbox(&mut enabled, text).changed() {
+                                if enabled {
+                                    self.disabled.remove(&id);
+                                } else {
+                                    self.disabled.insert(id);
+                                }
+                            }
+                        });
+                    }
+                    ui.monospace("}");
+                }
+                let mut notes = Vec::new();
+                if self.stylesheet.skipped > 0 {
+                    notes.push(format!(
+                        "{} at-rules or selectors are not evaluated",
+                        self.stylesheet.skipped
+                    ));
+                }
+                if !self.stylesheet.external.is_empty() {
+                    notes.push(format!(
+                        "{} linked stylesheets are not loaded",
+                        self.stylesheet.external.len()
+                    ));
+                }
+                if !notes.is_empty() {
+                    ui.weak(notes.join("; "));
+                }
+            });
+
+        egui::CollapsingHeader::new("Computed")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("element_computed")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for property in &style.properties {
+                            ui.monospace(&property.name);
+                            ui.monospace(&property.value);
+                            ui.weak(match &property.origin {
+                                PropertyOrigin::Declared(selector) => selector.clone(),
+                                PropertyOrigin::Inherited(from) => format!("inherited from {from}"),
+                                PropertyOrigin::Initial => "default".to_owned(),
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        egui::CollapsingHeader::new("Box model")
+            .default_open(true)
+            .show(ui, |ui| box_model_diagram(ui, &style.box_model));
+    }
+
+    /// Selects the element at `index` in the tree, expanding its ancestors.
+    pub(crate) fn select(&mut self, index: usize) {
+        self.selected = Some(index);
+        self.picking = false;
+        self.reveal = true;
+    }
+}
+
+struct ElementTree<'a> {
+    elements: &'a [SourceElement],
+    children: &'a [Vec<usize>],
+    ancestors: &'a HashSet<usize>,
+    selected: &'a mut Option<usize>,
+    reveal: bool,
+}
+
+impl ElementTree<'_> {
+    fn show_node(&mut self, ui: &mut egui::Ui, index: usize) {
+        let element = &self.elements[index];
+        let is_selected = *self.selected == Some(index);
+        let label = egui::RichText::new(format!("<{}>", element.label())).monospace();
+
+        if self.children[index].is_empty() {
+            let response = ui.selectable_label(is_selected, label);
+            self.node_clicked(&response, index);
+            return;
+        }
+
+        let id = ui.make_persistent_id(("element", index));
+        let mut state = egui::collapsing_header::CollapsingState::load_with_default_open(
+            ui.ctx(),
+            id,
+            element.depth < 2,
+        );
+        if self.ancestors.contains(&index) {
+            state.set_open(true);
+        }
+        state
+            .show_header(ui, |ui| {
+                let response = ui.selectable_label(is_selected, label);
+                self.node_clicked(&response, index);
+            })
+            .body(|ui| {
+                for &child in &self.children[index] {
+                    self.show_node(ui, child);
+                }
+            });
+    }
+
+    fn node_clicked(&mut self, response: &egui::Response, index: usize) {
+        if response.clicked() {
+            *self.selected = Some(index);
+        }
+        if self.reveal && *self.selected == Some(index) {
+            response.scroll_to_me(Some(Align::Center));
+        }
+    }
+}
+
+fn element_details(ui: &mut egui::Ui, element: &SourceElement) {
+    ui.strong(element.label());
+    ui.small(format!(
+        "source bytes {}..{} ({} bytes)",
+        element.start,
+        element.end,
+        element.end - element.start
+    ));
+    egui::CollapsingHeader::new(format!("Attributes ({})", element.attributes.len()))
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("element_attributes")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, value) in &element.attributes {
+                        ui.monospace(name.as_str());
+                        ui.monospace(value.as_str());
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// Draws the margin, border, padding and content boxes nested inside each
+/// other, labelled with their declared sizes.
+fn box_model_diagram(ui: &mut egui::Ui, box_model: &BoxModel) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(360.0, 220.0), Sense::hover());
+    let painter = ui.painter_at(rect);
+    let text_color = Color32::from_gray(30);
+    let layers = [
+        (
+            "margin",
+            &box_model.margin,
+            Color32::from_rgb(249, 204, 157),
+        ),
+        (
+            "border",
+            &box_model.border,
+            Color32::from_rgb(253, 221, 155),
+        ),
+        (
+            "padding",
+            &box_model.padding,
+            Color32::from_rgb(195, 208, 139),
+        ),
+    ];
+
+    let mut outer = rect.shrink(2.0);
+    for (name, edges, color) in layers {
+        painter.rect(outer, 0.0, color, Stroke::new(1.0, Color32::from_gray(90)));
+        painter.text(
+            outer.left_top() + egui::vec2(4.0, 2.0),
+            Align2::LEFT_TOP,
+            name,
+            FontId::proportional(10.0),
+            text_color,
+        );
+        let [top, right, bottom, left] = edges;
+        let font = FontId::monospace(10.0);
+        painter.text(
+            egui::pos2(outer.center().x, outer.top() + 9.0),
+            Align2::CENTER_CENTER,
+            top,
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            egui::pos2(outer.right() - 4.0, outer.center().y),
+            Align2::RIGHT_CENTER,
+            right,
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            egui::pos2(outer.center().x, outer.bottom() - 9.0),
+            Align2::CENTER_CENTER,
+            bottom,
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            egui::pos2(outer.left() + 4.0, outer.center().y),
+            Align2::LEFT_CENTER,
+            left,
+            font,
+            text_color,
+        );
+        outer = outer.shrink2(egui::vec2(40.0, 20.0));
+    }
+    painter.rect(
+        outer,
+        0.0,
+        Color32::from_rgb(139, 182, 194),
+        Stroke::new(1.0, Color32::from_gray(90)),
+    );
+    painter.text(
+        outer.center(),
+        Align2::CENTER_CENTER,
+        format!("{} \u{d7} {}", box_model.width, box_model.height),
+        FontId::monospace(11.0),
+        text_color,
+    );
+}
+
+/// Highlights the element under the pointer in the page preview. Returns the
+/// element's index when it was clicked.
+pub(crate) fn pick_in_preview(
+    ui: &egui::Ui,
+    output: &TextEditOutput,
+    page: &PageView,
+) -> Option<usize> {
+    let pointer = output.response.hover_pos()?;
+    let cursor = output.galley.cursor_from_pos(pointer - output.galley_pos);
+    let offset = page
+        .source
+        .char_indices()
+        .nth(cursor.ccursor.index)
+        .map(|(offset, _)| offset)?;
+    let index = element_at(&page.elements, offset)?;
+    let element = &page.elements[index];
+
+    let preview_chars = page.preview.chars().count();
+    let char_at = |byte: usize| page.source[..byte].chars().count().min(preview_chars);
+    let first = output
+        .galley
+        .pos_from_ccursor(CCursor::new(char_at(element.start)));
+    let last = output
+        .galley
+        .pos_from_ccursor(CCursor::new(char_at(element.end)));
+    let bounds = output.galley.rect;
+    let rect = if (first.top() - last.top()).abs() < f32::EPSILON {
+        Rect::from_min_max(first.left_top(), last.right_bottom())
+    } else {
+        Rect::from_min_max(
+            egui::pos2(bounds.left(), first.top()),
+            egui::pos2(bounds.right(), last.bottom()),
+        )
+    }
+    .translate(output.galley_pos.to_vec2());
+
+    let painter = ui.painter_at(output.text_clip_rect);
+    painter.rect(rect, 2.0, BOX_FILL, Stroke::new(1.0, BOX_STROKE));
+    let caption = format!(
+        "{}  {:.0} \u{d7} {:.0}",
+        element.label(),
+        rect.width(),
+        rect.height()
+    );
+    let caption_pos = if rect.top() - 16.0 > output.text_clip_rect.top() {
+        rect.left_top()
+    } else {
+        rect.left_bottom() + egui::vec2(0.0, 16.0)
+    };
+    let caption = painter.layout_no_wrap(caption, FontId::monospace(11.0), Color32::WHITE);
+    let caption_rect = Align2::LEFT_BOTTOM.anchor_size(caption_pos, caption.size());
+    painter.rect_filled(caption_rect.expand(2.0), 2.0, BOX_STROKE);
+    painter.galley(caption_rect.min, caption, Color32::WHITE);
+
+    output.response.clicked().then_some(index)
+}
+
+impl ShellApp {
+    /// Selects an element picked from the page and shows it in the devtools.
+    pub(crate) fn inspect_element(&mut self, index: usize) {
+        self.devtools.elements.select(index);
+        self.devtools.open = true;
+        self.devtools.tab = DevToolsTab::Elements;
+    }
+
+    /// Starts the element picker from the toolbar.
+    pub(crate) fn start_element_picker(&mut self) {
+        self.devtools.elements.picking = true;
+        self.status_line = "Click an element in the page preview to inspect it".to_owned();
+    }
+}
@@ -0,0 +1,703 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use asterix_browser::{
+    HistoryEntry, JobId, JobKind, PasswordCheckJob, PasswordReport, SavedLogin, ScheduledJob, TabId,
+};
+use eframe::egui;
+use egui::{CentralPanel, Context as EguiContext, RichText};
+use tracing::warn;
+use url::Url;
+
+use crate::{parse_user_url, ShellApp};
+
+/// Address of the page managing background jobs.
+pub(crate) const JOBS_PAGE: &str = "about:jobs";
+pub(crate) const HISTORY_PAGE: &str = "about:history";
+pub(crate) const DOWNLOADS_PAGE: &str = "about:downloads";
+pub(crate) const LOGINS_PAGE: &str = "about:logins";
+pub(crate) const CONFIG_PAGE: &str = "about:config";
+pub(crate) const FLAGS_PAGE: &str = "about:flags";
+/// Index of the internal pages.
+pub(crate) const ABOUT_PAGE: &str = "about:about";
+/// Shown in place of a site restricted mode does not allow.
+const BLOCKED_PAGE: &str = "about:blocked";
+/// Shown in place of a site whose certificate failed verification.
+const CERT_ERROR_PAGE: &str = "about:certerror";
+
+/// Pages listed on `about:about`, with what they show.
+const INDEXED_PAGES: [(&str, &str); 11] = [
+    ("about:version", "Version, platform and network settings"),
+    ("about:tabs", "Every open tab and whether it is loaded"),
+    (
+        "about:cache",
+        "Documents in the HTTP cache and preloaded pages",
+    ),
+    (
+        "about:network",
+        "Requests made so far and connection reuse per host",
+    ),
+    (JOBS_PAGE, "Background jobs and their schedules"),
+    (HISTORY_PAGE, "Visited pages"),
+    (DOWNLOADS_PAGE, "Downloaded files"),
+    (LOGINS_PAGE, "Saved passwords"),
+    (
+        CONFIG_PAGE,
+        "Every preference, including those not in the menus",
+    ),
+    (FLAGS_PAGE, "Experimental features"),
+    (ABOUT_PAGE, "This list"),
+];
+
+/// Most history entries listed at once on `about:history`.
+const HISTORY_PAGE_ROWS: usize = 500;
+
+/// Run intervals offered for background jobs, in minutes.
+const JOB_INTERVALS: [(u32, &str); 6] = [
+    (1, "Every minute"),
+    (5, "5 minutes"),
+    (15, "15 minutes"),
+    (60, "Hourly"),
+    (360, "6 hours"),
+    (1440, "Daily"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NewJobKind {
+    RefreshFeed,
+    WarmCache,
+}
+
+/// Inputs of the form adding a job on `about:jobs`.
+pub(crate) struct JobForm {
+    kind: NewJobKind,
+    url_input: String,
+    interval_minutes: u32,
+}
+
+impl Default for JobForm {
+    fn default() -> Self {
+        Self {
+            kind: NewJobKind::RefreshFeed,
+            url_input: String::new(),
+            interval_minutes: 60,
+        }
+    }
+}
+
+/// Two-factor secret being entered for a login on `about:logins`.
+#[derive(Default)]
+pub(crate) struct TotpForm {
+    /// Origin and user name of the login.
+    login: Option<(String, String)>,
+    secret: String,
+    error: Option<String>,
+}
+
+/// Breach check of the saved passwords started from `about:logins`.
+#[derive(Default)]
+pub(crate) struct PasswordCheck {
+    job: Option<PasswordCheckJob>,
+    /// Findings of the last check, by origin and user name.
+    reports: HashMap<(String, String), PasswordReport>,
+    error: Option<String>,
+}
+
+enum LoginAction<'a> {
+    Remove(&'a SavedLogin),
+    SetUpTotp(&'a SavedLogin),
+    RemoveTotp(&'a SavedLogin),
+}
+
+enum JobAction {
+    SetEnabled(JobId, bool),
+    SetInterval(JobId, u32),
+    RunNow(JobId),
+    Remove(JobId),
+    Add,
+}
+
+/// Title shown in the tab strip for the `about:` page at `url`.
+fn page_title(url: &Url) -> &'static str {
+    match url.path() {
+        "about" => "Internal Pages",
+        "version" => "About ASTERIX",
+        "tabs" => "Tabs",
+        "cache" => "Cache",
+        "network" => "Network",
+        "jobs" => "Background Jobs",
+        "history" => "History",
+        "downloads" => "Downloads",
+        "logins" => "Saved Passwords",
+        "config" => "Advanced Preferences",
+        "flags" => "Experiments",
+        "blocked" => "Blocked Site",
+        "certerror" => "Certificate Error",
+        _ => "Page Not Found",
+    }
+}
+
+/// `about:blocked` page explaining that `url` is not on the allowlist.
+pub(crate) fn blocked_page_url(url: &Url) -> Url {
+    let mut page = Url::parse(BLOCKED_PAGE).expect("valid internal page URL");
+    page.query_pairs_mut().append_pair("url", url.as_str());
+    page
+}
+
+/// `about:certerror` page warning that the certificate of `url` could not
+/// be verified.
+pub(crate) fn cert_error_page_url(url: &Url) -> Url {
+    let mut page = Url::parse(CERT_ERROR_PAGE).expect("valid internal page URL");
+    page.query_pairs_mut().append_pair("url", url.as_str());
+    page
+}
+
+fn interval_label(minutes: u32) -> String {
+    JOB_INTERVALS
+        .iter()
+        .find(|(interval, _)| *interval == minutes)
+        .map(|(_, label)| (*label).to_owned())
+        .unwrap_or_else(|| format!("{minutes} minutes"))
+}
+
+fn interval_picker(ui: &mut egui::Ui, id: impl std::hash::Hash, minutes: u32) -> Option<u32> {
+    let mut picked = None;
+    egui::ComboBox::from_id_source(id)
+        .selected_text(interval_label(minutes))
+        .show_ui(ui, |ui| {
+            for (interval, label) in JOB_INTERVALS {
+                if ui.selectable_label(minutes == interval, label).clicked() {
+                    picked = Some(interval);
+                }
+            }
+        });
+    picked
+}
+
+impl ShellApp {
+    /// Shows the `about:` page at `url` in `tab` instead of fetching it.
+    pub(crate) fn open_internal_page(&mut self, tab: TabId, url: Url) {
+        self.stop_loading(tab);
+        if let Some(view) = self.pages.remove(&tab) {
+            self.back_forward.store(tab, view);
+        }
+        self.deferred_pages.remove(&tab);
+        self.handle
+            .show_internal_page(tab, url.clone(), page_title(&url));
+        self.status_line = format!("Opened {url}");
+        self.refresh_tabs();
+    }
+
+    pub(crate) fn render_internal_page(&mut self, ctx: &EguiContext, url: &Url) {
+        CentralPanel::default().show(ctx, |ui| match url.path() {
+            "about" => self.render_about_page(ui),
+            "version" => self.render_version_page(ui),
+            "tabs" => self.render_tabs_page(ui),
+            "cache" => self.render_cache_page(ui),
+            "network" => self.render_network_page(ui),
+            "jobs" => self.render_jobs_page(ui),
+            "history" => self.render_history_page(ui),
+            "downloads" => self.render_downloads_page(ui),
+            "logins" => self.render_logins_page(ui),
+            "config" => self.render_config_page(ui),
+            "flags" => self.render_flags_page(ui),
+            "blocked" => render_blocked_page(ui, url),
+            "certerror" => self.render_cert_error_page(ui, url),
+            _ => {
+                ui.heading(page_title(url));
+                ui.label(format!("There is no internal page at {url}."));
+                ui.label(format!("The internal pages are listed at {ABOUT_PAGE}."));
+            }
+        });
+    }
+
+    fn render_about_page(&mut self, ui: &mut egui::Ui) {
+        let mut open = None;
+        ui.heading("Internal Pages");
+        ui.separator();
+        egui::Grid::new("internal_pages")
+            .num_columns(2)
+            .spacing([24.0, 6.0])
+            .show(ui, |ui| {
+                for (page, description) in INDEXED_PAGES {
+                    if ui.link(page).clicked() {
+                        open = Url::parse(page).ok();
+                    }
+                    ui.label(description);
+                    ui.end_row();
+                }
+            });
+        if let Some(url) = open {
+            self.navigate_active(url);
+        }
+    }
+
+    fn render_jobs_page(&mut self, ui: &mut egui::Ui) {
+        // Jobs finish in the background; keep the table current while open.
+        ui.ctx().request_repaint_after(Duration::from_secs(1));
+        let jobs = self.handle.jobs();
+        let mut action = None;
+
+        ui.heading("Background Jobs");
+        ui.label("Jobs run on their schedule while the browser is open, even when it is idle.");
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 80.0)
+            .show(ui, |ui| {
+                egui::Grid::new("scheduled_jobs")
+                    .num_columns(7)
+                    .striped(true)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        for header in ["On", "Job", "Target", "Runs", "Last run", "Result", ""] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+                        for job in &jobs {
+                            if let Some(row_action) = job_row(ui, job) {
+                                action = Some(row_action);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        ui.separator();
+
+        let form = &mut self.job_form;
+        ui.horizontal(|ui| {
+            ui.label("Add");
+            ui.selectable_value(&mut form.kind, NewJobKind::RefreshFeed, "Refresh feed")
+                .on_hover_text("Fetch an RSS or Atom feed and report new entries");
+            ui.selectable_value(&mut form.kind, NewJobKind::WarmCache, "Warm cache")
+                .on_hover_text("Keep a pinned site preloaded so it opens instantly");
+            ui.add(
+                egui::TextEdit::singleline(&mut form.url_input)
+                    .hint_text("https://example.com/feed.xml")
+                    .desired_width(280.0),
+            );
+            if let Some(minutes) = interval_picker(ui, "new_job_interval", form.interval_minutes) {
+                form.interval_minutes = minutes;
+            }
+            if ui
+                .add_enabled(
+                    !form.url_input.trim().is_empty(),
+                    egui::Button::new("Add Job"),
+                )
+                .clicked()
+            {
+                action = Some(JobAction::Add);
+            }
+        });
+
+        match action {
+            Some(JobAction::SetEnabled(id, enabled)) => self.handle.set_job_enabled(id, enabled),
+            Some(JobAction::SetInterval(id, minutes)) => self.handle.set_job_interval(id, minutes),
+            Some(JobAction::RunNow(id)) => {
+                self.handle.run_job_now(id);
+                self.status_line = "Job queued to run now".to_owned();
+            }
+            Some(JobAction::Remove(id)) => {
+                self.handle.remove_job(id);
+            }
+            Some(JobAction::Add) => {
+                let url = match parse_user_url(&self.job_form.url_input) {
+                    Ok(url) => url,
+                    Err(_) => {
+                        self.status_line = "Enter a valid URL for the job".to_owned();
+                        return;
+                    }
+                };
+                let kind = match self.job_form.kind {
+                    NewJobKind::RefreshFeed => JobKind::RefreshFeed { url },
+                    NewJobKind::WarmCache => JobKind::WarmCache { url },
+                };
+                self.status_line = format!("Added a {} job", kind.label().to_lowercase());
+                self.handle.add_job(kind, self.job_form.interval_minutes);
+                self.job_form.url_input.clear();
+            }
+            None => return,
+        }
+        self.save_jobs();
+    }
+
+    fn render_history_page(&mut self, ui: &mut egui::Ui) {
+        let filter = self.history_filter.trim().to_lowercase();
+        let history = self.handle.history();
+        let matching: Vec<&HistoryEntry> = history
+            .iter()
+            .filter(|entry| {
+                filter.is_empty()
+                    || entry.title.to_lowercase().contains(&filter)
+                    || entry.url.as_str().to_lowercase().contains(&filter)
+            })
+            .collect();
+        let mut open = None;
+        let mut remove = None;
+        let mut clear = false;
+
+        ui.heading("History");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.history_filter)
+                    .hint_text("Search history")
+                    .desired_width(280.0),
+            );
+            ui.weak(format!("{} of {} pages", matching.len(), history.len()));
+            if ui
+                .add_enabled(!history.is_empty(), egui::Button::new("Clear History"))
+                .clicked()
+            {
+                clear = true;
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("history_entries")
+                .num_columns(4)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for entry in matching.iter().take(HISTORY_PAGE_ROWS) {
+                        ui.label(entry.last_visit.format("%Y-%m-%d %H:%M").to_string());
+                        if ui
+                            .link(&entry.title)
+                            .on_hover_text(entry.url.as_str())
+                            .clicked()
+                        {
+                            open = Some(entry.url.clone());
+                        }
+                        ui.weak(format!("{} visits", entry.visit_count));
+                        if ui.small_button("Remove").clicked() {
+                            remove = Some(entry.url.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+            if matching.len() > HISTORY_PAGE_ROWS {
+                ui.weak("Refine the search to see older pages.");
+            }
+        });
+
+        if clear {
+            self.handle.clear_history();
+            self.status_line = "History cleared".to_owned();
+        } else if let Some(url) = remove {
+            self.handle.remove_history_entry(&url);
+        } else if let Some(url) = open {
+            self.navigate_active(url);
+            return;
+        } else {
+            return;
+        }
+        self.save_history();
+    }
+
+    fn render_logins_page(&mut self, ui: &mut egui::Ui) {
+        let logins = self.handle.saved_logins();
+        let mut action = None;
+        self.poll_password_check(ui.ctx());
+
+        ui.heading("Saved Passwords");
+        let mut start_check = false;
+        ui.horizontal(|ui| {
+            ui.weak(format!("{} saved passwords", logins.len()));
+            ui.checkbox(&mut self.show_passwords, "Show passwords");
+            if self.password_check.job.is_some() {
+                ui.spinner();
+                ui.label("Checking passwords...");
+            } else {
+                start_check = ui
+                    .add_enabled(!logins.is_empty(), egui::Button::new("Check for Breaches"))
+                    .on_hover_text(
+                        "Looks the passwords up in Have I Been Pwned. Only the first five \
+                         characters of each password's hash are sent.",
+                    )
+                    .clicked();
+            }
+        });
+        if let Some(err) = &self.password_check.error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        } else if !self.password_check.reports.is_empty() {
+            let reports = self.password_check.reports.values();
+            let compromised = reports.clone().filter(|r| r.is_compromised()).count();
+            let reused = reports.filter(|r| r.is_reused()).count();
+            ui.label(format!(
+                "{compromised} passwords found in data breaches, {reused} used on more than one site"
+            ));
+        }
+        if start_check {
+            self.start_password_check();
+        }
+        ui.separator();
+        if logins.is_empty() {
+            ui.label("Passwords imported from another browser are listed here.");
+            return;
+        }
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("saved_logins")
+                .num_columns(6)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    let headers = [
+                        "Site",
+                        "User name",
+                        "Password",
+                        "Two-factor code",
+                        "Status",
+                        "",
+                    ];
+                    for header in headers {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for login in &logins {
+                        let report = self
+                            .password_check
+                            .reports
+                            .get(&(login.origin.clone(), login.username.clone()));
+                        if let Some(row_action) = login_row(ui, login, report, self.show_passwords)
+                        {
+                            action = Some(row_action);
+                        }
+                        ui.end_row();
+                    }
+                });
+            self.render_totp_form(ui);
+        });
+        if let Some(remaining) = logins
+            .iter()
+            .filter_map(|login| login.totp())
+            .map(|totp| totp.current().1)
+            .min()
+        {
+            ui.ctx()
+                .request_repaint_after(Duration::from_secs(remaining));
+        }
+
+        match action {
+            Some(LoginAction::Remove(login)) => {
+                self.handle.remove_login(&login.origin, &login.username);
+                self.save_logins();
+            }
+            Some(LoginAction::SetUpTotp(login)) => {
+                self.totp_form = TotpForm {
+                    login: Some((login.origin.clone(), login.username.clone())),
+                    ..TotpForm::default()
+                };
+            }
+            Some(LoginAction::RemoveTotp(login)) => {
+                if let Err(err) = self
+                    .handle
+                    .set_login_totp(&login.origin, &login.username, None)
+                {
+                    warn!("failed to remove two-factor secret: {err}");
+                }
+                self.save_logins();
+            }
+            None => {}
+        }
+    }
+
+    fn start_password_check(&mut self) {
+        match self.handle.check_saved_passwords() {
+            Ok(job) => {
+                self.password_check.job = Some(job);
+                self.password_check.error = None;
+            }
+            Err(err) => self.password_check.error = Some(err.to_string()),
+        }
+    }
+
+    fn poll_password_check(&mut self, ctx: &EguiContext) {
+        let Some(job) = &mut self.password_check.job else {
+            return;
+        };
+        match job.try_complete() {
+            Some(Ok(reports)) => {
+                self.password_check.reports = reports
+                    .into_iter()
+                    .map(|report| ((report.origin.clone(), report.username.clone()), report))
+                    .collect();
+                self.password_check.job = None;
+            }
+            Some(Err(err)) => {
+                self.password_check.error = Some(format!("Password check failed: {err}"));
+                self.password_check.job = None;
+            }
+            None => ctx.request_repaint_after(Duration::from_millis(200)),
+        }
+    }
+
+    /// Input for the two-factor secret of the login being set up.
+    fn render_totp_form(&mut self, ui: &mut egui::Ui) {
+        let Some((origin, username)) = self.totp_form.login.clone() else {
+            return;
+        };
+        ui.separator();
+        ui.strong(format!("Two-factor code for {username} on {origin}"));
+        ui.weak(
+            "Paste the setup key the site shows next to its QR code, or the otpauth:// link \
+             it encodes.",
+        );
+        let mut save = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut self.totp_form.secret)
+                    .password(true)
+                    .hint_text("Setup key"),
+            );
+            save = edit.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+            save |= ui.button("Save").clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+        if let Some(err) = &self.totp_form.error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+        if cancel {
+            self.totp_form = TotpForm::default();
+        } else if save {
+            match self
+                .handle
+                .set_login_totp(&origin, &username, Some(&self.totp_form.secret))
+            {
+                Ok(()) => {
+                    self.totp_form = TotpForm::default();
+                    self.save_logins();
+                }
+                Err(err) => self.totp_form.error = Some(err.to_string()),
+            }
+        }
+    }
+
+    pub(crate) fn save_jobs(&self) {
+        if let Some(path) = &self.jobs_path {
+            if let Err(err) = self.handle.save_jobs(path) {
+                warn!("failed to save background jobs: {err:#}");
+            }
+        }
+    }
+}
+
+fn job_row(ui: &mut egui::Ui, job: &ScheduledJob) -> Option<JobAction> {
+    let mut action = None;
+    let mut enabled = job.enabled;
+    if ui.checkbox(&mut enabled, "").changed() {
+        action = Some(JobAction::SetEnabled(job.id, enabled));
+    }
+    ui.label(job.kind.label());
+    match job.kind.url() {
+        Some(url) => ui.label(url.as_str()),
+        None => ui.weak("Watched pages"),
+    };
+    if let Some(minutes) = interval_picker(ui, ("job_interval", job.id), job.interval_minutes) {
+        action = Some(JobAction::SetInterval(job.id, minutes));
+    }
+    match job.last_run {
+        Some(at) => ui.label(at.format("%Y-%m-%d %H:%M").to_string()),
+        None => ui.weak("never"),
+    };
+    if job.running {
+        ui.spinner();
+    } else {
+        match &job.last_result {
+            Some(Ok(summary)) => ui.label(summary),
+            Some(Err(err)) => ui.label(RichText::new(err).color(ui.visuals().error_fg_color)),
+            None => ui.weak("\u{2014}"),
+        };
+    }
+    ui.horizontal(|ui| {
+        if ui.small_button("Run Now").clicked() {
+            action = Some(JobAction::RunNow(job.id));
+        }
+        if job.kind != JobKind::WatchPages && ui.small_button("Remove").clicked() {
+            action = Some(JobAction::Remove(job.id));
+        }
+    });
+    action
+}
+
+fn login_row<'a>(
+    ui: &mut egui::Ui,
+    login: &'a SavedLogin,
+    report: Option<&PasswordReport>,
+    reveal: bool,
+) -> Option<LoginAction<'a>> {
+    ui.label(&login.origin);
+    ui.label(&login.username);
+    if reveal {
+        ui.monospace(&login.password);
+    } else {
+        ui.monospace("\u{2022}".repeat(login.password.chars().count().min(12)));
+    }
+    let mut action = None;
+    ui.horizontal(|ui| match login.totp() {
+        Some(totp) => {
+            let (code, remaining) = totp.current();
+            ui.monospace(&code);
+            ui.weak(format!("{remaining}s"));
+            if ui.small_button("Copy").clicked() {
+                ui.output_mut(|output| output.copied_text = code);
+            }
+            if ui.small_button("Remove").clicked() {
+                action = Some(LoginAction::RemoveTotp(login));
+            }
+        }
+        None => {
+            if ui.small_button("Set Up").clicked() {
+                action = Some(LoginAction::SetUpTotp(login));
+            }
+        }
+    });
+    password_status(ui, report);
+    ui.horizontal(|ui| {
+        if ui.small_button("Copy").clicked() {
+            ui.output_mut(|output| output.copied_text = login.password.clone());
+        }
+        if ui.small_button("Remove").clicked() {
+            action = Some(LoginAction::Remove(login));
+        }
+    });
+    action
+}
+
+/// Outcome of the last breach check for one password.
+fn password_status(ui: &mut egui::Ui, report: Option<&PasswordReport>) {
+    let Some(report) = report else {
+        ui.weak("\u{2014}");
+        return;
+    };
+    ui.vertical(|ui| {
+        if report.is_compromised() {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("Found in {} breaches", report.breach_count),
+            );
+        }
+        if report.is_reused() {
+            ui.colored_label(ui.visuals().warn_fg_color, "Reused")
+                .on_hover_text(format!("Also used on {}", report.reused_on.join(", ")));
+        }
+        if !report.is_compromised() && !report.is_reused() {
+            ui.label("No problems found");
+        }
+    });
+}
+
+fn render_blocked_page(ui: &mut egui::Ui, page: &Url) {
+    let blocked = page
+        .query_pairs()
+        .find(|(key, _)| key == "url")
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_default();
+    ui.vertical_centered(|ui| {
+        ui.add_space(40.0);
+        ui.heading("This site is not allowed");
+        ui.add_space(8.0);
+        ui.label(RichText::new(&blocked).monospace());
+        ui.add_space(8.0);
+        ui.label("Restricted mode is on, and only sites on the allowlist can be visited.");
+        ui.weak("Ask the person who manages this browser to allow the site.");
+    });
+}
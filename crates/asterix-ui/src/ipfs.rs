@@ -0,0 +1,94 @@
+use asterix_browser::{IpfsSettings, LOCAL_IPFS_GATEWAY};
+use eframe::egui;
+use egui::Context as EguiContext;
+use url::Url;
+
+use crate::ShellApp;
+
+/// Window editing how `ipfs://` and `ipns://` links are loaded.
+#[derive(Default)]
+pub(crate) struct IpfsPanel {
+    pub(crate) open: bool,
+    gateway: String,
+    prefer_local_node: bool,
+    error: Option<String>,
+}
+
+impl ShellApp {
+    pub(crate) fn open_ipfs_window(&mut self) {
+        let settings = self.handle.ipfs_settings();
+        self.ipfs = IpfsPanel {
+            open: true,
+            gateway: settings.gateway.to_string(),
+            prefer_local_node: settings.prefer_local_node,
+            error: None,
+        };
+    }
+
+    fn apply_ipfs_settings(&mut self) {
+        let gateway = match Url::parse(self.ipfs.gateway.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+            _ => {
+                self.ipfs.error = Some("The gateway must be an http:// or https:// URL".to_owned());
+                return;
+            }
+        };
+        let settings = IpfsSettings {
+            gateway,
+            prefer_local_node: self.ipfs.prefer_local_node,
+        };
+        self.handle.set_ipfs_settings(settings.clone());
+        self.settings.ipfs = settings;
+        self.save_settings();
+        self.ipfs.open = false;
+        self.status_line = "IPFS settings applied".to_owned();
+    }
+
+    pub(crate) fn render_ipfs_window(&mut self, ctx: &EguiContext) {
+        if !self.ipfs.open {
+            return;
+        }
+        let local_node = self.handle.local_ipfs_node();
+        let panel = &mut self.ipfs;
+        let mut open = true;
+        let mut apply = false;
+        egui::Window::new("IPFS")
+            .open(&mut open)
+            .default_size([420.0, 200.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "ipfs:// and ipns:// links are loaded over HTTP from a gateway, which \
+                     serves them under /ipfs/ and /ipns/.",
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Gateway");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut panel.gateway)
+                            .hint_text("https://ipfs.io/")
+                            .desired_width(260.0),
+                    );
+                });
+                ui.checkbox(
+                    &mut panel.prefer_local_node,
+                    format!("Use the local node at {LOCAL_IPFS_GATEWAY} when it is running"),
+                );
+                ui.weak(match local_node {
+                    Some(true) => "A local node answered the last time it was looked for.",
+                    Some(false) => "No local node answered the last time it was looked for.",
+                    None => "The local node is looked for when an IPFS link is opened.",
+                });
+                if let Some(err) = &panel.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+                ui.separator();
+                if ui.button("Apply").clicked() {
+                    apply = true;
+                }
+            });
+        panel.open &= open;
+
+        if apply {
+            self.apply_ipfs_settings();
+        }
+    }
+}
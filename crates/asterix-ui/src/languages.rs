@@ -0,0 +1,56 @@
+use eframe::egui;
+
+use crate::ShellApp;
+
+impl ShellApp {
+    /// Toolbar menu listing the other language versions of the page in the
+    /// active tab; hidden when the page declares none.
+    pub(crate) fn render_language_switcher(&mut self, ui: &mut egui::Ui) {
+        let Some(page) = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| self.pages.get(&tab.id))
+            .filter(|page| !page.alternates.is_empty())
+        else {
+            return;
+        };
+
+        let current = page
+            .alternates
+            .iter()
+            .find(|alternate| {
+                alternate.url == page.url && !alternate.hreflang.eq_ignore_ascii_case("x-default")
+            })
+            .map(|alternate| alternate.hreflang.clone())
+            .or_else(|| page.language.clone());
+        let label = match &current {
+            Some(language) => format!("Language: {language}"),
+            None => "Language".to_owned(),
+        };
+
+        let mut target = None;
+        ui.menu_button(label, |ui| {
+            for alternate in &page.alternates {
+                let name = if alternate.hreflang.eq_ignore_ascii_case("x-default") {
+                    "Default".to_owned()
+                } else {
+                    alternate.hreflang.clone()
+                };
+                let selected = alternate.url == page.url;
+                if ui
+                    .selectable_label(selected, name)
+                    .on_hover_text(alternate.url.as_str())
+                    .clicked()
+                {
+                    if !selected {
+                        target = Some(alternate.url.clone());
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+        if let Some(url) = target {
+            self.navigate_active(url);
+        }
+    }
+}
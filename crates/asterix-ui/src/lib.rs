@@ -1,11 +1,117 @@
-use std::time::Instant;
+mod about_pages;
+mod archive;
+mod audit_panel;
+mod auto_refresh;
+mod back_forward;
+mod backup;
+mod bookmarks;
+mod captive_portal;
+mod certificate;
+mod client_certs;
+mod clipper;
+mod composer;
+mod config_reload;
+mod console_panel;
+mod crawler;
+mod devtools;
+mod downloads;
+mod encoding;
+mod experiments;
+mod eyedropper;
+mod file_types;
+mod forms;
+mod http_auth;
+mod importer;
+mod inspector;
+mod internal_pages;
+mod ipfs;
+mod languages;
+mod link_checker;
+mod macros;
+mod magnet;
+mod menu_bar;
+mod network_panel;
+mod notes;
+mod overrides_panel;
+mod page_resources;
+mod parental;
+mod performance_panel;
+mod preferences;
+mod pretty_print;
+mod proxy;
+mod qr;
+mod reader;
+mod rendering;
+mod responsive;
+mod screenshot;
+mod scripts;
+mod seo_panel;
+mod settings;
+mod shield;
+mod site_files;
+mod startup;
+mod status_bar;
+mod sync;
+mod theme;
+mod toolbar;
+mod watches;
+mod websocket;
 
-use asterix_browser::{BrowserHandle, NavigationJob, TabSnapshot};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use asterix_browser::{
+    alternate_languages, extract_article, extract_forms, extract_links, extract_metadata,
+    format_citation, is_dead_status, map_elements, prefetch_hints, AlternateLanguage, Article,
+    BrowserError, BrowserHandle, CachePolicy, CacheStatus, CertErrorDetails, CitationStyle,
+    FileAction, MacroStep, MixedContentAction, NavigationJob, PageForm, PageLink, PageMetadata,
+    PageRequest, PageResources, PageResponse, PageText, PageWatch, ProxySettings, RefreshJob,
+    RenderOptions, RenderedPage, SiteUserAgent, SourceElement, SourceLanguage, TabId, TabOptions,
+    TabSnapshot, WorkspaceId, CONSENT_PLATFORMS,
+};
+use chrono::{DateTime, Utc};
 use eframe::egui;
 use egui::{CentralPanel, Context as EguiContext, Layout, RichText, TopBottomPanel};
-use tracing::info;
+use tracing::{info, warn};
 use url::Url;
 
+use crate::archive::DeadPage;
+use crate::auto_refresh::schedule_refresh;
+use crate::back_forward::BackForwardCache;
+use crate::backup::BackupPanel;
+use crate::captive_portal::ConnectivityMonitor;
+use crate::client_certs::ClientCertsPanel;
+use crate::composer::format_size;
+use crate::config_reload::ConfigWatcher;
+use crate::crawler::CrawlWizard;
+use crate::devtools::DevTools;
+use crate::downloads::Downloads;
+use crate::file_types::{FileTypesPanel, PendingFile};
+use crate::http_auth::AuthPrompt;
+use crate::importer::ImportWizard;
+use crate::internal_pages::{
+    blocked_page_url, cert_error_page_url, JobForm, PasswordCheck, TotpForm,
+};
+use crate::ipfs::IpfsPanel;
+use crate::link_checker::LinkCheck;
+use crate::macros::MacroLibrary;
+use crate::magnet::PendingMagnet;
+use crate::page_resources::ResourceFetch;
+use crate::parental::ParentalPanel;
+use crate::performance_panel::PageTimeline;
+use crate::preferences::PreferencesPage;
+use crate::proxy::ProxyPanel;
+use crate::responsive::ResponsiveMode;
+use crate::screenshot::ScreenshotTool;
+use crate::scripts::{list_scripts, scripts_dir, ScriptRun};
+use crate::settings::Settings;
+use crate::shield::ShieldPanel;
+use crate::site_files::SiteFileView;
+use crate::startup::{StartupPage, StartupPanel};
+use crate::sync::SyncPanel;
+use crate::websocket::WebSocketConsole;
+
 /// Launches the native ASTERIX shell on the current thread.
 pub fn launch_shell(handle: BrowserHandle) -> anyhow::Result<()> {
     let native_options = eframe::NativeOptions {
@@ -20,46 +126,523 @@ pub fn launch_shell(handle: BrowserHandle) -> anyhow::Result<()> {
     eframe::run_native(
         "ASTERIX",
         native_options,
-        Box::new(move |_cc| {
-            Box::new(ShellApp::new(handle).expect("failed to initialise UI")) as Box<dyn eframe::App>
+        Box::new(move |cc| {
+            Box::new(ShellApp::new(handle, &cc.egui_ctx).expect("failed to initialise UI"))
+                as Box<dyn eframe::App>
         }),
     )
     .map_err(|err| anyhow::anyhow!("failed to launch shell: {err}"))
 }
 
+/// How long the cursor has to rest on a link before it is preloaded.
+const HOVER_PRELOAD_DELAY: Duration = Duration::from_millis(400);
+
+const ANDROID_USER_AGENT: &str = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Mobile Safari/537.36";
+
+const IPHONE_USER_AGENT: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1";
+
+/// User agents offered by the "Reopen with user agent" tab action.
+const USER_AGENT_PRESETS: &[(&str, &str)] = &[
+    ("Android (Chrome)", ANDROID_USER_AGENT),
+    ("iPhone (Safari)", IPHONE_USER_AGENT),
+    (
+        "Desktop (Firefox)",
+        "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    ),
+];
+
+/// Rendered state of the document loaded in a tab.
+#[derive(Clone)]
+struct PageView {
+    /// The page as received, kept to re-decode it with another encoding.
+    response: PageResponse,
+    /// Encoding chosen in View > Text Encoding instead of the detected one.
+    encoding_override: Option<&'static str>,
+    url: Url,
+    source: PageText,
+    preview: String,
+    links: Vec<PageLink>,
+    article: Option<Article>,
+    metadata: PageMetadata,
+    elements: Vec<SourceElement>,
+    /// Other language versions declared by the page.
+    alternates: Vec<AlternateLanguage>,
+    /// The `lang` of the document element.
+    language: Option<String>,
+    /// Structured view of a `robots.txt` or sitemap.
+    site_file: Option<SiteFileView>,
+    /// Forms of the page with the values entered so far.
+    forms: Vec<PageForm>,
+    /// Text rendered for emulated media, and the options it was rendered
+    /// with.
+    rendered: Option<(RenderOptions, RenderedPage)>,
+    /// Subresources fetched once the page was shown.
+    resources: Option<PageResources>,
+    /// Language of a CSS, JavaScript or JSON resource, which can be pretty
+    /// printed.
+    source_language: Option<SourceLanguage>,
+    /// Reformatted source while pretty printing is on.
+    pretty: Option<String>,
+    received_at: DateTime<Utc>,
+    timeline: PageTimeline,
+    reading_progress: f32,
+    scroll_offset: f32,
+    restore_scroll: bool,
+}
+
+impl PageView {
+    fn new(page: &PageResponse) -> Self {
+        let elements = map_elements(&page.body);
+        let language = elements
+            .iter()
+            .find(|element| element.tag == "html")
+            .and_then(|html| html.attribute("lang"))
+            .map(|lang| lang.trim().to_owned())
+            .filter(|lang| !lang.is_empty());
+        Self {
+            response: page.clone(),
+            encoding_override: None,
+            url: page.url.clone(),
+            source: page.body.clone(),
+            preview: generate_preview(&page.body),
+            links: extract_links(page),
+            article: extract_article(page),
+            metadata: extract_metadata(page),
+            alternates: alternate_languages(&elements, &page.url),
+            language,
+            site_file: SiteFileView::new(page),
+            forms: extract_forms(&page.body, &page.url),
+            rendered: None,
+            resources: None,
+            source_language: SourceLanguage::detect(page.mime_type.as_deref(), &page.url),
+            pretty: None,
+            elements,
+            received_at: page.received_at,
+            timeline: PageTimeline::parsed_now(),
+            reading_progress: 0.0,
+            scroll_offset: 0.0,
+            restore_scroll: true,
+        }
+    }
+
+    /// Opens the view scrolled to `offset` instead of the top, e.g. to where
+    /// the page was left before going Back.
+    fn scrolled_to(self, offset: Option<f32>) -> Self {
+        Self {
+            scroll_offset: offset.unwrap_or(self.scroll_offset),
+            ..self
+        }
+    }
+}
+
+/// Actions offered by the tab strip context menu.
+enum TabAction {
+    Duplicate,
+    ReloadBypassingCache,
+    ReopenPrivate,
+    ReopenWithUserAgent(&'static str),
+    /// Sends the user agent to the tab's site from every tab, or stops
+    /// with `None`.
+    SetSiteUserAgent(Option<&'static str>),
+    ToggleQuiet,
+    ToggleAutoRefresh,
+}
+
+/// Link the cursor is currently resting on, used for hover preloading.
+struct HoveredLink {
+    url: Url,
+    since: Instant,
+}
+
 struct ShellApp {
     handle: BrowserHandle,
     tabs: Vec<TabSnapshot>,
     active_tab: Option<TabSnapshot>,
     url_input: String,
+    /// Set by Open Location to move the keyboard focus to the address bar.
+    focus_address_bar: bool,
+    /// Whether toolbar buttons are being dragged around instead of used.
+    customizing_toolbar: bool,
     nav_jobs: Vec<NavigationJob>,
+    /// Images, stylesheets and scripts being fetched for shown pages.
+    resource_fetches: Vec<ResourceFetch>,
+    /// Refreshes shown pages asked for, waiting out their delay.
+    refresh_jobs: Vec<RefreshJob>,
     status_line: String,
     last_update: Instant,
-    page_preview: Option<String>,
+    pages: HashMap<TabId, PageView>,
+    /// Textures of the icons in the tab strip, by origin.
+    favicons: HashMap<String, egui::TextureHandle>,
+    /// Documents loaded by background tabs, rendered once the tab is focused.
+    deferred_pages: HashMap<TabId, PageResponse>,
+    /// Scroll position to restore once a back or forward step has loaded.
+    pending_scroll: HashMap<TabId, f32>,
+    /// Pages navigated away from, shown again instantly by Back and Forward.
+    back_forward: BackForwardCache,
+    preload_on_hover: bool,
+    hovered_link: Option<HoveredLink>,
+    preloaded: HashSet<Url>,
+    active_workspace: WorkspaceId,
+    new_workspace_name: String,
+    session_path: Option<PathBuf>,
+    show_notes: bool,
+    note_url: Option<Url>,
+    note_draft: String,
+    /// Text currently selected in the page preview.
+    preview_selection: Option<String>,
+    annotations_path: Option<PathBuf>,
+    settings: Settings,
+    settings_path: Option<PathBuf>,
+    /// Edits to the configuration on disk, while they can be watched.
+    config_watcher: Option<ConfigWatcher>,
+    /// Search and edits in progress on `about:config`.
+    preferences: PreferencesPage,
+    clip_dir_input: String,
+    reader_mode: bool,
+    link_check: Option<LinkCheck>,
+    crawl: CrawlWizard,
+    websocket: WebSocketConsole,
+    /// Last script started from the Scripts menu.
+    script: Option<ScriptRun>,
+    /// Scripts the Scripts menu offers, listed again when the directory
+    /// changes.
+    scripts: Vec<PathBuf>,
+    macros: MacroLibrary,
+    /// Last navigation that hit a missing page, offered from the Wayback Machine.
+    dead_page: Option<DeadPage>,
+    watches: Vec<PageWatch>,
+    watches_path: Option<PathBuf>,
+    show_watches: bool,
+    /// Watched page whose latest diff is shown.
+    selected_watch: Option<Url>,
+    jobs_path: Option<PathBuf>,
+    /// Inputs of the add form on `about:jobs`.
+    job_form: JobForm,
+    bookmarks_path: Option<PathBuf>,
+    history_path: Option<PathBuf>,
+    /// Search typed on `about:history`.
+    history_filter: String,
+    downloads_path: Option<PathBuf>,
+    downloads: Downloads,
+    file_types: FileTypesPanel,
+    /// Response an "Always ask" rule held back for the user to decide on.
+    pending_file: Option<PendingFile>,
+    /// Magnet link shown before it is handed to a torrent client.
+    pending_magnet: Option<PendingMagnet>,
+    /// Sign-in a page asked for.
+    pending_auth: Option<AuthPrompt>,
+    connectivity: ConnectivityMonitor,
+    logins_path: Option<PathBuf>,
+    /// Whether `about:logins` shows passwords in the clear.
+    show_passwords: bool,
+    totp_form: TotpForm,
+    password_check: PasswordCheck,
+    import: ImportWizard,
+    sync: SyncPanel,
+    backup: BackupPanel,
+    parental: ParentalPanel,
+    proxy: ProxyPanel,
+    startup: StartupPanel,
+    client_certs: ClientCertsPanel,
+    ipfs: IpfsPanel,
+    /// Certificates refused per host, for `about:certerror`.
+    cert_errors: HashMap<String, CertErrorDetails>,
+    hsts_path: Option<PathBuf>,
+    /// Choices made on `about:flags`.
+    flags_path: Option<PathBuf>,
+    filter_lists_path: Option<PathBuf>,
+    shield: ShieldPanel,
+    devtools: DevTools,
+    responsive: Option<ResponsiveMode>,
+    /// Medium and image setting the page preview is rendered for.
+    render_options: RenderOptions,
+    screenshot: ScreenshotTool,
+    /// Whether the Keyboard Shortcuts window is open.
+    shortcuts_open: bool,
 }
 
 impl ShellApp {
-    fn new(handle: BrowserHandle) -> anyhow::Result<Self> {
+    fn new(handle: BrowserHandle, ctx: &EguiContext) -> anyhow::Result<Self> {
         let mut app = Self {
             handle: handle.clone(),
             tabs: Vec::new(),
             active_tab: None,
             url_input: String::new(),
+            focus_address_bar: false,
+            customizing_toolbar: false,
             nav_jobs: Vec::new(),
+            resource_fetches: Vec::new(),
+            refresh_jobs: Vec::new(),
             status_line: "Ready".to_owned(),
             last_update: Instant::now(),
-            page_preview: None,
+            pages: HashMap::new(),
+            favicons: HashMap::new(),
+            deferred_pages: HashMap::new(),
+            pending_scroll: HashMap::new(),
+            back_forward: BackForwardCache::default(),
+            preload_on_hover: true,
+            hovered_link: None,
+            preloaded: HashSet::new(),
+            active_workspace: handle.active_workspace(),
+            new_workspace_name: String::new(),
+            session_path: profile_dir().map(|dir| dir.join("session.json")),
+            show_notes: false,
+            note_url: None,
+            note_draft: String::new(),
+            preview_selection: None,
+            annotations_path: profile_dir().map(|dir| dir.join("annotations.json")),
+            settings: Settings::default(),
+            settings_path: profile_dir().map(|dir| dir.join("settings.json")),
+            config_watcher: None,
+            preferences: PreferencesPage::default(),
+            clip_dir_input: String::new(),
+            reader_mode: false,
+            link_check: None,
+            crawl: CrawlWizard::default(),
+            websocket: WebSocketConsole::default(),
+            script: None,
+            scripts: scripts_dir().as_deref().map(list_scripts).unwrap_or_default(),
+            macros: MacroLibrary::load(),
+            dead_page: None,
+            watches: Vec::new(),
+            watches_path: profile_dir().map(|dir| dir.join("watches.json")),
+            show_watches: false,
+            selected_watch: None,
+            jobs_path: profile_dir().map(|dir| dir.join("jobs.json")),
+            job_form: JobForm::default(),
+            bookmarks_path: profile_dir().map(|dir| dir.join("bookmarks.json")),
+            history_path: profile_dir().map(|dir| dir.join("history.json")),
+            history_filter: String::new(),
+            downloads_path: profile_dir().map(|dir| dir.join("downloads.json")),
+            downloads: Downloads::default(),
+            file_types: FileTypesPanel::default(),
+            pending_file: None,
+            pending_magnet: None,
+            pending_auth: None,
+            connectivity: ConnectivityMonitor::default(),
+            logins_path: profile_dir().map(|dir| dir.join("logins.json")),
+            show_passwords: false,
+            totp_form: TotpForm::default(),
+            password_check: PasswordCheck::default(),
+            import: ImportWizard::default(),
+            sync: SyncPanel::load(),
+            backup: BackupPanel::default(),
+            parental: ParentalPanel::load(),
+            proxy: ProxyPanel::default(),
+            startup: StartupPanel::default(),
+            client_certs: ClientCertsPanel::default(),
+            ipfs: IpfsPanel::default(),
+            cert_errors: HashMap::new(),
+            hsts_path: profile_dir().map(|dir| dir.join("hsts.json")),
+            flags_path: profile_dir().map(|dir| dir.join("flags.json")),
+            filter_lists_path: profile_dir().map(|dir| dir.join("filter_lists.json")),
+            shield: ShieldPanel::default(),
+            devtools: DevTools::default(),
+            responsive: None,
+            render_options: RenderOptions::default(),
+            screenshot: ScreenshotTool::default(),
+            shortcuts_open: false,
         };
-        let initial_tab = app
-            .handle
-            .create_tab("New Tab");
-        app.active_tab = Some(initial_tab);
-        app.refresh_tabs();
+        handle.apply_parental_controls(app.parental.controls());
+        // Experiments come first so the settings can turn on what they add.
+        if let Some(path) = &app.flags_path {
+            if let Err(err) = handle.load_flags(path) {
+                warn!("failed to load flags: {err:#}");
+            }
+        }
+        if let Some(path) = &app.settings_path {
+            match Settings::load(path) {
+                Ok(settings) => app.settings = settings,
+                Err(err) => warn!("failed to load settings: {err:#}"),
+            }
+        }
+        handle.set_consent_handling(app.settings.consent());
+        handle.set_tracker_protection(app.settings.privacy());
+        handle.set_file_type_rules(app.settings.file_type_rules.clone());
+        handle.set_ipfs_settings(app.settings.ipfs.clone());
+        handle.set_ip_preference(app.settings.ip_preference);
+        handle.set_content_codings(app.settings.content_codings());
+        if let Err(err) = handle.set_http_version(app.settings.http_version) {
+            warn!("failed to apply the HTTP version setting: {err:#}");
+        }
+        handle.set_site_user_agents(app.settings.site_user_agents.clone());
+        handle.set_content_blocking(!app.settings.pause_content_blocking);
+        app.apply_client_certificates();
+        app.clip_dir_input = app
+            .settings
+            .clip_directory
+            .as_ref()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let restore = app.settings.startup == StartupPage::RestoreSession;
+        if let Some(path) = app.session_path.as_ref().filter(|_| restore) {
+            if let Err(err) = app.handle.load_session(path) {
+                warn!("failed to restore session: {err:#}");
+            }
+        }
+        if let Some(path) = &app.annotations_path {
+            if let Err(err) = app.handle.load_annotations(path) {
+                warn!("failed to load annotations: {err:#}");
+            }
+        }
+        if let Some(path) = &app.watches_path {
+            if let Err(err) = app.handle.load_watches(path) {
+                warn!("failed to load watched pages: {err:#}");
+            }
+        }
+        app.refresh_watches();
+        if let Some(path) = &app.jobs_path {
+            if let Err(err) = app.handle.load_jobs(path) {
+                warn!("failed to load background jobs: {err:#}");
+            }
+        }
+        app.load_library();
+        app.apply_theme(ctx);
+        app.watch_config(ctx);
+        app.active_workspace = app.handle.active_workspace();
+        app.focus_workspace();
+        app.open_start_page();
         Ok(app)
     }
 
+    /// Focuses the first tab of the active workspace, opening one if it is empty.
+    fn focus_workspace(&mut self) {
+        let initial_tab = self
+            .handle
+            .workspace_tabs(self.active_workspace)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| self.handle.create_tab("New Tab"));
+        self.switch_to_tab(initial_tab);
+        self.refresh_tabs();
+    }
+
+    fn switch_workspace(&mut self, workspace: WorkspaceId) {
+        if workspace == self.active_workspace || !self.handle.switch_workspace(workspace) {
+            return;
+        }
+        self.active_workspace = workspace;
+
+        // Background workspaces are hibernated: drop their rendered pages and
+        // reload them lazily when their tabs are focused again.
+        let visible: HashSet<TabId> = self
+            .handle
+            .workspace_tabs(workspace)
+            .iter()
+            .map(|tab| tab.id)
+            .collect();
+        self.pages.retain(|tab, _| visible.contains(tab));
+        self.deferred_pages.retain(|tab, _| visible.contains(tab));
+        self.back_forward.retain_tabs(|tab| visible.contains(&tab));
+
+        self.focus_workspace();
+        self.save_session();
+    }
+
+    fn create_workspace(&mut self) {
+        let name = self.new_workspace_name.trim();
+        if name.is_empty() {
+            return;
+        }
+        let workspace = self.handle.create_workspace(name);
+        self.new_workspace_name.clear();
+        self.switch_workspace(workspace.id);
+    }
+
+    /// View menu entries for rejecting cookie banners, everywhere and on the
+    /// active site.
+    fn render_cookie_banner_settings(&mut self, ui: &mut egui::Ui, locked: bool) {
+        let platforms = CONSENT_PLATFORMS
+            .iter()
+            .map(|platform| platform.name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut changed = ui
+            .add_enabled(
+                !locked,
+                egui::Checkbox::new(
+                    &mut self.settings.reject_cookie_banners,
+                    "Reject cookie banners",
+                ),
+            )
+            .on_hover_text(format!(
+                "Refuses non-essential cookies and hides the banner for {platforms}"
+            ))
+            .changed();
+        let site = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| tab.url.clone())
+            .filter(|url| url.host_str().is_some());
+        if let (true, Some(url)) = (self.settings.reject_cookie_banners, site) {
+            let mut on_site = self
+                .settings
+                .consent()
+                .is_some_and(|consent| consent.applies_to(&url));
+            let label = format!("Reject on {}", url.host_str().unwrap_or_default());
+            if ui
+                .add_enabled(!locked, egui::Checkbox::new(&mut on_site, label))
+                .changed()
+            {
+                self.settings.reject_cookie_banners_on(&url, on_site);
+                changed = true;
+            }
+        }
+        if changed {
+            self.save_settings();
+            self.handle.set_consent_handling(self.settings.consent());
+        }
+    }
+
+    fn save_settings(&mut self) {
+        if let Some(path) = self.settings_path.clone() {
+            if let Err(err) = self.settings.save(&path) {
+                warn!("failed to save settings: {err:#}");
+            }
+            self.note_config_saved(&path);
+        }
+    }
+
+    /// Writes everything kept in memory to the profile directory.
+    fn save_profile(&mut self) {
+        self.save_session();
+        self.save_annotations();
+        self.save_watches();
+        self.save_jobs();
+        self.save_bookmarks();
+        self.save_history();
+        self.save_downloads();
+        self.save_logins();
+        self.save_cookies();
+        self.save_hsts();
+        self.save_filter_lists();
+        self.sync.save();
+    }
+
+    fn save_session(&self) {
+        if let Some(path) = &self.session_path {
+            if let Err(err) = self.handle.save_session(path) {
+                warn!("failed to save session: {err:#}");
+            }
+        }
+    }
+
+    fn save_cookies(&self) {
+        if let Err(err) = self.handle.save_cookies() {
+            warn!("failed to save cookies: {err}");
+        }
+    }
+
+    fn save_hsts(&self) {
+        if let Some(path) = &self.hsts_path {
+            if let Err(err) = self.handle.save_hsts(path) {
+                warn!("failed to save HSTS hosts: {err:#}");
+            }
+        }
+    }
+
     fn refresh_tabs(&mut self) {
-        self.tabs = self.handle.tabs();
+        self.tabs = self.handle.workspace_tabs(self.active_workspace);
         if let Some(active) = &self.active_tab {
             if let Some(updated) = self.tabs.iter().find(|tab| tab.id == active.id) {
                 self.active_tab = Some(updated.clone());
@@ -70,56 +653,417 @@ impl ShellApp {
     fn poll_navigation_jobs(&mut self) {
         let mut pending = Vec::with_capacity(self.nav_jobs.len());
         let mut needs_refresh = false;
+        let mut blocked = Vec::new();
+        let mut routed = Vec::new();
+        let mut shown = Vec::new();
         for mut job in self.nav_jobs.drain(..) {
             match job.try_complete() {
+                Some(Ok(page)) if page.action != FileAction::Open => {
+                    routed.push((job.tab(), job.url().clone(), page));
+                }
                 Some(Ok(page)) => {
                     info!(target = "ui", "loaded {} ({})", page.url, page.status);
                     self.status_line = format!("Loaded {}", page.url);
-                    self.page_preview = Some(generate_preview(&page.body));
+                    match page.cache {
+                        CacheStatus::Hit => self.status_line.push_str(" \u{b7} from cache"),
+                        CacheStatus::Revalidated => {
+                            self.status_line.push_str(" \u{b7} not modified")
+                        }
+                        CacheStatus::Miss => {}
+                    }
+                    if let Some(version) = &page.http_version {
+                        self.status_line.push_str(&format!(" \u{b7} {version}"));
+                    }
+                    if let Some(decoding) = &page.content_decoding {
+                        self.status_line.push_str(&format!(
+                            " \u{b7} {} {} \u{2192} {}",
+                            decoding.encoding,
+                            format_size(decoding.encoded_size),
+                            format_size(decoding.decoded_size)
+                        ));
+                    }
+                    if !page.redirects.is_empty() {
+                        self.status_line
+                            .push_str(&format!(" \u{b7} after {} redirects", page.redirects.len()));
+                    }
+                    let stripped = page.privacy.stripped_parameters.len();
+                    if stripped > 0 {
+                        self.status_line
+                            .push_str(&format!(" \u{b7} removed {stripped} tracking parameters"));
+                    }
+                    if !page.consent_rejected.is_empty() {
+                        self.status_line.push_str(&format!(
+                            " \u{b7} refused cookies for {}",
+                            page.consent_rejected.join(", ")
+                        ));
+                    }
+                    let blocked = page
+                        .mixed_content
+                        .iter()
+                        .filter(|resource| resource.action == MixedContentAction::Blocked)
+                        .count();
+                    if blocked > 0 {
+                        self.status_line
+                            .push_str(&format!(" \u{b7} blocked {blocked} insecure resources"));
+                    }
+                    self.preloaded.remove(&page.url);
+                    prefetch_hinted(&self.handle, job.tab(), &page);
+                    if self.dead_page.as_ref().map(|dead| dead.tab) == Some(job.tab()) {
+                        self.dead_page = None;
+                    }
+                    if is_dead_status(page.status) {
+                        let reason = format!("{} returned {}", page.url, page.status);
+                        self.dead_page = Some(DeadPage::new(job.tab(), page.url.clone(), reason));
+                    }
+                    if let Some(challenge) = page.auth_challenge.clone() {
+                        self.pending_auth = Some(AuthPrompt::new(job.tab(), challenge));
+                    }
+                    if let Some(refresh) = schedule_refresh(&self.handle, job.tab(), &page) {
+                        self.refresh_jobs
+                            .retain(|pending| pending.tab() != job.tab());
+                        self.refresh_jobs.push(refresh);
+                    }
+                    let is_active = self.active_tab.as_ref().map(|tab| tab.id) == Some(job.tab());
+                    if is_active {
+                        self.deferred_pages.remove(&job.tab());
+                        let view = PageView::new(&page)
+                            .scrolled_to(self.pending_scroll.remove(&job.tab()));
+                        self.back_forward.replace(&mut self.pages, job.tab(), view);
+                        shown.push((job.tab(), page));
+                    } else {
+                        self.deferred_pages.insert(job.tab(), page);
+                    }
                     needs_refresh = true;
                 }
+                Some(Err(BrowserError::Restricted(url))) => {
+                    blocked.push((job.tab(), blocked_page_url(&url)));
+                }
+                Some(Err(BrowserError::Tls(details))) => {
+                    blocked.push((job.tab(), cert_error_page_url(job.url())));
+                    self.cert_errors.insert(details.host.clone(), details);
+                }
+                Some(Err(BrowserError::Cancelled)) => {
+                    self.status_line = format!("Stopped loading {}", job.url());
+                }
                 Some(Err(err)) => {
                     self.status_line = format!("Failed: {err}");
+                    if err.is_unreachable() {
+                        let reason = format!("Could not reach {}", job.url());
+                        self.dead_page = Some(DeadPage::new(job.tab(), job.url().clone(), reason));
+                        self.connectivity.load_failed(job.tab(), job.url().clone());
+                    }
                 }
                 None => pending.push(job),
             }
         }
         self.nav_jobs = pending;
+        for (tab, page) in blocked {
+            self.open_internal_page(tab, page);
+        }
+        for (tab, requested, page) in routed {
+            self.route_page(tab, requested, page);
+        }
+        for (tab, page) in shown {
+            self.fetch_page_resources(tab, &page);
+        }
         if needs_refresh {
             self.refresh_tabs();
         }
     }
 
     fn initiate_navigation(&mut self) {
+        if let Ok(url) = parse_user_url(&self.url_input) {
+            self.macros.record(MacroStep::Open {
+                url: url.to_string(),
+            });
+            self.navigate_active(url);
+        } else {
+            self.status_line = "Enter a valid URL".to_owned();
+        }
+    }
+
+    fn navigate_active(&mut self, url: Url) {
         if let Some(active) = &self.active_tab {
-            if let Ok(url) = parse_user_url(&self.url_input) {
-                match self.handle.request_navigation(active.id, url.clone()) {
-                    Ok(job) => {
-                        self.nav_jobs.push(job);
-                        self.status_line = format!("Loading {url}");
-                    }
+            let tab = active.id;
+            self.url_input = url.to_string();
+            self.navigate_tab(tab, url);
+        }
+    }
+
+    fn navigate_tab(&mut self, tab: TabId, url: Url) {
+        // Remember where the page being left was scrolled to for Back.
+        if let Some(page) = self.pages.get(&tab) {
+            self.handle.set_scroll_offset(tab, page.scroll_offset);
+        }
+        self.load_tab(tab, url);
+    }
+
+    /// Sends `request`, replacing whatever its tab was still loading.
+    fn start_navigation(&mut self, request: PageRequest) -> anyhow::Result<()> {
+        self.stop_loading(request.tab);
+        self.connectivity.load_started(request.tab);
+        let job = self.handle.request_page(request)?;
+        self.nav_jobs.push(job);
+        Ok(())
+    }
+
+    /// Aborts the navigations in flight for `tab` and forgets their jobs.
+    fn stop_loading(&mut self, tab: TabId) {
+        if !self.nav_jobs.iter().any(|job| job.tab() == tab) {
+            return;
+        }
+        if let Err(err) = self.handle.cancel_navigation(tab) {
+            warn!("failed to stop loading: {err:#}");
+        }
+        self.nav_jobs.retain(|job| job.tab() != tab);
+    }
+
+    /// Loads `url` in `tab` without touching the page it is leaving.
+    fn load_tab(&mut self, tab: TabId, url: Url) {
+        if url.scheme() == "about" {
+            self.open_internal_page(tab, url);
+            return;
+        }
+        if url.scheme() == "magnet" {
+            self.open_magnet(url);
+            return;
+        }
+        match self.start_navigation(PageRequest::new(tab, url.clone())) {
+            Ok(()) => self.status_line = format!("Loading {url}"),
+            Err(err) => {
+                self.status_line = format!("Navigation error: {err}");
+            }
+        }
+    }
+
+    /// Steps the active tab back, or forward, through its history.
+    fn traverse_history(&mut self, forward: bool) {
+        let Some(tab) = self.active_tab.as_ref().map(|tab| tab.id) else {
+            return;
+        };
+        if let Some(page) = self.pages.get(&tab) {
+            self.handle.set_scroll_offset(tab, page.scroll_offset);
+        }
+        let entry = if forward {
+            self.handle.go_forward(tab)
+        } else {
+            self.handle.go_back(tab)
+        };
+        let Some(entry) = entry else {
+            return;
+        };
+        self.url_input = entry.url.to_string();
+        if self.restore_page(tab, &entry.url, entry.scroll_offset) {
+            return;
+        }
+        self.pending_scroll.insert(tab, entry.scroll_offset);
+        self.load_tab(tab, entry.url);
+    }
+
+    /// Opens `link` in a new tab without moving focus away from the current one.
+    fn open_in_background(&mut self, link: &PageLink) {
+        let tab = self.handle.create_tab(link.text.clone());
+        self.navigate_tab(tab.id, link.url.clone());
+        self.status_line = format!("Opened {} in background", link.url);
+        self.refresh_tabs();
+    }
+
+    fn switch_to_tab(&mut self, tab: TabSnapshot) {
+        self.handle.set_active_tab(tab.id);
+        if let Some(page) = self.deferred_pages.remove(&tab.id) {
+            let view = PageView::new(&page).scrolled_to(self.pending_scroll.remove(&tab.id));
+            self.back_forward.replace(&mut self.pages, tab.id, view);
+            self.fetch_page_resources(tab.id, &page);
+        }
+        let loading = self.nav_jobs.iter().any(|job| job.tab() == tab.id);
+        if let Some(url) = tab.url.clone() {
+            if !loading && !self.pages.contains_key(&tab.id) {
+                self.navigate_tab(tab.id, url);
+            }
+        }
+        self.url_input = tab
+            .url
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        self.active_tab = Some(tab);
+    }
+
+    fn apply_tab_action(&mut self, tab: &TabSnapshot, action: TabAction) {
+        match action {
+            TabAction::Duplicate => {
+                let Some(copy) = self.handle.duplicate_tab(tab.id) else {
+                    return;
+                };
+                if let Some(view) = self.pages.get(&tab.id).cloned() {
+                    self.pages.insert(
+                        copy.id,
+                        PageView {
+                            restore_scroll: true,
+                            ..view
+                        },
+                    );
+                }
+                self.status_line = format!("Duplicated {}", tab.title);
+                self.switch_to_tab(copy);
+            }
+            TabAction::ReloadBypassingCache => {
+                let Some(url) = tab.url.clone() else {
+                    return;
+                };
+                let request = PageRequest {
+                    cache: CachePolicy::Reload,
+                    ..PageRequest::new(tab.id, url.clone())
+                };
+                match self.start_navigation(request) {
+                    Ok(()) => self.status_line = format!("Reloading {url}"),
                     Err(err) => {
                         self.status_line = format!("Navigation error: {err}");
                     }
                 }
-            } else {
-                self.status_line = "Enter a valid URL".to_owned();
             }
+            TabAction::ReopenPrivate => {
+                let options = TabOptions {
+                    private: true,
+                    ..TabOptions::default()
+                };
+                self.reopen_tab(tab, options);
+            }
+            TabAction::ReopenWithUserAgent(user_agent) => {
+                let options = TabOptions {
+                    private: tab.private,
+                    user_agent: Some(user_agent.to_owned()),
+                };
+                self.reopen_tab(tab, options);
+            }
+            TabAction::SetSiteUserAgent(user_agent) => {
+                self.set_site_user_agent(tab, user_agent);
+            }
+            TabAction::ToggleQuiet => {
+                let quiet = !self.handle.is_tab_quiet(tab.id);
+                self.handle.set_tab_quiet(tab.id, quiet);
+                self.status_line = if quiet {
+                    format!("{} paused until focused", tab.title)
+                } else {
+                    format!("{} resumed", tab.title)
+                };
+            }
+            TabAction::ToggleAutoRefresh => self.toggle_auto_refresh(tab.id, &tab.title),
+        }
+        self.refresh_tabs();
+    }
+
+    /// Opens the location of `tab` in a fresh tab created with `options`.
+    /// User agent the site of `tab` is given, if it has one of its own.
+    fn site_user_agent(&self, tab: &TabSnapshot) -> Option<&str> {
+        let host = tab.url.as_ref()?.host_str()?;
+        self.settings
+            .site_user_agents
+            .iter()
+            .find(|site| site.site.eq_ignore_ascii_case(host))
+            .map(|site| site.user_agent.as_str())
+    }
+
+    /// Gives the site of `tab` its own user agent, or takes it away, and
+    /// reloads the tab to show the difference.
+    fn set_site_user_agent(&mut self, tab: &TabSnapshot, user_agent: Option<&str>) {
+        let Some(url) = tab.url.clone() else {
+            return;
+        };
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return;
+        };
+        let overrides = &mut self.settings.site_user_agents;
+        overrides.retain(|site| !site.site.eq_ignore_ascii_case(&host));
+        if let Some(user_agent) = user_agent {
+            overrides.push(SiteUserAgent {
+                site: host.clone(),
+                user_agent: user_agent.to_owned(),
+            });
+        }
+        self.handle.set_site_user_agents(overrides.clone());
+        self.save_settings();
+        self.status_line = match user_agent {
+            Some(_) => format!("{host} now gets its own user agent"),
+            None => format!("{host} gets the default user agent again"),
+        };
+        if tab.user_agent.is_none() {
+            self.navigate_tab(tab.id, url);
+        }
+    }
+
+    fn reopen_tab(&mut self, tab: &TabSnapshot, options: TabOptions) {
+        let reopened = self.handle.create_tab_with(tab.title.clone(), options);
+        if let Some(url) = tab.url.clone() {
+            self.navigate_tab(reopened.id, url);
+        }
+        self.switch_to_tab(reopened);
+    }
+
+    fn track_hovered_link(&mut self, hovered: Option<Url>) {
+        let Some(url) = hovered else {
+            self.hovered_link = None;
+            return;
+        };
+
+        let since = match &self.hovered_link {
+            Some(current) if current.url == url => current.since,
+            _ => {
+                self.hovered_link = Some(HoveredLink {
+                    url,
+                    since: Instant::now(),
+                });
+                return;
+            }
+        };
+
+        let ready = since.elapsed() >= HOVER_PRELOAD_DELAY;
+        if self.preload_on_hover
+            && ready
+            && !self.preloaded.contains(&url)
+            && self.handle.preload(url.clone()).is_ok()
+        {
+            self.preloaded.insert(url);
         }
     }
 
     fn render_toolbar(&mut self, ctx: &EguiContext) {
         TopBottomPanel::top("toolbar").show(ctx, |ui| {
             ui.with_layout(Layout::left_to_right(egui::Align::Center), |ui| {
+                self.render_workspace_picker(ui);
+                ui.separator();
+
                 let tabs_label = if let Some(active) = &self.active_tab {
-                    format!("{}", active.title)
+                    active.title.clone()
                 } else {
                     "No Tab".to_owned()
                 };
                 ui.label(RichText::new(tabs_label).strong());
                 ui.separator();
 
+                let active = self.active_tab.as_ref().map(|tab| tab.id);
+                let can_go_back = active.is_some_and(|tab| self.handle.can_go_back(tab));
+                let can_go_forward = active.is_some_and(|tab| self.handle.can_go_forward(tab));
+                if ui
+                    .add_enabled(can_go_back, egui::Button::new("\u{2190}"))
+                    .on_hover_text("Back (Alt+Left)")
+                    .clicked()
+                {
+                    self.traverse_history(false);
+                }
+                if ui
+                    .add_enabled(can_go_forward, egui::Button::new("\u{2192}"))
+                    .on_hover_text("Forward (Alt+Right)")
+                    .clicked()
+                {
+                    self.traverse_history(true);
+                }
+
+                self.render_connection_menu(ui);
                 let url_edit = ui.text_edit_singleline(&mut self.url_input);
+                if std::mem::take(&mut self.focus_address_bar) {
+                    url_edit.request_focus();
+                }
                 if url_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
                     self.initiate_navigation();
                 }
@@ -127,44 +1071,547 @@ impl ShellApp {
                 if ui.button("Go").clicked() {
                     self.initiate_navigation();
                 }
+                self.render_qr_menu(ui);
+                self.render_shield_menu(ui);
 
-                if ui.button("New Tab").clicked() {
-                    let tab = self.handle.create_tab("New Tab");
-                    self.active_tab = Some(tab);
-                    self.refresh_tabs();
-                }
+                self.render_toolbar_buttons(ctx, ui);
+
+                ui.checkbox(&mut self.preload_on_hover, "Preload on hover");
+                ui.toggle_value(&mut self.show_notes, "Notes");
+                self.render_watch_controls(ui);
+                self.render_language_switcher(ui);
+                self.render_macro_controls(ui);
+                ui.menu_button("Cite", |ui| {
+                    for style in CitationStyle::ALL {
+                        if ui.button(style.label()).clicked() {
+                            self.copy_citation(ctx, style);
+                            ui.close_menu();
+                        }
+                    }
+                });
 
                 ui.separator();
+                self.render_load_progress(ui);
                 ui.label(self.status_line.clone());
             });
         });
     }
 
-    fn render_content(&mut self, ctx: &EguiContext) {
-        CentralPanel::default().show(ctx, |ui| {
-            if let Some(preview) = &self.page_preview {
-                ui.heading("Page Preview");
+    /// Download progress and a Stop button while the active tab is loading.
+    fn render_load_progress(&self, ui: &mut egui::Ui) {
+        let active = self.active_tab.as_ref().map(|tab| tab.id);
+        let Some(job) = self.nav_jobs.iter().find(|job| Some(job.tab()) == active) else {
+            return;
+        };
+        let progress = job.progress();
+        let received = format_size(progress.received() as usize);
+        let rate = format_size(progress.bytes_per_second() as usize);
+        let bar = match (progress.fraction(), progress.total()) {
+            (Some(fraction), Some(total)) => egui::ProgressBar::new(fraction).text(format!(
+                "{received} of {} \u{b7} {rate}/s",
+                format_size(total as usize)
+            )),
+            _ => egui::ProgressBar::new(0.0)
+                .animate(true)
+                .text(format!("{received} \u{b7} {rate}/s")),
+        };
+        ui.add(bar.desired_width(220.0));
+        if ui.button("Stop").clicked() {
+            job.cancel();
+        }
+        ui.ctx().request_repaint_after(Duration::from_millis(100));
+    }
+
+    fn copy_citation(&mut self, ctx: &EguiContext, style: CitationStyle) {
+        let Some(page) = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| self.pages.get(&tab.id))
+        else {
+            self.status_line = "Load a page before citing it".to_owned();
+            return;
+        };
+        let citation = format_citation(&page.metadata, &page.url, page.received_at, style);
+        ctx.output_mut(|output| output.copied_text = citation);
+        self.status_line = format!("{} citation copied to clipboard", style.label());
+    }
+
+    fn render_workspace_picker(&mut self, ui: &mut egui::Ui) {
+        let workspaces = self.handle.workspaces();
+        let current = workspaces
+            .iter()
+            .find(|workspace| workspace.id == self.active_workspace)
+            .map(|workspace| workspace.name.clone())
+            .unwrap_or_default();
+
+        let mut selected = None;
+        let mut create = false;
+        egui::ComboBox::from_id_source("workspace_picker")
+            .selected_text(current)
+            .show_ui(ui, |ui| {
+                for workspace in &workspaces {
+                    let is_active = workspace.id == self.active_workspace;
+                    if ui.selectable_label(is_active, &workspace.name).clicked() {
+                        selected = Some(workspace.id);
+                    }
+                }
                 ui.separator();
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.code(preview);
+                ui.horizontal(|ui| {
+                    let edit = ui.add(
+                        egui::TextEdit::singleline(&mut self.new_workspace_name)
+                            .hint_text("New workspace")
+                            .desired_width(120.0),
+                    );
+                    let submitted =
+                        edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.button("Add").clicked() || submitted {
+                        create = true;
+                    }
                 });
-            } else {
-                ui.heading("Welcome to ASTERIX");
-                ui.label("Enter a URL above to load a page. Rendering is limited to a textual preview while the engine evolves.");
+            });
+
+        if create {
+            self.create_workspace();
+        } else if let Some(workspace) = selected {
+            self.switch_workspace(workspace);
+        }
+    }
+
+    fn render_tab_strip(&mut self, ctx: &EguiContext) {
+        TopBottomPanel::top("tab_strip").show(ctx, |ui| {
+            let mut selected = None;
+            let mut action = None;
+            let mut shown_origins = HashSet::new();
+            ui.horizontal_wrapped(|ui| {
+                let active_id = self.active_tab.as_ref().map(|tab| tab.id);
+                for tab in &self.tabs {
+                    if let (Some(favicon), Some(url)) = (&tab.favicon, &tab.url) {
+                        let origin = url.origin().ascii_serialization();
+                        let texture = self.favicons.entry(origin.clone()).or_insert_with(|| {
+                            let pixels = egui::ColorImage::from_rgba_unmultiplied(
+                                [favicon.width as usize, favicon.height as usize],
+                                &favicon.pixels,
+                            );
+                            ctx.load_texture(
+                                format!("favicon {origin}"),
+                                pixels,
+                                egui::TextureOptions::LINEAR,
+                            )
+                        });
+                        ui.add(
+                            egui::Image::new(&*texture).fit_to_exact_size(egui::vec2(16.0, 16.0)),
+                        );
+                        shown_origins.insert(origin);
+                    }
+                    let quiet = self.handle.is_tab_quiet(tab.id);
+                    let mut label = tab.title.clone();
+                    if tab.private {
+                        label = format!("Private · {label}");
+                    }
+                    if quiet {
+                        label = format!("Quiet · {label}");
+                    }
+                    let response = ui.selectable_label(Some(tab.id) == active_id, label);
+                    if response.clicked() {
+                        selected = Some(tab.clone());
+                    }
+                    let site_user_agent = self.site_user_agent(tab);
+                    response.context_menu(|ui| {
+                        if let Some(chosen) = tab_context_menu(ui, tab, quiet, site_user_agent) {
+                            action = Some((tab.clone(), chosen));
+                            ui.close_menu();
+                        }
+                    });
+                }
+            });
+            self.favicons
+                .retain(|origin, _| shown_origins.contains(origin));
+            if let Some(tab) = selected {
+                self.switch_to_tab(tab);
+            }
+            if let Some((tab, action)) = action {
+                self.apply_tab_action(&tab, action);
+            }
+        });
+    }
+
+    fn render_content(&mut self, ctx: &EguiContext) {
+        let internal = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| tab.url.clone())
+            .filter(|url| url.scheme() == "about");
+        if let Some(url) = internal {
+            self.render_internal_page(ctx, &url);
+            return;
+        }
+        let active_id = self.active_tab.as_ref().map(|tab| tab.id);
+        let active_private = self.active_tab.as_ref().is_some_and(|tab| tab.private);
+        let mut follow = None;
+        let mut clicked_link = false;
+        let mut form_action = None;
+        let mut background = None;
+        let mut download = None;
+        let mut hovered = None;
+        let mut selection = None;
+        let reader_mode = self.reader_mode;
+        let picking = self.devtools.elements.picking;
+        let mut picked = None;
+        let reading_time_on_articles = self.settings.reading_time_on_articles;
+        let highlights: Vec<String> = active_id
+            .and_then(|id| self.pages.get(&id))
+            .and_then(|page| self.handle.annotations_for(&page.url))
+            .map(|annotations| annotations.highlights.into_iter().map(|h| h.text).collect())
+            .unwrap_or_default();
+        let one_time_codes = active_id
+            .and_then(|id| self.pages.get(&id))
+            .map(|page| forms::one_time_codes(&self.handle.logins_for(&page.url)))
+            .unwrap_or_default();
+        let mut code_status = None;
+
+        let viewport = self.emulated_viewport();
+        let mut render_options = self.render_options;
+        let media_size = viewport.unwrap_or_else(|| ctx.screen_rect().size());
+        render_options.media.width = media_size.x.round();
+        render_options.media.height = media_size.y.round();
+        self.devtools.elements.media = render_options.media;
+        let emulating = rendering::is_emulating(&render_options);
+        CentralPanel::default().show(ctx, |ui| {
+            let mut render_page = |ui: &mut egui::Ui| {
+                if let Some((tab_id, page)) =
+                    active_id.and_then(|id| self.pages.get_mut(&id).map(|page| (id, page)))
+                {
+                    if !page.links.is_empty() {
+                        egui::SidePanel::right("page_links")
+                            .resizable(true)
+                            .show_inside(ui, |ui| {
+                                ui.heading("Links");
+                                ui.small("Ctrl+click or middle-click to open in a background tab");
+                                ui.separator();
+                                egui::ScrollArea::vertical().show(ui, |ui| {
+                                    for link in &page.links {
+                                        let response = ui.link(&link.text).on_hover_text(link.url.as_str());
+                                        response.context_menu(|ui| {
+                                            if ui.button("Download Link").clicked() {
+                                                download = Some(link.url.clone());
+                                                ui.close_menu();
+                                            }
+                                        });
+                                        if response.hovered() {
+                                            hovered = Some(link.url.clone());
+                                        }
+                                        let open_background = response.middle_clicked()
+                                            || (response.clicked() && ui.input(|i| i.modifiers.command));
+                                        if open_background {
+                                            background = Some(link.clone());
+                                        } else if response.clicked() {
+                                            follow = Some(link.url.clone());
+                                            clicked_link = true;
+                                        }
+                                    }
+                                });
+                            });
+                    }
+
+                    if let Some(view) = &mut page.site_file {
+                        ui.horizontal(|ui| {
+                            ui.heading(view.title());
+                            ui.toggle_value(&mut view.show_source, "Source");
+                        });
+                        if !view.show_source {
+                            ui.separator();
+                            if let Some(url) = view.show(ui, tab_id) {
+                                follow = Some(url);
+                            }
+                            return;
+                        }
+                    }
+
+                    if reader_mode && page.article.is_some() {
+                        reader::render_reader(ui, tab_id, page);
+                        return;
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.heading("Page Preview");
+                        pretty_print::render_toggle(ui, page);
+                    });
+                    if emulating {
+                        rendering::refresh_rendering(page, &render_options);
+                    }
+                    let rendered = page
+                        .rendered
+                        .as_ref()
+                        .map(|(_, rendered)| rendered)
+                        .filter(|_| emulating);
+                    if let Some(rendered) = rendered {
+                        ui.weak(rendering::summary(&render_options, rendered));
+                    }
+                    if !one_time_codes.is_empty() {
+                        code_status =
+                            forms::show_one_time_codes(ui, &one_time_codes, &mut page.forms);
+                    }
+                    if !page.forms.is_empty() {
+                        form_action =
+                            forms::show_forms(ui, tab_id, active_private, &mut page.forms)
+                                .map(|action| (tab_id, action));
+                    }
+                    if reading_time_on_articles {
+                        if let Some(article) = page.article.as_ref().filter(|a| a.is_article_like()) {
+                            reader::reading_status(ui, article, page.reading_progress);
+                        }
+                    }
+                    ui.separator();
+                    let mut scroll = egui::ScrollArea::vertical().id_source(("page_preview", tab_id));
+                    if page.restore_scroll {
+                        scroll = scroll.vertical_scroll_offset(page.scroll_offset);
+                        page.restore_scroll = false;
+                    }
+                    let output = scroll.show(ui, |ui| {
+                        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                            let job = notes::highlighted_layout(ui, text, &highlights, wrap_width);
+                            ui.fonts(|fonts| fonts.layout_job(job))
+                        };
+                        let shown = match rendered {
+                            Some(rendered) => {
+                                rendering::apply_page_colors(ui, rendered);
+                                rendered.text.as_str()
+                            }
+                            None => page.pretty.as_deref().unwrap_or(&page.preview),
+                        };
+                        let mut text = shown;
+                        let output = egui::TextEdit::multiline(&mut text)
+                            .code_editor()
+                            .desired_width(f32::INFINITY)
+                            .layouter(&mut layouter)
+                            .show(ui);
+                        // Rendered text no longer lines up with the source
+                        // the picker maps positions into.
+                        if picking && rendered.is_none() && page.pretty.is_none() {
+                            picked = inspector::pick_in_preview(ui, &output, page);
+                        }
+                        if let Some(range) = output.cursor_range {
+                            let chars = range.as_sorted_char_range();
+                            if !chars.is_empty() {
+                                selection = Some(
+                                    shown
+                                        .chars()
+                                        .skip(chars.start)
+                                        .take(chars.len())
+                                        .collect::<String>(),
+                                );
+                            }
+                        }
+                    });
+                    page.scroll_offset = output.state.offset.y;
+                    page.reading_progress = reader::scroll_progress(&output);
+                } else {
+                    ui.heading("Welcome to ASTERIX");
+                    ui.label("Enter a URL above to load a page. Rendering is limited to a textual preview while the engine evolves.");
+                }
+            };
+            match viewport {
+                Some(viewport) => responsive::show_in_viewport(ui, viewport, render_page),
+                None => render_page(ui),
             }
         });
+
+        self.preview_selection = selection;
+        if picking && ctx.input(|input| input.key_pressed(egui::Key::Escape)) {
+            self.devtools.elements.picking = false;
+        }
+        if let Some(index) = picked {
+            self.inspect_element(index);
+        }
+        if let Some(status) = code_status {
+            self.status_line = status;
+        }
+        self.track_hovered_link(hovered);
+        if self.hovered_link.is_some() {
+            ctx.request_repaint_after(HOVER_PRELOAD_DELAY);
+        }
+        if let Some((tab, action)) = form_action {
+            match action {
+                forms::FormAction::Submit(index) => self.submit_page_form(tab, index),
+                forms::FormAction::FillIdentity(index) => self.fill_throwaway_identity(tab, index),
+            }
+        } else if let Some(link) = background {
+            self.open_in_background(&link);
+        } else if let Some(url) = download {
+            self.start_download(url);
+        } else if let Some(url) = follow {
+            if clicked_link {
+                self.record_link_click(&url);
+            }
+            self.navigate_active(url);
+        }
     }
 }
 
 impl eframe::App for ShellApp {
     fn update(&mut self, ctx: &EguiContext, _frame: &mut eframe::Frame) {
+        self.mark_page_painted();
         self.poll_navigation_jobs();
+        self.poll_page_resources();
+        self.poll_refreshes();
+        self.poll_downloads(ctx);
+        self.poll_connectivity();
         if self.last_update.elapsed().as_secs() >= 1 {
             self.refresh_tabs();
+            self.refresh_watches();
+            self.watch_network();
+            self.back_forward.relieve_memory_pressure();
             self.last_update = Instant::now();
         }
+        self.reload_config(ctx);
+        self.run_shortcuts(ctx);
+        self.render_menu_bar(ctx);
         self.render_toolbar(ctx);
+        self.render_tab_strip(ctx);
+        self.render_status_bar(ctx);
+        self.render_responsive_bar(ctx);
+        self.render_archive_banner(ctx);
+        self.render_notes_panel(ctx);
+        self.render_link_checker(ctx);
+        self.render_crawl_wizard(ctx);
+        self.render_websocket_console(ctx);
+        self.render_import_wizard(ctx);
+        self.render_script_window(ctx);
+        self.render_macros_window(ctx);
+        self.render_watches_window(ctx);
+        self.render_sync_window(ctx);
+        self.render_backup_window(ctx);
+        self.render_proxy_window(ctx);
+        self.render_startup_window(ctx);
+        self.render_client_certs_window(ctx);
+        self.render_ipfs_window(ctx);
+        self.render_file_types_window(ctx);
+        self.render_file_prompt(ctx);
+        self.render_magnet_prompt(ctx);
+        self.render_auth_prompt(ctx);
+        self.render_captive_portal_prompt(ctx);
+        self.render_parental_window(ctx);
+        self.render_shortcuts_window(ctx);
+        self.render_customize_toolbar_window(ctx);
+        self.render_screenshot_editor(ctx);
+        self.render_devtools(ctx);
         self.render_content(ctx);
+        self.run_eyedropper(ctx);
+        self.run_screenshot_capture(ctx);
+        self.mark_page_laid_out();
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Writing the old state back would undo a restored backup.
+        if !self.backup.restored {
+            self.save_profile();
+        }
+    }
+}
+
+fn tab_context_menu(
+    ui: &mut egui::Ui,
+    tab: &TabSnapshot,
+    quiet: bool,
+    site_user_agent: Option<&str>,
+) -> Option<TabAction> {
+    let has_page = tab.url.is_some();
+    let mut action = None;
+    if ui.button("Duplicate Tab").clicked() {
+        action = Some(TabAction::Duplicate);
+    }
+    if ui
+        .add_enabled(has_page, egui::Button::new("Reload Without Cache"))
+        .clicked()
+    {
+        action = Some(TabAction::ReloadBypassingCache);
+    }
+    if ui
+        .add_enabled(
+            has_page && !tab.private,
+            egui::Button::new("Reopen in Private Tab"),
+        )
+        .clicked()
+    {
+        action = Some(TabAction::ReopenPrivate);
+    }
+    ui.add_enabled_ui(has_page, |ui| {
+        ui.menu_button("Reopen With User Agent", |ui| {
+            for (name, user_agent) in USER_AGENT_PRESETS {
+                if ui.button(*name).clicked() {
+                    action = Some(TabAction::ReopenWithUserAgent(user_agent));
+                }
+            }
+        });
+    });
+    let has_site = tab.url.as_ref().and_then(Url::host_str).is_some();
+    ui.add_enabled_ui(has_site, |ui| {
+        ui.menu_button("User Agent for This Site", |ui| {
+            if ui
+                .selectable_label(site_user_agent.is_none(), "Browser Default")
+                .clicked()
+            {
+                action = Some(TabAction::SetSiteUserAgent(None));
+            }
+            for (name, user_agent) in USER_AGENT_PRESETS {
+                if ui
+                    .selectable_label(site_user_agent == Some(*user_agent), *name)
+                    .clicked()
+                {
+                    action = Some(TabAction::SetSiteUserAgent(Some(user_agent)));
+                }
+            }
+        });
+    });
+    ui.separator();
+    let quiet_label = if quiet { "Wake Tab" } else { "Quiet Tab" };
+    if ui.button(quiet_label).clicked() {
+        action = Some(TabAction::ToggleQuiet);
+    }
+    let refresh_label = if tab.auto_refresh_disabled {
+        "Follow Page Refreshes"
+    } else {
+        "Ignore Page Refreshes"
+    };
+    if ui.button(refresh_label).clicked() {
+        action = Some(TabAction::ToggleAutoRefresh);
+    }
+    action
+}
+
+/// Directory holding the user's persistent browser state.
+pub fn profile_dir() -> Option<PathBuf> {
+    home::home_dir().map(|home| home.join(".asterix"))
+}
+
+/// Proxy configuration saved in the profile's settings, for starting the
+/// browser with.
+pub fn proxy_settings() -> ProxySettings {
+    let Some(path) = profile_dir().map(|dir| dir.join("settings.json")) else {
+        return ProxySettings::default();
+    };
+    match Settings::load(&path) {
+        Ok(settings) => settings.proxy,
+        Err(err) => {
+            warn!("failed to load proxy settings: {err:#}");
+            ProxySettings::default()
+        }
+    }
+}
+
+/// Fetches the pages `page` links as next, previous or worth prefetching, so
+/// following them is instant. Private tabs leave no such traces.
+fn prefetch_hinted(handle: &BrowserHandle, tab: TabId, page: &PageResponse) {
+    let private = handle
+        .tabs()
+        .iter()
+        .any(|snapshot| snapshot.id == tab && snapshot.private);
+    let hints = prefetch_hints(page);
+    if private || hints.is_empty() {
+        return;
+    }
+    if let Err(err) = handle.prefetch(tab, hints) {
+        warn!("failed to prefetch hinted pages: {err:#}");
     }
 }
 
@@ -174,6 +1621,18 @@ fn parse_user_url(input: &str) -> anyhow::Result<Url> {
         anyhow::bail!("empty url");
     }
 
+    // Paths typed into the address bar open as local files.
+    if trimmed.starts_with('/') || trimmed.starts_with("~/") {
+        let path = match trimmed.strip_prefix("~/") {
+            Some(rest) => home::home_dir()
+                .ok_or_else(|| anyhow::anyhow!("no home directory"))?
+                .join(rest),
+            None => std::path::PathBuf::from(trimmed),
+        };
+        return Url::from_file_path(&path)
+            .map_err(|()| anyhow::anyhow!("{} is not an absolute path", path.display()));
+    }
+
     let parsed = Url::parse(trimmed).or_else(|_| {
         let with_scheme = format!("https://{trimmed}");
         Url::parse(&with_scheme)
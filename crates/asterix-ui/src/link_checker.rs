@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use asterix_browser::{LinkCheckJob, LinkReport, LinkStatus};
+use eframe::egui;
+use egui::{Color32, Context as EguiContext, RichText};
+use url::Url;
+
+use crate::ShellApp;
+
+const BROKEN_COLOR: Color32 = Color32::from_rgb(220, 80, 80);
+const REDIRECT_COLOR: Color32 = Color32::from_rgb(220, 170, 60);
+const OK_COLOR: Color32 = Color32::from_rgb(90, 180, 110);
+
+/// Link check started from the developer tools, pending or finished.
+pub(crate) struct LinkCheck {
+    page: Url,
+    job: Option<LinkCheckJob>,
+    reports: Vec<LinkReport>,
+    problems_only: bool,
+}
+
+impl ShellApp {
+    /// Checks every link on the active page in the background.
+    pub(crate) fn start_link_check(&mut self) {
+        let Some(page) = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| self.pages.get(&tab.id))
+        else {
+            self.status_line = "Load a page before checking its links".to_owned();
+            return;
+        };
+        if page.links.is_empty() {
+            self.status_line = "No links to check on this page".to_owned();
+            return;
+        }
+
+        match self.handle.check_links(page.links.clone()) {
+            Ok(job) => {
+                self.status_line = format!("Checking {} links...", page.links.len());
+                self.link_check = Some(LinkCheck {
+                    page: page.url.clone(),
+                    job: Some(job),
+                    reports: Vec::new(),
+                    problems_only: true,
+                });
+            }
+            Err(err) => self.status_line = format!("Link check failed to start: {err}"),
+        }
+    }
+
+    /// Bottom panel listing broken, redirected and insecure links.
+    pub(crate) fn render_link_checker(&mut self, ctx: &EguiContext) {
+        let Some(check) = &mut self.link_check else {
+            return;
+        };
+        if let Some(job) = &mut check.job {
+            match job.try_complete() {
+                Some(reports) => {
+                    check.reports = reports;
+                    check.job = None;
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(200)),
+            }
+        }
+
+        let mut close = false;
+        egui::TopBottomPanel::bottom("link_checker")
+            .resizable(true)
+            .default_height(220.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Link Checker");
+                    ui.label(check.page.as_str());
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        close = ui.button("Close").clicked();
+                        ui.checkbox(&mut check.problems_only, "Problems only");
+                    });
+                });
+
+                if check.job.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Checking links...");
+                    });
+                    return;
+                }
+
+                let broken = check.reports.iter().filter(|r| r.is_broken()).count();
+                let redirected = check.reports.iter().filter(|r| r.is_redirected()).count();
+                let insecure = check.reports.iter().filter(|r| r.insecure).count();
+                ui.label(format!(
+                    "{} links checked: {broken} broken, {redirected} redirected, {insecure} insecure",
+                    check.reports.len()
+                ));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        egui::Grid::new("link_reports")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for report in &check.reports {
+                                    let problem = report.is_broken()
+                                        || report.is_redirected()
+                                        || report.insecure;
+                                    if check.problems_only && !problem {
+                                        continue;
+                                    }
+                                    link_report_row(ui, report);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        if close {
+            self.link_check = None;
+        }
+    }
+}
+
+fn link_report_row(ui: &mut egui::Ui, report: &LinkReport) {
+    let (label, color) = match &report.status {
+        LinkStatus::Ok { status } => (status.to_string(), OK_COLOR),
+        LinkStatus::Redirected { status, .. } => (format!("{status} redirect"), REDIRECT_COLOR),
+        LinkStatus::Broken { status } => (status.to_string(), BROKEN_COLOR),
+        LinkStatus::Failed(_) => ("error".to_owned(), BROKEN_COLOR),
+    };
+    ui.label(RichText::new(label).color(color).monospace());
+
+    let text = if report.link.text.is_empty() {
+        report.link.url.as_str()
+    } else {
+        report.link.text.as_str()
+    };
+    ui.hyperlink_to(text, report.link.url.as_str())
+        .on_hover_text(report.link.url.as_str());
+
+    let mut notes = Vec::new();
+    match &report.status {
+        LinkStatus::Redirected { location, .. } => notes.push(format!("-> {location}")),
+        LinkStatus::Failed(err) => notes.push(err.clone()),
+        _ => {}
+    }
+    if report.insecure {
+        notes.push("served over plain HTTP".to_owned());
+    }
+    ui.label(notes.join("; "));
+}
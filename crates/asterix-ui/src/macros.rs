@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use asterix_browser::{link_selector, load_macros, save_macros, Macro, MacroStep};
+use eframe::egui;
+use egui::{Color32, Context as EguiContext};
+use tracing::warn;
+use url::Url;
+
+use crate::scripts::scripts_dir;
+use crate::{profile_dir, ShellApp};
+
+const RECORDING_COLOR: Color32 = Color32::from_rgb(220, 60, 60);
+
+/// Macros saved in the profile, and the one being recorded.
+pub(crate) struct MacroLibrary {
+    macros: Vec<Macro>,
+    path: Option<PathBuf>,
+    /// Steps captured so far while recording.
+    recording: Option<Vec<MacroStep>>,
+    /// Finished recording waiting for a name.
+    unsaved: Option<Vec<MacroStep>>,
+    new_name: String,
+    pub(crate) open: bool,
+    selected: Option<usize>,
+    /// Values entered for the selected macro's parameters.
+    values: HashMap<String, String>,
+}
+
+impl MacroLibrary {
+    /// Loads the macros saved in the profile.
+    pub(crate) fn load() -> Self {
+        let path = profile_dir().map(|dir| dir.join("macros.json"));
+        let macros = match path.as_deref().map(load_macros) {
+            Some(Ok(macros)) => macros,
+            Some(Err(err)) => {
+                warn!("failed to load macros: {err:#}");
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        Self {
+            macros,
+            path,
+            recording: None,
+            unsaved: None,
+            new_name: String::new(),
+            open: false,
+            selected: None,
+            values: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Adds `step` to the recording, if one is running.
+    pub(crate) fn record(&mut self, step: MacroStep) {
+        if let Some(steps) = &mut self.recording {
+            steps.push(step);
+        }
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = save_macros(path, &self.macros) {
+                warn!("failed to save macros: {err:#}");
+            }
+        }
+    }
+}
+
+/// What the macros window asked the shell to do.
+enum MacroAction {
+    None,
+    Replay(usize),
+}
+
+impl ShellApp {
+    /// Record toggle and step count shown in the toolbar.
+    pub(crate) fn render_macro_controls(&mut self, ui: &mut egui::Ui) {
+        let library = &mut self.macros;
+        match &library.recording {
+            Some(steps) => {
+                ui.colored_label(
+                    RECORDING_COLOR,
+                    format!("\u{25cf} Recording ({})", steps.len()),
+                );
+                if ui.button("Stop").clicked() {
+                    library.unsaved = library.recording.take();
+                    library.new_name.clear();
+                    library.open = true;
+                }
+            }
+            None => {
+                if ui
+                    .button("Record")
+                    .on_hover_text(
+                        "Record navigations, link clicks and form submissions as a macro",
+                    )
+                    .clicked()
+                {
+                    library.recording = Some(Vec::new());
+                    library.unsaved = None;
+                    self.status_line = "Recording a macro".to_owned();
+                }
+            }
+        }
+    }
+
+    /// Records following `url` from the active page, as a click on the link
+    /// when the page source still has it.
+    pub(crate) fn record_link_click(&mut self, url: &Url) {
+        if !self.macros.is_recording() {
+            return;
+        }
+        let selector = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| self.pages.get(&tab.id))
+            .and_then(|page| link_selector(&page.source, &page.url, url));
+        self.macros.record(match selector {
+            Some(selector) => MacroStep::Click { selector },
+            None => MacroStep::Open {
+                url: url.to_string(),
+            },
+        });
+    }
+
+    pub(crate) fn render_macros_window(&mut self, ctx: &EguiContext) {
+        let library = &mut self.macros;
+        if !library.open {
+            return;
+        }
+        let mut open = true;
+        let mut action = MacroAction::None;
+        egui::Window::new("Macros")
+            .open(&mut open)
+            .default_size([560.0, 380.0])
+            .show(ctx, |ui| {
+                if let Some(steps) = &library.unsaved {
+                    ui.strong(format!("New recording, {} steps", steps.len()));
+                    let mut keep = None;
+                    ui.horizontal(|ui| {
+                        ui.label("Name");
+                        ui.text_edit_singleline(&mut library.new_name);
+                        let named = !library.new_name.trim().is_empty();
+                        if ui.add_enabled(named, egui::Button::new("Save")).clicked() {
+                            keep = Some(true);
+                        }
+                        if ui.button("Discard").clicked() {
+                            keep = Some(false);
+                        }
+                    });
+                    match keep {
+                        Some(true) => {
+                            let steps = library.unsaved.take().unwrap_or_default();
+                            library.macros.push(Macro {
+                                name: library.new_name.trim().to_owned(),
+                                steps,
+                            });
+                            library.selected = Some(library.macros.len() - 1);
+                            library.save();
+                        }
+                        Some(false) => library.unsaved = None,
+                        None => {}
+                    }
+                    ui.separator();
+                }
+
+                if library.macros.is_empty() {
+                    ui.weak("No macros yet. Press Record in the toolbar, browse, then press Stop.");
+                    return;
+                }
+                ui.horizontal_wrapped(|ui| {
+                    for (index, recorded) in library.macros.iter().enumerate() {
+                        if ui
+                            .selectable_label(library.selected == Some(index), &recorded.name)
+                            .clicked()
+                        {
+                            library.selected = Some(index);
+                            library.values.clear();
+                        }
+                    }
+                });
+                let Some(index) = library
+                    .selected
+                    .filter(|index| *index < library.macros.len())
+                else {
+                    return;
+                };
+                ui.separator();
+
+                let mut changed = false;
+                let mut delete = false;
+                let recorded = &mut library.macros[index];
+                ui.small("Write {{name}} in a value to be asked for it on every replay.");
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("macro_steps")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for step in &mut recorded.steps {
+                                    ui.strong(step.label());
+                                    if let MacroStep::Fill { selector, .. } = step {
+                                        ui.monospace(selector.as_str());
+                                    } else {
+                                        ui.label("");
+                                    }
+                                    let edit = ui.add(
+                                        egui::TextEdit::singleline(step.value_mut())
+                                            .desired_width(280.0),
+                                    );
+                                    changed |= edit.lost_focus();
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.separator();
+
+                let parameters = recorded.parameters();
+                if !parameters.is_empty() {
+                    egui::Grid::new("macro_parameters")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for name in parameters {
+                                ui.label(&name);
+                                ui.text_edit_singleline(library.values.entry(name).or_default());
+                                ui.end_row();
+                            }
+                        });
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Replay").clicked() {
+                        action = MacroAction::Replay(index);
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = true;
+                    }
+                });
+                if delete {
+                    library.macros.remove(index);
+                    library.selected = None;
+                }
+                if changed || delete {
+                    library.save();
+                }
+            });
+        library.open = open;
+
+        if let MacroAction::Replay(index) = action {
+            let recorded = &self.macros.macros[index];
+            let name = recorded.name.clone();
+            let source = recorded.to_script(&self.macros.values);
+            match scripts_dir() {
+                Some(dir) => self.run_script_source(name, source, dir),
+                None => self.status_line = "No profile directory to replay macros in".to_owned(),
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::process::Command;
+
+use asterix_browser::{parse_magnet, MagnetLink};
+use eframe::egui;
+use egui::Context as EguiContext;
+use tracing::info;
+use url::Url;
+
+use crate::composer::format_size;
+use crate::downloads::{open_with_system, spawn_detached};
+use crate::ShellApp;
+
+/// Magnet link waiting for the user to hand it to a torrent client.
+pub(crate) struct PendingMagnet {
+    url: Url,
+    link: MagnetLink,
+}
+
+impl ShellApp {
+    /// Previews the torrent `url` points to instead of loading it.
+    pub(crate) fn open_magnet(&mut self, url: Url) {
+        match parse_magnet(&url) {
+            Some(link) => self.pending_magnet = Some(PendingMagnet { url, link }),
+            None => self.status_line = "The magnet link names no torrent".to_owned(),
+        }
+        if let Some(tab) = &self.active_tab {
+            self.url_input = tab
+                .url
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+        }
+    }
+
+    pub(crate) fn render_magnet_prompt(&mut self, ctx: &EguiContext) {
+        let Some(pending) = &self.pending_magnet else {
+            return;
+        };
+        let link = &pending.link;
+        let handler = &mut self.settings.magnet_handler;
+        let mut open = true;
+        let mut hand_off = false;
+        let mut copy = false;
+        let mut cancel = false;
+        egui::Window::new("Open Torrent?")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.heading(link.display_name());
+                egui::Grid::new("magnet_details")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(if link.v2 {
+                            "Info hash (v2)"
+                        } else {
+                            "Info hash"
+                        });
+                        ui.monospace(&link.info_hash);
+                        ui.end_row();
+                        ui.label("Size");
+                        ui.label(link.size.map_or_else(
+                            || "Not given".to_owned(),
+                            |size| format_size(size as usize),
+                        ));
+                        ui.end_row();
+                        if !link.web_seeds.is_empty() {
+                            ui.label("Web seeds");
+                            ui.label(link.web_seeds.len().to_string());
+                            ui.end_row();
+                        }
+                    });
+                ui.collapsing(format!("Trackers ({})", link.trackers.len()), |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for tracker in &link.trackers {
+                                ui.monospace(tracker);
+                            }
+                            if link.trackers.is_empty() {
+                                ui.weak("None; the client finds peers through DHT.");
+                            }
+                        });
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Open with");
+                    ui.add(
+                        egui::TextEdit::singleline(handler)
+                            .hint_text("System torrent client")
+                            .desired_width(240.0),
+                    );
+                });
+                ui.weak("A command given here gets the magnet link as its last argument.");
+                ui.horizontal(|ui| {
+                    hand_off = ui.button("Open in Torrent Client").clicked();
+                    copy = ui.button("Copy Link").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if copy {
+            let url = pending.url.to_string();
+            ctx.output_mut(|output| output.copied_text = url);
+            self.status_line = "Magnet link copied".to_owned();
+        }
+        if !open || cancel {
+            self.pending_magnet = None;
+            return;
+        }
+        if !hand_off {
+            return;
+        }
+        let Some(pending) = self.pending_magnet.take() else {
+            return;
+        };
+        self.save_settings();
+        let handler = self.settings.magnet_handler.trim();
+        let mut words = handler.split_whitespace();
+        let launched = match words.next() {
+            Some(program) => {
+                spawn_detached(Command::new(program).args(words).arg(pending.url.as_str()))
+            }
+            None => open_with_system(pending.url.as_str()),
+        };
+        self.status_line = match launched {
+            Ok(()) => {
+                info!(
+                    target = "ui",
+                    "handed {} to a torrent client", pending.link.info_hash
+                );
+                format!(
+                    "Opened {} in the torrent client",
+                    pending.link.display_name()
+                )
+            }
+            Err(err) => format!("Could not start the torrent client: {err}"),
+        };
+    }
+}
@@ -1,20 +1,62 @@
+mod headless;
+
+use std::sync::Arc;
+
 use anyhow::Context;
-use asterix_browser::BrowserRuntime;
-use tracing::Level;
+use asterix_browser::{BrowserRuntime, CookieJar, HttpCache};
+use tracing::{warn, Level};
 use tracing_subscriber::EnvFilter;
 
+use crate::headless::Command;
+
 fn main() -> anyhow::Result<()> {
     setup_tracing()?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = Command::parse(&args)?;
 
-    let runtime = BrowserRuntime::new(Some(DEFAULT_USER_AGENT))
-        .context("failed to start browser runtime")?;
+    let runtime = BrowserRuntime::new(
+        Some(DEFAULT_USER_AGENT),
+        open_cookie_jar(),
+        open_http_cache(),
+        asterix_ui::proxy_settings(),
+    )
+    .context("failed to start browser runtime")?;
     let handle = runtime.handle();
 
-    asterix_ui::launch_shell(handle)?;
+    match command {
+        Some(command) => {
+            command.run(&handle)?;
+            if let Err(err) = handle.save_cookies() {
+                warn!("failed to save cookies: {err}");
+            }
+        }
+        None => asterix_ui::launch_shell(handle)?,
+    }
 
     Ok(())
 }
 
+/// The profile's cookie jar, or an empty one kept in memory when it cannot
+/// be read.
+fn open_cookie_jar() -> Arc<CookieJar> {
+    let Some(path) = asterix_ui::profile_dir().map(|dir| dir.join("cookies.json")) else {
+        return Arc::new(CookieJar::in_memory());
+    };
+    let jar = CookieJar::open(path).unwrap_or_else(|err| {
+        warn!("failed to load cookies: {err}");
+        CookieJar::in_memory()
+    });
+    Arc::new(jar)
+}
+
+/// The profile's HTTP cache, or none at all when it cannot be opened.
+fn open_http_cache() -> Option<HttpCache> {
+    let dir = asterix_ui::profile_dir()?.join("cache");
+    HttpCache::open(&dir)
+        .inspect_err(|err| warn!("failed to open HTTP cache in {}: {err}", dir.display()))
+        .ok()
+}
+
 fn setup_tracing() -> anyhow::Result<()> {
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::default().add_directive(Level::INFO.into()));
@@ -0,0 +1,635 @@
+use asterix_browser::TabOptions;
+use eframe::egui;
+use egui::{Context as EguiContext, Key, KeyboardShortcut, Modifiers, TopBottomPanel};
+use url::Url;
+
+use crate::devtools::DevToolsTab;
+use crate::internal_pages::{
+    ABOUT_PAGE, CONFIG_PAGE, DOWNLOADS_PAGE, FLAGS_PAGE, HISTORY_PAGE, JOBS_PAGE, LOGINS_PAGE,
+};
+use crate::{ShellApp, TabAction};
+
+/// Pages listed at the bottom of the History menu.
+const RECENT_HISTORY: usize = 10;
+
+/// Top-level menu of the menu bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Menu {
+    File,
+    Edit,
+    View,
+    History,
+    Bookmarks,
+    Tools,
+    Help,
+}
+
+impl Menu {
+    pub(crate) const ALL: [Self; 7] = [
+        Self::File,
+        Self::Edit,
+        Self::View,
+        Self::History,
+        Self::Bookmarks,
+        Self::Tools,
+        Self::Help,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::File => "File",
+            Self::Edit => "Edit",
+            Self::View => "View",
+            Self::History => "History",
+            Self::Bookmarks => "Bookmarks",
+            Self::Tools => "Tools",
+            Self::Help => "Help",
+        }
+    }
+}
+
+/// Command of the shell, run from its menu or its keyboard shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    NewTab,
+    NewPrivateTab,
+    OpenLocation,
+    SavePage,
+    ImportData,
+    Quit,
+    CopyAddress,
+    Preferences,
+    Experiments,
+    Reload,
+    ReloadBypassingCache,
+    Stop,
+    ReaderMode,
+    ResponsiveDesign,
+    CustomizeToolbar,
+    Back,
+    Forward,
+    Home,
+    ShowHistory,
+    ShowDownloads,
+    BookmarkPage,
+    InspectElement,
+    Screenshot,
+    Console,
+    Network,
+    SavedPasswords,
+    KeyboardShortcuts,
+    BrowserInternals,
+    AboutAsterix,
+}
+
+impl Action {
+    /// Every action, in the order its menu lists them.
+    pub(crate) const ALL: [Self; 29] = [
+        Self::NewTab,
+        Self::NewPrivateTab,
+        Self::OpenLocation,
+        Self::SavePage,
+        Self::ImportData,
+        Self::Quit,
+        Self::CopyAddress,
+        Self::Preferences,
+        Self::Experiments,
+        Self::Reload,
+        Self::ReloadBypassingCache,
+        Self::Stop,
+        Self::ReaderMode,
+        Self::ResponsiveDesign,
+        Self::CustomizeToolbar,
+        Self::Back,
+        Self::Forward,
+        Self::Home,
+        Self::ShowHistory,
+        Self::ShowDownloads,
+        Self::BookmarkPage,
+        Self::InspectElement,
+        Self::Screenshot,
+        Self::Console,
+        Self::Network,
+        Self::SavedPasswords,
+        Self::KeyboardShortcuts,
+        Self::BrowserInternals,
+        Self::AboutAsterix,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::NewTab => "New Tab",
+            Self::NewPrivateTab => "New Private Tab",
+            Self::OpenLocation => "Open Location",
+            Self::SavePage => "Save Page",
+            Self::ImportData => "Import from Another Browser\u{2026}",
+            Self::Quit => "Quit",
+            Self::CopyAddress => "Copy Page Address",
+            Self::Preferences => "Advanced Preferences",
+            Self::Experiments => "Experiments",
+            Self::Reload => "Reload",
+            Self::ReloadBypassingCache => "Reload Without Cache",
+            Self::Stop => "Stop",
+            Self::ReaderMode => "Reader Mode",
+            Self::ResponsiveDesign => "Responsive Design Mode",
+            Self::CustomizeToolbar => "Customize Toolbar\u{2026}",
+            Self::Back => "Back",
+            Self::Forward => "Forward",
+            Self::Home => "Home",
+            Self::ShowHistory => "Show History",
+            Self::ShowDownloads => "Downloads",
+            Self::BookmarkPage => "Bookmark This Page",
+            Self::InspectElement => "Inspect Element",
+            Self::Screenshot => "Screenshot",
+            Self::Console => "Console",
+            Self::Network => "Network",
+            Self::SavedPasswords => "Saved Passwords",
+            Self::KeyboardShortcuts => "Keyboard Shortcuts",
+            Self::BrowserInternals => "Browser Internals",
+            Self::AboutAsterix => "About ASTERIX",
+        }
+    }
+
+    pub(crate) fn menu(self) -> Menu {
+        match self {
+            Self::NewTab
+            | Self::NewPrivateTab
+            | Self::OpenLocation
+            | Self::SavePage
+            | Self::ImportData
+            | Self::Quit => Menu::File,
+            Self::CopyAddress | Self::Preferences | Self::Experiments => Menu::Edit,
+            Self::Reload
+            | Self::ReloadBypassingCache
+            | Self::Stop
+            | Self::ReaderMode
+            | Self::ResponsiveDesign
+            | Self::CustomizeToolbar => Menu::View,
+            Self::Back | Self::Forward | Self::Home | Self::ShowHistory | Self::ShowDownloads => {
+                Menu::History
+            }
+            Self::BookmarkPage => Menu::Bookmarks,
+            Self::InspectElement
+            | Self::Screenshot
+            | Self::Console
+            | Self::Network
+            | Self::SavedPasswords => Menu::Tools,
+            Self::KeyboardShortcuts | Self::BrowserInternals | Self::AboutAsterix => Menu::Help,
+        }
+    }
+
+    /// Keys that run the action from anywhere in the window.
+    pub(crate) fn shortcut(self) -> Option<KeyboardShortcut> {
+        let command = |key| KeyboardShortcut::new(Modifiers::COMMAND, key);
+        let command_shift = |key| KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, key);
+        let shortcut = match self {
+            Self::NewTab => command(Key::T),
+            Self::NewPrivateTab => command_shift(Key::P),
+            Self::OpenLocation => command(Key::L),
+            Self::SavePage => command(Key::S),
+            Self::Quit => command(Key::Q),
+            Self::Reload => command(Key::R),
+            Self::ReloadBypassingCache => command_shift(Key::R),
+            Self::ReaderMode => KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::ALT, Key::R),
+            Self::ResponsiveDesign => command_shift(Key::M),
+            Self::Back => KeyboardShortcut::new(Modifiers::ALT, Key::ArrowLeft),
+            Self::Forward => KeyboardShortcut::new(Modifiers::ALT, Key::ArrowRight),
+            Self::Home => KeyboardShortcut::new(Modifiers::ALT, Key::Home),
+            Self::ShowHistory => command(Key::H),
+            Self::ShowDownloads => command(Key::J),
+            Self::BookmarkPage => command(Key::D),
+            Self::InspectElement => command_shift(Key::C),
+            Self::Screenshot => command_shift(Key::S),
+            Self::Console => command_shift(Key::K),
+            Self::Network => command_shift(Key::E),
+            Self::KeyboardShortcuts => command(Key::Slash),
+            _ => return None,
+        };
+        Some(shortcut)
+    }
+}
+
+impl ShellApp {
+    /// Whether `action` has something to act on.
+    pub(crate) fn can_run(&self, action: Action) -> bool {
+        let tab = self.active_tab.as_ref();
+        let has_page = tab.is_some_and(|tab| tab.url.is_some());
+        let locked = self.settings_locked();
+        match action {
+            Action::OpenLocation | Action::ReaderMode | Action::ResponsiveDesign => tab.is_some(),
+            Action::SavePage
+            | Action::CopyAddress
+            | Action::Reload
+            | Action::ReloadBypassingCache => has_page,
+            Action::BookmarkPage => tab.is_some_and(|tab| {
+                !tab.private && tab.url.as_ref().is_some_and(|url| url.scheme() != "about")
+            }),
+            Action::Stop => {
+                tab.is_some_and(|tab| self.nav_jobs.iter().any(|job| job.tab() == tab.id))
+            }
+            Action::Back => tab.is_some_and(|tab| self.handle.can_go_back(tab.id)),
+            Action::Forward => tab.is_some_and(|tab| self.handle.can_go_forward(tab.id)),
+            Action::Home => tab.is_some() && self.settings.home_page().is_some(),
+            Action::ImportData | Action::CustomizeToolbar => !locked,
+            _ => true,
+        }
+    }
+
+    pub(crate) fn run_action(&mut self, ctx: &EguiContext, action: Action) {
+        if !self.can_run(action) {
+            return;
+        }
+        let internal_page = match action {
+            Action::Preferences => Some(CONFIG_PAGE),
+            Action::Experiments => Some(FLAGS_PAGE),
+            Action::ShowHistory => Some(HISTORY_PAGE),
+            Action::ShowDownloads => Some(DOWNLOADS_PAGE),
+            Action::SavedPasswords => Some(LOGINS_PAGE),
+            Action::BrowserInternals => Some(ABOUT_PAGE),
+            Action::AboutAsterix => Some("about:version"),
+            _ => None,
+        };
+        if let Some(url) = internal_page.and_then(|page| Url::parse(page).ok()) {
+            self.navigate_active(url);
+            return;
+        }
+        let active = self.active_tab.clone();
+        match action {
+            Action::NewTab => self.open_new_tab(),
+            Action::NewPrivateTab => {
+                let options = TabOptions {
+                    private: true,
+                    ..TabOptions::default()
+                };
+                let tab = self.handle.create_tab_with("Private Tab", options);
+                self.switch_to_tab(tab);
+                self.refresh_tabs();
+            }
+            Action::OpenLocation => self.focus_address_bar = true,
+            Action::SavePage => self.download_active_page(),
+            Action::ImportData => self.open_import_wizard(),
+            Action::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            Action::CopyAddress => {
+                if let Some(url) = active.and_then(|tab| tab.url) {
+                    ctx.output_mut(|output| output.copied_text = url.to_string());
+                    self.status_line = "Page address copied to clipboard".to_owned();
+                }
+            }
+            Action::Reload => {
+                if let Some((tab, url)) = active.and_then(|tab| Some((tab.id, tab.url?))) {
+                    self.navigate_tab(tab, url);
+                }
+            }
+            Action::ReloadBypassingCache => {
+                if let Some(tab) = active {
+                    self.apply_tab_action(&tab, TabAction::ReloadBypassingCache);
+                }
+            }
+            Action::Stop => {
+                if let Some(tab) = active {
+                    self.stop_loading(tab.id);
+                    self.status_line = "Stopped".to_owned();
+                }
+            }
+            Action::ReaderMode => self.reader_mode = !self.reader_mode,
+            Action::ResponsiveDesign => self.toggle_responsive_mode(),
+            Action::CustomizeToolbar => self.customizing_toolbar = true,
+            Action::Back => self.traverse_history(false),
+            Action::Forward => self.traverse_history(true),
+            Action::Home => {
+                if let Some(url) = self.settings.home_page() {
+                    self.navigate_active(url);
+                }
+            }
+            Action::BookmarkPage => self.toggle_bookmark(),
+            Action::InspectElement => self.start_element_picker(),
+            Action::Screenshot => self.start_screenshot(ctx, false),
+            Action::Console => self.open_devtools(DevToolsTab::Console),
+            Action::Network => self.open_devtools(DevToolsTab::Network),
+            Action::KeyboardShortcuts => self.shortcuts_open = true,
+            Action::Preferences
+            | Action::Experiments
+            | Action::ShowHistory
+            | Action::ShowDownloads
+            | Action::SavedPasswords
+            | Action::BrowserInternals
+            | Action::AboutAsterix => {}
+        }
+    }
+
+    /// Runs the actions whose shortcut was pressed this frame. Shortcuts
+    /// with more modifiers are tried first, so Ctrl+Shift+R is not taken
+    /// for Ctrl+R.
+    pub(crate) fn run_shortcuts(&mut self, ctx: &EguiContext) {
+        let mut shortcuts: Vec<(Action, KeyboardShortcut)> = Action::ALL
+            .into_iter()
+            .filter_map(|action| Some((action, action.shortcut()?)))
+            .collect();
+        shortcuts.sort_by_key(|(_, shortcut)| {
+            let modifiers = shortcut.modifiers;
+            std::cmp::Reverse(u8::from(modifiers.shift) + u8::from(modifiers.alt))
+        });
+        let pressed = ctx.input_mut(|input| {
+            shortcuts
+                .iter()
+                .find(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(action, _)| *action)
+        });
+        if let Some(action) = pressed {
+            self.run_action(ctx, action);
+        }
+    }
+
+    /// Menu entry running `action`, with its shortcut alongside.
+    fn action_item(&mut self, ui: &mut egui::Ui, action: Action) {
+        self.action_item_labelled(ui, action, action.label());
+    }
+
+    pub(crate) fn action_item_labelled(&mut self, ui: &mut egui::Ui, action: Action, label: &str) {
+        let mut button = egui::Button::new(label);
+        if let Some(shortcut) = action.shortcut() {
+            button = button.shortcut_text(ui.ctx().format_shortcut(&shortcut));
+        }
+        if ui.add_enabled(self.can_run(action), button).clicked() {
+            self.run_action(&ui.ctx().clone(), action);
+            ui.close_menu();
+        }
+    }
+
+    /// Entries of `menu` that are plain actions.
+    fn action_items(&mut self, ui: &mut egui::Ui, menu: Menu) {
+        for action in Action::ALL {
+            if action.menu() == menu {
+                self.action_item(ui, action);
+            }
+        }
+    }
+
+    /// File, Edit, View, History, Bookmarks, Tools and Help menus above
+    /// the toolbar.
+    pub(crate) fn render_menu_bar(&mut self, ctx: &EguiContext) {
+        TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button(Menu::File.label(), |ui| self.action_items(ui, Menu::File));
+                ui.menu_button(Menu::Edit.label(), |ui| self.action_items(ui, Menu::Edit));
+                ui.menu_button(Menu::View.label(), |ui| self.render_view_menu(ui));
+                ui.menu_button(Menu::History.label(), |ui| self.render_history_menu(ui));
+                self.render_bookmarks_menu(ui);
+                ui.menu_button(Menu::Tools.label(), |ui| self.render_tools_menu(ui));
+                ui.menu_button(Menu::Help.label(), |ui| self.action_items(ui, Menu::Help));
+            });
+        });
+    }
+
+    fn render_view_menu(&mut self, ui: &mut egui::Ui) {
+        self.action_item(ui, Action::Reload);
+        self.action_item(ui, Action::ReloadBypassingCache);
+        self.action_item(ui, Action::Stop);
+        ui.separator();
+        let reader = if self.reader_mode {
+            "Exit Reader Mode"
+        } else {
+            "Reader Mode"
+        };
+        self.action_item_labelled(ui, Action::ReaderMode, reader);
+        let responsive = if self.emulated_viewport().is_some() {
+            "Exit Responsive Design Mode"
+        } else {
+            "Responsive Design Mode"
+        };
+        self.action_item_labelled(ui, Action::ResponsiveDesign, responsive);
+        self.render_rendering_modes(ui);
+        ui.separator();
+        self.action_item(ui, Action::CustomizeToolbar);
+        if ui
+            .checkbox(&mut self.settings.show_status_bar, "Status Bar")
+            .changed()
+        {
+            self.save_settings();
+            ui.close_menu();
+        }
+        ui.separator();
+        let locked = self.settings_locked();
+        if ui
+            .add_enabled(
+                !locked,
+                egui::Checkbox::new(
+                    &mut self.settings.reading_time_on_articles,
+                    "Reading time on articles",
+                ),
+            )
+            .changed()
+        {
+            self.save_settings();
+        }
+        self.render_cookie_banner_settings(ui, locked);
+        if ui
+            .add_enabled(
+                !locked,
+                egui::Checkbox::new(&mut self.settings.tracker_protection, "Tracker protection"),
+            )
+            .on_hover_text(
+                "Removes tracking parameters from links, asks sites not to track \
+                 you and keeps cookies from other sites out of pages",
+            )
+            .changed()
+        {
+            self.save_settings();
+            self.handle.set_tracker_protection(self.settings.privacy());
+        }
+        self.render_throwaway_identity_settings(ui, locked);
+        ui.separator();
+        self.render_encoding_menu(ui);
+    }
+
+    fn render_history_menu(&mut self, ui: &mut egui::Ui) {
+        self.action_items(ui, Menu::History);
+        let history = self.handle.history();
+        if history.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.weak("Recently Visited");
+        let mut open = None;
+        for entry in history.into_iter().take(RECENT_HISTORY) {
+            let title = if entry.title.is_empty() {
+                entry.url.to_string()
+            } else {
+                entry.title
+            };
+            if ui.button(title).on_hover_text(entry.url.as_str()).clicked() {
+                open = Some(entry.url);
+            }
+        }
+        if let Some(url) = open {
+            self.navigate_active(url);
+            ui.close_menu();
+        }
+    }
+
+    fn render_tools_menu(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Check Links").clicked() {
+            self.start_link_check();
+            ui.close_menu();
+        }
+        self.action_item(ui, Action::InspectElement);
+        self.action_item(ui, Action::Screenshot);
+        if ui.button("Screenshot Region").clicked() {
+            self.start_screenshot(ui.ctx(), true);
+            ui.close_menu();
+        }
+        if ui.button("Eyedropper").clicked() {
+            self.open_devtools(DevToolsTab::Eyedropper);
+            self.devtools.eyedropper.picking = true;
+            ui.close_menu();
+        }
+        if ui.button("Accessibility Audit").clicked() {
+            self.run_accessibility_audit();
+            ui.close_menu();
+        }
+        if ui.button("SEO & Metadata").clicked() {
+            self.open_devtools(DevToolsTab::Seo);
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button("Open robots.txt").clicked() {
+            self.open_site_file("robots.txt");
+            ui.close_menu();
+        }
+        if ui.button("Open sitemap.xml").clicked() {
+            self.open_site_file("sitemap.xml");
+            ui.close_menu();
+        }
+        if ui.button("Crawl Site\u{2026}").clicked() {
+            self.open_crawl_wizard();
+            ui.close_menu();
+        }
+        if ui.button("WebSocket Console").clicked() {
+            self.websocket.open = true;
+            ui.close_menu();
+        }
+        if ui.button("Background Jobs").clicked() {
+            if let Ok(url) = Url::parse(JOBS_PAGE) {
+                self.navigate_active(url);
+            }
+            ui.close_menu();
+        }
+        self.action_item(ui, Action::SavedPasswords);
+        let locked = self.settings_locked();
+        if ui
+            .add_enabled(!locked, egui::Button::new("Sync\u{2026}"))
+            .clicked()
+        {
+            self.sync.open = true;
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(!locked, egui::Button::new("Backup & Restore\u{2026}"))
+            .clicked()
+        {
+            self.open_backup_window();
+            ui.close_menu();
+        }
+        let mut offline = self.handle.is_offline();
+        if ui
+            .checkbox(&mut offline, "Work Offline")
+            .on_hover_text("Shows pages from the cache without going online")
+            .changed()
+        {
+            self.handle.set_offline(offline);
+            self.status_line = if offline {
+                "Working offline: pages come from the cache".to_owned()
+            } else {
+                "Back online".to_owned()
+            };
+        }
+        if ui
+            .add_enabled(!locked, egui::Button::new("Home & Startup\u{2026}"))
+            .clicked()
+        {
+            self.open_startup_window();
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(!locked, egui::Button::new("Proxy\u{2026}"))
+            .clicked()
+        {
+            self.open_proxy_window();
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(!locked, egui::Button::new("Client Certificates\u{2026}"))
+            .clicked()
+        {
+            self.client_certs.open = true;
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(!locked, egui::Button::new("IPFS\u{2026}"))
+            .clicked()
+        {
+            self.open_ipfs_window();
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(!locked, egui::Button::new("File Types\u{2026}"))
+            .clicked()
+        {
+            self.file_types.open = true;
+            ui.close_menu();
+        }
+        if ui.button("Restricted Mode\u{2026}").clicked() {
+            self.parental.open = true;
+            ui.close_menu();
+        }
+        ui.separator();
+        self.action_item(ui, Action::Console);
+        self.action_item(ui, Action::Network);
+        if ui.button("Request Composer").clicked() {
+            self.open_devtools(DevToolsTab::Composer);
+            ui.close_menu();
+        }
+        if ui.button("Performance").clicked() {
+            self.open_devtools(DevToolsTab::Performance);
+            ui.close_menu();
+        }
+    }
+
+    /// Window listing every action that has a shortcut, by menu.
+    pub(crate) fn render_shortcuts_window(&mut self, ctx: &EguiContext) {
+        if !self.shortcuts_open {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Keyboard Shortcuts")
+            .open(&mut open)
+            .default_size([360.0, 420.0])
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("keyboard_shortcuts")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for menu in Menu::ALL {
+                                let mut actions = Action::ALL
+                                    .into_iter()
+                                    .filter(|action| action.menu() == menu)
+                                    .filter_map(|action| Some((action, action.shortcut()?)))
+                                    .peekable();
+                                if actions.peek().is_none() {
+                                    continue;
+                                }
+                                ui.strong(menu.label());
+                                ui.end_row();
+                                for (action, shortcut) in actions {
+                                    ui.label(action.label());
+                                    ui.monospace(ctx.format_shortcut(&shortcut));
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                });
+            });
+        self.shortcuts_open &= open;
+    }
+}
@@ -0,0 +1,429 @@
+use std::time::Duration;
+
+use asterix_browser::{
+    BlockRule, ComposedRequest, HostConnections, NetworkConditions, NetworkEntry, PageResources,
+    ResourceKind, Timings,
+};
+use eframe::egui;
+use egui::{Color32, RichText};
+use url::Url;
+
+use crate::composer::format_size;
+
+/// Selection and filter state of the network panel.
+#[derive(Default)]
+pub(crate) struct NetworkPanel {
+    filter: String,
+    selected: Option<u64>,
+}
+
+impl NetworkPanel {
+    /// Renders the captured requests and the active block rules. Returns the
+    /// follow-up the user asked for, if any.
+    pub(crate) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        entries: &[NetworkEntry],
+        block_rules: &[BlockRule],
+        connections: &[HostConnections],
+        conditions: NetworkConditions,
+        resources: Option<&PageResources>,
+    ) -> NetworkPanelAction {
+        let mut action = NetworkPanelAction::None;
+        if entries.iter().any(NetworkEntry::is_pending) {
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("URL contains"));
+            ui.label(format!("{} requests", entries.len()));
+            if ui.button("Clear").clicked() {
+                self.selected = None;
+                action = NetworkPanelAction::Clear;
+            }
+            let mut block_images = block_rules.contains(&BlockRule::Images);
+            if ui.checkbox(&mut block_images, "Block images").changed() {
+                action = if block_images {
+                    NetworkPanelAction::AddBlock(BlockRule::Images)
+                } else {
+                    NetworkPanelAction::RemoveBlock(BlockRule::Images)
+                };
+            }
+            if let Some(chosen) = throttle_menu(ui, conditions) {
+                action = NetworkPanelAction::SetConditions(chosen);
+            }
+        });
+        if let Some(rule_action) = block_rule_list(ui, block_rules) {
+            action = rule_action;
+        }
+        connection_summary(ui, connections);
+        if let Some(resources) = resources {
+            resource_summary(ui, resources);
+        }
+        ui.separator();
+
+        let filter = self.filter.trim().to_lowercase();
+        let visible: Vec<&NetworkEntry> = entries
+            .iter()
+            .filter(|entry| {
+                filter.is_empty() || entry.url.as_str().to_lowercase().contains(&filter)
+            })
+            .collect();
+
+        let selected = self
+            .selected
+            .and_then(|id| entries.iter().find(|entry| entry.id == id));
+        let list_height = if selected.is_some() {
+            ui.available_height() * 0.45
+        } else {
+            ui.available_height()
+        };
+
+        egui::ScrollArea::vertical()
+            .id_source("network_entries")
+            .max_height(list_height)
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                egui::Grid::new("network_grid")
+                    .num_columns(7)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Status");
+                        ui.strong("Method");
+                        ui.strong("URL");
+                        ui.strong("Type");
+                        ui.strong("Connection");
+                        ui.strong("Size");
+                        ui.strong("Time");
+                        ui.end_row();
+
+                        for entry in visible {
+                            let (status, color) = status_label(entry);
+                            ui.label(RichText::new(status).color(color).monospace());
+                            ui.monospace(&entry.method);
+                            let is_selected = self.selected == Some(entry.id);
+                            if ui
+                                .selectable_label(is_selected, truncate(entry.url.as_str(), 80))
+                                .on_hover_text(entry.url.as_str())
+                                .clicked()
+                            {
+                                self.selected = (!is_selected).then_some(entry.id);
+                            }
+                            ui.label(entry.kind.label());
+                            match &entry.connection {
+                                Some(connection) => {
+                                    let reuse = if connection.reused { "reused" } else { "new" };
+                                    ui.label(format!("{} \u{b7} {reuse}", connection.protocol))
+                                        .on_hover_text(connection.remote_addr.to_string());
+                                }
+                                None => {
+                                    ui.weak("\u{2014}");
+                                }
+                            }
+                            match entry.encoded_size {
+                                Some(encoded) => {
+                                    ui.label(format_size(entry.size)).on_hover_text(format!(
+                                        "{} transferred compressed",
+                                        format_size(encoded)
+                                    ));
+                                }
+                                None => {
+                                    ui.label(format_size(entry.size));
+                                }
+                            }
+                            ui.label(
+                                entry
+                                    .duration_ms
+                                    .map(|ms| format!("{ms} ms"))
+                                    .unwrap_or_else(|| "...".to_owned()),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(entry) = selected {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.strong(format!("{} {}", entry.method, entry.url));
+                if ui.button("Edit and Resend").clicked() {
+                    action = NetworkPanelAction::Resend(Box::new(entry.to_composed()));
+                }
+                if ui.button("Override").clicked() {
+                    action = NetworkPanelAction::Override(entry.url.clone());
+                }
+                if ui.button("Block URL").clicked() {
+                    action = NetworkPanelAction::AddBlock(BlockRule::Url(entry.url.clone()));
+                }
+                if let Some(host) = entry.url.host_str() {
+                    if ui.button("Block domain").clicked() {
+                        action = NetworkPanelAction::AddBlock(BlockRule::Domain(host.to_owned()));
+                    }
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_source("network_details")
+                .auto_shrink([false, false])
+                .show(ui, |ui| entry_details(ui, entry));
+        }
+
+        action
+    }
+}
+
+/// Follow-up requested from the network panel.
+pub(crate) enum NetworkPanelAction {
+    None,
+    Clear,
+    Resend(Box<ComposedRequest>),
+    AddBlock(BlockRule),
+    RemoveBlock(BlockRule),
+    Override(Url),
+    SetConditions(NetworkConditions),
+}
+
+/// Picker for the emulated network, returning the preset chosen.
+fn throttle_menu(ui: &mut egui::Ui, conditions: NetworkConditions) -> Option<NetworkConditions> {
+    let current = NetworkConditions::PRESETS
+        .iter()
+        .find(|(_, preset)| *preset == conditions)
+        .map_or("Custom", |(name, _)| name);
+    let mut chosen = None;
+    egui::ComboBox::from_id_source("network_throttle")
+        .selected_text(current)
+        .show_ui(ui, |ui| {
+            for (name, preset) in NetworkConditions::PRESETS {
+                if ui.selectable_label(preset == conditions, name).clicked() {
+                    chosen = Some(preset);
+                }
+            }
+        });
+    chosen
+}
+
+/// Lists the active block rules with a button to lift each one.
+fn block_rule_list(ui: &mut egui::Ui, block_rules: &[BlockRule]) -> Option<NetworkPanelAction> {
+    if block_rules.is_empty() {
+        return None;
+    }
+    let mut action = None;
+    egui::CollapsingHeader::new(format!("Blocked requests ({})", block_rules.len()))
+        .id_source("network_block_rules")
+        .show(ui, |ui| {
+            for rule in block_rules {
+                ui.horizontal(|ui| {
+                    if ui.small_button("Unblock").clicked() {
+                        action = Some(NetworkPanelAction::RemoveBlock(rule.clone()));
+                    }
+                    ui.label(truncate(&rule.describe(), 90));
+                });
+            }
+        });
+    action
+}
+
+/// Connections opened and reused per host, to tell whether keep-alive and
+/// HTTP/2 multiplexing are doing their job.
+/// Subresources fetched for the page in the tab, by kind, with those that
+/// failed listed under the heading.
+fn resource_summary(ui: &mut egui::Ui, resources: &PageResources) {
+    if resources.resources.is_empty() {
+        return;
+    }
+    let counts: Vec<String> = ResourceKind::ALL
+        .iter()
+        .map(|kind| (kind, resources.of_kind(*kind).count()))
+        .filter(|(_, count)| *count > 0)
+        .map(|(kind, count)| format!("{count} {}", kind.label()))
+        .collect();
+    let failed = resources.failed();
+    let mut heading = format!(
+        "Page resources ({}, {})",
+        counts.join(", "),
+        format_size(resources.total_size())
+    );
+    if failed > 0 {
+        heading.push_str(&format!(", {failed} failed"));
+    }
+    egui::CollapsingHeader::new(heading)
+        .id_source("network_page_resources")
+        .show(ui, |ui| {
+            egui::Grid::new("network_page_resource_grid")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    for resource in &resources.resources {
+                        match (&resource.error, resource.status) {
+                            (Some(err), _) => {
+                                ui.colored_label(ui.visuals().error_fg_color, "failed")
+                                    .on_hover_text(err);
+                            }
+                            (None, Some(status)) if resource.is_loaded() => {
+                                ui.monospace(status.to_string());
+                            }
+                            (None, status) => {
+                                let status = status.map_or_else(String::new, |s| s.to_string());
+                                ui.colored_label(ui.visuals().warn_fg_color, status);
+                            }
+                        }
+                        ui.label(resource.kind.label());
+                        ui.label(truncate(resource.url.as_str(), 80))
+                            .on_hover_text(resource.url.as_str());
+                        ui.label(format_size(resource.body.len()));
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn connection_summary(ui: &mut egui::Ui, connections: &[HostConnections]) {
+    if connections.is_empty() {
+        return;
+    }
+    let opened: u64 = connections.iter().map(|host| host.opened).sum();
+    let requests: u64 = connections.iter().map(|host| host.requests).sum();
+    egui::CollapsingHeader::new(format!(
+        "Connections ({opened} opened for {requests} requests)"
+    ))
+    .id_source("network_connections")
+    .show(ui, |ui| {
+        egui::Grid::new("network_connection_grid")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Host");
+                ui.strong("Opened");
+                ui.strong("Open now");
+                ui.strong("Requests");
+                ui.strong("Reused");
+                ui.strong("Protocols");
+                ui.end_row();
+                for host in connections {
+                    ui.monospace(&host.host);
+                    ui.label(host.opened.to_string());
+                    ui.label(host.open.to_string());
+                    ui.label(host.requests.to_string());
+                    ui.label(format!(
+                        "{} ({:.0}%)",
+                        host.reused,
+                        host.reuse_ratio() * 100.0
+                    ));
+                    ui.label(host.protocols.join(", "));
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+fn entry_details(ui: &mut egui::Ui, entry: &NetworkEntry) {
+    ui.label(format!(
+        "Started {} | {}",
+        entry.started_at.format("%H:%M:%S%.3f"),
+        entry.kind.label()
+    ));
+    if let Some(error) = &entry.error {
+        ui.colored_label(Color32::from_rgb(220, 80, 80), error);
+    }
+    if let Some(connection) = &entry.connection {
+        ui.label(format!(
+            "{} to {} on a {} connection",
+            connection.protocol,
+            connection.remote_addr,
+            if connection.reused { "reused" } else { "new" }
+        ));
+    }
+    if let Some(timings) = &entry.timings {
+        timing_section(ui, timings);
+    }
+    if !entry.redirects.is_empty() {
+        egui::CollapsingHeader::new(format!("Redirects ({})", entry.redirects.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for hop in &entry.redirects {
+                    ui.monospace(format!(
+                        "{} {} \u{2192} {} ({} ms)",
+                        hop.status, hop.url, hop.location, hop.duration_ms
+                    ));
+                }
+            });
+    }
+    header_section(ui, "Request headers", &entry.request_headers);
+    header_section(ui, "Response headers", &entry.response_headers);
+    if !entry.request_body.is_empty() {
+        egui::CollapsingHeader::new("Request body").show(ui, |ui| {
+            ui.monospace(&entry.request_body);
+        });
+    }
+}
+
+fn timing_section(ui: &mut egui::Ui, timings: &Timings) {
+    egui::CollapsingHeader::new("Timing")
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("network_timing")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    let phases = [
+                        ("Queued", Some(timings.queued_ms).filter(|&ms| ms > 0)),
+                        ("Redirects", Some(timings.redirect_ms).filter(|&ms| ms > 0)),
+                        ("DNS lookup", timings.dns_ms),
+                        ("Connecting", timings.connect_ms),
+                        ("TLS handshake", timings.tls_ms),
+                        ("Waiting (TTFB)", Some(timings.ttfb_ms)),
+                        ("Content download", Some(timings.download_ms)),
+                    ];
+                    for (name, ms) in phases {
+                        ui.label(name);
+                        match ms {
+                            Some(ms) => ui.monospace(format!("{ms} ms")),
+                            None => ui.weak("\u{2014}"),
+                        };
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn header_section(ui: &mut egui::Ui, title: &str, headers: &[(String, String)]) {
+    egui::CollapsingHeader::new(format!("{title} ({})", headers.len()))
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new(title)
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, value) in headers {
+                        ui.monospace(name);
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn status_label(entry: &NetworkEntry) -> (String, Color32) {
+    match (entry.status, &entry.error) {
+        (Some(status), _) => {
+            let color = match status {
+                200..=299 => Color32::from_rgb(90, 180, 110),
+                300..=399 => Color32::from_rgb(220, 170, 60),
+                _ => Color32::from_rgb(220, 80, 80),
+            };
+            (status.to_string(), color)
+        }
+        (None, Some(_)) => ("failed".to_owned(), Color32::from_rgb(220, 80, 80)),
+        (None, None) => ("pending".to_owned(), Color32::GRAY),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_owned();
+    }
+    let mut truncated: String = text.chars().take(max - 1).collect();
+    truncated.push('\u{2026}');
+    truncated
+}
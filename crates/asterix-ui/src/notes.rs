@@ -0,0 +1,180 @@
+use eframe::egui;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, Context as EguiContext, TextStyle};
+use tracing::warn;
+
+use crate::{profile_dir, ShellApp};
+
+const HIGHLIGHT_COLOR: Color32 = Color32::from_rgba_premultiplied(120, 100, 0, 120);
+
+impl ShellApp {
+    /// Side panel for the free-form note and highlights of the active page.
+    pub(crate) fn render_notes_panel(&mut self, ctx: &EguiContext) {
+        if !self.show_notes {
+            return;
+        }
+
+        let active = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| Some((tab.url.clone()?, tab.title.clone())));
+
+        egui::SidePanel::left("notes")
+            .resizable(true)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                ui.heading("Notes");
+                let Some((url, title)) = active else {
+                    ui.label("Load a page to attach notes to it.");
+                    return;
+                };
+
+                if self.note_url.as_ref() != Some(&url) {
+                    self.note_draft = self
+                        .handle
+                        .annotations_for(&url)
+                        .map(|annotations| annotations.note)
+                        .unwrap_or_default();
+                    self.note_url = Some(url.clone());
+                }
+
+                let edit = ui.add(
+                    egui::TextEdit::multiline(&mut self.note_draft)
+                        .hint_text("Write a note about this page")
+                        .desired_rows(6)
+                        .desired_width(f32::INFINITY),
+                );
+                if edit.changed() {
+                    self.handle
+                        .set_page_note(&url, &title, self.note_draft.clone());
+                }
+                if edit.lost_focus() {
+                    self.save_annotations();
+                }
+
+                ui.separator();
+                ui.heading("Highlights");
+                let selection = self.preview_selection.clone();
+                if ui
+                    .add_enabled(
+                        selection.is_some(),
+                        egui::Button::new("Highlight selection"),
+                    )
+                    .on_disabled_hover_text("Select text in the page preview first")
+                    .clicked()
+                {
+                    if let Some(text) = selection {
+                        self.handle.add_highlight(&url, &title, text);
+                        self.save_annotations();
+                    }
+                }
+
+                let highlights = self
+                    .handle
+                    .annotations_for(&url)
+                    .map(|annotations| annotations.highlights)
+                    .unwrap_or_default();
+                let mut removed = None;
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for (index, highlight) in highlights.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("✕").on_hover_text("Remove").clicked() {
+                                    removed = Some(index);
+                                }
+                                ui.label(excerpt(&highlight.text));
+                            });
+                        }
+                    });
+                if let Some(index) = removed {
+                    self.handle.remove_highlight(&url, index);
+                    self.save_annotations();
+                }
+
+                ui.separator();
+                if ui.button("Export all to Markdown").clicked() {
+                    self.export_annotations(ctx);
+                }
+
+                ui.separator();
+                self.render_clipper(ui, ctx);
+            });
+    }
+
+    pub(crate) fn save_annotations(&self) {
+        if let Some(path) = &self.annotations_path {
+            if let Err(err) = self.handle.save_annotations(path) {
+                warn!("failed to save annotations: {err:#}");
+            }
+        }
+    }
+
+    /// Copies every annotation to the clipboard and writes it next to the profile.
+    fn export_annotations(&mut self, ctx: &EguiContext) {
+        let markdown = self.handle.annotations_markdown();
+        ctx.output_mut(|output| output.copied_text = markdown.clone());
+
+        let Some(path) = profile_dir().map(|dir| dir.join("annotations.md")) else {
+            self.status_line = "Annotations copied to clipboard".to_owned();
+            return;
+        };
+        self.status_line = match std::fs::write(&path, markdown) {
+            Ok(()) => format!("Annotations exported to {}", path.display()),
+            Err(err) => format!("Failed to export annotations: {err}"),
+        };
+    }
+}
+
+/// Lays out page text in monospace, marking every occurrence of a highlight.
+pub(crate) fn highlighted_layout(
+    ui: &egui::Ui,
+    text: &str,
+    highlights: &[String],
+    wrap_width: f32,
+) -> LayoutJob {
+    let font_id = TextStyle::Monospace.resolve(ui.style());
+    let color = ui.visuals().text_color();
+    let format = |background| TextFormat {
+        font_id: font_id.clone(),
+        color,
+        background,
+        ..Default::default()
+    };
+
+    let mut ranges: Vec<(usize, usize)> = highlights
+        .iter()
+        .filter(|highlight| !highlight.is_empty())
+        .flat_map(|highlight| {
+            text.match_indices(highlight.as_str())
+                .map(|(start, matched)| (start, start + matched.len()))
+        })
+        .collect();
+    ranges.sort_unstable();
+
+    let mut job = LayoutJob::default();
+    job.wrap.max_width = wrap_width;
+    let mut cursor = 0;
+    for (start, end) in ranges {
+        let start = start.max(cursor);
+        if start >= end {
+            continue;
+        }
+        job.append(&text[cursor..start], 0.0, format(Color32::TRANSPARENT));
+        job.append(&text[start..end], 0.0, format(HIGHLIGHT_COLOR));
+        cursor = end;
+    }
+    job.append(&text[cursor..], 0.0, format(Color32::TRANSPARENT));
+    job
+}
+
+fn excerpt(text: &str) -> String {
+    const MAX_EXCERPT: usize = 160;
+    let flattened = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flattened.chars().count() > MAX_EXCERPT {
+        let cut = flattened.chars().take(MAX_EXCERPT).collect::<String>();
+        format!("{cut}…")
+    } else {
+        flattened
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use asterix_browser::{BrowserHandle, ResponseOverride};
+use eframe::egui;
+use egui::Color32;
+use url::Url;
+
+/// Form state of the local overrides tab.
+#[derive(Default)]
+pub(crate) struct OverridesPanel {
+    pattern: String,
+    path: String,
+    error: Option<String>,
+}
+
+impl OverridesPanel {
+    /// Prefills the form with `url` so a captured request can be mapped quickly.
+    pub(crate) fn prefill(&mut self, url: &Url) {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        self.pattern = url.to_string();
+        self.error = None;
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, handle: &BrowserHandle) {
+        ui.label("Requests whose URL matches a pattern are answered from a local file. Use * as a wildcard.");
+        ui.add_space(4.0);
+        egui::Grid::new("override_form")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("URL pattern");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.pattern)
+                        .hint_text("https://example.com/static/*.css")
+                        .desired_width(f32::INFINITY),
+                );
+                ui.end_row();
+                ui.label("Local file");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.path)
+                        .hint_text("/home/me/site/style.css")
+                        .desired_width(f32::INFINITY),
+                );
+                ui.end_row();
+            });
+        ui.horizontal(|ui| {
+            let ready = !self.pattern.trim().is_empty() && !self.path.trim().is_empty();
+            if ui
+                .add_enabled(ready, egui::Button::new("Add Override"))
+                .clicked()
+            {
+                self.add(handle);
+            }
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::from_rgb(220, 80, 80), error);
+            }
+        });
+        ui.separator();
+
+        let overrides = handle.response_overrides();
+        if overrides.is_empty() {
+            ui.weak("No overrides are active.");
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .id_source("override_list")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("override_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for rule in &overrides {
+                            ui.monospace(&rule.pattern);
+                            ui.monospace(rule.path.display().to_string());
+                            if ui.small_button("Remove").clicked() {
+                                handle.remove_response_override(&rule.pattern);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn add(&mut self, handle: &BrowserHandle) {
+        let path = PathBuf::from(self.path.trim());
+        if !path.is_file() {
+            self.error = Some(format!("{} is not a readable file", path.display()));
+            return;
+        }
+        handle.add_response_override(ResponseOverride {
+            pattern: self.pattern.trim().to_owned(),
+            path,
+        });
+        self.pattern.clear();
+        self.path.clear();
+        self.error = None;
+    }
+}
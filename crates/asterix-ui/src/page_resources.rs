@@ -0,0 +1,77 @@
+use asterix_browser::{BrowserError, PageResponse, SubresourcesJob, TabId};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::ShellApp;
+
+/// Subresource fetch started for the page a tab shows.
+pub(crate) struct ResourceFetch {
+    tab: TabId,
+    page: Url,
+    job: SubresourcesJob,
+}
+
+impl ShellApp {
+    /// Fetches the images, stylesheets and scripts of an HTML `page` just
+    /// shown in `tab`, replacing a fetch for the page it showed before.
+    pub(crate) fn fetch_page_resources(&mut self, tab: TabId, page: &PageResponse) {
+        self.resource_fetches.retain(|fetch| fetch.tab != tab);
+        let is_html = page
+            .mime_type
+            .as_deref()
+            .is_none_or(|mime| mime.starts_with("text/html"));
+        if !is_html {
+            return;
+        }
+        match self.handle.fetch_subresources(tab, page) {
+            Ok(job) => self.resource_fetches.push(ResourceFetch {
+                tab,
+                page: page.url.clone(),
+                job,
+            }),
+            Err(err) => warn!("failed to fetch page resources: {err:#}"),
+        }
+    }
+
+    /// Whether the subresources of the page `tab` shows are still loading.
+    pub(crate) fn fetching_resources(&self, tab: TabId) -> bool {
+        self.resource_fetches.iter().any(|fetch| fetch.tab == tab)
+    }
+
+    /// Hands finished fetches to the pages they were made for, which are
+    /// rendered again with their linked stylesheets.
+    pub(crate) fn poll_page_resources(&mut self) {
+        let mut pending = Vec::with_capacity(self.resource_fetches.len());
+        for mut fetch in self.resource_fetches.drain(..) {
+            let resources = match fetch.job.try_complete() {
+                None => {
+                    pending.push(fetch);
+                    continue;
+                }
+                Some(Ok(resources)) => resources,
+                Some(Err(BrowserError::Cancelled)) => continue,
+                Some(Err(err)) => {
+                    warn!("page resources of {} failed: {err}", fetch.page);
+                    continue;
+                }
+            };
+            let Some(view) = self
+                .pages
+                .get_mut(&fetch.tab)
+                .filter(|view| view.url == fetch.page)
+            else {
+                debug!("{} was left before its resources arrived", fetch.page);
+                continue;
+            };
+            debug!(
+                "{} resources of {}, {} failed",
+                resources.resources.len(),
+                fetch.page,
+                resources.failed()
+            );
+            view.resources = Some(resources);
+            view.rendered = None;
+        }
+        self.resource_fetches = pending;
+    }
+}
@@ -0,0 +1,239 @@
+use std::path::PathBuf;
+
+use asterix_browser::{load_parental_controls, save_parental_controls, ParentalControls};
+use eframe::egui;
+use egui::Context as EguiContext;
+use tracing::warn;
+use url::Url;
+
+use crate::{profile_dir, ShellApp};
+
+/// Restricted mode settings and the window managing them.
+pub(crate) struct ParentalPanel {
+    controls: ParentalControls,
+    path: Option<PathBuf>,
+    pub(crate) open: bool,
+    /// The administrator unlocked the settings for this session.
+    unlocked: bool,
+    passphrase_input: String,
+    new_passphrase: String,
+    confirm: String,
+    domain_input: String,
+    message: Option<Result<String, String>>,
+}
+
+impl ParentalPanel {
+    /// Loads the restricted mode settings from the profile.
+    pub(crate) fn load() -> Self {
+        let path = profile_dir().map(|dir| dir.join("parental.json"));
+        let controls = match path.as_deref().map(load_parental_controls) {
+            Some(Ok(controls)) => controls,
+            Some(Err(err)) => {
+                warn!("failed to load restricted mode settings: {err:#}");
+                ParentalControls::default()
+            }
+            None => ParentalControls::default(),
+        };
+        Self {
+            controls,
+            path,
+            open: false,
+            unlocked: false,
+            passphrase_input: String::new(),
+            new_passphrase: String::new(),
+            confirm: String::new(),
+            domain_input: String::new(),
+            message: None,
+        }
+    }
+
+    pub(crate) fn controls(&self) -> &ParentalControls {
+        &self.controls
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = save_parental_controls(path, &self.controls) {
+                warn!("failed to save restricted mode settings: {err:#}");
+            }
+        }
+    }
+}
+
+/// Host typed into the allowlist form, accepting full URLs as well.
+fn domain_from_input(input: &str) -> Option<String> {
+    let input = input.trim();
+    let host = match Url::parse(input) {
+        Ok(url) => url.host_str()?.to_owned(),
+        Err(_) => input.split('/').next()?.to_owned(),
+    };
+    let host = host.trim_start_matches("*.").to_ascii_lowercase();
+    (!host.is_empty() && !host.contains(char::is_whitespace)).then_some(host)
+}
+
+impl ShellApp {
+    /// Whether restricted mode is on and settings have not been unlocked.
+    pub(crate) fn settings_locked(&self) -> bool {
+        self.parental.controls.enabled && !self.parental.unlocked
+    }
+
+    pub(crate) fn render_parental_window(&mut self, ctx: &EguiContext) {
+        if !self.parental.open {
+            return;
+        }
+        let locked = self.settings_locked();
+        let mut open = true;
+        let mut changed = false;
+        let panel = &mut self.parental;
+        egui::Window::new("Restricted Mode")
+            .open(&mut open)
+            .default_size([420.0, 400.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "In restricted mode only the sites on the allowlist can be visited, \
+                     and changing settings needs the administrator passphrase.",
+                );
+                ui.separator();
+
+                if locked {
+                    ui.label("Enter the administrator passphrase to change settings.");
+                    let mut unlock = false;
+                    ui.horizontal(|ui| {
+                        let edit = ui.add(
+                            egui::TextEdit::singleline(&mut panel.passphrase_input).password(true),
+                        );
+                        unlock = edit.lost_focus()
+                            && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                        unlock |= ui.button("Unlock").clicked();
+                    });
+                    if unlock {
+                        if panel.controls.verify(&panel.passphrase_input) {
+                            panel.unlocked = true;
+                            panel.message = None;
+                        } else {
+                            panel.message = Some(Err("Wrong passphrase".to_owned()));
+                        }
+                        panel.passphrase_input.clear();
+                    }
+                } else {
+                    let has_passphrase = panel.controls.has_passphrase();
+                    changed |= ui
+                        .add_enabled(
+                            has_passphrase,
+                            egui::Checkbox::new(
+                                &mut panel.controls.enabled,
+                                "Only allow the sites below",
+                            ),
+                        )
+                        .on_disabled_hover_text("Set an administrator passphrase first")
+                        .changed();
+
+                    ui.strong("Allowed sites");
+                    ui.weak("Each site is allowed together with its subdomains.");
+                    let mut remove = None;
+                    egui::ScrollArea::vertical()
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for (index, domain) in
+                                panel.controls.allowlist.domains.iter().enumerate()
+                            {
+                                ui.horizontal(|ui| {
+                                    ui.label(domain);
+                                    if ui.small_button("Remove").clicked() {
+                                        remove = Some(index);
+                                    }
+                                });
+                            }
+                        });
+                    if let Some(index) = remove {
+                        panel.controls.allowlist.domains.remove(index);
+                        changed = true;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.domain_input)
+                                .hint_text("example.org"),
+                        );
+                        if ui.button("Allow").clicked() {
+                            match domain_from_input(&panel.domain_input) {
+                                Some(domain) => {
+                                    let domains = &mut panel.controls.allowlist.domains;
+                                    if !domains.contains(&domain) {
+                                        domains.push(domain);
+                                        domains.sort();
+                                        changed = true;
+                                    }
+                                    panel.domain_input.clear();
+                                }
+                                None => panel.message = Some(Err("Enter a site name".to_owned())),
+                            }
+                        }
+                    });
+                    ui.separator();
+
+                    ui.strong(if has_passphrase {
+                        "Change passphrase"
+                    } else {
+                        "Administrator passphrase"
+                    });
+                    egui::Grid::new("parental_passphrase")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Passphrase");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut panel.new_passphrase)
+                                    .password(true),
+                            );
+                            ui.end_row();
+                            ui.label("Repeat");
+                            ui.add(egui::TextEdit::singleline(&mut panel.confirm).password(true));
+                            ui.end_row();
+                        });
+                    let ready =
+                        !panel.new_passphrase.is_empty() && panel.new_passphrase == panel.confirm;
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(ready, egui::Button::new("Set Passphrase"))
+                            .clicked()
+                        {
+                            panel.controls.set_passphrase(&panel.new_passphrase);
+                            panel.new_passphrase.clear();
+                            panel.confirm.clear();
+                            panel.message = Some(Ok("Passphrase saved".to_owned()));
+                            changed = true;
+                        }
+                        if panel.controls.enabled && ui.button("Lock Settings").clicked() {
+                            panel.unlocked = false;
+                        }
+                    });
+                }
+
+                if let Some(message) = &panel.message {
+                    match message {
+                        Ok(message) => ui.label(message),
+                        Err(err) => ui.colored_label(ui.visuals().error_fg_color, err),
+                    };
+                }
+            });
+        if !open {
+            // Leaving the window locks the settings again.
+            panel.unlocked = false;
+            panel.message = None;
+        }
+        panel.open = open;
+
+        if changed {
+            self.parental.save();
+            self.handle
+                .apply_parental_controls(self.parental.controls());
+            self.status_line = if self.parental.controls.enabled {
+                format!(
+                    "Restricted mode on: {} allowed sites",
+                    self.parental.controls.allowlist.domains.len()
+                )
+            } else {
+                "Restricted mode off".to_owned()
+            };
+        }
+    }
+}
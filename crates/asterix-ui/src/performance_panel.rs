@@ -0,0 +1,409 @@
+use asterix_browser::{NetworkEntry, RequestKind, TabId};
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use egui::{Align2, Color32, Context as EguiContext, FontId, Pos2, Rect, Sense, Stroke, Vec2};
+use serde_json::json;
+use url::Url;
+
+use crate::{profile_dir, PageView, ShellApp};
+
+const LABEL_WIDTH: f32 = 260.0;
+const ROW_HEIGHT: f32 = 18.0;
+const AXIS_HEIGHT: f32 = 22.0;
+const MIN_ZOOM: f32 = 1.0;
+const MAX_ZOOM: f32 = 50.0;
+
+const WAITING_COLOR: Color32 = Color32::from_rgb(110, 140, 200);
+const DOWNLOAD_COLOR: Color32 = Color32::from_rgb(90, 180, 110);
+const FAILED_COLOR: Color32 = Color32::from_rgb(220, 80, 80);
+
+/// Moments in the life of a displayed page, recorded by the shell.
+#[derive(Clone)]
+pub(crate) struct PageTimeline {
+    /// The text preview, links, article and metadata have been extracted.
+    pub(crate) parsed_at: DateTime<Utc>,
+    /// The first frame showing the page has been laid out.
+    pub(crate) laid_out_at: Option<DateTime<Utc>>,
+    /// That frame has been handed to the display.
+    pub(crate) painted_at: Option<DateTime<Utc>>,
+}
+
+impl PageTimeline {
+    pub(crate) fn parsed_now() -> Self {
+        Self {
+            parsed_at: Utc::now(),
+            laid_out_at: None,
+            painted_at: None,
+        }
+    }
+}
+
+/// Zoom state of the performance tab.
+pub(crate) struct PerformancePanel {
+    zoom: f32,
+}
+
+impl Default for PerformancePanel {
+    fn default() -> Self {
+        Self { zoom: MIN_ZOOM }
+    }
+}
+
+/// Requests and page markers of the last navigation in a tab.
+struct Waterfall<'a> {
+    url: &'a Url,
+    start: DateTime<Utc>,
+    rows: Vec<&'a NetworkEntry>,
+    markers: Vec<(&'static str, i64)>,
+}
+
+impl Waterfall<'_> {
+    fn offset_ms(&self, at: DateTime<Utc>) -> i64 {
+        at.signed_duration_since(self.start)
+            .num_milliseconds()
+            .max(0)
+    }
+
+    fn row_start_ms(&self, entry: &NetworkEntry) -> i64 {
+        self.offset_ms(entry.started_at)
+    }
+
+    fn span_ms(&self) -> i64 {
+        let rows = self
+            .rows
+            .iter()
+            .map(|entry| self.row_start_ms(entry) + entry.duration_ms.unwrap_or(0) as i64);
+        let markers = self.markers.iter().map(|(_, ms)| *ms);
+        rows.chain(markers).max().unwrap_or(0).max(1)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let markers: serde_json::Map<String, serde_json::Value> = self
+            .markers
+            .iter()
+            .map(|(name, ms)| ((*name).to_owned(), json!(ms)))
+            .collect();
+        let requests: Vec<serde_json::Value> = self
+            .rows
+            .iter()
+            .map(|entry| {
+                json!({
+                    "url": entry.url.as_str(),
+                    "method": entry.method,
+                    "kind": entry.kind.label(),
+                    "status": entry.status,
+                    "start_ms": self.row_start_ms(entry),
+                    "duration_ms": entry.duration_ms,
+                    "waiting_ms": entry.timings.map(|timings| timings.waiting_ms),
+                    "download_ms": entry.timings.map(|timings| timings.download_ms),
+                    "size": entry.size,
+                    "protocol": entry.connection.as_ref().map(|connection| &connection.protocol),
+                    "reused_connection": entry.connection.as_ref().map(|connection| connection.reused),
+                    "error": entry.error,
+                })
+            })
+            .collect();
+        json!({
+            "url": self.url.as_str(),
+            "navigation_start": self.start.to_rfc3339(),
+            "markers": markers,
+            "requests": requests,
+        })
+    }
+}
+
+/// Collects the document request of the page shown in `tab` and everything
+/// issued after it until the tab navigated again.
+fn waterfall<'a>(
+    entries: &'a [NetworkEntry],
+    tab: TabId,
+    page: &'a PageView,
+) -> Option<Waterfall<'a>> {
+    let is_navigation =
+        |entry: &NetworkEntry| entry.kind == RequestKind::Document && entry.tab == Some(tab);
+    let document = entries
+        .iter()
+        .rev()
+        .find(|entry| is_navigation(entry) && entry.url == page.url)?;
+    let next = entries
+        .iter()
+        .find(|entry| entry.id > document.id && is_navigation(entry))
+        .map(|entry| entry.id);
+    let rows = entries
+        .iter()
+        .filter(|entry| entry.id >= document.id && next.is_none_or(|next| entry.id < next))
+        .filter(|entry| entry.tab.is_none_or(|owner| owner == tab))
+        .collect();
+
+    let mut waterfall = Waterfall {
+        url: &page.url,
+        start: document.started_at,
+        rows,
+        markers: Vec::new(),
+    };
+    let timeline = &page.timeline;
+    let markers = [
+        ("parse", Some(timeline.parsed_at)),
+        ("layout", timeline.laid_out_at),
+        ("paint", timeline.painted_at),
+    ];
+    waterfall.markers = markers
+        .into_iter()
+        .filter_map(|(name, at)| Some((name, waterfall.offset_ms(at?))))
+        .collect();
+    Some(waterfall)
+}
+
+impl PerformancePanel {
+    /// Renders the waterfall for the page in `tab`. Returns the exported
+    /// JSON when the user asks for it.
+    pub(crate) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        entries: &[NetworkEntry],
+        page: Option<(TabId, &PageView)>,
+    ) -> Option<String> {
+        let Some(waterfall) = page.and_then(|(tab, page)| waterfall(entries, tab, page)) else {
+            ui.weak("No network activity recorded for the page in this tab. Reload it to capture a waterfall.");
+            return None;
+        };
+
+        let mut export = None;
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} requests, {} ms",
+                waterfall.rows.len(),
+                waterfall.span_ms()
+            ));
+            ui.separator();
+            ui.label("Zoom");
+            ui.add(egui::Slider::new(&mut self.zoom, MIN_ZOOM..=MAX_ZOOM).logarithmic(true));
+            if ui.button("Fit").clicked() {
+                self.zoom = MIN_ZOOM;
+            }
+            if ui.button("Export").clicked() {
+                export = serde_json::to_string_pretty(&waterfall.to_json()).ok();
+            }
+        });
+        ui.horizontal(|ui| {
+            legend(ui, WAITING_COLOR, "waiting");
+            legend(ui, DOWNLOAD_COLOR, "download");
+            for (name, _) in &waterfall.markers {
+                legend(ui, marker_color(name), name);
+            }
+        });
+        ui.separator();
+
+        egui::ScrollArea::both()
+            .id_source("performance_waterfall")
+            .auto_shrink([false, false])
+            .show(ui, |ui| self.paint_waterfall(ui, &waterfall));
+        export
+    }
+
+    fn paint_waterfall(&mut self, ui: &mut egui::Ui, waterfall: &Waterfall) {
+        let span = waterfall.span_ms() as f32;
+        let bar_width = (ui.available_width() - LABEL_WIDTH).max(100.0) * self.zoom;
+        let px_per_ms = bar_width / span;
+        let size = Vec2::new(
+            LABEL_WIDTH + bar_width,
+            AXIS_HEIGHT + waterfall.rows.len() as f32 * ROW_HEIGHT,
+        );
+        let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+        let painter = ui.painter_at(rect);
+        let text_color = ui.visuals().text_color();
+        let grid_color = ui.visuals().weak_text_color().gamma_multiply(0.4);
+        let x_at = |ms: f32| rect.left() + LABEL_WIDTH + ms * px_per_ms;
+
+        let step = tick_step(px_per_ms);
+        let mut tick = 0;
+        while tick as f32 <= span {
+            let x = x_at(tick as f32);
+            painter.line_segment(
+                [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+                Stroke::new(1.0, grid_color),
+            );
+            painter.text(
+                Pos2::new(x + 2.0, rect.top() + 2.0),
+                Align2::LEFT_TOP,
+                format!("{tick} ms"),
+                FontId::proportional(11.0),
+                text_color,
+            );
+            tick += step;
+        }
+
+        let mut hovered = None;
+        for (index, entry) in waterfall.rows.iter().enumerate() {
+            let top = rect.top() + AXIS_HEIGHT + index as f32 * ROW_HEIGHT;
+            let row = Rect::from_min_size(
+                Pos2::new(rect.left(), top),
+                Vec2::new(rect.width(), ROW_HEIGHT),
+            );
+            if response.hover_pos().is_some_and(|pos| row.contains(pos)) {
+                hovered = Some(*entry);
+                painter.rect_filled(row, 0.0, ui.visuals().faint_bg_color);
+            }
+            painter.text(
+                Pos2::new(rect.left() + 4.0, row.center().y),
+                Align2::LEFT_CENTER,
+                short_url(&entry.url),
+                FontId::monospace(11.0),
+                text_color,
+            );
+
+            let start = waterfall.row_start_ms(entry) as f32;
+            let bar = |from: f32, to: f32| {
+                Rect::from_min_max(
+                    Pos2::new(x_at(from), top + 4.0),
+                    Pos2::new(x_at(to).max(x_at(from) + 2.0), top + ROW_HEIGHT - 4.0),
+                )
+            };
+            match (entry.timings, entry.duration_ms) {
+                (Some(timings), _) => {
+                    let waiting_end = start + timings.waiting_ms as f32;
+                    let end = waiting_end + timings.download_ms as f32;
+                    painter.rect_filled(bar(start, waiting_end), 1.0, WAITING_COLOR);
+                    painter.rect_filled(bar(waiting_end, end), 1.0, DOWNLOAD_COLOR);
+                }
+                (None, Some(duration)) => {
+                    painter.rect_filled(bar(start, start + duration as f32), 1.0, FAILED_COLOR);
+                }
+                (None, None) => {
+                    painter.rect_stroke(bar(start, span), 1.0, Stroke::new(1.0, WAITING_COLOR));
+                }
+            }
+        }
+
+        for (name, ms) in &waterfall.markers {
+            let x = x_at(*ms as f32);
+            let color = marker_color(name);
+            painter.line_segment(
+                [
+                    Pos2::new(x, rect.top() + AXIS_HEIGHT),
+                    Pos2::new(x, rect.bottom()),
+                ],
+                Stroke::new(1.5, color),
+            );
+            painter.text(
+                Pos2::new(x + 2.0, rect.top() + AXIS_HEIGHT - 2.0),
+                Align2::LEFT_BOTTOM,
+                name,
+                FontId::proportional(10.0),
+                color,
+            );
+        }
+
+        if response.hovered() {
+            let zoom = ui.input(|input| input.zoom_delta());
+            if zoom != 1.0 {
+                self.zoom = (self.zoom * zoom).clamp(MIN_ZOOM, MAX_ZOOM);
+            }
+        }
+        if let Some(entry) = hovered {
+            response.on_hover_text(row_details(waterfall, entry));
+        }
+    }
+}
+
+impl ShellApp {
+    /// Records the first frame that laid out the active page.
+    pub(crate) fn mark_page_laid_out(&mut self) {
+        if let Some(timeline) = self.active_timeline() {
+            timeline.laid_out_at.get_or_insert_with(Utc::now);
+        }
+    }
+
+    /// Called at the start of a frame: the previous one, if it laid out the
+    /// active page, has been painted by now.
+    pub(crate) fn mark_page_painted(&mut self) {
+        if let Some(timeline) = self.active_timeline() {
+            if timeline.laid_out_at.is_some() && timeline.painted_at.is_none() {
+                timeline.painted_at = Some(Utc::now());
+            }
+        }
+    }
+
+    fn active_timeline(&mut self) -> Option<&mut PageTimeline> {
+        let tab = self.active_tab.as_ref()?;
+        self.pages.get_mut(&tab.id).map(|page| &mut page.timeline)
+    }
+
+    /// Copies an exported waterfall to the clipboard and writes it next to
+    /// the profile.
+    pub(crate) fn export_performance(&mut self, ctx: &EguiContext, json: String) {
+        ctx.output_mut(|output| output.copied_text = json.clone());
+        let Some(path) = profile_dir().map(|dir| dir.join("performance.json")) else {
+            self.status_line = "Performance waterfall copied to clipboard".to_owned();
+            return;
+        };
+        self.status_line = match std::fs::write(&path, json) {
+            Ok(()) => format!("Performance waterfall exported to {}", path.display()),
+            Err(err) => format!("Failed to export performance waterfall: {err}"),
+        };
+    }
+}
+
+fn row_details(waterfall: &Waterfall, entry: &NetworkEntry) -> String {
+    let mut details = format!(
+        "{} {}\n{} | starts at {} ms",
+        entry.method,
+        entry.url,
+        entry.kind.label(),
+        waterfall.row_start_ms(entry)
+    );
+    match (entry.timings, entry.duration_ms) {
+        (Some(timings), _) => details.push_str(&format!(
+            "\nwaiting {} ms, download {} ms",
+            timings.waiting_ms, timings.download_ms
+        )),
+        (None, Some(duration)) => details.push_str(&format!("\nfailed after {duration} ms")),
+        (None, None) => details.push_str("\npending"),
+    }
+    if let Some(error) = &entry.error {
+        details.push_str(&format!("\n{error}"));
+    }
+    details
+}
+
+fn legend(ui: &mut egui::Ui, color: Color32, label: &str) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(10.0), Sense::hover());
+    ui.painter().rect_filled(rect, 2.0, color);
+    ui.small(label);
+}
+
+fn marker_color(name: &str) -> Color32 {
+    match name {
+        "parse" => Color32::from_rgb(200, 120, 220),
+        "layout" => Color32::from_rgb(230, 160, 60),
+        _ => Color32::from_rgb(60, 190, 200),
+    }
+}
+
+/// Smallest round interval that keeps axis labels at least 60 px apart.
+fn tick_step(px_per_ms: f32) -> i64 {
+    let mut base = 1;
+    loop {
+        for factor in [1, 2, 5] {
+            let step = base * factor;
+            if step as f32 * px_per_ms >= 60.0 {
+                return step;
+            }
+        }
+        base *= 10;
+    }
+}
+
+fn short_url(url: &Url) -> String {
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_owned(),
+    };
+    let text = format!("{}{path}", url.host_str().unwrap_or_default());
+    if text.chars().count() <= 38 {
+        return text;
+    }
+    let mut short: String = text.chars().take(37).collect();
+    short.push('\u{2026}');
+    short
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use eframe::egui;
+use egui::RichText;
+use serde_json::Value;
+
+use crate::settings::Settings;
+use crate::ShellApp;
+
+/// Search and half-typed values of `about:config`.
+#[derive(Default)]
+pub(crate) struct PreferencesPage {
+    filter: String,
+    /// Text of the field being edited, by preference, until it loses focus.
+    drafts: HashMap<String, String>,
+    error: Option<String>,
+}
+
+/// Every preference as a dotted key with its value, sorted by key. Nested
+/// settings such as `proxy.mode` get a key each; lists are one value.
+fn flatten(value: &Value) -> Vec<(String, Value)> {
+    fn walk(value: &Value, key: String, out: &mut Vec<(String, Value)>) {
+        match value {
+            Value::Object(fields) if !fields.is_empty() => {
+                for (name, field) in fields {
+                    let key = if key.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{key}.{name}")
+                    };
+                    walk(field, key, out);
+                }
+            }
+            _ => out.push((key, value.clone())),
+        }
+    }
+    let mut preferences = Vec::new();
+    walk(value, String::new(), &mut preferences);
+    preferences
+}
+
+/// Sets the preference at dotted `key` in `root`.
+fn set_preference(root: &mut Value, key: &str, value: Value) {
+    let mut target = root;
+    for name in key.split('.') {
+        target = &mut target[name];
+    }
+    *target = value;
+}
+
+/// Value in the field of a preference that is edited as text.
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Reads `text` back as a preference shaped like `default`, which is how
+/// the schema tells strings, optional values and lists apart.
+fn parse_text(text: &str, default: &Value) -> Result<Value, String> {
+    match default {
+        Value::String(_) => Ok(Value::String(text.to_owned())),
+        Value::Null if text.trim().is_empty() => Ok(Value::Null),
+        // Optional values are mostly paths and names; JSON is tried first
+        // so `null` and structured values still get through.
+        Value::Null => {
+            Ok(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_owned())))
+        }
+        _ => serde_json::from_str(text).map_err(|err| format!("not valid JSON: {err}")),
+    }
+}
+
+/// Field editing one preference, returning the value it was changed to.
+fn edit_preference(
+    ui: &mut egui::Ui,
+    key: &str,
+    value: &Value,
+    default: &Value,
+    drafts: &mut HashMap<String, String>,
+) -> Option<Result<Value, String>> {
+    match value {
+        Value::Bool(enabled) => {
+            let label = if *enabled { "true" } else { "false" };
+            let mut enabled = *enabled;
+            ui.checkbox(&mut enabled, label)
+                .changed()
+                .then_some(Ok(Value::Bool(enabled)))
+        }
+        Value::Number(number) if number.is_u64() => {
+            let mut number = number.as_u64().unwrap_or_default();
+            ui.add(egui::DragValue::new(&mut number))
+                .changed()
+                .then(|| Ok(Value::from(number)))
+        }
+        Value::Number(number) if number.is_i64() => {
+            let mut number = number.as_i64().unwrap_or_default();
+            ui.add(egui::DragValue::new(&mut number))
+                .changed()
+                .then(|| Ok(Value::from(number)))
+        }
+        Value::Number(number) => {
+            let mut number = number.as_f64().unwrap_or_default();
+            ui.add(egui::DragValue::new(&mut number).speed(0.1))
+                .changed()
+                .then(|| Ok(Value::from(number)))
+        }
+        _ => {
+            let shown = text_of(value);
+            let mut text = drafts.get(key).cloned().unwrap_or_else(|| shown.clone());
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut text)
+                    .code_editor()
+                    .desired_width(360.0),
+            );
+            if response.lost_focus() {
+                drafts.remove(key);
+                return (text != shown).then(|| parse_text(&text, default));
+            }
+            if response.has_focus() {
+                drafts.insert(key.to_owned(), text);
+            }
+            None
+        }
+    }
+}
+
+/// Short name of the kind of value a preference holds.
+fn type_label(value: &Value, default: &Value) -> &'static str {
+    match (value, default) {
+        (Value::Bool(_), _) => "boolean",
+        (Value::Number(_), _) => "number",
+        (Value::String(_), _) | (Value::Null, Value::Null) => "string",
+        (Value::Array(_), _) => "list",
+        _ => "object",
+    }
+}
+
+impl ShellApp {
+    /// `about:config`: every setting the shell stores, editable in place.
+    pub(crate) fn render_config_page(&mut self, ui: &mut egui::Ui) {
+        let locked = self.settings_locked();
+        let defaults = serde_json::to_value(Settings::default()).unwrap_or_default();
+        let current = serde_json::to_value(&self.settings).unwrap_or_default();
+        let schema = flatten(&defaults);
+        let page = &mut self.preferences;
+        let mut change = None;
+
+        ui.heading("Advanced Preferences");
+        ui.label(
+            "Every setting the browser keeps, including those without a place in the menus. \
+             Changes apply at once and are saved to the profile.",
+        );
+        ui.horizontal(|ui| {
+            ui.label("Search");
+            ui.add(egui::TextEdit::singleline(&mut page.filter).hint_text("Preference name"));
+        });
+        if locked {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Restricted mode is on; preferences cannot be changed.",
+            );
+        }
+        if let Some(err) = &page.error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+        ui.separator();
+
+        let filter = page.filter.trim().to_lowercase();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("preferences")
+                .num_columns(4)
+                .striped(true)
+                .spacing([16.0, 6.0])
+                .show(ui, |ui| {
+                    for header in ["Preference", "Type", "Value", ""] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for (key, default) in &schema {
+                        if !filter.is_empty() && !key.to_lowercase().contains(&filter) {
+                            continue;
+                        }
+                        let value = current
+                            .pointer(&format!("/{}", key.replace('.', "/")))
+                            .unwrap_or(default);
+                        let modified = value != default;
+                        let name = RichText::new(key).monospace();
+                        ui.label(if modified { name.strong() } else { name });
+                        ui.label(type_label(value, default));
+                        ui.add_enabled_ui(!locked, |ui| {
+                            if let Some(edited) =
+                                edit_preference(ui, key, value, default, &mut page.drafts)
+                            {
+                                change = Some((key.clone(), edited));
+                            }
+                        });
+                        if modified
+                            && ui
+                                .add_enabled(!locked, egui::Button::new("Reset"))
+                                .on_hover_text(format!("Back to {}", text_of(default)))
+                                .clicked()
+                        {
+                            change = Some((key.clone(), Ok(default.clone())));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        let Some((key, edited)) = change else {
+            return;
+        };
+        let settings = edited.and_then(|value| {
+            let mut root = current;
+            set_preference(&mut root, &key, value);
+            serde_json::from_value::<Settings>(root).map_err(|err| err.to_string())
+        });
+        match settings {
+            Ok(settings) => {
+                self.preferences.error = None;
+                self.apply_settings(&ui.ctx().clone(), settings);
+                self.save_settings();
+                self.status_line = format!("Changed {key}");
+            }
+            Err(err) => self.preferences.error = Some(format!("{key}: {err}")),
+        }
+    }
+}
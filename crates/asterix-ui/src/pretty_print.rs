@@ -0,0 +1,23 @@
+use asterix_browser::prettify;
+use eframe::egui;
+
+use crate::PageView;
+
+/// Toggle reformatting a minified CSS, JavaScript or JSON page for reading.
+/// Shown only for pages in one of those languages.
+pub(crate) fn render_toggle(ui: &mut egui::Ui, page: &mut PageView) {
+    let Some(language) = page.source_language else {
+        return;
+    };
+    let mut pretty = page.pretty.is_some();
+    if ui
+        .toggle_value(&mut pretty, "{ } Pretty Print")
+        .on_hover_text(format!(
+            "Reformat the {} source for reading",
+            language.label()
+        ))
+        .changed()
+    {
+        page.pretty = pretty.then(|| prettify(&page.source, language));
+    }
+}
@@ -0,0 +1,195 @@
+use asterix_browser::{
+    ContentCoding, HttpVersionPolicy, IpPreference, ProxyMode, ProxySettings, CONNECTIVITY_PROBE,
+};
+use eframe::egui;
+use egui::Context as EguiContext;
+
+use crate::ShellApp;
+
+/// Window editing how requests reach the network.
+#[derive(Default)]
+pub(crate) struct ProxyPanel {
+    pub(crate) open: bool,
+    mode: ProxyMode,
+    http: String,
+    https: String,
+    /// Comma-separated hosts reached directly.
+    bypass: String,
+    ip_preference: IpPreference,
+    http_version: HttpVersionPolicy,
+    /// Compression codings offered to servers.
+    content_codings: Vec<ContentCoding>,
+    check_captive_portals: bool,
+    error: Option<String>,
+}
+
+impl ProxyPanel {
+    fn settings(&self) -> ProxySettings {
+        let url = |input: &str| Some(input.trim().to_owned()).filter(|url| !url.is_empty());
+        ProxySettings {
+            mode: self.mode,
+            http: url(&self.http),
+            https: url(&self.https),
+            bypass: self
+                .bypass
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        }
+    }
+}
+
+impl ShellApp {
+    pub(crate) fn open_proxy_window(&mut self) {
+        let settings = self.handle.proxy_settings();
+        self.proxy = ProxyPanel {
+            open: true,
+            mode: settings.mode,
+            http: settings.http.unwrap_or_default(),
+            https: settings.https.unwrap_or_default(),
+            bypass: settings.bypass.join(", "),
+            ip_preference: self.handle.ip_preference(),
+            http_version: self.handle.http_version(),
+            content_codings: self.handle.content_codings(),
+            check_captive_portals: !self.settings.skip_captive_portal_check,
+            error: None,
+        };
+    }
+
+    fn apply_proxy_settings(&mut self) {
+        let settings = self.proxy.settings();
+        let applied = self
+            .handle
+            .set_proxy(settings.clone())
+            .and_then(|()| self.handle.set_http_version(self.proxy.http_version));
+        match applied {
+            Ok(()) => {
+                self.handle.set_ip_preference(self.proxy.ip_preference);
+                self.handle
+                    .set_content_codings(self.proxy.content_codings.clone());
+                self.settings.proxy = settings;
+                self.settings.ip_preference = self.proxy.ip_preference;
+                self.settings.http_version = self.proxy.http_version;
+                self.settings.suppressed_content_codings = ContentCoding::DEFAULT
+                    .into_iter()
+                    .filter(|coding| !self.proxy.content_codings.contains(coding))
+                    .collect();
+                self.settings.skip_captive_portal_check = !self.proxy.check_captive_portals;
+                self.save_settings();
+                self.proxy.open = false;
+                self.status_line = "Proxy settings applied".to_owned();
+            }
+            Err(err) => self.proxy.error = Some(format!("{err:#}")),
+        }
+    }
+
+    pub(crate) fn render_proxy_window(&mut self, ctx: &EguiContext) {
+        let panel = &mut self.proxy;
+        if !panel.open {
+            return;
+        }
+        let mut open = true;
+        let mut apply = false;
+        egui::Window::new("Proxy")
+            .open(&mut open)
+            .default_size([420.0, 260.0])
+            .show(ctx, |ui| {
+                ui.radio_value(&mut panel.mode, ProxyMode::Direct, "No proxy");
+                ui.radio_value(
+                    &mut panel.mode,
+                    ProxyMode::System,
+                    "Use system proxy settings",
+                );
+                ui.radio_value(&mut panel.mode, ProxyMode::Manual, "Manual configuration");
+                ui.add_enabled_ui(panel.mode == ProxyMode::Manual, |ui| {
+                    egui::Grid::new("proxy_form").num_columns(2).show(ui, |ui| {
+                        ui.label("HTTP proxy");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.http)
+                                .hint_text("http://proxy.example:3128"),
+                        );
+                        ui.end_row();
+                        ui.label("HTTPS proxy");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.https)
+                                .hint_text("socks5h://127.0.0.1:9050"),
+                        );
+                        ui.end_row();
+                        ui.label("No proxy for");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.bypass)
+                                .hint_text("localhost, .corp.example, 10.0.0.0/8"),
+                        );
+                        ui.end_row();
+                    });
+                    ui.weak(
+                        "Use http:// or https:// for HTTP proxies and socks5:// or socks5h:// \
+                         for SOCKS5, resolving names locally or on the proxy. Credentials go \
+                         in the URL as user:password@.",
+                    );
+                });
+                ui.weak("Proxy auto-config (PAC) scripts are not supported.");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("IP version");
+                    for preference in IpPreference::ALL {
+                        ui.radio_value(&mut panel.ip_preference, preference, preference.label());
+                    }
+                });
+                ui.weak(
+                    "Both versions are tried in parallel when the first is slow to connect. \
+                     Turn IPv6 off on networks where it is broken.",
+                );
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("HTTP version");
+                    for policy in HttpVersionPolicy::ALL {
+                        ui.radio_value(&mut panel.http_version, policy, policy.label());
+                    }
+                });
+                ui.weak(
+                    "Limit connections to HTTP/1.1 to check whether a site's HTTP/2 setup \
+                     is what breaks it. The version each page used is shown once it loads.",
+                );
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Compression");
+                    for coding in ContentCoding::ALL {
+                        let mut offered = panel.content_codings.contains(&coding);
+                        let changed = ui.checkbox(&mut offered, coding.label()).changed();
+                        if changed && offered {
+                            panel.content_codings.push(coding);
+                        } else if changed {
+                            panel.content_codings.retain(|offered| *offered != coding);
+                        }
+                    }
+                });
+                ui.weak(
+                    "Codings servers may compress responses with. Turn them off to see a \
+                     site's uncompressed responses; the network panel shows both sizes.",
+                );
+                ui.separator();
+                ui.checkbox(
+                    &mut panel.check_captive_portals,
+                    "Detect Wi-Fi login pages (captive portals)",
+                );
+                ui.weak(format!(
+                    "Fetches {CONNECTIVITY_PROBE} at startup and whenever the network changes."
+                ));
+                if let Some(err) = &panel.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+                ui.separator();
+                if ui.button("Apply").clicked() {
+                    apply = true;
+                }
+            });
+        panel.open &= open;
+
+        if apply {
+            self.apply_proxy_settings();
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+
+use arboard::{Clipboard, ImageData};
+use asterix_browser::decode_qr;
+use eframe::egui;
+use egui::{Color32, Rect, Sense, Vec2};
+use qrcode::{Color, QrCode};
+use url::Url;
+
+use crate::ShellApp;
+
+/// Light modules kept around a code so scanners find its edges.
+const QUIET_ZONE: usize = 4;
+/// Pixels per module in copied images.
+const IMAGE_SCALE: usize = 8;
+/// Width of the code shown in the popover, in points.
+const POPOVER_SIZE: f32 = 220.0;
+
+impl ShellApp {
+    /// Toolbar popover showing the active page as a QR code, with a way to
+    /// open the link in a QR code on the clipboard.
+    pub(crate) fn render_qr_menu(&mut self, ui: &mut egui::Ui) {
+        let url = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| tab.url.clone())
+            .filter(|url| url.scheme() != "about");
+        ui.menu_button("QR", |ui| {
+            match url.and_then(|url| Some((QrCode::new(url.as_str()).ok()?, url))) {
+                Some((code, url)) => {
+                    paint_code(ui, &code, POPOVER_SIZE);
+                    ui.set_max_width(POPOVER_SIZE);
+                    ui.add(egui::Label::new(egui::RichText::new(url.as_str()).small()).wrap(true));
+                    if ui.button("Copy Image").clicked() {
+                        self.copy_qr_image(&code);
+                        ui.close_menu();
+                    }
+                }
+                None => {
+                    ui.weak("Open a page to share it as a QR code.");
+                }
+            }
+            ui.separator();
+            if ui
+                .button("Open QR Code from Clipboard")
+                .on_hover_text("Opens the link in a copied screenshot of a QR code")
+                .clicked()
+            {
+                self.open_qr_from_clipboard();
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text("Share as QR code");
+    }
+
+    fn copy_qr_image(&mut self, code: &QrCode) {
+        let (side, bytes) = code_image(code);
+        let image = ImageData {
+            width: side,
+            height: side,
+            bytes: Cow::Owned(bytes),
+        };
+        self.status_line =
+            match Clipboard::new().and_then(|mut clipboard| clipboard.set_image(image)) {
+                Ok(()) => "QR code copied to clipboard".to_owned(),
+                Err(err) => format!("Failed to copy QR code: {err}"),
+            };
+    }
+
+    fn open_qr_from_clipboard(&mut self) {
+        let image = match Clipboard::new().and_then(|mut clipboard| clipboard.get_image()) {
+            Ok(image) => image,
+            Err(err) => {
+                self.status_line = format!("No image on the clipboard: {err}");
+                return;
+            }
+        };
+        let text = match decode_qr(&image.bytes, image.width, image.height) {
+            Ok(text) => text,
+            Err(err) => {
+                self.status_line = format!("Failed to read QR code: {err}");
+                return;
+            }
+        };
+        // Codes often wrap the link in a sentence, e.g. "Join at https://…".
+        let url = text
+            .split_whitespace()
+            .filter_map(|word| Url::parse(word).ok())
+            .find(|url| matches!(url.scheme(), "http" | "https"));
+        match url {
+            Some(url) => self.navigate_active(url),
+            None => self.status_line = format!("QR code holds no link: {text}"),
+        }
+    }
+}
+
+/// Draws `code` as a square `size` points wide, quiet zone included.
+fn paint_code(ui: &mut egui::Ui, code: &QrCode, size: f32) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(size), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+    let width = code.width();
+    let module = size / (width + 2 * QUIET_ZONE) as f32;
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            let x = (i % width + QUIET_ZONE) as f32;
+            let y = (i / width + QUIET_ZONE) as f32;
+            let min = rect.min + Vec2::new(x, y) * module;
+            painter.rect_filled(
+                Rect::from_min_size(min, Vec2::splat(module)),
+                0.0,
+                Color32::BLACK,
+            );
+        }
+    }
+}
+
+/// RGBA pixels of `code` with its quiet zone, and the side of the square
+/// image.
+fn code_image(code: &QrCode) -> (usize, Vec<u8>) {
+    let width = code.width();
+    let colors = code.to_colors();
+    let side = (width + 2 * QUIET_ZONE) * IMAGE_SCALE;
+    let module = |pixel: usize| {
+        (pixel / IMAGE_SCALE)
+            .checked_sub(QUIET_ZONE)
+            .filter(|&module| module < width)
+    };
+    let mut rgba = Vec::with_capacity(side * side * 4);
+    for y in 0..side {
+        for x in 0..side {
+            let dark = module(x)
+                .zip(module(y))
+                .is_some_and(|(x, y)| colors[y * width + x] == Color::Dark);
+            let value = if dark { 0 } else { 255 };
+            rgba.extend_from_slice(&[value, value, value, 255]);
+        }
+    }
+    (side, rgba)
+}
@@ -0,0 +1,71 @@
+use asterix_browser::{Article, ArticleBlock, TabId};
+use eframe::egui;
+use egui::scroll_area::ScrollAreaOutput;
+use egui::RichText;
+
+use crate::PageView;
+
+/// Renders the extracted article of `page` as clean, readable text.
+pub(crate) fn render_reader(ui: &mut egui::Ui, tab_id: TabId, page: &mut PageView) {
+    let Some(article) = &page.article else {
+        return;
+    };
+
+    if let Some(title) = &article.title {
+        ui.heading(title);
+    }
+    reading_status(ui, article, page.reading_progress);
+    ui.separator();
+
+    let output = egui::ScrollArea::vertical()
+        .id_source(("reader", tab_id))
+        .show(ui, |ui| {
+            ui.set_max_width(720.0);
+            for block in &article.blocks {
+                match block {
+                    ArticleBlock::Heading { level, text } => {
+                        let size = match level {
+                            1 => 26.0,
+                            2 => 22.0,
+                            3 => 19.0,
+                            _ => 17.0,
+                        };
+                        ui.add_space(8.0);
+                        ui.label(RichText::new(text).size(size).strong());
+                    }
+                    ArticleBlock::Paragraph(text) => {
+                        ui.label(RichText::new(text).size(16.0));
+                        ui.add_space(6.0);
+                    }
+                }
+            }
+        });
+    page.reading_progress = scroll_progress(&output);
+}
+
+/// Shows the estimated reading time and a thin bar tracking scroll position.
+pub(crate) fn reading_status(ui: &mut egui::Ui, article: &Article, progress: f32) {
+    ui.label(
+        RichText::new(format!(
+            "{} min read · {} words",
+            article.reading_minutes(),
+            article.word_count
+        ))
+        .weak(),
+    );
+    ui.add(
+        egui::ProgressBar::new(progress)
+            .desired_height(3.0)
+            .desired_width(ui.available_width()),
+    );
+}
+
+/// Fraction of the scrollable content that has been scrolled past.
+pub(crate) fn scroll_progress<R>(output: &ScrollAreaOutput<R>) -> f32 {
+    let scrollable = output.content_size.y - output.inner_rect.height();
+    if scrollable <= 0.0 {
+        1.0
+    } else {
+        (output.state.offset.y / scrollable).clamp(0.0, 1.0)
+    }
+}
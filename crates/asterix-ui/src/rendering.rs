@@ -0,0 +1,85 @@
+use asterix_browser::{render_with_stylesheets, ColorScheme, RenderOptions, RenderedPage};
+use eframe::egui;
+use egui::Color32;
+
+use crate::{PageView, ShellApp};
+
+impl ShellApp {
+    /// View menu toggles previewing the page for another medium. Changing
+    /// one re-runs the cascade on the loaded source.
+    pub(crate) fn render_rendering_modes(&mut self, ui: &mut egui::Ui) {
+        let options = &mut self.render_options;
+        let mut dark = options.media.color_scheme == ColorScheme::Dark;
+        if ui
+            .checkbox(&mut dark, "Emulate Dark Color Scheme")
+            .on_hover_text("Applies the page's prefers-color-scheme: dark styles")
+            .changed()
+        {
+            options.media.color_scheme = if dark {
+                ColorScheme::Dark
+            } else {
+                ColorScheme::Light
+            };
+        }
+        ui.checkbox(&mut options.media.print, "Emulate Print Styles")
+            .on_hover_text("Applies the page's print media styles");
+        let mut images_off = !options.images;
+        if ui
+            .checkbox(&mut images_off, "Disable Images")
+            .on_hover_text("Shows alt text in place of images")
+            .changed()
+        {
+            options.images = !images_off;
+        }
+    }
+}
+
+/// Whether `options` differ from a plain screen rendering, so the preview
+/// shows the rendered text instead of the source.
+pub(crate) fn is_emulating(options: &RenderOptions) -> bool {
+    options.media.print || options.media.color_scheme == ColorScheme::Dark || !options.images
+}
+
+/// Renders `page` for `options` unless its cached rendering already used
+/// them, with the linked stylesheets fetched for it so far.
+pub(crate) fn refresh_rendering(page: &mut PageView, options: &RenderOptions) {
+    if page.rendered.as_ref().map(|(cached, _)| cached) != Some(options) {
+        let linked = page
+            .resources
+            .as_ref()
+            .map(|resources| resources.stylesheets())
+            .unwrap_or_default();
+        let rendered = render_with_stylesheets(&page.source, &page.elements, options, &linked);
+        page.rendered = Some((*options, rendered));
+    }
+}
+
+/// One-line description of the active modes and what they hid.
+pub(crate) fn summary(options: &RenderOptions, rendered: &RenderedPage) -> String {
+    let mut modes = Vec::new();
+    if options.media.color_scheme == ColorScheme::Dark {
+        modes.push("dark color scheme");
+    }
+    if options.media.print {
+        modes.push("print styles");
+    }
+    if !options.images {
+        modes.push("images disabled");
+    }
+    format!(
+        "Rendered with {}; {} hidden elements",
+        modes.join(", "),
+        rendered.hidden
+    )
+}
+
+/// Paints the preview in the page's own colors where its styles set them.
+pub(crate) fn apply_page_colors(ui: &mut egui::Ui, rendered: &RenderedPage) {
+    let visuals = ui.visuals_mut();
+    if let Some([r, g, b]) = rendered.background {
+        visuals.extreme_bg_color = Color32::from_rgb(r, g, b);
+    }
+    if let Some([r, g, b]) = rendered.color {
+        visuals.override_text_color = Some(Color32::from_rgb(r, g, b));
+    }
+}
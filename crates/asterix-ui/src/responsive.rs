@@ -0,0 +1,278 @@
+use asterix_browser::{TabId, Viewport};
+use eframe::egui;
+use egui::{Context as EguiContext, Rect, Stroke, TopBottomPanel, Vec2};
+
+use crate::{ShellApp, ANDROID_USER_AGENT, IPHONE_USER_AGENT};
+
+const MIN_VIEWPORT: u32 = 200;
+const MAX_VIEWPORT: u32 = 3840;
+
+/// Device offered by responsive design mode.
+struct DevicePreset {
+    name: &'static str,
+    width: u32,
+    height: u32,
+    device_pixel_ratio: f32,
+    user_agent: Option<&'static str>,
+}
+
+const DEVICE_PRESETS: [DevicePreset; 5] = [
+    DevicePreset {
+        name: "iPhone 15",
+        width: 393,
+        height: 852,
+        device_pixel_ratio: 3.0,
+        user_agent: Some(IPHONE_USER_AGENT),
+    },
+    DevicePreset {
+        name: "Pixel 8",
+        width: 412,
+        height: 915,
+        device_pixel_ratio: 2.625,
+        user_agent: Some(ANDROID_USER_AGENT),
+    },
+    DevicePreset {
+        name: "iPad Air",
+        width: 820,
+        height: 1180,
+        device_pixel_ratio: 2.0,
+        user_agent: Some(IPHONE_USER_AGENT),
+    },
+    DevicePreset {
+        name: "Laptop",
+        width: 1280,
+        height: 800,
+        device_pixel_ratio: 1.0,
+        user_agent: None,
+    },
+    DevicePreset {
+        name: "Desktop HD",
+        width: 1920,
+        height: 1080,
+        device_pixel_ratio: 1.0,
+        user_agent: None,
+    },
+];
+
+/// Responsive design mode for one tab: the page area is constrained to the
+/// emulated viewport and requests carry matching client hints.
+pub(crate) struct ResponsiveMode {
+    tab: TabId,
+    /// Index into the device presets; `None` once dimensions are edited.
+    device: Option<usize>,
+    viewport: Viewport,
+    mobile_user_agent: bool,
+    /// User agent the tab had before emulation started, restored on exit.
+    previous_user_agent: Option<String>,
+}
+
+impl ResponsiveMode {
+    fn user_agent(&self) -> Option<String> {
+        let emulated = self
+            .device
+            .and_then(|device| DEVICE_PRESETS[device].user_agent)
+            .unwrap_or(IPHONE_USER_AGENT);
+        if self.mobile_user_agent {
+            Some(emulated.to_owned())
+        } else {
+            self.previous_user_agent.clone()
+        }
+    }
+}
+
+impl ShellApp {
+    /// Turns responsive design mode on for the active tab, or off again.
+    pub(crate) fn toggle_responsive_mode(&mut self) {
+        let active = self.active_tab.clone();
+        if let Some(mode) = self.responsive.take() {
+            let was_active = active.as_ref().map(|tab| tab.id) == Some(mode.tab);
+            self.stop_responsive_mode(mode);
+            if was_active {
+                return;
+            }
+        }
+        let Some(tab) = active else {
+            return;
+        };
+        let preset = &DEVICE_PRESETS[0];
+        self.responsive = Some(ResponsiveMode {
+            tab: tab.id,
+            device: Some(0),
+            viewport: Viewport {
+                width: preset.width,
+                height: preset.height,
+                device_pixel_ratio: preset.device_pixel_ratio,
+            },
+            mobile_user_agent: preset.user_agent.is_some(),
+            previous_user_agent: tab.user_agent,
+        });
+        self.apply_responsive_mode(true);
+    }
+
+    fn stop_responsive_mode(&mut self, mode: ResponsiveMode) {
+        self.handle.set_tab_viewport(mode.tab, None);
+        self.handle
+            .set_user_agent(mode.tab, mode.previous_user_agent.clone());
+        self.reload_emulated_tab(mode.tab);
+        self.status_line = "Responsive design mode off".to_owned();
+    }
+
+    /// Pushes the emulated viewport and user agent to the tab, reloading it
+    /// when `reload` is set so the server sees the new device.
+    fn apply_responsive_mode(&mut self, reload: bool) {
+        let Some(mode) = &self.responsive else {
+            return;
+        };
+        let tab = mode.tab;
+        self.handle.set_tab_viewport(tab, Some(mode.viewport));
+        self.handle.set_user_agent(tab, mode.user_agent());
+        self.status_line = format!(
+            "Emulating {} \u{d7} {} at {}x",
+            mode.viewport.width, mode.viewport.height, mode.viewport.device_pixel_ratio
+        );
+        if reload {
+            self.reload_emulated_tab(tab);
+        }
+    }
+
+    fn reload_emulated_tab(&mut self, tab: TabId) {
+        let url = self
+            .tabs
+            .iter()
+            .find(|snapshot| snapshot.id == tab)
+            .and_then(|snapshot| snapshot.url.clone());
+        if let Some(url) = url {
+            self.navigate_tab(tab, url);
+        }
+    }
+
+    /// Size the page area is constrained to, when the active tab emulates a
+    /// device.
+    pub(crate) fn emulated_viewport(&self) -> Option<Vec2> {
+        let mode = self.responsive.as_ref()?;
+        let active = self.active_tab.as_ref()?;
+        (mode.tab == active.id)
+            .then(|| Vec2::new(mode.viewport.width as f32, mode.viewport.height as f32))
+    }
+
+    pub(crate) fn render_responsive_bar(&mut self, ctx: &EguiContext) {
+        if self.emulated_viewport().is_none() {
+            return;
+        }
+        let Some(mode) = self.responsive.as_mut() else {
+            return;
+        };
+
+        let mut reload = false;
+        let mut changed = false;
+        let mut close = false;
+        TopBottomPanel::top("responsive_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong("Responsive");
+                let selected = mode
+                    .device
+                    .map_or("Custom", |device| DEVICE_PRESETS[device].name);
+                egui::ComboBox::from_id_source("responsive_device")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for (index, preset) in DEVICE_PRESETS.iter().enumerate() {
+                            if ui
+                                .selectable_label(mode.device == Some(index), preset.name)
+                                .clicked()
+                            {
+                                mode.device = Some(index);
+                                mode.viewport = Viewport {
+                                    width: preset.width,
+                                    height: preset.height,
+                                    device_pixel_ratio: preset.device_pixel_ratio,
+                                };
+                                mode.mobile_user_agent = preset.user_agent.is_some();
+                                reload = true;
+                            }
+                        }
+                    });
+
+                let size = [&mut mode.viewport.width, &mut mode.viewport.height];
+                let mut resized = false;
+                for (index, value) in size.into_iter().enumerate() {
+                    if index == 1 {
+                        ui.label("\u{d7}");
+                    }
+                    resized |= ui
+                        .add(egui::DragValue::new(value).clamp_range(MIN_VIEWPORT..=MAX_VIEWPORT))
+                        .changed();
+                }
+                if ui.button("Rotate").clicked() {
+                    let viewport = &mut mode.viewport;
+                    std::mem::swap(&mut viewport.width, &mut viewport.height);
+                    resized = true;
+                }
+                ui.label("DPR");
+                resized |= ui
+                    .add(
+                        egui::DragValue::new(&mut mode.viewport.device_pixel_ratio)
+                            .clamp_range(0.5..=4.0)
+                            .speed(0.05)
+                            .max_decimals(3),
+                    )
+                    .changed();
+                if resized {
+                    mode.device = None;
+                    changed = true;
+                }
+                if ui
+                    .checkbox(&mut mode.mobile_user_agent, "Mobile user agent")
+                    .changed()
+                {
+                    reload = true;
+                }
+                ui.separator();
+                if ui
+                    .button("Reload")
+                    .on_hover_text("Reload so the server sees the emulated device")
+                    .clicked()
+                {
+                    reload = true;
+                }
+                if ui.button("Close").clicked() {
+                    close = true;
+                }
+            });
+        });
+
+        if close {
+            if let Some(mode) = self.responsive.take() {
+                self.stop_responsive_mode(mode);
+            }
+        } else if reload || changed {
+            self.apply_responsive_mode(reload);
+        }
+    }
+}
+
+/// Lays out `add_contents` in a frame of the emulated `size`, centred
+/// horizontally in the available space and clipped to it.
+pub(crate) fn show_in_viewport(
+    ui: &mut egui::Ui,
+    size: Vec2,
+    add_contents: impl FnOnce(&mut egui::Ui),
+) {
+    let available = ui.available_rect_before_wrap();
+    let size = size.min(available.size());
+    let frame = Rect::from_min_size(
+        egui::pos2(available.center().x - size.x / 2.0, available.top()),
+        size,
+    );
+    ui.painter()
+        .rect_filled(available, 0.0, ui.visuals().extreme_bg_color);
+    ui.painter().rect(
+        frame.expand(1.0),
+        0.0,
+        ui.visuals().panel_fill,
+        Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color),
+    );
+    let mut child = ui.child_ui(frame, *ui.layout());
+    child.set_clip_rect(frame.intersect(ui.clip_rect()));
+    add_contents(&mut child);
+    ui.allocate_rect(available, egui::Sense::hover());
+}
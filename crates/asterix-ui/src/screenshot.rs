@@ -0,0 +1,562 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use arboard::{Clipboard, ImageData};
+use chrono::Local;
+use eframe::egui;
+use egui::{
+    Color32, ColorImage, Context as EguiContext, CursorIcon, Pos2, Rect, Sense, Stroke,
+    TextureHandle, TextureOptions, Vec2,
+};
+
+use crate::ShellApp;
+
+/// Colour of arrows and boxes.
+const MARK_COLOR: Color32 = Color32::from_rgb(230, 40, 40);
+/// Width of arrows and boxes, in points.
+const MARK_WIDTH: f32 = 3.0;
+/// Radius of the blur, in points.
+const BLUR_RADIUS: f32 = 6.0;
+/// Widest the screenshot is shown in the editor, in points.
+const EDITOR_WIDTH: f32 = 760.0;
+/// Smallest region worth capturing, in points.
+const MIN_REGION: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum AnnotationTool {
+    #[default]
+    Arrow,
+    Box,
+    Blur,
+}
+
+impl AnnotationTool {
+    const ALL: [Self; 3] = [Self::Arrow, Self::Box, Self::Blur];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Arrow => "Arrow",
+            Self::Box => "Box",
+            Self::Blur => "Blur",
+        }
+    }
+}
+
+/// An arrow, box or blurred area, in screenshot pixels.
+#[derive(Debug, Clone, Copy)]
+struct Annotation {
+    tool: AnnotationTool,
+    from: Pos2,
+    to: Pos2,
+}
+
+enum Capture {
+    /// The user is dragging out the region to capture from `origin`, which
+    /// is `None` until the drag starts.
+    Selecting { origin: Option<Pos2> },
+    /// A window screenshot was requested and is cropped to `region` when it
+    /// arrives.
+    Waiting { region: Option<Rect> },
+}
+
+/// Screenshot capture and the editor annotating the last one.
+#[derive(Default)]
+pub(crate) struct ScreenshotTool {
+    capture: Option<Capture>,
+    editor: Option<ScreenshotEditor>,
+}
+
+struct ScreenshotEditor {
+    image: ColorImage,
+    /// Points to screenshot pixels.
+    pixels_per_point: f32,
+    annotations: Vec<Annotation>,
+    tool: AnnotationTool,
+    /// Where the annotation being dragged out started, in pixels.
+    drag_start: Option<Pos2>,
+    /// The screenshot with its annotations, as shown and saved.
+    preview: Option<TextureHandle>,
+    destination: String,
+    result: Option<Result<String, String>>,
+}
+
+impl ScreenshotEditor {
+    fn new(image: ColorImage, pixels_per_point: f32) -> Self {
+        Self {
+            image,
+            pixels_per_point,
+            annotations: Vec::new(),
+            tool: AnnotationTool::default(),
+            drag_start: None,
+            preview: None,
+            destination: default_destination()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            result: None,
+        }
+    }
+
+    /// The screenshot with every annotation drawn into it.
+    fn flatten(&self) -> ColorImage {
+        let mut image = self.image.clone();
+        let width = MARK_WIDTH * self.pixels_per_point;
+        for annotation in &self.annotations {
+            let (from, to) = (annotation.from, annotation.to);
+            match annotation.tool {
+                AnnotationTool::Arrow => draw_arrow(&mut image, from, to, width),
+                AnnotationTool::Box => draw_box(&mut image, Rect::from_two_pos(from, to), width),
+                AnnotationTool::Blur => blur(
+                    &mut image,
+                    Rect::from_two_pos(from, to),
+                    (BLUR_RADIUS * self.pixels_per_point) as usize,
+                ),
+            }
+        }
+        image
+    }
+
+    fn refresh_preview(&mut self, ctx: &EguiContext) {
+        let image = self.flatten();
+        match &mut self.preview {
+            Some(texture) => texture.set(image, TextureOptions::LINEAR),
+            None => {
+                self.preview = Some(ctx.load_texture("screenshot", image, TextureOptions::LINEAR))
+            }
+        }
+    }
+}
+
+/// Where a screenshot is offered to be saved, e.g.
+/// `~/Pictures/asterix-2024-05-01-120000.png`.
+fn default_destination() -> Option<PathBuf> {
+    let name = format!("asterix-{}.png", Local::now().format("%Y-%m-%d-%H%M%S"));
+    let home = home::home_dir()?;
+    let pictures = home.join("Pictures");
+    Some(if pictures.is_dir() { pictures } else { home }.join(name))
+}
+
+fn rgba_bytes(image: &ColorImage) -> Vec<u8> {
+    image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect()
+}
+
+fn save_png(image: &ColorImage, path: &Path) -> anyhow::Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let [width, height] = image.size;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&rgba_bytes(image)))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+impl ShellApp {
+    /// Captures the window, or a region of it dragged out first, and opens
+    /// the result in the annotation editor.
+    pub(crate) fn start_screenshot(&mut self, ctx: &EguiContext, region: bool) {
+        let screenshot = &mut self.screenshot;
+        screenshot.editor = None;
+        if region {
+            screenshot.capture = Some(Capture::Selecting { origin: None });
+        } else {
+            screenshot.capture = Some(Capture::Waiting { region: None });
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+        }
+        ctx.request_repaint();
+    }
+
+    /// Lets the user drag out a region while selecting, and opens the
+    /// editor once the requested window screenshot arrives.
+    pub(crate) fn run_screenshot_capture(&mut self, ctx: &EguiContext) {
+        let screenshot = &mut self.screenshot;
+        match screenshot.capture {
+            None => {}
+            Some(Capture::Waiting { region }) => {
+                let image = ctx.input(|input| {
+                    input.events.iter().find_map(|event| match event {
+                        egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                        _ => None,
+                    })
+                });
+                if let Some(image) = image {
+                    let pixels_per_point = ctx.pixels_per_point();
+                    let image = match region {
+                        Some(region) => image.region(&region, Some(pixels_per_point)),
+                        None => (*image).clone(),
+                    };
+                    screenshot.capture = None;
+                    let mut editor = ScreenshotEditor::new(image, pixels_per_point);
+                    editor.refresh_preview(ctx);
+                    screenshot.editor = Some(editor);
+                }
+            }
+            Some(Capture::Selecting { mut origin }) => {
+                if ctx.input(|input| input.key_pressed(egui::Key::Escape)) {
+                    screenshot.capture = None;
+                    return;
+                }
+                let screen = ctx.screen_rect();
+                let mut selected = None;
+                egui::Area::new(egui::Id::new("screenshot_overlay"))
+                    .order(egui::Order::Foreground)
+                    .fixed_pos(screen.min)
+                    .show(ctx, |ui| {
+                        let response = ui
+                            .allocate_rect(screen, Sense::drag())
+                            .on_hover_cursor(CursorIcon::Crosshair);
+                        let painter = ui.painter();
+                        let shade = Color32::from_black_alpha(96);
+                        let current = response.interact_pointer_pos();
+                        if response.drag_started() {
+                            origin = current;
+                        }
+                        match origin.zip(current) {
+                            Some((origin, current)) => {
+                                let region = Rect::from_two_pos(origin, current);
+                                // Shade around the region so it shows as it
+                                // will be captured.
+                                for rect in [
+                                    Rect::from_x_y_ranges(
+                                        screen.x_range(),
+                                        screen.top()..=region.top(),
+                                    ),
+                                    Rect::from_x_y_ranges(
+                                        screen.x_range(),
+                                        region.bottom()..=screen.bottom(),
+                                    ),
+                                    Rect::from_x_y_ranges(
+                                        screen.left()..=region.left(),
+                                        region.y_range(),
+                                    ),
+                                    Rect::from_x_y_ranges(
+                                        region.right()..=screen.right(),
+                                        region.y_range(),
+                                    ),
+                                ] {
+                                    painter.rect_filled(rect, 0.0, shade);
+                                }
+                                painter.rect_stroke(region, 0.0, Stroke::new(1.0, Color32::WHITE));
+                            }
+                            None => {
+                                painter.rect_filled(screen, 0.0, shade);
+                                painter.text(
+                                    screen.center(),
+                                    egui::Align2::CENTER_CENTER,
+                                    "Drag to select a region \u{b7} Escape cancels",
+                                    egui::FontId::proportional(18.0),
+                                    Color32::WHITE,
+                                );
+                            }
+                        }
+                        if response.drag_stopped() {
+                            selected = origin
+                                .zip(response.interact_pointer_pos())
+                                .map(|(origin, end)| Rect::from_two_pos(origin, end));
+                        }
+                    });
+                let Some(region) = selected else {
+                    screenshot.capture = Some(Capture::Selecting { origin });
+                    return;
+                };
+                if region.width() < MIN_REGION || region.height() < MIN_REGION {
+                    screenshot.capture = Some(Capture::Selecting { origin: None });
+                    return;
+                }
+                // The overlay is no longer drawn, so the next frame, which is
+                // the one captured, shows the page alone.
+                screenshot.capture = Some(Capture::Waiting {
+                    region: Some(region),
+                });
+                ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    pub(crate) fn render_screenshot_editor(&mut self, ctx: &EguiContext) {
+        let Some(editor) = &mut self.screenshot.editor else {
+            return;
+        };
+        let mut open = true;
+        let mut changed = false;
+        let mut save = false;
+        let mut copy = false;
+        egui::Window::new("Screenshot")
+            .open(&mut open)
+            .default_width(EDITOR_WIDTH)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for tool in AnnotationTool::ALL {
+                        ui.selectable_value(&mut editor.tool, tool, tool.label());
+                    }
+                    ui.separator();
+                    if ui
+                        .add_enabled(!editor.annotations.is_empty(), egui::Button::new("Undo"))
+                        .clicked()
+                    {
+                        editor.annotations.pop();
+                        changed = true;
+                    }
+                    if ui
+                        .add_enabled(!editor.annotations.is_empty(), egui::Button::new("Clear"))
+                        .clicked()
+                    {
+                        editor.annotations.clear();
+                        changed = true;
+                    }
+                });
+                ui.separator();
+
+                let Some(preview) = editor.preview.as_ref().map(TextureHandle::id) else {
+                    return;
+                };
+                let [width, height] = editor.image.size;
+                let scale = (EDITOR_WIDTH / width as f32).min(1.0 / editor.pixels_per_point);
+                let size = Vec2::new(width as f32, height as f32) * scale;
+                egui::ScrollArea::both().max_height(520.0).show(ui, |ui| {
+                    let (rect, response) = ui.allocate_exact_size(size, Sense::drag());
+                    let painter = ui.painter_at(rect);
+                    painter.image(
+                        preview,
+                        rect,
+                        Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                        Color32::WHITE,
+                    );
+                    let to_pixels = |pos: Pos2| {
+                        let pos = rect.clamp(pos);
+                        Pos2::new((pos.x - rect.left()) / scale, (pos.y - rect.top()) / scale)
+                    };
+                    let response = response.on_hover_cursor(CursorIcon::Crosshair);
+                    if response.drag_started() {
+                        editor.drag_start = response.interact_pointer_pos().map(to_pixels);
+                    }
+                    if let Some((start, current)) =
+                        editor.drag_start.zip(response.interact_pointer_pos())
+                    {
+                        let from = rect.min + start.to_vec2() * scale;
+                        let to = rect.clamp(current);
+                        let stroke =
+                            Stroke::new(MARK_WIDTH * scale * editor.pixels_per_point, MARK_COLOR);
+                        let area = Rect::from_two_pos(from, to);
+                        match editor.tool {
+                            AnnotationTool::Arrow => painter.arrow(from, to - from, stroke),
+                            AnnotationTool::Box => {
+                                painter.rect_stroke(area, 0.0, stroke);
+                            }
+                            AnnotationTool::Blur => {
+                                painter.rect(
+                                    area,
+                                    0.0,
+                                    Color32::from_white_alpha(64),
+                                    Stroke::new(1.0, Color32::GRAY),
+                                );
+                            }
+                        }
+                        if response.drag_stopped() {
+                            let to = to_pixels(current);
+                            if start.distance(to) >= MIN_REGION {
+                                editor.annotations.push(Annotation {
+                                    tool: editor.tool,
+                                    from: start,
+                                    to,
+                                });
+                                changed = true;
+                            }
+                            editor.drag_start = None;
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Save to");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut editor.destination).desired_width(360.0),
+                    );
+                    if ui
+                        .add_enabled(
+                            !editor.destination.trim().is_empty(),
+                            egui::Button::new("Save"),
+                        )
+                        .clicked()
+                    {
+                        save = true;
+                    }
+                    if ui.button("Copy").clicked() {
+                        copy = true;
+                    }
+                });
+                match &editor.result {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(err)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, err);
+                    }
+                    None => {}
+                }
+            });
+
+        if changed {
+            editor.refresh_preview(ctx);
+        }
+        if save {
+            let path = PathBuf::from(editor.destination.trim());
+            editor.result = Some(match save_png(&editor.flatten(), &path) {
+                Ok(()) => Ok(format!("Saved to {}", path.display())),
+                Err(err) => Err(format!("{err:#}")),
+            });
+        }
+        if copy {
+            let image = editor.flatten();
+            let data = ImageData {
+                width: image.size[0],
+                height: image.size[1],
+                bytes: Cow::Owned(rgba_bytes(&image)),
+            };
+            editor.result = Some(
+                Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.set_image(data))
+                    .map(|()| "Copied to clipboard".to_owned())
+                    .map_err(|err| format!("Failed to copy: {err}")),
+            );
+        }
+        if let Some(Ok(message)) = &editor.result {
+            if save || copy {
+                self.status_line = format!("Screenshot: {message}");
+            }
+        }
+        if !open {
+            self.screenshot.editor = None;
+        }
+    }
+}
+
+fn set_pixel(image: &mut ColorImage, x: i64, y: i64, color: Color32) {
+    let [width, height] = image.size;
+    if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+        image.pixels[y as usize * width + x as usize] = color;
+    }
+}
+
+/// Draws a line `width` pixels thick by stamping squares along it.
+fn draw_line(image: &mut ColorImage, from: Pos2, to: Pos2, width: f32) {
+    let half = (width / 2.0).max(0.5);
+    let steps = (from.distance(to) * 2.0).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let point = from.lerp(to, step as f32 / steps as f32);
+        let (left, right) = (
+            (point.x - half).round() as i64,
+            (point.x + half).round() as i64,
+        );
+        let (top, bottom) = (
+            (point.y - half).round() as i64,
+            (point.y + half).round() as i64,
+        );
+        for y in top..bottom {
+            for x in left..right {
+                set_pixel(image, x, y, MARK_COLOR);
+            }
+        }
+    }
+}
+
+fn draw_arrow(image: &mut ColorImage, from: Pos2, to: Pos2, width: f32) {
+    draw_line(image, from, to, width);
+    let length = from.distance(to);
+    if length == 0.0 {
+        return;
+    }
+    let back = (from - to) / length;
+    let head = (width * 5.0).min(length / 2.0);
+    for angle in [-0.5f32, 0.5] {
+        let (sin, cos) = angle.sin_cos();
+        let side = Vec2::new(back.x * cos - back.y * sin, back.x * sin + back.y * cos);
+        draw_line(image, to, to + side * head, width);
+    }
+}
+
+fn draw_box(image: &mut ColorImage, rect: Rect, width: f32) {
+    let corners = [
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+    ];
+    for (i, &corner) in corners.iter().enumerate() {
+        draw_line(image, corner, corners[(i + 1) % 4], width);
+    }
+}
+
+/// Blurs the pixels in `rect` past reading with three box blurs of
+/// `radius`, which together come close to a gaussian blur.
+fn blur(image: &mut ColorImage, rect: Rect, radius: usize) {
+    let [width, height] = image.size;
+    let left = (rect.left().max(0.0) as usize).min(width);
+    let top = (rect.top().max(0.0) as usize).min(height);
+    let right = (rect.right().max(0.0).ceil() as usize).min(width);
+    let bottom = (rect.bottom().max(0.0).ceil() as usize).min(height);
+    if right <= left || bottom <= top || radius == 0 {
+        return;
+    }
+    let (region_width, region_height) = (right - left, bottom - top);
+    let mut pixels: Vec<[f32; 4]> = Vec::with_capacity(region_width * region_height);
+    for y in top..bottom {
+        for pixel in &image.pixels[y * width + left..y * width + right] {
+            pixels.push(pixel.to_array().map(f32::from));
+        }
+    }
+    for _ in 0..3 {
+        box_blur(&mut pixels, region_width, region_height, radius, true);
+        box_blur(&mut pixels, region_width, region_height, radius, false);
+    }
+    for (i, pixel) in pixels.into_iter().enumerate() {
+        let [r, g, b, a] = pixel.map(|channel| channel.round() as u8);
+        let (x, y) = (left + i % region_width, top + i / region_width);
+        image.pixels[y * width + x] = Color32::from_rgba_premultiplied(r, g, b, a);
+    }
+}
+
+/// Averages each pixel with the `radius` pixels either side of it along
+/// rows or columns, clamping at the edges.
+fn box_blur(pixels: &mut [[f32; 4]], width: usize, height: usize, radius: usize, rows: bool) {
+    let (lines, length) = if rows {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let index = |line: usize, i: usize| {
+        if rows {
+            line * width + i
+        } else {
+            i * width + line
+        }
+    };
+    let mut line_pixels = vec![[0.0; 4]; length];
+    for line in 0..lines {
+        for (i, pixel) in line_pixels.iter_mut().enumerate() {
+            *pixel = pixels[index(line, i)];
+        }
+        for i in 0..length {
+            let start = i.saturating_sub(radius);
+            let end = (i + radius + 1).min(length);
+            let mut sum = [0.0; 4];
+            for pixel in &line_pixels[start..end] {
+                for (total, channel) in sum.iter_mut().zip(pixel) {
+                    *total += channel;
+                }
+            }
+            let count = (end - start) as f32;
+            pixels[index(line, i)] = sum.map(|total| total / count);
+        }
+    }
+}
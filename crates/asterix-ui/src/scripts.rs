@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use asterix_browser::{ScriptJob, SCRIPT_API};
+use eframe::egui;
+use egui::{Context as EguiContext, RichText};
+
+use crate::{profile_dir, ShellApp};
+
+/// Most output lines kept for one script run.
+const MAX_OUTPUT_LINES: usize = 2_000;
+
+/// Script started from the Scripts menu, running or finished.
+pub(crate) struct ScriptRun {
+    name: String,
+    job: Option<ScriptJob>,
+    output: Vec<String>,
+    result: Option<Result<(), String>>,
+}
+
+/// Directory the Scripts menu lists `.rhai` files from; relative paths a
+/// script saves to are resolved against it too.
+pub(crate) fn scripts_dir() -> Option<PathBuf> {
+    profile_dir().map(|dir| dir.join("scripts"))
+}
+
+/// `.rhai` files in `dir`, by name.
+pub(crate) fn list_scripts(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut scripts: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+impl ShellApp {
+    pub(crate) fn render_scripts_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Scripts", |ui| {
+            let Some(dir) = scripts_dir() else {
+                ui.weak("No profile directory");
+                return;
+            };
+            if self.scripts.is_empty() {
+                ui.weak(format!("Add .rhai files to {}", dir.display()));
+            }
+            for script in self.scripts.clone() {
+                let name = script
+                    .file_stem()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                if ui.button(&name).clicked() {
+                    self.start_script(name, &script, dir.clone());
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            if ui.button("Macros\u{2026}").clicked() {
+                self.macros.open = true;
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn start_script(&mut self, name: String, path: &Path, base_dir: PathBuf) {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                self.status_line = format!("Could not read {}: {err}", path.display());
+                return;
+            }
+        };
+        self.run_script_source(name, source, base_dir);
+    }
+
+    /// Runs `source` and shows its output in the script window.
+    pub(crate) fn run_script_source(&mut self, name: String, source: String, base_dir: PathBuf) {
+        match self.handle.run_script(&name, source, base_dir) {
+            Ok(job) => {
+                self.status_line = format!("Running script {name}");
+                self.script = Some(ScriptRun {
+                    name,
+                    job: Some(job),
+                    output: Vec::new(),
+                    result: None,
+                });
+                self.refresh_tabs();
+            }
+            Err(err) => self.status_line = format!("Script failed to start: {err}"),
+        }
+    }
+
+    /// Window with the output of the last script; closing it stops the script.
+    pub(crate) fn render_script_window(&mut self, ctx: &EguiContext) {
+        let Some(run) = &mut self.script else {
+            return;
+        };
+        if let Some(job) = &mut run.job {
+            while let Some(line) = job.try_next() {
+                run.output.push(line);
+            }
+            if run.output.len() > MAX_OUTPUT_LINES {
+                run.output.drain(..run.output.len() - MAX_OUTPUT_LINES);
+            }
+            match job.try_complete() {
+                Some(result) => {
+                    self.status_line = match &result {
+                        Ok(()) => format!("Script {} finished", run.name),
+                        Err(_) => format!("Script {} failed", run.name),
+                    };
+                    run.result = Some(result);
+                    run.job = None;
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(200)),
+            }
+        }
+
+        let mut open = true;
+        egui::Window::new(format!("Script: {}", run.name))
+            .open(&mut open)
+            .default_size([520.0, 320.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| match (&run.job, &run.result) {
+                    (Some(job), _) => {
+                        ui.spinner();
+                        ui.label("Running");
+                        if ui.button("Stop").clicked() {
+                            job.stop();
+                        }
+                    }
+                    (None, Some(Ok(()))) => {
+                        ui.label("Finished");
+                    }
+                    (None, Some(Err(err))) => {
+                        ui.colored_label(ui.visuals().error_fg_color, err);
+                    }
+                    (None, None) => {}
+                });
+                egui::CollapsingHeader::new("Script functions").show(ui, |ui| {
+                    ui.label(RichText::new(SCRIPT_API).monospace());
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for line in &run.output {
+                            ui.monospace(line);
+                        }
+                    });
+            });
+        if !open {
+            self.script = None;
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::time::Duration;
+
+use asterix_browser::{extract_seo, BrowserHandle, ImageJob, SeoSummary, ShareCard, TabId};
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use egui::{
+    Color32, ColorImage, Pos2, Rect, RichText, Sense, Stroke, TextureHandle, TextureOptions, Vec2,
+};
+use url::Url;
+
+use crate::PageView;
+
+/// Lengths search engines show in full before truncating.
+const TITLE_LENGTH: usize = 60;
+const DESCRIPTION_LENGTH: usize = 160;
+
+/// Search and sharing metadata of the page in the active tab.
+#[derive(Default)]
+pub(crate) struct SeoPanel {
+    page_key: Option<(Url, DateTime<Utc>)>,
+    summary: SeoSummary,
+    card_image: CardImage,
+}
+
+/// The share card's image, fetched once per page.
+#[derive(Default)]
+enum CardImage {
+    #[default]
+    None,
+    Loading(ImageJob),
+    Loaded(TextureHandle),
+    Failed(String),
+}
+
+impl SeoPanel {
+    pub(crate) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &BrowserHandle,
+        page: Option<(TabId, &PageView)>,
+    ) {
+        let Some((tab, page)) = page else {
+            ui.weak("Load a page to see its metadata.");
+            return;
+        };
+        let page_key = (page.url.clone(), page.received_at);
+        if self.page_key.as_ref() != Some(&page_key) {
+            self.page_key = Some(page_key);
+            self.summary = extract_seo(&page.source, &page.url);
+            self.card_image = match &self.summary.card.image {
+                Some(url) => match handle.fetch_image(tab, url.clone()) {
+                    Ok(job) => CardImage::Loading(job),
+                    Err(err) => CardImage::Failed(err.to_string()),
+                },
+                None => CardImage::None,
+            };
+        }
+        self.poll_card_image(ui.ctx());
+        let summary = &self.summary;
+
+        egui::ScrollArea::vertical()
+            .id_source("seo_panel")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("seo_basics")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Title");
+                        length_checked(ui, summary.title.as_deref(), TITLE_LENGTH);
+                        ui.end_row();
+                        ui.label("Description");
+                        length_checked(ui, summary.description.as_deref(), DESCRIPTION_LENGTH);
+                        ui.end_row();
+                        ui.label("Canonical");
+                        match &summary.canonical {
+                            Some(canonical) if *canonical == page.url => {
+                                ui.label(format!("{canonical} (this page)"));
+                            }
+                            Some(canonical) => {
+                                ui.label(canonical.as_str());
+                            }
+                            None => {
+                                ui.weak("none");
+                            }
+                        }
+                        ui.end_row();
+                        ui.label("Robots");
+                        ui.vertical(|ui| {
+                            if summary.robots.is_empty() {
+                                ui.weak("none (indexable)");
+                            }
+                            for (agent, directives) in &summary.robots {
+                                ui.label(format!("{agent}: {directives}"));
+                            }
+                            if summary.is_noindex() {
+                                ui.colored_label(
+                                    ui.visuals().warn_fg_color,
+                                    "This page asks not to be indexed",
+                                );
+                            }
+                        });
+                        ui.end_row();
+                    });
+
+                ui.add_space(8.0);
+                ui.strong("Share card preview");
+                share_card(ui, &summary.card, &self.card_image);
+
+                ui.add_space(8.0);
+                property_list(ui, "Open Graph", "seo_open_graph", &summary.open_graph);
+                property_list(ui, "Twitter", "seo_twitter", &summary.twitter);
+
+                let heading = format!("Structured data ({})", summary.structured_data.len());
+                egui::CollapsingHeader::new(heading)
+                    .id_source("seo_structured_data")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        if summary.structured_data.is_empty() {
+                            ui.weak("No JSON-LD or microdata found.");
+                        }
+                        for (index, block) in summary.structured_data.iter().enumerate() {
+                            let types = if block.types.is_empty() {
+                                "untyped".to_owned()
+                            } else {
+                                block.types.join(", ")
+                            };
+                            egui::CollapsingHeader::new(format!(
+                                "{} \u{2014} {types}",
+                                block.format.label()
+                            ))
+                            .id_source(("seo_block", index))
+                            .show(ui, |ui| {
+                                if let Some(error) = &block.error {
+                                    ui.colored_label(
+                                        ui.visuals().error_fg_color,
+                                        format!("Invalid JSON: {error}"),
+                                    );
+                                }
+                                ui.monospace(&block.content);
+                            });
+                        }
+                    });
+            });
+    }
+}
+
+impl SeoPanel {
+    fn poll_card_image(&mut self, ctx: &egui::Context) {
+        let CardImage::Loading(job) = &mut self.card_image else {
+            return;
+        };
+        let Some(result) = job.try_complete() else {
+            ctx.request_repaint_after(Duration::from_millis(100));
+            return;
+        };
+        self.card_image = match result {
+            Ok(image) => {
+                let pixels = ColorImage::from_rgba_unmultiplied(
+                    [image.width as usize, image.height as usize],
+                    &image.pixels,
+                );
+                CardImage::Loaded(ctx.load_texture(
+                    "seo_share_card",
+                    pixels,
+                    TextureOptions::LINEAR,
+                ))
+            }
+            Err(err) => CardImage::Failed(err.to_string()),
+        };
+    }
+}
+
+/// Shows `value` with its length, warning when search results would cut it.
+fn length_checked(ui: &mut egui::Ui, value: Option<&str>, limit: usize) {
+    let Some(value) = value else {
+        ui.colored_label(ui.visuals().warn_fg_color, "missing");
+        return;
+    };
+    ui.horizontal_wrapped(|ui| {
+        ui.label(value);
+        let length = value.chars().count();
+        let text = format!("{length} chars");
+        if length > limit {
+            ui.colored_label(ui.visuals().warn_fg_color, text)
+                .on_hover_text(format!("Likely truncated after about {limit} characters"));
+        } else {
+            ui.weak(text);
+        }
+    });
+}
+
+fn property_list(ui: &mut egui::Ui, title: &str, id: &str, properties: &[(String, String)]) {
+    egui::CollapsingHeader::new(format!("{title} ({})", properties.len()))
+        .id_source(id)
+        .show(ui, |ui| {
+            if properties.is_empty() {
+                ui.weak("No tags found.");
+                return;
+            }
+            egui::Grid::new(id)
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, content) in properties {
+                        ui.monospace(name);
+                        ui.label(content);
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// Approximates the link card social sites build from the page's tags.
+fn share_card(ui: &mut egui::Ui, card: &ShareCard, card_image: &CardImage) {
+    let large_image = card.kind == "summary_large_image";
+    egui::Frame::group(ui.style())
+        .fill(ui.visuals().extreme_bg_color)
+        .show(ui, |ui| {
+            ui.set_max_width(420.0);
+            let image_size = if large_image {
+                Vec2::new(ui.available_width(), 180.0)
+            } else {
+                Vec2::splat(96.0)
+            };
+            let image = |ui: &mut egui::Ui| {
+                let (rect, mut response) = ui.allocate_exact_size(image_size, Sense::hover());
+                ui.painter().rect(
+                    rect,
+                    4.0,
+                    Color32::from_gray(70),
+                    Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color),
+                );
+                let caption = match card_image {
+                    CardImage::Loaded(texture) => {
+                        ui.painter().image(
+                            texture.id(),
+                            rect,
+                            cover_uv(texture.size_vec2(), rect.size()),
+                            Color32::WHITE,
+                        );
+                        None
+                    }
+                    CardImage::Loading(_) => Some("loading\u{2026}"),
+                    CardImage::Failed(err) => {
+                        response = response.on_hover_text(err);
+                        Some("image failed")
+                    }
+                    CardImage::None => Some("no image"),
+                };
+                if let Some(caption) = caption {
+                    ui.painter().text(
+                        rect.center(),
+                        egui::Align2::CENTER_CENTER,
+                        caption,
+                        egui::FontId::proportional(12.0),
+                        Color32::from_gray(200),
+                    );
+                }
+                if let Some(image) = &card.image {
+                    response.on_hover_text(image.as_str());
+                }
+            };
+            let text = |ui: &mut egui::Ui| {
+                if let Some(site) = &card.site_name {
+                    ui.small(site);
+                }
+                ui.label(RichText::new(card.title.as_deref().unwrap_or("(no title)")).strong());
+                if let Some(description) = &card.description {
+                    ui.label(description);
+                }
+            };
+            if large_image {
+                image(ui);
+                text(ui);
+            } else {
+                ui.horizontal(|ui| {
+                    image(ui);
+                    ui.vertical(text);
+                });
+            }
+        });
+    ui.weak(format!("Card type: {}", card.kind));
+}
+
+/// Texture coordinates that fill a `frame` with an image of `size`, cropping
+/// its overflowing sides the way social sites crop card images.
+fn cover_uv(size: Vec2, frame: Vec2) -> Rect {
+    let scale = (frame.x / size.x).max(frame.y / size.y);
+    let visible = frame / (size * scale);
+    let margin = (Vec2::splat(1.0) - visible) / 2.0;
+    Rect::from_min_max(Pos2::ZERO + margin, Pos2::ZERO + margin + visible)
+}
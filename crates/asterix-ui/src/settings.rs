@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use asterix_browser::{
+    BlockRule, ConsentSettings, ContentCoding, FileTypeRule, HttpVersionPolicy, IpPreference,
+    IpfsSettings, PrivacySettings, ProxySettings, SiteUserAgent,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::client_certs::ClientCertificate;
+use crate::startup::StartupPage;
+use crate::toolbar::ToolbarLayout;
+
+/// User preferences for the desktop shell, stored as JSON in the profile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    /// Directory clipped pages are written to, e.g. an Obsidian vault.
+    pub(crate) clip_directory: Option<PathBuf>,
+    /// Show reading time and progress on article-like pages outside reader mode.
+    pub(crate) reading_time_on_articles: bool,
+    /// Refuse non-essential cookies in the banners of known consent platforms.
+    pub(crate) reject_cookie_banners: bool,
+    /// Sites, with their subdomains, whose cookie banners are left alone.
+    pub(crate) cookie_banner_exceptions: Vec<String>,
+    /// Strip tracking parameters, ask sites not to track and keep
+    /// third-party cookies out.
+    pub(crate) tracker_protection: bool,
+    /// Catch-all domain of an email alias service used for throwaway
+    /// identities; empty for a public disposable inbox.
+    pub(crate) throwaway_email_domain: String,
+    /// How requests reach the network, applied when the browser starts.
+    pub(crate) proxy: ProxySettings,
+    /// IP version connections try first, or IPv4 only.
+    pub(crate) ip_preference: IpPreference,
+    /// HTTP versions requests may use.
+    pub(crate) http_version: HttpVersionPolicy,
+    /// Compression codings left out of `Accept-Encoding`, e.g. to see how
+    /// a site serves uncompressed responses.
+    pub(crate) suppressed_content_codings: Vec<ContentCoding>,
+    /// Never probe for captive portals, which reaches out to a fixed
+    /// address at startup and on every network change.
+    pub(crate) skip_captive_portal_check: bool,
+    /// Let every request through, keeping the filter lists for later.
+    pub(crate) pause_content_blocking: bool,
+    /// What to do with responses by MIME type or extension, first match wins.
+    pub(crate) file_type_rules: Vec<FileTypeRule>,
+    /// Gateway and local node preference for `ipfs://` and `ipns://` links.
+    pub(crate) ipfs: IpfsSettings,
+    /// User agents given to particular sites, such as a phone's for sites
+    /// whose mobile version reads better.
+    pub(crate) site_user_agents: Vec<SiteUserAgent>,
+    /// Command magnet links are handed to; empty for the system's torrent
+    /// client.
+    pub(crate) magnet_handler: String,
+    /// Certificates identifying the browser to mTLS-protected hosts.
+    pub(crate) client_certificates: Vec<ClientCertificate>,
+    /// Page the Home button, startup and new tabs open; empty for none.
+    pub(crate) home_page: String,
+    /// What the shell shows when it starts.
+    pub(crate) startup: StartupPage,
+    /// Open the home page in new tabs instead of a blank page.
+    pub(crate) new_tabs_open_home: bool,
+    /// Buttons of the customizable part of the toolbar, in order.
+    pub(crate) toolbar: ToolbarLayout,
+    /// Show load progress, transfer size and the hovered link at the
+    /// bottom of the window.
+    pub(crate) show_status_bar: bool,
+    /// Theme file in the profile's `themes` directory, without `.json`;
+    /// empty for the plain light or dark look.
+    pub(crate) theme: String,
+}
+
+impl Settings {
+    /// Loads settings from `path`, falling back to defaults when it is missing.
+    pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_slice(&json)
+            .with_context(|| format!("failed to decode {}", path.display()))
+    }
+
+    /// Cookie banner handling to hand to the browser, `None` when it is off.
+    pub(crate) fn consent(&self) -> Option<ConsentSettings> {
+        self.reject_cookie_banners.then(|| ConsentSettings {
+            disabled_sites: self.cookie_banner_exceptions.clone(),
+        })
+    }
+
+    /// Page the Home button opens, when one is set.
+    pub(crate) fn home_page(&self) -> Option<Url> {
+        Url::parse(self.home_page.trim()).ok()
+    }
+
+    /// Compression codings requests offer: those the browser decodes, less
+    /// the suppressed ones.
+    pub(crate) fn content_codings(&self) -> Vec<ContentCoding> {
+        ContentCoding::DEFAULT
+            .into_iter()
+            .filter(|coding| !self.suppressed_content_codings.contains(coding))
+            .collect()
+    }
+
+    /// Tracker protection to hand to the browser, `None` when it is off.
+    pub(crate) fn privacy(&self) -> Option<PrivacySettings> {
+        self.tracker_protection.then(PrivacySettings::default)
+    }
+
+    /// Turns cookie banner rejection back on for the site of `url`, or off
+    /// for its host.
+    pub(crate) fn reject_cookie_banners_on(&mut self, url: &Url, reject: bool) {
+        if reject {
+            self.cookie_banner_exceptions
+                .retain(|site| !BlockRule::Domain(site.clone()).matches(url));
+        } else if let Some(host) = url.host_str() {
+            self.cookie_banner_exceptions.push(host.to_owned());
+        }
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_vec_pretty(self).context("failed to encode settings")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        std::fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,230 @@
+use std::path::Path;
+use std::time::Duration;
+
+use asterix_browser::{FilterList, FilterListJob};
+use eframe::egui;
+use egui::Context as EguiContext;
+use tracing::warn;
+use url::Url;
+
+use crate::ShellApp;
+
+/// Content blocker controls behind the toolbar shield button.
+#[derive(Default)]
+pub(crate) struct ShieldPanel {
+    /// Address or file path of a list to add.
+    source: String,
+    job: Option<FilterListJob>,
+    error: Option<String>,
+}
+
+impl ShellApp {
+    /// Toolbar popover with the number of requests blocked on the active
+    /// page, the switch for the blocker and the filter lists it applies.
+    pub(crate) fn render_shield_menu(&mut self, ui: &mut egui::Ui) {
+        self.poll_filter_list_job(ui.ctx());
+        let blocked = self
+            .active_tab
+            .as_ref()
+            .map(|tab| self.handle.blocked_requests(tab.id))
+            .unwrap_or_default();
+        let label = match blocked {
+            0 => "Shield".to_owned(),
+            blocked => format!("Shield ({blocked})"),
+        };
+        ui.menu_button(label, |ui| {
+            ui.set_min_width(280.0);
+            match blocked {
+                1 => ui.label("1 request blocked on this page"),
+                blocked => ui.label(format!("{blocked} requests blocked on this page")),
+            };
+            let locked = self.settings_locked();
+            let mut enabled = !self.settings.pause_content_blocking;
+            if ui
+                .add_enabled(
+                    !locked,
+                    egui::Checkbox::new(&mut enabled, "Block ads and trackers"),
+                )
+                .changed()
+            {
+                self.settings.pause_content_blocking = !enabled;
+                self.save_settings();
+                self.handle.set_content_blocking(enabled);
+            }
+            ui.separator();
+            self.render_filter_lists(ui, locked);
+            ui.separator();
+            self.render_privacy_report(ui);
+        })
+        .response
+        .on_hover_text("Content blocker");
+    }
+
+    fn render_filter_lists(&mut self, ui: &mut egui::Ui, locked: bool) {
+        let lists = self.handle.filter_lists();
+        if lists.is_empty() {
+            ui.weak("No filter lists. Add EasyList or another Adblock Plus list below.");
+        }
+        for list in lists {
+            ui.horizontal(|ui| {
+                ui.label(&list.name).on_hover_text(match &list.source {
+                    Some(source) => source.to_string(),
+                    None => "Added from a file".to_owned(),
+                });
+                ui.weak(format!("{} filters", list.filters))
+                    .on_hover_text(format!(
+                        "{} lines skipped: element hiding, regular expressions and \
+                         unsupported options",
+                        list.skipped
+                    ));
+                if let Some(source) = list.source.clone() {
+                    if ui
+                        .add_enabled(
+                            !locked && self.shield.job.is_none(),
+                            egui::Button::new("Update"),
+                        )
+                        .clicked()
+                    {
+                        self.start_filter_list_subscription(source);
+                    }
+                }
+                if ui
+                    .add_enabled(!locked, egui::Button::new("Remove"))
+                    .clicked()
+                {
+                    self.handle.remove_filter_list(&list.name);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.shield.source)
+                    .hint_text("List URL or file path")
+                    .desired_width(200.0),
+            );
+            if ui
+                .add_enabled(
+                    !locked && self.shield.job.is_none() && !self.shield.source.trim().is_empty(),
+                    egui::Button::new("Add"),
+                )
+                .clicked()
+            {
+                self.add_filter_list_from_input();
+            }
+        });
+        if self.shield.job.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Fetching filter list…");
+            });
+        }
+        if let Some(err) = &self.shield.error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+    }
+
+    /// What tracker protection removed from and added to the active page.
+    fn render_privacy_report(&self, ui: &mut egui::Ui) {
+        let Some(tab) = self.active_tab.as_ref().map(|tab| tab.id) else {
+            return;
+        };
+        let report = self
+            .pages
+            .get(&tab)
+            .map(|view| view.response.privacy.clone())
+            .unwrap_or_default();
+        if !report.is_protected() {
+            ui.weak("Tracker protection was off when this page loaded");
+            return;
+        }
+        match report.stripped_parameters.len() {
+            0 => ui.label("No tracking parameters in the address"),
+            stripped => ui
+                .label(format!(
+                    "{stripped} tracking parameters removed from the address"
+                ))
+                .on_hover_text(report.stripped_parameters.join("\n")),
+        };
+        if report.opt_out_sent {
+            ui.label("Asked not to be tracked (Global Privacy Control, Do Not Track)");
+        }
+        if report.third_party_cookies_blocked {
+            match self.handle.withheld_cookies(tab) {
+                0 => ui.label("No third-party cookies kept out yet"),
+                1 => ui.label("Cookies kept out of 1 third-party request"),
+                withheld => ui.label(format!(
+                    "Cookies kept out of {withheld} third-party requests"
+                )),
+            };
+        }
+    }
+
+    fn add_filter_list_from_input(&mut self) {
+        let source = self.shield.source.trim().to_owned();
+        match Url::parse(&source) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                self.start_filter_list_subscription(url);
+            }
+            _ => {
+                let path = Path::new(&source);
+                match std::fs::read_to_string(path) {
+                    Ok(text) => {
+                        let name = path
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| source.clone());
+                        let list = self.handle.add_filter_list(FilterList {
+                            name,
+                            source: None,
+                            text,
+                        });
+                        self.status_line =
+                            format!("Added {} with {} filters", list.name, list.filters);
+                        self.shield.source.clear();
+                        self.shield.error = None;
+                    }
+                    Err(err) => {
+                        self.shield.error = Some(format!("Failed to read {source}: {err}"));
+                    }
+                }
+            }
+        }
+    }
+
+    fn start_filter_list_subscription(&mut self, url: Url) {
+        match self.handle.subscribe_filter_list(url) {
+            Ok(job) => {
+                self.shield.job = Some(job);
+                self.shield.error = None;
+            }
+            Err(err) => self.shield.error = Some(err.to_string()),
+        }
+    }
+
+    fn poll_filter_list_job(&mut self, ctx: &EguiContext) {
+        let Some(job) = &mut self.shield.job else {
+            return;
+        };
+        match job.try_complete() {
+            Some(Ok(list)) => {
+                self.status_line = format!("Added {} with {} filters", list.name, list.filters);
+                self.shield.source.clear();
+                self.shield.job = None;
+            }
+            Some(Err(err)) => {
+                self.shield.error = Some(format!("Failed to fetch filter list: {err}"));
+                self.shield.job = None;
+            }
+            None => ctx.request_repaint_after(Duration::from_millis(200)),
+        }
+    }
+
+    pub(crate) fn save_filter_lists(&mut self) {
+        if let Some(path) = self.filter_lists_path.clone() {
+            if let Err(err) = self.handle.save_filter_lists(&path) {
+                warn!("failed to save filter lists: {err:#}");
+            }
+            self.note_config_saved(&path);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+
+use asterix_browser::{
+    parse_site_file, PageResponse, RobotsTxt, SiteFile, Sitemap, SitemapKind, TabId,
+};
+use eframe::egui;
+use egui::RichText;
+use url::Url;
+
+use crate::ShellApp;
+
+/// A `robots.txt` or sitemap shown in a structured viewer instead of the
+/// source preview.
+#[derive(Clone)]
+pub(crate) struct SiteFileView {
+    file: SiteFile,
+    /// Sitemap URLs grouped by host and path, built once per load.
+    tree: UrlNode,
+    pub(crate) show_source: bool,
+}
+
+#[derive(Clone, Default)]
+struct UrlNode {
+    /// Sitemap entry for exactly this path, if listed.
+    entry: Option<usize>,
+    children: BTreeMap<String, UrlNode>,
+    /// Number of entries at or below this node.
+    count: usize,
+}
+
+impl SiteFileView {
+    pub(crate) fn new(page: &PageResponse) -> Option<Self> {
+        let file = parse_site_file(page)?;
+        let mut tree = UrlNode::default();
+        if let SiteFile::Sitemap(sitemap) = &file {
+            for (index, entry) in sitemap.entries.iter().enumerate() {
+                let mut segments = vec![entry.loc.origin().ascii_serialization()];
+                segments.extend(
+                    entry
+                        .loc
+                        .path()
+                        .split('/')
+                        .filter(|segment| !segment.is_empty())
+                        .map(str::to_owned),
+                );
+                if let Some(query) = entry.loc.query() {
+                    if let Some(last) = segments.last_mut() {
+                        last.push('?');
+                        last.push_str(query);
+                    }
+                }
+                tree.insert(&segments, index);
+            }
+        }
+        Some(Self {
+            file,
+            tree,
+            show_source: false,
+        })
+    }
+
+    pub(crate) fn title(&self) -> String {
+        match &self.file {
+            SiteFile::Robots(_) => "robots.txt".to_owned(),
+            SiteFile::Sitemap(sitemap) => match sitemap.kind {
+                SitemapKind::Pages => format!("Sitemap ({} URLs)", sitemap.entries.len()),
+                SitemapKind::Index => format!("Sitemap index ({} sitemaps)", sitemap.entries.len()),
+            },
+        }
+    }
+
+    /// Shows the parsed file and returns a URL the user clicked.
+    pub(crate) fn show(&self, ui: &mut egui::Ui, tab_id: TabId) -> Option<Url> {
+        let mut follow = None;
+        egui::ScrollArea::vertical()
+            .id_source(("site_file", tab_id))
+            .auto_shrink([false, false])
+            .show(ui, |ui| match &self.file {
+                SiteFile::Robots(robots) => robots_view(ui, robots, &mut follow),
+                SiteFile::Sitemap(sitemap) if sitemap.kind == SitemapKind::Index => {
+                    sitemap_index_view(ui, sitemap, &mut follow);
+                }
+                SiteFile::Sitemap(sitemap) => {
+                    if sitemap.entries.is_empty() {
+                        ui.weak("The sitemap lists no URLs.");
+                    }
+                    for (name, node) in &self.tree.children {
+                        node.show(ui, name, name, sitemap, &mut follow);
+                    }
+                }
+            });
+        follow
+    }
+}
+
+impl UrlNode {
+    fn insert(&mut self, segments: &[String], entry: usize) {
+        self.count += 1;
+        match segments.split_first() {
+            Some((first, rest)) => self
+                .children
+                .entry(first.clone())
+                .or_default()
+                .insert(rest, entry),
+            None => self.entry = Some(entry),
+        }
+    }
+
+    fn show(
+        &self,
+        ui: &mut egui::Ui,
+        name: &str,
+        path: &str,
+        sitemap: &Sitemap,
+        follow: &mut Option<Url>,
+    ) {
+        if self.children.is_empty() {
+            if let Some(entry) = self.entry {
+                entry_link(ui, name, sitemap, entry, follow);
+            }
+            return;
+        }
+        egui::CollapsingHeader::new(format!("{name}  ({})", self.count))
+            .id_source(("sitemap_node", path))
+            .show(ui, |ui| {
+                if let Some(entry) = self.entry {
+                    entry_link(ui, "(this page)", sitemap, entry, follow);
+                }
+                for (child_name, child) in &self.children {
+                    child.show(
+                        ui,
+                        child_name,
+                        &format!("{path}/{child_name}"),
+                        sitemap,
+                        follow,
+                    );
+                }
+            });
+    }
+}
+
+fn entry_link(
+    ui: &mut egui::Ui,
+    label: &str,
+    sitemap: &Sitemap,
+    entry: usize,
+    follow: &mut Option<Url>,
+) {
+    let entry = &sitemap.entries[entry];
+    let mut details = entry.loc.to_string();
+    for (name, value) in [
+        ("Last modified", &entry.lastmod),
+        ("Change frequency", &entry.changefreq),
+        ("Priority", &entry.priority),
+    ] {
+        if let Some(value) = value {
+            details.push_str(&format!("\n{name}: {value}"));
+        }
+    }
+    if ui.link(label).on_hover_text(details).clicked() {
+        *follow = Some(entry.loc.clone());
+    }
+}
+
+fn sitemap_index_view(ui: &mut egui::Ui, sitemap: &Sitemap, follow: &mut Option<Url>) {
+    egui::Grid::new("sitemap_index")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            for entry in &sitemap.entries {
+                if ui.link(entry.loc.as_str()).clicked() {
+                    *follow = Some(entry.loc.clone());
+                }
+                ui.weak(entry.lastmod.as_deref().unwrap_or_default());
+                ui.end_row();
+            }
+        });
+}
+
+fn robots_view(ui: &mut egui::Ui, robots: &RobotsTxt, follow: &mut Option<Url>) {
+    if robots.groups.is_empty() {
+        ui.weak("No user-agent groups; every path may be crawled.");
+    }
+    for (index, group) in robots.groups.iter().enumerate() {
+        let agents = group.user_agents.join(", ");
+        egui::CollapsingHeader::new(RichText::new(format!("User-agent: {agents}")).strong())
+            .id_source(("robots_group", index))
+            .default_open(true)
+            .show(ui, |ui| {
+                if let Some(delay) = group.crawl_delay {
+                    ui.label(format!("Crawl delay: {delay} s"));
+                }
+                if group.rules.is_empty() {
+                    ui.weak("No rules; every path may be crawled.");
+                    return;
+                }
+                egui::Grid::new(("robots_rules", index))
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for rule in &group.rules {
+                            if rule.allow {
+                                ui.colored_label(egui::Color32::from_rgb(90, 170, 90), "Allow");
+                            } else {
+                                ui.colored_label(ui.visuals().error_fg_color, "Disallow");
+                            }
+                            ui.monospace(&rule.path);
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    if !robots.sitemaps.is_empty() {
+        ui.add_space(8.0);
+        ui.strong("Sitemaps");
+        for sitemap in &robots.sitemaps {
+            if ui.link(sitemap.as_str()).clicked() {
+                *follow = Some(sitemap.clone());
+            }
+        }
+    }
+    if !robots.unknown_lines.is_empty() {
+        ui.add_space(8.0);
+        ui.strong("Unrecognised lines");
+        for line in &robots.unknown_lines {
+            ui.monospace(line);
+        }
+    }
+}
+
+impl ShellApp {
+    /// Opens `file` (such as `robots.txt`) at the root of the active tab's
+    /// site.
+    pub(crate) fn open_site_file(&mut self, file: &str) {
+        let url = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| tab.url.as_ref())
+            .filter(|url| url.has_host())
+            .and_then(|url| url.join(&format!("/{file}")).ok());
+        match url {
+            Some(url) => self.navigate_active(url),
+            None => self.status_line = format!("Open a site before viewing its {file}"),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use eframe::egui;
+use egui::Context as EguiContext;
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_user_url, ShellApp};
+
+/// What the shell shows when it starts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum StartupPage {
+    /// The home page, in a single tab.
+    Home,
+    /// A single blank tab.
+    Blank,
+    /// The tabs and workspaces open when the shell last quit.
+    #[default]
+    RestoreSession,
+}
+
+impl StartupPage {
+    pub(crate) const ALL: [Self; 3] = [Self::Home, Self::Blank, Self::RestoreSession];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Home => "Show the home page",
+            Self::Blank => "Show a blank page",
+            Self::RestoreSession => "Restore tabs from the last session",
+        }
+    }
+}
+
+/// Window editing the home page and what startup and new tabs show.
+#[derive(Default)]
+pub(crate) struct StartupPanel {
+    pub(crate) open: bool,
+    home_page: String,
+    startup: StartupPage,
+    new_tabs_open_home: bool,
+    error: Option<String>,
+}
+
+impl ShellApp {
+    /// Opens the home page in the first tab when startup is set to show it.
+    /// The tab is left blank when no home page is set.
+    pub(crate) fn open_start_page(&mut self) {
+        if self.settings.startup != StartupPage::Home {
+            return;
+        }
+        if let Some(url) = self.settings.home_page() {
+            self.navigate_active(url);
+        }
+    }
+
+    /// Opens and focuses a tab, showing the home page when new tabs are set
+    /// to.
+    pub(crate) fn open_new_tab(&mut self) {
+        let tab = self.handle.create_tab("New Tab");
+        self.switch_to_tab(tab);
+        if self.settings.new_tabs_open_home {
+            if let Some(url) = self.settings.home_page() {
+                self.navigate_active(url);
+            }
+        }
+        self.refresh_tabs();
+    }
+
+    pub(crate) fn open_startup_window(&mut self) {
+        self.startup = StartupPanel {
+            open: true,
+            home_page: self.settings.home_page.clone(),
+            startup: self.settings.startup,
+            new_tabs_open_home: self.settings.new_tabs_open_home,
+            error: None,
+        };
+    }
+
+    fn apply_startup_settings(&mut self) {
+        let input = self.startup.home_page.trim();
+        let home_page = if input.is_empty() {
+            String::new()
+        } else {
+            match parse_user_url(input) {
+                Ok(url) => url.to_string(),
+                Err(err) => {
+                    self.startup.error = Some(format!("{err:#}"));
+                    return;
+                }
+            }
+        };
+        self.settings.home_page = home_page;
+        self.settings.startup = self.startup.startup;
+        self.settings.new_tabs_open_home = self.startup.new_tabs_open_home;
+        self.save_settings();
+        self.startup.open = false;
+        self.status_line = "Home and startup settings applied".to_owned();
+    }
+
+    pub(crate) fn render_startup_window(&mut self, ctx: &EguiContext) {
+        if !self.startup.open {
+            return;
+        }
+        let current = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| tab.url.clone())
+            .filter(|url| url.scheme() != "about");
+        let panel = &mut self.startup;
+        let mut open = true;
+        let mut apply = false;
+        egui::Window::new("Home & Startup")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Home page");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut panel.home_page)
+                            .hint_text("https://example.com"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(current.is_some(), egui::Button::new("Use Current Page"))
+                        .clicked()
+                    {
+                        if let Some(url) = &current {
+                            panel.home_page = url.to_string();
+                        }
+                    }
+                    if ui.button("Clear").clicked() {
+                        panel.home_page.clear();
+                    }
+                });
+                ui.separator();
+                ui.label("When ASTERIX starts");
+                for page in StartupPage::ALL {
+                    ui.radio_value(&mut panel.startup, page, page.label());
+                }
+                ui.separator();
+                ui.checkbox(
+                    &mut panel.new_tabs_open_home,
+                    "Open the home page in new tabs",
+                );
+                ui.weak("Without a home page, startup and new tabs show a blank page.");
+                if let Some(err) = &panel.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+                ui.separator();
+                if ui.button("Apply").clicked() {
+                    apply = true;
+                }
+            });
+        panel.open &= open;
+        if apply {
+            self.apply_startup_settings();
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use eframe::egui;
+use egui::{Align, Context as EguiContext, Layout, TopBottomPanel};
+
+use crate::composer::format_size;
+use crate::ShellApp;
+
+/// How often the bar is redrawn while requests of the active tab are in
+/// flight.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+impl ShellApp {
+    /// Bar along the bottom of the window with the target of the hovered
+    /// link, or the status line, and how far the active tab has loaded.
+    pub(crate) fn render_status_bar(&self, ctx: &EguiContext) {
+        if !self.settings.show_status_bar {
+            return;
+        }
+        let active = self.active_tab.as_ref().map(|tab| tab.id);
+        let pending = active.map_or(0, |tab| self.handle.pending_requests(tab));
+        let job = self.nav_jobs.iter().find(|job| Some(job.tab()) == active);
+        let fetching_resources = active.is_some_and(|tab| self.fetching_resources(tab));
+        let view = active.and_then(|tab| self.pages.get(&tab));
+
+        TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                match &self.hovered_link {
+                    Some(link) => ui.label(link.url.as_str()),
+                    None => ui.weak(&self.status_line),
+                };
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    ui.label(match pending {
+                        1 => "1 request".to_owned(),
+                        count => format!("{count} requests"),
+                    });
+                    ui.separator();
+                    if let Some(job) = job {
+                        let progress = job.progress();
+                        let received = format_size(progress.received() as usize);
+                        ui.label(match progress.total() {
+                            Some(total) => format!("{received} of {}", format_size(total as usize)),
+                            None => received,
+                        });
+                        ui.separator();
+                        let bar = match progress.fraction() {
+                            Some(fraction) => egui::ProgressBar::new(fraction)
+                                .text(format!("{:.0}%", fraction * 100.0)),
+                            None => egui::ProgressBar::new(0.0).animate(true),
+                        };
+                        ui.add(bar.desired_width(120.0));
+                    } else if let Some(view) = view {
+                        // The document and the subresources that came back.
+                        let resources = view
+                            .resources
+                            .as_ref()
+                            .map_or(0, |resources| resources.total_size());
+                        ui.label(format_size(view.response.bytes.len() + resources));
+                        ui.separator();
+                        if fetching_resources {
+                            ui.weak("Loading resources");
+                        } else {
+                            ui.weak("Done");
+                        }
+                    }
+                });
+            });
+        });
+        if job.is_some() || fetching_resources || pending > 0 {
+            ctx.request_repaint_after(REFRESH_INTERVAL);
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use asterix_browser::{
+    load_sync_config, load_sync_state, save_sync_config, save_sync_state, Collection, SyncBackend,
+    SyncBatch, SyncConfig, SyncJob, SyncRecord, SyncSecrets, SyncState,
+};
+use chrono::Utc;
+use eframe::egui;
+use egui::Context as EguiContext;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use url::Url;
+
+use crate::settings::Settings;
+use crate::{parse_user_url, profile_dir, ShellApp};
+
+/// Id of the single record the shell settings are synced as.
+const SETTINGS_RECORD: &str = "shell";
+
+/// Open tabs of one device, as synced in the tabs collection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeviceTabs {
+    device: String,
+    tabs: Vec<SyncedTab>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncedTab {
+    title: String,
+    url: Url,
+}
+
+/// Sync settings, the state agreed at the last sync and the sync window.
+pub(crate) struct SyncPanel {
+    config: SyncConfig,
+    config_path: Option<PathBuf>,
+    state: SyncState,
+    state_path: Option<PathBuf>,
+    secrets: SyncSecrets,
+    endpoint_input: String,
+    pub(crate) open: bool,
+    job: Option<SyncJob>,
+    /// One line per collection describing the last sync.
+    report: Vec<Result<String, String>>,
+}
+
+impl SyncPanel {
+    /// Loads the sync settings and last sync state from the profile.
+    pub(crate) fn load() -> Self {
+        let config_path = profile_dir().map(|dir| dir.join("sync.json"));
+        let state_path = profile_dir().map(|dir| dir.join("sync-state.json"));
+        let config = match config_path.as_deref().map(load_sync_config) {
+            Some(Ok(config)) => config,
+            Some(Err(err)) => {
+                warn!("failed to load sync settings: {err:#}");
+                SyncConfig::default()
+            }
+            None => SyncConfig::default(),
+        };
+        let state = match state_path.as_deref().map(load_sync_state) {
+            Some(Ok(state)) => state,
+            Some(Err(err)) => {
+                warn!("failed to load sync state: {err:#}");
+                SyncState::default()
+            }
+            None => SyncState::default(),
+        };
+        Self {
+            endpoint_input: config
+                .endpoint
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            config,
+            config_path,
+            state,
+            state_path,
+            secrets: SyncSecrets::default(),
+            open: false,
+            job: None,
+            report: Vec::new(),
+        }
+    }
+
+    pub(crate) fn save(&self) {
+        if let Some(path) = &self.config_path {
+            if let Err(err) = save_sync_config(path, &self.config) {
+                warn!("failed to save sync settings: {err:#}");
+            }
+        }
+        if let Some(path) = &self.state_path {
+            if let Err(err) = save_sync_state(path, &self.state) {
+                warn!("failed to save sync state: {err:#}");
+            }
+        }
+    }
+
+    /// Tabs other devices had open at their last sync.
+    fn remote_tabs(&self) -> Vec<DeviceTabs> {
+        self.state
+            .records(Collection::Tabs)
+            .iter()
+            .filter(|record| !record.deleted && record.id != self.config.device_name)
+            .filter_map(|record| serde_json::from_value(record.data.clone()).ok())
+            .collect()
+    }
+}
+
+impl ShellApp {
+    /// Current local items of `collection`, keyed by record id.
+    fn sync_items(&self, collection: Collection) -> Vec<(String, serde_json::Value)> {
+        match collection {
+            Collection::Tabs => {
+                let device = self.sync.config.device_name.clone();
+                let tabs = self
+                    .handle
+                    .tabs()
+                    .into_iter()
+                    .filter(|tab| !tab.private)
+                    .filter_map(|tab| {
+                        let url = tab.url?;
+                        (url.scheme() != "about").then_some(SyncedTab {
+                            title: tab.title,
+                            url,
+                        })
+                    })
+                    .collect();
+                let own = DeviceTabs {
+                    device: device.clone(),
+                    tabs,
+                };
+                // Other devices' tabs are carried over as last seen.
+                let mut items: Vec<(String, serde_json::Value)> = self
+                    .sync
+                    .state
+                    .records(Collection::Tabs)
+                    .iter()
+                    .filter(|record| !record.deleted && record.id != device)
+                    .map(|record| (record.id.clone(), record.data.clone()))
+                    .collect();
+                if let Ok(data) = serde_json::to_value(own) {
+                    items.push((device, data));
+                }
+                items
+            }
+            Collection::Settings => serde_json::to_value(&self.settings)
+                .map(|data| vec![(SETTINGS_RECORD.to_owned(), data)])
+                .unwrap_or_default(),
+            // Bookmarks and history entries are keyed by their URL.
+            Collection::Bookmarks => self
+                .handle
+                .bookmarks()
+                .into_iter()
+                .filter_map(|bookmark| {
+                    Some((
+                        bookmark.url.to_string(),
+                        serde_json::to_value(bookmark).ok()?,
+                    ))
+                })
+                .collect(),
+            Collection::History => self
+                .handle
+                .history()
+                .into_iter()
+                .filter_map(|entry| {
+                    Some((entry.url.to_string(), serde_json::to_value(entry).ok()?))
+                })
+                .collect(),
+        }
+    }
+
+    fn start_sync(&mut self) {
+        if self.sync.job.is_some() {
+            return;
+        }
+        match parse_user_url(&self.sync.endpoint_input) {
+            Ok(endpoint) => self.sync.config.endpoint = Some(endpoint),
+            Err(_) => {
+                self.sync.report = vec![Err("Enter the address of the sync server".to_owned())];
+                return;
+            }
+        }
+        self.sync.save();
+
+        let now = Utc::now();
+        let batches = Collection::ALL
+            .into_iter()
+            .filter(|collection| self.sync.config.is_enabled(*collection))
+            .map(|collection| SyncBatch {
+                collection,
+                base: self.sync.state.records(collection).to_vec(),
+                local: self
+                    .sync
+                    .state
+                    .stage(collection, self.sync_items(collection), now),
+            })
+            .collect();
+        match self
+            .handle
+            .sync(self.sync.config.clone(), self.sync.secrets.clone(), batches)
+        {
+            Ok(job) => {
+                self.sync.job = Some(job);
+                self.sync.report.clear();
+                self.status_line = "Syncing\u{2026}".to_owned();
+            }
+            Err(err) => self.status_line = format!("Sync failed to start: {err}"),
+        }
+    }
+
+    fn poll_sync(&mut self) {
+        let Some(result) = self.sync.job.as_mut().and_then(SyncJob::try_complete) else {
+            return;
+        };
+        self.sync.job = None;
+        let outcomes = match result {
+            Ok(outcomes) => outcomes,
+            Err(err) => {
+                self.status_line = format!("Sync failed: {err}");
+                self.sync.report = vec![Err(err.to_string())];
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        let mut report = Vec::with_capacity(outcomes.len());
+        for (collection, outcome) in outcomes {
+            match outcome {
+                Ok(outcome) => {
+                    self.apply_synced(collection, &outcome.records);
+                    let mut line = format!("{}: {} received", collection.label(), outcome.pulled);
+                    if outcome.pushed {
+                        line.push_str(", changes uploaded");
+                    }
+                    if outcome.conflicts > 0 {
+                        line.push_str(&format!(
+                            ", {} conflicts resolved by newest change",
+                            outcome.conflicts
+                        ));
+                    }
+                    report.push(Ok(line));
+                    self.sync.state.commit(collection, outcome.records, now);
+                }
+                Err(err) => report.push(Err(format!("{}: {err}", collection.label()))),
+            }
+        }
+        self.status_line = if report.iter().all(Result::is_ok) {
+            "Sync complete".to_owned()
+        } else {
+            "Sync finished with errors".to_owned()
+        };
+        self.sync.report = report;
+        self.sync.save();
+    }
+
+    /// Brings local data in line with the merged records of `collection`.
+    fn apply_synced(&mut self, collection: Collection, records: &[SyncRecord]) {
+        match collection {
+            Collection::Settings => self.apply_synced_settings(records),
+            Collection::Bookmarks => {
+                self.handle.replace_bookmarks(synced_items(records));
+                self.save_bookmarks();
+            }
+            Collection::History => {
+                self.handle.replace_history(synced_items(records));
+                self.save_history();
+            }
+            Collection::Tabs => {}
+        }
+    }
+
+    fn apply_synced_settings(&mut self, records: &[SyncRecord]) {
+        let Some(record) = records
+            .iter()
+            .find(|record| record.id == SETTINGS_RECORD && !record.deleted)
+        else {
+            return;
+        };
+        let unchanged =
+            serde_json::to_value(&self.settings).is_ok_and(|local| local == record.data);
+        if unchanged {
+            return;
+        }
+        match serde_json::from_value::<Settings>(record.data.clone()) {
+            Ok(settings) => {
+                self.clip_dir_input = settings
+                    .clip_directory
+                    .as_ref()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default();
+                self.settings = settings;
+                self.save_settings();
+            }
+            Err(err) => warn!("ignoring synced settings: {err}"),
+        }
+    }
+
+    pub(crate) fn render_sync_window(&mut self, ctx: &EguiContext) {
+        self.poll_sync();
+        if !self.sync.open {
+            return;
+        }
+        if self.sync.job.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        }
+
+        let mut open = true;
+        let mut sync_now = false;
+        let mut open_tab = None;
+        let remote_tabs = self.sync.remote_tabs();
+        let panel = &mut self.sync;
+        egui::Window::new("Sync")
+            .open(&mut open)
+            .default_size([460.0, 420.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "Data is encrypted with your passphrase before it is uploaded; \
+                     the server never sees it in the clear.",
+                );
+                egui::Grid::new("sync_server")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Server");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.endpoint_input)
+                                .hint_text("https://sync.example.com/asterix/"),
+                        );
+                        ui.end_row();
+                        ui.label("Storage");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut panel.config.backend, SyncBackend::Http, "HTTP");
+                            ui.radio_value(
+                                &mut panel.config.backend,
+                                SyncBackend::WebDav,
+                                "WebDAV",
+                            );
+                        });
+                        ui.end_row();
+                        ui.label("User name");
+                        ui.text_edit_singleline(&mut panel.config.username);
+                        ui.end_row();
+                        ui.label("Password");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.secrets.password).password(true),
+                        );
+                        ui.end_row();
+                        ui.label("Passphrase");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut panel.secrets.passphrase)
+                                .password(true),
+                        )
+                        .on_hover_text(
+                            "Use the same passphrase on every device. It is never stored.",
+                        );
+                        ui.end_row();
+                        ui.label("This device");
+                        ui.text_edit_singleline(&mut panel.config.device_name);
+                        ui.end_row();
+                    });
+                ui.separator();
+
+                ui.label("Sync");
+                ui.horizontal_wrapped(|ui| {
+                    for collection in Collection::ALL {
+                        ui.checkbox(panel.config.enabled_mut(collection), collection.label());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if panel.job.is_some() {
+                        ui.spinner();
+                        ui.label("Syncing\u{2026}");
+                    } else if ui.button("Sync Now").clicked() {
+                        sync_now = true;
+                    }
+                    if let Some(synced) = panel.state.last_synced {
+                        ui.weak(format!("last synced {}", synced.format("%Y-%m-%d %H:%M")));
+                    }
+                });
+                for line in &panel.report {
+                    match line {
+                        Ok(line) => ui.label(line),
+                        Err(err) => ui.colored_label(ui.visuals().error_fg_color, err),
+                    };
+                }
+
+                if !remote_tabs.is_empty() {
+                    ui.separator();
+                    ui.strong("Tabs on other devices");
+                    egui::ScrollArea::vertical()
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for device in &remote_tabs {
+                                egui::CollapsingHeader::new(&device.device)
+                                    .default_open(true)
+                                    .show(ui, |ui| {
+                                        for tab in &device.tabs {
+                                            if ui
+                                                .link(&tab.title)
+                                                .on_hover_text(tab.url.as_str())
+                                                .clicked()
+                                            {
+                                                open_tab = Some(tab.clone());
+                                            }
+                                        }
+                                    });
+                            }
+                        });
+                }
+            });
+        self.sync.open = open;
+
+        if sync_now {
+            self.start_sync();
+        }
+        if let Some(tab) = open_tab {
+            let opened = self.handle.create_tab(tab.title);
+            self.navigate_tab(opened.id, tab.url);
+            self.switch_to_tab(opened);
+            self.refresh_tabs();
+        }
+    }
+}
+
+/// Items of the live records, skipping any this version cannot read.
+fn synced_items<T: serde::de::DeserializeOwned>(records: &[SyncRecord]) -> Vec<T> {
+    records
+        .iter()
+        .filter(|record| !record.deleted)
+        .filter_map(|record| serde_json::from_value(record.data.clone()).ok())
+        .collect()
+}
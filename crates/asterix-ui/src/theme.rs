@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use eframe::egui;
+use egui::{Color32, Context as EguiContext, Visuals};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{profile_dir, ShellApp};
+
+/// Colours a theme file overrides, each left to the light or dark default
+/// when missing.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Theme {
+    /// Dark or light base; the current one when missing.
+    dark: Option<bool>,
+    /// Selections and links.
+    accent: Option<[u8; 3]>,
+    /// Panels and windows.
+    background: Option<[u8; 3]>,
+    text: Option<[u8; 3]>,
+}
+
+/// Directory the `theme` setting picks `.json` files from.
+pub(crate) fn themes_dir() -> Option<PathBuf> {
+    profile_dir().map(|dir| dir.join("themes"))
+}
+
+impl Theme {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let json =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_slice(&json)
+            .with_context(|| format!("failed to decode {}", path.display()))
+    }
+
+    fn visuals(&self, dark_mode: bool) -> Visuals {
+        let mut visuals = if self.dark.unwrap_or(dark_mode) {
+            Visuals::dark()
+        } else {
+            Visuals::light()
+        };
+        let rgb = |[r, g, b]: [u8; 3]| Color32::from_rgb(r, g, b);
+        if let Some(accent) = self.accent.map(rgb) {
+            visuals.selection.bg_fill = accent;
+            visuals.hyperlink_color = accent;
+        }
+        if let Some(background) = self.background.map(rgb) {
+            visuals.panel_fill = background;
+            visuals.window_fill = background;
+        }
+        visuals.override_text_color = self.text.map(rgb);
+        visuals
+    }
+}
+
+impl ShellApp {
+    /// Restyles the shell with the theme the settings name, or the plain
+    /// light or dark look when they name none.
+    pub(crate) fn apply_theme(&mut self, ctx: &EguiContext) {
+        let name = self.settings.theme.trim();
+        let theme = match themes_dir().filter(|_| !name.is_empty()) {
+            Some(dir) => match Theme::load(&dir.join(format!("{name}.json"))) {
+                Ok(theme) => theme,
+                Err(err) => {
+                    warn!("failed to load theme: {err:#}");
+                    self.status_line = format!("Could not load theme {name}: {err:#}");
+                    Theme::default()
+                }
+            },
+            None => Theme::default(),
+        };
+        let dark_mode = ctx.style().visuals.dark_mode;
+        ctx.set_visuals(theme.visuals(dark_mode));
+    }
+}
@@ -0,0 +1,230 @@
+use eframe::egui;
+use egui::{Context as EguiContext, Id, RichText};
+use serde::{Deserialize, Serialize};
+
+use crate::menu_bar::Action;
+use crate::ShellApp;
+
+/// Button the toolbar can be customized to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ToolbarButton {
+    NewTab,
+    Home,
+    ReaderMode,
+    Downloads,
+    /// Menu of the user scripts, the shell's extensions.
+    Scripts,
+    Screenshot,
+}
+
+impl ToolbarButton {
+    pub(crate) const ALL: [Self; 6] = [
+        Self::NewTab,
+        Self::Home,
+        Self::ReaderMode,
+        Self::Downloads,
+        Self::Scripts,
+        Self::Screenshot,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::NewTab => "New Tab",
+            Self::Home => "Home",
+            Self::ReaderMode => "Reader",
+            Self::Downloads => "Downloads",
+            Self::Scripts => "Scripts",
+            Self::Screenshot => "Screenshot",
+        }
+    }
+
+    /// Action the button runs; the Scripts button opens a menu instead.
+    fn action(self) -> Option<Action> {
+        match self {
+            Self::NewTab => Some(Action::NewTab),
+            Self::Home => Some(Action::Home),
+            Self::ReaderMode => Some(Action::ReaderMode),
+            Self::Downloads => Some(Action::ShowDownloads),
+            Self::Scripts => None,
+            Self::Screenshot => Some(Action::Screenshot),
+        }
+    }
+}
+
+/// Buttons of the toolbar in the order they are shown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct ToolbarLayout(pub(crate) Vec<ToolbarButton>);
+
+impl Default for ToolbarLayout {
+    fn default() -> Self {
+        Self(vec![
+            ToolbarButton::Home,
+            ToolbarButton::NewTab,
+            ToolbarButton::ReaderMode,
+            ToolbarButton::Scripts,
+        ])
+    }
+}
+
+impl ToolbarLayout {
+    /// Buttons that can still be added.
+    fn available(&self) -> Vec<ToolbarButton> {
+        ToolbarButton::ALL
+            .into_iter()
+            .filter(|button| !self.0.contains(button))
+            .collect()
+    }
+
+    /// Puts the dragged button before the one at `target`, or at the end.
+    fn drop(&mut self, drag: ToolbarDrag, target: Option<usize>) {
+        let (button, from) = match drag {
+            ToolbarDrag::Placed(index) => match self.0.get(index) {
+                Some(button) => (*button, Some(index)),
+                None => return,
+            },
+            ToolbarDrag::Available(button) => (button, None),
+        };
+        let mut target = target.unwrap_or(self.0.len());
+        if let Some(from) = from {
+            self.0.remove(from);
+            if from < target {
+                target -= 1;
+            }
+        }
+        self.0.insert(target.min(self.0.len()), button);
+    }
+}
+
+/// What is being dragged while the toolbar is customized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolbarDrag {
+    /// Button on the toolbar, by position.
+    Placed(usize),
+    /// Button from the palette of those not shown.
+    Available(ToolbarButton),
+}
+
+impl ShellApp {
+    /// Buttons of the customizable part of the toolbar, or their
+    /// placeholders to drag around while customizing.
+    pub(crate) fn render_toolbar_buttons(&mut self, ctx: &EguiContext, ui: &mut egui::Ui) {
+        if self.customizing_toolbar {
+            self.render_toolbar_drop_targets(ui);
+            return;
+        }
+        for button in self.settings.toolbar.0.clone() {
+            match button {
+                ToolbarButton::Scripts => self.render_scripts_menu(ui),
+                ToolbarButton::ReaderMode => {
+                    ui.toggle_value(&mut self.reader_mode, button.label());
+                }
+                _ => {
+                    let Some(action) = button.action() else {
+                        continue;
+                    };
+                    let mut hover = action.label().to_owned();
+                    if let Some(shortcut) = action.shortcut() {
+                        hover.push_str(&format!(" ({})", ctx.format_shortcut(&shortcut)));
+                    }
+                    if ui
+                        .add_enabled(self.can_run(action), egui::Button::new(button.label()))
+                        .on_hover_text(hover)
+                        .clicked()
+                    {
+                        self.run_action(ctx, action);
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_toolbar_drop_targets(&mut self, ui: &mut egui::Ui) {
+        let mut dropped = None;
+        for (index, button) in self.settings.toolbar.0.iter().enumerate() {
+            let id = Id::new(("toolbar_button", index));
+            let response = ui
+                .dnd_drag_source(id, ToolbarDrag::Placed(index), |ui| {
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.add(egui::Label::new(button.label()).selectable(false));
+                    });
+                })
+                .response;
+            if let Some(drag) = response.dnd_release_payload::<ToolbarDrag>() {
+                dropped = Some((*drag, Some(index)));
+            }
+        }
+        let (_, end) = ui.dnd_drop_zone::<ToolbarDrag, _>(egui::Frame::group(ui.style()), |ui| {
+            ui.weak("Drop here");
+        });
+        if let Some(drag) = end {
+            dropped = Some((*drag, None));
+        }
+        if let Some((drag, target)) = dropped {
+            self.settings.toolbar.drop(drag, target);
+            self.save_settings();
+        }
+    }
+
+    /// Palette of the buttons not on the toolbar while it is customized.
+    /// Buttons dragged onto it are taken off the toolbar.
+    pub(crate) fn render_customize_toolbar_window(&mut self, ctx: &EguiContext) {
+        if !self.customizing_toolbar {
+            return;
+        }
+        let mut open = true;
+        let mut removed = None;
+        let mut reset = false;
+        let mut done = false;
+        egui::Window::new("Customize Toolbar")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Drag buttons onto the toolbar to add them, along it to move them, and \
+                     back here to remove them.",
+                );
+                ui.separator();
+                let (_, payload) =
+                    ui.dnd_drop_zone::<ToolbarDrag, _>(egui::Frame::group(ui.style()), |ui| {
+                        ui.set_min_width(ui.available_width());
+                        ui.horizontal_wrapped(|ui| {
+                            let available = self.settings.toolbar.available();
+                            if available.is_empty() {
+                                ui.weak("Every button is on the toolbar.");
+                            }
+                            for button in available {
+                                let id = Id::new(("toolbar_palette", button));
+                                ui.dnd_drag_source(id, ToolbarDrag::Available(button), |ui| {
+                                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                                        let label = RichText::new(button.label());
+                                        ui.add(egui::Label::new(label).selectable(false));
+                                    });
+                                });
+                            }
+                        });
+                    });
+                if let Some(ToolbarDrag::Placed(index)) = payload.as_deref() {
+                    removed = Some(*index);
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    reset = ui.button("Restore Defaults").clicked();
+                    done = ui.button("Done").clicked();
+                });
+            });
+
+        if let Some(index) = removed.filter(|index| *index < self.settings.toolbar.0.len()) {
+            self.settings.toolbar.0.remove(index);
+            self.save_settings();
+        }
+        if reset {
+            self.settings.toolbar = ToolbarLayout::default();
+            self.save_settings();
+        }
+        if done || !open {
+            self.customizing_toolbar = false;
+        }
+    }
+}
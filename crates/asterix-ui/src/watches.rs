@@ -0,0 +1,231 @@
+use asterix_browser::{DiffLine, PageWatch};
+use eframe::egui;
+use egui::{Color32, Context as EguiContext, RichText};
+use tracing::warn;
+use url::Url;
+
+use crate::ShellApp;
+
+/// Refetch intervals offered for watched pages, in minutes.
+const WATCH_INTERVALS: [(u32, &str); 5] = [
+    (15, "15 minutes"),
+    (60, "Hourly"),
+    (360, "6 hours"),
+    (1440, "Daily"),
+    (10080, "Weekly"),
+];
+
+const DEFAULT_WATCH_INTERVAL: u32 = 60;
+
+const ADDED_COLOR: Color32 = Color32::from_rgb(90, 180, 110);
+const REMOVED_COLOR: Color32 = Color32::from_rgb(220, 80, 80);
+
+impl ShellApp {
+    /// Pulls the latest watch state from the runtime and announces new changes.
+    pub(crate) fn refresh_watches(&mut self) {
+        let watches = self.handle.watches();
+        let fresh: Vec<&PageWatch> = watches
+            .iter()
+            .filter(|watch| watch.unseen)
+            .filter(|watch| {
+                !self
+                    .watches
+                    .iter()
+                    .any(|known| known.url == watch.url && known.last_changed == watch.last_changed)
+            })
+            .collect();
+        if let [watch] = fresh.as_slice() {
+            self.status_line = format!("Changes detected on {}", watch.title);
+        } else if !fresh.is_empty() {
+            self.status_line = format!("Changes detected on {} watched pages", fresh.len());
+        }
+        self.watches = watches;
+    }
+
+    /// Toolbar controls for watching the active page and opening the watch list.
+    pub(crate) fn render_watch_controls(&mut self, ui: &mut egui::Ui) {
+        let active = self
+            .active_tab
+            .as_ref()
+            .and_then(|tab| Some((tab.url.clone()?, tab.title.clone())));
+        let watched = active
+            .as_ref()
+            .is_some_and(|(url, _)| self.watches.iter().any(|watch| &watch.url == url));
+
+        let label = if watched { "Unwatch" } else { "Watch" };
+        if ui
+            .add_enabled(active.is_some(), egui::Button::new(label))
+            .on_hover_text("Periodically refetch this page and report changes")
+            .clicked()
+        {
+            if let Some((url, title)) = active {
+                if watched {
+                    self.handle.unwatch_page(&url);
+                    self.status_line = format!("Stopped watching {title}");
+                } else {
+                    self.handle
+                        .watch_page(url, title.clone(), DEFAULT_WATCH_INTERVAL);
+                    self.status_line = format!("Watching {title} for changes");
+                }
+                self.refresh_watches();
+                self.save_watches();
+            }
+        }
+
+        let unseen = self.watches.iter().filter(|watch| watch.unseen).count();
+        let label = if unseen > 0 {
+            format!("Watched ({unseen} new)")
+        } else {
+            "Watched".to_owned()
+        };
+        ui.toggle_value(&mut self.show_watches, label);
+    }
+
+    /// Window listing watched pages with a viewer for the latest diff.
+    pub(crate) fn render_watches_window(&mut self, ctx: &EguiContext) {
+        if !self.show_watches {
+            return;
+        }
+
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Watched Pages")
+            .open(&mut open)
+            .default_size([560.0, 420.0])
+            .show(ctx, |ui| {
+                if self.watches.is_empty() {
+                    ui.label("Use \"Watch\" in the toolbar to monitor the current page.");
+                    return;
+                }
+                for watch in &self.watches {
+                    if let Some(row_action) = watch_row(ui, watch) {
+                        action = Some(row_action);
+                    }
+                }
+
+                let Some(selected) = &self.selected_watch else {
+                    return;
+                };
+                let Some(watch) = self.watches.iter().find(|watch| &watch.url == selected) else {
+                    return;
+                };
+                ui.separator();
+                render_diff(ui, watch);
+            });
+        self.show_watches = open;
+
+        match action {
+            Some(WatchAction::Open(url)) => {
+                self.url_input = url.to_string();
+                self.navigate_active(url);
+            }
+            Some(WatchAction::ShowChanges(url)) => {
+                self.handle.mark_watch_seen(&url);
+                self.selected_watch = Some(url);
+                self.refresh_watches();
+            }
+            Some(WatchAction::SetInterval(url, minutes)) => {
+                self.handle.set_watch_interval(&url, minutes);
+                self.refresh_watches();
+                self.save_watches();
+            }
+            Some(WatchAction::Remove(url)) => {
+                self.handle.unwatch_page(&url);
+                if self.selected_watch.as_ref() == Some(&url) {
+                    self.selected_watch = None;
+                }
+                self.refresh_watches();
+                self.save_watches();
+            }
+            None => {}
+        }
+    }
+
+    pub(crate) fn save_watches(&self) {
+        if let Some(path) = &self.watches_path {
+            if let Err(err) = self.handle.save_watches(path) {
+                warn!("failed to save watched pages: {err:#}");
+            }
+        }
+    }
+}
+
+enum WatchAction {
+    Open(Url),
+    ShowChanges(Url),
+    SetInterval(Url, u32),
+    Remove(Url),
+}
+
+fn watch_row(ui: &mut egui::Ui, watch: &PageWatch) -> Option<WatchAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        let title = if watch.unseen {
+            RichText::new(format!("* {}", watch.title)).strong()
+        } else {
+            RichText::new(&watch.title)
+        };
+        if ui.link(title).on_hover_text(watch.url.as_str()).clicked() {
+            action = Some(WatchAction::Open(watch.url.clone()));
+        }
+
+        let checked = watch
+            .last_checked
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "pending".to_owned());
+        ui.weak(format!("checked {checked}"));
+
+        let current = WATCH_INTERVALS
+            .iter()
+            .find(|(minutes, _)| *minutes == watch.interval_minutes)
+            .map(|(_, label)| (*label).to_owned())
+            .unwrap_or_else(|| format!("{} minutes", watch.interval_minutes));
+        egui::ComboBox::from_id_source(("watch_interval", watch.url.as_str()))
+            .selected_text(current)
+            .show_ui(ui, |ui| {
+                for (minutes, label) in WATCH_INTERVALS {
+                    if ui
+                        .selectable_label(watch.interval_minutes == minutes, label)
+                        .clicked()
+                    {
+                        action = Some(WatchAction::SetInterval(watch.url.clone(), minutes));
+                    }
+                }
+            });
+
+        if ui
+            .add_enabled(!watch.changes.is_empty(), egui::Button::new("Changes"))
+            .clicked()
+        {
+            action = Some(WatchAction::ShowChanges(watch.url.clone()));
+        }
+        if ui.small_button("Remove").clicked() {
+            action = Some(WatchAction::Remove(watch.url.clone()));
+        }
+    });
+    action
+}
+
+fn render_diff(ui: &mut egui::Ui, watch: &PageWatch) {
+    if let Some(changed) = watch.last_changed {
+        ui.label(format!(
+            "Changes to {} detected {}",
+            watch.title,
+            changed.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            for line in &watch.changes {
+                let text = match line {
+                    DiffLine::Added(text) => RichText::new(format!("+ {text}")).color(ADDED_COLOR),
+                    DiffLine::Removed(text) => {
+                        RichText::new(format!("- {text}")).color(REMOVED_COLOR)
+                    }
+                    DiffLine::Same(text) => RichText::new(format!("  {text}")).weak(),
+                };
+                ui.label(text.monospace());
+            }
+        });
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use asterix_browser::{WebSocketEvent, WebSocketJob, WebSocketMessage};
+use eframe::egui;
+use egui::{Color32, Context as EguiContext, RichText};
+use url::Url;
+
+use crate::composer::format_size;
+use crate::ShellApp;
+
+const SENT_COLOR: Color32 = Color32::from_rgb(110, 160, 230);
+const CLOSED_COLOR: Color32 = Color32::from_rgb(220, 80, 80);
+
+/// Close code for a socket the user disconnected on purpose.
+const NORMAL_CLOSURE: u16 = 1000;
+
+/// Most lines kept in the console; older ones scroll away for good.
+const MAX_LINES: usize = 500;
+
+/// Developer tool for talking to a WebSocket endpoint by hand.
+#[derive(Default)]
+pub(crate) struct WebSocketConsole {
+    pub(crate) open: bool,
+    url: String,
+    draft: String,
+    job: Option<WebSocketJob>,
+    lines: Vec<(Color32, String)>,
+}
+
+impl WebSocketConsole {
+    fn push(&mut self, color: Color32, text: String) {
+        if self.lines.len() == MAX_LINES {
+            self.lines.remove(0);
+        }
+        self.lines.push((color, text));
+    }
+}
+
+impl ShellApp {
+    pub(crate) fn render_websocket_console(&mut self, ctx: &EguiContext) {
+        let console = &mut self.websocket;
+        if !console.open {
+            return;
+        }
+        if let Some(job) = &mut console.job {
+            let mut received = Vec::new();
+            while let Some(event) = job.try_next() {
+                received.push(event);
+            }
+            let finished = job.try_complete();
+            for event in received {
+                let text = match event {
+                    WebSocketEvent::Opened { url } => format!("connected to {url}"),
+                    WebSocketEvent::Message(WebSocketMessage::Text(text)) => text,
+                    WebSocketEvent::Message(WebSocketMessage::Binary(bytes)) => {
+                        format!("[binary, {}]", format_size(bytes.len()))
+                    }
+                };
+                console.push(Color32::GRAY, text);
+            }
+            match finished {
+                Some(result) => {
+                    let text = match result {
+                        Ok(close) => match close.code {
+                            Some(code) if close.reason.is_empty() => format!("closed ({code})"),
+                            Some(code) => format!("closed ({code}): {}", close.reason),
+                            None => format!("closed: {}", close.reason),
+                        },
+                        Err(err) => format!("failed: {err}"),
+                    };
+                    console.push(CLOSED_COLOR, text);
+                    console.job = None;
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(100)),
+            }
+        }
+
+        let mut open = true;
+        let mut connect = false;
+        let mut disconnect = false;
+        let mut send = false;
+        egui::Window::new("WebSocket Console")
+            .open(&mut open)
+            .default_size([520.0, 380.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let connected = console.job.is_some();
+                    ui.add_enabled(
+                        !connected,
+                        egui::TextEdit::singleline(&mut console.url)
+                            .hint_text("wss://example.com/socket")
+                            .desired_width(340.0),
+                    );
+                    if connected {
+                        disconnect = ui.button("Disconnect").clicked();
+                    } else {
+                        connect = ui.button("Connect").clicked();
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(260.0)
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for (color, text) in &console.lines {
+                            ui.label(RichText::new(text).monospace().color(*color));
+                        }
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let response = ui.add_enabled(
+                        console.job.is_some(),
+                        egui::TextEdit::singleline(&mut console.draft).desired_width(400.0),
+                    );
+                    send = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    send |= ui
+                        .add_enabled(console.job.is_some(), egui::Button::new("Send"))
+                        .clicked();
+                });
+            });
+        // Closing the window drops the job, which closes the socket.
+        if !open {
+            self.websocket = WebSocketConsole::default();
+            return;
+        }
+        if disconnect {
+            if let Some(job) = &console.job {
+                let _ = job.close(NORMAL_CLOSURE, "");
+            }
+        }
+        if send && !console.draft.is_empty() {
+            if let Some(job) = &console.job {
+                let text = std::mem::take(&mut console.draft);
+                match job.send(WebSocketMessage::Text(text.clone())) {
+                    Ok(()) => console.push(SENT_COLOR, text),
+                    Err(err) => console.push(CLOSED_COLOR, err.to_string()),
+                }
+            }
+        }
+        if connect {
+            self.connect_websocket();
+        }
+    }
+
+    fn connect_websocket(&mut self) {
+        let url = match Url::parse(self.websocket.url.trim()) {
+            Ok(url) => url,
+            Err(err) => {
+                self.status_line = format!("Invalid WebSocket URL: {err}");
+                return;
+            }
+        };
+        // Cookies and privacy follow the active tab, as for the page's own
+        // sockets.
+        let Some(tab) = self.active_tab.as_ref().map(|tab| tab.id) else {
+            self.status_line = "Open a tab to connect from".to_owned();
+            return;
+        };
+        match self.handle.open_websocket(tab, url) {
+            Ok(job) => self.websocket.job = Some(job),
+            Err(err) => self.status_line = format!("WebSocket failed to open: {err}"),
+        }
+    }
+}
@@ -13,4 +13,6 @@ ASTERIX development roadmap
 3. Future feature milestones.
 	- Integrate a real rendering engine (Servo or `wgpu` compositor) and HTML parser.
 	- Embed a JavaScript runtime and strengthen sandboxing/process isolation.
-	- Expand automated tests, telemetry, and continuous delivery pipelines.
\ No newline at end of file
+	- Expand automated tests, telemetry, and continuous delivery pipelines.
+	- Experimental HTTP/3 over QUIC (`quinn`/`h3`) next to the HTTP/1.1 and HTTP/2 choices, left out
+	  of the HTTP version controls until the QUIC stack can be built into the networking pipeline.
\ No newline at end of file